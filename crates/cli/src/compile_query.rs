@@ -0,0 +1,114 @@
+//! Translate a query request into SQL, without contacting the database.
+
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use query_engine_sql::sql;
+use query_engine_translation::translation;
+use tokio::fs;
+
+use super::{Context, OutputFormat};
+use ndc_postgres_configuration as configuration;
+use ndc_postgres_configuration::environment::{self, Environment, Variable};
+
+/// Print the SQL statements a query request translates to, formatted, followed by the
+/// parameters of the query.
+pub async fn run(request: PathBuf, context: Context<impl Environment>) -> anyhow::Result<()> {
+    let parsed_configuration = configuration::parse_configuration(&context.context_path).await?;
+    let configuration = configuration::make_runtime_configuration(
+        parsed_configuration,
+        OfflineEnvironment(context.environment),
+    )?;
+    let request: ndc_models::QueryRequest =
+        serde_json::from_str(&fs::read_to_string(request).await?)?;
+    match context.output_format {
+        OutputFormat::Text => print!("{}", compile(&configuration, request)?),
+        OutputFormat::Json => {
+            let (statements, params) = statements_and_params(&configuration, request)?;
+            let params: BTreeMap<usize, String> = params
+                .into_iter()
+                .map(|(index, param)| (index, format!("{param:?}")))
+                .collect();
+            println!(
+                "{}",
+                serde_json::json!({ "statements": statements, "params": params })
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Translate a query request using the configuration, and render the SQL statements of the
+/// resulting plan.
+pub fn compile(
+    configuration: &configuration::Configuration,
+    request: ndc_models::QueryRequest,
+) -> anyhow::Result<String> {
+    let (statements, params) = statements_and_params(configuration, request)?;
+    Ok(format!("{}{params:#?}\n", statements.join("")))
+}
+
+/// The formatted SQL statements a query request translates to, and the parameters of the query
+/// by their number in the SQL.
+fn statements_and_params(
+    configuration: &configuration::Configuration,
+    request: ndc_models::QueryRequest,
+) -> anyhow::Result<(Vec<String>, BTreeMap<usize, sql::string::Param>)> {
+    let plan = plan(configuration, request)?;
+
+    let query = plan.query.query_sql();
+    let statements: Vec<String> = plan
+        .pre
+        .iter()
+        .map(|statement| &statement.0.sql)
+        .chain(std::iter::once(&query.sql))
+        .chain(plan.post.iter().map(|statement| &statement.0.sql))
+        .map(|sql| format_sql(sql))
+        .collect();
+
+    // Parameters are numbered from 1, as they are in the SQL.
+    let params: BTreeMap<usize, _> = query
+        .params
+        .into_iter()
+        .enumerate()
+        .map(|(index, param)| (index + 1, param))
+        .collect();
+
+    Ok((statements, params))
+}
+
+/// Translate a query request into the plan the connector would run for it, using the
+/// configuration.
+pub fn plan(
+    configuration: &configuration::Configuration,
+    request: ndc_models::QueryRequest,
+) -> anyhow::Result<sql::execution_plan::ExecutionPlan<sql::execution_plan::Query>> {
+    Ok(translation::query::translate(
+        &configuration.metadata,
+        &translation::options::Options::from_configuration(configuration),
+        request,
+    )?)
+}
+
+fn format_sql(sql: &str) -> String {
+    sqlformat::format(
+        sql,
+        &sqlformat::QueryParams::None,
+        sqlformat::FormatOptions::default(),
+    ) + ";\n\n"
+}
+
+/// The environment of a command which does not connect to the database. Variables which are not
+/// set, such as the one holding the connection string, read as a placeholder instead.
+pub(crate) struct OfflineEnvironment<E>(pub(crate) E);
+
+impl<E: Environment> Environment for OfflineEnvironment<E> {
+    fn read(&self, variable: &Variable) -> Result<environment::Value, environment::Error> {
+        match self.0.read(variable) {
+            Err(environment::Error::VariableNotPresent(_)) => {
+                Ok("the command does not connect to the database".to_string())
+            }
+            result => result,
+        }
+    }
+}
@@ -0,0 +1,320 @@
+//! Render documentation of what the connector exposes, based on the configuration.
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::path::PathBuf;
+
+use query_engine_metadata::metadata;
+use query_engine_translation::translation::helpers::Env;
+use query_engine_translation::translation::mutation;
+use query_engine_translation::translation::options::Options;
+use tokio::fs;
+
+use super::Context;
+use ndc_postgres_configuration as configuration;
+use ndc_postgres_configuration::environment::Environment;
+
+/// Render the documentation as Markdown, and write it to the output file or standard output.
+pub async fn run(
+    output: Option<PathBuf>,
+    context: Context<impl Environment>,
+) -> anyhow::Result<()> {
+    let parsed_configuration = configuration::parse_configuration(&context.context_path).await?;
+    let configuration =
+        configuration::make_runtime_configuration(parsed_configuration, context.environment)?;
+    let documentation = render(&configuration);
+    match output {
+        None => print!("{documentation}"),
+        Some(path) => fs::write(path, documentation).await?,
+    }
+    Ok(())
+}
+
+/// Render the collections, types, native operations and generated mutations of a
+/// configuration as Markdown.
+pub fn render(configuration: &configuration::Configuration) -> String {
+    let metadata = &configuration.metadata;
+    let mut out = String::new();
+
+    writeln!(out, "# Schema").unwrap();
+
+    // Tables are referred to by their collection names in relationships.
+    let collections_by_table: BTreeMap<(&str, &str), &str> = metadata
+        .tables
+        .0
+        .iter()
+        .map(|(name, table)| {
+            (
+                (table.schema_name.as_str(), table.table_name.as_str()),
+                name.as_str(),
+            )
+        })
+        .collect();
+
+    writeln!(out, "\n## Collections").unwrap();
+    for (name, table) in &metadata.tables.0 {
+        writeln!(out, "\n### {}\n", escape(name.as_str())).unwrap();
+        writeln!(
+            out,
+            "Table {}.",
+            code_span(&format!("{}.{}", table.schema_name, table.table_name))
+        )
+        .unwrap();
+        write_description(&mut out, table.description.as_ref());
+
+        writeln!(
+            out,
+            "\n| Column | Type | Nullable | Default | Description |"
+        )
+        .unwrap();
+        writeln!(out, "| --- | --- | --- | --- | --- |").unwrap();
+        for (column_name, column) in &table.columns {
+            writeln!(
+                out,
+                "| {} | {} | {} | {} | {} |",
+                escape(column_name.as_str()),
+                type_to_string(&column.r#type),
+                nullable_to_string(&column.nullable),
+                column
+                    .default_expression
+                    .as_deref()
+                    .map(code_cell)
+                    .unwrap_or_default(),
+                escape_cell(column.description.as_deref().unwrap_or_default()),
+            )
+            .unwrap();
+        }
+
+        if table.row_identifiers().next().is_some() {
+            writeln!(out, "\nUniqueness constraints:\n").unwrap();
+            for (constraint_name, constraint) in table.row_identifiers() {
+                let columns: Vec<String> =
+                    constraint.0.values().map(|c| escape(c.as_str())).collect();
+                writeln!(
+                    out,
+                    "- {}: {}",
+                    code_span(constraint_name),
+                    columns.join(", ")
+                )
+                .unwrap();
+            }
+        }
+
+        if !table.foreign_relations.0.is_empty() {
+            writeln!(out, "\nRelationships:\n").unwrap();
+            for (constraint_name, relation) in &table.foreign_relations.0 {
+                let foreign_schema = relation
+                    .foreign_schema
+                    .as_deref()
+                    .unwrap_or(&table.schema_name);
+                let target = collections_by_table
+                    .get(&(foreign_schema, relation.foreign_table.as_str()))
+                    .copied()
+                    .unwrap_or(&relation.foreign_table);
+                let mapping: Vec<String> = relation
+                    .column_mapping
+                    .iter()
+                    .map(|(from, to)| {
+                        format!("{} → {}", escape(from.as_str()), escape(to.as_str()))
+                    })
+                    .collect();
+                writeln!(
+                    out,
+                    "- {}: to {} ({})",
+                    code_span(constraint_name),
+                    escape(target),
+                    mapping.join(", ")
+                )
+                .unwrap();
+            }
+        }
+    }
+
+    if !metadata.native_operations.queries.0.is_empty() {
+        writeln!(out, "\n## Native queries").unwrap();
+        for (name, info) in &metadata.native_operations.queries.0 {
+            write_native_operation(&mut out, name.as_str(), info);
+        }
+    }
+
+    if !metadata.native_operations.mutations.0.is_empty() {
+        writeln!(out, "\n## Native mutations").unwrap();
+        for (name, info) in &metadata.native_operations.mutations.0 {
+            write_native_operation(&mut out, name.as_str(), info);
+        }
+    }
+
+    let options = Options::from_configuration(configuration);
+    let env = Env::new(metadata, BTreeMap::new(), None, &options);
+    let generated_mutations = mutation::generate::generate(&env);
+    if !generated_mutations.is_empty() {
+        writeln!(out, "\n## Generated mutations\n").unwrap();
+        writeln!(out, "| Procedure | Description |").unwrap();
+        writeln!(out, "| --- | --- |").unwrap();
+        for (name, mutation) in &generated_mutations {
+            writeln!(
+                out,
+                "| {} | {} |",
+                escape(name.as_str()),
+                escape_cell(mutation.description())
+            )
+            .unwrap();
+        }
+    }
+
+    if !metadata.composite_types.0.is_empty() {
+        writeln!(out, "\n## Composite types").unwrap();
+        for (name, composite_type) in &metadata.composite_types.0 {
+            writeln!(out, "\n### {}", escape(name.as_str())).unwrap();
+            write_description(&mut out, composite_type.description.as_ref());
+            writeln!(out, "\n| Field | Type | Description |").unwrap();
+            writeln!(out, "| --- | --- | --- |").unwrap();
+            for (field_name, field) in &composite_type.fields {
+                writeln!(
+                    out,
+                    "| {} | {} | {} |",
+                    escape(field_name.as_str()),
+                    type_to_string(&field.r#type),
+                    escape_cell(field.description.as_deref().unwrap_or_default()),
+                )
+                .unwrap();
+            }
+        }
+    }
+
+    writeln!(out, "\n## Scalar types\n").unwrap();
+    writeln!(
+        out,
+        "| Type | Comparison operators | Aggregate functions | Description |"
+    )
+    .unwrap();
+    writeln!(out, "| --- | --- | --- | --- |").unwrap();
+    for (name, scalar_type) in &metadata.scalar_types.0 {
+        let operators: Vec<String> = scalar_type
+            .comparison_operators
+            .keys()
+            .map(|operator| escape(operator.as_str()))
+            .collect();
+        let aggregates: Vec<String> = scalar_type
+            .aggregate_functions
+            .keys()
+            .map(|function| escape(function.as_str()))
+            .collect();
+        writeln!(
+            out,
+            "| {} | {} | {} | {} |",
+            escape(name.as_str()),
+            operators.join(", "),
+            aggregates.join(", "),
+            escape_cell(scalar_type.description.as_deref().unwrap_or_default()),
+        )
+        .unwrap();
+    }
+
+    out
+}
+
+fn write_native_operation(out: &mut String, name: &str, info: &metadata::NativeQueryInfo) {
+    writeln!(out, "\n### {}", escape(name)).unwrap();
+    write_description(out, info.description.as_ref());
+
+    if !info.arguments.is_empty() {
+        writeln!(out, "\n| Argument | Type | Nullable | Description |").unwrap();
+        writeln!(out, "| --- | --- | --- | --- |").unwrap();
+        for (argument_name, argument) in &info.arguments {
+            writeln!(
+                out,
+                "| {} | {} | {} | {} |",
+                escape(argument_name.as_str()),
+                type_to_string(&argument.r#type),
+                nullable_to_string(&argument.nullable),
+                escape_cell(argument.description.as_deref().unwrap_or_default()),
+            )
+            .unwrap();
+        }
+    }
+
+    writeln!(out, "\n| Column | Type | Nullable | Description |").unwrap();
+    writeln!(out, "| --- | --- | --- | --- |").unwrap();
+    for (column_name, column) in &info.columns {
+        writeln!(
+            out,
+            "| {} | {} | {} | {} |",
+            escape(column_name.as_str()),
+            type_to_string(&column.r#type),
+            nullable_to_string(&column.nullable),
+            escape_cell(column.description.as_deref().unwrap_or_default()),
+        )
+        .unwrap();
+    }
+}
+
+fn write_description(out: &mut String, description: Option<&String>) {
+    if let Some(description) = description {
+        writeln!(out, "\n{description}").unwrap();
+    }
+}
+
+fn type_to_string(r#type: &metadata::Type) -> String {
+    match r#type {
+        metadata::Type::ScalarType(name) => escape(name.as_str()),
+        metadata::Type::CompositeType(name) => escape(name.as_str()),
+        metadata::Type::ArrayType(element_type) => format!("[{}]", type_to_string(element_type)),
+    }
+}
+
+fn nullable_to_string(nullable: &metadata::Nullable) -> &'static str {
+    match nullable {
+        metadata::Nullable::Nullable => "yes",
+        metadata::Nullable::NonNullable => "no",
+    }
+}
+
+/// Escape the characters of a name which Markdown would take for syntax, such as the pipes
+/// separating table cells, and join lines, as neither table cells nor headings can span several.
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' | '`' | '*' | '_' | '[' | ']' | '<' | '>' | '#' | '|' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            '\r' => {}
+            '\n' => escaped.push(' '),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Descriptions may use Markdown, but table cells cannot contain pipes or line breaks.
+fn escape_cell(text: &str) -> String {
+    text.replace('|', "\\|").replace('\n', " ")
+}
+
+/// Render text as inline code, delimited by more backticks than any run of them in the text.
+fn code_span(text: &str) -> String {
+    let longest_run = text
+        .split(|c| c != '`')
+        .map(str::len)
+        .max()
+        .unwrap_or_default();
+    let fence = "`".repeat(longest_run + 1);
+    // A backtick at either end would otherwise run into the fence. The space is dropped again
+    // when the code is rendered.
+    let padding = if text.starts_with('`') || text.ends_with('`') {
+        " "
+    } else {
+        ""
+    };
+    format!(
+        "{fence}{padding}{}{padding}{fence}",
+        text.replace(['\r', '\n'], " ")
+    )
+}
+
+/// Render text as inline code in a table cell, in which pipes separate cells even within code.
+fn code_cell(text: &str) -> String {
+    code_span(text).replace('|', "\\|")
+}
@@ -0,0 +1,148 @@
+//! Generate a translation test case from a query request, without contacting the database.
+
+use std::path::PathBuf;
+
+use tokio::fs;
+
+use super::{compile_query, Context};
+use ndc_postgres_configuration as configuration;
+use ndc_postgres_configuration::environment::Environment;
+use ndc_postgres_configuration::version5;
+use ndc_postgres_configuration::version5::metadata::{
+    NativeQueryInfo, NativeQuerySql, NativeQuerySqlEither,
+};
+
+/// The test file the snapshots of the translation tests belong to, relative to the root of the
+/// repository.
+const TESTS_SOURCE: &str = "crates/query-engine/translation/tests/tests.rs";
+
+/// Write a goldenfile directory for the query request, holding the request and the configuration,
+/// and the snapshot of the SQL it translates to, to the translation tests directory. The test
+/// function which checks the snapshot is printed, to be added to the tests.
+///
+/// The request is translated before anything is written, so that a request which does not
+/// translate leaves no test case behind.
+pub async fn run(
+    request: PathBuf,
+    name: String,
+    tests_directory: PathBuf,
+    context: Context<impl Environment>,
+) -> anyhow::Result<()> {
+    if !is_identifier(&name) {
+        anyhow::bail!("the test case name {name:?} is not a valid Rust identifier");
+    }
+    let goldenfile_directory = tests_directory.join("goldenfiles").join(&name);
+    if fs::try_exists(&goldenfile_directory).await? {
+        anyhow::bail!(
+            "the test case {} already exists",
+            goldenfile_directory.display()
+        );
+    }
+
+    let request: ndc_models::QueryRequest =
+        serde_json::from_str(&fs::read_to_string(request).await?)?;
+    let parsed_configuration = configuration::parse_configuration(&context.context_path).await?;
+    let test_configuration = test_configuration(parsed_configuration)?;
+    let sql = compile_test_case(&test_configuration, request.clone())?;
+
+    fs::create_dir_all(&goldenfile_directory).await?;
+    fs::write(
+        goldenfile_directory.join("request.json"),
+        serde_json::to_string_pretty(&request)? + "\n",
+    )
+    .await?;
+    configuration::write_parsed_configuration(
+        configuration::ParsedConfiguration::Version5(test_configuration),
+        &goldenfile_directory,
+    )
+    .await?;
+    let snapshot_file = tests_directory
+        .join("snapshots")
+        .join(format!("tests__{name}.snap"));
+    fs::write(
+        &snapshot_file,
+        format!("---\nsource: {TESTS_SOURCE}\nexpression: result\n---\n{sql}"),
+    )
+    .await?;
+
+    eprintln!("Wrote {}", goldenfile_directory.display());
+    eprintln!("Wrote {}", snapshot_file.display());
+    eprintln!("Add the following test to {TESTS_SOURCE}:");
+    println!();
+    println!("#[tokio::test]");
+    println!("async fn {name}() {{");
+    println!("    let result = common::test_translation({name:?}).await.unwrap();");
+    println!("    insta::assert_snapshot!(result);");
+    println!("}}");
+    Ok(())
+}
+
+/// Whether the name can be used as the name of the test function, and so of its goldenfile
+/// directory.
+fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    match chars.next() {
+        Some(first) if first.is_ascii_alphabetic() || first == '_' => {
+            name != "_" && chars.all(|char| char.is_ascii_alphanumeric() || char == '_')
+        }
+        _ => false,
+    }
+}
+
+/// The configuration as a test case holds it: in the latest version, in a single JSON file with
+/// the SQL of native operations inline, and reading the connection string from the environment,
+/// so that no credentials are written to the test case.
+fn test_configuration(
+    parsed_configuration: configuration::ParsedConfiguration,
+) -> anyhow::Result<version5::ParsedConfiguration> {
+    let mut parsed_configuration = match parsed_configuration {
+        configuration::ParsedConfiguration::Version3(parsed_configuration) => {
+            version5::upgrade_from_v4(configuration::version4::upgrade_from_v3(
+                parsed_configuration,
+            ))
+        }
+        configuration::ParsedConfiguration::Version4(parsed_configuration) => {
+            version5::upgrade_from_v4(parsed_configuration)
+        }
+        configuration::ParsedConfiguration::Version5(parsed_configuration) => parsed_configuration,
+    };
+    let native_operations = &mut parsed_configuration.metadata.native_operations;
+    for info in native_operations
+        .queries
+        .0
+        .values_mut()
+        .chain(native_operations.mutations.0.values_mut())
+    {
+        inline_sql(info)?;
+    }
+    Ok(version5::ParsedConfiguration {
+        connection_settings: version5::connection_settings::DatabaseConnectionSettings::empty(),
+        split_metadata: false,
+        format: version5::ConfigurationFormat::Json,
+        ..parsed_configuration
+    })
+}
+
+/// Hold the SQL of a native operation inline, rather than in a file next to the configuration,
+/// which the test case would not have.
+fn inline_sql(info: &mut NativeQueryInfo) -> anyhow::Result<()> {
+    let sql = info.sql.clone().sql().map_err(|err| anyhow::anyhow!(err))?;
+    info.sql = NativeQuerySqlEither::NativeQuerySql(NativeQuerySql::Inline { sql });
+    Ok(())
+}
+
+/// Translate the request using the configuration of the test case, with the environment the
+/// translation tests provide, so that the snapshot matches what the test produces.
+fn compile_test_case(
+    test_configuration: &version5::ParsedConfiguration,
+    request: ndc_models::QueryRequest,
+) -> anyhow::Result<String> {
+    let configuration = configuration::make_runtime_configuration(
+        configuration::ParsedConfiguration::Version5(test_configuration.clone()),
+        configuration::environment::FixedEnvironment::from([(
+            configuration::DEFAULT_CONNECTION_URI_VARIABLE.into(),
+            "the translation tests do not rely on a database connection".into(),
+        )]),
+    )?;
+    compile_query::compile(&configuration, request)
+}
@@ -3,10 +3,20 @@
 //! The CLI can do a few things. This provides a central point where those things are routed and
 //! then done, making it easier to test this crate deterministically.
 
+mod compile_query;
+mod document;
+mod generate_test_case;
 mod metadata;
 mod native_operations;
+pub mod schema;
+mod seed_metadata;
+mod test_connection;
+mod validate;
+mod verify_requests;
+mod watch;
 
 use std::path::PathBuf;
+use std::time::Instant;
 
 use clap::Subcommand;
 use metadata::NativeToolchainDefinition;
@@ -17,11 +27,40 @@ use ndc_postgres_configuration::environment::Environment;
 
 const UPDATE_ATTEMPTS: u8 = 3;
 
+/// The format to write a new configuration file in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum ConfigurationFormat {
+    /// `configuration.json`
+    Json,
+    /// `configuration.yaml`
+    Yaml,
+}
+
+impl From<ConfigurationFormat> for configuration::version5::ConfigurationFormat {
+    fn from(format: ConfigurationFormat) -> Self {
+        match format {
+            ConfigurationFormat::Json => configuration::version5::ConfigurationFormat::Json,
+            ConfigurationFormat::Yaml => configuration::version5::ConfigurationFormat::Yaml,
+        }
+    }
+}
+
+/// How commands write what they report to standard output.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// Text, for people to read.
+    #[default]
+    Text,
+    /// JSON objects, one per line, for other tools to consume.
+    Json,
+}
+
 /// The various contextual bits and bobs we need to run.
 pub struct Context<Env: Environment> {
     pub context_path: PathBuf,
     pub environment: Env,
     pub release_version: Option<&'static str>,
+    pub output_format: OutputFormat,
 }
 
 /// The command invoked by the user.
@@ -32,9 +71,31 @@ pub enum Command {
         #[arg(long)]
         /// Whether to create the hasura connector metadata.
         with_metadata: bool,
+        /// The format to write the configuration file in.
+        #[arg(long, value_enum, default_value_t = ConfigurationFormat::Json)]
+        format: ConfigurationFormat,
     },
     /// Update the configuration by introspecting the database, using the configuration options.
-    Update,
+    Update {
+        /// Fail, rather than warn, if the Native Operations or the computed relationships do not
+        /// match the database.
+        #[arg(long)]
+        strict: bool,
+        /// Cancel statements run against the database which take longer than this
+        /// (milliseconds). 0, the default, disables the timeout.
+        ///
+        /// Session settings are sent as the `options` startup parameter, which some connection
+        /// poolers, such as PgBouncer, reject.
+        #[arg(long, default_value_t = 0)]
+        statement_timeout_ms: u64,
+        /// Give up on statements which wait for a lock for longer than this, rather than
+        /// holding up other sessions (milliseconds). 0, the default, disables the timeout.
+        #[arg(long, default_value_t = 0)]
+        lock_timeout_ms: u64,
+        /// Run every transaction against the database read-only.
+        #[arg(long)]
+        read_only: bool,
+    },
     /// Upgrade the configuration to the latest version. This does not involve the database.
     Upgrade {
         #[arg(long)]
@@ -44,6 +105,64 @@ pub enum Command {
     },
     #[command(subcommand)]
     NativeOperation(native_operations::Command),
+    /// Print JSON Schemas describing the files the connector reads.
+    #[command(subcommand)]
+    Schema(schema::Command),
+    /// Write Markdown documentation of the collections, types and procedures the connector exposes.
+    Document {
+        /// The file to write the documentation to, instead of standard output.
+        #[arg(long)]
+        output: Option<PathBuf>,
+    },
+    /// Translate a query request into SQL using the configuration, and print the SQL and its
+    /// parameters. This does not involve the database.
+    CompileQuery {
+        /// The JSON file holding the query request.
+        request: PathBuf,
+    },
+    /// Write a translation test case for a query request, holding the request, the configuration
+    /// and a snapshot of the SQL it translates to, and print the test function to add for it.
+    /// This does not involve the database.
+    GenerateTestCase {
+        /// The JSON file holding the query request.
+        request: PathBuf,
+        /// The name of the test case, which is also the name of the test function.
+        #[arg(long)]
+        name: String,
+        /// The directory of the translation tests, holding the goldenfiles and snapshots.
+        #[arg(long, default_value = "crates/query-engine/translation/tests")]
+        tests_directory: PathBuf,
+    },
+    /// Translate each query request in a directory, such as requests recorded from a running
+    /// connector, and check that the database accepts the SQL of each using `EXPLAIN`, without
+    /// running it.
+    VerifyRequests {
+        /// The directory holding the query requests, one JSON file each.
+        directory: PathBuf,
+    },
+    /// Check the configuration for problems, such as undefined types, and report all of them.
+    Validate {
+        /// Also check that the tables and columns in the metadata exist in the database.
+        #[arg(long)]
+        check_database: bool,
+    },
+    /// Check the SQL files of the Native Operations again whenever they change, until
+    /// interrupted. This does not involve the database.
+    Watch,
+    /// Connect to the database using the configuration, and report the server version, the SSL
+    /// status, the current role, the search path and the access it has to the schemas of the
+    /// tables in the metadata.
+    TestConnection,
+    /// Write skeletons of the Hasura DDN metadata for the tables of the configuration, with a
+    /// file for each table holding its object type, its model and its relationships.
+    SeedMetadata {
+        /// The name of the data connector in the DDN metadata.
+        #[arg(long, default_value = "postgres")]
+        data_connector_name: String,
+        /// The directory to write the files to.
+        #[arg(long)]
+        output_dir: PathBuf,
+    },
 }
 
 /// The set of errors that can go wrong _in addition to_ generic I/O or parsing errors.
@@ -51,15 +170,54 @@ pub enum Command {
 pub enum Error {
     #[error("directory is not empty")]
     DirectoryIsNotEmpty,
+    #[error("the configuration has {0} problem(s)")]
+    InvalidConfiguration(usize),
+    #[error("{0} schema(s) of the metadata cannot be read")]
+    InaccessibleSchemas(usize),
+    #[error("the database rejected {0} request(s)")]
+    RejectedRequests(usize),
 }
 
 /// Run a command in a given directory.
 pub async fn run(command: Command, context: Context<impl Environment>) -> anyhow::Result<()> {
     match command {
-        Command::Initialize { with_metadata } => initialize(with_metadata, context).await?,
-        Command::Update => update(context).await?,
+        Command::Initialize {
+            with_metadata,
+            format,
+        } => initialize(with_metadata, format, context).await?,
+        Command::Update {
+            strict,
+            statement_timeout_ms,
+            lock_timeout_ms,
+            read_only,
+        } => {
+            let session = configuration::SessionSettings {
+                statement_timeout: timeout_from_millis(statement_timeout_ms),
+                lock_timeout: timeout_from_millis(lock_timeout_ms),
+                read_only,
+            };
+            update(strict, &session, context).await?;
+        }
         Command::Upgrade { dir_from, dir_to } => upgrade(dir_from, dir_to).await?,
         Command::NativeOperation(cmd) => native_operations::run(cmd, context).await?,
+        Command::Schema(cmd) => schema::run(cmd).await?,
+        Command::Document { output } => document::run(output, context).await?,
+        Command::CompileQuery { request } => compile_query::run(request, context).await?,
+        Command::GenerateTestCase {
+            request,
+            name,
+            tests_directory,
+        } => generate_test_case::run(request, name, tests_directory, context).await?,
+        Command::VerifyRequests { directory } => {
+            verify_requests::run(directory, context).await?;
+        }
+        Command::Validate { check_database } => validate::run(check_database, context).await?,
+        Command::Watch => watch::run(context).await?,
+        Command::TestConnection => test_connection::run(context).await?,
+        Command::SeedMetadata {
+            data_connector_name,
+            output_dir,
+        } => seed_metadata::run(&data_connector_name, &output_dir, context).await?,
     };
     Ok(())
 }
@@ -72,15 +230,23 @@ pub async fn run(command: Command, context: Context<impl Environment>) -> anyhow
 ///
 /// Optionally, this can also create the connector metadata, which is used by the Hasura CLI to
 /// automatically work with this CLI as a plugin.
-async fn initialize(with_metadata: bool, context: Context<impl Environment>) -> anyhow::Result<()> {
+async fn initialize(
+    with_metadata: bool,
+    format: ConfigurationFormat,
+    context: Context<impl Environment>,
+) -> anyhow::Result<()> {
     // refuse to initialize the directory unless it is empty
     let mut items_in_dir = fs::read_dir(&context.context_path).await?;
     if items_in_dir.next_entry().await?.is_some() {
         Err(Error::DirectoryIsNotEmpty)?;
     }
 
+    let initial_configuration = configuration::version5::ParsedConfiguration {
+        format: format.into(),
+        ..configuration::version5::ParsedConfiguration::empty()
+    };
     configuration::write_parsed_configuration(
-        configuration::ParsedConfiguration::initial(),
+        configuration::ParsedConfiguration::Version5(initial_configuration),
         &context.context_path,
     )
     .await?;
@@ -158,15 +324,36 @@ async fn initialize(with_metadata: bool, context: Context<impl Environment>) ->
 /// Update the configuration in the current directory by introspecting the database.
 ///
 /// This expects a configuration with a valid connection URI.
-async fn update(context: Context<impl Environment>) -> anyhow::Result<()> {
+///
+/// Native Operations are not introspected, so they are checked against the database instead, as
+/// are the conditions of computed relationships. Any problems are printed as warnings, or fail
+/// the update if `strict` is set, in which case the configuration is left as it was.
+///
+/// The database is introspected over a single connection per database, with the given session
+/// settings, so that updating against a busy database does not get in its way.
+async fn update(
+    strict: bool,
+    session: &configuration::SessionSettings,
+    context: Context<impl Environment>,
+) -> anyhow::Result<()> {
     // It is possible to change the file in the middle of introspection.
     // We want to detect this scenario and retry, or fail if we are unable to.
     // We do that with a few attempts.
-    for _attempt in 1..=UPDATE_ATTEMPTS {
+    for attempt in 1..=UPDATE_ATTEMPTS {
         let existing_configuration =
             configuration::parse_configuration(&context.context_path).await?;
-        let output =
-            configuration::introspect(existing_configuration.clone(), &context.environment).await?;
+        tracing::info!(attempt, "Introspecting the database");
+        let start = Instant::now();
+        let output = configuration::introspect(
+            existing_configuration.clone(),
+            &context.environment,
+            session,
+        )
+        .await?;
+        tracing::info!(
+            elapsed_ms = start.elapsed().as_millis(),
+            "Introspected the database"
+        );
 
         // Check that the input file did not change since we started introspecting,
         let input_again_before_write =
@@ -174,13 +361,55 @@ async fn update(context: Context<impl Environment>) -> anyhow::Result<()> {
 
         // and skip this attempt if it has.
         if input_again_before_write == existing_configuration {
+            if let configuration::ParsedConfiguration::Version5(ref output) = output {
+                let start = Instant::now();
+                let problems = configuration::version5::native_operations::verify(
+                    output,
+                    &context.environment,
+                    session,
+                )
+                .await?;
+                tracing::info!(
+                    problems = problems.len(),
+                    elapsed_ms = start.elapsed().as_millis(),
+                    "Checked the Native Operations against the database"
+                );
+                if strict && !problems.is_empty() {
+                    anyhow::bail!(
+                        "The Native Operations do not match the database:\n{}",
+                        problems.join("\n")
+                    );
+                }
+                for problem in problems {
+                    tracing::warn!("{problem}");
+                }
+
+                let problems = configuration::version5::computed_relationships::verify(
+                    output,
+                    &context.environment,
+                    session,
+                )
+                .await?;
+                if strict && !problems.is_empty() {
+                    anyhow::bail!(
+                        "The computed relationships do not match the database:\n{}",
+                        problems.join("\n")
+                    );
+                }
+                for problem in problems {
+                    tracing::warn!("{problem}");
+                }
+            }
+
             // In order to be sure to capture default values absent in the initial input we have to
             // always write out the updated configuration.
             configuration::write_parsed_configuration(output, &context.context_path).await?;
+            tracing::info!(path = %context.context_path.display(), "Wrote the configuration");
             return Ok(());
         }
 
         // If we have reached here, the input file changed before writing.
+        tracing::warn!(attempt, "The configuration changed during introspection");
     }
 
     // We ran out of attempts.
@@ -189,6 +418,11 @@ async fn update(context: Context<impl Environment>) -> anyhow::Result<()> {
     ))
 }
 
+/// A timeout given in milliseconds, where 0 means no timeout.
+fn timeout_from_millis(millis: u64) -> Option<std::time::Duration> {
+    (millis > 0).then(|| std::time::Duration::from_millis(millis))
+}
+
 /// Upgrade the configuration in a directory by trying to read it and then write it back
 /// out to a different directory.
 ///
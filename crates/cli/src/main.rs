@@ -27,6 +27,13 @@ pub struct Args {
     /// The path to the configuration. Defaults to the current directory.
     #[arg(long = "context", env = "HASURA_PLUGIN_CONNECTOR_CONTEXT_PATH")]
     pub context_path: Option<PathBuf>,
+    /// The most detailed messages to write: `error`, `warn`, `info`, `debug` or `trace`.
+    #[arg(long, env = "HASURA_PLUGIN_LOG_LEVEL", default_value_t = tracing::Level::WARN)]
+    pub log_level: tracing::Level,
+    /// Write the output of commands, messages and errors as JSON objects, one per line, for other
+    /// tools to consume.
+    #[arg(long)]
+    pub json: bool,
     /// The command to invoke.
     #[command(subcommand)]
     pub subcommand: Command,
@@ -34,19 +41,46 @@ pub struct Args {
 
 #[tokio::main]
 pub async fn main() -> ExitCode {
-    if let Err(err) = try_main().await {
-        // The default formatting for anyhow in our case includes a 'Caused by' section
-        // that duplicates what's already in the error message, so we don't display it.
-        eprintln!("ERROR: {err}");
+    let args = Args::parse();
+    let json = args.json;
+    init_logging(args.log_level, json);
+    if let Err(err) = try_main(args).await {
+        if json {
+            eprintln!(
+                "{}",
+                serde_json::json!({
+                    "level": "ERROR",
+                    "message": err.to_string(),
+                    "causes": err.chain().skip(1).map(ToString::to_string).collect::<Vec<_>>(),
+                })
+            );
+        } else {
+            // The default formatting for anyhow in our case includes a 'Caused by' section
+            // that duplicates what's already in the error message, so we don't display it.
+            eprintln!("ERROR: {err}");
+        }
         return ExitCode::FAILURE;
     }
     ExitCode::SUCCESS
 }
 
+/// Write messages up to the given level to standard error, as text or as JSON lines.
+/// Standard output is left to the output of the commands themselves.
+fn init_logging(level: tracing::Level, json: bool) {
+    let subscriber = tracing_subscriber::fmt()
+        .with_max_level(level)
+        .with_writer(std::io::stderr)
+        .with_target(false);
+    if json {
+        subscriber.json().flatten_event(true).init();
+    } else {
+        subscriber.without_time().init();
+    }
+}
+
 /// The application entrypoint. It pulls information from the environment and then calls the [run]
 /// function. The library remains unaware of the environment, so that we can more easily test it.
-async fn try_main() -> anyhow::Result<()> {
-    let args = Args::parse();
+async fn try_main(args: Args) -> anyhow::Result<()> {
     // Default the context path to the current directory.
     let context_path = match args.context_path {
         Some(path) => path,
@@ -56,6 +90,11 @@ async fn try_main() -> anyhow::Result<()> {
         context_path,
         environment: configuration::environment::ProcessEnvironment,
         release_version: RELEASE_VERSION,
+        output_format: if args.json {
+            OutputFormat::Json
+        } else {
+            OutputFormat::Text
+        },
     };
     run(args.subcommand, context).await?;
     Ok(())
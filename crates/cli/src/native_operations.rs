@@ -2,11 +2,12 @@
 
 use std::path::PathBuf;
 
-use super::{update, Context};
+use super::{update, Context, OutputFormat};
 use ndc_postgres_configuration as configuration;
 use ndc_postgres_configuration::environment::Environment;
 
 pub use configuration::version4::native_operations::Kind;
+pub use configuration::version5::native_operations::Inference;
 
 /// Commands on Native Operations.
 #[derive(Debug, Clone, clap::Subcommand)]
@@ -26,6 +27,12 @@ pub enum Command {
         /// Override the Native Operation definition if it exists.
         #[arg(long)]
         r#override: bool,
+
+        /// How to infer whether the columns can be null.
+        /// `explain` plans the statement in a read-only transaction to recognise computed
+        /// columns that are never null.
+        #[arg(long, value_enum, default_value_t = Inference::Describe)]
+        infer: Inference,
     },
     /// Delete an existing Native Operation from the configuration.
     Delete {
@@ -37,6 +44,20 @@ pub enum Command {
         #[arg(long)]
         kind: Kind,
     },
+    /// Run a Native Operation in a transaction which is rolled back, and print its results.
+    Test {
+        /// The name of the Native Operation.
+        #[arg(long)]
+        name: String,
+
+        /// Operation kind.
+        #[arg(long)]
+        kind: Kind,
+
+        /// The arguments, as a JSON object from argument names to values.
+        #[arg(long, default_value = "{}")]
+        arguments: String,
+    },
 }
 
 /// Run a command in a given directory.
@@ -47,6 +68,7 @@ pub async fn run(command: Command, context: Context<impl Environment>) -> anyhow
             operation_path,
             kind,
             r#override,
+            infer,
         } => {
             create(
                 context,
@@ -57,12 +79,20 @@ pub async fn run(command: Command, context: Context<impl Environment>) -> anyhow
                 } else {
                     Override::No
                 },
+                infer,
             )
             .await?;
         }
         Command::Delete { name, kind } => {
             delete(context, name, kind).await?;
         }
+        Command::Test {
+            name,
+            kind,
+            arguments,
+        } => {
+            test(context, name, kind, arguments).await?;
+        }
     };
     Ok(())
 }
@@ -79,28 +109,55 @@ async fn list(context: Context<impl Environment>) -> anyhow::Result<()> {
         ))?,
         configuration::ParsedConfiguration::Version4(ref mut configuration) => {
             let operations = &configuration.metadata.native_queries.0;
-            println!("Native Queries:");
-            for native_operation in operations.iter().filter(|op| !op.1.is_procedure) {
-                println!("- {}", native_operation.0);
-            }
-            println!("Native Mutations:");
-            for native_operation in operations.iter().filter(|op| op.1.is_procedure) {
-                println!("- {}", native_operation.0);
-            }
+            print_list(
+                context.output_format,
+                operations
+                    .iter()
+                    .filter(|op| !op.1.is_procedure)
+                    .map(|op| op.0.to_string()),
+                operations
+                    .iter()
+                    .filter(|op| op.1.is_procedure)
+                    .map(|op| op.0.to_string()),
+            );
         }
         configuration::ParsedConfiguration::Version5(ref mut configuration) => {
             let operations = &configuration.metadata.native_operations;
+            print_list(
+                context.output_format,
+                operations.queries.0.keys().map(ToString::to_string),
+                operations.mutations.0.keys().map(ToString::to_string),
+            );
+        }
+    };
+    Ok(())
+}
+
+/// Print the names of the Native Queries and Native Mutations.
+fn print_list(
+    output_format: OutputFormat,
+    queries: impl Iterator<Item = String>,
+    mutations: impl Iterator<Item = String>,
+) {
+    match output_format {
+        OutputFormat::Text => {
             println!("Native Queries:");
-            for native_operation in &operations.queries.0 {
-                println!("- {}", native_operation.0);
+            for name in queries {
+                println!("- {name}");
             }
             println!("Native Mutations:");
-            for native_operation in &operations.mutations.0 {
-                println!("- {}", native_operation.0);
+            for name in mutations {
+                println!("- {name}");
             }
         }
-    };
-    Ok(())
+        OutputFormat::Json => println!(
+            "{}",
+            serde_json::json!({
+                "nativeQueries": queries.collect::<Vec<_>>(),
+                "nativeMutations": mutations.collect::<Vec<_>>(),
+            })
+        ),
+    }
 }
 
 /// Override Native Operation definition if exists?
@@ -117,6 +174,7 @@ async fn create(
     operation_path: PathBuf,
     kind: Kind,
     override_entry: Override,
+    inference: Inference,
 ) -> anyhow::Result<()> {
     // Read the configuration.
     let mut configuration =
@@ -149,6 +207,7 @@ async fn create(
                 &operation_path,
                 &file_contents,
                 kind,
+                inference,
             )
             .await?;
 
@@ -194,6 +253,7 @@ async fn create(
                 &connection_string,
                 &operation_path,
                 &file_contents,
+                inference,
             )
             .await?;
 
@@ -259,6 +319,83 @@ async fn create(
     update(context).await
 }
 
+/// Run a Native Operation by name, and print the types of its columns and the rows it returns.
+async fn test(
+    context: Context<impl Environment>,
+    name: String,
+    kind: Kind,
+    arguments: String,
+) -> anyhow::Result<()> {
+    // Read the configuration.
+    let configuration = configuration::parse_configuration(context.context_path.clone()).await?;
+
+    let configuration::ParsedConfiguration::Version5(configuration) = configuration else {
+        anyhow::bail!("To test native operations, please upgrade to the latest version.");
+    };
+
+    let arguments: std::collections::BTreeMap<String, serde_json::Value> =
+        serde_json::from_str(&arguments)
+            .map_err(|err| anyhow::anyhow!("The arguments must be a JSON object: {err}"))?;
+
+    let kind = match kind {
+        Kind::Query => configuration::version5::native_operations::Kind::Query,
+        Kind::Mutation => configuration::version5::native_operations::Kind::Mutation,
+    };
+
+    let result = configuration::version5::native_operations::test(
+        &configuration,
+        &context.environment,
+        &name,
+        kind,
+        &arguments,
+    )
+    .await?;
+
+    let rows: Vec<serde_json::Value> = result
+        .rows
+        .into_iter()
+        .map(|row| {
+            serde_json::Value::Object(
+                result
+                    .columns
+                    .iter()
+                    .map(|(column, _)| column.clone())
+                    .zip(row.into_iter().map(|value| {
+                        value.map_or(serde_json::Value::Null, serde_json::Value::String)
+                    }))
+                    .collect(),
+            )
+        })
+        .collect();
+    match context.output_format {
+        OutputFormat::Text => {
+            println!("Columns:");
+            for (column, scalar_type) in &result.columns {
+                println!("- {column}: {scalar_type}");
+            }
+            println!("Rows:");
+            for row in rows {
+                println!("{row}");
+            }
+            println!("The transaction was rolled back.");
+        }
+        OutputFormat::Json => {
+            let columns: Vec<serde_json::Value> = result
+                .columns
+                .iter()
+                .map(|(column, scalar_type)| {
+                    serde_json::json!({ "name": column, "type": scalar_type })
+                })
+                .collect();
+            println!(
+                "{}",
+                serde_json::json!({ "columns": columns, "rows": rows })
+            );
+        }
+    }
+    Ok(())
+}
+
 /// Delete a Native Operation by name.
 async fn delete(
     context: Context<impl Environment>,
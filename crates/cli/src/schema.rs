@@ -0,0 +1,42 @@
+//! Print the JSON Schemas of the configuration formats.
+
+use std::path::PathBuf;
+
+use tokio::fs;
+
+use ndc_postgres_configuration as configuration;
+use ndc_postgres_configuration::VersionTag;
+
+/// Commands on JSON Schemas.
+#[derive(Debug, Clone, clap::Subcommand)]
+pub enum Command {
+    /// Print the JSON Schema of a version of the configuration format.
+    Configuration {
+        /// The version of the configuration format.
+        #[arg(long, default_value_t = 5, value_parser = clap::value_parser!(u8).range(3..=5))]
+        version: u8,
+        /// The file to write the schema to, instead of standard output.
+        #[arg(long)]
+        output: Option<PathBuf>,
+    },
+}
+
+/// Run a command, writing the schema to the output file or standard output.
+pub async fn run(command: Command) -> anyhow::Result<()> {
+    match command {
+        Command::Configuration { version, output } => {
+            let version = match version {
+                3 => VersionTag::Version3,
+                4 => VersionTag::Version4,
+                _ => VersionTag::Version5,
+            };
+            let schema = configuration::generate_schema(version);
+            let contents = serde_json::to_string_pretty(&schema)? + "\n";
+            match output {
+                None => print!("{contents}"),
+                Some(path) => fs::write(path, contents).await?,
+            }
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,543 @@
+//! Generate skeletons of the Hasura DDN metadata for the tables of a configuration, so that they
+//! do not have to be written by hand.
+//!
+//! Each table gets a file holding an 'ObjectType', a 'Model' and a 'Relationship' for each
+//! foreign key from or to it. These are a starting point: the scalar types they refer to, boolean
+//! expressions and permissions still need to be defined.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
+
+use query_engine_metadata::metadata;
+use serde::Serialize;
+use tokio::fs;
+
+use super::{Context, OutputFormat};
+use ndc_postgres_configuration as configuration;
+use ndc_postgres_configuration::environment::Environment;
+use ndc_postgres_configuration::{RelationshipKind, RelationshipNaming};
+
+/// Write a '.hml' file for every table in the metadata to the output directory.
+pub async fn run(
+    data_connector_name: &str,
+    output_dir: &Path,
+    context: Context<impl Environment>,
+) -> anyhow::Result<()> {
+    let parsed_configuration = configuration::parse_configuration(&context.context_path).await?;
+    let configuration =
+        configuration::make_runtime_configuration(parsed_configuration, context.environment)?;
+
+    fs::create_dir_all(output_dir).await?;
+    let files = render(
+        &configuration.metadata,
+        &configuration.relationship_naming,
+        data_connector_name,
+    )?;
+    for (file_name, contents) in &files {
+        fs::write(output_dir.join(file_name), contents).await?;
+    }
+    match context.output_format {
+        OutputFormat::Text => {
+            println!("Wrote {} file(s) to {}.", files.len(), output_dir.display());
+        }
+        OutputFormat::Json => println!(
+            "{}",
+            serde_json::json!({
+                "outputDir": output_dir.display().to_string(),
+                "files": files.keys().collect::<Vec<_>>(),
+            })
+        ),
+    }
+    Ok(())
+}
+
+/// Render the metadata of every table, by file name.
+pub fn render(
+    metadata: &metadata::Metadata,
+    naming: &RelationshipNaming,
+    data_connector_name: &str,
+) -> anyhow::Result<BTreeMap<String, String>> {
+    // Tables are referred to by their collection names in relationships.
+    let collections_by_table: BTreeMap<(Option<&str>, &str, &str), &str> = metadata
+        .tables
+        .0
+        .iter()
+        .map(|(name, table)| {
+            (
+                (
+                    table.target.as_deref(),
+                    table.schema_name.as_str(),
+                    table.table_name.as_str(),
+                ),
+                name.as_str(),
+            )
+        })
+        .collect();
+
+    let mut relationships: BTreeMap<&str, Vec<Relationship>> = BTreeMap::new();
+    let mut relationship_names: BTreeMap<&str, BTreeSet<String>> = BTreeMap::new();
+    let mut array_relationships = vec![];
+    for (collection, table) in &metadata.tables.0 {
+        for (constraint_name, relation) in &table.foreign_relations.0 {
+            let Some(&foreign_collection) = collections_by_table.get(&(
+                table.target.as_deref(),
+                relation
+                    .foreign_schema
+                    .as_deref()
+                    .unwrap_or(&table.schema_name),
+                relation.foreign_table.as_str(),
+            )) else {
+                continue;
+            };
+            let name = unique_name(
+                relationship_names.entry(collection.as_str()).or_default(),
+                naming,
+                RelationshipKind::Object,
+                collection.as_str(),
+                foreign_collection,
+                constraint_name,
+            );
+            relationships
+                .entry(collection.as_str())
+                .or_default()
+                .push(Relationship::new(
+                    name,
+                    collection.as_str(),
+                    foreign_collection,
+                    "Object",
+                    relation
+                        .column_mapping
+                        .iter()
+                        .map(|(from, to)| (from.as_str(), to.as_str())),
+                ));
+            array_relationships.push((
+                collection.as_str(),
+                foreign_collection,
+                constraint_name,
+                relation,
+            ));
+        }
+    }
+    // The other side of a foreign key gets the rows which refer to it, once the names of the
+    // object relationships have been taken.
+    for (collection, foreign_collection, constraint_name, relation) in array_relationships {
+        let name = unique_name(
+            relationship_names.entry(foreign_collection).or_default(),
+            naming,
+            RelationshipKind::Array,
+            foreign_collection,
+            collection,
+            constraint_name,
+        );
+        relationships
+            .entry(foreign_collection)
+            .or_default()
+            .push(Relationship::new(
+                name,
+                foreign_collection,
+                collection,
+                "Array",
+                relation
+                    .column_mapping
+                    .iter()
+                    .map(|(from, to)| (to.as_str(), from.as_str())),
+            ));
+    }
+
+    // Both the types and the files are named after the collections, so collections whose names
+    // differ only in case or punctuation, such as 'invoice_line' and 'InvoiceLine', would
+    // overwrite each other.
+    let mut collections_by_type_name: BTreeMap<String, &str> = BTreeMap::new();
+    for collection in metadata.tables.0.keys() {
+        let type_name = pascal_case(collection.as_str());
+        if let Some(other) = collections_by_type_name.insert(type_name.clone(), collection.as_str())
+        {
+            anyhow::bail!(
+                "The collections '{other}' and '{collection}' would both be written as the type '{type_name}' in '{type_name}.hml'. Rename one of them in the configuration."
+            );
+        }
+    }
+
+    let mut files = BTreeMap::new();
+    for (collection, table) in &metadata.tables.0 {
+        let type_name = pascal_case(collection.as_str());
+        let mut out = String::new();
+        write_document(
+            &mut out,
+            "ObjectType",
+            &ObjectType::new(&type_name, table, data_connector_name, collection.as_str()),
+        )?;
+        write_document(
+            &mut out,
+            "Model",
+            &Model::new(&type_name, table, data_connector_name, collection.as_str()),
+        )?;
+        for relationship in relationships
+            .remove(collection.as_str())
+            .unwrap_or_default()
+        {
+            write_document(&mut out, "Relationship", &relationship)?;
+        }
+        files.insert(format!("{type_name}.hml"), out);
+    }
+    Ok(files)
+}
+
+/// Append a YAML document of the given kind.
+fn write_document(
+    out: &mut String,
+    kind: &'static str,
+    definition: impl Serialize,
+) -> anyhow::Result<()> {
+    out.push_str("---\n");
+    out.push_str(&serde_yaml::to_string(&Document {
+        kind,
+        version: "v1",
+        definition,
+    })?);
+    Ok(())
+}
+
+/// Name a relationship as configured unless the type already has one by that name, in which
+/// case it is named after its foreign key constraint.
+fn unique_name(
+    taken: &mut BTreeSet<String>,
+    naming: &RelationshipNaming,
+    kind: RelationshipKind,
+    source: &str,
+    target: &str,
+    constraint_name: &str,
+) -> String {
+    let mut name = naming.name(kind, source, target, constraint_name);
+    if taken.contains(&name) {
+        let by_constraint = RelationshipNaming {
+            pluralize_array_relationships: false,
+            object_relationship_template: "{constraint}".to_string(),
+            array_relationship_template: "{constraint}".to_string(),
+            ..naming.clone()
+        };
+        name = by_constraint.name(kind, source, target, constraint_name);
+    }
+    taken.insert(name.clone());
+    name
+}
+
+/// Turn a name such as 'invoice_line' into 'InvoiceLine'.
+fn pascal_case(name: &str) -> String {
+    name.split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(|word| {
+            let mut chars = word.chars();
+            chars
+                .next()
+                .map(|first| first.to_ascii_uppercase().to_string() + chars.as_str())
+                .unwrap_or_default()
+        })
+        .collect()
+}
+
+/// Turn a name such as 'invoice_line' or 'InvoiceLine' into 'invoiceLine'.
+fn camel_case(name: &str) -> String {
+    let pascal = pascal_case(name);
+    let mut chars = pascal.chars();
+    chars
+        .next()
+        .map(|first| first.to_ascii_lowercase().to_string() + chars.as_str())
+        .unwrap_or_default()
+}
+
+/// The name of a type in the DDN metadata.
+fn type_name(r#type: &metadata::Type, nullable: &metadata::Nullable) -> String {
+    let name = match r#type {
+        metadata::Type::ScalarType(name) => pascal_case(name.as_str()),
+        metadata::Type::CompositeType(name) => pascal_case(name.as_str()),
+        metadata::Type::ArrayType(element_type) => format!(
+            "[{}]",
+            type_name(element_type, &metadata::Nullable::Nullable)
+        ),
+    };
+    match nullable {
+        metadata::Nullable::Nullable => name,
+        metadata::Nullable::NonNullable => format!("{name}!"),
+    }
+}
+
+#[derive(Serialize)]
+struct Document<T> {
+    kind: &'static str,
+    version: &'static str,
+    definition: T,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ObjectType {
+    name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    description: Option<String>,
+    fields: Vec<ObjectField>,
+    graphql: ObjectTypeGraphql,
+    data_connector_type_mapping: Vec<DataConnectorTypeMapping>,
+}
+
+impl ObjectType {
+    fn new(
+        type_name: &str,
+        table: &metadata::TableInfo,
+        data_connector_name: &str,
+        collection: &str,
+    ) -> Self {
+        ObjectType {
+            name: type_name.to_string(),
+            description: table.description.clone(),
+            fields: table
+                .columns
+                .iter()
+                .map(|(field_name, column)| ObjectField {
+                    name: field_name.to_string(),
+                    r#type: self::type_name(&column.r#type, &column.nullable),
+                    description: column.description.clone(),
+                })
+                .collect(),
+            graphql: ObjectTypeGraphql {
+                type_name: type_name.to_string(),
+                input_type_name: format!("{type_name}Input"),
+            },
+            data_connector_type_mapping: vec![DataConnectorTypeMapping {
+                data_connector_name: data_connector_name.to_string(),
+                data_connector_object_type: collection.to_string(),
+                field_mapping: table
+                    .columns
+                    .keys()
+                    .map(|field_name| {
+                        (
+                            field_name.to_string(),
+                            FieldMapping {
+                                column: ColumnMapping {
+                                    name: field_name.to_string(),
+                                },
+                            },
+                        )
+                    })
+                    .collect(),
+            }],
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct ObjectField {
+    name: String,
+    #[serde(rename = "type")]
+    r#type: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    description: Option<String>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ObjectTypeGraphql {
+    type_name: String,
+    input_type_name: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct DataConnectorTypeMapping {
+    data_connector_name: String,
+    data_connector_object_type: String,
+    field_mapping: BTreeMap<String, FieldMapping>,
+}
+
+#[derive(Serialize)]
+struct FieldMapping {
+    column: ColumnMapping,
+}
+
+#[derive(Serialize)]
+struct ColumnMapping {
+    name: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Model {
+    name: String,
+    object_type: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    description: Option<String>,
+    source: ModelSource,
+    orderable_fields: Vec<OrderableField>,
+    graphql: ModelGraphql,
+}
+
+impl Model {
+    fn new(
+        type_name: &str,
+        table: &metadata::TableInfo,
+        data_connector_name: &str,
+        collection: &str,
+    ) -> Self {
+        let root_field = camel_case(collection);
+        Model {
+            name: type_name.to_string(),
+            object_type: type_name.to_string(),
+            description: table.description.clone(),
+            source: ModelSource {
+                data_connector_name: data_connector_name.to_string(),
+                collection: collection.to_string(),
+            },
+            orderable_fields: table
+                .columns
+                .keys()
+                .map(|field_name| OrderableField {
+                    field_name: field_name.to_string(),
+                    order_by_directions: OrderByDirections { enable_all: true },
+                })
+                .collect(),
+            graphql: ModelGraphql {
+                select_many: SelectMany {
+                    query_root_field: root_field.clone(),
+                },
+                // every uniqueness constraint or logical key can look up a single row
+                select_uniques: table
+                    .row_identifiers()
+                    .map(|(_, constraint)| {
+                        let fields: Vec<String> =
+                            constraint.0.values().map(ToString::to_string).collect();
+                        let by: Vec<String> =
+                            fields.iter().map(|field| pascal_case(field)).collect();
+                        SelectUnique {
+                            query_root_field: format!("{root_field}By{}", by.join("And")),
+                            unique_identifier: fields,
+                        }
+                    })
+                    .collect(),
+                order_by_expression_type: format!("{type_name}OrderBy"),
+            },
+        }
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ModelSource {
+    data_connector_name: String,
+    collection: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct OrderableField {
+    field_name: String,
+    order_by_directions: OrderByDirections,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct OrderByDirections {
+    enable_all: bool,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ModelGraphql {
+    select_many: SelectMany,
+    select_uniques: Vec<SelectUnique>,
+    order_by_expression_type: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SelectMany {
+    query_root_field: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SelectUnique {
+    query_root_field: String,
+    unique_identifier: Vec<String>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Relationship {
+    name: String,
+    source_type: String,
+    target: RelationshipTarget,
+    mapping: Vec<RelationshipMapping>,
+}
+
+impl Relationship {
+    /// A relationship from the model of one collection to that of another, joining the fields of
+    /// the source to the fields of the target.
+    fn new<'a>(
+        name: String,
+        source_collection: &str,
+        target_collection: &str,
+        relationship_type: &'static str,
+        fields: impl Iterator<Item = (&'a str, &'a str)>,
+    ) -> Self {
+        Relationship {
+            name,
+            source_type: pascal_case(source_collection),
+            target: RelationshipTarget {
+                model: RelationshipTargetModel {
+                    name: pascal_case(target_collection),
+                    relationship_type,
+                },
+            },
+            mapping: fields
+                .map(|(source, target)| RelationshipMapping {
+                    source: MappingSource {
+                        field_path: vec![FieldPathElement {
+                            field_name: source.to_string(),
+                        }],
+                    },
+                    target: MappingTarget {
+                        model_field: vec![FieldPathElement {
+                            field_name: target.to_string(),
+                        }],
+                    },
+                })
+                .collect(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct RelationshipTarget {
+    model: RelationshipTargetModel,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct RelationshipTargetModel {
+    name: String,
+    relationship_type: &'static str,
+}
+
+#[derive(Serialize)]
+struct RelationshipMapping {
+    source: MappingSource,
+    target: MappingTarget,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct MappingSource {
+    field_path: Vec<FieldPathElement>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct MappingTarget {
+    model_field: Vec<FieldPathElement>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct FieldPathElement {
+    field_name: String,
+}
@@ -0,0 +1,86 @@
+//! Connect to the database of a configuration and describe what the connector would get.
+
+use ndc_postgres_configuration as configuration;
+use ndc_postgres_configuration::environment::Environment;
+
+use super::{Context, Error, OutputFormat};
+
+/// Resolve the connection URI, connect to the database and report on the server, the session and
+/// the access the connector has to the schemas in the metadata.
+pub async fn run(context: Context<impl Environment>) -> anyhow::Result<()> {
+    let parsed_configuration = configuration::parse_configuration(&context.context_path).await?;
+    let configuration =
+        configuration::make_runtime_configuration(parsed_configuration, &context.environment)?;
+
+    let report =
+        configuration::connection_check::check_connection(&configuration, &context.environment)
+            .await?;
+
+    let problems = report.problems();
+    match context.output_format {
+        OutputFormat::Text => print_report(&report, &problems),
+        OutputFormat::Json => {
+            let schemas: Vec<serde_json::Value> = report
+                .schemas
+                .iter()
+                .map(|access| {
+                    serde_json::json!({
+                        "schema": access.schema,
+                        "exists": access.exists,
+                        "usage": access.usage,
+                        "create": access.create,
+                    })
+                })
+                .collect();
+            println!(
+                "{}",
+                serde_json::json!({
+                    "serverVersion": report.server_version,
+                    "ssl": report.ssl,
+                    "currentRole": report.current_role,
+                    "searchPath": report.search_path,
+                    "schemas": schemas,
+                    "problems": problems,
+                })
+            );
+        }
+    }
+    if problems.is_empty() {
+        Ok(())
+    } else {
+        Err(Error::InaccessibleSchemas(problems.len()))?
+    }
+}
+
+/// Describe the connection for people to read.
+fn print_report(report: &configuration::connection_check::ConnectionReport, problems: &[String]) {
+    println!("Connected to the database.");
+    println!("  server version: {}", report.server_version);
+    println!(
+        "  SSL: {}",
+        match report.ssl {
+            Some(true) => "on",
+            Some(false) => "off",
+            None => "unknown",
+        }
+    );
+    println!("  current role: {}", report.current_role);
+    println!("  search path: {}", report.search_path);
+    if report.schemas.is_empty() {
+        println!("  schemas: there are no tables in the metadata yet");
+    } else {
+        println!("  schemas:");
+        for access in &report.schemas {
+            let privileges = match (access.exists, access.usage, access.create) {
+                (false, _, _) => "missing",
+                (true, false, _) => "no access",
+                (true, true, false) => "USAGE",
+                (true, true, true) => "USAGE, CREATE",
+            };
+            println!("    {}: {privileges}", access.schema);
+        }
+    }
+    for problem in problems {
+        eprintln!("- {problem}");
+    }
+}
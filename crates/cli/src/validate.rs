@@ -0,0 +1,75 @@
+//! Check a configuration for problems, reporting all of them at once.
+
+use super::compile_query::OfflineEnvironment;
+use super::{Context, Error, OutputFormat};
+use ndc_postgres_configuration as configuration;
+use ndc_postgres_configuration::environment::Environment;
+
+/// Parse the configuration, resolve its environment variables and check its metadata for
+/// consistency. Optionally, check that the tables and columns it describes exist in the
+/// database.
+pub async fn run(check_database: bool, context: Context<impl Environment>) -> anyhow::Result<()> {
+    let output_format = context.output_format;
+    let parsed_configuration = configuration::parse_configuration(&context.context_path).await?;
+    let mut problems = vec![];
+
+    // Variables missing from the environment do not stop us from checking the metadata, which
+    // we do with placeholders for them instead.
+    let (configuration, can_connect) = match configuration::make_runtime_configuration(
+        parsed_configuration.clone(),
+        &context.environment,
+    ) {
+        Ok(configuration) => (configuration, true),
+        Err(error) => {
+            problems.push(error.to_string());
+            match configuration::make_runtime_configuration(
+                parsed_configuration,
+                OfflineEnvironment(&context.environment),
+            ) {
+                Ok(configuration) => (configuration, false),
+                // The configuration itself is invalid, so there is nothing more we can check.
+                Err(_) => return report(&problems, output_format),
+            }
+        }
+    };
+
+    problems.extend(configuration::validate::check_metadata(&configuration));
+    if check_database {
+        if can_connect {
+            problems.extend(
+                configuration::validate::check_database(&configuration, context.environment)
+                    .await?,
+            );
+        } else {
+            problems.push(
+                "the database was not checked, as the connection settings cannot be resolved"
+                    .to_string(),
+            );
+        }
+    }
+
+    report(&problems, output_format)
+}
+
+/// Print the problems found, if any, failing if there are some.
+fn report(problems: &[String], output_format: OutputFormat) -> anyhow::Result<()> {
+    match output_format {
+        OutputFormat::Text => {
+            if problems.is_empty() {
+                println!("The configuration is valid.");
+            }
+            for problem in problems {
+                eprintln!("- {problem}");
+            }
+        }
+        OutputFormat::Json => println!(
+            "{}",
+            serde_json::json!({ "valid": problems.is_empty(), "problems": problems })
+        ),
+    }
+    if problems.is_empty() {
+        Ok(())
+    } else {
+        Err(Error::InvalidConfiguration(problems.len()))?
+    }
+}
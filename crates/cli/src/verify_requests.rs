@@ -0,0 +1,146 @@
+//! Check that the database accepts the SQL generated for a directory of recorded query requests.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use tokio::fs;
+
+use super::{compile_query, Context, Error, OutputFormat};
+use ndc_postgres_configuration as configuration;
+use ndc_postgres_configuration::environment::Environment;
+
+/// Translate every query request in the directory using the configuration, and check that the
+/// database accepts the SQL of each with `EXPLAIN`, without running it. Each request is checked
+/// against the database its collections are in. Every request which cannot be translated or
+/// which the database rejects is reported.
+pub async fn run(directory: PathBuf, context: Context<impl Environment>) -> anyhow::Result<()> {
+    let parsed_configuration = configuration::parse_configuration(&context.context_path).await?;
+    let configuration =
+        configuration::make_runtime_configuration(parsed_configuration, &context.environment)?;
+    let mut pools = Pools::default();
+
+    let mut request_files = vec![];
+    let mut entries = fs::read_dir(&directory).await?;
+    while let Some(entry) = entries.next_entry().await? {
+        let path = entry.path();
+        if path
+            .extension()
+            .is_some_and(|extension| extension == "json")
+        {
+            request_files.push(path);
+        }
+    }
+    request_files.sort();
+
+    let mut rejected = 0;
+    let mut results = vec![];
+    for request_file in &request_files {
+        let result = verify_request(
+            &configuration,
+            &context.environment,
+            &mut pools,
+            request_file,
+        )
+        .await;
+        match (&result, context.output_format) {
+            (Ok(()), OutputFormat::Text) => println!("ok: {}", request_file.display()),
+            (Err(err), OutputFormat::Text) => eprintln!("- {}: {err:#}", request_file.display()),
+            (_, OutputFormat::Json) => results.push(serde_json::json!({
+                "file": request_file.display().to_string(),
+                "error": result.as_ref().err().map(|err| format!("{err:#}")),
+            })),
+        }
+        if result.is_err() {
+            rejected += 1;
+        }
+    }
+    for pool in pools.values() {
+        pool.close().await;
+    }
+
+    match context.output_format {
+        OutputFormat::Text if rejected == 0 => {
+            println!(
+                "The database accepts all {} request(s).",
+                request_files.len()
+            );
+        }
+        OutputFormat::Text => {}
+        OutputFormat::Json => println!("{}", serde_json::json!({ "requests": results })),
+    }
+    if rejected == 0 {
+        Ok(())
+    } else {
+        Err(Error::RejectedRequests(rejected))?
+    }
+}
+
+/// A connection to each database requests have been checked against so far: the primary
+/// database, under `None`, and the connection targets by name.
+type Pools = BTreeMap<Option<String>, sqlx::PgPool>;
+
+/// Read a query request, translate it and check that the database it is for accepts its SQL.
+async fn verify_request(
+    configuration: &configuration::Configuration,
+    environment: &impl Environment,
+    pools: &mut Pools,
+    request_file: &Path,
+) -> anyhow::Result<()> {
+    let request: ndc_models::QueryRequest =
+        serde_json::from_str(&fs::read_to_string(request_file).await?)?;
+    let target = request_target(configuration, &request)?.map(ToString::to_string);
+    let plan = compile_query::plan(configuration, request)?;
+
+    let pool = match pools.entry(target) {
+        std::collections::btree_map::Entry::Occupied(entry) => entry.into_mut(),
+        std::collections::btree_map::Entry::Vacant(entry) => {
+            let connection_uri = match entry.key() {
+                None => &configuration.connection_uri,
+                Some(target) => configuration.targets.get(target).ok_or_else(|| {
+                    anyhow::anyhow!("there is no connection to the target '{target}'")
+                })?,
+            };
+            let connect_options = configuration::get_connect_options(
+                &configuration::ConnectionUri::from(connection_uri.as_str()),
+                environment,
+            )?;
+            let pool = sqlx::postgres::PgPoolOptions::new()
+                .max_connections(1)
+                .connect_with(connect_options)
+                .await?;
+            entry.insert(pool)
+        }
+    };
+    query_engine_execution::query::verify(pool, &plan).await?;
+    Ok(())
+}
+
+/// The connection target of the collections a request refers to, or `None` for the primary
+/// database, as the connector routes it.
+fn request_target<'a>(
+    configuration: &'a configuration::Configuration,
+    request: &ndc_models::QueryRequest,
+) -> anyhow::Result<Option<&'a str>> {
+    let metadata = &configuration.metadata;
+    let collection_target =
+        |collection: &ndc_models::CollectionName| match metadata.tables.0.get(collection) {
+            Some(table) => table.target.as_deref(),
+            None => metadata
+                .native_operations
+                .queries
+                .0
+                .get(collection)
+                .and_then(|native_query| native_query.target.as_deref()),
+        };
+    let target = collection_target(&request.collection);
+    for relationship in request.collection_relationships.values() {
+        if collection_target(&relationship.target_collection) != target {
+            anyhow::bail!(
+                "'{}' and '{}' are in different databases, so they cannot be used in the same request",
+                request.collection,
+                relationship.target_collection
+            );
+        }
+    }
+    Ok(target)
+}
@@ -0,0 +1,67 @@
+//! Watch the SQL files of Native Operations, and check them again whenever they change.
+
+use ndc_postgres_configuration as configuration;
+use ndc_postgres_configuration::environment::Environment;
+use ndc_postgres_configuration::watch::{NativeQueryFiles, POLL_INTERVAL};
+
+use super::{Context, OutputFormat};
+
+/// Check the Native Operations again whenever one of the SQL files they are read from changes,
+/// reporting any problems, until interrupted.
+pub async fn run(context: Context<impl Environment>) -> anyhow::Result<()> {
+    let parsed_configuration = configuration::parse_configuration(&context.context_path).await?;
+    let configuration =
+        configuration::make_runtime_configuration(parsed_configuration, &context.environment)?;
+
+    let mut files = NativeQueryFiles::new(
+        &context.context_path,
+        &configuration.metadata.native_operations,
+    );
+    if files.is_empty() {
+        match context.output_format {
+            OutputFormat::Text => println!("There are no Native Operation SQL files to watch."),
+            OutputFormat::Json => {
+                tracing::warn!("There are no Native Operation SQL files to watch.");
+            }
+        }
+        return Ok(());
+    }
+    if context.output_format == OutputFormat::Text {
+        println!("Watching the SQL files of the Native Operations for changes.");
+    }
+
+    let mut interval = tokio::time::interval(POLL_INTERVAL);
+    loop {
+        interval.tick().await;
+        let changed = files.changed();
+        if changed.is_empty() {
+            continue;
+        }
+
+        let problems = files.reload(&configuration).err().unwrap_or_default();
+        match context.output_format {
+            OutputFormat::Text => {
+                for file in &changed {
+                    println!("{} changed.", file.display());
+                }
+                if problems.is_empty() {
+                    println!("The Native Operations are valid.");
+                }
+                for problem in &problems {
+                    eprintln!("- {problem}");
+                }
+            }
+            // Each check is reported as a line of its own, as it happens.
+            OutputFormat::Json => {
+                let changed: Vec<String> = changed
+                    .iter()
+                    .map(|file| file.display().to_string())
+                    .collect();
+                println!(
+                    "{}",
+                    serde_json::json!({ "changed": changed, "problems": problems })
+                );
+            }
+        }
+    }
+}
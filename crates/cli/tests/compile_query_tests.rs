@@ -0,0 +1,73 @@
+use tokio::fs;
+
+use ndc_postgres_cli::*;
+use ndc_postgres_configuration::environment::EmptyEnvironment;
+
+/// Compiling a query does not involve the database, so it does not need a connection string.
+#[tokio::test]
+async fn test_compile_query_without_a_connection_uri() -> anyhow::Result<()> {
+    let dir = tempfile::tempdir()?;
+    fs::write(
+        dir.path().join("configuration.json"),
+        r#"{
+          "version": "5",
+          "metadata": {
+            "tables": {
+              "albums": {
+                "schemaName": "public",
+                "tableName": "albums",
+                "columns": {
+                  "id": {
+                    "name": "id",
+                    "type": { "scalarType": "int4" },
+                    "nullable": "nonNullable",
+                    "description": null
+                  }
+                },
+                "uniquenessConstraints": {},
+                "foreignRelations": {}
+              }
+            },
+            "types": {
+              "scalar": {
+                "int4": {
+                  "typeName": "int4",
+                  "schemaName": "pg_catalog",
+                  "description": null,
+                  "aggregateFunctions": {},
+                  "comparisonOperators": {},
+                  "typeRepresentation": "int32"
+                }
+              },
+              "composite": {}
+            },
+            "nativeOperations": { "queries": {}, "mutations": {} }
+          }
+        }"#,
+    )
+    .await?;
+    let request = dir.path().join("request.json");
+    fs::write(
+        &request,
+        r#"{
+          "collection": "albums",
+          "query": {
+            "fields": {
+              "id": { "type": "column", "column": "id", "arguments": {} }
+            }
+          },
+          "arguments": {},
+          "collection_relationships": {}
+        }"#,
+    )
+    .await?;
+
+    let context = Context {
+        context_path: dir.path().to_owned(),
+        environment: EmptyEnvironment,
+        release_version: None,
+        output_format: OutputFormat::Text,
+    };
+    run(Command::CompileQuery { request }, context).await?;
+    Ok(())
+}
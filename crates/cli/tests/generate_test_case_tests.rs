@@ -0,0 +1,160 @@
+use std::path::Path;
+
+use tokio::fs;
+
+use ndc_postgres_cli::*;
+use ndc_postgres_configuration::environment::EmptyEnvironment;
+
+/// A configuration whose native query reads its SQL from a file next to it.
+async fn write_configuration(directory: &Path) -> anyhow::Result<()> {
+    fs::create_dir(directory.join("native_queries")).await?;
+    fs::write(
+        directory.join("native_queries").join("album_ids.sql"),
+        "SELECT 1 AS id",
+    )
+    .await?;
+    fs::write(
+        directory.join("configuration.json"),
+        r#"{
+          "version": "5",
+          "metadata": {
+            "tables": {},
+            "types": {
+              "scalar": {
+                "int4": {
+                  "typeName": "int4",
+                  "schemaName": "pg_catalog",
+                  "description": null,
+                  "aggregateFunctions": {},
+                  "comparisonOperators": {},
+                  "typeRepresentation": "int32"
+                }
+              },
+              "composite": {}
+            },
+            "nativeOperations": {
+              "queries": {
+                "album_ids": {
+                  "sql": { "file": "./native_queries/album_ids.sql" },
+                  "columns": {
+                    "id": {
+                      "name": "id",
+                      "type": { "scalarType": "int4" },
+                      "nullable": "nonNullable",
+                      "description": null
+                    }
+                  }
+                }
+              },
+              "mutations": {}
+            }
+          }
+        }"#,
+    )
+    .await?;
+    Ok(())
+}
+
+async fn write_request(directory: &Path, collection: &str) -> anyhow::Result<std::path::PathBuf> {
+    let request = directory.join("request.json");
+    fs::write(
+        &request,
+        format!(
+            r#"{{
+              "collection": "{collection}",
+              "query": {{
+                "fields": {{
+                  "id": {{ "type": "column", "column": "id", "arguments": {{}} }}
+                }}
+              }},
+              "arguments": {{}},
+              "collection_relationships": {{}}
+            }}"#
+        ),
+    )
+    .await?;
+    Ok(request)
+}
+
+async fn generate_test_case(
+    context_path: &Path,
+    request: std::path::PathBuf,
+    name: &str,
+    tests_directory: &Path,
+) -> anyhow::Result<()> {
+    let context = Context {
+        context_path: context_path.to_owned(),
+        environment: EmptyEnvironment,
+        release_version: None,
+        output_format: OutputFormat::Text,
+    };
+    run(
+        Command::GenerateTestCase {
+            request,
+            name: name.to_string(),
+            tests_directory: tests_directory.to_owned(),
+        },
+        context,
+    )
+    .await
+}
+
+/// The test case holds the SQL of native queries inline, as it has no copy of their files.
+#[tokio::test]
+async fn test_generate_test_case_inlines_native_query_sql() -> anyhow::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let tests_directory = tempfile::tempdir()?;
+    fs::create_dir(tests_directory.path().join("snapshots")).await?;
+    write_configuration(dir.path()).await?;
+    let request = write_request(dir.path(), "album_ids").await?;
+
+    generate_test_case(dir.path(), request, "album_ids", tests_directory.path()).await?;
+
+    let goldenfile_directory = tests_directory.path().join("goldenfiles").join("album_ids");
+    let configuration: serde_json::Value = serde_json::from_str(
+        &fs::read_to_string(goldenfile_directory.join("configuration.json")).await?,
+    )?;
+    assert_eq!(
+        configuration["metadata"]["nativeOperations"]["queries"]["album_ids"]["sql"],
+        serde_json::json!({ "inline": "SELECT 1 AS id" })
+    );
+    assert!(fs::try_exists(goldenfile_directory.join("request.json")).await?);
+    let snapshot = fs::read_to_string(
+        tests_directory
+            .path()
+            .join("snapshots")
+            .join("tests__album_ids.snap"),
+    )
+    .await?;
+    assert!(snapshot.contains("SELECT 1 AS id"));
+    Ok(())
+}
+
+/// A request which does not translate leaves no test case behind.
+#[tokio::test]
+async fn test_generate_test_case_writes_nothing_when_translation_fails() -> anyhow::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let tests_directory = tempfile::tempdir()?;
+    write_configuration(dir.path()).await?;
+    let request = write_request(dir.path(), "no_such_collection").await?;
+
+    let result = generate_test_case(dir.path(), request, "missing", tests_directory.path()).await;
+
+    assert!(result.is_err());
+    assert!(!fs::try_exists(tests_directory.path().join("goldenfiles")).await?);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_generate_test_case_rejects_names_which_are_not_identifiers() -> anyhow::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let tests_directory = tempfile::tempdir()?;
+    write_configuration(dir.path()).await?;
+    let request = write_request(dir.path(), "album_ids").await?;
+
+    let result = generate_test_case(dir.path(), request, "../x", tests_directory.path()).await;
+
+    assert!(result.is_err());
+    assert!(!fs::try_exists(tests_directory.path().join("x")).await?);
+    Ok(())
+}
@@ -14,10 +14,12 @@ async fn test_initialize_directory() -> anyhow::Result<()> {
         context_path: dir.path().to_owned(),
         environment: configuration::environment::EmptyEnvironment,
         release_version: None,
+        output_format: OutputFormat::Text,
     };
     run(
         Command::Initialize {
             with_metadata: false,
+            format: ConfigurationFormat::Json,
         },
         context,
     )
@@ -42,6 +44,40 @@ async fn test_initialize_directory() -> anyhow::Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn test_initialize_directory_with_yaml() -> anyhow::Result<()> {
+    let dir = tempfile::tempdir()?;
+
+    let context = Context {
+        context_path: dir.path().to_owned(),
+        environment: configuration::environment::EmptyEnvironment,
+        release_version: None,
+        output_format: OutputFormat::Text,
+    };
+    run(
+        Command::Initialize {
+            with_metadata: false,
+            format: ConfigurationFormat::Yaml,
+        },
+        context,
+    )
+    .await?;
+
+    assert!(dir.path().join("configuration.yaml").exists());
+    assert!(!dir.path().join("configuration.json").exists());
+    let ParsedConfiguration::Version5(parsed_configuration) =
+        configuration::parse_configuration(&dir).await?
+    else {
+        panic!("Expected a version 5 configuration");
+    };
+    assert_eq!(
+        parsed_configuration.format,
+        configuration::version5::ConfigurationFormat::Yaml
+    );
+
+    Ok(())
+}
+
 #[tokio::test]
 async fn test_initialize_version_is_unchanged() -> anyhow::Result<()> {
     let dir = tempfile::tempdir()?;
@@ -50,10 +86,12 @@ async fn test_initialize_version_is_unchanged() -> anyhow::Result<()> {
         context_path: dir.path().to_owned(),
         environment: configuration::environment::EmptyEnvironment,
         release_version: None,
+        output_format: OutputFormat::Text,
     };
     run(
         Command::Initialize {
             with_metadata: false,
+            format: ConfigurationFormat::Json,
         },
         context,
     )
@@ -87,10 +125,12 @@ async fn test_do_not_initialize_when_files_already_exist() -> anyhow::Result<()>
         context_path: dir.path().to_owned(),
         environment: configuration::environment::EmptyEnvironment,
         release_version: None,
+        output_format: OutputFormat::Text,
     };
     match run(
         Command::Initialize {
             with_metadata: false,
+            format: ConfigurationFormat::Json,
         },
         context,
     )
@@ -114,10 +154,12 @@ async fn test_initialize_directory_with_metadata() -> anyhow::Result<()> {
         context_path: dir.path().to_owned(),
         environment: configuration::environment::EmptyEnvironment,
         release_version: None,
+        output_format: OutputFormat::Text,
     };
     run(
         Command::Initialize {
             with_metadata: true,
+            format: ConfigurationFormat::Json,
         },
         context,
     )
@@ -151,10 +193,12 @@ async fn test_initialize_directory_with_metadata_and_release_version() -> anyhow
         context_path: dir.path().to_owned(),
         environment: configuration::environment::EmptyEnvironment,
         release_version: Some("v1.2.3"),
+        output_format: OutputFormat::Text,
     };
     run(
         Command::Initialize {
             with_metadata: true,
+            format: ConfigurationFormat::Json,
         },
         context,
     )
@@ -0,0 +1,45 @@
+mod common;
+
+use tokio::fs;
+
+use ndc_postgres_cli::*;
+use ndc_postgres_configuration::environment::EmptyEnvironment;
+
+/// Each version of the configuration format gets its own JSON Schema, which only accepts its
+/// own version tag.
+#[tokio::test]
+async fn test_schema_configuration_writes_the_schema_of_each_version() -> anyhow::Result<()> {
+    for version in 3..=5 {
+        let dir = tempfile::tempdir()?;
+        let output = dir.path().join("configuration.schema.json");
+
+        let context = Context {
+            context_path: dir.path().to_owned(),
+            environment: EmptyEnvironment,
+            release_version: None,
+            output_format: OutputFormat::Text,
+        };
+        run(
+            Command::Schema(schema::Command::Configuration {
+                version,
+                output: Some(output.clone()),
+            }),
+            context,
+        )
+        .await?;
+
+        common::assert_file_ends_with_newline(&output).await?;
+        let contents = fs::read_to_string(&output).await?;
+        let schema: serde_json::Value = serde_json::from_str(&contents)?;
+        assert_eq!(
+            schema["$schema"], "http://json-schema.org/draft-07/schema#",
+            "{contents}"
+        );
+        assert_eq!(
+            schema["definitions"]["Version"]["enum"],
+            serde_json::json!([version.to_string()]),
+            "{contents}"
+        );
+    }
+    Ok(())
+}
@@ -0,0 +1,58 @@
+use tokio::fs;
+
+use ndc_postgres_cli::*;
+use ndc_postgres_configuration::environment::EmptyEnvironment;
+
+/// A connection string missing from the environment is reported along with the problems of the
+/// metadata, rather than stopping us from checking it.
+#[tokio::test]
+async fn test_validate_reports_all_problems() -> anyhow::Result<()> {
+    let dir = tempfile::tempdir()?;
+    fs::write(
+        dir.path().join("configuration.json"),
+        r#"{
+          "version": "5",
+          "metadata": {
+            "tables": {
+              "albums": {
+                "schemaName": "public",
+                "tableName": "albums",
+                "columns": {
+                  "id": {
+                    "name": "id",
+                    "type": { "scalarType": "int4" },
+                    "nullable": "nonNullable",
+                    "description": null
+                  }
+                },
+                "uniquenessConstraints": {},
+                "foreignRelations": {}
+              }
+            },
+            "types": {
+              "scalar": {},
+              "composite": {}
+            },
+            "nativeOperations": { "queries": {}, "mutations": {} }
+          }
+        }"#,
+    )
+    .await?;
+
+    let context = Context {
+        context_path: dir.path().to_owned(),
+        environment: EmptyEnvironment,
+        release_version: None,
+        output_format: OutputFormat::Text,
+    };
+    let error = run(
+        Command::Validate {
+            check_database: false,
+        },
+        context,
+    )
+    .await
+    .expect_err("Expected the configuration to be invalid");
+    assert_eq!(error.to_string(), "the configuration has 2 problem(s)");
+    Ok(())
+}
@@ -1,20 +1,27 @@
 //! Configuration for the connector.
 
+use std::fmt::Write;
 use std::path::Path;
 
 use query_engine_metadata::metadata;
 
+use crate::connect::SessionSettings;
 use crate::environment::Environment;
 use crate::error::{
     MakeRuntimeConfigurationError, MultiError, ParseConfigurationError,
     WriteParsedConfigurationError,
 };
-use crate::values::{IsolationLevel, PoolSettings};
+use crate::values::{
+    CollectionPolicy, CountEstimation, DistributedQueryRouting, HealthCheckSettings,
+    IsolationLevel, MutationFailurePolicy, PoolSettings, QueryLimits, RelationshipNaming,
+    RequestRecordingSettings, RetryPolicy, UsageStatisticsSettings,
+};
 use crate::version3;
 use crate::version4;
 use crate::version5;
 use crate::VersionTag;
 use schemars::{gen::SchemaSettings, schema::RootSchema};
+use sha2::{Digest, Sha256};
 
 pub fn generate_latest_schema() -> RootSchema {
     SchemaSettings::openapi3()
@@ -22,6 +29,17 @@ pub fn generate_latest_schema() -> RootSchema {
         .into_root_schema_for::<version4::ParsedConfiguration>()
 }
 
+/// Generate the JSON Schema of a version of the configuration format, which editors can use to
+/// validate and complete `configuration.json`.
+pub fn generate_schema(version: VersionTag) -> RootSchema {
+    let generator = SchemaSettings::draft07().into_generator();
+    match version {
+        VersionTag::Version3 => generator.into_root_schema_for::<version3::RawConfiguration>(),
+        VersionTag::Version4 => generator.into_root_schema_for::<version4::ParsedConfiguration>(),
+        VersionTag::Version5 => generator.into_root_schema_for::<version5::ParsedConfiguration>(),
+    }
+}
+
 pub const DEFAULT_CONNECTION_URI_VARIABLE: &str = "CONNECTION_URI";
 
 /// The 'ParsedConfiguration' type models the various concrete configuration formats that are
@@ -44,6 +62,17 @@ pub enum ParsedConfiguration {
 }
 
 impl ParsedConfiguration {
+    /// A digest of the contents of the configuration, which tells deployments running different
+    /// configurations apart.
+    pub fn hash(&self) -> Result<String, serde_json::Error> {
+        let serialized = match self {
+            ParsedConfiguration::Version3(c) => serde_json::to_vec(c),
+            ParsedConfiguration::Version4(c) => serde_json::to_vec(c),
+            ParsedConfiguration::Version5(c) => serde_json::to_vec(c),
+        }?;
+        Ok(hex(&Sha256::digest(serialized)))
+    }
+
     pub fn initial() -> Self {
         ParsedConfiguration::Version5(version5::ParsedConfiguration::empty())
     }
@@ -56,6 +85,14 @@ impl ParsedConfiguration {
     }
 }
 
+/// A digest written as hexadecimal digits.
+pub(crate) fn hex(digest: &[u8]) -> String {
+    digest.iter().fold(String::new(), |mut hash, byte| {
+        let _ = write!(hash, "{byte:02x}");
+        hash
+    })
+}
+
 /// The 'Configuration' type collects all the information necessary to serve queries at runtime.
 ///
 /// 'ParsedConfiguration' deals with a multitude of different concrete version formats, and each
@@ -67,29 +104,107 @@ impl ParsedConfiguration {
 /// logic by placing the responsibility of dealing with configuration format evolution in
 /// 'ParsedConfiguration.
 ///
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct Configuration {
     pub metadata: metadata::Metadata,
     pub configuration_version_tag: VersionTag,
+    /// A digest of the configuration this was made from. See 'ParsedConfiguration::hash'.
+    pub configuration_hash: String,
     pub pool_settings: PoolSettings,
     pub connection_uri: String,
     pub isolation_level: IsolationLevel,
     pub mutations_version: Option<metadata::mutations::MutationsVersion>,
     pub mutations_prefix: Option<String>,
+    pub mutations_returning_aggregates: bool,
+    pub dialect: query_engine_sql::sql::dialect::SqlDialect,
+    pub use_lateral_joins: bool,
+    pub limit_with_ties: bool,
+    pub strict_boolean_comparisons: bool,
+    /// Whether table collections offer the `snapshot` argument.
+    pub snapshot_reads: bool,
+    /// Whether table collections offer the `distinct_on` argument.
+    pub distinct_on: bool,
+    /// Whether foreach queries with many variable sets copy them into a temporary table.
+    pub temporary_variables_table: bool,
+    /// Hints for `pg_hint_plan` to attach to queries over collections, in which `{{table}}`
+    /// stands for the alias of the collection.
+    pub planner_hints: std::collections::BTreeMap<ndc_models::CollectionName, String>,
+    /// The isolation levels of the Native Mutations which need a stricter one than
+    /// `isolation_level`, as their definitions give them.
+    pub mutation_isolation_levels:
+        std::collections::BTreeMap<ndc_models::ProcedureName, IsolationLevel>,
+    /// Restrictions on the rows of collections which apply to every query.
+    pub collection_policies:
+        std::collections::BTreeMap<ndc_models::CollectionName, CollectionPolicy>,
+    /// How queries over tables distributed by Citus are routed to their shards.
+    pub distributed_query_routing: DistributedQueryRouting,
+    /// Whether star counts over large tables are answered with estimates.
+    pub count_estimation: Option<CountEstimation>,
+    /// Whether queries are planned with `EXPLAIN` before they are run.
+    pub verify_queries: bool,
+    pub read_replicas: Option<ReadReplicas>,
+    /// The connection strings of the other databases tables are served from, by target name.
+    pub targets: std::collections::BTreeMap<String, String>,
+    pub health_check: Option<HealthCheckSettings>,
+    pub annotate_queries: bool,
+    /// The run-time parameter to set to the trace ID of each request, if any.
+    pub request_id_setting: Option<String>,
+    /// Queries which take longer than this are logged (milliseconds).
+    pub slow_query_threshold_ms: Option<u64>,
+    pub log_slow_query_parameters: bool,
+    /// Statements which run for longer than this are cancelled (milliseconds).
+    pub query_timeout_ms: Option<u64>,
+    pub query_limits: QueryLimits,
+    pub retry_policy: RetryPolicy,
+    pub mutation_failure_policy: MutationFailurePolicy,
+    /// Count how each collection is used, if at all.
+    pub usage_statistics: Option<UsageStatisticsSettings>,
+    /// Record each query request, if at all.
+    pub request_recording: Option<RequestRecordingSettings>,
+    pub relationship_naming: RelationshipNaming,
+    pub watch_native_query_files: bool,
+    /// The collections which are JSON tables, whose rows are made of JSON documents with
+    /// `JSON_TABLE`, and so need PostgreSQL 17 or later.
+    pub json_tables: std::collections::BTreeSet<ndc_models::CollectionName>,
+    /// The directory the configuration was read from, which the SQL files of Native Operations
+    /// are relative to. Set by whoever reads the configuration.
+    pub configuration_directory: Option<std::path::PathBuf>,
+}
+
+impl Configuration {
+    /// A stable fingerprint of the configuration this was made from, which is the same for every
+    /// replica serving the same configuration, so that replicas serving different versions of it
+    /// can be told apart during a rollout.
+    pub fn fingerprint(&self) -> &str {
+        &self.configuration_hash
+    }
+}
+
+/// The replicas to run queries against, with their connection strings resolved.
+#[derive(Clone, Debug)]
+pub struct ReadReplicas {
+    pub connection_uris: Vec<String>,
+    /// The replication lag beyond which a replica is not read from (seconds).
+    pub max_replication_lag: u64,
+    /// How often to measure the replication lag (seconds).
+    pub lag_check_interval: u64,
+    /// How long to read from the primary after a mutation (seconds).
+    pub read_your_writes_window: Option<u64>,
 }
 pub async fn introspect(
     input: ParsedConfiguration,
     environment: impl Environment,
+    session: &SessionSettings,
 ) -> anyhow::Result<ParsedConfiguration> {
     match input {
         ParsedConfiguration::Version3(config) => Ok(ParsedConfiguration::Version3(
-            version3::introspect(config, environment).await?,
+            version3::introspect(config, environment, session).await?,
         )),
         ParsedConfiguration::Version4(config) => Ok(ParsedConfiguration::Version4(
-            version4::introspect(config, environment).await?,
+            version4::introspect(config, environment, session).await?,
         )),
         ParsedConfiguration::Version5(config) => Ok(ParsedConfiguration::Version5(
-            version5::introspect(config, environment).await?,
+            version5::introspect(config, environment, session).await?,
         )),
     }
 }
@@ -125,11 +240,20 @@ pub fn make_runtime_configuration(
     parsed_config: ParsedConfiguration,
     environment: impl Environment,
 ) -> Result<Configuration, MakeRuntimeConfigurationError> {
-    match parsed_config {
+    let configuration_hash = parsed_config.hash().map_err(|error| {
+        MakeRuntimeConfigurationError::ConfigurationDigest {
+            message: error.to_string(),
+        }
+    })?;
+    let configuration = match parsed_config {
         ParsedConfiguration::Version3(c) => version3::make_runtime_configuration(c, environment),
         ParsedConfiguration::Version4(c) => version4::make_runtime_configuration(c, environment),
         ParsedConfiguration::Version5(c) => version5::make_runtime_configuration(c, environment),
-    }
+    }?;
+    Ok(Configuration {
+        configuration_hash,
+        ..configuration
+    })
 }
 
 /// Write out a parsed configuration to a directory.
@@ -159,3 +283,22 @@ pub fn upgrade_to_latest_version(parsed_config: ParsedConfiguration) -> ParsedCo
         ParsedConfiguration::Version5(_) => parsed_config,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_hash_depends_only_on_the_contents() -> Result<(), serde_json::Error> {
+        let initial = ParsedConfiguration::initial().hash()?;
+        assert_eq!(initial, ParsedConfiguration::initial().hash()?);
+        assert_eq!(initial.len(), 64);
+
+        let ParsedConfiguration::Version5(mut changed) = ParsedConfiguration::initial() else {
+            panic!("the initial configuration is not version 5");
+        };
+        changed.limit_with_ties = true;
+        assert_ne!(initial, ParsedConfiguration::Version5(changed).hash()?);
+        Ok(())
+    }
+}
@@ -1,6 +1,7 @@
 //! Connection settings.
 
 use std::borrow::Cow;
+use std::time::Duration;
 
 use sqlx::postgres::PgConnectOptions;
 use sqlx::ConnectOptions;
@@ -38,6 +39,44 @@ pub fn get_connect_options(
     })
 }
 
+/// Settings for the sessions the CLI opens to introspect the database and check Native
+/// Operations, so that it does not get in the way of a busy production database.
+/// By default, sessions keep the settings of the database.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SessionSettings {
+    /// Cancel statements which run for longer than this.
+    pub statement_timeout: Option<Duration>,
+    /// Give up on statements which wait for a lock for longer than this, rather than holding up
+    /// the sessions queued behind them.
+    pub lock_timeout: Option<Duration>,
+    /// Make every transaction read-only.
+    pub read_only: bool,
+}
+
+impl SessionSettings {
+    /// Ask for these settings as run-time parameters when connecting.
+    pub fn apply(&self, connect_options: PgConnectOptions) -> PgConnectOptions {
+        let mut parameters = vec![];
+        if let Some(statement_timeout) = self.statement_timeout {
+            parameters.push((
+                "statement_timeout",
+                statement_timeout.as_millis().to_string(),
+            ));
+        }
+        if let Some(lock_timeout) = self.lock_timeout {
+            parameters.push(("lock_timeout", lock_timeout.as_millis().to_string()));
+        }
+        if self.read_only {
+            parameters.push(("default_transaction_read_only", "on".to_string()));
+        }
+        if parameters.is_empty() {
+            connect_options
+        } else {
+            connect_options.options(parameters)
+        }
+    }
+}
+
 /// SSL certificate information.
 struct SslInfo {
     client: Option<SslClientInfo>,
@@ -84,3 +123,30 @@ fn read_ssl_info(environment: impl Environment) -> SslInfo {
         root_certificate,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn session_settings_are_sent_as_options() {
+        let session = SessionSettings {
+            statement_timeout: Some(Duration::from_secs(300)),
+            lock_timeout: Some(Duration::from_secs(5)),
+            read_only: true,
+        };
+        let connect_options = session.apply(PgConnectOptions::new());
+        assert_eq!(
+            connect_options.get_options(),
+            Some(
+                "-c statement_timeout=300000 -c lock_timeout=5000 -c default_transaction_read_only=on"
+            )
+        );
+    }
+
+    #[test]
+    fn default_session_settings_send_no_options() {
+        let connect_options = SessionSettings::default().apply(PgConnectOptions::new());
+        assert_eq!(connect_options.get_options(), None);
+    }
+}
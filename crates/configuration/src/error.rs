@@ -28,6 +28,12 @@ pub enum ParseConfigurationError {
     #[error("Did not find expected version tag: \"{0}\"")]
     DidNotFindExpectedVersionTag(String),
 
+    #[error("{name} is defined more than once, most recently in {file_path}")]
+    DuplicateMetadataEntry {
+        file_path: std::path::PathBuf,
+        name: String,
+    },
+
     #[error("Unable to parse any configuration versions: {0}")]
     UnableToParseAnyVersions(MultiError),
 }
@@ -71,4 +77,78 @@ pub enum MakeRuntimeConfigurationError {
         file_path: std::path::PathBuf,
         message: String,
     },
+    #[error(
+        "invalid value in environment variable {variable} when processing {file_path}: {message}"
+    )]
+    InvalidEnvironmentVariable {
+        file_path: std::path::PathBuf,
+        variable: crate::environment::Variable,
+        message: String,
+    },
+    #[error("could not compute the digest of the configuration: {message}")]
+    ConfigurationDigest { message: String },
+    #[error("invalid planner hint for {collection} in {file_path}: a hint cannot contain '*/'")]
+    InvalidPlannerHint {
+        file_path: std::path::PathBuf,
+        collection: ndc_models::CollectionName,
+    },
+    #[error("the read replica lag check interval in {file_path} must be at least one second")]
+    InvalidLagCheckInterval { file_path: std::path::PathBuf },
+    #[error("the health check interval in {file_path} must be at least one second")]
+    InvalidHealthCheckInterval { file_path: std::path::PathBuf },
+    #[error("the type override of {schema_name}.{type_name} in {file_path} casts values to {cast_as}, which is not the name of a built-in type")]
+    InvalidTypeOverrideCast {
+        file_path: std::path::PathBuf,
+        schema_name: String,
+        type_name: String,
+        cast_as: String,
+    },
+    #[error("the table {collection} in {file_path} refers to the target {target}, which is not in the connection settings")]
+    UnknownTarget {
+        file_path: std::path::PathBuf,
+        collection: ndc_models::CollectionName,
+        target: String,
+    },
+    #[error("the foreign key {constraint} of the native query {native_query} in {file_path} refers to the table {foreign_table}, which is not in the metadata")]
+    UnknownForeignTable {
+        file_path: std::path::PathBuf,
+        native_query: ndc_models::CollectionName,
+        constraint: String,
+        foreign_table: String,
+    },
+    #[error("the logical key {logical_key} of the table {collection} in {file_path} refers to the field {field}, which is not a column of the table")]
+    UnknownLogicalKeyField {
+        file_path: std::path::PathBuf,
+        collection: ndc_models::CollectionName,
+        logical_key: String,
+        field: ndc_models::FieldName,
+    },
+    #[error("there is a policy for the collection {collection} in {file_path}, which is not a table or native query")]
+    UnknownPolicyCollection {
+        file_path: std::path::PathBuf,
+        collection: ndc_models::CollectionName,
+    },
+    #[error("invalid policy filter for the collection {collection} in {file_path}: {message}")]
+    InvalidPolicyFilter {
+        file_path: std::path::PathBuf,
+        collection: ndc_models::CollectionName,
+        message: String,
+    },
+    #[error("the native query {collection} in {file_path} has an isolation level, which only native mutations can have")]
+    NativeQueryIsolationLevel {
+        file_path: std::path::PathBuf,
+        collection: ndc_models::CollectionName,
+    },
+    #[error("the procedure {procedure} of the sequence {sequence} in {file_path} has the same name as a native mutation")]
+    SequenceProcedureConflict {
+        file_path: std::path::PathBuf,
+        sequence: String,
+        procedure: ndc_models::ProcedureName,
+    },
+    #[error("invalid JSON table {collection} in {file_path}: {message}")]
+    InvalidJsonTable {
+        file_path: std::path::PathBuf,
+        collection: ndc_models::CollectionName,
+        message: String,
+    },
 }
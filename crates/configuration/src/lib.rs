@@ -1,25 +1,36 @@
 mod configuration;
 mod connect;
+mod native_operations;
 mod values;
+mod words;
 
+pub mod connection_check;
 pub mod environment;
 pub mod error;
 pub mod metrics;
+pub mod validate;
+pub mod watch;
 
 pub mod version3;
 pub mod version4;
 pub mod version5;
 
 pub use configuration::{
-    generate_latest_schema, introspect, make_runtime_configuration, parse_configuration,
-    upgrade_to_latest_version, write_parsed_configuration, Configuration, ParsedConfiguration,
-    DEFAULT_CONNECTION_URI_VARIABLE,
+    generate_latest_schema, generate_schema, introspect, make_runtime_configuration,
+    parse_configuration, upgrade_to_latest_version, write_parsed_configuration, Configuration,
+    ParsedConfiguration, ReadReplicas, DEFAULT_CONNECTION_URI_VARIABLE,
+};
+pub use values::{
+    CollectionPolicy, ConnectionUri, CountEstimation, DistributedQueryRouting, EnvironmentValue,
+    EnvironmentValueError, HealthCheckProbe, HealthCheckSettings, IsolationLevel,
+    MutationFailurePolicy, PoolSettings, QueryLimits, ReadReplicaSettings, RelationshipKind,
+    RelationshipNameCase, RelationshipNaming, RequestRecordingSettings, RetryPolicy, Secret,
+    UsageStatisticsSettings,
 };
-pub use values::{ConnectionUri, IsolationLevel, PoolSettings, Secret};
 
 pub use metrics::Metrics;
 
-pub use connect::get_connect_options;
+pub use connect::{get_connect_options, SessionSettings};
 
 #[derive(Debug, Copy, Clone)]
 pub enum VersionTag {
@@ -1,6 +1,6 @@
 //! Metrics setup and update for our connector.
 
-use prometheus::{IntGauge, Registry};
+use prometheus::{IntGauge, IntGaugeVec, Registry};
 
 use crate::VersionTag;
 
@@ -10,6 +10,9 @@ pub struct Metrics {
     configuration_version_3: IntGauge,
     configuration_version_4: IntGauge,
     configuration_version_5: IntGauge,
+    configuration_hash: IntGaugeVec,
+    database_version: IntGauge,
+    database_healthy: IntGauge,
 }
 
 impl Metrics {
@@ -33,10 +36,32 @@ impl Metrics {
             "Get whether configuration version 5 is used",
         )?;
 
+        let configuration_hash = add_int_gauge_vec_metric(
+            metrics_registry,
+            "ndc_postgres_configuration_hash",
+            "Set to 1 for the digest of the configuration used, given in the 'hash' label",
+            &["hash"],
+        )?;
+
+        let database_version = add_int_gauge_metric(
+            metrics_registry,
+            "ndc_postgres_database_version",
+            "Get the version number of the database server, as in 'server_version_num'",
+        )?;
+
+        let database_healthy = add_int_gauge_metric(
+            metrics_registry,
+            "ndc_postgres_database_healthy",
+            "Get whether the database passed the last health check",
+        )?;
+
         Ok(Self {
             configuration_version_3,
             configuration_version_4,
             configuration_version_5,
+            configuration_hash,
+            database_version,
+            database_healthy,
         })
     }
 
@@ -48,6 +73,30 @@ impl Metrics {
             VersionTag::Version5 => self.configuration_version_5.set(1),
         }
     }
+
+    /// Set the digest of the configuration used by this connector instance.
+    pub fn set_configuration_hash(&self, hash: &str) {
+        self.configuration_hash.with_label_values(&[hash]).set(1);
+    }
+
+    /// Replace the digest of the configuration used by this connector instance, once it has
+    /// changed while running.
+    pub fn replace_configuration_hash(&self, old_hash: &str, new_hash: &str) {
+        let _ = self.configuration_hash.remove_label_values(&[old_hash]);
+        self.set_configuration_hash(new_hash);
+    }
+
+    /// Set the version number of the database server, if we could find it out.
+    pub fn set_database_version(&self, version_number: Option<u32>) {
+        if let Some(version_number) = version_number {
+            self.database_version.set(version_number.into());
+        }
+    }
+
+    /// Set whether the database passed the last health check.
+    pub fn set_database_healthy(&self, healthy: bool) {
+        self.database_healthy.set(i64::from(healthy));
+    }
 }
 
 /// Create a new int gauge metric and register it with the provided Prometheus Registry
@@ -60,6 +109,21 @@ fn add_int_gauge_metric(
     register_collector(metrics_registry, int_gauge)
 }
 
+/// Create a new int gauge metric with labels and register it with the provided Prometheus
+/// Registry
+fn add_int_gauge_vec_metric(
+    metrics_registry: &mut Registry,
+    metric_name: &str,
+    metric_description: &str,
+    labels: &[&str],
+) -> Result<IntGaugeVec, prometheus::Error> {
+    let int_gauge_vec = IntGaugeVec::new(
+        prometheus::Opts::new(metric_name, metric_description),
+        labels,
+    )?;
+    register_collector(metrics_registry, int_gauge_vec)
+}
+
 /// Register a new collector with the registry, and returns it for later use.
 fn register_collector<Collector: prometheus::core::Collector + std::clone::Clone + 'static>(
     metrics_registry: &mut Registry,
@@ -0,0 +1,304 @@
+//! Inference of the nullability of the columns and arguments of Native Operations, which is
+//! shared by every configuration version.
+
+use std::collections::BTreeSet;
+
+use sqlx::Connection;
+
+/// How to work out whether the columns of a Native Operation can be null.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum Inference {
+    /// Use what the database reports when describing the prepared statement.
+    #[default]
+    Describe,
+    /// Also plan the statement with `EXPLAIN (VERBOSE)` in a read-only transaction, which
+    /// recognises columns that can never be null, such as the results of window functions.
+    Explain,
+}
+
+/// Plan the statement without running it, and report which of its outputs can never be null.
+///
+/// Describing a statement only tells us about columns which come straight from a table, so
+/// anything computed, such as `row_number() OVER (...)`, is assumed to be nullable. The verbose
+/// plan lists the expression behind every output, which lets us recognise the functions that
+/// always return a value. Outputs we know nothing about are reported as possibly null.
+pub(crate) async fn explain_non_nullable_outputs(
+    connection: &mut sqlx::PgConnection,
+    sql: &str,
+    parameter_count: usize,
+) -> anyhow::Result<Vec<bool>> {
+    let mut transaction = connection.begin().await?;
+    // Planning a mutation does not run it, but we make sure of it anyway.
+    sqlx::raw_sql("SET TRANSACTION READ ONLY")
+        .execute(&mut *transaction)
+        .await?;
+
+    let prepare = format!("PREPARE {EXPLAINED_STATEMENT} AS {sql}");
+    sqlx::raw_sql(&prepare).execute(&mut *transaction).await?;
+
+    // Every parameter is null, as we only care about the shape of the plan.
+    let explain = if parameter_count == 0 {
+        format!("EXPLAIN (VERBOSE, FORMAT JSON) EXECUTE {EXPLAINED_STATEMENT}")
+    } else {
+        format!(
+            "EXPLAIN (VERBOSE, FORMAT JSON) EXECUTE {EXPLAINED_STATEMENT}({})",
+            vec!["NULL"; parameter_count].join(", ")
+        )
+    };
+    let plan: sqlx::types::Json<serde_json::Value> = sqlx::query_scalar(&explain)
+        .fetch_one(&mut *transaction)
+        .await?;
+
+    sqlx::raw_sql(&format!("DEALLOCATE {EXPLAINED_STATEMENT}"))
+        .execute(&mut *transaction)
+        .await?;
+    transaction.rollback().await?;
+
+    let outputs = plan
+        .0
+        .pointer("/0/Plan/Output")
+        .and_then(serde_json::Value::as_array)
+        .map(|outputs| {
+            outputs
+                .iter()
+                .map(|output| output.as_str().is_some_and(is_never_null))
+                .collect()
+        })
+        .unwrap_or_default();
+
+    Ok(outputs)
+}
+
+/// The name of the prepared statement we explain.
+const EXPLAINED_STATEMENT: &str = "ndc_postgres_explained_native_operation";
+
+/// Functions which return a value for every row, whatever their arguments are.
+const NEVER_NULL_FUNCTIONS: [&str; 6] = [
+    "row_number",
+    "rank",
+    "dense_rank",
+    "percent_rank",
+    "cume_dist",
+    "count",
+];
+
+/// Is the expression, as printed in a verbose plan, a call to a function that is never null,
+/// possibly over a window, or a constant?
+fn is_never_null(expression: &str) -> bool {
+    let mut expression = expression.trim();
+    // Expressions computed by a lower plan node are wrapped in parentheses.
+    while let Some(inner) = expression.strip_prefix('(') {
+        if after_parenthesized(inner) != Some("") {
+            break;
+        }
+        expression = inner[..inner.len() - 1].trim();
+    }
+
+    let Some((function, arguments)) = expression.split_once('(') else {
+        return is_constant(expression);
+    };
+    let Some(rest) = after_parenthesized(arguments) else {
+        return false;
+    };
+    // `COALESCE` is never null if its last argument is never null, such as in
+    // `COALESCE(sum(x), 0)`.
+    if function == "COALESCE" && rest.is_empty() {
+        let arguments = &arguments[..arguments.len() - 1];
+        return split_arguments(arguments)
+            .last()
+            .is_some_and(|last| is_never_null(last));
+    }
+    if !NEVER_NULL_FUNCTIONS.contains(&function) {
+        return false;
+    }
+    match rest.strip_prefix(" OVER ") {
+        None => rest.is_empty(),
+        // Either an inline window definition, or the name of a window.
+        Some(window) => match window.strip_prefix('(') {
+            Some(definition) => after_parenthesized(definition) == Some(""),
+            None => window.chars().all(|c| c.is_alphanumeric() || c == '_'),
+        },
+    }
+}
+
+/// Is the expression, as printed in a verbose plan, a constant other than `NULL`, such as `0` or
+/// `'none'::text`?
+fn is_constant(expression: &str) -> bool {
+    let value = expression
+        .split_once("::")
+        .map_or(expression, |(value, _)| value);
+    if let Some(string) = value.strip_prefix('\'') {
+        return string.ends_with('\'');
+    }
+    let number = value.strip_prefix('-').unwrap_or(value);
+    value == "true"
+        || value == "false"
+        || (number.starts_with(|c: char| c.is_ascii_digit())
+            && number.chars().all(|c| c.is_ascii_digit() || c == '.'))
+}
+
+/// Split the arguments of a function call at the commas which are not nested in parentheses
+/// or string literals.
+fn split_arguments(arguments: &str) -> Vec<&str> {
+    let mut results = vec![];
+    let mut depth = 0;
+    let mut in_string = false;
+    let mut start = 0;
+    for (index, c) in arguments.char_indices() {
+        match c {
+            '\'' => in_string = !in_string,
+            '(' if !in_string => depth += 1,
+            ')' if !in_string => depth -= 1,
+            ',' if !in_string && depth == 0 => {
+                results.push(arguments[start..index].trim());
+                start = index + 1;
+            }
+            _ => {}
+        }
+    }
+    results.push(arguments[start..].trim());
+    results
+}
+
+/// Given the text following an opening parenthesis, return what follows the matching closing
+/// parenthesis.
+fn after_parenthesized(text: &str) -> Option<&str> {
+    let mut depth = 1;
+    for (index, c) in text.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(&text[index + 1..]);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// The arguments of a Native Operation whose SQL handles them being null, and which can
+/// therefore be null themselves.
+///
+/// The database does not know whether a parameter can be null, so we look at how the SQL uses
+/// each argument: an argument that is compared with `IS [NOT] NULL` or `IS [NOT] DISTINCT FROM`,
+/// or which is the first argument of `COALESCE`, is nullable. Every other argument is not.
+pub(crate) fn null_checked_arguments(operation_file_contents: &str) -> BTreeSet<String> {
+    let mut arguments = BTreeSet::new();
+    let mut rest = operation_file_contents;
+    while let Some((preceding, parameter)) = rest.split_once("{{") {
+        let Some((name, following)) = parameter.split_once("}}") else {
+            break;
+        };
+        if is_coalesced(preceding) || is_compared_with_null(following) {
+            arguments.insert(name.to_string());
+        }
+        rest = following;
+    }
+    arguments
+}
+
+/// Does the text before a parameter open a call to `COALESCE`?
+fn is_coalesced(preceding: &str) -> bool {
+    let preceding = preceding.trim_end();
+    preceding
+        .strip_suffix('(')
+        .map(str::trim_end)
+        .and_then(|preceding| preceding.get(preceding.len().checked_sub("coalesce".len())?..))
+        .is_some_and(|function| function.eq_ignore_ascii_case("coalesce"))
+}
+
+/// Does the text after a parameter, possibly after a cast, compare it with null?
+fn is_compared_with_null(following: &str) -> bool {
+    let mut following = following.trim_start();
+    // Skip a cast, such as `::int4` or `::text[]`.
+    if let Some(cast) = following.strip_prefix("::") {
+        following = cast.trim_start_matches(|c: char| {
+            c.is_alphanumeric() || c == '_' || c == '.' || c == '"' || c == '[' || c == ']'
+        });
+    }
+    let words: Vec<String> = following
+        .split_whitespace()
+        .take(4)
+        .map(str::to_ascii_uppercase)
+        .collect();
+    let words: Vec<&str> = words.iter().map(String::as_str).collect();
+    matches!(
+        words.as_slice(),
+        ["IS", "NULL", ..]
+            | ["IS", "NOT", "NULL", ..]
+            | ["IS", "DISTINCT", "FROM", ..]
+            | ["IS", "NOT", "DISTINCT", "FROM"]
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use super::{is_never_null, null_checked_arguments};
+
+    #[test]
+    fn window_functions_are_never_null() {
+        assert!(is_never_null("row_number() OVER (?)"));
+        assert!(is_never_null("(rank() OVER (?))"));
+        assert!(is_never_null("dense_rank() OVER w1"));
+        assert!(is_never_null("count(*)"));
+        assert!(is_never_null(
+            "count(\"Album\".\"AlbumId\") OVER (PARTITION BY \"Album\".\"ArtistId\")"
+        ));
+    }
+
+    #[test]
+    fn other_expressions_may_be_null() {
+        assert!(!is_never_null("\"Album\".\"Title\""));
+        assert!(!is_never_null("lag(\"Album\".\"Title\") OVER (?)"));
+        assert!(!is_never_null(
+            "(row_number() OVER (?) + \"Album\".\"ArtistId\")"
+        ));
+        assert!(!is_never_null("(count(*) + NULL::bigint)"));
+    }
+
+    #[test]
+    fn coalesce_with_a_constant_is_never_null() {
+        assert!(is_never_null(
+            "COALESCE(sum(\"Track\".\"Milliseconds\"), '0'::bigint)"
+        ));
+        assert!(is_never_null("COALESCE(\"Album\".\"Title\", 'a, b'::text)"));
+        assert!(is_never_null(
+            "COALESCE(\"Album\".\"Title\", COALESCE(\"Artist\".\"Name\", ''::text))"
+        ));
+        assert!(!is_never_null(
+            "COALESCE(\"Album\".\"Title\", \"Artist\".\"Name\")"
+        ));
+        assert!(!is_never_null("COALESCE(\"Album\".\"Title\", NULL::text)"));
+    }
+
+    #[test]
+    fn arguments_compared_with_null_are_nullable() {
+        let sql = "SELECT * FROM \"Album\" \
+            WHERE ({{title}} IS NULL OR \"Title\" = {{title}}) \
+            AND \"ArtistId\" = coalesce({{artist_id}}, \"ArtistId\") \
+            AND {{year}}::int4 is not null \
+            AND \"AlbumId\" = {{album_id}}";
+        assert_eq!(
+            null_checked_arguments(sql),
+            BTreeSet::from([
+                "artist_id".to_string(),
+                "title".to_string(),
+                "year".to_string()
+            ])
+        );
+    }
+
+    #[test]
+    fn arguments_which_are_not_compared_with_null_are_not_nullable() {
+        let sql = "SELECT * FROM \"Album\" \
+            WHERE \"Title\" = {{title}} \
+            AND \"ArtistId\" = coalesce(\"ArtistId\", {{artist_id}}) \
+            AND \"Title\" IS NULL";
+        assert!(null_checked_arguments(sql).is_empty());
+    }
+}
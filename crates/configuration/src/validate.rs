@@ -0,0 +1,259 @@
+//! Check a runtime configuration for problems which parsing it does not catch, such as
+//! references to types which are not defined.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use sqlx::{Connection, Row};
+
+use query_engine_metadata::metadata;
+
+use crate::environment::Environment;
+use crate::Configuration;
+
+/// Every column of every relation in the database, by schema and relation name.
+const COLUMNS_QUERY: &str = "
+    SELECT n.nspname, c.relname, a.attname
+    FROM pg_catalog.pg_attribute AS a
+    JOIN pg_catalog.pg_class AS c ON c.oid = a.attrelid
+    JOIN pg_catalog.pg_namespace AS n ON n.oid = c.relnamespace
+    WHERE a.attnum > 0
+      AND NOT a.attisdropped
+      AND c.relkind IN ('r', 'v', 'm', 'f', 'p')";
+
+/// Check that the tables which foreign keys refer to are in the metadata.
+fn check_foreign_relations(
+    metadata: &metadata::Metadata,
+    source: &str,
+    foreign_relations: &metadata::ForeignRelations,
+    target: Option<&str>,
+    default_schema: &str,
+    problems: &mut Vec<String>,
+) {
+    for (constraint_name, relation) in &foreign_relations.0 {
+        let foreign_schema = relation.foreign_schema.as_deref().unwrap_or(default_schema);
+        let is_tracked = metadata.tables.0.values().any(|table| {
+            table.target.as_deref() == target
+                && table.schema_name == foreign_schema
+                && table.table_name == relation.foreign_table
+        });
+        if !is_tracked {
+            problems.push(format!(
+                "foreign key '{constraint_name}' of {source} refers to the table '{foreign_schema}.{}', which is not in the metadata",
+                relation.foreign_table
+            ));
+        }
+    }
+}
+
+/// Describe every problem with the metadata of a configuration: types that are not defined,
+/// relationships to tables that are not tracked, parameters of native operations that are not
+/// declared as arguments, and planner hints for collections that do not exist.
+pub fn check_metadata(configuration: &Configuration) -> Vec<String> {
+    let metadata = &configuration.metadata;
+    let mut problems = vec![];
+
+    for (collection, table) in &metadata.tables.0 {
+        for (column_name, column) in &table.columns {
+            check_type(
+                metadata,
+                &column.r#type,
+                &format!("column '{column_name}' of collection '{collection}'"),
+                &mut problems,
+            );
+        }
+        check_foreign_relations(
+            metadata,
+            &format!("collection '{collection}'"),
+            &table.foreign_relations,
+            table.target.as_deref(),
+            &table.schema_name,
+            &mut problems,
+        );
+    }
+
+    for (type_name, composite_type) in &metadata.composite_types.0 {
+        for (field_name, field) in &composite_type.fields {
+            check_type(
+                metadata,
+                &field.r#type,
+                &format!("field '{field_name}' of composite type '{type_name}'"),
+                &mut problems,
+            );
+        }
+    }
+
+    let native_operations = metadata
+        .native_operations
+        .queries
+        .0
+        .iter()
+        .map(|(name, info)| ("native query", name.to_string(), info))
+        .chain(
+            metadata
+                .native_operations
+                .mutations
+                .0
+                .iter()
+                .map(|(name, info)| ("native mutation", name.to_string(), info)),
+        );
+    for (kind, name, info) in native_operations {
+        check_foreign_relations(
+            metadata,
+            &format!("{kind} '{name}'"),
+            &info.foreign_relations,
+            None,
+            metadata::NATIVE_QUERY_DEFAULT_FOREIGN_SCHEMA,
+            &mut problems,
+        );
+        for (column_name, column) in &info.columns {
+            check_type(
+                metadata,
+                &column.r#type,
+                &format!("column '{column_name}' of {kind} '{name}'"),
+                &mut problems,
+            );
+        }
+        for (argument_name, argument) in &info.arguments {
+            check_type(
+                metadata,
+                &argument.r#type,
+                &format!("argument '{argument_name}' of {kind} '{name}'"),
+                &mut problems,
+            );
+        }
+        match info.sql.clone().sql() {
+            Err(error) => problems.push(format!("the SQL of {kind} '{name}': {error}")),
+            Ok(parts) => {
+                let parameters: BTreeSet<&str> = parts
+                    .0
+                    .iter()
+                    .filter_map(|part| match part {
+                        metadata::NativeQueryPart::Parameter(parameter) => Some(parameter.as_str()),
+                        metadata::NativeQueryPart::Text(_) => None,
+                    })
+                    .collect();
+                for parameter in parameters {
+                    if !info
+                        .arguments
+                        .keys()
+                        .any(|argument| argument.as_str() == parameter)
+                    {
+                        problems.push(format!(
+                            "the SQL of {kind} '{name}' refers to '{{{{{parameter}}}}}', which is not one of its arguments"
+                        ));
+                    }
+                }
+            }
+        }
+    }
+
+    for collection in configuration.planner_hints.keys() {
+        if !metadata.tables.0.contains_key(collection)
+            && !metadata
+                .native_operations
+                .queries
+                .0
+                .contains_key(collection)
+        {
+            problems.push(format!(
+                "there is a planner hint for the collection '{collection}', which does not exist"
+            ));
+        }
+    }
+
+    problems
+}
+
+/// Describe the tables and columns of the metadata which are missing from the database.
+pub async fn check_database(
+    configuration: &Configuration,
+    environment: impl Environment,
+) -> anyhow::Result<Vec<String>> {
+    let mut relations_by_target = BTreeMap::new();
+    relations_by_target.insert(
+        None,
+        fetch_relations(&configuration.connection_uri, &environment).await?,
+    );
+    for (target, connection_uri) in &configuration.targets {
+        relations_by_target.insert(
+            Some(target.as_str()),
+            fetch_relations(connection_uri, &environment).await?,
+        );
+    }
+
+    let mut problems = vec![];
+    for (collection, table) in &configuration.metadata.tables.0 {
+        let key = (table.schema_name.clone(), table.table_name.clone());
+        match relations_by_target
+            .get(&table.target.as_deref())
+            .and_then(|relations| relations.get(&key))
+        {
+            None => problems.push(format!(
+                "the table '{}.{}' of collection '{collection}' does not exist in the database",
+                table.schema_name, table.table_name
+            )),
+            Some(columns) => {
+                for column in table.columns.values() {
+                    if !columns.contains(&column.name) {
+                        problems.push(format!(
+                            "the column '{}' of collection '{collection}' does not exist in the table '{}.{}'",
+                            column.name, table.schema_name, table.table_name
+                        ));
+                    }
+                }
+            }
+        }
+    }
+    Ok(problems)
+}
+
+/// Fetch the columns of every relation in a database.
+async fn fetch_relations(
+    connection_uri: &str,
+    environment: impl Environment,
+) -> anyhow::Result<BTreeMap<(String, String), BTreeSet<String>>> {
+    let connect_options =
+        crate::get_connect_options(&crate::ConnectionUri::from(connection_uri), environment)?;
+    let mut connection = sqlx::PgConnection::connect_with(&connect_options).await?;
+
+    let mut relations: BTreeMap<(String, String), BTreeSet<String>> = BTreeMap::new();
+    for row in sqlx::query(COLUMNS_QUERY)
+        .fetch_all(&mut connection)
+        .await?
+    {
+        relations
+            .entry((row.try_get(0)?, row.try_get(1)?))
+            .or_default()
+            .insert(row.try_get(2)?);
+    }
+    connection.close().await?;
+    Ok(relations)
+}
+
+/// Check that a type, or the element type of an array, is defined.
+fn check_type(
+    metadata: &metadata::Metadata,
+    r#type: &metadata::Type,
+    location: &str,
+    problems: &mut Vec<String>,
+) {
+    match r#type {
+        metadata::Type::ScalarType(name) => {
+            if !metadata.scalar_types.0.contains_key(name) {
+                problems.push(format!(
+                    "{location} has the scalar type '{name}', which is not defined"
+                ));
+            }
+        }
+        metadata::Type::CompositeType(name) => {
+            if !metadata.composite_types.0.contains_key(name) {
+                problems.push(format!(
+                    "{location} has the composite type '{name}', which is not defined"
+                ));
+            }
+        }
+        metadata::Type::ArrayType(element_type) => {
+            check_type(metadata, element_type, location, problems);
+        }
+    }
+}
@@ -0,0 +1,19 @@
+//! Restrictions on the rows of a collection which apply to every query.
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// Restrictions on the rows queries may see of a collection, applied whatever the request asks
+/// for, as a defense in depth against permissions missing from upstream.
+#[derive(Clone, PartialEq, Eq, Debug, Default, Deserialize, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct CollectionPolicy {
+    /// A predicate over the fields of the collection which every row must satisfy, in the form
+    /// of the predicates of query requests. It is combined with the predicate of the query using
+    /// `AND`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub filter: Option<ndc_models::Expression>,
+    /// The most rows a single query may return, whatever limit it asks for.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_rows: Option<u32>,
+}
@@ -0,0 +1,22 @@
+//! Estimating the number of rows of large tables.
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// Answer `star_count` aggregates over whole tables with the number of rows PostgreSQL estimates
+/// they have, from the statistics it keeps in `pg_class.reltuples`, for tables estimated to have
+/// at least `minRows` rows. Counting every row of a very large table can take longer than a query
+/// may run. Such aggregates are returned along with `is_estimate`, which says whether they were
+/// estimated. The statistics of a table do not include the rows of the tables which inherit from
+/// it, such as its partitions, so the rows of tables which have any are always counted.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Deserialize, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct CountEstimation {
+    /// The number of rows from which the rows of a table are estimated rather than counted.
+    #[serde(default = "default_min_rows")]
+    pub min_rows: u32,
+}
+
+fn default_min_rows() -> u32 {
+    1_000_000
+}
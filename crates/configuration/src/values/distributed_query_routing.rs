@@ -0,0 +1,22 @@
+//! How queries over tables which Citus distributes across nodes are routed to their shards.
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// How queries over tables which Citus distributes across nodes are planned. A query can be
+/// routed to a single node when it pins the distribution column of its tables to one value.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default, Deserialize, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub enum DistributedQueryRouting {
+    /// Queries are planned as they are.
+    #[default]
+    Off,
+    /// Log a warning the first time a distributed table is queried without filtering its
+    /// distribution column by equality, so that the query cannot be routed to a single shard.
+    Warn,
+    /// Also warn, and add the equality of the distribution columns to relationships between
+    /// colocated distributed tables, so that Citus can join them on the same shard. This assumes
+    /// related rows share the value of their distribution column, as they do when tables are
+    /// distributed by a tenant.
+    PushDown,
+}
@@ -0,0 +1,110 @@
+use schemars::JsonSchema;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use crate::environment::{self, Environment};
+
+/// Configuration type for settings which can either be given literally, or refer to an
+/// environment variable, like a [`super::Secret`]. The variable is read when the runtime
+/// configuration is made.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize, JsonSchema)]
+#[serde(untagged, rename_all = "camelCase")]
+pub enum EnvironmentValue<T> {
+    Value(T),
+    FromEnvironment { variable: environment::Variable },
+}
+
+impl<T: Clone + DeserializeOwned> EnvironmentValue<T> {
+    /// Produce the value, reading it from the environment if necessary.
+    ///
+    /// The contents of the variable are parsed as JSON, so that `50` is a number, falling back
+    /// to a string, so that an isolation level can be written as `Serializable` rather than
+    /// `"Serializable"`.
+    pub fn resolve(&self, environment: impl Environment) -> Result<T, EnvironmentValueError> {
+        match self {
+            Self::Value(value) => Ok(value.clone()),
+            Self::FromEnvironment { variable } => {
+                let contents = environment
+                    .read(variable)
+                    .map_err(EnvironmentValueError::Missing)?;
+                serde_json::from_str(&contents)
+                    .or_else(|_| serde_json::from_value(serde_json::Value::String(contents)))
+                    .map_err(|error| EnvironmentValueError::Invalid {
+                        variable: variable.clone(),
+                        message: error.to_string(),
+                    })
+            }
+        }
+    }
+}
+
+impl<T: Default> Default for EnvironmentValue<T> {
+    fn default() -> Self {
+        Self::Value(T::default())
+    }
+}
+
+impl<T> From<T> for EnvironmentValue<T> {
+    fn from(value: T) -> Self {
+        Self::Value(value)
+    }
+}
+
+/// Why a value could not be read from the environment.
+#[derive(Debug, thiserror::Error)]
+pub enum EnvironmentValueError {
+    #[error("{0}")]
+    Missing(environment::Error),
+    #[error("invalid value in {variable}: {message}")]
+    Invalid {
+        variable: environment::Variable,
+        message: String,
+    },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::environment::FixedEnvironment;
+    use crate::values::IsolationLevel;
+
+    #[test]
+    fn test_literal_values_are_used_as_they_are() {
+        let value: EnvironmentValue<u32> = serde_json::from_str("50").unwrap();
+
+        assert_eq!(value.resolve(FixedEnvironment::new()).unwrap(), 50);
+    }
+
+    #[test]
+    fn test_numbers_are_read_from_the_environment() {
+        let environment = FixedEnvironment::from([("MAX_CONNECTIONS".into(), "10".into())]);
+        let value: EnvironmentValue<u32> =
+            serde_json::from_str(r#"{"variable": "MAX_CONNECTIONS"}"#).unwrap();
+
+        assert_eq!(value.resolve(environment).unwrap(), 10);
+    }
+
+    #[test]
+    fn test_enumerations_are_read_from_the_environment_without_quotes() {
+        let environment = FixedEnvironment::from([("ISOLATION".into(), "Serializable".into())]);
+        let value: EnvironmentValue<IsolationLevel> =
+            serde_json::from_str(r#"{"variable": "ISOLATION"}"#).unwrap();
+
+        assert_eq!(
+            value.resolve(environment).unwrap(),
+            IsolationLevel::Serializable
+        );
+    }
+
+    #[test]
+    fn test_invalid_values_in_the_environment_are_rejected() {
+        let environment = FixedEnvironment::from([("MAX_CONNECTIONS".into(), "many".into())]);
+        let value: EnvironmentValue<u32> =
+            serde_json::from_str(r#"{"variable": "MAX_CONNECTIONS"}"#).unwrap();
+
+        assert!(matches!(
+            value.resolve(environment),
+            Err(EnvironmentValueError::Invalid { .. })
+        ));
+    }
+}
@@ -0,0 +1,38 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// Settings for periodically checking that the database is reachable
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct HealthCheckSettings {
+    /// how far to go when checking the database
+    #[serde(default)]
+    pub probe: HealthCheckProbe,
+    /// the database is considered unhealthy if the probe takes longer than this (seconds)
+    #[serde(default = "timeout_default")]
+    pub timeout: u64,
+    /// how often to check the database (seconds)
+    #[serde(default = "interval_default")]
+    pub interval: u64,
+}
+
+/// How far a health check goes to confirm that the database is reachable.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub enum HealthCheckProbe {
+    /// Only check that the connector is running.
+    None,
+    /// Check that a connection can be acquired from the pool.
+    Pool,
+    /// Check that the database answers `SELECT 1`.
+    #[default]
+    Query,
+}
+
+fn timeout_default() -> u64 {
+    5
+}
+
+fn interval_default() -> u64 {
+    10
+}
@@ -1,8 +1,11 @@
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-/// The isolation level of the transaction in which a query is executed.
-#[derive(Debug, PartialEq, Eq, Clone, Copy, Default, Deserialize, Serialize, JsonSchema)]
+/// The isolation level of the transaction in which a query is executed. Levels are ordered from
+/// the least to the most strict.
+#[derive(
+    Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Default, Deserialize, Serialize, JsonSchema,
+)]
 pub enum IsolationLevel {
     /// Prevents reading data from another uncommitted transaction.
     #[default]
@@ -1,9 +1,33 @@
+mod collection_policy;
+mod count_estimation;
+mod distributed_query_routing;
+mod environment_value;
+mod health_check;
 mod isolation_level;
+mod mutation_failure_policy;
 mod pool_settings;
+mod query_limits;
+mod read_replicas;
+mod relationship_naming;
+mod request_recording;
+mod retry_policy;
 mod secret;
 mod uri;
+mod usage_statistics;
 
+pub use collection_policy::CollectionPolicy;
+pub use count_estimation::CountEstimation;
+pub use distributed_query_routing::DistributedQueryRouting;
+pub use environment_value::{EnvironmentValue, EnvironmentValueError};
+pub use health_check::{HealthCheckProbe, HealthCheckSettings};
 pub use isolation_level::IsolationLevel;
+pub use mutation_failure_policy::MutationFailurePolicy;
 pub use pool_settings::PoolSettings;
+pub use query_limits::QueryLimits;
+pub use read_replicas::ReadReplicaSettings;
+pub use relationship_naming::{RelationshipKind, RelationshipNameCase, RelationshipNaming};
+pub use request_recording::RequestRecordingSettings;
+pub use retry_policy::RetryPolicy;
 pub use secret::Secret;
 pub use uri::ConnectionUri;
+pub use usage_statistics::UsageStatisticsSettings;
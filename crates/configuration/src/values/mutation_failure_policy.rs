@@ -0,0 +1,17 @@
+//! What happens to the other operations of a mutation request when one of them fails.
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// What happens to the other operations of a mutation request when one of them fails. The
+/// operations of a request always run in a single transaction.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default, Deserialize, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub enum MutationFailurePolicy {
+    /// The request fails, and none of its operations are committed.
+    #[default]
+    AllOrNothing,
+    /// Each operation runs in a savepoint. One which fails is rolled back on its own and
+    /// returns a null result, and the others are committed.
+    BestEffort,
+}
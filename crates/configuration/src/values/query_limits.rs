@@ -0,0 +1,21 @@
+//! Limits on the size of queries.
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// Limits on the size of the SQL generated for a query. Requests exceeding them are rejected
+/// rather than sent to the database. Every limit is unset by default.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, Deserialize, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct QueryLimits {
+    /// How deeply relationships may be nested in a selection.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_relationship_depth: Option<u32>,
+    /// How many relationships a query may select in total.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_joins: Option<u32>,
+    /// How many nodes (comparisons, `and`, `or`, `not` and `exists`) the predicates of a query
+    /// may have in total.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_predicate_nodes: Option<u32>,
+}
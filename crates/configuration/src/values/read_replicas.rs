@@ -0,0 +1,31 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use super::ConnectionUri;
+
+/// Settings for reading from replicas of the database
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ReadReplicaSettings {
+    /// connection strings for the replicas. Queries are spread across them, while mutations
+    /// always run against the primary database.
+    pub connection_uris: Vec<ConnectionUri>,
+    /// replicas lagging further behind the primary are not read from until they catch up (seconds)
+    #[serde(default = "max_replication_lag_default")]
+    pub max_replication_lag: u64,
+    /// how often to measure the replication lag of each replica (seconds)
+    #[serde(default = "lag_check_interval_default")]
+    pub lag_check_interval: u64,
+    /// after a mutation, run queries against the primary for this long, so that they see what
+    /// it wrote (seconds). Queries are not sent to the primary after mutations if this is unset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub read_your_writes_window: Option<u64>,
+}
+
+fn max_replication_lag_default() -> u64 {
+    10
+}
+
+fn lag_check_interval_default() -> u64 {
+    5
+}
@@ -0,0 +1,209 @@
+//! Naming the relationships derived from foreign keys.
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::words::{self, WordCase};
+
+/// How to name the relationships derived from the foreign keys of tables, for tools which
+/// generate them, such as `ndc-postgres-cli seed-metadata`. Each foreign key gives an object
+/// relationship from the table holding it to the table it refers to, and an array relationship
+/// the other way.
+#[derive(Clone, PartialEq, Eq, Debug, Deserialize, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct RelationshipNaming {
+    /// the case of the names
+    #[serde(default)]
+    pub case: RelationshipNameCase,
+    /// whether to use the plural of the last word of the names of array relationships, such as
+    /// `albums` rather than `album`
+    #[serde(default = "pluralize_array_relationships_default")]
+    pub pluralize_array_relationships: bool,
+    /// the name of object relationships, in which `{source}` and `{target}` stand for the
+    /// collections the relationship is from and to, and `{constraint}` for the foreign key
+    #[serde(default = "object_relationship_template_default")]
+    pub object_relationship_template: String,
+    /// the name of array relationships, with the same placeholders as object relationships
+    #[serde(default = "array_relationship_template_default")]
+    pub array_relationship_template: String,
+}
+
+impl Default for RelationshipNaming {
+    fn default() -> RelationshipNaming {
+        RelationshipNaming {
+            case: RelationshipNameCase::default(),
+            pluralize_array_relationships: true,
+            object_relationship_template: "{target}".to_string(),
+            array_relationship_template: "{target}".to_string(),
+        }
+    }
+}
+
+/// The case of relationship names.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, Deserialize, Serialize, JsonSchema)]
+pub enum RelationshipNameCase {
+    /// `invoiceLines`
+    #[default]
+    #[serde(rename = "camelCase")]
+    CamelCase,
+    /// `InvoiceLines`
+    #[serde(rename = "PascalCase")]
+    PascalCase,
+    /// `invoice_lines`
+    #[serde(rename = "snake_case")]
+    SnakeCase,
+}
+
+/// Whether a relationship gives at most one row or any number of them.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum RelationshipKind {
+    Object,
+    Array,
+}
+
+impl RelationshipNaming {
+    /// Name the relationship of the given kind from one collection to another, which follows the
+    /// given foreign key constraint.
+    pub fn name(
+        &self,
+        kind: RelationshipKind,
+        source: &str,
+        target: &str,
+        constraint: &str,
+    ) -> String {
+        let template = match kind {
+            RelationshipKind::Object => &self.object_relationship_template,
+            RelationshipKind::Array => &self.array_relationship_template,
+        };
+        let name = template
+            .replace("{source}", source)
+            .replace("{target}", target)
+            .replace("{constraint}", constraint);
+
+        let mut words = words::split(&name);
+        if kind == RelationshipKind::Array && self.pluralize_array_relationships {
+            if let Some(last) = words.last_mut() {
+                *last = pluralize(last);
+            }
+        }
+        words::join(self.case.word_case(), &words)
+    }
+}
+
+impl RelationshipNameCase {
+    fn word_case(self) -> WordCase {
+        match self {
+            RelationshipNameCase::CamelCase => WordCase::Camel,
+            RelationshipNameCase::PascalCase => WordCase::Pascal,
+            RelationshipNameCase::SnakeCase => WordCase::Snake,
+        }
+    }
+}
+
+/// The plural of an English noun, following the regular rules.
+fn pluralize(word: &str) -> String {
+    let lowercase = word.to_lowercase();
+    if lowercase.ends_with('y')
+        && !["ay", "ey", "iy", "oy", "uy"]
+            .iter()
+            .any(|ending| lowercase.ends_with(ending))
+    {
+        format!("{}ies", &word[..word.len() - 1])
+    } else if ["s", "x", "z", "ch", "sh"]
+        .iter()
+        .any(|ending| lowercase.ends_with(ending))
+    {
+        format!("{word}es")
+    } else {
+        format!("{word}s")
+    }
+}
+
+fn pluralize_array_relationships_default() -> bool {
+    RelationshipNaming::default().pluralize_array_relationships
+}
+
+fn object_relationship_template_default() -> String {
+    RelationshipNaming::default().object_relationship_template
+}
+
+fn array_relationship_template_default() -> String {
+    RelationshipNaming::default().array_relationship_template
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names_follow_the_foreign_key_by_default() {
+        let naming = RelationshipNaming::default();
+        assert_eq!(
+            naming.name(
+                RelationshipKind::Object,
+                "InvoiceLine",
+                "Invoice",
+                "FK_InvoiceLineInvoiceId"
+            ),
+            "invoice"
+        );
+        assert_eq!(
+            naming.name(
+                RelationshipKind::Array,
+                "Invoice",
+                "InvoiceLine",
+                "FK_InvoiceLineInvoiceId"
+            ),
+            "invoiceLines"
+        );
+        assert_eq!(
+            naming.name(RelationshipKind::Array, "company", "category", "fk"),
+            "categories"
+        );
+        assert_eq!(
+            naming.name(RelationshipKind::Array, "person", "address", "fk"),
+            "addresses"
+        );
+    }
+
+    #[test]
+    fn templates_and_cases_are_applied() {
+        let naming = RelationshipNaming {
+            case: RelationshipNameCase::SnakeCase,
+            pluralize_array_relationships: false,
+            object_relationship_template: "{target}_via_{constraint}".to_string(),
+            array_relationship_template: "{target}_list".to_string(),
+        };
+        assert_eq!(
+            naming.name(
+                RelationshipKind::Object,
+                "Track",
+                "MediaType",
+                "media_type_id"
+            ),
+            "media_type_via_media_type_id"
+        );
+        assert_eq!(
+            naming.name(
+                RelationshipKind::Array,
+                "MediaType",
+                "Track",
+                "media_type_id"
+            ),
+            "track_list"
+        );
+    }
+
+    #[test]
+    fn acronyms_keep_their_case() {
+        let naming = RelationshipNaming::default();
+        assert_eq!(
+            naming.name(RelationshipKind::Object, "Track", "ownerID", "fk"),
+            "ownerID"
+        );
+        assert_eq!(
+            naming.name(RelationshipKind::Array, "User", "APIKey", "fk"),
+            "apiKeys"
+        );
+    }
+}
@@ -0,0 +1,35 @@
+//! Recording the requests the connector serves.
+
+use std::path::PathBuf;
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// Settings for recording each query, mutation and explain request, the SQL it was translated
+/// to, and how long it took to run, so that the recordings can be replayed after the translation
+/// changes
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct RequestRecordingSettings {
+    /// the directory to write the recordings to, one file per request
+    pub directory: PathBuf,
+    /// whether to record the literal values of the requests and the parameters of the SQL, which
+    /// may be sensitive. Otherwise they are replaced with empty strings, zeros and `false`, which
+    /// the database may reject when the recorded requests are replayed
+    #[serde(default)]
+    pub include_values: bool,
+    /// the most requests to record, after which the connector stops recording
+    #[serde(default = "default_max_recordings")]
+    pub max_recordings: u64,
+    /// the size of the largest recording to write, in bytes. Larger recordings are skipped
+    #[serde(default = "default_max_recording_bytes")]
+    pub max_recording_bytes: u64,
+}
+
+fn default_max_recordings() -> u64 {
+    10_000
+}
+
+fn default_max_recording_bytes() -> u64 {
+    1_048_576
+}
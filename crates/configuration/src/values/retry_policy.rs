@@ -0,0 +1,52 @@
+//! Retrying requests which fail for transient reasons.
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// How to retry requests which fail for reasons which may go away on their own: serialization
+/// failures and deadlocks, and, for queries only, dropped connections.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Deserialize, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct RetryPolicy {
+    /// how many times to attempt a request before giving up, including the first attempt
+    #[serde(default = "max_attempts_default")]
+    pub max_attempts: u32,
+    /// how long to wait before the first retry, which doubles with each further retry
+    /// (milliseconds)
+    #[serde(default = "initial_backoff_default")]
+    pub initial_backoff_ms: u64,
+    /// the longest to wait before any retry (milliseconds)
+    #[serde(default = "max_backoff_default")]
+    pub max_backoff_ms: u64,
+    /// whether to wait a random time up to the backoff instead, so that requests which failed
+    /// together do not all retry at once
+    #[serde(default = "jitter_default")]
+    pub jitter: bool,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> RetryPolicy {
+        RetryPolicy {
+            max_attempts: 3,
+            initial_backoff_ms: 50,
+            max_backoff_ms: 1000,
+            jitter: true,
+        }
+    }
+}
+
+fn max_attempts_default() -> u32 {
+    RetryPolicy::default().max_attempts
+}
+
+fn initial_backoff_default() -> u64 {
+    RetryPolicy::default().initial_backoff_ms
+}
+
+fn max_backoff_default() -> u64 {
+    RetryPolicy::default().max_backoff_ms
+}
+
+fn jitter_default() -> bool {
+    RetryPolicy::default().jitter
+}
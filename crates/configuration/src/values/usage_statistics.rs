@@ -0,0 +1,35 @@
+//! Counting how each collection is used.
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// Settings for counting the requests to each collection over a sliding window, to find tables
+/// which are not used and those which are used the most
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct UsageStatisticsSettings {
+    /// how far back to count requests (seconds)
+    #[serde(default = "window_default")]
+    pub window: u64,
+    /// how often to update the statistics, which is also how far the window slides at a time
+    /// (seconds)
+    #[serde(default = "interval_default")]
+    pub interval: u64,
+}
+
+impl Default for UsageStatisticsSettings {
+    fn default() -> UsageStatisticsSettings {
+        UsageStatisticsSettings {
+            window: 3600,
+            interval: 60,
+        }
+    }
+}
+
+fn window_default() -> u64 {
+    UsageStatisticsSettings::default().window
+}
+
+fn interval_default() -> u64 {
+    UsageStatisticsSettings::default().interval
+}
@@ -79,6 +79,7 @@ impl RawConfiguration {
 pub async fn introspect(
     args: RawConfiguration,
     environment: impl Environment,
+    session: &crate::SessionSettings,
 ) -> anyhow::Result<RawConfiguration> {
     let uri = match &args.connection_settings.connection_uri {
         ConnectionUri(Secret::Plain(value)) => Cow::Borrowed(value),
@@ -87,7 +88,7 @@ pub async fn introspect(
         }
     };
 
-    let mut connection = PgConnection::connect(&uri)
+    let mut connection = PgConnection::connect_with(&session.apply(uri.parse()?))
         .instrument(info_span!("Connect to database"))
         .await?;
 
@@ -601,7 +602,39 @@ pub fn make_runtime_configuration(
         isolation_level: configuration.connection_settings.isolation_level,
         mutations_version: convert_mutations_version(configuration.mutations_version),
         configuration_version_tag: VersionTag::Version3,
+        configuration_hash: String::new(),
         mutations_prefix: None,
+        mutations_returning_aggregates: false,
+        dialect: query_engine_sql::sql::dialect::SqlDialect::Postgres,
+        use_lateral_joins: true,
+        limit_with_ties: false,
+        strict_boolean_comparisons: false,
+        snapshot_reads: false,
+        distinct_on: false,
+        temporary_variables_table: false,
+        planner_hints: BTreeMap::new(),
+        mutation_isolation_levels: BTreeMap::new(),
+        collection_policies: BTreeMap::new(),
+        distributed_query_routing: crate::DistributedQueryRouting::default(),
+        count_estimation: None,
+        verify_queries: false,
+        read_replicas: None,
+        targets: BTreeMap::new(),
+        health_check: None,
+        annotate_queries: false,
+        request_id_setting: None,
+        slow_query_threshold_ms: None,
+        log_slow_query_parameters: false,
+        query_timeout_ms: None,
+        query_limits: crate::QueryLimits::default(),
+        retry_policy: crate::RetryPolicy::default(),
+        mutation_failure_policy: crate::MutationFailurePolicy::default(),
+        usage_statistics: None,
+        request_recording: None,
+        relationship_naming: crate::RelationshipNaming::default(),
+        watch_native_query_files: false,
+        json_tables: std::collections::BTreeSet::new(),
+        configuration_directory: None,
     })
 }
 
@@ -678,6 +711,7 @@ pub fn convert_metadata(metadata: metadata::Metadata) -> query_engine_metadata::
         tables: convert_tables(metadata.tables),
         composite_types: convert_composite_types(composite_types),
         native_operations: convert_native_queries(metadata.native_queries),
+        sequences: query_engine_metadata::metadata::Sequences::empty(),
         scalar_types: convert_scalar_types(
             scalar_types,
             metadata.aggregate_functions,
@@ -715,6 +749,7 @@ fn convert_scalar_types(
                             .unwrap_or(BTreeMap::new()),
 
                         type_representation: representations.0.get(&t).cloned(),
+                        cast_as: None,
                     },
                 )
             })
@@ -788,6 +823,8 @@ fn convert_native_query_info(
             .map(|(k, v)| (k, convert_read_only_column_info(v)))
             .collect(),
         description: native_query_info.description,
+        foreign_relations: query_engine_metadata::metadata::ForeignRelations::default(),
+        target: None,
     }
 }
 
@@ -1057,7 +1094,11 @@ fn convert_composite_type_field_info(
     query_engine_metadata::metadata::FieldInfo {
         field_name: field.name,
         r#type: convert_type(field.r#type),
+        // Older versions of the configuration do not record this, so we assume the worst.
+        nullable: query_engine_metadata::metadata::Nullable::Nullable,
+        has_default: query_engine_metadata::metadata::HasDefault::NoDefault,
         description: field.description,
+        position: None,
     }
 }
 
@@ -1085,6 +1126,19 @@ fn convert_table_info(
         uniqueness_constraints: convert_uniqueness_constraints(table_info.uniqueness_constraints),
         foreign_relations: convert_foreign_relations(table_info.foreign_relations),
         description: table_info.description,
+        inherited_rows: query_engine_metadata::metadata::InheritedRows::Included,
+        target: None,
+        statement_timeout_ms: None,
+        logical_keys: query_engine_metadata::metadata::UniquenessConstraints::default(),
+        row_expiry: None,
+        soft_delete: None,
+        audit_columns: BTreeMap::new(),
+        computed_relationships: BTreeMap::new(),
+        computed_fields: BTreeMap::new(),
+        insertable: true,
+        updatable: true,
+        deletable: true,
+        distribution: None,
     }
 }
 
@@ -1144,8 +1198,15 @@ fn convert_column_info(
         r#type: convert_type(column_info.r#type),
         nullable: convert_nullable(&column_info.nullable),
         has_default: convert_has_default(&column_info.has_default),
+        default_expression: None,
         is_identity: convert_is_identity(&column_info.is_identity),
         is_generated: convert_is_generated(&column_info.is_generated),
+        generates_uuid: false,
+        selectable: true,
+        insertable: true,
+        updatable: true,
+        filterable: true,
+        sortable: true,
         description: column_info.description,
     }
 }
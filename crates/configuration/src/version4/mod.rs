@@ -123,6 +123,7 @@ fn native_query_field_types(native_queries: &metadata::NativeQueries) -> Vec<Str
 pub async fn introspect(
     args: ParsedConfiguration,
     environment: impl Environment,
+    session: &crate::SessionSettings,
 ) -> anyhow::Result<ParsedConfiguration> {
     let uri = match &args.connection_settings.connection_uri {
         ConnectionUri(Secret::Plain(value)) => Cow::Borrowed(value),
@@ -131,7 +132,7 @@ pub async fn introspect(
         }
     };
 
-    let mut connection = PgConnection::connect(&uri)
+    let mut connection = PgConnection::connect_with(&session.apply(uri.parse()?))
         .instrument(info_span!("Connect to database"))
         .await?;
 
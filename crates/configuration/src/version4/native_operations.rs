@@ -11,6 +11,7 @@ use sqlx::Executor;
 use sqlx::{Column, PgConnection};
 
 use super::metadata;
+pub use crate::native_operations::Inference;
 use tracing::{info_span, Instrument};
 
 /// Query or Mutation.
@@ -28,6 +29,7 @@ pub async fn create(
     operation_path: &Path,
     operation_file_contents: &str,
     kind: Kind,
+    inference: Inference,
 ) -> anyhow::Result<metadata::NativeQueryInfo> {
     // Connect to the db.
     let mut connection = sqlx::PgConnection::connect(connection_string).await?;
@@ -70,6 +72,19 @@ pub async fn create(
         arguments_to_oids.insert(param_name, i64::from(the_oid));
     }
 
+    let non_nullable_outputs = match inference {
+        Inference::Describe => vec![],
+        Inference::Explain => {
+            crate::native_operations::explain_non_nullable_outputs(
+                &mut connection,
+                &sql.sql,
+                result_parameters.len(),
+            )
+            .instrument(info_span!("Explain native operation"))
+            .await?
+        }
+    };
+
     // Fill the columns list.
     for (index, column) in result.columns.iter().enumerate() {
         let the_oid = column
@@ -79,12 +94,29 @@ pub async fn create(
                 "Internal error: All sqlx TypeInfos should have an oid."
             ))?
             .0;
-        let is_nullable = result.nullable(index).unwrap_or(
-            // If we don't know, we assume it is nullable.
-            true,
-        );
+        let is_nullable = !non_nullable_outputs.get(index).copied().unwrap_or(false)
+            && result.nullable(index).unwrap_or(
+                // If we don't know, we assume it is nullable.
+                true,
+            );
 
-        columns_to_oids.insert(column.name().to_string(), (i64::from(the_oid), is_nullable));
+        // Expressions such as `x + 1` are named `?column?`, and aggregates after their
+        // function, such as `sum`, unless they are given an alias.
+        if column.name() == "?column?" {
+            anyhow::bail!(
+                "Column {} of the Native Operation has no name. Please give it one with AS.",
+                index + 1
+            );
+        }
+        if columns_to_oids
+            .insert(column.name().to_string(), (i64::from(the_oid), is_nullable))
+            .is_some()
+        {
+            anyhow::bail!(
+                "The Native Operation returns more than one column named '{}'. Please rename them with AS.",
+                column.name()
+            );
+        }
     }
 
     let mut oids: BTreeSet<i64> = arguments_to_oids.values().copied().collect();
@@ -92,6 +124,8 @@ pub async fn create(
     let oids_vec: Vec<_> = oids.into_iter().collect();
     let oids_map = oids_to_typenames(configuration, connection_string, &oids_vec).await?;
 
+    let nullable_arguments =
+        crate::native_operations::null_checked_arguments(operation_file_contents);
     let mut arguments = BTreeMap::new();
     for (name, oid) in arguments_to_oids {
         arguments.insert(
@@ -105,8 +139,11 @@ pub async fn create(
                         .clone(),
                 ),
                 description: None,
-                // we don't have this information, so we assume not nullable.
-                nullable: metadata::Nullable::NonNullable,
+                nullable: if nullable_arguments.contains(name.as_str()) {
+                    metadata::Nullable::Nullable
+                } else {
+                    metadata::Nullable::NonNullable
+                },
             },
         );
     }
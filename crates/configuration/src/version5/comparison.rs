@@ -14,6 +14,10 @@ pub struct ComparisonOperatorMapping {
     pub exposed_name: String,
     /// Equal, In or Custom.
     pub operator_kind: OperatorKind,
+    /// Only expose the operator on the type with this name, such as `hstore`, rather than on
+    /// every type which defines it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub type_name: Option<String>,
 }
 
 impl ComparisonOperatorMapping {
@@ -25,108 +29,143 @@ impl ComparisonOperatorMapping {
                 operator_name: "=".to_string(),
                 exposed_name: "_eq".to_string(),
                 operator_kind: OperatorKind::Equal,
+                type_name: None,
             },
             ComparisonOperatorMapping {
                 operator_name: "<=".to_string(),
                 exposed_name: "_lte".to_string(),
                 operator_kind: OperatorKind::Custom,
+                type_name: None,
             },
             ComparisonOperatorMapping {
                 operator_name: ">".to_string(),
                 exposed_name: "_gt".to_string(),
                 operator_kind: OperatorKind::Custom,
+                type_name: None,
             },
             ComparisonOperatorMapping {
                 operator_name: ">=".to_string(),
                 exposed_name: "_gte".to_string(),
                 operator_kind: OperatorKind::Custom,
+                type_name: None,
             },
             ComparisonOperatorMapping {
                 operator_name: "<".to_string(),
                 exposed_name: "_lt".to_string(),
                 operator_kind: OperatorKind::Custom,
+                type_name: None,
             },
             ComparisonOperatorMapping {
                 operator_name: "<>".to_string(),
                 exposed_name: "_neq".to_string(),
                 operator_kind: OperatorKind::Custom,
+                type_name: None,
             },
             // Preferred by CockroachDB
             ComparisonOperatorMapping {
                 operator_name: "!=".to_string(),
                 exposed_name: "_neq".to_string(),
                 operator_kind: OperatorKind::Custom,
+                type_name: None,
             },
             ComparisonOperatorMapping {
                 operator_name: "LIKE".to_string(),
                 exposed_name: "_like".to_string(),
                 operator_kind: OperatorKind::Custom,
+                type_name: None,
             },
             ComparisonOperatorMapping {
                 operator_name: "NOT LIKE".to_string(),
                 exposed_name: "_nlike".to_string(),
                 operator_kind: OperatorKind::Custom,
+                type_name: None,
             },
             ComparisonOperatorMapping {
                 operator_name: "ILIKE".to_string(),
                 exposed_name: "_ilike".to_string(),
                 operator_kind: OperatorKind::Custom,
+                type_name: None,
             },
             ComparisonOperatorMapping {
                 operator_name: "NOT ILIKE".to_string(),
                 exposed_name: "_nilike".to_string(),
                 operator_kind: OperatorKind::Custom,
+                type_name: None,
             },
             ComparisonOperatorMapping {
                 operator_name: "SIMILAR TO".to_string(),
                 exposed_name: "_similar".to_string(),
                 operator_kind: OperatorKind::Custom,
+                type_name: None,
             },
             ComparisonOperatorMapping {
                 operator_name: "NOT SIMILAR TO".to_string(),
                 exposed_name: "_nsimilar".to_string(),
                 operator_kind: OperatorKind::Custom,
+                type_name: None,
             },
             // Preferred by Postgres
             ComparisonOperatorMapping {
                 operator_name: "~~".to_string(),
                 exposed_name: "_like".to_string(),
                 operator_kind: OperatorKind::Custom,
+                type_name: None,
             },
             ComparisonOperatorMapping {
                 operator_name: "!~~".to_string(),
                 exposed_name: "_nlike".to_string(),
                 operator_kind: OperatorKind::Custom,
+                type_name: None,
             },
             ComparisonOperatorMapping {
                 operator_name: "~~*".to_string(),
                 exposed_name: "_ilike".to_string(),
                 operator_kind: OperatorKind::Custom,
+                type_name: None,
             },
             ComparisonOperatorMapping {
                 operator_name: "!~~*".to_string(),
                 exposed_name: "_nilike".to_string(),
                 operator_kind: OperatorKind::Custom,
+                type_name: None,
             },
             ComparisonOperatorMapping {
                 operator_name: "~".to_string(),
                 exposed_name: "_regex".to_string(),
                 operator_kind: OperatorKind::Custom,
+                type_name: None,
             },
             ComparisonOperatorMapping {
                 operator_name: "!~".to_string(),
                 exposed_name: "_nregex".to_string(),
                 operator_kind: OperatorKind::Custom,
+                type_name: None,
             },
             ComparisonOperatorMapping {
                 operator_name: "~*".to_string(),
                 exposed_name: "_iregex".to_string(),
                 operator_kind: OperatorKind::Custom,
+                type_name: None,
             },
             ComparisonOperatorMapping {
                 operator_name: "!~*".to_string(),
                 exposed_name: "_niregex".to_string(),
                 operator_kind: OperatorKind::Custom,
+                type_name: None,
+            },
+            // Containment of hstores. Other types, such as jsonb, ranges and geometric types,
+            // define these operators with meanings of their own.
+            ComparisonOperatorMapping {
+                operator_name: "@>".to_string(),
+                exposed_name: "_contains".to_string(),
+                operator_kind: OperatorKind::Custom,
+                type_name: Some("hstore".to_string()),
+            },
+            ComparisonOperatorMapping {
+                operator_name: "<@".to_string(),
+                exposed_name: "_contained_in".to_string(),
+                operator_kind: OperatorKind::Custom,
+                type_name: Some("hstore".to_string()),
             },
         ]
     }
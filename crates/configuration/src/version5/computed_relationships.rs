@@ -0,0 +1,184 @@
+//! Check the conditions of computed relationships against the database.
+
+use query_engine_metadata::metadata::{ComputedRelationship, ConditionPart};
+use query_engine_sql::sql::dialect::Dialect;
+use sqlx::Executor;
+
+use crate::environment::Environment;
+
+/// Check the computed relationships of the tables in the configuration, and describe each one
+/// which cannot be used: one to a collection which is not a table of the same database, one
+/// whose condition uses a placeholder other than `{{source}}` and `{{target}}`, and one whose
+/// condition the database rejects, such as for referring to a column which does not exist or
+/// for not being boolean.
+///
+/// Only relationships between tables of the primary database are described by the database.
+pub async fn verify(
+    configuration: &super::ParsedConfiguration,
+    environment: &impl Environment,
+    session: &crate::SessionSettings,
+) -> anyhow::Result<Vec<String>> {
+    let tables = &configuration.metadata.tables.0;
+    let relationships = tables
+        .iter()
+        .flat_map(|(source, source_info)| {
+            source_info
+                .computed_relationships
+                .iter()
+                .map(move |(target, relationship)| (source, source_info, target, relationship))
+        })
+        .collect::<Vec<_>>();
+    if relationships.is_empty() {
+        return Ok(vec![]);
+    }
+
+    let mut connection =
+        super::native_operations::connect(configuration, environment, session).await?;
+    let dialect = super::to_runtime_configuration::convert_dialect(configuration.dialect).dialect();
+
+    let mut problems = vec![];
+    for (source, source_info, target, relationship) in relationships {
+        let Some(target_info) = tables.get(target) else {
+            problems.push(format!(
+                "The computed relationship from '{source}' to '{target}' is not to a table."
+            ));
+            continue;
+        };
+        if source_info.target != target_info.target {
+            problems.push(format!(
+                "The computed relationship from '{source}' to '{target}' is between tables of different databases."
+            ));
+            continue;
+        }
+
+        let Some(sql) = condition_query(
+            dialect,
+            (&source_info.schema_name, &source_info.table_name),
+            (&target_info.schema_name, &target_info.table_name),
+            &relationship.condition,
+        ) else {
+            problems.push(format!(
+                "The condition of the computed relationship from '{source}' to '{target}' uses a placeholder other than {{{{source}}}} and {{{{target}}}}."
+            ));
+            continue;
+        };
+        // tables of other targets are not in the database we are connected to
+        if source_info.target.is_some() {
+            continue;
+        }
+
+        if let Err(err) = connection.describe(&sql).await {
+            problems.push(format!(
+                "The condition of the computed relationship from '{source}' to '{target}' could not be described: {err}"
+            ));
+        }
+    }
+
+    Ok(problems)
+}
+
+/// The query which selects from the source and target tables, by schema and table name, with
+/// the condition as its predicate, or nothing if the condition uses a placeholder other than
+/// `{{source}}` and `{{target}}`.
+fn condition_query(
+    dialect: &dyn Dialect,
+    (source_schema, source_table): (&str, &str),
+    (target_schema, target_table): (&str, &str),
+    condition: &str,
+) -> Option<String> {
+    let mut predicate = String::new();
+    for part in ComputedRelationship::parse(condition).condition {
+        match part {
+            ConditionPart::Text(text) => predicate.push_str(&text),
+            ConditionPart::Source => dialect.quote_identifier("source", &mut predicate),
+            ConditionPart::Target => dialect.quote_identifier("target", &mut predicate),
+        }
+    }
+    if predicate.contains("{{") {
+        return None;
+    }
+
+    let mut sql = "SELECT 1 FROM ".to_string();
+    dialect.quote_identifier(source_schema, &mut sql);
+    sql.push('.');
+    dialect.quote_identifier(source_table, &mut sql);
+    sql.push_str(" AS ");
+    dialect.quote_identifier("source", &mut sql);
+    sql.push_str(", ");
+    dialect.quote_identifier(target_schema, &mut sql);
+    sql.push('.');
+    dialect.quote_identifier(target_table, &mut sql);
+    sql.push_str(" AS ");
+    dialect.quote_identifier("target", &mut sql);
+    sql.push_str(" WHERE ");
+    sql.push_str(&predicate);
+    Some(sql)
+}
+
+#[cfg(test)]
+mod tests {
+    use query_engine_metadata::metadata::{ComputedRelationship, ConditionPart};
+    use query_engine_sql::sql::dialect::Postgres;
+
+    use super::condition_query;
+
+    #[test]
+    fn conditions_are_split_at_their_placeholders() {
+        assert_eq!(
+            ComputedRelationship::parse(
+                "ST_DWithin({{source}}.location, {{ target }}.location, 10)"
+            ),
+            ComputedRelationship {
+                condition: vec![
+                    ConditionPart::Text("ST_DWithin(".to_string()),
+                    ConditionPart::Source,
+                    ConditionPart::Text(".location, ".to_string()),
+                    ConditionPart::Target,
+                    ConditionPart::Text(".location, 10)".to_string()),
+                ]
+            }
+        );
+    }
+
+    #[test]
+    fn other_text_between_braces_is_kept() {
+        assert_eq!(
+            ComputedRelationship::parse("{{source}}.tags @> '{{a}}' AND {{unclosed"),
+            ComputedRelationship {
+                condition: vec![
+                    ConditionPart::Source,
+                    ConditionPart::Text(".tags @> '{{a}}' AND {{unclosed".to_string()),
+                ]
+            }
+        );
+    }
+
+    #[test]
+    fn conditions_are_described_over_both_tables() {
+        assert_eq!(
+            condition_query(
+                &Postgres,
+                ("public", "Artist"),
+                ("public", "Al\"bum"),
+                "{{source}}.\"ArtistId\" = {{target}}.\"ArtistId\"",
+            )
+            .as_deref(),
+            Some(
+                r#"SELECT 1 FROM "public"."Artist" AS "source", "public"."Al""bum" AS "target" WHERE "source"."ArtistId" = "target"."ArtistId""#
+            )
+        );
+    }
+
+    #[test]
+    fn conditions_with_other_placeholders_are_not_described() {
+        assert_eq!(
+            condition_query(
+                &Postgres,
+                ("public", "Artist"),
+                ("public", "Album"),
+                "{{source}}.\"ArtistId\" = {{album}}.\"ArtistId\"",
+            ),
+            None
+        );
+    }
+}
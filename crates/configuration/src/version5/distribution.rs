@@ -0,0 +1,77 @@
+//! Introspect how Citus distributes tables across its nodes.
+
+use std::collections::BTreeMap;
+
+use sqlx::postgres::PgConnection;
+use sqlx::{Executor, Row};
+
+use super::metadata::{Distribution, TablesInfo};
+
+/// Whether the Citus extension is installed in the database, whose catalog of distributed tables
+/// only exists when it is.
+const CITUS_INSTALLED_QUERY: &str =
+    "SELECT EXISTS (SELECT 1 FROM pg_catalog.pg_extension WHERE extname = 'citus')";
+
+/// The distributed and reference tables, with the column distributed tables are distributed by
+/// and the group of tables they are colocated with. Citus records the distribution method of
+/// each table in `pg_dist_partition`: hash, range or append for distributed tables, and none for
+/// reference tables and local tables, of which only reference tables are replicated to every
+/// node. Tables which are not colocated with any other are in colocation group 0.
+const DISTRIBUTED_TABLES_QUERY: &str = "
+SELECT
+  ns.nspname AS schema_name,
+  cl.relname AS table_name,
+  CASE
+    WHEN part.partmethod IN ('h', 'r', 'a')
+    THEN column_to_column_name(part.logicalrelid, part.partkey)
+  END AS distribution_column,
+  nullif(part.colocationid, 0) AS colocation_id,
+  part.partmethod = 'n' AND part.repmodel = 't' AS is_reference
+FROM
+  pg_dist_partition AS part
+INNER JOIN
+  pg_catalog.pg_class AS cl
+  ON (cl.oid = part.logicalrelid)
+INNER JOIN
+  pg_catalog.pg_namespace AS ns
+  ON (ns.oid = cl.relnamespace)
+";
+
+/// Record how Citus distributes each of the tables, if the database uses Citus at all.
+pub async fn introspect(
+    connection: &mut PgConnection,
+    tables: &mut TablesInfo,
+) -> anyhow::Result<()> {
+    let citus_installed: bool = connection
+        .fetch_one(CITUS_INSTALLED_QUERY)
+        .await?
+        .try_get(0)?;
+    if !citus_installed {
+        return Ok(());
+    }
+
+    let mut distributions = BTreeMap::new();
+    for row in connection.fetch_all(DISTRIBUTED_TABLES_QUERY).await? {
+        let schema_name: String = row.try_get("schema_name")?;
+        let table_name: String = row.try_get("table_name")?;
+        let distribution_column: Option<String> = row.try_get("distribution_column")?;
+        let colocation_id: Option<i32> = row.try_get("colocation_id")?;
+        let is_reference: bool = row.try_get("is_reference")?;
+        let distribution = match distribution_column {
+            Some(column) => Distribution::Distributed {
+                column,
+                colocation_id: colocation_id.and_then(|id| u32::try_from(id).ok()),
+            },
+            None if is_reference => Distribution::Reference,
+            None => continue,
+        };
+        distributions.insert((schema_name, table_name), distribution);
+    }
+
+    for table_info in tables.0.values_mut() {
+        table_info.distribution = distributions
+            .get(&(table_info.schema_name.clone(), table_info.table_name.clone()))
+            .cloned();
+    }
+    Ok(())
+}
@@ -8,7 +8,7 @@
 -- query with arguments set.
 
 -- DEALLOCATE ALL; -- Or use 'DEALLOCATE configuration' between reloads
--- PREPARE configuration(varchar[], varchar[], varchar[], jsonb, varchar[], jsonb, varchar[]) AS
+-- PREPARE configuration(varchar[], varchar[], varchar[], jsonb, varchar[], jsonb, varchar[], boolean) AS
 
 WITH
   -- The overall structure of this query is a CTE (i.e. 'WITH .. SELECT')
@@ -70,6 +70,44 @@ WITH
       ON (ns.nspname = schema_name)
   ),
 
+  -- Table inheritance is recorded in `pg_inherits`, see
+  -- https://www.postgresql.org/docs/current/catalog-pg-inherits.html for its
+  -- schema.
+  --
+  -- Partitions are recorded there as well, but we treat those as ordinary
+  -- relations and only consider tables declared with 'INHERITS'.
+  table_inheritance AS
+  (
+    SELECT
+      inh.inhrelid::regclass AS relation_id,
+      inh.inhparent::regclass AS parent_relation_id
+    FROM
+      pg_inherits AS inh
+    INNER JOIN
+      pg_class AS cl
+      ON (cl.oid = inh.inhrelid)
+    WHERE
+      NOT cl.relispartition
+  ),
+
+  -- Tables that inherit from some other table.
+  inheriting_relations AS
+  (
+    SELECT DISTINCT
+      relation_id
+    FROM
+      table_inheritance
+  ),
+
+  -- Tables that some other table inherits from.
+  inherited_relations AS
+  (
+    SELECT DISTINCT
+      parent_relation_id AS relation_id
+    FROM
+      table_inheritance
+  ),
+
   -- Tables and views etc. are recorded in `pg_class`, see
   -- https://www.postgresql.org/docs/current/catalog-pg-class.html for its
   -- schema.
@@ -84,7 +122,15 @@ WITH
     INNER JOIN
       schemas_for_table_collections
       ON (schemas_for_table_collections.schema_id = cl.relnamespace)
-    WHERE relkind IN
+
+    LEFT OUTER JOIN
+      inheriting_relations
+      ON (inheriting_relations.relation_id = cl.oid)
+    WHERE
+      -- Inheriting tables are only exposed separately when asked for. Otherwise
+      -- their rows are part of the parent table.
+      ($8 OR inheriting_relations.relation_id IS NULL)
+      AND relkind IN
       -- Lots of different types of relations exist, but we're only interested in
       -- the ones that can be queried.
       (
@@ -160,6 +206,12 @@ WITH
       AS nullable,
       CASE WHEN att.atthasdef THEN 'hasDefault' ELSE 'noDefault' END
       AS has_default,
+      -- The expressions of generated columns are recorded as their defaults,
+      -- but they are not defaults as far as users are concerned.
+      CASE WHEN attgenerated_exists AND attgenerated::text = 's' THEN NULL
+           ELSE pg_get_expr(def.adbin, def.adrelid)
+      END
+      AS default_expression,
       CASE WHEN att.attidentity = 'd' THEN 'identityByDefault'
            WHEN att.attidentity = 'a' THEN 'identityAlways'
            ELSE 'notIdentity'
@@ -168,9 +220,16 @@ WITH
       CASE WHEN attgenerated_exists
            THEN CASE WHEN attgenerated::text = 's' THEN 'stored' ELSE 'notGenerated' END
            ELSE 'notGenerated'
-      END as is_generated
+      END as is_generated,
+      -- Column privileges of the role we introspect with. These are only
+      -- defaults, as the connector may well run as a different role.
+      has_column_privilege(att.attrelid, att.attnum, 'SELECT') AS selectable,
+      has_column_privilege(att.attrelid, att.attnum, 'INSERT') AS insertable,
+      has_column_privilege(att.attrelid, att.attnum, 'UPDATE') AS updatable
     FROM
       pg_catalog.pg_attribute AS att
+      LEFT OUTER JOIN pg_catalog.pg_attrdef AS def
+        ON (def.adrelid = att.attrelid AND def.adnum = att.attnum)
       CROSS JOIN (SELECT current_setting('server_version_num')::int >= 120000) AS attgenerated(attgenerated_exists)
     WHERE
       -- We only include columns that are actually part of the table currently.
@@ -232,6 +291,66 @@ WITH
       AND objsubid = 0
   ),
 
+  -- CockroachDB records the row-level TTL of a table in its storage
+  -- parameters. Rows expire at the time in the hidden column
+  -- 'crdb_internal_expiration' when the TTL is a fixed interval, or at the
+  -- time given by 'ttl_expiration_expression', which we only understand when
+  -- it names a column.
+  row_expiry AS
+  (
+    SELECT
+      relation_id,
+      CASE
+        WHEN options.expire_after IS NOT NULL
+        THEN jsonb_build_object('column', 'crdb_internal_expiration')
+        WHEN options.expiration_expression ~ '^"[^"]+"$'
+        THEN jsonb_build_object('column', btrim(options.expiration_expression, '"'))
+        WHEN options.expiration_expression ~ '^[A-Za-z_][A-Za-z0-9_]*$'
+        THEN jsonb_build_object('column', lower(options.expiration_expression))
+      END AS result
+    FROM
+      (
+        SELECT
+          cl.oid::regclass AS relation_id,
+          (
+            SELECT substring(option FROM '^ttl_expire_after=(.*)$')
+            FROM unnest(cl.reloptions) AS option
+            WHERE option LIKE 'ttl\_expire\_after=%'
+          ) AS expire_after,
+          (
+            SELECT btrim(substring(option FROM '^ttl_expiration_expression=(.*)$'), '''')
+            FROM unnest(cl.reloptions) AS option
+            WHERE option LIKE 'ttl\_expiration\_expression=%'
+          ) AS expiration_expression
+        FROM
+          pg_class AS cl
+        WHERE
+          cl.reloptions IS NOT NULL
+      ) AS options
+  ),
+
+  -- Whether rows may be inserted into a relation, updated and deleted, as a
+  -- bitmask of the events 'pg_relation_is_updatable' reports: 8 for INSERT, 4
+  -- for UPDATE and 16 for DELETE. Views support those they are automatically
+  -- updatable for or have an 'INSTEAD OF' trigger or rule for, and
+  -- materialized views none.
+  relation_writability AS
+  (
+    SELECT
+      relation_id,
+      (events & 8) = 8 AS insertable,
+      (events & 4) = 4 AS updatable,
+      (events & 16) = 16 AS deletable
+    FROM
+      (
+        SELECT
+          relation_id,
+          pg_relation_is_updatable(relation_id, true) AS events
+        FROM
+          relations
+      ) AS updatable_events
+  ),
+
   type_comments AS
   (
     SELECT
@@ -244,6 +363,25 @@ WITH
       AND objsubid = 0
   ),
 
+  -- The built-in types come with comments too, but these describe their
+  -- storage rather than document anything about the database at hand, so we
+  -- only surface comments on scalar types defined outside 'pg_catalog'.
+  scalar_type_comments AS
+  (
+    SELECT
+      comm.type_id,
+      comm.description
+    FROM
+      type_comments
+      AS comm
+    INNER JOIN
+      pg_type
+      AS t
+      ON (t.oid = comm.type_id)
+    WHERE
+      t.typnamespace != 'pg_catalog'::regnamespace
+  ),
+
   -- Composite types, including those defined implicitly through a table and
   -- explicitly via `CREATE TYPE`.
   composite_types AS
@@ -449,15 +587,48 @@ WITH
   --
   -- Domain types are created using the `CREATE DOMAIN` statement (see
   -- https://www.postgresql.org/docs/current/sql-createdomain.html).
+  --
+  -- A domain may itself be defined over another domain. We resolve each domain
+  -- to the type at the bottom of that chain, so that it gets the operators,
+  -- casts and type representation of an actual base or enum type.
   domain_types AS
   (
+    WITH
+      RECURSIVE domain_chain(type_id, base_type) AS
+      (
+        SELECT
+          t.oid::regtype AS type_id,
+          t.typbasetype::regtype AS base_type
+        FROM
+          pg_catalog.pg_type AS t
+        WHERE
+          t.typtype = 'd'
+        UNION
+        SELECT
+          chain.type_id,
+          t.typbasetype::regtype AS base_type
+        FROM
+          domain_chain
+          AS chain
+        INNER JOIN
+          pg_catalog.pg_type
+          AS t
+          ON (t.oid = chain.base_type)
+        WHERE
+          t.typtype = 'd'
+      )
     SELECT
-      t.oid::regtype AS type_id,
-      t.typbasetype::regtype AS base_type
+      chain.type_id,
+      chain.base_type
     FROM
-      pg_catalog.pg_type AS t
+      domain_chain
+      AS chain
+    INNER JOIN
+      pg_catalog.pg_type
+      AS base
+      ON (base.oid = chain.base_type)
     WHERE
-      t.typtype = 'd'
+      base.typtype != 'd'
   ),
 
   -- Enum types are scalar types that consist of a finite, enumerated set of
@@ -926,8 +1097,14 @@ WITH
           c.column_name,
           'type',
           t.result,
+          'nullable',
+          c.nullable,
+          'hasDefault',
+          c.has_default,
           'description',
-          comm.description
+          comm.description,
+          'position',
+          c.column_number
         )
       )
       AS result
@@ -1075,6 +1252,9 @@ WITH
   -- Enum types are totally ordered and support the conventional comparison operators.
   -- They are defined implicitly (i.e., not registered in `pg_proc` or
   -- `pg_operator`) so we have to make up some definitions for them.
+  --
+  -- The order of the labels is rarely meaningful to a client however, so we
+  -- only expose (in)equality, along with the 'IN' operator derived from it.
   enum_comparison_operators AS
   (
     SELECT
@@ -1085,11 +1265,7 @@ WITH
     FROM
       (VALUES
         ('='),
-        ('!='),
-        ('<='),
-        ('>'),
-        ('>='),
-        ('<')
+        ('!=')
       )
       AS op(oprname),
       enum_types e
@@ -1322,7 +1498,8 @@ WITH
     SELECT
       v ->> 'operatorName' AS operator_name,
       v ->> 'exposedName' AS exposed_name,
-      v ->> 'operatorKind' AS operator_kind
+      v ->> 'operatorKind' AS operator_kind,
+      v ->> 'typeName' AS type_name
     FROM
       jsonb_array_elements($4) AS v
   ),
@@ -1447,6 +1624,8 @@ WITH
       enum_types
   ),
 
+  -- Domains take on the representation of the type they resolve to, unless
+  -- they were given one of their own.
   domain_type_representations AS
   (
     SELECT
@@ -1456,8 +1635,16 @@ WITH
       domain_types
 
     INNER JOIN
-      base_type_representations
-      ON (domain_types.base_type = base_type_representations.type_id)
+      (
+        SELECT * FROM base_type_representations
+        UNION
+        SELECT * FROM enum_type_representations
+      )
+      AS resolved_type_representations
+      ON (domain_types.base_type = resolved_type_representations.type_id)
+
+    WHERE
+      domain_types.type_id NOT IN (SELECT type_id FROM base_type_representations)
   ),
 
   type_representations_json AS
@@ -1501,8 +1688,14 @@ WITH
           operator_mappings
           AS map
           USING (operator_name)
+        LEFT OUTER JOIN
+          scalar_type_names
+          AS argument1_type_names
+          ON (op.argument1_type = argument1_type_names.type_id)
         WHERE
           op.is_infix = 't'
+          -- Some mappings only apply to the operators of a specific type.
+          AND (map.type_name IS NULL OR map.type_name = argument1_type_names.type_name)
       ),
 
       comparison_prefix_operators AS
@@ -1649,6 +1842,7 @@ WITH
             'schemaName', schema_name,
             'aggregateFunctions', coalesce(aggregates.result, '{}'::jsonb),
             'comparisonOperators', coalesce(comparisons.result, '{}'::jsonb),
+            'description', comm.description,
             'typeRepresentation', representation.result
           )
           AS result
@@ -1658,6 +1852,10 @@ WITH
         INNER JOIN
           live_types
           USING (type_id)
+        LEFT OUTER JOIN
+          scalar_type_comments
+          AS comm
+          USING (type_id)
         LEFT OUTER JOIN
           aggregate_functions_json
           AS aggregates
@@ -1788,10 +1986,18 @@ WITH
             c.nullable,
             'hasDefault',
             c.has_default,
+            'defaultExpression',
+            c.default_expression,
             'isIdentity',
             c.is_identity,
             'isGenerated',
             c.is_generated,
+            'selectable',
+            c.selectable,
+            'insertable',
+            c.insertable,
+            'updatable',
+            c.updatable,
             'description',
             comm.description
             )
@@ -1809,6 +2015,72 @@ WITH
       HAVING
         -- All columns must have a supported type for us to list this table.
         bool_and(NOT t.result IS NULL)
+    ),
+
+    -- Functions which take a row of a table as their only argument and return
+    -- a single value of a supported type are computed fields of that table,
+    -- named after the function. Functions named like a column of the table
+    -- are left out, as the column takes precedence.
+    computed_fields_json AS
+    (
+      SELECT
+        cl.oid::regclass AS relation_id,
+        jsonb_object_agg(
+          proc.proname,
+          jsonb_build_object(
+            'function',
+            jsonb_build_object(
+              'schemaName',
+              schemas.schema_name,
+              'functionName',
+              proc.proname
+            ),
+            'type',
+            t.result,
+            'description',
+            comm.description
+          )
+        )
+        AS result
+      FROM
+        pg_catalog.pg_proc
+        AS proc
+      INNER JOIN
+        pg_class
+        AS cl
+        ON (cl.reltype = proc.proargtypes[0])
+      INNER JOIN
+        relations
+        ON (relations.relation_id = cl.oid)
+      INNER JOIN
+        schemas_for_table_collections
+        AS schemas
+        ON (schemas.schema_id = proc.pronamespace)
+      INNER JOIN
+        column_types_json
+        AS t
+        ON (t.type_id = proc.prorettype)
+      LEFT OUTER JOIN
+        columns
+        AS c
+        ON (c.relation_id = cl.oid AND c.column_name = proc.proname)
+      LEFT OUTER JOIN
+        pg_description
+        AS comm
+        ON (
+          comm.classoid = 'pg_catalog.pg_proc'::regclass
+          AND comm.objoid = proc.oid
+          AND comm.objsubid = 0
+        )
+      WHERE
+        cardinality(proc.proargtypes) = 1
+        AND proc.prokind = 'f'
+        AND NOT proc.proretset
+        -- Computed fields are selected as often as the rows they belong to, so functions with
+        -- side effects are left out.
+        AND proc.provolatile IN ('i', 's')
+        AND c.column_name IS NULL
+      GROUP BY cl.oid
     )
     -- Tables and views
     SELECT
@@ -1826,7 +2098,23 @@ WITH
           'uniquenessConstraints',
           coalesce(uniqueness_constraints_json.result, '{}'::jsonb),
           'foreignRelations',
-          coalesce(foreign_key_constraints_json.result, '{}'::jsonb)
+          coalesce(foreign_key_constraints_json.result, '{}'::jsonb),
+          'inheritedRows',
+          CASE
+            WHEN $8 AND inherited_relations.relation_id IS NOT NULL
+            THEN 'excluded'
+            ELSE 'included'
+          END,
+          'rowExpiry',
+          row_expiry.result,
+          'computedFields',
+          coalesce(computed_fields_json.result, '{}'::jsonb),
+          'insertable',
+          writability.insertable,
+          'updatable',
+          writability.updatable,
+          'deletable',
+          writability.deletable
         )
       )
       AS result
@@ -1853,15 +2141,83 @@ WITH
     LEFT OUTER JOIN
       foreign_key_constraints_json
     USING (relation_id)
+
+    -- Whether other tables inherit from this one.
+    LEFT OUTER JOIN
+      inherited_relations
+    USING (relation_id)
+
+    -- When rows expire, if they do.
+    LEFT OUTER JOIN
+      row_expiry
+    USING (relation_id)
+
+    -- Functions of the rows of this table.
+    LEFT OUTER JOIN
+      computed_fields_json
+    USING (relation_id)
+
+    -- Which statements may write to this table.
+    INNER JOIN
+      relation_writability
+      AS writability
+    USING (relation_id)
+  ),
+
+  -- Sequences are recorded in `pg_class` as well, as relations of kind 'S'.
+  -- They are named like tables, and exposed as the procedures
+  -- 'nextval_<name>' and 'currval_<name>'.
+  sequences_json AS
+  (
+    SELECT
+      jsonb_object_agg(
+        CASE
+          WHEN unqualified.schema_id IS NOT NULL
+          THEN cl.relname
+          ELSE schemas.schema_name || '_' || cl.relname
+        END,
+        jsonb_build_object(
+          'schemaName',
+          schemas.schema_name,
+          'sequenceName',
+          cl.relname,
+          'description',
+          comm.description
+        )
+      )
+      AS result
+    FROM
+      pg_class
+      AS cl
+
+    -- We only want to know about sequences that don't live in uninteresting schemas.
+    INNER JOIN
+      schemas_for_table_collections
+      AS schemas
+      ON (schemas.schema_id = cl.relnamespace)
+
+    LEFT OUTER JOIN
+      unqualified_schemas_for_tables
+      AS unqualified
+      ON (cl.relnamespace = unqualified.schema_id)
+
+    LEFT OUTER JOIN
+      table_comments
+      AS comm
+      ON (comm.relation_id = cl.oid::regclass)
+    WHERE
+      cl.relkind = 'S'
   )
 
 SELECT
   coalesce(tables_json.result, '{}'::jsonb) AS "Tables",
   coalesce(scalar_types_json.result, '{}'::jsonb) AS "ScalarTypes",
-  coalesce(composite_types_json.result, '{}'::jsonb) AS "CompositeTypes"
+  coalesce(composite_types_json.result, '{}'::jsonb) AS "CompositeTypes",
+  coalesce(sequences_json.result, '{}'::jsonb) AS "Sequences"
 FROM scalar_types_json
 CROSS JOIN composite_types_json
 CROSS JOIN tables_json
+CROSS JOIN sequences_json
 ;
 
 -- Uncomment the following lines to just run the configuration query with reasonable default arguments
@@ -1888,5 +2244,6 @@ CROSS JOIN tables_json
 --    ]'::jsonb,
 --   '{box_above,box_below, st_covers, st_coveredby}'::varchar[],
 --   '{"int4": "integer"}'::jsonb,
---   '{bool}'::varchar[]
+--   '{bool}'::varchar[],
+--   false
 -- );
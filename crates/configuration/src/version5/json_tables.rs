@@ -0,0 +1,330 @@
+//! Collections derived from the JSON documents in a column of a table, using `JSON_TABLE`.
+
+use std::collections::BTreeMap;
+
+use ndc_models as models;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use query_engine_sql::sql::dialect::Dialect;
+
+use super::metadata;
+
+/// The alias of the table the documents are in.
+const SOURCE_ALIAS: &str = "source";
+/// The alias of the rows `JSON_TABLE` makes of the documents.
+const ROWS_ALIAS: &str = "rows";
+
+/// A collection whose rows are found in the JSON documents of a column of a table, such as the
+/// line items of orders stored as `jsonb`. Each document can give any number of rows, and each
+/// row is the document of the source row at `path`, flattened into the given columns with
+/// `JSON_TABLE`. Requires PostgreSQL 17 or later.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct JsonTable {
+    /// The table collection with the JSON documents.
+    pub collection: models::CollectionName,
+    /// The field of that collection holding the documents, which should be of type `json` or
+    /// `jsonb`.
+    pub field: models::FieldName,
+    /// The SQL/JSON path of the rows within each document, such as `$.items[*]`.
+    pub path: String,
+    /// The columns of each row.
+    pub columns: BTreeMap<models::FieldName, JsonTableColumn>,
+    /// Fields of the source collection to include in each row, such as its primary key, so that
+    /// rows can be related back to the row they come from.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub source_fields: Vec<models::FieldName>,
+    #[serde(default)]
+    pub description: Option<String>,
+}
+
+/// A column of the rows of a JSON table.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct JsonTableColumn {
+    /// The SQL/JSON path of the value within the row, such as `$.quantity`.
+    pub path: String,
+    /// The scalar type the value is converted to.
+    pub r#type: models::ScalarTypeName,
+    #[serde(default)]
+    pub description: Option<String>,
+}
+
+/// Describe a JSON table as the Native Query which selects its rows.
+pub(crate) fn to_native_query(
+    json_table: &JsonTable,
+    metadata: &metadata::Metadata,
+    dialect: &dyn Dialect,
+) -> Result<metadata::NativeQueryInfo, String> {
+    let table = metadata
+        .tables
+        .0
+        .get(&json_table.collection)
+        .ok_or_else(|| format!("there is no table named '{}'", json_table.collection))?;
+    let lookup_column = |field: &models::FieldName| {
+        table.columns.get(field).ok_or_else(|| {
+            format!(
+                "the table '{}' has no field named '{field}'",
+                json_table.collection
+            )
+        })
+    };
+    let document_column = lookup_column(&json_table.field)?;
+    let is_document = match &document_column.r#type {
+        metadata::Type::ScalarType(scalar_type) => metadata
+            .types
+            .scalar
+            .0
+            .get(scalar_type)
+            .is_some_and(|scalar_type| {
+                scalar_type.schema_name == "pg_catalog"
+                    && matches!(scalar_type.type_name.as_str(), "json" | "jsonb")
+            }),
+        metadata::Type::CompositeType(_) | metadata::Type::ArrayType(_) => false,
+    };
+    if !is_document {
+        return Err(format!(
+            "the field '{}' of the table '{}' is not of type json or jsonb",
+            json_table.field, json_table.collection
+        ));
+    }
+
+    let mut sql = String::from("SELECT ");
+    let mut columns = BTreeMap::new();
+    for field in &json_table.source_fields {
+        if json_table.columns.contains_key(field) {
+            return Err(format!(
+                "'{field}' is both a field of the source table and a column"
+            ));
+        }
+        let column = lookup_column(field)?;
+        if !column.selectable {
+            return Err(format!(
+                "the field '{field}' of the table '{}' may not be selected",
+                json_table.collection
+            ));
+        }
+        dialect.quote_identifier(SOURCE_ALIAS, &mut sql);
+        sql.push('.');
+        dialect.quote_identifier(&column.name, &mut sql);
+        sql.push_str(" AS ");
+        dialect.quote_identifier(field.as_str(), &mut sql);
+        sql.push_str(", ");
+        columns.insert(
+            field.clone(),
+            metadata::ReadOnlyColumnInfo {
+                name: field.to_string(),
+                r#type: column.r#type.clone(),
+                nullable: column.nullable.clone(),
+                description: column.description.clone(),
+            },
+        );
+    }
+
+    let mut json_columns = vec![];
+    for (field, column) in &json_table.columns {
+        let scalar_type = metadata
+            .types
+            .scalar
+            .0
+            .get(&column.r#type)
+            .ok_or_else(|| format!("there is no scalar type named '{}'", column.r#type))?;
+        let mut json_column = String::new();
+        dialect.quote_identifier(field.as_str(), &mut json_column);
+        json_column.push(' ');
+        dialect.quote_identifier(&scalar_type.schema_name, &mut json_column);
+        json_column.push('.');
+        dialect.quote_identifier(&scalar_type.type_name, &mut json_column);
+        json_column.push_str(" PATH ");
+        dialect.quote_literal(&column.path, &mut json_column);
+        json_columns.push(json_column);
+        columns.insert(
+            field.clone(),
+            metadata::ReadOnlyColumnInfo {
+                name: field.to_string(),
+                r#type: metadata::Type::ScalarType(column.r#type.clone()),
+                nullable: metadata::Nullable::Nullable,
+                description: column.description.clone(),
+            },
+        );
+    }
+    if json_columns.is_empty() {
+        return Err("a JSON table needs at least one column".to_string());
+    }
+
+    dialect.quote_identifier(ROWS_ALIAS, &mut sql);
+    sql.push_str(".* FROM ");
+    dialect.quote_identifier(&table.schema_name, &mut sql);
+    sql.push('.');
+    dialect.quote_identifier(&table.table_name, &mut sql);
+    sql.push_str(" AS ");
+    dialect.quote_identifier(SOURCE_ALIAS, &mut sql);
+    sql.push_str(", JSON_TABLE(");
+    dialect.quote_identifier(SOURCE_ALIAS, &mut sql);
+    sql.push('.');
+    dialect.quote_identifier(&document_column.name, &mut sql);
+    sql.push_str(", ");
+    dialect.quote_literal(&json_table.path, &mut sql);
+    sql.push_str(" COLUMNS (");
+    sql.push_str(&json_columns.join(", "));
+    sql.push_str(")) AS ");
+    dialect.quote_identifier(ROWS_ALIAS, &mut sql);
+
+    Ok(metadata::NativeQueryInfo {
+        sql: metadata::NativeQuerySqlEither::NativeQuerySql(metadata::NativeQuerySql::Inline {
+            sql: metadata::NativeQueryParts(vec![metadata::NativeQueryPart::Text(sql)]),
+        }),
+        columns,
+        arguments: BTreeMap::new(),
+        description: json_table.description.clone(),
+        foreign_relations: metadata::ForeignRelations::default(),
+        isolation_level: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use query_engine_sql::sql::dialect::Postgres;
+
+    use super::*;
+
+    fn orders_metadata() -> metadata::Metadata {
+        let column = |name: &str, scalar_type: &str| metadata::ColumnInfo {
+            name: name.to_string(),
+            r#type: metadata::Type::ScalarType(scalar_type.into()),
+            nullable: metadata::Nullable::NonNullable,
+            has_default: metadata::HasDefault::NoDefault,
+            default_expression: None,
+            is_identity: metadata::IsIdentity::NotIdentity,
+            is_generated: metadata::IsGenerated::NotGenerated,
+            generates_uuid: false,
+            selectable: true,
+            insertable: true,
+            updatable: true,
+            filterable: true,
+            sortable: true,
+            description: None,
+        };
+        let scalar_type = |name: &str| metadata::ScalarType {
+            type_name: name.to_string(),
+            schema_name: "pg_catalog".to_string(),
+            description: None,
+            aggregate_functions: BTreeMap::new(),
+            comparison_operators: BTreeMap::new(),
+            type_representation: None,
+        };
+        let mut metadata = metadata::Metadata::default();
+        metadata.tables.0.insert(
+            "orders".into(),
+            metadata::TableInfo {
+                schema_name: "public".to_string(),
+                table_name: "orders".to_string(),
+                columns: BTreeMap::from([
+                    ("id".into(), column("order_id", "int4")),
+                    ("document".into(), column("document", "jsonb")),
+                ]),
+                uniqueness_constraints: metadata::UniquenessConstraints::default(),
+                foreign_relations: metadata::ForeignRelations::default(),
+                description: None,
+                inherited_rows: metadata::InheritedRows::default(),
+                target: None,
+                statement_timeout_ms: None,
+                logical_keys: metadata::UniquenessConstraints::default(),
+                row_expiry: None,
+                soft_delete: None,
+                audit_columns: BTreeMap::new(),
+                computed_relationships: BTreeMap::new(),
+                computed_fields: BTreeMap::new(),
+                insertable: true,
+                updatable: true,
+                deletable: true,
+                mutable: None,
+                distribution: None,
+            },
+        );
+        for name in ["int4", "jsonb", "text"] {
+            metadata
+                .types
+                .scalar
+                .0
+                .insert(name.into(), scalar_type(name));
+        }
+        metadata
+    }
+
+    #[test]
+    fn json_tables_select_from_json_table() {
+        let json_table = JsonTable {
+            collection: "orders".into(),
+            field: "document".into(),
+            path: "$.items[*]".to_string(),
+            columns: BTreeMap::from([
+                (
+                    "sku".into(),
+                    JsonTableColumn {
+                        path: "$.sku".to_string(),
+                        r#type: "text".into(),
+                        description: None,
+                    },
+                ),
+                (
+                    "quantity".into(),
+                    JsonTableColumn {
+                        path: "$.quantity".to_string(),
+                        r#type: "int4".into(),
+                        description: None,
+                    },
+                ),
+            ]),
+            source_fields: vec!["id".into()],
+            description: None,
+        };
+        let native_query = to_native_query(&json_table, &orders_metadata(), &Postgres).unwrap();
+        let sql: String = native_query.sql.sql().unwrap().into();
+        assert_eq!(
+            sql,
+            "SELECT \"source\".\"order_id\" AS \"id\", \"rows\".* FROM \"public\".\"orders\" AS \"source\", JSON_TABLE(\"source\".\"document\", '$.items[*]' COLUMNS (\"quantity\" \"pg_catalog\".\"int4\" PATH '$.quantity', \"sku\" \"pg_catalog\".\"text\" PATH '$.sku')) AS \"rows\""
+        );
+        assert_eq!(
+            native_query
+                .columns
+                .keys()
+                .map(models::FieldName::as_str)
+                .collect::<Vec<_>>(),
+            vec!["id", "quantity", "sku"]
+        );
+    }
+
+    #[test]
+    fn json_tables_must_refer_to_existing_fields() {
+        let json_table = JsonTable {
+            collection: "orders".into(),
+            field: "payload".into(),
+            path: "$".to_string(),
+            columns: BTreeMap::new(),
+            source_fields: vec![],
+            description: None,
+        };
+        assert_eq!(
+            to_native_query(&json_table, &orders_metadata(), &Postgres).unwrap_err(),
+            "the table 'orders' has no field named 'payload'"
+        );
+    }
+
+    #[test]
+    fn json_tables_must_be_made_of_json_documents() {
+        let json_table = JsonTable {
+            collection: "orders".into(),
+            field: "id".into(),
+            path: "$".to_string(),
+            columns: BTreeMap::new(),
+            source_fields: vec![],
+            description: None,
+        };
+        assert_eq!(
+            to_native_query(&json_table, &orders_metadata(), &Postgres).unwrap_err(),
+            "the field 'id' of the table 'orders' is not of type json or jsonb"
+        );
+    }
+}
@@ -64,7 +64,14 @@ pub struct FieldInfo {
     pub field_name: String,
     pub r#type: Type,
     #[serde(default)]
+    pub nullable: Nullable,
+    #[serde(default)]
+    pub has_default: HasDefault,
+    #[serde(default)]
     pub description: Option<String>,
+    /// The position of the field within the type, by which values of the type are constructed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub position: Option<u16>,
 }
 
 /// Represents a postgres binary comparison operator
@@ -113,6 +120,223 @@ pub struct TableInfo {
     pub foreign_relations: ForeignRelations,
     #[serde(default)]
     pub description: Option<String>,
+    #[serde(skip_serializing_if = "includes_inherited_rows")]
+    #[serde(default)]
+    pub inherited_rows: InheritedRows,
+    /// The connection target the table is in, as named in `connectionSettings.targets`. Tables
+    /// which do not name one are in the database at `connectionUri`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub target: Option<String>,
+    /// Cancel queries over the table which run for longer than this, instead of the statement
+    /// timeout of the database, such as to give slow views a longer budget (milliseconds).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub statement_timeout_ms: Option<u64>,
+    /// Sets of columns which identify the rows of a table which has no uniqueness constraints of
+    /// its own, such as a view. These are treated like uniqueness constraints, except that no
+    /// mutations by key are generated for them.
+    #[serde(default, skip_serializing_if = "UniquenessConstraints::is_empty")]
+    pub logical_keys: UniquenessConstraints,
+    /// When the rows of the table expire, such as with the row-level TTL of CockroachDB, which
+    /// is introspected, or a retention policy of TimescaleDB. Queries leave out expired rows
+    /// which have not been deleted yet.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub row_expiry: Option<RowExpiry>,
+    /// Mark rows of the table as deleted instead of deleting them. Queries leave out the rows
+    /// marked as deleted, and the generated delete mutations mark rows instead of deleting them.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub soft_delete: Option<SoftDelete>,
+    /// Columns which the generated insert and update mutations set themselves, such as
+    /// `created_at` or `updated_by`, by field name. Values given for them in mutations are
+    /// overridden.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub audit_columns: BTreeMap<models::FieldName, AuditColumn>,
+    /// Relationships from the table whose rows are matched by a SQL condition rather than by
+    /// equal columns, such as rows within some distance or with overlapping ranges, by target
+    /// collection. The condition is used by relationships to the target which map no columns.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub computed_relationships: BTreeMap<models::CollectionName, ComputedRelationship>,
+    /// Fields of the table computed by SQL over its rows rather than stored in a column, by
+    /// field name. They can be selected, filtered, ordered and aggregated by like columns. Functions
+    /// taking a row of the table as their only argument, which are not volatile, are introspected
+    /// as computed fields.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub computed_fields: BTreeMap<models::FieldName, ComputedField>,
+    /// Whether rows may be inserted into the table, as introspected. Views which are not
+    /// automatically updatable and have no `INSTEAD OF` trigger or rule for a kind of statement
+    /// do not support it, and the generated mutations leave it out.
+    #[serde(skip_serializing_if = "is_true")]
+    #[serde(default = "default_true")]
+    pub insertable: bool,
+    /// Whether rows of the table may be updated, as introspected.
+    #[serde(skip_serializing_if = "is_true")]
+    #[serde(default = "default_true")]
+    pub updatable: bool,
+    /// Whether rows of the table may be deleted, as introspected.
+    #[serde(skip_serializing_if = "is_true")]
+    #[serde(default = "default_true")]
+    pub deletable: bool,
+    /// Override the introspected `insertable`, `updatable` and `deletable` of the table: `true`
+    /// generates all its mutations, even for a view PostgreSQL cannot write to, and `false` none,
+    /// even for a table.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mutable: Option<bool>,
+    /// How Citus distributes the rows of the table across its nodes, as introspected.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub distribution: Option<Distribution>,
+}
+
+/// Mapping from a sequence name to its information.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct Sequences(pub BTreeMap<String, SequenceInfo>);
+
+impl Sequences {
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+/// Information about a database sequence, which is exposed as the procedure `nextval_<name>`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct SequenceInfo {
+    pub schema_name: String,
+    pub sequence_name: String,
+    #[serde(default)]
+    pub description: Option<String>,
+}
+
+/// When the rows of a table expire.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct RowExpiry {
+    /// The column with the time rows expire at, or, with `after`, the time they expire from.
+    pub column: String,
+    /// How long after the time in the column rows expire, as a PostgreSQL interval such as
+    /// `30 days`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub after: Option<String>,
+}
+
+/// How the rows of a table are marked as deleted.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct SoftDelete {
+    /// The column with the time a row was deleted at, such as `deleted_at`, which is `NULL` for
+    /// rows which have not been deleted.
+    pub column: String,
+}
+
+/// How Citus distributes the rows of a table across its nodes.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub enum Distribution {
+    /// The rows are spread over shards by the value of a column.
+    #[serde(rename_all = "camelCase")]
+    Distributed {
+        column: String,
+        /// The group of tables whose shards Citus places together, so that rows with the same
+        /// value of the distribution column are on the same node.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        colocation_id: Option<u32>,
+    },
+    /// Every node has a copy of all the rows.
+    Reference,
+}
+
+/// A relationship whose rows are matched by a SQL condition.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ComputedRelationship {
+    /// A boolean SQL expression, in which `{{source}}` and `{{target}}` stand for the table and
+    /// the target collection, such as
+    /// `ST_DWithin({{source}}.location, {{target}}.location, 1000)`. It is checked against the
+    /// database when the configuration is updated.
+    pub condition: String,
+}
+
+/// A field of a table computed by SQL over its rows.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ComputedField {
+    /// How the value of the field is computed.
+    #[serde(flatten)]
+    pub definition: ComputedFieldDefinition,
+    /// The type of the value of the field, which may be null.
+    pub r#type: Type,
+    #[serde(default)]
+    pub description: Option<String>,
+}
+
+/// How the value of a computed field is computed.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub enum ComputedFieldDefinition {
+    /// A SQL expression in which `{{row}}` stands for the row of the table, such as
+    /// `{{row}}.first_name || ' ' || {{row}}.last_name`.
+    Expression(String),
+    /// A function taking a row of the table as its only argument.
+    Function(FunctionReference),
+}
+
+/// A reference to a SQL function.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct FunctionReference {
+    pub schema_name: String,
+    pub function_name: String,
+}
+
+/// A column which the generated mutations set themselves.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditColumn {
+    /// The value the column is set to.
+    pub value: AuditValue,
+    /// Which mutations set the column.
+    #[serde(default)]
+    pub on: AuditOn,
+}
+
+/// The value of an audit column.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub enum AuditValue {
+    /// The time the transaction started at, `now()`.
+    Now,
+    /// The value of a setting of the session, such as `myapp.user_id`, converted to the type of
+    /// the column. The setting can be given to the connecting role with `ALTER ROLE ... SET`,
+    /// or be the `requestIdSetting`. If it is not set, the column is set to `NULL`.
+    Setting(String),
+}
+
+/// Which of the generated mutations set an audit column.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub enum AuditOn {
+    /// Inserts only, as for `created_at`.
+    Insert,
+    /// Updates only.
+    Update,
+    /// Both inserts and updates, as for `updated_at`.
+    #[default]
+    InsertAndUpdate,
+}
+
+/// Should querying a table also return the rows of the tables that inherit from it.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub enum InheritedRows {
+    /// Query the table as usual, returning the rows of inheriting tables as well.
+    #[default]
+    Included,
+    /// Query the table using `ONLY`, as the inheriting tables are exposed as separate
+    /// collections.
+    Excluded,
+}
+
+fn includes_inherited_rows(inherited_rows: &InheritedRows) -> bool {
+    matches!(inherited_rows, InheritedRows::Included)
 }
 
 /// Can this column contain null values
@@ -163,12 +387,45 @@ pub struct ColumnInfo {
     #[serde(skip_serializing_if = "does_not_have_default")]
     #[serde(default)]
     pub has_default: HasDefault,
+    /// The expression of the default of this column, as the database prints it,
+    /// e.g. `now()` or `'active'::text`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub default_expression: Option<String>,
     #[serde(skip_serializing_if = "is_not_identity")]
     #[serde(default)]
     pub is_identity: IsIdentity,
     #[serde(skip_serializing_if = "is_not_generated")]
     #[serde(default)]
     pub is_generated: IsGenerated,
+    /// Whether the connector fills in a random UUID when generated inserts omit this column, for
+    /// columns without a default to do so. This is set by hand and kept by introspection; columns
+    /// whose `defaultExpression` is `gen_random_uuid()` or `uuid_generate_v4()` are recognised
+    /// without it.
+    #[serde(skip_serializing_if = "is_false")]
+    #[serde(default)]
+    pub generates_uuid: bool,
+    /// Whether this column may be selected, filtered or ordered by.
+    #[serde(skip_serializing_if = "is_true")]
+    #[serde(default = "default_true")]
+    pub selectable: bool,
+    /// Whether this column may be set by generated insert mutations.
+    #[serde(skip_serializing_if = "is_true")]
+    #[serde(default = "default_true")]
+    pub insertable: bool,
+    /// Whether this column may be set by generated update mutations.
+    #[serde(skip_serializing_if = "is_true")]
+    #[serde(default = "default_true")]
+    pub updatable: bool,
+    /// Whether requests may filter by this column. Columns which are too large to compare
+    /// without an index, such as large text or bytea columns, can be marked as not filterable.
+    #[serde(skip_serializing_if = "is_true")]
+    #[serde(default = "default_true")]
+    pub filterable: bool,
+    /// Whether requests may order by this column.
+    #[serde(skip_serializing_if = "is_true")]
+    #[serde(default = "default_true")]
+    pub sortable: bool,
     #[serde(default)]
     pub description: Option<String>,
 }
@@ -185,11 +442,27 @@ fn is_not_generated(is_generated: &IsGenerated) -> bool {
     matches!(is_generated, IsGenerated::NotGenerated)
 }
 
+#[allow(clippy::trivially_copy_pass_by_ref)] // serde requires a reference here
+fn is_true(value: &bool) -> bool {
+    *value
+}
+
+#[allow(clippy::trivially_copy_pass_by_ref)] // serde requires a reference here
+fn is_false(value: &bool) -> bool {
+    !*value
+}
+
 /// A mapping from the name of a unique constraint to its value.
 #[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct UniquenessConstraints(pub BTreeMap<String, UniquenessConstraint>);
 
+impl UniquenessConstraints {
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
 /// The set of columns that make up a uniqueness constraint.
 /// We map each table column to their ndc field names.
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
@@ -201,6 +474,12 @@ pub struct UniquenessConstraint(pub BTreeSet<models::FieldName>);
 #[serde(rename_all = "camelCase")]
 pub struct ForeignRelations(pub BTreeMap<String, ForeignRelation>);
 
+impl ForeignRelations {
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
 /// A foreign key constraint.
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
@@ -263,6 +542,10 @@ pub enum TypeRepresentation {
     Geography,
     /// geometry
     Geometry,
+    /// hstore, as a JSON object of strings
+    Hstore,
+    /// bytea, as a base64 encoded string
+    Bytes,
     /// Any JSON number
     Number,
     /// Any JSON number, with no decimal part
@@ -279,7 +562,7 @@ pub enum TypeRepresentation {
 mod tests {
     use std::collections::BTreeMap;
 
-    use super::TypeRepresentation;
+    use super::{TableInfo, TypeRepresentation};
 
     #[test]
     fn parse_type_representations() {
@@ -305,4 +588,24 @@ mod tests {
 
         );
     }
+
+    #[test]
+    fn tables_are_writable_unless_introspected_otherwise() {
+        let table: TableInfo =
+            serde_json::from_str(r#"{"schemaName": "public", "tableName": "t", "columns": {}}"#)
+                .unwrap();
+        assert!(table.insertable && table.updatable && table.deletable);
+        assert_eq!(table.mutable, None);
+
+        let view: TableInfo = serde_json::from_str(
+            r#"{"schemaName": "public", "tableName": "v", "columns": {}, "insertable": false, "updatable": false, "deletable": false, "mutable": true}"#,
+        )
+        .unwrap();
+        assert!(!view.insertable && !view.updatable && !view.deletable);
+        assert_eq!(view.mutable, Some(true));
+        assert_eq!(
+            serde_json::to_value(&view).unwrap()["insertable"],
+            serde_json::json!(false)
+        );
+    }
 }
@@ -11,6 +11,8 @@ pub use native_operations::*;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
+use super::options::NamingConvention;
+
 /// Metadata information.
 #[derive(Clone, PartialEq, Eq, Debug, Default, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
@@ -21,4 +23,12 @@ pub struct Metadata {
     pub types: Types,
     #[serde(default)]
     pub native_operations: NativeOperations,
+    /// The sequences of the database, by the name their procedures are given.
+    #[serde(default, skip_serializing_if = "Sequences::is_empty")]
+    pub sequences: Sequences,
+    /// The naming convention the names of the tables and their columns were given with when the
+    /// metadata was last updated, so that the names it gave can be told apart from customized
+    /// ones once it changes.
+    #[serde(default, skip_serializing_if = "NamingConvention::is_default")]
+    pub naming_convention: NamingConvention,
 }
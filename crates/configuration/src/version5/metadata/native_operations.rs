@@ -49,6 +49,17 @@ pub struct NativeQueryInfo {
     pub arguments: BTreeMap<models::ArgumentName, ReadOnlyColumnInfo>,
     #[serde(default)]
     pub description: Option<String>,
+    /// Foreign keys from the columns of a Native Query to tables, which the schema reports so
+    /// that relationships can be defined from the Native Query. `foreignSchema` defaults to
+    /// `public`.
+    #[serde(default, skip_serializing_if = "ForeignRelations::is_empty")]
+    pub foreign_relations: ForeignRelations,
+    /// The isolation level a Native Mutation needs, if it is stricter than
+    /// `connectionSettings.isolationLevel`, such as for one which reads rows and writes them
+    /// back. A mutation request runs at the strictest isolation level of its Native Mutations
+    /// and the configured one. Native Queries cannot have one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub isolation_level: Option<crate::values::IsolationLevel>,
 }
 
 /// Information about a native query column.
@@ -1,15 +1,20 @@
 //! Internal Configuration and state for our connector.
 
 mod comparison;
+pub mod computed_relationships;
 pub mod connection_settings;
+mod distribution;
+pub mod json_tables;
 pub mod metadata;
 pub mod native_operations;
 mod options;
-mod to_runtime_configuration;
+mod split_metadata;
+mod targets;
+pub(crate) mod to_runtime_configuration;
 mod upgrade_from_v4;
 
-use ndc_models::{CollectionName, TypeName};
-use std::collections::{BTreeMap, HashSet};
+use ndc_models::{CollectionName, FieldName, TypeName};
+use std::collections::{BTreeMap, BTreeSet, HashSet};
 use std::path::Path;
 pub use to_runtime_configuration::make_runtime_configuration;
 pub use upgrade_from_v4::upgrade_from_v4;
@@ -27,6 +32,7 @@ use crate::environment::Environment;
 use crate::error::{ParseConfigurationError, WriteParsedConfigurationError};
 
 const CONFIGURATION_FILENAME: &str = "configuration.json";
+const CONFIGURATION_YAML_FILENAME: &str = "configuration.yaml";
 const CONFIGURATION_JSONSCHEMA_FILENAME: &str = "schema.json";
 const CONFIGURATION_QUERY: &str = include_str!("introspection.sql");
 
@@ -55,6 +61,148 @@ pub struct ParsedConfiguration {
     /// Provide a custom prefix for generated mutation names. Defaults to mutations version.
     #[serde(default)]
     pub mutations_prefix: Option<String>,
+    /// Whether the responses of procedures have a field for each aggregate function of each
+    /// column of the rows they return, such as `balance_sum`. This makes the schema much larger,
+    /// so it is off unless enabled.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub mutations_returning_aggregates: bool,
+    /// The database the connector generates SQL for.
+    #[serde(default)]
+    pub dialect: Dialect,
+    /// Whether to select relationships using `LATERAL` joins. Some query planners handle these
+    /// poorly, in which case relationships can be selected using correlated subqueries instead.
+    #[serde(default = "default_true")]
+    pub use_lateral_joins: bool,
+    /// Whether the limit of a query which has an ordering also returns the rows which tie with
+    /// the last one (`FETCH FIRST n ROWS WITH TIES`), so that pages never split groups of rows
+    /// with equal sort keys. Ignored by databases which do not support it.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub limit_with_ties: bool,
+    /// Whether comparisons of boolean columns with `true` or `false` are kept as `= true` and
+    /// `= false`, rather than shortened to the column itself or its negation.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub strict_boolean_comparisons: bool,
+    /// Whether table collections accept a `snapshot` argument, which runs a query against the
+    /// data as it was at an earlier point: a time for `AS OF SYSTEM TIME` on CockroachDB, or the
+    /// identifier of a snapshot exported by `pg_export_snapshot()` on PostgreSQL.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub snapshot_reads: bool,
+    /// Whether table collections accept a `distinct_on` argument, which returns only the first
+    /// row of each group of rows with equal values in the given columns.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub distinct_on: bool,
+    /// Whether foreach queries with more than 10,000 variable sets copy them into a
+    /// session-temporary table with `COPY`, rather than passing them in a single parameter.
+    /// Temporary tables cannot be created on hot standby servers, and do not survive connection
+    /// poolers such as PgBouncer in transaction mode, so this is off unless enabled.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub temporary_variables_table: bool,
+    /// Override how values of specific types are represented in responses.
+    #[serde(default)]
+    pub type_overrides: Vec<TypeOverride>,
+    /// Hints for the query planner, such as `IndexScan({{table}} idx_name)`, to attach to queries
+    /// over specific collections. `{{table}}` stands for the alias the collection is given in the
+    /// query. These are only sent when the `pg_hint_plan` extension is loaded.
+    #[serde(default)]
+    pub planner_hints: BTreeMap<CollectionName, String>,
+    /// Restrictions on the rows queries may see of specific collections, such as a predicate
+    /// every row must satisfy and a maximum number of rows, applied whatever the request asks for.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub collection_policies: BTreeMap<CollectionName, crate::values::CollectionPolicy>,
+    /// How queries over tables which Citus distributes across nodes are planned: `off`, the
+    /// default, `warn`, which logs queries which cannot be routed to a single shard, or
+    /// `pushDown`, which also joins related distributed tables on their distribution columns.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub distributed_query_routing: Option<crate::values::DistributedQueryRouting>,
+    /// Answer `star_count` aggregates over whole tables which PostgreSQL estimates to have at
+    /// least `minRows` rows with that estimate, rather than counting them.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub count_estimation: Option<crate::values::CountEstimation>,
+    /// Whether every query is first planned with `EXPLAIN`, and only run if the database accepts
+    /// it, so that queries it would reject are reported without running anything. This costs a
+    /// round trip to the database for each query, so it is meant for testing changes to the
+    /// configuration rather than for production.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub verify_queries: bool,
+    /// Collections whose rows are found in the JSON documents of a column of a table, flattened
+    /// into columns using `JSON_TABLE`, which requires PostgreSQL 17 or later.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub json_tables: BTreeMap<CollectionName, json_tables::JsonTable>,
+    /// Whether to append the trace context of each request to the SQL statements it runs, in a
+    /// comment, so that statements in the database logs can be matched up with their traces.
+    #[serde(default)]
+    pub annotate_queries: bool,
+    /// Log the SQL of queries which take longer than this to run against the database
+    /// (milliseconds).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub slow_query_threshold_ms: Option<u64>,
+    /// Whether the SQL logged for slow queries includes the values of its parameters, which may
+    /// be sensitive. They are redacted otherwise.
+    #[serde(default)]
+    pub log_slow_query_parameters: bool,
+    /// Cancel queries and mutations which run for longer than this against the database, or
+    /// whose client disconnects before they finish (milliseconds).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub query_timeout_ms: Option<u64>,
+    /// Reject queries which would generate overly large SQL, such as deeply nested relationships.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub query_limits: Option<crate::values::QueryLimits>,
+    /// Retry requests which fail for transient reasons, such as serialization failures.
+    /// Defaults to three attempts, with a jittered exponential backoff.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retry_policy: Option<crate::values::RetryPolicy>,
+    /// What happens to the other operations of a mutation request when one of them fails:
+    /// `allOrNothing`, the default, or `bestEffort`, which rolls back only the operation which
+    /// failed using a savepoint.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mutation_failure_policy: Option<crate::values::MutationFailurePolicy>,
+    /// Count the queries, mutations and rows returned of each collection, and how long they
+    /// take, over a sliding window, and export them as metrics.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub usage_statistics: Option<crate::values::UsageStatisticsSettings>,
+    /// Record each query request, the SQL it was translated to, and how long it took, to a
+    /// directory, so that the requests can be replayed after the translation changes.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub request_recording: Option<crate::values::RequestRecordingSettings>,
+    /// How to name the relationships derived from foreign keys, for tools which generate them.
+    /// Defaults to camelCase names after the collection the relationship is to, in the plural for
+    /// array relationships.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub relationship_naming: Option<crate::values::RelationshipNaming>,
+    /// Whether the connector watches the SQL files Native Operations are read from, and applies
+    /// changes to them without restarting. Meant for development; leave this off in production.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub watch_native_query_files: bool,
+    /// Whether to write tables to `tables/<schema>.json`, and native queries and mutations to
+    /// `native_queries/<name>.json` and `native_mutations/<name>.json`, rather than inline in
+    /// this file.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub split_metadata: bool,
+    /// The format of the file the configuration was read from, which it is written back in.
+    #[serde(skip)]
+    pub format: ConfigurationFormat,
+}
+
+/// The format of the configuration file: `configuration.json` or `configuration.yaml`.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum ConfigurationFormat {
+    #[default]
+    Json,
+    Yaml,
+}
+
+impl ConfigurationFormat {
+    /// The name of the configuration file in this format.
+    pub fn file_name(self) -> &'static str {
+        match self {
+            ConfigurationFormat::Json => CONFIGURATION_FILENAME,
+            ConfigurationFormat::Yaml => CONFIGURATION_YAML_FILENAME,
+        }
+    }
+}
+
+fn default_true() -> bool {
+    true
 }
 
 #[derive(Clone, PartialEq, Eq, Debug, Deserialize, Serialize, JsonSchema)]
@@ -63,6 +211,37 @@ pub enum Version {
     This,
 }
 
+/// The database the connector generates SQL for. Databases which are compatible with PostgreSQL
+/// differ in some details, such as whether they support table inheritance. Databases which run
+/// PostgreSQL itself underneath, such as AlloyDB, use `postgres`.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, Deserialize, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub enum Dialect {
+    #[default]
+    Postgres,
+    #[serde(rename = "cockroachDB")]
+    CockroachDB,
+    #[serde(rename = "yugabyteDB")]
+    YugabyteDB,
+    Citus,
+}
+
+/// Override the type representation of a scalar type, and how its values are converted to it.
+/// This is useful for types introduced by extensions, such as `citext` or `ltree`.
+#[derive(Clone, PartialEq, Eq, Debug, Deserialize, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct TypeOverride {
+    /// The schema the type is defined in.
+    pub schema_name: String,
+    /// The name of the type.
+    pub type_name: String,
+    /// The type representation to report in the schema.
+    pub type_representation: database::TypeRepresentation,
+    /// The name of a built-in type, such as `text`, to cast values to when selecting them.
+    #[serde(default)]
+    pub cast_as: Option<String>,
+}
+
 impl ParsedConfiguration {
     pub fn empty() -> Self {
         Self {
@@ -73,12 +252,43 @@ impl ParsedConfiguration {
             introspection_options: options::IntrospectionOptions::default(),
             mutations_version: Some(metadata::mutations::MutationsVersion::V2),
             mutations_prefix: Some(String::new()),
+            mutations_returning_aggregates: false,
+            dialect: Dialect::default(),
+            use_lateral_joins: true,
+            limit_with_ties: false,
+            strict_boolean_comparisons: false,
+            snapshot_reads: false,
+            distinct_on: false,
+            temporary_variables_table: false,
+            type_overrides: vec![],
+            planner_hints: BTreeMap::new(),
+            collection_policies: BTreeMap::new(),
+            distributed_query_routing: None,
+            count_estimation: None,
+            verify_queries: false,
+            json_tables: BTreeMap::new(),
+            annotate_queries: false,
+            slow_query_threshold_ms: None,
+            log_slow_query_parameters: false,
+            query_timeout_ms: None,
+            query_limits: None,
+            retry_policy: None,
+            mutation_failure_policy: None,
+            usage_statistics: None,
+            request_recording: None,
+            relationship_naming: None,
+            watch_native_query_files: false,
+            split_metadata: false,
+            format: ConfigurationFormat::default(),
         }
     }
 
     /// Extract the connection uri from the configuration + ENV if needed.
     pub fn get_connection_uri(&self) -> Result<String, anyhow::Error> {
-        let connection_uri = self.connection_settings.connection_uri.clone();
+        let connection_uri = self
+            .connection_settings
+            .connection_uri
+            .to_connection_uri(crate::environment::ProcessEnvironment)?;
 
         match connection_uri.0 {
             super::values::Secret::Plain(connection_string) => Ok(connection_string),
@@ -134,22 +344,154 @@ fn native_operations_field_types(native_operations: &metadata::NativeOperations)
 pub async fn introspect(
     args: ParsedConfiguration,
     environment: impl Environment,
+    session: &crate::SessionSettings,
 ) -> anyhow::Result<ParsedConfiguration> {
-    let connect_options =
-        crate::get_connect_options(&args.connection_settings.connection_uri, environment)?;
+    let (tables, mut scalar_types, mut composite_types, sequences) = introspect_database(
+        &args.connection_settings.connection_uri,
+        &args,
+        &environment,
+        session,
+    )
+    .await?;
+    let mut tables: Vec<(CollectionName, metadata::TableInfo)> = tables.0.into_iter().collect();
+
+    for (target, settings) in &args.connection_settings.targets {
+        // sequences are only exposed for the primary database.
+        let (target_tables, target_scalar_types, target_composite_types, _) =
+            introspect_database(&settings.connection_uri, &args, &environment, session)
+                .instrument(info_span!(
+                    "Introspect target",
+                    connection_target = target.as_str()
+                ))
+                .await?;
+        tables.extend(
+            target_tables
+                .0
+                .into_iter()
+                .map(|(collection_name, table_info)| {
+                    (
+                        collection_name,
+                        metadata::TableInfo {
+                            target: Some(target.clone()),
+                            ..table_info
+                        },
+                    )
+                }),
+        );
+        targets::merge_types(
+            target,
+            &mut scalar_types,
+            &mut composite_types,
+            target_scalar_types,
+            target_composite_types,
+        )?;
+    }
+
+    // build a list of names to ensure they are unique. We assume scalar type names + composite types are already a unique set.
+    let mut type_names: HashSet<TypeName> = scalar_types
+        .0
+        .keys()
+        .map(|t| t.clone().into_inner())
+        .collect();
+
+    type_names.extend(composite_types.0.keys().cloned());
+
+    let tables = get_aliased_tables(
+        type_names,
+        tables,
+        &args.metadata.tables,
+        &args.metadata.naming_convention,
+        &args.introspection_options.naming_convention,
+    );
+
+    Ok(ParsedConfiguration {
+        version: Version::This,
+        schema: args.schema,
+        connection_settings: args.connection_settings,
+        metadata: metadata::Metadata {
+            tables,
+            types: metadata::Types {
+                scalar: scalar_types,
+                composite: composite_types,
+            },
+            native_operations: args.metadata.native_operations,
+            sequences: if args.introspection_options.introspect_sequences {
+                sequences
+            } else {
+                metadata::Sequences::default()
+            },
+            naming_convention: args.introspection_options.naming_convention.clone(),
+        },
+        introspection_options: args.introspection_options,
+        mutations_version: args.mutations_version,
+        mutations_prefix: args.mutations_prefix,
+        mutations_returning_aggregates: args.mutations_returning_aggregates,
+        dialect: args.dialect,
+        use_lateral_joins: args.use_lateral_joins,
+        limit_with_ties: args.limit_with_ties,
+        strict_boolean_comparisons: args.strict_boolean_comparisons,
+        snapshot_reads: args.snapshot_reads,
+        distinct_on: args.distinct_on,
+        temporary_variables_table: args.temporary_variables_table,
+        type_overrides: args.type_overrides,
+        planner_hints: args.planner_hints,
+        collection_policies: args.collection_policies,
+        distributed_query_routing: args.distributed_query_routing,
+        count_estimation: args.count_estimation,
+        verify_queries: args.verify_queries,
+        json_tables: args.json_tables,
+        annotate_queries: args.annotate_queries,
+        slow_query_threshold_ms: args.slow_query_threshold_ms,
+        log_slow_query_parameters: args.log_slow_query_parameters,
+        query_timeout_ms: args.query_timeout_ms,
+        query_limits: args.query_limits,
+        retry_policy: args.retry_policy,
+        mutation_failure_policy: args.mutation_failure_policy,
+        usage_statistics: args.usage_statistics,
+        request_recording: args.request_recording,
+        relationship_naming: args.relationship_naming,
+        watch_native_query_files: args.watch_native_query_files,
+        split_metadata: args.split_metadata,
+        format: args.format,
+    })
+}
+
+/// Run the introspection query against one database.
+async fn introspect_database(
+    connection_uri: &connection_settings::DatabaseConnectionUri,
+    args: &ParsedConfiguration,
+    environment: impl Environment,
+    session: &crate::SessionSettings,
+) -> anyhow::Result<(
+    metadata::TablesInfo,
+    metadata::ScalarTypes,
+    metadata::CompositeTypes,
+    metadata::Sequences,
+)> {
+    let connect_options = session.apply(crate::get_connect_options(
+        &connection_uri.to_connection_uri(&environment)?,
+        &environment,
+    )?);
 
     let mut connection = PgConnection::connect_with(&connect_options)
         .instrument(info_span!("Connect to database"))
         .await?;
 
     let query = sqlx::query(CONFIGURATION_QUERY)
-        .bind(&args.introspection_options.excluded_schemas)
-        .bind(&args.introspection_options.unqualified_schemas_for_tables)
-        .bind(
+        .bind(options::resolve_schemas(
+            &args.introspection_options.excluded_schemas,
+            &environment,
+        )?)
+        .bind(options::resolve_schemas(
+            &args.introspection_options.unqualified_schemas_for_tables,
+            &environment,
+        )?)
+        .bind(options::resolve_schemas(
             &args
                 .introspection_options
                 .unqualified_schemas_for_types_and_procedures,
-        )
+            &environment,
+        )?)
         .bind(serde_json::to_value(
             &args.introspection_options.comparison_operator_mapping,
         )?)
@@ -163,111 +505,284 @@ pub async fn introspect(
         )?)
         .bind(native_operations_field_types(
             &args.metadata.native_operations,
-        ));
+        ))
+        .bind(args.introspection_options.inherited_tables == options::InheritedTables::Separate);
 
     let row = connection
         .fetch_one(query)
         .instrument(info_span!("Run introspection query"))
         .await?;
 
-    let (tables, scalar_types, composite_types) = async {
+    let (mut tables, scalar_types, composite_types, sequences) = async {
         let tables: metadata::TablesInfo = serde_json::from_value(row.get(0))?;
         let scalar_types: metadata::ScalarTypes = serde_json::from_value(row.get(1))?;
         let composite_types: metadata::CompositeTypes = serde_json::from_value(row.get(2))?;
+        let sequences: metadata::Sequences = serde_json::from_value(row.get(3))?;
+        tracing::info!(
+            tables = tables.0.len(),
+            scalar_types = scalar_types.0.len(),
+            composite_types = composite_types.0.len(),
+            sequences = sequences.0.len(),
+            "Decoded the introspection result"
+        );
 
         // We need to specify the concrete return type explicitly so that rustc knows that it can
         // be sent across an async boundary.
         // (last verified with rustc 1.72.1)
-        Ok::<_, anyhow::Error>((tables, scalar_types, composite_types))
+        Ok::<_, anyhow::Error>((tables, scalar_types, composite_types, sequences))
     }
     .instrument(info_span!("Decode introspection result"))
     .await?;
 
-    // build a list of names to ensure they are unique. We assume scalar type names + composite types are already a unique set.
-    let mut type_names: HashSet<TypeName> = scalar_types
-        .0
-        .keys()
-        .map(|t| t.clone().into_inner())
-        .collect();
-
-    type_names.extend(composite_types.0.keys().cloned());
-
-    let tables = get_aliased_tables(type_names, tables, &args.metadata.tables);
+    distribution::introspect(&mut connection, &mut tables)
+        .instrument(info_span!("Introspect table distribution"))
+        .await?;
 
-    Ok(ParsedConfiguration {
-        version: Version::This,
-        schema: args.schema,
-        connection_settings: args.connection_settings,
-        metadata: metadata::Metadata {
-            tables,
-            types: metadata::Types {
-                scalar: scalar_types,
-                composite: composite_types,
-            },
-            native_operations: args.metadata.native_operations,
-        },
-        introspection_options: args.introspection_options,
-        mutations_version: args.mutations_version,
-        mutations_prefix: args.mutations_prefix,
-    })
+    Ok((tables, scalar_types, composite_types, sequences))
 }
 
 /// given scalar type names already in use, introspected tables, and optionally any existing table configuration:
-/// get collections with names guaranteed unique, preserving customized collection and field names if any
+/// get collections with names guaranteed unique, preserving customized collection and field names if any,
+/// and naming the rest following the naming convention. Names which are those introspection or the
+/// previous naming convention gave are not customized, so a change of convention renames them.
 fn get_aliased_tables(
     type_names: HashSet<TypeName>,
-    tables: metadata::TablesInfo,
+    tables: Vec<(CollectionName, metadata::TableInfo)>,
     old_tables: &metadata::TablesInfo,
+    old_naming_convention: &options::NamingConvention,
+    naming_convention: &options::NamingConvention,
 ) -> metadata::TablesInfo {
     let mut type_names = type_names;
     let mut mapped_tables = BTreeMap::new();
+    // the field names the columns of each table were given, by the names introspection gave them
+    let mut renamed_fields_by_table = BTreeMap::new();
 
-    for (collection_name, table_info) in tables.0 {
+    for (introspected_collection_name, table_info) in tables {
         let old_config = old_tables.0.iter().find(|(_, old_table_info)| {
             old_table_info.table_name == table_info.table_name
                 && old_table_info.schema_name == table_info.schema_name
+                && old_table_info.target == table_info.target
         });
 
-        // use the old collection alias if one exists
-        let collection_name = old_config
-            .map_or(&collection_name, |(collection_name, _)| collection_name)
-            .to_owned();
+        // use the old collection alias if it was customized; a name which is the one
+        // introspection or the previous convention gives is named following the convention
+        let collection_name = match old_config {
+            Some((old_collection_name, _))
+                if *old_collection_name != introspected_collection_name
+                    && old_collection_name.as_str()
+                        != old_naming_convention
+                            .collection_name(introspected_collection_name.as_str()) =>
+            {
+                old_collection_name.clone()
+            }
+            _ => naming_convention
+                .collection_name(introspected_collection_name.as_str())
+                .into(),
+        };
 
         // add a suffix to the collection name if needed
         let collection_name = get_unique_collection_name(collection_name, &type_names);
 
         type_names.insert(collection_name.clone().into_inner().into());
 
-        // if a column has a customized field name, keep it
+        // if a column has a customized field name, keep it, along with whether it may be
+        // filtered or ordered by, which is not introspected
+        let introspected_field_names: BTreeSet<FieldName> =
+            table_info.columns.keys().cloned().collect();
+        let mut renamed_fields = BTreeMap::new();
+        let mut columns = BTreeMap::new();
+        for (field_name, column_info) in table_info.columns {
+            let old_column = old_config.and_then(|(_, table_info)| {
+                table_info
+                    .columns
+                    .iter()
+                    .find(|(_, old_column_info)| old_column_info.name == column_info.name)
+            });
+            let customized_field_name = match old_column {
+                Some((old_field_name, _))
+                    if *old_field_name != field_name
+                        && old_field_name.as_str()
+                            != old_naming_convention.field_name(
+                                introspected_collection_name.as_str(),
+                                field_name.as_str(),
+                            ) =>
+                {
+                    old_field_name.clone()
+                }
+                _ => {
+                    let conventional_field_name: FieldName = naming_convention
+                        .field_name(introspected_collection_name.as_str(), field_name.as_str())
+                        .into();
+                    // columns whose names only differ by a prefix or their case keep theirs
+                    if columns.contains_key(&conventional_field_name)
+                        || (conventional_field_name != field_name
+                            && introspected_field_names.contains(&conventional_field_name))
+                    {
+                        field_name.clone()
+                    } else {
+                        conventional_field_name
+                    }
+                }
+            };
+
+            let column_info = match old_column {
+                None => column_info,
+                Some((_, old_column_info)) => metadata::ColumnInfo {
+                    filterable: old_column_info.filterable,
+                    sortable: old_column_info.sortable,
+                    generates_uuid: old_column_info.generates_uuid,
+                    selectable: old_column_info.selectable,
+                    insertable: old_column_info.insertable,
+                    updatable: old_column_info.updatable,
+                    ..column_info
+                },
+            };
+            renamed_fields.insert(field_name, customized_field_name.clone());
+            columns.insert(customized_field_name, column_info);
+        }
+
         let table_info = metadata::TableInfo {
-            columns: table_info
-                .columns
-                .into_iter()
-                .map(|(field_name, column_info)| {
-                    let field_name = old_config
-                        .and_then(|(_, table_info)| {
-                            table_info
-                                .columns
-                                .iter()
-                                .find(|(_, old_column_info)| {
-                                    old_column_info.name == column_info.name
-                                })
-                                .map(|(field_name, _)| field_name.to_owned())
-                        })
-                        .unwrap_or(field_name);
-
-                    (field_name, column_info)
-                })
-                .collect(),
+            columns,
+            // the uniqueness constraints refer to the fields of the columns, which may have
+            // been renamed
+            uniqueness_constraints: metadata::UniquenessConstraints(
+                table_info
+                    .uniqueness_constraints
+                    .0
+                    .into_iter()
+                    .map(
+                        |(constraint_name, metadata::UniquenessConstraint(fields))| {
+                            let fields = fields
+                                .into_iter()
+                                .map(|field_name| rename_field(&renamed_fields, field_name))
+                                .collect();
+                            (constraint_name, metadata::UniquenessConstraint(fields))
+                        },
+                    )
+                    .collect(),
+            ),
+            // keep the statement timeout, the logical keys, the soft deletion, the audit columns
+            // and the computed relationships of the table, which are not introspected
+            statement_timeout_ms: old_config
+                .and_then(|(_, table_info)| table_info.statement_timeout_ms),
+            logical_keys: old_config
+                .map(|(_, table_info)| table_info.logical_keys.clone())
+                .unwrap_or_default(),
+            // the expiry of rows is only introspected for CockroachDB, so keep one configured by
+            // hand otherwise
+            row_expiry: table_info
+                .row_expiry
+                .or_else(|| old_config.and_then(|(_, table_info)| table_info.row_expiry.clone())),
+            soft_delete: old_config.and_then(|(_, table_info)| table_info.soft_delete.clone()),
+            audit_columns: old_config
+                .map(|(_, table_info)| table_info.audit_columns.clone())
+                .unwrap_or_default(),
+            computed_relationships: old_config
+                .map(|(_, table_info)| table_info.computed_relationships.clone())
+                .unwrap_or_default(),
+            computed_fields: merge_computed_fields(
+                table_info.computed_fields,
+                old_config.map(|(_, table_info)| &table_info.computed_fields),
+            ),
+            // whether tables may be written to is introspected, but it can be overridden by hand
+            mutable: old_config.and_then(|(_, table_info)| table_info.mutable),
             ..table_info
         };
 
+        renamed_fields_by_table.insert(
+            (
+                table_info.target.clone(),
+                table_info.schema_name.clone(),
+                table_info.table_name.clone(),
+            ),
+            renamed_fields,
+        );
         mapped_tables.insert(collection_name, table_info);
     }
 
+    // the foreign keys refer to the fields of the columns of both tables, which may have been
+    // renamed
+    let no_renamed_fields = BTreeMap::new();
+    for table_info in mapped_tables.values_mut() {
+        let renamed_fields = renamed_fields_by_table
+            .get(&(
+                table_info.target.clone(),
+                table_info.schema_name.clone(),
+                table_info.table_name.clone(),
+            ))
+            .unwrap_or(&no_renamed_fields);
+        for foreign_relation in table_info.foreign_relations.0.values_mut() {
+            let foreign_renamed_fields = renamed_fields_by_table
+                .get(&(
+                    table_info.target.clone(),
+                    foreign_relation
+                        .foreign_schema
+                        .clone()
+                        .unwrap_or_else(|| table_info.schema_name.clone()),
+                    foreign_relation.foreign_table.clone(),
+                ))
+                .unwrap_or(&no_renamed_fields);
+            foreign_relation.column_mapping = std::mem::take(&mut foreign_relation.column_mapping)
+                .into_iter()
+                .map(|(field_name, foreign_field_name)| {
+                    (
+                        rename_field(renamed_fields, field_name),
+                        rename_field(foreign_renamed_fields, foreign_field_name),
+                    )
+                })
+                .collect();
+        }
+    }
+
     metadata::TablesInfo(mapped_tables)
 }
 
+/// Functions of the rows of a table are introspected as computed fields, but those configured
+/// before take precedence, so that they keep the names they were given. A computed field calling
+/// a function which is no longer introspected is dropped, as the function is gone, or now
+/// volatile; one computing an expression is kept, as it is only ever configured by hand.
+fn merge_computed_fields(
+    introspected: BTreeMap<FieldName, metadata::ComputedField>,
+    old: Option<&BTreeMap<FieldName, metadata::ComputedField>>,
+) -> BTreeMap<FieldName, metadata::ComputedField> {
+    let function_of = |computed_field: &metadata::ComputedField| match &computed_field.definition {
+        metadata::ComputedFieldDefinition::Function(function) => Some(function.clone()),
+        metadata::ComputedFieldDefinition::Expression(_) => None,
+    };
+    let introspected_functions: Vec<metadata::FunctionReference> =
+        introspected.values().filter_map(function_of).collect();
+    let kept: BTreeMap<FieldName, metadata::ComputedField> = old
+        .into_iter()
+        .flatten()
+        .filter(|(_, computed_field)| {
+            function_of(computed_field)
+                .map_or(true, |function| introspected_functions.contains(&function))
+        })
+        .map(|(field_name, computed_field)| (field_name.clone(), computed_field.clone()))
+        .collect();
+    let kept_functions: Vec<metadata::FunctionReference> =
+        kept.values().filter_map(function_of).collect();
+    let mut computed_fields = kept;
+    for (field_name, computed_field) in introspected {
+        if function_of(&computed_field).is_some_and(|function| kept_functions.contains(&function)) {
+            continue;
+        }
+        computed_fields.entry(field_name).or_insert(computed_field);
+    }
+    computed_fields
+}
+
+/// The field name a column was given, by the name introspection gave it.
+fn rename_field(
+    renamed_fields: &BTreeMap<FieldName, FieldName>,
+    field_name: FieldName,
+) -> FieldName {
+    renamed_fields
+        .get(&field_name)
+        .cloned()
+        .unwrap_or(field_name)
+}
+
 /// given a collection name and a list of already used type names, get a unique name by adding a suffix if needed
 fn get_unique_collection_name(
     collection_name: CollectionName,
@@ -292,10 +807,21 @@ fn get_unique_collection_name(
 }
 
 /// Parse the configuration format from a directory.
+///
+/// The configuration is read from `configuration.json`, or from `configuration.yaml` if there is
+/// no `configuration.json`.
 pub async fn parse_configuration(
     configuration_dir: impl AsRef<Path>,
 ) -> Result<ParsedConfiguration, ParseConfigurationError> {
-    let configuration_file = configuration_dir.as_ref().join(CONFIGURATION_FILENAME);
+    let json_configuration_file = configuration_dir.as_ref().join(CONFIGURATION_FILENAME);
+    let yaml_configuration_file = configuration_dir.as_ref().join(CONFIGURATION_YAML_FILENAME);
+    let (configuration_file, format) = if !fs::try_exists(&json_configuration_file).await?
+        && fs::try_exists(&yaml_configuration_file).await?
+    {
+        (yaml_configuration_file, ConfigurationFormat::Yaml)
+    } else {
+        (json_configuration_file, ConfigurationFormat::Json)
+    };
 
     let configuration_file_contents =
         fs::read_to_string(&configuration_file)
@@ -308,13 +834,28 @@ pub async fn parse_configuration(
                 ))
             })?;
 
-    let mut parsed_config: ParsedConfiguration = serde_json::from_str(&configuration_file_contents)
-        .map_err(|error| ParseConfigurationError::ParseError {
-            file_path: configuration_file.clone(),
-            line: error.line(),
-            column: error.column(),
-            message: error.to_string(),
-        })?;
+    let mut parsed_config: ParsedConfiguration =
+        match format {
+            ConfigurationFormat::Json => serde_json::from_str(&configuration_file_contents)
+                .map_err(|error| ParseConfigurationError::ParseError {
+                    file_path: configuration_file.clone(),
+                    line: error.line(),
+                    column: error.column(),
+                    message: error.to_string(),
+                })?,
+            ConfigurationFormat::Yaml => serde_yaml::from_str(&configuration_file_contents)
+                .map_err(|error| ParseConfigurationError::ParseError {
+                    file_path: configuration_file.clone(),
+                    line: error.location().map_or(0, |location| location.line()),
+                    column: error.location().map_or(0, |location| location.column()),
+                    message: error.to_string(),
+                })?,
+        };
+    parsed_config.format = format;
+
+    if parsed_config.split_metadata {
+        split_metadata::read(configuration_dir.as_ref(), &mut parsed_config).await?;
+    }
 
     // look for native query sql file references and read from disk.
     for native_query_sql in parsed_config
@@ -354,17 +895,37 @@ pub async fn write_parsed_configuration(
     parsed_config: ParsedConfiguration,
     out_dir: impl AsRef<Path>,
 ) -> Result<(), WriteParsedConfigurationError> {
-    let configuration_file = out_dir.as_ref().to_owned().join(CONFIGURATION_FILENAME);
+    let (configuration_filename, other_configuration_filename) = match parsed_config.format {
+        ConfigurationFormat::Json => (CONFIGURATION_FILENAME, CONFIGURATION_YAML_FILENAME),
+        ConfigurationFormat::Yaml => (CONFIGURATION_YAML_FILENAME, CONFIGURATION_FILENAME),
+    };
+    let configuration_file = out_dir.as_ref().to_owned().join(configuration_filename);
     fs::create_dir_all(out_dir.as_ref()).await?;
 
+    // remove the configuration file in the other format, so that it is not read instead.
+    let other_configuration_file = out_dir.as_ref().join(other_configuration_filename);
+    if fs::try_exists(&other_configuration_file).await? {
+        fs::remove_file(other_configuration_file).await?;
+    }
+
+    // write the tables and native operations to their own files if requested, and leave them
+    // out of the configuration file.
+    let mut configuration_file_contents = parsed_config.clone();
+    if parsed_config.split_metadata {
+        split_metadata::write(&mut configuration_file_contents, out_dir.as_ref()).await?;
+    }
+
     // create the configuration file
-    fs::write(
-        configuration_file,
-        serde_json::to_string_pretty(&parsed_config)
-            .map_err(|e| WriteParsedConfigurationError::IoError(e.into()))?
-            + "\n",
-    )
-    .await?;
+    let serialized = match parsed_config.format {
+        ConfigurationFormat::Json => {
+            serde_json::to_string_pretty(&configuration_file_contents)
+                .map_err(|e| WriteParsedConfigurationError::IoError(e.into()))?
+                + "\n"
+        }
+        ConfigurationFormat::Yaml => serde_yaml::to_string(&configuration_file_contents)
+            .map_err(|e| WriteParsedConfigurationError::IoError(std::io::Error::other(e)))?,
+    };
+    fs::write(configuration_file, serialized).await?;
 
     // look for native query sql file references and write them to disk.
     for native_query_sql in parsed_config.metadata.native_operations.queries.0.values() {
@@ -433,3 +994,220 @@ pub async fn write_parsed_configuration(
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{BTreeMap, BTreeSet, HashSet};
+
+    use super::*;
+
+    fn table(
+        table_name: &str,
+        columns: &[(&str, &str)],
+        uniqueness_constraints: &[(&str, &[&str])],
+    ) -> metadata::TableInfo {
+        serde_json::from_value(serde_json::json!({
+            "schemaName": "public",
+            "tableName": table_name,
+            "columns": columns
+                .iter()
+                .map(|(field_name, column_name)| {
+                    (
+                        (*field_name).to_string(),
+                        serde_json::json!({
+                            "name": column_name,
+                            "type": { "scalarType": "int4" },
+                        }),
+                    )
+                })
+                .collect::<serde_json::Map<_, _>>(),
+            "uniquenessConstraints": uniqueness_constraints
+                .iter()
+                .map(|(constraint_name, field_names)| {
+                    ((*constraint_name).to_string(), serde_json::json!(field_names))
+                })
+                .collect::<serde_json::Map<_, _>>(),
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn uniqueness_constraints_refer_to_customized_field_names() {
+        let old_tables = metadata::TablesInfo(BTreeMap::from([(
+            "customer".into(),
+            table("customer", &[("customerId", "id"), ("email", "email")], &[]),
+        )]));
+        let introspected_table = table(
+            "customer",
+            &[("id", "id"), ("email", "email")],
+            &[
+                ("customer_pkey", &["id"]),
+                ("customer_id_email_key", &["id", "email"]),
+            ],
+        );
+
+        let tables = get_aliased_tables(
+            HashSet::new(),
+            vec![("customer".into(), introspected_table)],
+            &old_tables,
+            &options::NamingConvention::default(),
+            &options::NamingConvention::default(),
+        );
+
+        let uniqueness_constraints = &tables.0[&CollectionName::from("customer".to_string())]
+            .uniqueness_constraints
+            .0;
+        assert_eq!(
+            uniqueness_constraints["customer_pkey"],
+            metadata::UniquenessConstraint(BTreeSet::from(["customerId".into()]))
+        );
+        assert_eq!(
+            uniqueness_constraints["customer_id_email_key"],
+            metadata::UniquenessConstraint(BTreeSet::from(["customerId".into(), "email".into()]))
+        );
+    }
+
+    #[test]
+    fn whether_the_connector_generates_uuids_is_kept() {
+        let mut old_table = table("customer", &[("id", "id")], &[]);
+        old_table.columns.get_mut("id").unwrap().generates_uuid = true;
+        let old_tables = metadata::TablesInfo(BTreeMap::from([("customer".into(), old_table)]));
+
+        let tables = get_aliased_tables(
+            HashSet::new(),
+            vec![("customer".into(), table("customer", &[("id", "id")], &[]))],
+            &old_tables,
+            &options::NamingConvention::default(),
+            &options::NamingConvention::default(),
+        );
+
+        assert!(
+            tables.0[&CollectionName::from("customer".to_string())].columns["id"].generates_uuid
+        );
+    }
+
+    fn naming_convention(case: options::NameCase) -> options::NamingConvention {
+        options::NamingConvention {
+            case,
+            strip_prefixes: vec!["tbl_".to_string(), "col_".to_string()],
+            rename: BTreeMap::new(),
+        }
+    }
+
+    fn names(tables: &metadata::TablesInfo) -> Vec<(String, Vec<String>)> {
+        tables
+            .0
+            .iter()
+            .map(|(collection_name, table_info)| {
+                (
+                    collection_name.to_string(),
+                    table_info.columns.keys().map(ToString::to_string).collect(),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn new_tables_are_named_following_the_naming_convention() {
+        let introspected_table = table(
+            "tbl_customer_order",
+            &[("col_order_id", "col_order_id"), ("col_nm", "col_nm")],
+            &[],
+        );
+
+        let tables = get_aliased_tables(
+            HashSet::new(),
+            vec![("tbl_customer_order".into(), introspected_table)],
+            &metadata::TablesInfo::default(),
+            &options::NamingConvention::default(),
+            &naming_convention(options::NameCase::CamelCase),
+        );
+
+        assert_eq!(
+            names(&tables),
+            vec![(
+                "customerOrder".to_string(),
+                vec!["nm".to_string(), "orderId".to_string()]
+            )]
+        );
+    }
+
+    #[test]
+    fn a_new_naming_convention_renames_what_the_old_one_named() {
+        let old_naming_convention = naming_convention(options::NameCase::CamelCase);
+        let old_tables = metadata::TablesInfo(BTreeMap::from([(
+            "customerOrder".into(),
+            table(
+                "tbl_customer_order",
+                // the name of 'col_nm' was customized
+                &[("orderId", "col_order_id"), ("name", "col_nm")],
+                &[],
+            ),
+        )]));
+        let introspected_table = table(
+            "tbl_customer_order",
+            &[("col_order_id", "col_order_id"), ("col_nm", "col_nm")],
+            &[],
+        );
+
+        let tables = get_aliased_tables(
+            HashSet::new(),
+            vec![("tbl_customer_order".into(), introspected_table)],
+            &old_tables,
+            &old_naming_convention,
+            &naming_convention(options::NameCase::SnakeCase),
+        );
+
+        assert_eq!(
+            names(&tables),
+            vec![(
+                "customer_order".to_string(),
+                vec!["name".to_string(), "order_id".to_string()]
+            )]
+        );
+    }
+
+    fn computed_field(definition: serde_json::Value) -> metadata::ComputedField {
+        let mut computed_field = serde_json::json!({ "type": { "scalarType": "int4" } });
+        computed_field
+            .as_object_mut()
+            .unwrap()
+            .extend(definition.as_object().unwrap().clone());
+        serde_json::from_value(computed_field).unwrap()
+    }
+
+    fn function(function_name: &str) -> serde_json::Value {
+        serde_json::json!({
+            "function": { "schemaName": "public", "functionName": function_name }
+        })
+    }
+
+    #[test]
+    fn computed_fields_calling_functions_which_are_gone_are_dropped() {
+        let old = BTreeMap::from([
+            (
+                "shout".into(),
+                computed_field(serde_json::json!({ "expression": "upper({{row}}.name)" })),
+            ),
+            ("nameLength".into(), computed_field(function("name_length"))),
+            ("dropped".into(), computed_field(function("dropped"))),
+        ]);
+        let introspected = BTreeMap::from([
+            (
+                "name_length".into(),
+                computed_field(function("name_length")),
+            ),
+            ("initials".into(), computed_field(function("initials"))),
+        ]);
+
+        let computed_fields = merge_computed_fields(introspected, Some(&old));
+
+        assert_eq!(
+            computed_fields
+                .keys()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>(),
+            vec!["initials", "nameLength", "shout"]
+        );
+    }
+}
@@ -9,6 +9,7 @@ use query_engine_sql::sql;
 use sqlx::Column;
 use sqlx::Connection;
 use sqlx::Executor;
+use sqlx::Row;
 
 use crate::environment::Environment;
 
@@ -22,6 +23,8 @@ pub enum Kind {
     Mutation,
 }
 
+pub use crate::native_operations::Inference;
+
 /// Take a SQL file containing a Native Operation, check against the database that it is valid,
 /// and add it to the configuration if it is.
 pub async fn create(
@@ -30,6 +33,7 @@ pub async fn create(
     connection_string: &str,
     operation_path: &Path,
     operation_file_contents: &str,
+    inference: Inference,
 ) -> anyhow::Result<metadata::NativeQueryInfo> {
     let connect_options =
         crate::get_connect_options(&crate::ConnectionUri::from(connection_string), environment)?;
@@ -74,6 +78,19 @@ pub async fn create(
         arguments_to_oids.insert(param_name, i64::from(the_oid));
     }
 
+    let non_nullable_outputs = match inference {
+        Inference::Describe => vec![],
+        Inference::Explain => {
+            crate::native_operations::explain_non_nullable_outputs(
+                &mut connection,
+                &sql.sql,
+                result_parameters.len(),
+            )
+            .instrument(info_span!("Explain native operation"))
+            .await?
+        }
+    };
+
     // Fill the columns list.
     for (index, column) in result.columns.iter().enumerate() {
         let the_oid = column
@@ -83,12 +100,29 @@ pub async fn create(
                 "Internal error: All sqlx TypeInfos should have an oid."
             ))?
             .0;
-        let is_nullable = result.nullable(index).unwrap_or(
-            // If we don't know, we assume it is nullable.
-            true,
-        );
+        let is_nullable = !non_nullable_outputs.get(index).copied().unwrap_or(false)
+            && result.nullable(index).unwrap_or(
+                // If we don't know, we assume it is nullable.
+                true,
+            );
 
-        columns_to_oids.insert(column.name().to_string(), (i64::from(the_oid), is_nullable));
+        // Expressions such as `x + 1` are named `?column?`, and aggregates after their
+        // function, such as `sum`, unless they are given an alias.
+        if column.name() == "?column?" {
+            anyhow::bail!(
+                "Column {} of the Native Operation has no name. Please give it one with AS.",
+                index + 1
+            );
+        }
+        if columns_to_oids
+            .insert(column.name().to_string(), (i64::from(the_oid), is_nullable))
+            .is_some()
+        {
+            anyhow::bail!(
+                "The Native Operation returns more than one column named '{}'. Please rename them with AS.",
+                column.name()
+            );
+        }
     }
 
     let mut oids: BTreeSet<i64> = arguments_to_oids.values().copied().collect();
@@ -97,6 +131,8 @@ pub async fn create(
     let oids_map =
         oids_to_typenames(configuration, connection_string, environment, &oids_vec).await?;
 
+    let nullable_arguments =
+        crate::native_operations::null_checked_arguments(operation_file_contents);
     let mut arguments = BTreeMap::new();
     for (name, oid) in arguments_to_oids {
         arguments.insert(
@@ -110,8 +146,11 @@ pub async fn create(
                         .clone(),
                 ),
                 description: None,
-                // we don't have this information, so we assume not nullable.
-                nullable: metadata::Nullable::NonNullable,
+                nullable: if nullable_arguments.contains(name.as_str()) {
+                    metadata::Nullable::Nullable
+                } else {
+                    metadata::Nullable::NonNullable
+                },
             },
         );
     }
@@ -146,11 +185,315 @@ pub async fn create(
         arguments,
         columns,
         description: None,
+        foreign_relations: metadata::ForeignRelations::default(),
+        isolation_level: None,
     };
 
     Ok(new_native_operation)
 }
 
+/// Connect to the database of a configuration, with the given session settings, to check it.
+pub(crate) async fn connect(
+    configuration: &super::ParsedConfiguration,
+    environment: &impl Environment,
+    session: &crate::SessionSettings,
+) -> anyhow::Result<sqlx::PgConnection> {
+    let connection_string = match configuration
+        .connection_settings
+        .connection_uri
+        .to_connection_uri(environment)?
+        .0
+    {
+        crate::values::Secret::Plain(connection_string) => connection_string,
+        crate::values::Secret::FromEnvironment { variable } => environment.read(&variable)?,
+    };
+    let connect_options = session.apply(crate::get_connect_options(
+        &crate::ConnectionUri::from(connection_string.as_str()),
+        environment,
+    )?);
+    Ok(sqlx::PgConnection::connect_with(&connect_options)
+        .instrument(info_span!("Connect to database"))
+        .await?)
+}
+
+/// Check the Native Operations in the configuration against what the database reports for their
+/// SQL, and describe each disagreement: a column the SQL does not return, a column declared
+/// non-nullable which the database knows can be null, or a column or argument whose declared
+/// scalar type differs from the one the database infers.
+///
+/// Native Operations are not introspected, so this is how schema changes which break them are
+/// noticed.
+pub async fn verify(
+    configuration: &super::ParsedConfiguration,
+    environment: &impl Environment,
+    session: &crate::SessionSettings,
+) -> anyhow::Result<Vec<String>> {
+    let native_operations = &configuration.metadata.native_operations;
+    if native_operations.queries.0.is_empty() && native_operations.mutations.0.is_empty() {
+        return Ok(vec![]);
+    }
+
+    let mut connection = connect(configuration, environment, session).await?;
+
+    let operations = native_operations
+        .queries
+        .0
+        .iter()
+        .map(|(name, info)| ("Native Query", name.to_string(), info))
+        .chain(
+            native_operations
+                .mutations
+                .0
+                .iter()
+                .map(|(name, info)| ("Native Mutation", name.to_string(), info)),
+        );
+
+    let mut problems = vec![];
+    // the types are compared once every statement is described, so that they can be looked up
+    // together.
+    let mut described = vec![];
+    for (kind, name, info) in operations {
+        let sql = match info.sql.clone().sql() {
+            Ok(parts) => parts.to_sql(),
+            Err(err) => {
+                problems.push(format!("{kind} '{name}': {err}"));
+                continue;
+            }
+        };
+        let description = match connection.describe(&sql.sql).await {
+            Ok(description) => description,
+            Err(err) => {
+                problems.push(format!("{kind} '{name}' could not be described: {err}"));
+                continue;
+            }
+        };
+
+        let mut column_oids = BTreeMap::new();
+        for column in info.columns.values() {
+            let Some(index) = description
+                .columns
+                .iter()
+                .position(|described| described.name() == column.name)
+            else {
+                problems.push(format!(
+                    "{kind} '{name}' declares the column '{}', which its SQL does not return.",
+                    column.name
+                ));
+                continue;
+            };
+            if column.nullable == metadata::Nullable::NonNullable
+                && description.nullable(index) == Some(true)
+            {
+                problems.push(format!(
+                    "{kind} '{name}' declares the column '{}' as non-nullable, but the database reports that it can be null.",
+                    column.name
+                ));
+            }
+            if let Some(oid) = description.columns[index].type_info().oid() {
+                column_oids.insert(column.name.clone(), i64::from(oid.0));
+            }
+        }
+
+        let mut argument_oids = BTreeMap::new();
+        if let Some(sqlx::Either::Left(parameters)) = &description.parameters {
+            for (parameter, param) in parameters.iter().zip(sql.params.iter()) {
+                if let (sql::string::Param::Variable(argument), Some(oid)) =
+                    (param, parameter.oid())
+                {
+                    argument_oids.insert(argument.clone(), i64::from(oid.0));
+                }
+            }
+        }
+
+        described.push((kind, name, info, column_oids, argument_oids));
+    }
+
+    let oids: Vec<i64> = described
+        .iter()
+        .flat_map(|(_, _, _, column_oids, argument_oids)| {
+            column_oids.values().chain(argument_oids.values()).copied()
+        })
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect();
+    let type_names = lookup_typenames(configuration, environment, &mut connection, &oids).await?;
+
+    for (kind, name, info, column_oids, argument_oids) in described {
+        for column in info.columns.values() {
+            if let Some(problem) =
+                type_drift(&column.r#type, column_oids.get(&column.name), &type_names)
+            {
+                problems.push(format!(
+                    "{kind} '{name}' declares the column '{}' {problem}",
+                    column.name
+                ));
+            }
+        }
+        for (argument_name, argument) in &info.arguments {
+            if let Some(problem) = type_drift(
+                &argument.r#type,
+                argument_oids.get(argument_name.as_str()),
+                &type_names,
+            ) {
+                problems.push(format!(
+                    "{kind} '{name}' declares the argument '{argument_name}' {problem}"
+                ));
+            }
+        }
+    }
+
+    Ok(problems)
+}
+
+/// Describe how a declared scalar type differs from the type the database infers, if it does.
+/// Composite and array types are not compared.
+fn type_drift(
+    declared: &metadata::Type,
+    inferred_oid: Option<&i64>,
+    type_names: &BTreeMap<i64, models::ScalarTypeName>,
+) -> Option<String> {
+    let metadata::Type::ScalarType(declared) = declared else {
+        return None;
+    };
+    let inferred = type_names.get(inferred_oid?)?;
+    (declared != inferred).then(|| {
+        format!("with the type '{declared}', but the database infers the type '{inferred}'.")
+    })
+}
+
+/// The outcome of running a Native Operation with some arguments.
+#[derive(Debug, Clone)]
+pub struct TestResult {
+    /// The columns the statement returns, with the types inferred for them.
+    pub columns: Vec<(String, models::ScalarTypeName)>,
+    /// The rows the statement returned, with every value in its text representation.
+    pub rows: Vec<Vec<Option<String>>>,
+}
+
+/// Run a Native Operation from the configuration with the given arguments, inside a
+/// transaction which is rolled back afterwards, and report the rows it returns.
+pub async fn test(
+    configuration: &super::ParsedConfiguration,
+    environment: &impl Environment,
+    name: &str,
+    kind: Kind,
+    arguments: &BTreeMap<String, serde_json::Value>,
+) -> anyhow::Result<TestResult> {
+    let native_operations = &configuration.metadata.native_operations;
+    let native_operation = match kind {
+        Kind::Query => native_operations
+            .queries
+            .0
+            .iter()
+            .find(|(operation_name, _)| operation_name.as_str() == name)
+            .map(|(_, info)| info),
+        Kind::Mutation => native_operations
+            .mutations
+            .0
+            .iter()
+            .find(|(operation_name, _)| operation_name.as_str() == name)
+            .map(|(_, info)| info),
+    }
+    .ok_or_else(|| anyhow::anyhow!("A Native Operation with the name '{name}' does not exist."))?;
+
+    for argument in arguments.keys() {
+        if !native_operation
+            .arguments
+            .keys()
+            .any(|known| known.as_str() == argument)
+        {
+            anyhow::bail!("The Native Operation '{name}' has no argument '{argument}'.");
+        }
+    }
+
+    let sql = native_operation
+        .sql
+        .clone()
+        .sql()
+        .map_err(|err| anyhow::anyhow!(err))?
+        .to_sql();
+
+    // Every argument is sent as a quoted literal, so that the database converts it to the type
+    // it infers for the parameter.
+    let dialect = super::to_runtime_configuration::convert_dialect(configuration.dialect).dialect();
+    let mut literals = Vec::with_capacity(sql.params.len());
+    for param in &sql.params {
+        let sql::string::Param::Variable(param_name) = param else {
+            anyhow::bail!("Internal error: Native operation parameter was not a variable.")
+        };
+        let mut literal = String::new();
+        match arguments.get(param_name) {
+            None => anyhow::bail!("Missing a value for the argument '{param_name}'."),
+            Some(serde_json::Value::Null) => literal.push_str("NULL"),
+            Some(serde_json::Value::String(string)) => dialect.quote_literal(string, &mut literal),
+            Some(value) => dialect.quote_literal(&value.to_string(), &mut literal),
+        }
+        literals.push(literal);
+    }
+
+    let connection_string = configuration.get_connection_uri()?;
+    let connect_options = crate::get_connect_options(
+        &crate::ConnectionUri::from(connection_string.as_str()),
+        environment,
+    )?;
+    let mut connection = sqlx::PgConnection::connect_with(&connect_options).await?;
+
+    let description = connection.describe(&sql.sql).await?;
+    let mut oids = Vec::with_capacity(description.columns.len());
+    for column in &description.columns {
+        let the_oid = column
+            .type_info()
+            .oid()
+            .ok_or(anyhow::anyhow!(
+                "Internal error: All sqlx TypeInfos should have an oid."
+            ))?
+            .0;
+        oids.push(i64::from(the_oid));
+    }
+    let oids_map = oids_to_typenames(configuration, &connection_string, environment, &oids).await?;
+    let columns = description
+        .columns
+        .iter()
+        .zip(&oids)
+        .map(|(column, oid)| {
+            let type_name = oids_map
+                .get(oid)
+                .ok_or_else(|| anyhow::anyhow!("Internal error: oid not found in map."))?;
+            Ok((column.name().to_string(), type_name.clone()))
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+
+    let mut transaction = connection.begin().await?;
+
+    let prepare = format!("PREPARE {TESTED_STATEMENT} AS {}", sql.sql);
+    sqlx::raw_sql(&prepare).execute(&mut *transaction).await?;
+
+    let execute = if literals.is_empty() {
+        format!("EXECUTE {TESTED_STATEMENT}")
+    } else {
+        format!("EXECUTE {TESTED_STATEMENT}({})", literals.join(", "))
+    };
+    // The simple query protocol returns every value as text, whatever its type.
+    let result_rows = sqlx::raw_sql(&execute).fetch_all(&mut *transaction).await?;
+
+    // Roll back whatever the statement did, mutations included.
+    transaction.rollback().await?;
+
+    let rows = result_rows
+        .iter()
+        .map(|row| {
+            (0..row.len())
+                .map(|index| row.try_get_unchecked::<Option<String>, _>(index))
+                .collect::<Result<Vec<_>, _>>()
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(TestResult { columns, rows })
+}
+
+/// The name of the prepared statement we test.
+const TESTED_STATEMENT: &str = "ndc_postgres_tested_native_operation";
+
 /// Given a vector of OIDs, ask postgres to provide the equivalent type names.
 pub async fn oids_to_typenames(
     configuration: &super::ParsedConfiguration,
@@ -165,9 +508,27 @@ pub async fn oids_to_typenames(
         .instrument(info_span!("Connect to database"))
         .await?;
 
+    lookup_typenames(configuration, environment, &mut connection, oids).await
+}
+
+/// Given a vector of OIDs, ask postgres to provide the equivalent type names, over an existing
+/// connection.
+async fn lookup_typenames(
+    configuration: &super::ParsedConfiguration,
+    environment: &impl Environment,
+    connection: &mut sqlx::PgConnection,
+    oids: &[i64],
+) -> anyhow::Result<BTreeMap<i64, models::ScalarTypeName>> {
+    let unqualified_schemas = super::options::resolve_schemas(
+        &configuration
+            .introspection_options
+            .unqualified_schemas_for_types_and_procedures,
+        environment,
+    )?;
+
     let rows: Vec<OidQueryRow> = sqlx::query_as(OID_QUERY)
         .bind(oids)
-        .fetch_all(&mut connection)
+        .fetch_all(connection)
         .instrument(info_span!("Run oid lookup query"))
         .await?;
 
@@ -193,11 +554,7 @@ pub async fn oids_to_typenames(
         // or just typename depending if the schema is in the unqualified list or not,
         // then add the nq and run the introspection.
         if !found {
-            if configuration
-                .introspection_options
-                .unqualified_schemas_for_types_and_procedures
-                .contains(&schema_name)
-            {
+            if unqualified_schemas.contains(&schema_name) {
                 oids_map.insert(oid, type_name.into());
             } else {
                 oids_map.insert(oid, format!("{schema_name}_{type_name}").into());
@@ -1,8 +1,14 @@
 //! The part of the configuration that dictates how the rest is generated.
 
+use std::collections::BTreeMap;
+
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
+use crate::environment::Environment;
+use crate::values::{EnvironmentValue, EnvironmentValueError};
+use crate::words::{self, WordCase};
+
 use super::comparison::ComparisonOperatorMapping;
 use super::database::{TypeRepresentation, TypeRepresentations};
 
@@ -12,15 +18,17 @@ use super::database::{TypeRepresentation, TypeRepresentations};
 pub struct IntrospectionOptions {
     /// Schemas which are excluded from introspection. The default setting will exclude the
     /// internal schemas of Postgres, Citus, Cockroach, and the PostGIS extension.
+    /// Any of them can be read from an environment variable, such as
+    /// `{"variable": "EXCLUDED_SCHEMA"}`.
     #[serde(default = "default_excluded_schemas")]
-    pub excluded_schemas: Vec<String>,
+    pub excluded_schemas: Vec<EnvironmentValue<String>>,
     /// The names of Tables and Views in these schemas will be returned unqualified.
     /// The default setting will set the `public` schema as unqualified.
     #[serde(default = "default_unqualified_schemas_for_tables")]
-    pub unqualified_schemas_for_tables: Vec<String>,
+    pub unqualified_schemas_for_tables: Vec<EnvironmentValue<String>>,
     /// The types and procedures in these schemas will be returned unqualified.
     #[serde(default = "default_unqualified_schemas_for_types_and_procedures")]
-    pub unqualified_schemas_for_types_and_procedures: Vec<String>,
+    pub unqualified_schemas_for_types_and_procedures: Vec<EnvironmentValue<String>>,
     /// The mapping of comparison operator names to apply when updating the configuration
     #[serde(default = "ComparisonOperatorMapping::default_mappings")]
     pub comparison_operator_mapping: Vec<ComparisonOperatorMapping>,
@@ -36,6 +44,33 @@ pub struct IntrospectionOptions {
     /// The type representations to pick for base scalar types.
     #[serde(default = "default_base_type_representations")]
     pub type_representations: TypeRepresentations,
+
+    /// How to treat tables that inherit from other tables (via `INHERITS`). Partitions are not
+    /// affected by this setting.
+    #[serde(default)]
+    pub inherited_tables: InheritedTables,
+
+    /// How to name the collections and fields of tables and columns. Names which have been
+    /// changed in the configuration are kept.
+    #[serde(default, skip_serializing_if = "NamingConvention::is_default")]
+    pub naming_convention: NamingConvention,
+
+    /// Introspect the sequences of the database, which are exposed as procedures advancing them.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub introspect_sequences: bool,
+}
+
+/// How tables using Postgres table inheritance are exposed.
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug, Deserialize, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub enum InheritedTables {
+    /// Only expose the parent tables. Querying a parent table returns the rows of all the
+    /// tables inheriting from it.
+    #[default]
+    Collapse,
+    /// Expose inheriting tables as separate collections. Parent tables are queried using `ONLY`,
+    /// so that each row is returned by exactly one collection.
+    Separate,
 }
 
 impl Default for IntrospectionOptions {
@@ -49,12 +84,107 @@ impl Default for IntrospectionOptions {
             introspect_prefix_function_comparison_operators:
                 default_introspect_prefix_function_comparison_operators(),
             type_representations: default_base_type_representations(),
+            inherited_tables: InheritedTables::default(),
+            naming_convention: NamingConvention::default(),
+            introspect_sequences: false,
         }
     }
 }
 
-fn default_excluded_schemas() -> Vec<String> {
-    vec![
+/// How to derive the names of collections and fields from the names of tables and columns, such
+/// as turning `tbl_customer_order` into `customerOrder`.
+#[derive(Clone, Default, PartialEq, Eq, Debug, Deserialize, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct NamingConvention {
+    /// The case to put names in.
+    #[serde(default)]
+    pub case: NameCase,
+    /// Prefixes to remove from the names of tables and columns, such as `tbl_`. Only the first
+    /// one which matches is removed.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub strip_prefixes: Vec<String>,
+    /// Names to use instead of those the other rules give. Tables are looked up by the name
+    /// they would otherwise be given, which is qualified by their schema unless it is one of
+    /// `unqualifiedSchemasForTables`, and columns by that name and theirs, as in `table.column`.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub rename: BTreeMap<String, String>,
+}
+
+/// The case of the names of collections and fields.
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug, Deserialize, Serialize, JsonSchema)]
+pub enum NameCase {
+    /// Keep the names of the database.
+    #[default]
+    #[serde(rename = "asIs")]
+    AsIs,
+    /// `customerOrder`
+    #[serde(rename = "camelCase")]
+    CamelCase,
+    /// `CustomerOrder`
+    #[serde(rename = "PascalCase")]
+    PascalCase,
+    /// `customer_order`
+    #[serde(rename = "snake_case")]
+    SnakeCase,
+}
+
+impl NamingConvention {
+    pub(crate) fn is_default(&self) -> bool {
+        *self == NamingConvention::default()
+    }
+
+    /// The name of the collection of a table, given the name it would otherwise get.
+    pub fn collection_name(&self, name: &str) -> String {
+        self.rename
+            .get(name)
+            .cloned()
+            .unwrap_or_else(|| self.apply(name))
+    }
+
+    /// The name of the field of a column, given the names the table and column would otherwise
+    /// get.
+    pub fn field_name(&self, table: &str, name: &str) -> String {
+        self.rename
+            .get(&format!("{table}.{name}"))
+            .cloned()
+            .unwrap_or_else(|| self.apply(name))
+    }
+
+    fn apply(&self, name: &str) -> String {
+        let name = self
+            .strip_prefixes
+            .iter()
+            .find_map(|prefix| name.strip_prefix(prefix.as_str()))
+            .filter(|rest| !rest.is_empty())
+            .unwrap_or(name);
+        let case = match self.case {
+            NameCase::AsIs => return name.to_string(),
+            NameCase::CamelCase => WordCase::Camel,
+            NameCase::PascalCase => WordCase::Pascal,
+            NameCase::SnakeCase => WordCase::Snake,
+        };
+        let words = words::split(name);
+        if words.is_empty() {
+            name.to_string()
+        } else {
+            words::join(case, &words)
+        }
+    }
+}
+
+/// Read the names of schemas, any of which can refer to the environment.
+pub fn resolve_schemas(
+    schemas: &[EnvironmentValue<String>],
+    environment: impl Environment,
+) -> Result<Vec<String>, EnvironmentValueError> {
+    schemas
+        .iter()
+        .map(|schema| schema.resolve(&environment))
+        .collect()
+}
+
+fn default_excluded_schemas() -> Vec<EnvironmentValue<String>> {
+    [
         // From Postgres itself
         "information_schema".to_string(),
         "pg_catalog".to_string(),
@@ -75,19 +205,21 @@ fn default_excluded_schemas() -> Vec<String> {
         "timescaledb_information".to_string(),
         "_timescaledb_debug".to_string(),
     ]
+    .map(EnvironmentValue::from)
+    .into()
 }
 
 /// Collection names of tables in these schemas will be appear as unqualified.
-fn default_unqualified_schemas_for_tables() -> Vec<String> {
-    vec!["public".to_string()]
+fn default_unqualified_schemas_for_tables() -> Vec<EnvironmentValue<String>> {
+    vec!["public".to_string().into()]
 }
 
 /// Types, operators and procedures from these schemas will appear unqualified in the configuration.
-fn default_unqualified_schemas_for_types_and_procedures() -> Vec<String> {
+fn default_unqualified_schemas_for_types_and_procedures() -> Vec<EnvironmentValue<String>> {
     vec![
-        "public".to_string(),
-        "pg_catalog".to_string(),
-        "tiger".to_string(),
+        "public".to_string().into(),
+        "pg_catalog".to_string().into(),
+        "tiger".to_string().into(),
     ]
 }
 
@@ -236,10 +368,14 @@ fn default_base_type_representations() -> TypeRepresentations {
             ("bit".into(), TypeRepresentation::String),
             ("bool".into(), TypeRepresentation::Boolean),
             ("bpchar".into(), TypeRepresentation::String),
+            // Binary strings are sent as base64.
+            ("bytea".into(), TypeRepresentation::Bytes),
             ("char".into(), TypeRepresentation::String),
             ("date".into(), TypeRepresentation::Date),
             ("float4".into(), TypeRepresentation::Float32),
             ("float8".into(), TypeRepresentation::Float64),
+            // The hstore extension: https://www.postgresql.org/docs/current/hstore.html
+            ("hstore".into(), TypeRepresentation::Hstore),
             ("int2".into(), TypeRepresentation::Int16),
             ("int4".into(), TypeRepresentation::Int32),
             (
@@ -262,3 +398,32 @@ fn default_base_type_representations() -> TypeRepresentations {
         .into(),
     )
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names_follow_the_naming_convention() {
+        let naming_convention = NamingConvention {
+            case: NameCase::CamelCase,
+            strip_prefixes: vec!["tbl_".to_string(), "col_".to_string()],
+            rename: BTreeMap::from([
+                ("tbl_cust".to_string(), "customer".to_string()),
+                ("tbl_cust.col_nm".to_string(), "name".to_string()),
+            ]),
+        };
+        assert_eq!(
+            naming_convention.collection_name("tbl_customer_order"),
+            "customerOrder"
+        );
+        assert_eq!(naming_convention.collection_name("tbl_cust"), "customer");
+        assert_eq!(naming_convention.field_name("tbl_cust", "col_nm"), "name");
+        assert_eq!(
+            naming_convention.field_name("tbl_cust", "col_created_at"),
+            "createdAt"
+        );
+        // a prefix is not removed if nothing would be left
+        assert_eq!(naming_convention.field_name("tbl_cust", "tbl_"), "tbl");
+    }
+}
@@ -0,0 +1,224 @@
+//! Reading and writing the metadata of a configuration which is split across several files.
+//!
+//! When `splitMetadata` is set, tables are written to `tables/<schema>.json`, and each native
+//! query and mutation is written to `native_queries/<name>.json` or
+//! `native_mutations/<name>.json` respectively, rather than inline in `configuration.json`.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use tokio::fs;
+
+use super::metadata;
+use super::ParsedConfiguration;
+use crate::error::{ParseConfigurationError, WriteParsedConfigurationError};
+
+const TABLES_DIRNAME: &str = "tables";
+const NATIVE_QUERIES_DIRNAME: &str = "native_queries";
+const NATIVE_MUTATIONS_DIRNAME: &str = "native_mutations";
+
+/// Merge the metadata found in the subdirectories of the configuration directory into the
+/// parsed configuration.
+pub async fn read(
+    configuration_dir: &Path,
+    parsed_config: &mut ParsedConfiguration,
+) -> Result<(), ParseConfigurationError> {
+    for (file_path, tables) in
+        read_dir::<metadata::TablesInfo>(&configuration_dir.join(TABLES_DIRNAME)).await?
+    {
+        for (name, table) in tables.0 {
+            insert_unique(
+                &mut parsed_config.metadata.tables.0,
+                name,
+                table,
+                &file_path,
+            )?;
+        }
+    }
+
+    let native_operations = &mut parsed_config.metadata.native_operations;
+    for (file_path, native_query) in
+        read_dir::<metadata::NativeQueryInfo>(&configuration_dir.join(NATIVE_QUERIES_DIRNAME))
+            .await?
+    {
+        let name = file_stem(&file_path).into();
+        insert_unique(
+            &mut native_operations.queries.0,
+            name,
+            native_query,
+            &file_path,
+        )?;
+    }
+    for (file_path, native_mutation) in
+        read_dir::<metadata::NativeQueryInfo>(&configuration_dir.join(NATIVE_MUTATIONS_DIRNAME))
+            .await?
+    {
+        let name = file_stem(&file_path).into();
+        insert_unique(
+            &mut native_operations.mutations.0,
+            name,
+            native_mutation,
+            &file_path,
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Move the tables and native operations out of the parsed configuration and write them to
+/// their own files, leaving the remainder to be written to `configuration.json`.
+pub async fn write(
+    parsed_config: &mut ParsedConfiguration,
+    out_dir: &Path,
+) -> Result<(), WriteParsedConfigurationError> {
+    let mut tables_by_schema = BTreeMap::<String, metadata::TablesInfo>::new();
+    for (name, table) in std::mem::take(&mut parsed_config.metadata.tables.0) {
+        tables_by_schema
+            .entry(table.schema_name.clone())
+            .or_default()
+            .0
+            .insert(name, table);
+    }
+    write_dir(out_dir, TABLES_DIRNAME, tables_by_schema).await?;
+
+    let native_operations = &mut parsed_config.metadata.native_operations;
+    write_dir(
+        out_dir,
+        NATIVE_QUERIES_DIRNAME,
+        std::mem::take(&mut native_operations.queries.0),
+    )
+    .await?;
+    write_dir(
+        out_dir,
+        NATIVE_MUTATIONS_DIRNAME,
+        std::mem::take(&mut native_operations.mutations.0),
+    )
+    .await?;
+
+    Ok(())
+}
+
+/// Parse every JSON file in a directory, in order of their names. A missing directory is
+/// treated as an empty one.
+async fn read_dir<T: DeserializeOwned>(
+    dir: &Path,
+) -> Result<Vec<(PathBuf, T)>, ParseConfigurationError> {
+    let mut entries = match fs::read_dir(dir).await {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(vec![]),
+        Err(err) => {
+            return Err(ParseConfigurationError::IoErrorButStringified(format!(
+                "{}: {}",
+                dir.display(),
+                err
+            )))
+        }
+    };
+
+    let mut file_paths = vec![];
+    while let Some(entry) = entries.next_entry().await? {
+        let file_path = entry.path();
+        if file_path
+            .extension()
+            .is_some_and(|extension| extension == "json")
+        {
+            file_paths.push(file_path);
+        }
+    }
+    file_paths.sort();
+
+    let mut results = Vec::with_capacity(file_paths.len());
+    for file_path in file_paths {
+        let contents = fs::read_to_string(&file_path).await.map_err(|err| {
+            ParseConfigurationError::IoErrorButStringified(format!(
+                "{}: {}",
+                file_path.display(),
+                err
+            ))
+        })?;
+        let value = serde_json::from_str(&contents).map_err(|error| {
+            ParseConfigurationError::ParseError {
+                file_path: file_path.clone(),
+                line: error.line(),
+                column: error.column(),
+                message: error.to_string(),
+            }
+        })?;
+        results.push((file_path, value));
+    }
+    Ok(results)
+}
+
+/// Replace the JSON files of a directory with one file per entry, named after its key.
+async fn write_dir<K: std::fmt::Display, V: Serialize>(
+    out_dir: &Path,
+    dirname: &str,
+    entries: BTreeMap<K, V>,
+) -> Result<(), WriteParsedConfigurationError> {
+    let dir = out_dir.join(dirname);
+
+    // remove files left over from entries which no longer exist.
+    match fs::read_dir(&dir).await {
+        Ok(mut existing) => {
+            while let Some(entry) = existing.next_entry().await? {
+                let file_path = entry.path();
+                if file_path
+                    .extension()
+                    .is_some_and(|extension| extension == "json")
+                {
+                    fs::remove_file(file_path).await?;
+                }
+            }
+        }
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => {}
+        Err(err) => return Err(err.into()),
+    }
+
+    if entries.is_empty() {
+        return Ok(());
+    }
+    fs::create_dir_all(&dir).await?;
+
+    for (name, value) in entries {
+        let name = name.to_string();
+        let file = PathBuf::from(format!("{name}.json"));
+        // the name must not escape the directory it is written to.
+        if file.components().count() != 1 || name.starts_with('.') {
+            return Err(WriteParsedConfigurationError::WritingOutsideDestinationDir { dir, file });
+        }
+        fs::write(
+            dir.join(file),
+            serde_json::to_string_pretty(&value)
+                .map_err(|e| WriteParsedConfigurationError::IoError(e.into()))?
+                + "\n",
+        )
+        .await?;
+    }
+    Ok(())
+}
+
+fn file_stem(file_path: &Path) -> String {
+    file_path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+/// Insert an entry read from a file, failing if an entry of the same name was already read.
+fn insert_unique<K: Ord + std::fmt::Display, V>(
+    map: &mut BTreeMap<K, V>,
+    name: K,
+    value: V,
+    file_path: &Path,
+) -> Result<(), ParseConfigurationError> {
+    if map.contains_key(&name) {
+        return Err(ParseConfigurationError::DuplicateMetadataEntry {
+            file_path: file_path.to_owned(),
+            name: name.to_string(),
+        });
+    }
+    map.insert(name, value);
+    Ok(())
+}
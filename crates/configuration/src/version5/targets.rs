@@ -0,0 +1,189 @@
+//! Combine the types introspected from the connection targets with those of the primary database.
+
+use super::metadata;
+
+/// Add the types of a target to those introspected so far. A type both databases have is only
+/// given the aggregate functions and comparison operators they agree on, so that every operation
+/// the schema offers on it works against either database. Types whose representations or fields
+/// differ cannot be served as one, so they are rejected.
+pub fn merge_types(
+    target: &str,
+    scalar_types: &mut metadata::ScalarTypes,
+    composite_types: &mut metadata::CompositeTypes,
+    target_scalar_types: metadata::ScalarTypes,
+    target_composite_types: metadata::CompositeTypes,
+) -> anyhow::Result<()> {
+    for (name, target_scalar_type) in target_scalar_types.0 {
+        match scalar_types.0.get_mut(&name) {
+            None => {
+                scalar_types.0.insert(name, target_scalar_type);
+            }
+            Some(scalar_type) => {
+                if scalar_type.type_representation != target_scalar_type.type_representation {
+                    anyhow::bail!(
+                        "The scalar type '{name}' of the target '{target}' is represented differently than in the primary database."
+                    );
+                }
+                scalar_type
+                    .aggregate_functions
+                    .retain(|function, definition| {
+                        target_scalar_type.aggregate_functions.get(function) == Some(definition)
+                    });
+                scalar_type
+                    .comparison_operators
+                    .retain(|operator, definition| {
+                        target_scalar_type.comparison_operators.get(operator) == Some(definition)
+                    });
+            }
+        }
+    }
+    for (name, target_composite_type) in target_composite_types.0 {
+        match composite_types.0.get(&name) {
+            None => {
+                composite_types.0.insert(name, target_composite_type);
+            }
+            Some(composite_type) => {
+                if composite_type.fields != target_composite_type.fields {
+                    anyhow::bail!(
+                        "The composite type '{name}' of the target '{target}' has different fields than in the primary database."
+                    );
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::*;
+
+    fn int4(aggregate_functions: &[&str], comparison_operators: &[&str]) -> metadata::ScalarType {
+        metadata::ScalarType {
+            type_name: "int4".to_string(),
+            schema_name: "pg_catalog".to_string(),
+            description: None,
+            aggregate_functions: aggregate_functions
+                .iter()
+                .map(|function| {
+                    (
+                        (*function).into(),
+                        metadata::AggregateFunction {
+                            return_type: "int4".into(),
+                        },
+                    )
+                })
+                .collect(),
+            comparison_operators: comparison_operators
+                .iter()
+                .map(|operator| {
+                    (
+                        (*operator).into(),
+                        metadata::ComparisonOperator {
+                            operator_name: (*operator).to_string(),
+                            operator_kind: metadata::OperatorKind::Custom,
+                            argument_type: "int4".into(),
+                            is_infix: true,
+                        },
+                    )
+                })
+                .collect(),
+            type_representation: Some(metadata::TypeRepresentation::Int32),
+        }
+    }
+
+    fn composite_type(fields: &[&str]) -> metadata::CompositeType {
+        metadata::CompositeType {
+            type_name: "address".to_string(),
+            schema_name: "public".to_string(),
+            fields: fields
+                .iter()
+                .map(|field| {
+                    (
+                        (*field).into(),
+                        metadata::FieldInfo {
+                            field_name: (*field).to_string(),
+                            r#type: metadata::Type::ScalarType("text".into()),
+                            nullable: metadata::Nullable::Nullable,
+                            has_default: metadata::HasDefault::NoDefault,
+                            description: None,
+                            position: None,
+                        },
+                    )
+                })
+                .collect(),
+            description: None,
+        }
+    }
+
+    #[test]
+    fn shared_scalar_types_keep_the_operations_both_databases_have() {
+        let mut scalar_types = metadata::ScalarTypes(BTreeMap::from([(
+            "int4".into(),
+            int4(&["max", "sum"], &["_eq", "_gt"]),
+        )]));
+        let mut composite_types = metadata::CompositeTypes::default();
+
+        merge_types(
+            "analytics",
+            &mut scalar_types,
+            &mut composite_types,
+            metadata::ScalarTypes(BTreeMap::from([
+                ("int4".into(), int4(&["max", "avg"], &["_eq"])),
+                ("text".into(), int4(&[], &[])),
+            ])),
+            metadata::CompositeTypes::default(),
+        )
+        .unwrap();
+
+        assert_eq!(scalar_types.0["int4"], int4(&["max"], &["_eq"]));
+        assert!(scalar_types.0.contains_key("text"));
+    }
+
+    #[test]
+    fn scalar_types_represented_differently_are_rejected() {
+        let mut scalar_types =
+            metadata::ScalarTypes(BTreeMap::from([("int4".into(), int4(&[], &[]))]));
+        let mut composite_types = metadata::CompositeTypes::default();
+
+        let result = merge_types(
+            "analytics",
+            &mut scalar_types,
+            &mut composite_types,
+            metadata::ScalarTypes(BTreeMap::from([(
+                "int4".into(),
+                metadata::ScalarType {
+                    type_representation: Some(metadata::TypeRepresentation::Int64),
+                    ..int4(&[], &[])
+                },
+            )])),
+            metadata::CompositeTypes::default(),
+        );
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn composite_types_with_different_fields_are_rejected() {
+        let mut scalar_types = metadata::ScalarTypes::default();
+        let mut composite_types = metadata::CompositeTypes(BTreeMap::from([(
+            "address".into(),
+            composite_type(&["street", "city"]),
+        )]));
+
+        let result = merge_types(
+            "analytics",
+            &mut scalar_types,
+            &mut composite_types,
+            metadata::ScalarTypes::default(),
+            metadata::CompositeTypes(BTreeMap::from([(
+                "address".into(),
+                composite_type(&["street"]),
+            )])),
+        );
+
+        assert!(result.is_err());
+    }
+}
@@ -1,13 +1,15 @@
 //! Convert the parsed configuration metadata to internal engine metadata
 //! That can be used by the connector at runtime.
 
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet};
+
+use query_engine_sql::sql::dialect::Dialect;
 
 use super::metadata;
 use super::ParsedConfiguration;
 use crate::environment::Environment;
 use crate::error::MakeRuntimeConfigurationError;
-use crate::values::{ConnectionUri, Secret};
+use crate::values::{ConnectionUri, EnvironmentValueError, Secret};
 use crate::VersionTag;
 
 /// Convert the parsed configuration metadata to internal engine metadata
@@ -16,39 +18,540 @@ pub fn make_runtime_configuration(
     parsed_config: ParsedConfiguration,
     environment: impl Environment,
 ) -> Result<crate::Configuration, MakeRuntimeConfigurationError> {
-    let connection_uri = match parsed_config.connection_settings.connection_uri {
-        ConnectionUri(Secret::Plain(uri)) => Ok(uri),
-        ConnectionUri(Secret::FromEnvironment { variable }) => {
-            environment.read(&variable).map_err(|error| {
-                MakeRuntimeConfigurationError::MissingEnvironmentVariable {
-                    file_path: super::CONFIGURATION_FILENAME.into(),
-                    message: error.to_string(),
-                }
+    let file_path = std::path::PathBuf::from(parsed_config.format.file_name());
+    let connection_uri = resolve_connection_uri(
+        parsed_config
+            .connection_settings
+            .connection_uri
+            .to_connection_uri(&environment)
+            .map_err(|error| environment_value_error(error, &file_path))?,
+        &environment,
+        &file_path,
+    )?;
+    let read_replicas = parsed_config
+        .connection_settings
+        .read_replicas
+        .map(|settings| {
+            // A lag check interval of zero would check the replicas continuously.
+            if settings.lag_check_interval == 0 {
+                return Err(MakeRuntimeConfigurationError::InvalidLagCheckInterval {
+                    file_path: file_path.clone(),
+                });
+            }
+            Ok::<_, MakeRuntimeConfigurationError>(crate::ReadReplicas {
+                connection_uris: settings
+                    .connection_uris
+                    .into_iter()
+                    .map(|uri| resolve_connection_uri(uri, &environment, &file_path))
+                    .collect::<Result<_, _>>()?,
+                max_replication_lag: settings.max_replication_lag,
+                lag_check_interval: settings.lag_check_interval,
+                read_your_writes_window: settings.read_your_writes_window,
+            })
+        })
+        .transpose()?;
+    // A health check interval of zero would probe the database continuously.
+    if parsed_config
+        .connection_settings
+        .health_check
+        .as_ref()
+        .is_some_and(|settings| settings.interval == 0)
+    {
+        return Err(MakeRuntimeConfigurationError::InvalidHealthCheckInterval {
+            file_path: file_path.clone(),
+        });
+    }
+    let targets = parsed_config
+        .connection_settings
+        .targets
+        .into_iter()
+        .map(|(name, settings)| {
+            let connection_uri = resolve_connection_uri(
+                settings
+                    .connection_uri
+                    .to_connection_uri(&environment)
+                    .map_err(|error| environment_value_error(error, &file_path))?,
+                &environment,
+                &file_path,
+            )?;
+            Ok((name, connection_uri))
+        })
+        .collect::<Result<BTreeMap<_, _>, MakeRuntimeConfigurationError>>()?;
+    if let Some((collection, table)) = parsed_config.metadata.tables.0.iter().find(|(_, table)| {
+        table
+            .target
+            .as_ref()
+            .is_some_and(|target| !targets.contains_key(target))
+    }) {
+        return Err(MakeRuntimeConfigurationError::UnknownTarget {
+            file_path: file_path.clone(),
+            collection: collection.clone(),
+            target: table.target.clone().unwrap_or_default(),
+        });
+    }
+    parsed_config
+        .connection_settings
+        .pool_settings
+        .check(&environment)
+        .map_err(|error| environment_value_error(error, &file_path))?;
+    let isolation_level = parsed_config
+        .connection_settings
+        .isolation_level
+        .resolve(&environment)
+        .map_err(|error| environment_value_error(error, &file_path))?;
+    if let Some((collection, _)) = parsed_config
+        .planner_hints
+        .iter()
+        .find(|(_, hint)| hint.contains("*/"))
+    {
+        return Err(MakeRuntimeConfigurationError::InvalidPlannerHint {
+            file_path: file_path.clone(),
+            collection: collection.clone(),
+        });
+    }
+    let invalid_cast = parsed_config
+        .type_overrides
+        .iter()
+        .find_map(|type_override| {
+            type_override
+                .cast_as
+                .as_ref()
+                .filter(|cast_as| !is_built_in_type_name(cast_as))
+                .map(|cast_as| (type_override, cast_as))
+        });
+    if let Some((type_override, cast_as)) = invalid_cast {
+        return Err(MakeRuntimeConfigurationError::InvalidTypeOverrideCast {
+            file_path: file_path.clone(),
+            schema_name: type_override.schema_name.clone(),
+            type_name: type_override.type_name.clone(),
+            cast_as: cast_as.clone(),
+        });
+    }
+    if let Some((collection, _)) = parsed_config
+        .metadata
+        .native_operations
+        .queries
+        .0
+        .iter()
+        .find(|(_, info)| info.isolation_level.is_some())
+    {
+        return Err(MakeRuntimeConfigurationError::NativeQueryIsolationLevel {
+            file_path: file_path.clone(),
+            collection: collection.clone(),
+        });
+    }
+    let mutation_isolation_levels = parsed_config
+        .metadata
+        .native_operations
+        .mutations
+        .0
+        .iter()
+        .filter_map(|(procedure, info)| {
+            info.isolation_level
+                .map(|isolation_level| (procedure.clone(), isolation_level))
+        })
+        .collect();
+    if let Some(sequence) = parsed_config.metadata.sequences.0.keys().find(|sequence| {
+        parsed_config
+            .metadata
+            .native_operations
+            .mutations
+            .0
+            .contains_key(&query_engine_metadata::metadata::Sequences::procedure_name(
+                sequence,
+            ))
+    }) {
+        return Err(MakeRuntimeConfigurationError::SequenceProcedureConflict {
+            file_path: file_path.clone(),
+            sequence: sequence.clone(),
+            procedure: query_engine_metadata::metadata::Sequences::procedure_name(sequence),
+        });
+    }
+    // the foreign keys of native queries are written by hand, so they may refer to tables which
+    // are not in the metadata.
+    for (native_query, info) in &parsed_config.metadata.native_operations.queries.0 {
+        for (constraint, relation) in &info.foreign_relations.0 {
+            let foreign_schema = relation
+                .foreign_schema
+                .as_deref()
+                .unwrap_or(query_engine_metadata::metadata::NATIVE_QUERY_DEFAULT_FOREIGN_SCHEMA);
+            let is_tracked = parsed_config.metadata.tables.0.values().any(|table| {
+                table.target.is_none()
+                    && table.schema_name == foreign_schema
+                    && table.table_name == relation.foreign_table
+            });
+            if !is_tracked {
+                return Err(MakeRuntimeConfigurationError::UnknownForeignTable {
+                    file_path: file_path.clone(),
+                    native_query: native_query.clone(),
+                    constraint: constraint.clone(),
+                    foreign_table: format!("{foreign_schema}.{}", relation.foreign_table),
+                });
+            }
+        }
+    }
+    // logical keys are also written by hand, so they may name fields which are not columns.
+    for (collection, table) in &parsed_config.metadata.tables.0 {
+        for (logical_key, metadata::UniquenessConstraint(fields)) in &table.logical_keys.0 {
+            if let Some(field) = fields
+                .iter()
+                .find(|field| !table.columns.contains_key(*field))
+            {
+                return Err(MakeRuntimeConfigurationError::UnknownLogicalKeyField {
+                    file_path: file_path.clone(),
+                    collection: collection.clone(),
+                    logical_key: logical_key.clone(),
+                    field: field.clone(),
+                });
+            }
+        }
+    }
+    let is_collection = |collection: &ndc_models::CollectionName| {
+        parsed_config.metadata.tables.0.contains_key(collection)
+            || parsed_config
+                .metadata
+                .native_operations
+                .queries
+                .0
+                .contains_key(collection)
+    };
+    if let Some(collection) = parsed_config.collection_policies.keys().find(|collection| {
+        !is_collection(collection) && !parsed_config.json_tables.contains_key(*collection)
+    }) {
+        return Err(MakeRuntimeConfigurationError::UnknownPolicyCollection {
+            file_path: file_path.clone(),
+            collection: collection.clone(),
+        });
+    }
+    // policy filters are written by hand as well, and are only translated with the first query
+    // over their collection, so we check them now.
+    let collection_fields = |collection: &ndc_models::CollectionName| {
+        let metadata = &parsed_config.metadata;
+        if let Some(table) = metadata.tables.0.get(collection) {
+            Some(table.columns.keys().collect::<BTreeSet<_>>())
+        } else if let Some(native_query) = metadata.native_operations.queries.0.get(collection) {
+            Some(native_query.columns.keys().collect())
+        } else {
+            parsed_config.json_tables.get(collection).map(|json_table| {
+                json_table
+                    .columns
+                    .keys()
+                    .chain(&json_table.source_fields)
+                    .collect()
             })
         }
-    }?;
+    };
+    for (collection, policy) in &parsed_config.collection_policies {
+        if let (Some(filter), Some(fields)) = (&policy.filter, collection_fields(collection)) {
+            check_policy_filter(filter, &fields, &fields, &collection_fields).map_err(
+                |message| MakeRuntimeConfigurationError::InvalidPolicyFilter {
+                    file_path: file_path.clone(),
+                    collection: collection.clone(),
+                    message,
+                },
+            )?;
+        }
+    }
+    let dialect = convert_dialect(parsed_config.dialect);
+    let mut json_table_queries = BTreeMap::new();
+    let mut inherited_policy_filters = BTreeMap::new();
+    for (collection, json_table) in &parsed_config.json_tables {
+        let invalid_json_table = |message| MakeRuntimeConfigurationError::InvalidJsonTable {
+            file_path: file_path.clone(),
+            collection: collection.clone(),
+            message,
+        };
+        if is_collection(collection) {
+            return Err(invalid_json_table(
+                "there is already a collection with this name".to_string(),
+            ));
+        }
+        let native_query = super::json_tables::to_native_query(
+            json_table,
+            &parsed_config.metadata,
+            dialect.dialect(),
+        )
+        .map_err(invalid_json_table)?;
+        let mut native_query = convert_native_query_info(native_query);
+        // The documents are read from the database the table is in.
+        native_query.target = parsed_config
+            .metadata
+            .tables
+            .0
+            .get(&json_table.collection)
+            .and_then(|table| table.target.clone());
+        // The rows of the JSON table come from the rows of the table, so the policy of the table
+        // restricts them as well, through the fields of the table the JSON table includes.
+        if let Some(filter) = parsed_config
+            .collection_policies
+            .get(&json_table.collection)
+            .and_then(|policy| policy.filter.as_ref())
+        {
+            let fields = collection_fields(collection).unwrap_or_default();
+            check_policy_filter(filter, &fields, &fields, &collection_fields).map_err(
+                |message| {
+                    invalid_json_table(format!(
+                        "the policy of the table '{}' cannot restrict its rows, as {message}",
+                        json_table.collection
+                    ))
+                },
+            )?;
+            inherited_policy_filters.insert(collection.clone(), filter.clone());
+        }
+        json_table_queries.insert(collection.clone(), native_query);
+    }
+    let mut collection_policies = parsed_config.collection_policies;
+    for (collection, filter) in inherited_policy_filters {
+        let policy = collection_policies.entry(collection).or_default();
+        policy.filter = Some(match policy.filter.take() {
+            None => filter,
+            Some(own_filter) => ndc_models::Expression::And {
+                expressions: vec![own_filter, filter],
+            },
+        });
+    }
+    let json_tables = json_table_queries.keys().cloned().collect();
+    let mut metadata = apply_type_overrides(
+        convert_metadata(parsed_config.metadata, dialect.dialect()),
+        parsed_config.type_overrides,
+    );
+    metadata
+        .native_operations
+        .queries
+        .0
+        .extend(json_table_queries);
     Ok(crate::Configuration {
-        metadata: convert_metadata(parsed_config.metadata),
+        metadata,
         pool_settings: parsed_config.connection_settings.pool_settings,
         connection_uri,
-        isolation_level: parsed_config.connection_settings.isolation_level,
+        isolation_level,
         mutations_version: convert_mutations_version(parsed_config.mutations_version),
-        configuration_version_tag: VersionTag::Version4,
+        configuration_version_tag: VersionTag::Version5,
+        configuration_hash: String::new(),
         mutations_prefix: parsed_config.mutations_prefix,
+        mutations_returning_aggregates: parsed_config.mutations_returning_aggregates,
+        dialect,
+        use_lateral_joins: parsed_config.use_lateral_joins,
+        limit_with_ties: parsed_config.limit_with_ties,
+        strict_boolean_comparisons: parsed_config.strict_boolean_comparisons,
+        snapshot_reads: parsed_config.snapshot_reads,
+        distinct_on: parsed_config.distinct_on,
+        temporary_variables_table: parsed_config.temporary_variables_table,
+        planner_hints: parsed_config.planner_hints,
+        mutation_isolation_levels,
+        collection_policies,
+        distributed_query_routing: parsed_config.distributed_query_routing.unwrap_or_default(),
+        count_estimation: parsed_config.count_estimation,
+        verify_queries: parsed_config.verify_queries,
+        read_replicas,
+        targets,
+        health_check: parsed_config.connection_settings.health_check,
+        annotate_queries: parsed_config.annotate_queries,
+        request_id_setting: parsed_config.connection_settings.request_id_setting,
+        slow_query_threshold_ms: parsed_config.slow_query_threshold_ms,
+        log_slow_query_parameters: parsed_config.log_slow_query_parameters,
+        query_timeout_ms: parsed_config.query_timeout_ms,
+        query_limits: parsed_config.query_limits.unwrap_or_default(),
+        retry_policy: parsed_config.retry_policy.unwrap_or_default(),
+        mutation_failure_policy: parsed_config.mutation_failure_policy.unwrap_or_default(),
+        usage_statistics: parsed_config.usage_statistics,
+        request_recording: parsed_config.request_recording,
+        relationship_naming: parsed_config.relationship_naming.unwrap_or_default(),
+        watch_native_query_files: parsed_config.watch_native_query_files,
+        json_tables,
+        configuration_directory: None,
     })
 }
 
+/// Read a connection string, looking it up in the environment if necessary.
+fn resolve_connection_uri(
+    connection_uri: ConnectionUri,
+    environment: impl Environment,
+    file_path: &std::path::Path,
+) -> Result<String, MakeRuntimeConfigurationError> {
+    match connection_uri {
+        ConnectionUri(Secret::Plain(uri)) => Ok(uri),
+        ConnectionUri(Secret::FromEnvironment { variable }) => {
+            environment.read(&variable).map_err(|error| {
+                MakeRuntimeConfigurationError::MissingEnvironmentVariable {
+                    file_path: file_path.to_path_buf(),
+                    message: error.to_string(),
+                }
+            })
+        }
+    }
+}
+
+fn environment_value_error(
+    error: EnvironmentValueError,
+    file_path: &std::path::Path,
+) -> MakeRuntimeConfigurationError {
+    match error {
+        EnvironmentValueError::Missing(error) => {
+            MakeRuntimeConfigurationError::MissingEnvironmentVariable {
+                file_path: file_path.to_path_buf(),
+                message: error.to_string(),
+            }
+        }
+        EnvironmentValueError::Invalid { variable, message } => {
+            MakeRuntimeConfigurationError::InvalidEnvironmentVariable {
+                file_path: file_path.to_path_buf(),
+                variable,
+                message,
+            }
+        }
+    }
+}
+
 /// Convert the metadata specified in the parsed configuration to an engine metadata.
 /// This function is used by tests as well
-pub fn convert_metadata(metadata: metadata::Metadata) -> query_engine_metadata::metadata::Metadata {
+pub fn convert_metadata(
+    metadata: metadata::Metadata,
+    dialect: &dyn Dialect,
+) -> query_engine_metadata::metadata::Metadata {
     query_engine_metadata::metadata::Metadata {
-        tables: convert_tables(metadata.tables),
+        tables: convert_tables(metadata.tables, dialect),
         scalar_types: convert_scalar_types(metadata.types.scalar),
         composite_types: convert_composite_types(metadata.types.composite),
         native_operations: convert_native_operations(metadata.native_operations),
+        sequences: convert_sequences(metadata.sequences),
+    }
+}
+
+fn convert_sequences(sequences: metadata::Sequences) -> query_engine_metadata::metadata::Sequences {
+    query_engine_metadata::metadata::Sequences(
+        sequences
+            .0
+            .into_iter()
+            .map(|(name, sequence)| {
+                (
+                    name,
+                    query_engine_metadata::metadata::SequenceInfo {
+                        schema_name: sequence.schema_name,
+                        sequence_name: sequence.sequence_name,
+                        description: sequence.description,
+                    },
+                )
+            })
+            .collect(),
+    )
+}
+
+/// Whether a name can refer to a built-in type, such as `text` or `int8`. Values are cast to
+/// the types of overrides by their quoted names, so these must be spelled the way the database
+/// stores them, and cannot be types with modifiers such as `varchar(10)`.
+/// Check that a policy filter only refers to fields of the collections it is over. Policies
+/// apply outside of any request, so they cannot follow its relationships or read its variables.
+fn check_policy_filter<'a>(
+    expression: &ndc_models::Expression,
+    root_fields: &BTreeSet<&ndc_models::FieldName>,
+    fields: &BTreeSet<&ndc_models::FieldName>,
+    collection_fields: &impl Fn(
+        &ndc_models::CollectionName,
+    ) -> Option<BTreeSet<&'a ndc_models::FieldName>>,
+) -> Result<(), String> {
+    let check_target = |target: &ndc_models::ComparisonTarget| match target {
+        ndc_models::ComparisonTarget::Column { name, path, .. } => {
+            if let Some(path_element) = path.first() {
+                Err(format!(
+                    "the relationship '{}' cannot be used in a policy",
+                    path_element.relationship
+                ))
+            } else if fields.contains(name) {
+                Ok(())
+            } else {
+                Err(format!("there is no field named '{name}'"))
+            }
+        }
+        ndc_models::ComparisonTarget::RootCollectionColumn { name, .. } => {
+            if root_fields.contains(name) {
+                Ok(())
+            } else {
+                Err(format!("there is no field named '{name}'"))
+            }
+        }
+    };
+    match expression {
+        ndc_models::Expression::And { expressions }
+        | ndc_models::Expression::Or { expressions } => {
+            expressions.iter().try_for_each(|expression| {
+                check_policy_filter(expression, root_fields, fields, collection_fields)
+            })
+        }
+        ndc_models::Expression::Not { expression } => {
+            check_policy_filter(expression, root_fields, fields, collection_fields)
+        }
+        ndc_models::Expression::UnaryComparisonOperator { column, .. } => check_target(column),
+        ndc_models::Expression::BinaryComparisonOperator { column, value, .. } => {
+            check_target(column)?;
+            match value {
+                ndc_models::ComparisonValue::Column { column } => check_target(column),
+                ndc_models::ComparisonValue::Scalar { .. } => Ok(()),
+                ndc_models::ComparisonValue::Variable { name } => {
+                    Err(format!("the variable '{name}' cannot be used in a policy"))
+                }
+            }
+        }
+        ndc_models::Expression::Exists {
+            in_collection,
+            predicate,
+        } => match in_collection {
+            ndc_models::ExistsInCollection::Related { relationship, .. } => Err(format!(
+                "the relationship '{relationship}' cannot be used in a policy"
+            )),
+            ndc_models::ExistsInCollection::Unrelated { collection, .. } => {
+                let collection_fields_of_exists = collection_fields(collection)
+                    .ok_or_else(|| format!("there is no collection named '{collection}'"))?;
+                predicate.as_deref().map_or(Ok(()), |predicate| {
+                    check_policy_filter(
+                        predicate,
+                        root_fields,
+                        &collection_fields_of_exists,
+                        collection_fields,
+                    )
+                })
+            }
+            // The fields of nested objects are checked when the filter is translated.
+            ndc_models::ExistsInCollection::NestedCollection { column_name, .. } => {
+                if fields.contains(column_name) {
+                    Ok(())
+                } else {
+                    Err(format!("there is no field named '{column_name}'"))
+                }
+            }
+        },
     }
 }
 
+fn is_built_in_type_name(name: &str) -> bool {
+    name.chars()
+        .next()
+        .is_some_and(|first| first.is_ascii_lowercase() || first == '_')
+        && name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+}
+
+/// Replace the type representations of the scalar types which have been overridden.
+fn apply_type_overrides(
+    mut metadata: query_engine_metadata::metadata::Metadata,
+    type_overrides: Vec<super::TypeOverride>,
+) -> query_engine_metadata::metadata::Metadata {
+    for type_override in type_overrides {
+        for scalar_type in metadata.scalar_types.0.values_mut() {
+            if scalar_type.type_name == type_override.type_name
+                && scalar_type.schema_name.as_ref() == Some(&type_override.schema_name)
+            {
+                scalar_type.type_representation = Some(convert_type_representation(
+                    type_override.type_representation.clone(),
+                ));
+                scalar_type.cast_as.clone_from(&type_override.cast_as);
+            }
+        }
+    }
+    metadata
+}
+
 fn convert_scalar_types(
     scalar_types: metadata::ScalarTypes,
 ) -> query_engine_metadata::metadata::ScalarTypes {
@@ -76,6 +579,7 @@ fn convert_scalar_types(
                         type_representation: scalar_type
                             .type_representation
                             .map(convert_type_representation),
+                        cast_as: None,
                     },
                 )
             })
@@ -126,6 +630,8 @@ fn convert_native_query_info(
             .map(|(k, v)| (k, convert_read_only_column_info(v)))
             .collect(),
         description: native_query_info.description,
+        foreign_relations: convert_foreign_relations(native_query_info.foreign_relations),
+        target: None,
     }
 }
 
@@ -212,7 +718,7 @@ fn convert_native_query_sql_external(
     }
 }
 
-fn convert_native_query_parts(
+pub(crate) fn convert_native_query_parts(
     inline: metadata::NativeQueryParts,
 ) -> query_engine_metadata::metadata::NativeQueryParts {
     query_engine_metadata::metadata::NativeQueryParts(
@@ -295,6 +801,12 @@ fn convert_type_representation(
         metadata::TypeRepresentation::Geometry => {
             query_engine_metadata::metadata::TypeRepresentation::Geometry
         }
+        metadata::TypeRepresentation::Hstore => {
+            query_engine_metadata::metadata::TypeRepresentation::Hstore
+        }
+        metadata::TypeRepresentation::Bytes => {
+            query_engine_metadata::metadata::TypeRepresentation::Bytes
+        }
         // This is deprecated in ndc-spec
         metadata::TypeRepresentation::Number
         | metadata::TypeRepresentation::Integer
@@ -361,23 +873,45 @@ fn convert_composite_type_field_info(
     query_engine_metadata::metadata::FieldInfo {
         field_name: field.field_name,
         r#type: convert_type(field.r#type),
+        nullable: convert_nullable(&field.nullable),
+        has_default: convert_has_default(&field.has_default),
         description: field.description,
+        position: field.position,
     }
 }
 
-pub fn convert_tables(tables: metadata::TablesInfo) -> query_engine_metadata::metadata::TablesInfo {
+pub fn convert_tables(
+    tables: metadata::TablesInfo,
+    dialect: &dyn Dialect,
+) -> query_engine_metadata::metadata::TablesInfo {
     query_engine_metadata::metadata::TablesInfo(
         tables
             .0
             .into_iter()
-            .map(|(k, table_info)| (k, convert_table_info(table_info)))
+            .map(|(k, table_info)| (k, convert_table_info(table_info, dialect)))
             .collect(),
     )
 }
 
 fn convert_table_info(
     table_info: metadata::TableInfo,
+    dialect: &dyn Dialect,
 ) -> query_engine_metadata::metadata::TableInfo {
+    let computed_fields = table_info
+        .computed_fields
+        .into_iter()
+        .map(|(field, computed_field)| {
+            (
+                field,
+                convert_computed_field(
+                    &table_info.schema_name,
+                    &table_info.table_name,
+                    computed_field,
+                    dialect,
+                ),
+            )
+        })
+        .collect();
     query_engine_metadata::metadata::TableInfo {
         schema_name: table_info.schema_name,
         table_name: table_info.table_name,
@@ -389,6 +923,130 @@ fn convert_table_info(
         uniqueness_constraints: convert_uniqueness_constraints(table_info.uniqueness_constraints),
         foreign_relations: convert_foreign_relations(table_info.foreign_relations),
         description: table_info.description,
+        inherited_rows: convert_inherited_rows(&table_info.inherited_rows),
+        target: table_info.target,
+        statement_timeout_ms: table_info.statement_timeout_ms,
+        logical_keys: convert_uniqueness_constraints(table_info.logical_keys),
+        row_expiry: table_info.row_expiry.map(|row_expiry| {
+            query_engine_metadata::metadata::RowExpiry {
+                column: row_expiry.column,
+                after: row_expiry.after,
+            }
+        }),
+        soft_delete: table_info.soft_delete.map(|soft_delete| {
+            query_engine_metadata::metadata::SoftDelete {
+                column: soft_delete.column,
+            }
+        }),
+        audit_columns: table_info
+            .audit_columns
+            .into_iter()
+            .map(|(field, audit_column)| (field, convert_audit_column(audit_column)))
+            .collect(),
+        computed_relationships: table_info
+            .computed_relationships
+            .into_iter()
+            .map(|(target, computed_relationship)| {
+                (
+                    target,
+                    query_engine_metadata::metadata::ComputedRelationship::parse(
+                        &computed_relationship.condition,
+                    ),
+                )
+            })
+            .collect(),
+        computed_fields,
+        insertable: table_info.mutable.unwrap_or(table_info.insertable),
+        updatable: table_info.mutable.unwrap_or(table_info.updatable),
+        deletable: table_info.mutable.unwrap_or(table_info.deletable),
+        distribution: table_info
+            .distribution
+            .map(|distribution| match distribution {
+                metadata::Distribution::Distributed {
+                    column,
+                    colocation_id,
+                } => query_engine_metadata::metadata::Distribution::Distributed {
+                    column,
+                    colocation_id,
+                },
+                metadata::Distribution::Reference => {
+                    query_engine_metadata::metadata::Distribution::Reference
+                }
+            }),
+    }
+}
+
+/// Convert a computed field to the expression computing it. A function is called with the row
+/// converted to the type of the table, as the row we select from is that of a subquery, which may
+/// have columns the table does not, such as those mutations add to check their constraints.
+/// Converting it through JSON keeps the columns of the table by name and drops the others:
+///
+///   "schema"."function"(json_populate_record(cast(null as "schema"."table"), row_to_json(<row>)))
+fn convert_computed_field(
+    schema_name: &str,
+    table_name: &str,
+    computed_field: metadata::ComputedField,
+    dialect: &dyn Dialect,
+) -> query_engine_metadata::metadata::ComputedField {
+    use query_engine_metadata::metadata::{ComputedField, ExpressionPart};
+    let expression = match computed_field.definition {
+        metadata::ComputedFieldDefinition::Expression(expression) => {
+            ComputedField::parse_expression(&expression)
+        }
+        metadata::ComputedFieldDefinition::Function(function) => {
+            let mut call = String::new();
+            dialect.quote_identifier(&function.schema_name, &mut call);
+            call.push('.');
+            dialect.quote_identifier(&function.function_name, &mut call);
+            call.push_str("(json_populate_record(cast(null as ");
+            dialect.quote_identifier(schema_name, &mut call);
+            call.push('.');
+            dialect.quote_identifier(table_name, &mut call);
+            call.push_str("), row_to_json(");
+            vec![
+                ExpressionPart::Text(call),
+                ExpressionPart::Row,
+                ExpressionPart::Text(")))".to_string()),
+            ]
+        }
+    };
+    ComputedField {
+        expression,
+        r#type: convert_type(computed_field.r#type),
+        description: computed_field.description,
+    }
+}
+
+fn convert_audit_column(
+    audit_column: metadata::AuditColumn,
+) -> query_engine_metadata::metadata::AuditColumn {
+    query_engine_metadata::metadata::AuditColumn {
+        value: match audit_column.value {
+            metadata::AuditValue::Now => query_engine_metadata::metadata::AuditValue::Now,
+            metadata::AuditValue::Setting(setting) => {
+                query_engine_metadata::metadata::AuditValue::Setting(setting)
+            }
+        },
+        on: match audit_column.on {
+            metadata::AuditOn::Insert => query_engine_metadata::metadata::AuditOn::Insert,
+            metadata::AuditOn::Update => query_engine_metadata::metadata::AuditOn::Update,
+            metadata::AuditOn::InsertAndUpdate => {
+                query_engine_metadata::metadata::AuditOn::InsertAndUpdate
+            }
+        },
+    }
+}
+
+fn convert_inherited_rows(
+    inherited_rows: &metadata::InheritedRows,
+) -> query_engine_metadata::metadata::InheritedRows {
+    match inherited_rows {
+        metadata::InheritedRows::Included => {
+            query_engine_metadata::metadata::InheritedRows::Included
+        }
+        metadata::InheritedRows::Excluded => {
+            query_engine_metadata::metadata::InheritedRows::Excluded
+        }
     }
 }
 
@@ -443,17 +1101,38 @@ fn convert_uniqueness_constraint(
 fn convert_column_info(
     column_info: metadata::ColumnInfo,
 ) -> query_engine_metadata::metadata::ColumnInfo {
+    let generates_uuid = column_info.generates_uuid
+        || column_info
+            .default_expression
+            .as_deref()
+            .is_some_and(is_uuid_generating_default);
     query_engine_metadata::metadata::ColumnInfo {
         name: column_info.name,
         r#type: convert_type(column_info.r#type),
         nullable: convert_nullable(&column_info.nullable),
         has_default: convert_has_default(&column_info.has_default),
+        default_expression: column_info.default_expression,
         is_identity: convert_is_identity(&column_info.is_identity),
         is_generated: convert_is_generated(&column_info.is_generated),
+        generates_uuid,
+        selectable: column_info.selectable,
+        insertable: column_info.insertable,
+        updatable: column_info.updatable,
+        filterable: column_info.filterable,
+        sortable: column_info.sortable,
         description: column_info.description,
     }
 }
 
+/// Whether a default expression, as the database prints it, generates a random UUID, such as
+/// `gen_random_uuid()` or `public.uuid_generate_v4()`.
+fn is_uuid_generating_default(default_expression: &str) -> bool {
+    let function = default_expression
+        .rsplit_once('.')
+        .map_or(default_expression, |(_, function)| function);
+    matches!(function, "gen_random_uuid()" | "uuid_generate_v4()")
+}
+
 fn convert_is_generated(
     is_generated: &metadata::IsGenerated,
 ) -> query_engine_metadata::metadata::IsGenerated {
@@ -490,6 +1169,17 @@ fn convert_has_default(
     }
 }
 
+pub(super) fn convert_dialect(
+    dialect: super::Dialect,
+) -> query_engine_sql::sql::dialect::SqlDialect {
+    match dialect {
+        super::Dialect::Postgres => query_engine_sql::sql::dialect::SqlDialect::Postgres,
+        super::Dialect::CockroachDB => query_engine_sql::sql::dialect::SqlDialect::CockroachDB,
+        super::Dialect::YugabyteDB => query_engine_sql::sql::dialect::SqlDialect::YugabyteDB,
+        super::Dialect::Citus => query_engine_sql::sql::dialect::SqlDialect::Citus,
+    }
+}
+
 fn convert_mutations_version(
     mutations_version_opt: Option<metadata::mutations::MutationsVersion>,
 ) -> Option<query_engine_metadata::metadata::mutations::MutationsVersion> {
@@ -502,3 +1192,228 @@ fn convert_mutations_version(
         }
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::environment::FixedEnvironment;
+
+    fn configuration_with_logical_key(fields: &[&str]) -> ParsedConfiguration {
+        let mut parsed_config = ParsedConfiguration::empty();
+        parsed_config.metadata.tables.0.insert(
+            "report".into(),
+            serde_json::from_value(serde_json::json!({
+                "schemaName": "public",
+                "tableName": "report",
+                "columns": {
+                    "id": { "name": "id", "type": { "scalarType": "int4" } },
+                    "period": { "name": "period", "type": { "scalarType": "text" } },
+                },
+                "logicalKeys": { "report_key": fields },
+            }))
+            .unwrap(),
+        );
+        parsed_config
+    }
+
+    fn environment() -> FixedEnvironment {
+        FixedEnvironment::from([(
+            "CONNECTION_URI".into(),
+            "postgresql://localhost:5432/postgres".into(),
+        )])
+    }
+
+    #[test]
+    fn logical_keys_over_columns_are_accepted() {
+        let configuration = make_runtime_configuration(
+            configuration_with_logical_key(&["id", "period"]),
+            environment(),
+        )
+        .unwrap();
+
+        assert!(configuration.metadata.tables.0["report"]
+            .logical_keys
+            .0
+            .contains_key("report_key"));
+    }
+
+    #[test]
+    fn logical_keys_over_unknown_fields_are_rejected() {
+        let result = make_runtime_configuration(
+            configuration_with_logical_key(&["id", "year"]),
+            environment(),
+        );
+
+        assert!(matches!(
+            result,
+            Err(MakeRuntimeConfigurationError::UnknownLogicalKeyField {
+                collection,
+                logical_key,
+                field,
+                ..
+            }) if collection.as_str() == "report" && logical_key == "report_key" && field.as_str() == "year"
+        ));
+    }
+
+    fn configuration_with_policy_filter(filter: serde_json::Value) -> ParsedConfiguration {
+        let mut parsed_config = configuration_with_logical_key(&["id"]);
+        parsed_config.collection_policies.insert(
+            "report".into(),
+            serde_json::from_value(serde_json::json!({ "filter": filter })).unwrap(),
+        );
+        parsed_config
+    }
+
+    #[test]
+    fn policy_filters_over_fields_of_the_collection_are_accepted() {
+        let result = make_runtime_configuration(
+            configuration_with_policy_filter(serde_json::json!({
+                "type": "binary_comparison_operator",
+                "column": { "type": "column", "name": "period", "path": [] },
+                "operator": "_eq",
+                "value": { "type": "scalar", "value": "2024" },
+            })),
+            environment(),
+        );
+
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn policy_filters_over_unknown_fields_are_rejected() {
+        let result = make_runtime_configuration(
+            configuration_with_policy_filter(serde_json::json!({
+                "type": "unary_comparison_operator",
+                "column": { "type": "column", "name": "deleted_at", "path": [] },
+                "operator": "is_null",
+            })),
+            environment(),
+        );
+
+        assert!(matches!(
+            result,
+            Err(MakeRuntimeConfigurationError::InvalidPolicyFilter { message, .. })
+                if message == "there is no field named 'deleted_at'"
+        ));
+    }
+
+    #[test]
+    fn policy_filters_over_relationships_are_rejected() {
+        let result = make_runtime_configuration(
+            configuration_with_policy_filter(serde_json::json!({
+                "type": "exists",
+                "in_collection": { "type": "related", "relationship": "owner", "arguments": {} },
+                "predicate": { "type": "and", "expressions": [] },
+            })),
+            environment(),
+        );
+
+        assert!(matches!(
+            result,
+            Err(MakeRuntimeConfigurationError::InvalidPolicyFilter { message, .. })
+                if message == "the relationship 'owner' cannot be used in a policy"
+        ));
+    }
+
+    #[test]
+    fn sequences_named_like_native_mutations_are_rejected() {
+        let mut parsed_config = ParsedConfiguration::empty();
+        parsed_config.metadata.sequences.0.insert(
+            "report_id_seq".into(),
+            serde_json::from_value(serde_json::json!({
+                "schemaName": "public",
+                "sequenceName": "report_id_seq",
+            }))
+            .unwrap(),
+        );
+        parsed_config.metadata.native_operations.mutations.0.insert(
+            "nextval_report_id_seq".into(),
+            serde_json::from_value(serde_json::json!({
+                "sql": { "inline": "SELECT 1 AS id" },
+                "columns": { "id": { "name": "id", "type": { "scalarType": "int4" } } },
+            }))
+            .unwrap(),
+        );
+
+        let result = make_runtime_configuration(parsed_config, environment());
+
+        assert!(matches!(
+            result,
+            Err(MakeRuntimeConfigurationError::SequenceProcedureConflict { sequence, procedure, .. })
+                if sequence == "report_id_seq" && procedure.as_str() == "nextval_report_id_seq"
+        ));
+    }
+
+    fn native_operation(isolation_level: &str) -> metadata::NativeQueryInfo {
+        serde_json::from_value(serde_json::json!({
+            "sql": { "inline": "SELECT 1 AS id" },
+            "columns": { "id": { "name": "id", "type": { "scalarType": "int4" } } },
+            "isolationLevel": isolation_level,
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn isolation_levels_of_native_mutations_are_kept() {
+        let mut parsed_config = ParsedConfiguration::empty();
+        parsed_config
+            .metadata
+            .native_operations
+            .mutations
+            .0
+            .insert("archive_reports".into(), native_operation("Serializable"));
+
+        let configuration = make_runtime_configuration(parsed_config, environment()).unwrap();
+
+        assert_eq!(
+            configuration.mutation_isolation_levels["archive_reports"],
+            crate::values::IsolationLevel::Serializable
+        );
+    }
+
+    #[test]
+    fn isolation_levels_of_native_queries_are_rejected() {
+        let mut parsed_config = ParsedConfiguration::empty();
+        parsed_config
+            .metadata
+            .native_operations
+            .queries
+            .0
+            .insert("report_ids".into(), native_operation("Serializable"));
+
+        let result = make_runtime_configuration(parsed_config, environment());
+
+        assert!(matches!(
+            result,
+            Err(MakeRuntimeConfigurationError::NativeQueryIsolationLevel { collection, .. })
+                if collection.as_str() == "report_ids"
+        ));
+    }
+
+    #[test]
+    fn columns_whose_default_generates_uuids_generate_uuids() {
+        let column = |default_expression: Option<&str>, generates_uuid: bool| {
+            convert_column_info(
+                serde_json::from_value(serde_json::json!({
+                    "name": "id",
+                    "type": { "scalarType": "uuid" },
+                    "hasDefault": if default_expression.is_some() { "hasDefault" } else { "noDefault" },
+                    "defaultExpression": default_expression,
+                    "generatesUuid": generates_uuid,
+                }))
+                .unwrap(),
+            )
+            .generates_uuid
+        };
+
+        assert!(column(Some("gen_random_uuid()"), false));
+        assert!(column(Some("public.uuid_generate_v4()"), false));
+        assert!(column(None, true));
+        assert!(!column(
+            Some("'00000000-0000-0000-0000-000000000000'::uuid"),
+            false
+        ));
+        // a default which was dropped no longer generates them.
+        assert!(!column(None, false));
+    }
+}
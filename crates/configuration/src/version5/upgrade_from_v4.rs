@@ -7,6 +7,7 @@ use super::connection_settings;
 use super::metadata;
 use super::options;
 use super::ParsedConfiguration;
+use crate::values::EnvironmentValue;
 use crate::version4;
 
 pub fn upgrade_from_v4(v: version4::ParsedConfiguration) -> super::ParsedConfiguration {
@@ -27,6 +28,34 @@ pub fn upgrade_from_v4(v: version4::ParsedConfiguration) -> super::ParsedConfigu
         metadata: upgrade_metadata(metadata),
         mutations_version: mutations_version.map(upgrade_mutations_version),
         mutations_prefix: Some(String::new()), // default to no prefixes
+        mutations_returning_aggregates: false,
+        dialect: super::Dialect::default(),
+        use_lateral_joins: true,
+        limit_with_ties: false,
+        strict_boolean_comparisons: false,
+        snapshot_reads: false,
+        distinct_on: false,
+        temporary_variables_table: false,
+        type_overrides: vec![],
+        planner_hints: BTreeMap::new(),
+        collection_policies: BTreeMap::new(),
+        distributed_query_routing: None,
+        count_estimation: None,
+        verify_queries: false,
+        json_tables: BTreeMap::new(),
+        annotate_queries: false,
+        slow_query_threshold_ms: None,
+        log_slow_query_parameters: false,
+        query_timeout_ms: None,
+        query_limits: None,
+        retry_policy: None,
+        mutation_failure_policy: None,
+        usage_statistics: None,
+        request_recording: None,
+        relationship_naming: None,
+        watch_native_query_files: false,
+        split_metadata: false,
+        format: super::ConfigurationFormat::default(),
     }
 }
 
@@ -43,18 +72,27 @@ fn ugrade_introspection_options(
     } = introspection_options;
 
     options::IntrospectionOptions {
-        excluded_schemas,
-        unqualified_schemas_for_tables,
-        unqualified_schemas_for_types_and_procedures,
+        excluded_schemas: upgrade_schemas(excluded_schemas),
+        unqualified_schemas_for_tables: upgrade_schemas(unqualified_schemas_for_tables),
+        unqualified_schemas_for_types_and_procedures: upgrade_schemas(
+            unqualified_schemas_for_types_and_procedures,
+        ),
         comparison_operator_mapping: comparison_operator_mapping
             .into_iter()
             .map(upgrade_comparison_operator_mapping)
             .collect(),
         introspect_prefix_function_comparison_operators,
         type_representations: upgrade_type_representations(&type_representations),
+        inherited_tables: options::InheritedTables::default(),
+        naming_convention: options::NamingConvention::default(),
+        introspect_sequences: false,
     }
 }
 
+fn upgrade_schemas(schemas: Vec<String>) -> Vec<EnvironmentValue<String>> {
+    schemas.into_iter().map(EnvironmentValue::from).collect()
+}
+
 fn upgrade_comparison_operator_mapping(
     comparison_operator_mapping: version4::comparison::ComparisonOperatorMapping,
 ) -> comparison::ComparisonOperatorMapping {
@@ -68,6 +106,7 @@ fn upgrade_comparison_operator_mapping(
         operator_name,
         exposed_name,
         operator_kind: upgrade_operator_kind(operator_kind),
+        type_name: None,
     }
 }
 
@@ -104,9 +143,13 @@ fn upgrade_connection_settings(
     } = connection_settings;
 
     connection_settings::DatabaseConnectionSettings {
-        connection_uri,
+        connection_uri: connection_settings::DatabaseConnectionUri::Uri(connection_uri),
         pool_settings,
-        isolation_level,
+        isolation_level: isolation_level.into(),
+        read_replicas: None,
+        health_check: None,
+        request_id_setting: None,
+        targets: BTreeMap::new(),
     }
 }
 
@@ -133,6 +176,8 @@ fn upgrade_metadata(metadata: version4::metadata::Metadata) -> metadata::Metadat
             composite: upgraded_composite_types,
         },
         native_operations: upgraded_native_operations,
+        sequences: metadata::Sequences::default(),
+        naming_convention: options::NamingConvention::default(),
     }
 }
 
@@ -186,6 +231,8 @@ fn upgrade_native_query_info(
             })
             .collect(),
         description,
+        foreign_relations: metadata::ForeignRelations::default(),
+        isolation_level: None,
     }
 }
 
@@ -349,7 +396,10 @@ fn upgrade_field_info(field_info: version4::metadata::FieldInfo) -> metadata::Fi
     metadata::FieldInfo {
         field_name,
         r#type: upgrade_type(r#type),
+        nullable: metadata::Nullable::Nullable,
+        has_default: metadata::HasDefault::NoDefault,
         description,
+        position: None,
     }
 }
 
@@ -503,6 +553,20 @@ fn upgrade_table_info(table_info: version4::metadata::TableInfo) -> metadata::Ta
         uniqueness_constraints: upgrade_uniqueness_constraints(uniqueness_constraints),
         foreign_relations: upgrade_foreign_relations(foreign_relations),
         description,
+        inherited_rows: metadata::InheritedRows::default(),
+        target: None,
+        statement_timeout_ms: None,
+        logical_keys: metadata::UniquenessConstraints::default(),
+        row_expiry: None,
+        soft_delete: None,
+        audit_columns: BTreeMap::new(),
+        computed_relationships: BTreeMap::new(),
+        computed_fields: BTreeMap::new(),
+        insertable: true,
+        updatable: true,
+        deletable: true,
+        mutable: None,
+        distribution: None,
     }
 }
 
@@ -571,8 +635,15 @@ fn upgrade_column_info(column_info: version4::metadata::ColumnInfo) -> metadata:
         r#type: upgraded_type,
         nullable: upgrade_nullable(nullable),
         has_default: upgrade_has_default(has_default),
+        default_expression: None,
         is_identity: upgrade_is_identity(is_identity),
         is_generated: upgrade_is_generated(is_generated),
+        generates_uuid: false,
+        selectable: true,
+        insertable: true,
+        updatable: true,
+        filterable: true,
+        sortable: true,
         description,
     }
 }
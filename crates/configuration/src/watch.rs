@@ -0,0 +1,254 @@
+//! Watch the SQL files Native Operations are read from, so that changes to them can be applied
+//! without restarting.
+//!
+//! Files are polled for changes to their modification times, rather than watched with the
+//! facilities of the operating system, which do not work across all the filesystems
+//! configurations are mounted from.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use query_engine_metadata::metadata;
+use sha2::{Digest, Sha256};
+
+use crate::configuration::{hex, Configuration};
+use crate::{validate, version5};
+
+/// How often to check the files for changes.
+pub const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// The SQL files of the Native Operations of a configuration, and when they were last modified.
+#[derive(Debug, Clone)]
+pub struct NativeQueryFiles {
+    configuration_directory: PathBuf,
+    modified: BTreeMap<PathBuf, Option<SystemTime>>,
+}
+
+impl NativeQueryFiles {
+    /// The files the Native Operations are read from, relative to the configuration directory.
+    pub fn new(
+        configuration_directory: impl Into<PathBuf>,
+        native_operations: &metadata::NativeOperations,
+    ) -> Self {
+        let configuration_directory = configuration_directory.into();
+        let modified = native_query_infos(native_operations)
+            .filter_map(|(_, info)| sql_file(info))
+            .map(|file| {
+                let modified = modified(&configuration_directory.join(file));
+                (file.to_path_buf(), modified)
+            })
+            .collect();
+        NativeQueryFiles {
+            configuration_directory,
+            modified,
+        }
+    }
+
+    /// Whether there are no files to watch.
+    pub fn is_empty(&self) -> bool {
+        self.modified.is_empty()
+    }
+
+    /// The files which were modified, created or deleted since they were last checked.
+    pub fn changed(&mut self) -> Vec<PathBuf> {
+        let mut changed = vec![];
+        for (file, last_modified) in &mut self.modified {
+            let modified = modified(&self.configuration_directory.join(file));
+            if modified != *last_modified {
+                *last_modified = modified;
+                changed.push(file.clone());
+            }
+        }
+        changed
+    }
+
+    /// Read the SQL of the Native Operations of a configuration from their files again, and check
+    /// the configuration with it as `validate` does, so that SQL which refers to parameters which
+    /// are not arguments is caught along with every other problem. Returns every problem found,
+    /// in which case the configuration should be kept as it was.
+    ///
+    /// The fingerprint of the configuration combines that of the configuration it was read from
+    /// with the SQL read, so that replicas serving different SQL can be told apart.
+    pub fn reload(&self, configuration: &Configuration) -> Result<Configuration, Vec<String>> {
+        let native_operations = &configuration.metadata.native_operations;
+        let mut errors = vec![];
+        let mut fingerprint = Sha256::new();
+        fingerprint.update(configuration.fingerprint());
+        let mut reload = |info: &metadata::NativeQueryInfo| {
+            let Some(file) = sql_file(info) else {
+                return info.clone();
+            };
+            match version5::metadata::parse_native_query_from_file(
+                &self.configuration_directory,
+                file,
+            ) {
+                Err(error) => {
+                    errors.push(error);
+                    info.clone()
+                }
+                Ok(sql) => {
+                    let sql = sql.sql();
+                    fingerprint.update(file.to_string_lossy().as_bytes());
+                    fingerprint.update(String::from(sql.clone()));
+                    metadata::NativeQueryInfo {
+                        sql: metadata::NativeQuerySqlEither::NativeQuerySql(
+                            metadata::NativeQuerySql::FromFile {
+                                file: file.to_path_buf(),
+                                sql: version5::to_runtime_configuration::convert_native_query_parts(
+                                    sql,
+                                ),
+                            },
+                        ),
+                        ..info.clone()
+                    }
+                }
+            }
+        };
+        let mut reloaded = configuration.clone();
+        reloaded.metadata.native_operations = metadata::NativeOperations {
+            queries: metadata::NativeQueries(
+                native_operations
+                    .queries
+                    .0
+                    .iter()
+                    .map(|(name, info)| (name.clone(), reload(info)))
+                    .collect(),
+            ),
+            mutations: metadata::NativeMutations(
+                native_operations
+                    .mutations
+                    .0
+                    .iter()
+                    .map(|(name, info)| (name.clone(), reload(info)))
+                    .collect(),
+            ),
+        };
+        if !errors.is_empty() {
+            return Err(errors);
+        }
+        let problems = validate::check_metadata(&reloaded);
+        if !problems.is_empty() {
+            return Err(problems);
+        }
+        reloaded.configuration_hash = hex(&fingerprint.finalize());
+        Ok(reloaded)
+    }
+}
+
+/// The names and information of all Native Operations.
+fn native_query_infos(
+    native_operations: &metadata::NativeOperations,
+) -> impl Iterator<Item = (&str, &metadata::NativeQueryInfo)> {
+    let queries = native_operations
+        .queries
+        .0
+        .iter()
+        .map(|(name, info)| (name.as_str(), info));
+    let mutations = native_operations
+        .mutations
+        .0
+        .iter()
+        .map(|(name, info)| (name.as_str(), info));
+    queries.chain(mutations)
+}
+
+/// The file the SQL of a Native Operation is read from, if any.
+fn sql_file(info: &metadata::NativeQueryInfo) -> Option<&Path> {
+    match &info.sql {
+        metadata::NativeQuerySqlEither::NativeQuerySql(metadata::NativeQuerySql::FromFile {
+            file,
+            ..
+        })
+        | metadata::NativeQuerySqlEither::NativeQuerySqlExternal(
+            metadata::NativeQuerySqlExternal::File { file },
+        ) => Some(file),
+        _ => None,
+    }
+}
+
+/// When a file was last modified, or `None` if it cannot be read.
+fn modified(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use query_engine_metadata::metadata;
+
+    use super::NativeQueryFiles;
+    use crate::environment::FixedEnvironment;
+
+    const CONFIGURATION: &str = r#"{
+      "version": "5",
+      "metadata": {
+        "tables": {},
+        "types": { "scalar": {}, "composite": {} },
+        "nativeOperations": {
+          "queries": {
+            "album": {
+              "sql": { "file": "album.sql" },
+              "columns": {},
+              "arguments": {},
+              "description": null
+            }
+          },
+          "mutations": {}
+        }
+      }
+    }"#;
+
+    #[tokio::test]
+    async fn reloads_and_checks_changed_sql() {
+        let directory = tempfile::tempdir().unwrap();
+        std::fs::write(directory.path().join("configuration.json"), CONFIGURATION).unwrap();
+        let file = directory.path().join("album.sql");
+        std::fs::write(&file, "SELECT 1 AS one").unwrap();
+
+        let parsed_configuration = crate::parse_configuration(directory.path()).await.unwrap();
+        let configuration = crate::make_runtime_configuration(
+            parsed_configuration,
+            FixedEnvironment::from([(
+                "CONNECTION_URI".into(),
+                "postgresql://localhost".to_string(),
+            )]),
+        )
+        .unwrap();
+        let mut files =
+            NativeQueryFiles::new(directory.path(), &configuration.metadata.native_operations);
+        assert!(files.changed().is_empty());
+
+        std::fs::write(&file, "SELECT 2 AS one").unwrap();
+        let reloaded = files.reload(&configuration).unwrap();
+        assert_eq!(
+            reloaded.metadata.native_operations.queries.0["album"]
+                .sql
+                .clone()
+                .sql()
+                .unwrap()
+                .0,
+            vec![metadata::NativeQueryPart::Text(
+                "SELECT 2 AS one".to_string()
+            )]
+        );
+        assert_ne!(reloaded.fingerprint(), configuration.fingerprint());
+        assert_eq!(
+            files.reload(&configuration).unwrap().fingerprint(),
+            reloaded.fingerprint()
+        );
+
+        std::fs::write(&file, "SELECT * FROM album WHERE title = {{title}}").unwrap();
+        assert_eq!(
+            files.reload(&configuration).unwrap_err(),
+            vec!["the SQL of native query 'album' refers to '{{title}}', which is not one of its arguments"]
+        );
+
+        std::fs::remove_file(&file).unwrap();
+        assert_eq!(files.changed(), vec![PathBuf::from("album.sql")]);
+        assert!(files.reload(&configuration).is_err());
+    }
+}
@@ -0,0 +1,122 @@
+//! Splitting the names of the database into words, and joining them again in another case, for
+//! the names the configuration derives from them.
+
+/// The case words are joined in.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub(crate) enum WordCase {
+    /// `invoiceLines`
+    Camel,
+    /// `InvoiceLines`
+    Pascal,
+    /// `invoice_lines`
+    Snake,
+}
+
+/// Split a name such as `invoice_line` or `InvoiceLine` into its words. An acronym is a word of
+/// its own, as in `HTTPRequest`, which is split into `HTTP` and `Request`.
+pub(crate) fn split(name: &str) -> Vec<String> {
+    let mut words = vec![];
+    let mut word = String::new();
+    let chars: Vec<char> = name.chars().collect();
+    for (index, &c) in chars.iter().enumerate() {
+        if !c.is_alphanumeric() {
+            if !word.is_empty() {
+                words.push(std::mem::take(&mut word));
+            }
+            continue;
+        }
+        if c.is_uppercase() && !word.is_empty() {
+            let previous = chars[index - 1];
+            let next_is_lowercase = chars.get(index + 1).is_some_and(|next| next.is_lowercase());
+            if previous.is_lowercase()
+                || previous.is_numeric()
+                || (previous.is_uppercase() && next_is_lowercase)
+            {
+                words.push(std::mem::take(&mut word));
+            }
+        }
+        word.push(c);
+    }
+    if !word.is_empty() {
+        words.push(word);
+    }
+    words
+}
+
+/// Join words in a case. Acronyms, words in capitals within a name which is not all in capitals,
+/// keep their case unless they begin a camelCase name, as in `ownerID`.
+pub(crate) fn join(case: WordCase, words: &[String]) -> String {
+    let name_is_uppercase = words.iter().all(|word| is_uppercase(word));
+    let capitalize_word = |word: &String| {
+        if !name_is_uppercase && word.chars().count() > 1 && is_uppercase(word) {
+            word.clone()
+        } else {
+            capitalize(word)
+        }
+    };
+    match case {
+        WordCase::Snake => words
+            .iter()
+            .map(|word| word.to_lowercase())
+            .collect::<Vec<_>>()
+            .join("_"),
+        WordCase::Pascal => words.iter().map(capitalize_word).collect(),
+        WordCase::Camel => words
+            .iter()
+            .enumerate()
+            .map(|(index, word)| {
+                if index == 0 {
+                    word.to_lowercase()
+                } else {
+                    capitalize_word(word)
+                }
+            })
+            .collect(),
+    }
+}
+
+/// Whether a word has letters and they are all capitals.
+fn is_uppercase(word: &str) -> bool {
+    word.chars().any(char::is_alphabetic) && !word.chars().any(char::is_lowercase)
+}
+
+/// Capitalize the first letter of a word, and lowercase the rest.
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        None => String::new(),
+        Some(first) => first
+            .to_uppercase()
+            .chain(chars.flat_map(char::to_lowercase))
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names_are_split_into_words() {
+        assert_eq!(split("invoice_line"), vec!["invoice", "line"]);
+        assert_eq!(split("InvoiceLine"), vec!["Invoice", "Line"]);
+        assert_eq!(split("HTTPRequest"), vec!["HTTP", "Request"]);
+        assert_eq!(split("ownerID"), vec!["owner", "ID"]);
+        assert_eq!(split("album2track"), vec!["album2track"]);
+    }
+
+    #[test]
+    fn acronyms_keep_their_case() {
+        let words = split("ownerID");
+        assert_eq!(join(WordCase::Camel, &words), "ownerID");
+        assert_eq!(join(WordCase::Pascal, &words), "OwnerID");
+        assert_eq!(join(WordCase::Snake, &words), "owner_id");
+        assert_eq!(join(WordCase::Camel, &split("HTTPRequest")), "httpRequest");
+        // names entirely in capitals are not made of acronyms
+        assert_eq!(join(WordCase::Camel, &split("INVOICE_LINE")), "invoiceLine");
+        assert_eq!(
+            join(WordCase::Pascal, &split("INVOICE_LINE")),
+            "InvoiceLine"
+        );
+    }
+}
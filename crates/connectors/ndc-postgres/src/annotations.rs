@@ -0,0 +1,93 @@
+//! Annotate the SQL we run with the request it was run for.
+//!
+//! When `annotateQueries` is enabled, every statement ends in a comment in the
+//! [sqlcommenter](https://google.github.io/sqlcommenter/spec/) format, holding the endpoint the
+//! request came in on, and the `request_id` (the trace ID) and W3C `traceparent` of the current
+//! span. The comment shows up in the database's slow-query logs and `pg_stat_activity`, which
+//! lets us find the connector trace behind a statement. As this makes the SQL of every request
+//! unique, annotated statements are not cached as prepared statements.
+//!
+//! When `requestIdSetting` is configured, each request also runs in a transaction which sets
+//! that run-time parameter (usually `application_name`) to the trace ID, so that the sessions
+//! serving a request can be told apart while it runs, and in `log_line_prefix` output.
+
+use opentelemetry::trace::TraceContextExt;
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+
+use ndc_postgres_configuration as configuration;
+use query_engine_sql::sql;
+use query_engine_sql::sql::execution_plan::Annotations;
+
+use crate::configuration_mapping;
+
+/// The annotations for the statements run on behalf of the current span, if enabled.
+pub fn annotations(configuration: &configuration::Configuration, route: &str) -> Annotations {
+    let mut annotations = Annotations::new();
+    if configuration.annotate_queries {
+        annotations.insert("route".to_string(), route.to_string());
+        if let Some(span_context) = current_span_context() {
+            annotations.insert(
+                "request_id".to_string(),
+                span_context.trace_id().to_string(),
+            );
+            annotations.insert("traceparent".to_string(), traceparent(&span_context));
+        }
+    }
+    annotations
+}
+
+/// Tag a query plan with the trace ID of the current span, if enabled.
+///
+/// Queries do not otherwise run in a transaction, unless they set a statement timeout of their
+/// own, so we wrap them in a read-only one for the setting to be undone when they finish.
+pub fn tag_query(
+    configuration: &configuration::Configuration,
+    plan: &mut sql::execution_plan::ExecutionPlan<sql::execution_plan::Query>,
+) {
+    if let Some(statement) = request_id_statement(configuration) {
+        if plan.pre.is_empty() {
+            plan.pre = sql::helpers::begin(
+                configuration_mapping::convert_isolation_level(configuration.isolation_level),
+                sql::ast::transaction::TransactionMode::ReadOnly,
+            );
+            plan.post = sql::helpers::commit();
+        }
+        plan.pre.push(statement);
+    }
+}
+
+/// Tag a mutations plan with the trace ID of the current span, if enabled.
+/// Mutations already run in a transaction, which we set the parameter at the start of.
+pub fn tag_mutations(
+    configuration: &configuration::Configuration,
+    plan: &mut sql::execution_plan::ExecutionPlan<sql::execution_plan::Mutations>,
+) {
+    if let Some(statement) = request_id_statement(configuration) {
+        plan.pre.push(statement);
+    }
+}
+
+fn request_id_statement(
+    configuration: &configuration::Configuration,
+) -> Option<sql::string::Statement> {
+    let setting = configuration.request_id_setting.as_ref()?;
+    let trace_id = current_span_context()?.trace_id().to_string();
+    Some(sql::helpers::set_local(setting.clone(), trace_id))
+}
+
+/// The W3C trace context of a span.
+fn traceparent(span_context: &opentelemetry::trace::SpanContext) -> String {
+    format!(
+        "00-{}-{}-{:02x}",
+        span_context.trace_id(),
+        span_context.span_id(),
+        span_context.trace_flags().to_u8()
+    )
+}
+
+/// The OpenTelemetry context of the current span, if it is being traced.
+fn current_span_context() -> Option<opentelemetry::trace::SpanContext> {
+    let context = tracing::Span::current().context();
+    let span_context = context.span().span_context().clone();
+    span_context.is_valid().then_some(span_context)
+}
@@ -18,3 +18,36 @@ pub(crate) fn convert_isolation_level(
         }
     }
 }
+
+/// Convert the configured policy for failing mutation operations into the policy the execution
+/// plan of mutations follows.
+pub(crate) fn convert_mutation_failure_policy(
+    input: ndc_postgres_configuration::MutationFailurePolicy,
+) -> query_engine_sql::sql::execution_plan::FailurePolicy {
+    match input {
+        ndc_postgres_configuration::MutationFailurePolicy::AllOrNothing => {
+            query_engine_sql::sql::execution_plan::FailurePolicy::AllOrNothing
+        }
+        ndc_postgres_configuration::MutationFailurePolicy::BestEffort => {
+            query_engine_sql::sql::execution_plan::FailurePolicy::BestEffort
+        }
+    }
+}
+
+/// Convert the configured query timeout into the duration after which execution cancels a
+/// statement.
+pub(crate) fn convert_query_timeout(input: Option<u64>) -> Option<std::time::Duration> {
+    input.map(std::time::Duration::from_millis)
+}
+
+/// Convert the configured retry policy into the one execution follows.
+pub(crate) fn convert_retry_policy(
+    input: ndc_postgres_configuration::RetryPolicy,
+) -> query_engine_execution::retry::RetryPolicy {
+    query_engine_execution::retry::RetryPolicy {
+        max_attempts: input.max_attempts,
+        initial_backoff: std::time::Duration::from_millis(input.initial_backoff_ms),
+        max_backoff: std::time::Duration::from_millis(input.max_backoff_ms),
+        jitter: input.jitter,
+    }
+}
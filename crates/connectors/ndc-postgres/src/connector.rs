@@ -17,10 +17,13 @@ use ndc_postgres_configuration as configuration;
 use ndc_postgres_configuration::environment::Environment;
 
 use super::capabilities;
+use super::health;
 use super::mutation;
 use super::query;
 use super::schema;
 use super::state;
+use super::usage;
+use super::watch;
 
 pub struct Postgres;
 
@@ -55,23 +58,31 @@ impl Connector for Postgres {
     ///
     /// This function implements the [schema endpoint](https://hasura.github.io/ndc-spec/specification/schema/index.html)
     /// from the NDC specification.
+    ///
+    /// Unlike the other endpoints, this is not given the state, so it cannot see Native Operation
+    /// SQL reloaded by [watch]. That only changes the SQL they run, which the schema does not
+    /// describe: their arguments and columns, and so the schema, stay as they were.
     async fn get_schema(
         configuration: &Self::Configuration,
     ) -> Result<JsonResponse<models::SchemaResponse>> {
-        schema::get_schema(configuration)
-            .map_err(|err| {
-                tracing::error!(
-                    meta.signal_type = "log",
-                    event.domain = "ndc",
-                    event.name = "Schema error",
-                    name = "Schema error",
-                    body = %err,
-                    error = true,
-                    "Schema error",
-                );
-                err
-            })
-            .map(Into::into)
+        info_span!(
+            "/schema",
+            configuration.fingerprint = configuration.fingerprint()
+        )
+        .in_scope(|| schema::get_schema(configuration))
+        .map_err(|err| {
+            tracing::error!(
+                meta.signal_type = "log",
+                event.domain = "ndc",
+                event.name = "Schema error",
+                name = "Schema error",
+                body = %err,
+                error = true,
+                "Schema error",
+            );
+            err
+        })
+        .map(Into::into)
     }
 
     /// Explain a query by creating an execution plan
@@ -83,7 +94,7 @@ impl Connector for Postgres {
         state: &Self::State,
         request: models::QueryRequest,
     ) -> Result<JsonResponse<models::ExplainResponse>> {
-        query::explain(configuration, state, request)
+        query::explain(&state.configuration(configuration), state, request)
             .await
             .map_err(|err| {
                 tracing::error!(
@@ -109,7 +120,7 @@ impl Connector for Postgres {
         state: &Self::State,
         request: models::MutationRequest,
     ) -> Result<JsonResponse<models::ExplainResponse>> {
-        mutation::explain(configuration, state, request)
+        mutation::explain(&state.configuration(configuration), state, request)
             .await
             .map_err(|err| {
                 tracing::error!(
@@ -135,7 +146,7 @@ impl Connector for Postgres {
         state: &Self::State,
         request: models::MutationRequest,
     ) -> Result<JsonResponse<models::MutationResponse>> {
-        mutation::mutation(configuration, state, request)
+        mutation::mutation(&state.configuration(configuration), state, request)
             .await
             .map_err(|err| {
                 tracing::error!(
@@ -160,7 +171,7 @@ impl Connector for Postgres {
         state: &Self::State,
         query_request: models::QueryRequest,
     ) -> Result<JsonResponse<models::QueryResponse>> {
-        query::query(configuration, state, query_request)
+        query::query(&state.configuration(configuration), state, query_request)
             .await
             .map_err(|err| {
                 tracing::error!(
@@ -200,6 +211,7 @@ impl<Env: Environment + Send + Sync> ConnectorSetup for PostgresSetup<Env> {
         // Note that we don't log validation errors, because they are part of the normal business
         // operation of configuration validation, i.e. they don't represent an error condition that
         // signifies that anything has gone wrong with the ndc process or infrastructure.
+        let configuration_directory = configuration_dir.as_ref().to_path_buf();
         let parsed_configuration = configuration::parse_configuration(configuration_dir)
             .instrument(info_span!("parse configuration"))
             .await
@@ -232,6 +244,17 @@ impl<Env: Environment + Send + Sync> ConnectorSetup for PostgresSetup<Env> {
                 configuration::error::ParseConfigurationError::IoErrorButStringified(inner) => {
                     inner.into()
                 }
+                configuration::error::ParseConfigurationError::DuplicateMetadataEntry {
+                    ref file_path,
+                    ..
+                } => connector::ParseError::ValidateError(connector::InvalidNodes(vec![
+                    connector::InvalidNode {
+                        file_path: file_path.clone(),
+                        node_path: vec![],
+                        message: error.to_string(),
+                    },
+                ]))
+                .into(),
                 configuration::error::ParseConfigurationError::DidNotFindExpectedVersionTag(_)
                 | configuration::error::ParseConfigurationError::UnableToParseAnyVersions(_) => {
                     connector::ErrorResponse::from_error(error)
@@ -259,10 +282,212 @@ impl<Env: Environment + Send + Sync> ConnectorSetup for PostgresSetup<Env> {
                     message,
                 },
             ])),
+            configuration::error::MakeRuntimeConfigurationError::InvalidEnvironmentVariable {
+                file_path,
+                variable,
+                message,
+            } => connector::ParseError::ValidateError(connector::InvalidNodes(vec![
+                connector::InvalidNode {
+                    file_path,
+                    node_path: vec![connector::KeyOrIndex::Key("connectionSettings".into())],
+                    message: format!("invalid value in {variable}: {message}"),
+                },
+            ])),
+            error @ configuration::error::MakeRuntimeConfigurationError::ConfigurationDigest {
+                ..
+            } => connector::ParseError::Other(Box::new(error)),
+            configuration::error::MakeRuntimeConfigurationError::InvalidPlannerHint {
+                file_path,
+                collection,
+            } => connector::ParseError::ValidateError(connector::InvalidNodes(vec![
+                connector::InvalidNode {
+                    file_path,
+                    node_path: vec![
+                        connector::KeyOrIndex::Key("plannerHints".into()),
+                        connector::KeyOrIndex::Key(collection.to_string()),
+                    ],
+                    message: "a planner hint cannot contain '*/'".to_string(),
+                },
+            ])),
+            configuration::error::MakeRuntimeConfigurationError::InvalidLagCheckInterval {
+                file_path,
+            } => connector::ParseError::ValidateError(connector::InvalidNodes(vec![
+                connector::InvalidNode {
+                    file_path,
+                    node_path: vec![
+                        connector::KeyOrIndex::Key("connectionSettings".into()),
+                        connector::KeyOrIndex::Key("readReplicas".into()),
+                        connector::KeyOrIndex::Key("lagCheckInterval".into()),
+                    ],
+                    message: "the lag check interval must be at least one second".to_string(),
+                },
+            ])),
+            configuration::error::MakeRuntimeConfigurationError::InvalidHealthCheckInterval {
+                file_path,
+            } => connector::ParseError::ValidateError(connector::InvalidNodes(vec![
+                connector::InvalidNode {
+                    file_path,
+                    node_path: vec![
+                        connector::KeyOrIndex::Key("connectionSettings".into()),
+                        connector::KeyOrIndex::Key("healthCheck".into()),
+                        connector::KeyOrIndex::Key("interval".into()),
+                    ],
+                    message: "the health check interval must be at least one second".to_string(),
+                },
+            ])),
+            configuration::error::MakeRuntimeConfigurationError::InvalidTypeOverrideCast {
+                file_path,
+                schema_name,
+                type_name,
+                cast_as,
+            } => connector::ParseError::ValidateError(connector::InvalidNodes(vec![
+                connector::InvalidNode {
+                    file_path,
+                    node_path: vec![connector::KeyOrIndex::Key("typeOverrides".into())],
+                    message: format!(
+                        "the override of {schema_name}.{type_name} casts values to '{cast_as}', which is not the name of a built-in type"
+                    ),
+                },
+            ])),
+            configuration::error::MakeRuntimeConfigurationError::UnknownForeignTable {
+                file_path,
+                native_query,
+                constraint,
+                foreign_table,
+            } => connector::ParseError::ValidateError(connector::InvalidNodes(vec![
+                connector::InvalidNode {
+                    file_path,
+                    node_path: vec![
+                        connector::KeyOrIndex::Key("metadata".into()),
+                        connector::KeyOrIndex::Key("nativeOperations".into()),
+                        connector::KeyOrIndex::Key("queries".into()),
+                        connector::KeyOrIndex::Key(native_query.to_string()),
+                        connector::KeyOrIndex::Key("foreignRelations".into()),
+                        connector::KeyOrIndex::Key(constraint),
+                    ],
+                    message: format!(
+                        "the table '{foreign_table}' is not in the metadata"
+                    ),
+                },
+            ])),
+            configuration::error::MakeRuntimeConfigurationError::UnknownTarget {
+                file_path,
+                collection,
+                target,
+            } => connector::ParseError::ValidateError(connector::InvalidNodes(vec![
+                connector::InvalidNode {
+                    file_path,
+                    node_path: vec![
+                        connector::KeyOrIndex::Key("metadata".into()),
+                        connector::KeyOrIndex::Key("tables".into()),
+                        connector::KeyOrIndex::Key(collection.to_string()),
+                        connector::KeyOrIndex::Key("target".into()),
+                    ],
+                    message: format!(
+                        "there is no target named '{target}' in the connection settings"
+                    ),
+                },
+            ])),
+            configuration::error::MakeRuntimeConfigurationError::UnknownLogicalKeyField {
+                file_path,
+                collection,
+                logical_key,
+                field,
+            } => connector::ParseError::ValidateError(connector::InvalidNodes(vec![
+                connector::InvalidNode {
+                    file_path,
+                    node_path: vec![
+                        connector::KeyOrIndex::Key("metadata".into()),
+                        connector::KeyOrIndex::Key("tables".into()),
+                        connector::KeyOrIndex::Key(collection.to_string()),
+                        connector::KeyOrIndex::Key("logicalKeys".into()),
+                        connector::KeyOrIndex::Key(logical_key),
+                    ],
+                    message: format!("the field '{field}' is not a column of the table"),
+                },
+            ])),
+            configuration::error::MakeRuntimeConfigurationError::UnknownPolicyCollection {
+                file_path,
+                collection,
+            } => connector::ParseError::ValidateError(connector::InvalidNodes(vec![
+                connector::InvalidNode {
+                    file_path,
+                    node_path: vec![
+                        connector::KeyOrIndex::Key("collectionPolicies".into()),
+                        connector::KeyOrIndex::Key(collection.to_string()),
+                    ],
+                    message: "there is no table or native query with this name".to_string(),
+                },
+            ])),
+            configuration::error::MakeRuntimeConfigurationError::NativeQueryIsolationLevel {
+                file_path,
+                collection,
+            } => connector::ParseError::ValidateError(connector::InvalidNodes(vec![
+                connector::InvalidNode {
+                    file_path,
+                    node_path: vec![
+                        connector::KeyOrIndex::Key("metadata".into()),
+                        connector::KeyOrIndex::Key("nativeOperations".into()),
+                        connector::KeyOrIndex::Key("queries".into()),
+                        connector::KeyOrIndex::Key(collection.to_string()),
+                        connector::KeyOrIndex::Key("isolationLevel".into()),
+                    ],
+                    message: "only native mutations can have an isolation level".to_string(),
+                },
+            ])),
+            configuration::error::MakeRuntimeConfigurationError::SequenceProcedureConflict {
+                file_path,
+                sequence,
+                procedure,
+            } => connector::ParseError::ValidateError(connector::InvalidNodes(vec![
+                connector::InvalidNode {
+                    file_path,
+                    node_path: vec![
+                        connector::KeyOrIndex::Key("metadata".into()),
+                        connector::KeyOrIndex::Key("sequences".into()),
+                        connector::KeyOrIndex::Key(sequence),
+                    ],
+                    message: format!(
+                        "the procedure '{procedure}' of this sequence has the same name as a native mutation"
+                    ),
+                },
+            ])),
+            configuration::error::MakeRuntimeConfigurationError::InvalidPolicyFilter {
+                file_path,
+                collection,
+                message,
+            } => connector::ParseError::ValidateError(connector::InvalidNodes(vec![
+                connector::InvalidNode {
+                    file_path,
+                    node_path: vec![
+                        connector::KeyOrIndex::Key("collectionPolicies".into()),
+                        connector::KeyOrIndex::Key(collection.to_string()),
+                        connector::KeyOrIndex::Key("filter".into()),
+                    ],
+                    message,
+                },
+            ])),
+            configuration::error::MakeRuntimeConfigurationError::InvalidJsonTable {
+                file_path,
+                collection,
+                message,
+            } => connector::ParseError::ValidateError(connector::InvalidNodes(vec![
+                connector::InvalidNode {
+                    file_path,
+                    node_path: vec![
+                        connector::KeyOrIndex::Key("jsonTables".into()),
+                        connector::KeyOrIndex::Key(collection.to_string()),
+                    ],
+                    message,
+                },
+            ])),
         }
                 })?;
 
-        Ok(Arc::new(runtime_configuration))
+        Ok(Arc::new(configuration::Configuration {
+            configuration_directory: Some(configuration_directory),
+            ..runtime_configuration
+        }))
     }
 
     /// Initialize the connector's in-memory state.
@@ -278,15 +503,23 @@ impl<Env: Environment + Send + Sync> ConnectorSetup for PostgresSetup<Env> {
         metrics: &mut prometheus::Registry,
     ) -> Result<<Self::Connector as Connector>::State> {
         // create the state
-        state::create_state(
+        let state = state::create_state(
             &configuration.connection_uri,
             &self.environment,
             &configuration.pool_settings,
+            configuration.read_replicas.as_ref(),
+            &configuration.targets,
+            configuration.usage_statistics.as_ref(),
             metrics,
             configuration.configuration_version_tag,
+            &configuration.configuration_hash,
         )
         .instrument(info_span!("Initialise state"))
         .await
+        .and_then(|state| {
+            state::check_json_tables(&state, configuration)?;
+            Ok(state)
+        })
         .map(Arc::new)
         .map_err(|err| {
             tracing::error!(
@@ -299,6 +532,22 @@ impl<Env: Environment + Send + Sync> ConnectorSetup for PostgresSetup<Env> {
                 "Initialization error",
             );
             connector::ErrorResponse::from_error(err)
-        })
+        })?;
+
+        if let Some(health_check) = &configuration.health_check {
+            health::spawn_health_monitor(&state, health_check);
+        }
+
+        if let Some(usage_statistics) = &configuration.usage_statistics {
+            usage::spawn_usage_reporter(
+                &state,
+                usage_statistics,
+                configuration.metadata.tables.0.keys().cloned().collect(),
+            );
+        }
+
+        watch::spawn_native_query_watcher(&state, configuration);
+
+        Ok(state)
     }
 }
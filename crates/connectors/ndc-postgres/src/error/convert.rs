@@ -1,6 +1,12 @@
 //! Functions to convert between internal error types and the error types exposed by ndc-sdk.
 
+use std::collections::BTreeMap;
+
 use ndc_sdk::connector::{self, ErrorResponse};
+use ndc_sdk::models;
+use query_engine_metadata::metadata::database::ColumnInfo;
+
+use super::database::DatabaseError;
 
 /// Convert an error from [query_engine_execution] to [ErrorResponse].
 pub fn execution_error_to_response(error: query_engine_execution::error::Error) -> ErrorResponse {
@@ -13,17 +19,77 @@ pub fn execution_error_to_response(error: query_engine_execution::error::Error)
             QueryError::NotSupported(_) => {
                 connector::QueryError::new_unsupported_operation(&query_error.to_string()).into()
             }
-            QueryError::DBError(_) => {
-                connector::QueryError::new_unprocessable_content(&query_error.to_string()).into()
-            }
-            QueryError::DBConstraintError(_) | QueryError::MutationConstraintFailed => {
+            QueryError::DBError(err) => match DatabaseError::from_sqlx(err) {
+                Some(db_err) => connector::QueryError::new_unprocessable_content(&db_err.message())
+                    .with_details(db_err.details())
+                    .into(),
+                None => connector::QueryError::new_unprocessable_content(&query_error.to_string())
+                    .into(),
+            },
+            QueryError::DBConstraintError { error: err, .. } => match DatabaseError::from_sqlx(err)
+            {
+                Some(db_err) => connector::MutationError::new_constraint_not_met(&db_err.message())
+                    .with_details(db_err.details())
+                    .into(),
+                None => connector::MutationError::new_constraint_not_met(&query_error.to_string())
+                    .into(),
+            },
+            QueryError::MutationConstraintFailed => {
                 connector::MutationError::new_constraint_not_met(&query_error.to_string()).into()
             }
+            QueryError::MutationVersionMismatch => {
+                connector::MutationError::new_conflict(&query_error.to_string()).into()
+            }
         },
         Error::DB(_) => {
             ErrorResponse::new_internal_with_details(serde_json::Value::String(error.to_string()))
         }
+        Error::Timeout(_) => {
+            connector::QueryError::new_unprocessable_content(&error.to_string()).into()
+        }
+    }
+}
+
+/// Convert a unique or check constraint violation raised by an auto-generated insert procedure to
+/// [ErrorResponse], or return `None` for other errors. Conflicts with existing rows are reported
+/// as such, so that clients can choose to update the row instead, and the details name the
+/// procedure, and the fields and values of the conflicting key where the database reported them.
+pub fn insert_error_to_response(
+    procedure: &models::ProcedureName,
+    columns: &BTreeMap<models::FieldName, ColumnInfo>,
+    error: &DatabaseError,
+) -> Option<ErrorResponse> {
+    let kind = error.kind().filter(|kind| {
+        matches!(
+            *kind,
+            "unique_violation" | "check_violation" | "exclusion_violation"
+        )
+    })?;
+    let mut details = error.details();
+    if let serde_json::Value::Object(details) = &mut details {
+        details.insert("procedure".to_string(), procedure.to_string().into());
+        if let Some((key_columns, key_values)) = error.key() {
+            let fields = key_columns
+                .iter()
+                .map(|column| {
+                    columns
+                        .iter()
+                        .find(|(_, info)| info.name == *column)
+                        .map_or(*column, |(field, _)| field.as_str())
+                })
+                .collect::<Vec<_>>();
+            details.insert("fields".to_string(), fields.into());
+            if let Some(key_values) = key_values {
+                details.insert("values".to_string(), key_values.into());
+            }
+        }
     }
+    let error = if kind == "check_violation" {
+        connector::MutationError::new_constraint_not_met(&error.message())
+    } else {
+        connector::MutationError::new_conflict(&error.message())
+    };
+    Some(error.with_details(details).into())
 }
 
 /// Convert an error from [query_engine_translation] to [connector::QueryError].
@@ -35,6 +101,22 @@ pub fn translation_error_to_response(
         Error::CapabilityNotSupported(_) | Error::NotImplementedYet(_) => {
             connector::QueryError::new_unsupported_operation(&error.to_string()).into()
         }
+        Error::QueryLimitExceeded { .. } => {
+            connector::QueryError::new_unprocessable_content(&error.to_string()).into()
+        }
         _ => connector::QueryError::new_invalid_request(&error.to_string()).into(),
     }
 }
+
+/// Convert an error from [crate::targets] to [ErrorResponse].
+pub fn target_error_to_response(error: &crate::targets::Error) -> ErrorResponse {
+    use crate::targets::Error;
+    match error {
+        Error::DifferentTargets { .. } => {
+            connector::QueryError::new_unsupported_operation(&error.to_string()).into()
+        }
+        Error::UnknownTarget { .. } => {
+            ErrorResponse::new_internal_with_details(serde_json::Value::String(error.to_string()))
+        }
+    }
+}
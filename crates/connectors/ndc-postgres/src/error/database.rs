@@ -0,0 +1,201 @@
+//! Describe the errors the database reports by their SQLSTATE code.
+//!
+//! Postgres tells us which constraint, table and column a violation concerns. We pass these on
+//! in the details of the error response, so that clients can act on them without parsing the
+//! message, and write a message of our own.
+
+/// The SQLSTATE codes of the errors we describe, and the names we describe them by.
+/// See <https://www.postgresql.org/docs/current/errcodes-appendix.html>.
+const ERROR_KINDS: &[(&str, &str)] = &[
+    ("23502", "not_null_violation"),
+    ("23503", "foreign_key_violation"),
+    ("23505", "unique_violation"),
+    ("23514", "check_violation"),
+    ("23P01", "exclusion_violation"),
+];
+
+/// An error reported by the database, with the fields Postgres fills in for it.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct DatabaseError<'a> {
+    pub code: &'a str,
+    pub message: &'a str,
+    pub detail: Option<&'a str>,
+    pub schema: Option<&'a str>,
+    pub table: Option<&'a str>,
+    pub column: Option<&'a str>,
+    pub constraint: Option<&'a str>,
+}
+
+impl<'a> DatabaseError<'a> {
+    /// The error reported by the database, if it was one.
+    pub fn from_sqlx(error: &'a sqlx::Error) -> Option<Self> {
+        let error = error
+            .as_database_error()?
+            .try_downcast_ref::<sqlx::postgres::PgDatabaseError>()?;
+        Some(DatabaseError {
+            code: error.code(),
+            message: error.message(),
+            detail: error.detail(),
+            schema: error.schema(),
+            table: error.table(),
+            column: error.column(),
+            constraint: error.constraint(),
+        })
+    }
+
+    /// The name of the kind of error, if it is one we describe.
+    pub fn kind(&self) -> Option<&'static str> {
+        ERROR_KINDS
+            .iter()
+            .find(|(code, _)| *code == self.code)
+            .map(|(_, kind)| *kind)
+    }
+
+    /// A message describing the error in terms of the table and constraint it concerns, or the
+    /// message of the database if it did not tell us which.
+    pub fn message(&self) -> String {
+        let table = self.table.map(|table| match self.schema {
+            Some(schema) => format!("\"{schema}\".\"{table}\""),
+            None => format!("\"{table}\""),
+        });
+        match (self.kind(), table, self.constraint, self.column) {
+            (Some("unique_violation"), Some(table), Some(constraint), _) => format!(
+                "A row with the same values for the unique constraint \"{constraint}\" already exists in {table}."
+            ),
+            (Some("foreign_key_violation"), Some(table), Some(constraint), _) => format!(
+                "The foreign key constraint \"{constraint}\" of {table} refers to a row which does not exist, or a row which is still referred to."
+            ),
+            (Some("check_violation"), Some(table), Some(constraint), _) => {
+                format!("The check constraint \"{constraint}\" of {table} is not satisfied.")
+            }
+            (Some("exclusion_violation"), Some(table), Some(constraint), _) => format!(
+                "The exclusion constraint \"{constraint}\" of {table} conflicts with an existing row."
+            ),
+            (Some("not_null_violation"), Some(table), _, Some(column)) => {
+                format!("The column \"{column}\" of {table} cannot be null.")
+            }
+            _ => self.message.to_string(),
+        }
+    }
+
+    /// The columns and values of the key the error concerns, from a detail such as
+    /// `Key ("AlbumId")=(1) already exists.` Postgres only reports the values to roles which may
+    /// read them. We only return values when there are as many as there are columns, as values
+    /// which contain commas cannot be told apart.
+    pub fn key(&self) -> Option<(Vec<&'a str>, Option<Vec<&'a str>>)> {
+        let key = self.detail?.strip_prefix("Key (")?;
+        let (columns, rest) = key.split_once(")=(")?;
+        let columns: Vec<&str> = columns
+            .split(", ")
+            .map(|column| column.trim_matches('"'))
+            .collect();
+        let values = [") already exists.", ") is not present", ") conflicts with"]
+            .iter()
+            .find_map(|suffix| rest.find(suffix).map(|end| &rest[..end]))
+            .map(|values| values.split(", ").collect::<Vec<_>>())
+            .filter(|values| values.len() == columns.len());
+        Some((columns, values))
+    }
+
+    /// The details of the error response: the SQLSTATE code, the kind of error, and the
+    /// schema, table, column and constraint it concerns, where known. The detail of the error is
+    /// left out, as it can hold the values of whole rows, which the client may not be allowed to
+    /// see; it is only logged.
+    pub fn details(&self) -> serde_json::Value {
+        let mut details = serde_json::Map::new();
+        details.insert("sqlstate".to_string(), self.code.into());
+        let fields = [
+            ("kind", self.kind()),
+            ("schema", self.schema),
+            ("table", self.table),
+            ("column", self.column),
+            ("constraint", self.constraint),
+        ];
+        for (name, value) in fields {
+            if let Some(value) = value {
+                details.insert(name.to_string(), value.into());
+            }
+        }
+        details.insert("database_message".to_string(), self.message.into());
+        serde_json::Value::Object(details)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn unique_violation() -> DatabaseError<'static> {
+        DatabaseError {
+            code: "23505",
+            message: "duplicate key value violates unique constraint \"PK_Album\"",
+            detail: Some("Key (\"AlbumId\")=(1) already exists."),
+            schema: Some("public"),
+            table: Some("Album"),
+            column: None,
+            constraint: Some("PK_Album"),
+        }
+    }
+
+    #[test]
+    fn describes_constraint_violations() {
+        let error = unique_violation();
+        assert_eq!(error.kind(), Some("unique_violation"));
+        assert_eq!(
+            error.message(),
+            "A row with the same values for the unique constraint \"PK_Album\" already exists in \"public\".\"Album\"."
+        );
+        assert_eq!(
+            error.details(),
+            serde_json::json!({
+                "sqlstate": "23505",
+                "kind": "unique_violation",
+                "schema": "public",
+                "table": "Album",
+                "constraint": "PK_Album",
+                "database_message": "duplicate key value violates unique constraint \"PK_Album\"",
+            })
+        );
+    }
+
+    #[test]
+    fn parses_the_key_from_the_detail() {
+        assert_eq!(
+            unique_violation().key(),
+            Some((vec!["AlbumId"], Some(vec!["1"])))
+        );
+        let error = DatabaseError {
+            detail: Some("Key (\"Name\", \"ArtistId\")=(Hello, world, 1) already exists."),
+            ..unique_violation()
+        };
+        assert_eq!(error.key(), Some((vec!["Name", "ArtistId"], None)));
+        let error = DatabaseError {
+            detail: Some("Failing row contains (1, null)."),
+            ..unique_violation()
+        };
+        assert_eq!(error.key(), None);
+    }
+
+    #[test]
+    fn keeps_the_database_message_without_a_table() {
+        let error = DatabaseError {
+            table: None,
+            ..unique_violation()
+        };
+        assert_eq!(error.message(), error.message);
+    }
+
+    #[test]
+    fn unknown_codes_have_no_kind() {
+        let error = DatabaseError {
+            code: "XX000",
+            message: "internal error",
+            ..DatabaseError::default()
+        };
+        assert_eq!(error.kind(), None);
+        assert_eq!(
+            error.details(),
+            serde_json::json!({"sqlstate": "XX000", "database_message": "internal error"})
+        );
+    }
+}
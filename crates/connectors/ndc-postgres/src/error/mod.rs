@@ -1,4 +1,5 @@
 //! Tools for working with error types.
 
 pub mod convert;
+pub mod database;
 pub mod record;
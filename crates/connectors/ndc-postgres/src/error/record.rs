@@ -2,16 +2,29 @@
 
 use query_engine_execution::metrics;
 
+use super::database::DatabaseError;
+
 /// Record an execution error in the current trace, and increment a counter.
 pub fn execution_error(error: &query_engine_execution::error::Error, metrics: &metrics::Metrics) {
     use query_engine_execution::error::*;
     tracing::error!("{}", error);
+    // The detail of a database error is kept out of the response, as it can hold the values of
+    // rows, so it is only recorded here.
+    if let Error::Query(
+        QueryError::DBError(err) | QueryError::DBConstraintError { error: err, .. },
+    ) = error
+    {
+        if let Some(detail) = DatabaseError::from_sqlx(err).and_then(|err| err.detail) {
+            tracing::error!(detail, "The database reported a detail of the error");
+        }
+    }
     match error {
         Error::Query(err) => match &err {
             QueryError::VariableNotFound(_)
             | QueryError::DBError(_)
             | QueryError::MutationConstraintFailed
-            | QueryError::DBConstraintError(_) => {
+            | QueryError::MutationVersionMismatch
+            | QueryError::DBConstraintError { .. } => {
                 metrics.error_metrics.record_invalid_request();
             }
             QueryError::NotSupported(_) => {
@@ -21,6 +34,9 @@ pub fn execution_error(error: &query_engine_execution::error::Error, metrics: &m
         Error::DB(_) => {
             metrics.error_metrics.record_database_error();
         }
+        Error::Timeout(_) => {
+            metrics.error_metrics.record_query_timeout();
+        }
     }
 }
 
@@ -31,6 +47,15 @@ pub fn translation_error(
 ) {
     use query_engine_translation::translation::error::*;
     tracing::error!("{}", error);
+    if let Some(feature) = error.unsupported_feature() {
+        tracing::warn!(
+            unsupported_feature = feature,
+            "The request needs a feature the connector does not support"
+        );
+        metrics
+            .error_metrics
+            .record_unsupported_feature_use(feature);
+    }
     match error {
         Error::CapabilityNotSupported(_) => {
             metrics.error_metrics.record_unsupported_capability();
@@ -43,3 +68,18 @@ pub fn translation_error(
         }
     }
 }
+
+/// Record a request which cannot be routed to a single database in the current trace, and
+/// increment a counter.
+pub fn target_error(error: &crate::targets::Error, metrics: &metrics::Metrics) {
+    use crate::targets::Error;
+    tracing::error!("{}", error);
+    match error {
+        Error::DifferentTargets { .. } => {
+            metrics.error_metrics.record_unsupported_feature();
+        }
+        Error::UnknownTarget { .. } => {
+            metrics.error_metrics.record_connector_error();
+        }
+    }
+}
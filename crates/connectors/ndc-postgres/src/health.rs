@@ -1,17 +1,87 @@
 //! Health check endpoint for the connector.
+//!
+//! When configured, a background task checks the health of the database periodically and
+//! exports the result as a metric.
 
-use ndc_sdk::connector::ErrorResponse;
+use std::sync::{Arc, Weak};
+use std::time::Duration;
+
+use ndc_postgres_configuration::{HealthCheckProbe, HealthCheckSettings};
+use thiserror::Error;
+use tracing::{info_span, Instrument};
+
+use crate::state::State;
+
+/// Why the database failed a health check.
+#[derive(Debug, Error)]
+pub enum HealthCheckError {
+    #[error("{0}")]
+    Database(#[from] sqlx::Error),
+    #[error("the health check did not complete within {} seconds", .0.as_secs())]
+    Timeout(Duration),
+}
 
 /// Check the health of the connector.
 ///
-/// For example, this function should check that the connector
-/// is able to reach its data source over the network.
-pub async fn health_check(pool: &sqlx::PgPool) -> Result<(), ErrorResponse> {
-    let sqlx_query = sqlx::query("SELECT 1");
+/// Depending on the probe, this checks that the connector is running, that it can acquire a
+/// connection to the database, or that the database answers queries, within the timeout.
+pub async fn health_check(
+    pool: &sqlx::PgPool,
+    probe: HealthCheckProbe,
+    timeout: Duration,
+) -> Result<(), HealthCheckError> {
+    let check = async {
+        match probe {
+            HealthCheckProbe::None => Ok(()),
+            HealthCheckProbe::Pool => pool.acquire().await.map(|_| ()),
+            HealthCheckProbe::Query => sqlx::query("SELECT 1").fetch_one(pool).await.map(|_| ()),
+        }
+    };
 
-    sqlx_query.fetch_one(pool).await.map_err(|err| {
-        ErrorResponse::new_internal_with_details(serde_json::Value::String(err.to_string()))
-    })?;
+    tokio::time::timeout(timeout, check)
+        .await
+        .map_err(|_| HealthCheckError::Timeout(timeout))?
+        .map_err(HealthCheckError::Database)
+}
 
-    Ok(())
+/// Periodically check the health of the database, and record the result in the metrics.
+/// Changes of the health of the database are logged, along with why it failed the check.
+///
+/// Like the replication lag monitor, the task only holds on to the state weakly, and stops once
+/// it is dropped. The `none` probe has nothing to check, so it only records that the connector
+/// is running.
+pub fn spawn_health_monitor(state: &Arc<State>, settings: &HealthCheckSettings) {
+    if settings.probe == HealthCheckProbe::None {
+        state.configuration_metrics.set_database_healthy(true);
+        return;
+    }
+    let state: Weak<State> = Arc::downgrade(state);
+    let probe = settings.probe;
+    let timeout = Duration::from_secs(settings.timeout);
+    let interval = Duration::from_secs(settings.interval);
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(interval);
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        let mut was_healthy = None;
+        loop {
+            interval.tick().await;
+            let Some(state) = state.upgrade() else {
+                break;
+            };
+            let result = health_check(&state.pool, probe, timeout)
+                .instrument(info_span!("Check database health"))
+                .await;
+            match (&result, was_healthy) {
+                (Err(error), None | Some(true)) => {
+                    tracing::warn!(%error, "Database health check failed");
+                }
+                (Ok(()), Some(false)) => tracing::info!("Database health check passed again"),
+                _ => {}
+            }
+            was_healthy = Some(result.is_ok());
+            state
+                .configuration_metrics
+                .set_database_healthy(result.is_ok());
+        }
+    });
 }
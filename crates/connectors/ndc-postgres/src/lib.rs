@@ -1,5 +1,6 @@
 //! A Hasura v3 PostgreSQL Native Data Connector.
 
+pub mod annotations;
 pub mod capabilities;
 pub mod configuration_mapping;
 pub mod connector;
@@ -7,8 +8,13 @@ pub mod error;
 pub mod health;
 pub mod mutation;
 pub mod query;
+pub mod recording;
+pub mod replicas;
 pub mod schema;
 pub mod state;
+pub mod targets;
+pub mod usage;
+pub mod watch;
 
 // we expose the sdk used for this connector so that we are able to use it in multitenant
 pub use ndc_sdk as sdk;
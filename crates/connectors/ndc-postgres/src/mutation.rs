@@ -6,6 +6,9 @@
 mod explain;
 pub use explain::explain;
 
+use std::collections::BTreeMap;
+use std::sync::OnceLock;
+
 use tracing::{info_span, Instrument};
 
 use ndc_postgres_configuration as configuration;
@@ -15,11 +18,17 @@ use ndc_sdk::models;
 use query_engine_execution;
 use query_engine_sql::sql;
 use query_engine_translation::translation;
+use query_engine_translation::translation::helpers::Env;
+use query_engine_translation::translation::options::Options;
 
+use crate::annotations;
 use crate::configuration_mapping;
 use crate::error::convert;
+use crate::error::database::DatabaseError;
 use crate::error::record;
+use crate::recording;
 use crate::state;
+use crate::targets;
 
 /// Execute a mutation
 ///
@@ -31,6 +40,7 @@ pub async fn mutation(
     request: models::MutationRequest,
 ) -> Result<JsonResponse<models::MutationResponse>, connector::ErrorResponse> {
     let timer = state.query_metrics.time_mutation_total();
+    let started = std::time::Instant::now();
 
     // See https://docs.rs/tracing/0.1.29/tracing/span/struct.Span.html#in-asynchronous-code
     let result = async move {
@@ -39,6 +49,22 @@ pub async fn mutation(
             request = ?request
         );
 
+        let generated = GeneratedMutations::default();
+        let target = mutation_target(configuration, state, &generated, &request)?;
+
+        let recorded_request = configuration
+            .request_recording
+            .as_ref()
+            .map(|settings| recording::recorded_request(settings, &request));
+
+        let procedures = request
+            .operations
+            .iter()
+            .map(|operation| match operation {
+                models::MutationOperation::Procedure { name, .. } => name.clone(),
+            })
+            .collect::<Vec<_>>();
+
         let plan = async {
             plan_mutation(configuration, state, request).map_err(|err| {
                 record::translation_error(&err, &state.query_metrics);
@@ -48,24 +74,75 @@ pub async fn mutation(
         .instrument(info_span!("Plan mutation"))
         .await?;
 
+        let recorded_sql = recorded_request
+            .as_ref()
+            .map(|_| recorded_statements(&plan));
+
         let result = async {
-            execute_mutation(state, plan).await.map_err(|err| {
-                record::execution_error(&err, &state.query_metrics);
-                convert::execution_error_to_response(err)
-            })
+            execute_mutation(configuration, state, target, plan)
+                .await
+                .map_err(|err| {
+                    record::execution_error(&err, &state.query_metrics);
+                    insert_error_to_response(configuration, &generated, &procedures, &err)
+                        .unwrap_or_else(|| convert::execution_error_to_response(err))
+                })
         }
         .instrument(info_span!("Execute mutation"))
         .await?;
 
+        // the replicas may not have caught up with what the mutation wrote yet.
+        if let (None, Some(read_replicas)) = (target, &state.read_replicas) {
+            read_replicas.record_write();
+        }
+
+        if let (Some(settings), Some(request), Some(statements)) = (
+            &configuration.request_recording,
+            recorded_request,
+            recorded_sql,
+        ) {
+            recording::record(
+                settings,
+                recording::Endpoint::Mutation,
+                request,
+                &statements,
+                started.elapsed(),
+            );
+        }
+
+        // each operation is counted as taking as long as it ran for.
+        if let Some(usage_statistics) = &state.usage_statistics {
+            let generated = generated.get(configuration);
+            for (name, duration) in procedures.iter().zip(&result.durations) {
+                usage_statistics.record_mutation(
+                    procedure_collection(configuration, generated, name),
+                    *duration,
+                );
+            }
+        }
+
         state.query_metrics.record_successful_mutation();
-        Ok(result)
+        Ok(JsonResponse::Serialized(result.response))
     }
-    .instrument(info_span!("/mutation"))
+    .instrument(info_span!(
+        "/mutation",
+        configuration.fingerprint = configuration.fingerprint()
+    ))
     .await;
 
     timer.complete_with(result)
 }
 
+/// The SQL of each mutation of a plan, to record.
+fn recorded_statements(
+    plan: &sql::execution_plan::ExecutionPlan<sql::execution_plan::Mutations>,
+) -> Vec<sql::string::SQL> {
+    plan.query
+        .0
+        .iter()
+        .map(sql::execution_plan::Mutation::query_sql)
+        .collect()
+}
+
 /// Create a mutation execution plan from a request.
 fn plan_mutation(
     configuration: &configuration::Configuration,
@@ -76,35 +153,286 @@ fn plan_mutation(
     translation::error::Error,
 > {
     let timer = state.query_metrics.time_mutation_plan();
+    let isolation_level = isolation_level(configuration, &request);
+    // The policies of collections restrict the rows mutations may modify, as they do the rows
+    // queries read.
+    let options = Options::from_configuration(configuration);
     let mutations = request
         .operations
         .into_iter()
         .map(|operation| {
             translation::mutation::translate(
                 &configuration.metadata,
+                &options,
                 operation,
                 request.collection_relationships.clone(),
-                configuration.mutations_version,
-                configuration.mutations_prefix.clone(),
             )
+            .map(|mut mutation| {
+                mutation.annotations = annotations::annotations(configuration, "/mutation");
+                mutation
+            })
         })
         .collect::<Result<Vec<_>, _>>()?;
-    timer.complete_with(Ok(sql::execution_plan::simple_mutations_execution_plan(
-        configuration_mapping::convert_isolation_level(configuration.isolation_level),
+    let mut plan = sql::execution_plan::simple_mutations_execution_plan(
+        configuration_mapping::convert_isolation_level(isolation_level),
+        configuration_mapping::convert_mutation_failure_policy(
+            configuration.mutation_failure_policy,
+        ),
         mutations,
-    )))
+    );
+    annotations::tag_mutations(configuration, &mut plan);
+    timer.complete_with(Ok(plan))
+}
+
+/// The isolation level to run a mutation request at: the strictest of the configured one and
+/// those of its native mutations.
+fn isolation_level(
+    configuration: &configuration::Configuration,
+    request: &models::MutationRequest,
+) -> configuration::IsolationLevel {
+    request
+        .operations
+        .iter()
+        .filter_map(|operation| match operation {
+            models::MutationOperation::Procedure { name, .. } => {
+                configuration.mutation_isolation_levels.get(name).copied()
+            }
+        })
+        .fold(configuration.isolation_level, std::cmp::max)
+}
+
+/// The connection target to run a mutation against. Its operations run in a single transaction,
+/// so the tables they mutate must all be in the same database.
+fn mutation_target<'s>(
+    configuration: &configuration::Configuration,
+    state: &'s state::State,
+    generated: &GeneratedMutations,
+    request: &models::MutationRequest,
+) -> Result<Option<&'s state::Target>, connector::ErrorResponse> {
+    if configuration.targets.is_empty() {
+        return Ok(None);
+    }
+    let generated = generated.get(configuration);
+    let operation_targets = request.operations.iter().map(|operation| match operation {
+        models::MutationOperation::Procedure { name, .. } => {
+            // native mutations take precedence, as they do when translating.
+            if configuration
+                .metadata
+                .native_operations
+                .mutations
+                .0
+                .contains_key(name)
+            {
+                (name.to_string(), None)
+            } else {
+                let collection = generated.get(name).map_or(name.as_str(), |mutation| {
+                    mutation.collection_name().as_str()
+                });
+                (
+                    collection.to_string(),
+                    targets::collection_target(&configuration.metadata, collection),
+                )
+            }
+        }
+    });
+    let relationship_targets = request
+        .collection_relationships
+        .values()
+        .map(|relationship| {
+            (
+                relationship.target_collection.to_string(),
+                targets::collection_target(
+                    &configuration.metadata,
+                    relationship.target_collection.as_str(),
+                ),
+            )
+        });
+    targets::single_target(operation_targets.chain(relationship_targets))
+        .and_then(|target| state.target(target))
+        .map_err(|err| {
+            record::target_error(&err, &state.query_metrics);
+            convert::target_error_to_response(&err)
+        })
+}
+
+/// The collection a procedure mutates: the table of an auto-generated mutation, or otherwise the
+/// procedure itself.
+fn procedure_collection<'a>(
+    configuration: &configuration::Configuration,
+    generated: &'a BTreeMap<models::ProcedureName, translation::mutation::generate::Mutation>,
+    name: &'a models::ProcedureName,
+) -> &'a str {
+    // native mutations take precedence, as they do when translating.
+    if configuration
+        .metadata
+        .native_operations
+        .mutations
+        .0
+        .contains_key(name)
+    {
+        name.as_str()
+    } else {
+        generated.get(name).map_or(name.as_str(), |mutation| {
+            mutation.collection_name().as_str()
+        })
+    }
+}
+
+/// The auto-generated mutations, by the names of their procedures. They are only worked out if a
+/// request needs them, and then only once.
+#[derive(Default)]
+struct GeneratedMutations(
+    OnceLock<BTreeMap<models::ProcedureName, translation::mutation::generate::Mutation>>,
+);
+
+impl GeneratedMutations {
+    fn get(
+        &self,
+        configuration: &configuration::Configuration,
+    ) -> &BTreeMap<models::ProcedureName, translation::mutation::generate::Mutation> {
+        self.0.get_or_init(|| {
+            let options = Options::from_configuration(configuration);
+            let env = Env::new(&configuration.metadata, BTreeMap::new(), None, &options);
+            translation::mutation::generate::generate(&env)
+        })
+    }
+}
+
+/// Describe a constraint violation raised by an auto-generated insert procedure, if that is what
+/// the error is. The procedure is that of the operation of the request which failed.
+fn insert_error_to_response(
+    configuration: &configuration::Configuration,
+    generated: &GeneratedMutations,
+    procedures: &[models::ProcedureName],
+    error: &query_engine_execution::error::Error,
+) -> Option<connector::ErrorResponse> {
+    use query_engine_execution::error::{Error, QueryError};
+    let Error::Query(QueryError::DBConstraintError {
+        error: err,
+        operation: Some(operation),
+    }) = error
+    else {
+        return None;
+    };
+    let db_err = DatabaseError::from_sqlx(err)?;
+    let name = procedures.get(*operation)?;
+    // native mutations take precedence, as they do when translating.
+    if configuration
+        .metadata
+        .native_operations
+        .mutations
+        .0
+        .contains_key(name)
+    {
+        return None;
+    }
+    let (schema, table, columns) = generated.get(configuration).get(name)?.insert_target()?;
+    // a trigger may have violated a constraint of another table, whose columns we do not know.
+    if db_err.schema != Some(schema.0.as_str()) || db_err.table != Some(table.0.as_str()) {
+        return None;
+    }
+    convert::insert_error_to_response(name, columns, &db_err)
 }
 
 async fn execute_mutation(
+    configuration: &configuration::Configuration,
     state: &state::State,
+    target: Option<&state::Target>,
     plan: sql::execution_plan::ExecutionPlan<sql::execution_plan::Mutations>,
-) -> Result<JsonResponse<models::MutationResponse>, query_engine_execution::error::Error> {
+) -> Result<query_engine_execution::mutation::MutationsResponse, query_engine_execution::error::Error>
+{
     query_engine_execution::mutation::execute(
-        &state.pool,
-        &state.database_info,
+        state.pool(target),
+        state.database_info(target),
         &state.query_metrics,
+        configuration_mapping::convert_query_timeout(configuration.query_timeout_ms),
+        &configuration_mapping::convert_retry_policy(configuration.retry_policy),
         plan,
     )
     .await
-    .map(JsonResponse::Serialized)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn configuration_with_native_mutations(
+        isolation_levels: &[(&str, Option<&str>)],
+    ) -> configuration::Configuration {
+        let mut parsed_config = configuration::version5::ParsedConfiguration::empty();
+        for (procedure, isolation_level) in isolation_levels {
+            parsed_config.metadata.native_operations.mutations.0.insert(
+                (*procedure).into(),
+                serde_json::from_value(serde_json::json!({
+                    "sql": { "inline": "DELETE FROM report RETURNING id" },
+                    "columns": { "id": { "name": "id", "type": { "scalarType": "int4" } } },
+                    "isolationLevel": isolation_level,
+                }))
+                .unwrap(),
+            );
+        }
+        configuration::make_runtime_configuration(
+            configuration::ParsedConfiguration::Version5(parsed_config),
+            configuration::environment::FixedEnvironment::from([(
+                configuration::DEFAULT_CONNECTION_URI_VARIABLE.into(),
+                "postgresql://localhost:5432/postgres".into(),
+            )]),
+        )
+        .unwrap()
+    }
+
+    fn request(procedures: &[&str]) -> models::MutationRequest {
+        serde_json::from_value(serde_json::json!({
+            "operations": procedures
+                .iter()
+                .map(|procedure| serde_json::json!({
+                    "type": "procedure",
+                    "name": procedure,
+                    "arguments": {},
+                }))
+                .collect::<Vec<_>>(),
+            "collection_relationships": {},
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn mutations_run_at_the_strictest_isolation_level_of_their_native_mutations() {
+        let configuration = configuration_with_native_mutations(&[
+            ("archive_reports", Some("RepeatableRead")),
+            ("rebalance_reports", Some("Serializable")),
+            ("delete_reports", None),
+        ]);
+
+        assert_eq!(
+            isolation_level(&configuration, &request(&["delete_reports"])),
+            configuration::IsolationLevel::ReadCommitted
+        );
+        assert_eq!(
+            isolation_level(
+                &configuration,
+                &request(&["delete_reports", "archive_reports"])
+            ),
+            configuration::IsolationLevel::RepeatableRead
+        );
+        assert_eq!(
+            isolation_level(
+                &configuration,
+                &request(&["rebalance_reports", "archive_reports"])
+            ),
+            configuration::IsolationLevel::Serializable
+        );
+    }
+
+    #[test]
+    fn native_mutations_do_not_loosen_the_configured_isolation_level() {
+        let mut configuration =
+            configuration_with_native_mutations(&[("archive_reports", Some("RepeatableRead"))]);
+        configuration.isolation_level = configuration::IsolationLevel::Serializable;
+
+        assert_eq!(
+            isolation_level(&configuration, &request(&["archive_reports"])),
+            configuration::IsolationLevel::Serializable
+        );
+    }
 }
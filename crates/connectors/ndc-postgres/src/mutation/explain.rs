@@ -11,10 +11,12 @@ use ndc_postgres_configuration as configuration;
 
 use ndc_sdk::connector;
 use ndc_sdk::models;
+use query_engine_sql::sql;
 
 use crate::error::convert;
 use crate::error::record;
 use crate::mutation;
+use crate::recording;
 use crate::state;
 
 /// Explain a mutation by creating an execution plan.
@@ -26,12 +28,27 @@ pub async fn explain(
     state: &state::State,
     mutation_request: models::MutationRequest,
 ) -> Result<models::ExplainResponse, connector::ErrorResponse> {
-    async move {
+    let timer = state.query_metrics.time_explain_total();
+    let started = std::time::Instant::now();
+
+    let result = async move {
         tracing::info!(
             mutation_request_json = serde_json::to_string(&mutation_request).unwrap(),
             mutation_request = ?mutation_request
         );
 
+        let target = mutation::mutation_target(
+            configuration,
+            state,
+            &mutation::GeneratedMutations::default(),
+            &mutation_request,
+        )?;
+
+        let recorded_request = configuration
+            .request_recording
+            .as_ref()
+            .map(|settings| recording::recorded_request(settings, &mutation_request));
+
         // Compile the mutation.
         let plan = async { mutation::plan_mutation(configuration, state, mutation_request) }
             .instrument(info_span!("Plan mutation"))
@@ -41,11 +58,21 @@ pub async fn explain(
                 convert::translation_error_to_response(&err)
             })?;
 
+        let summaries: Vec<String> = plan
+            .query
+            .0
+            .iter()
+            .map(|mutation| sql::summary::summarize(&mutation.query).to_string())
+            .collect();
+        let recorded_sql = recorded_request
+            .as_ref()
+            .map(|_| mutation::recorded_statements(&plan));
+
         // Execute an explain query.
         let results = async {
             query_engine_execution::mutation::explain(
-                &state.pool,
-                &state.database_info,
+                state.pool(target),
+                state.database_info(target),
                 &state.query_metrics,
                 plan,
             )
@@ -60,21 +87,42 @@ pub async fn explain(
 
         state.query_metrics.record_successful_explain();
 
+        if let (Some(settings), Some(request), Some(statements)) = (
+            &configuration.request_recording,
+            recorded_request,
+            recorded_sql,
+        ) {
+            recording::record(
+                settings,
+                recording::Endpoint::MutationExplain,
+                request,
+                &statements,
+                started.elapsed(),
+            );
+        }
+
         let details: BTreeMap<String, String> = results
             .into_iter()
             // we enumerate because the procedure may be invoked multiple times
             // in a mutation request.
+            .zip(summaries)
             .enumerate()
-            .flat_map(|(number, (name, sql, plan))| {
+            .flat_map(|(number, ((name, sql, plan), summary))| {
                 vec![
                     (format!("{number} {name} SQL Mutation"), sql),
                     (format!("{number} {name} Execution Plan"), plan),
+                    (format!("{number} {name} Translation Summary"), summary),
                 ]
             })
             .collect();
 
         Ok(models::ExplainResponse { details })
     }
-    .instrument(info_span!("/explain"))
-    .await
+    .instrument(info_span!(
+        "/explain",
+        configuration.fingerprint = configuration.fingerprint()
+    ))
+    .await;
+
+    timer.complete_with(result)
 }
@@ -6,6 +6,8 @@
 mod explain;
 pub use explain::explain;
 
+use std::collections::BTreeSet;
+
 use tracing::{info_span, Instrument};
 
 use ndc_sdk::connector;
@@ -16,9 +18,14 @@ use ndc_postgres_configuration as configuration;
 use query_engine_sql::sql;
 use query_engine_translation::translation;
 
+use crate::annotations;
+use crate::configuration_mapping;
 use crate::error::convert;
 use crate::error::record;
+use crate::recording;
 use crate::state;
+use crate::targets;
+use crate::usage;
 
 /// Execute a query
 ///
@@ -30,6 +37,13 @@ pub async fn query(
     query_request: models::QueryRequest,
 ) -> Result<JsonResponse<models::QueryResponse>, connector::ErrorResponse> {
     let timer = state.query_metrics.time_query_total();
+    let collection = query_request.collection.clone();
+    let collection_label = collection_metrics_label(configuration, &collection);
+    let collection_timer = state
+        .query_metrics
+        .time_query_for_collection(collection_label);
+    let collection_name = collection.as_str();
+    let started = std::time::Instant::now();
 
     // See https://docs.rs/tracing/0.1.29/tracing/span/struct.Span.html#in-asynchronous-code
     let result = async move {
@@ -38,6 +52,13 @@ pub async fn query(
             query_request = ?query_request
         );
 
+        let target = query_target(configuration, state, &query_request)?;
+
+        let recorded_request = configuration
+            .request_recording
+            .as_ref()
+            .map(|settings| recording::recorded_request(settings, &query_request));
+
         let plan = async {
             plan_query(configuration, state, query_request).map_err(|err| {
                 record::translation_error(&err, &state.query_metrics);
@@ -47,22 +68,86 @@ pub async fn query(
         .instrument(info_span!("Plan query"))
         .await?;
 
+        let recorded_sql = recorded_request.as_ref().map(|_| plan.query.query_sql());
+
         let result = async {
-            execute_query(state, plan).await.map_err(|err| {
-                record::execution_error(&err, &state.query_metrics);
-                convert::execution_error_to_response(err)
-            })
+            execute_query(configuration, state, target, plan)
+                .await
+                .map_err(|err| {
+                    record::execution_error(&err, &state.query_metrics);
+                    convert::execution_error_to_response(err)
+                })
         }
         .instrument(info_span!("Execute query"))
         .await?;
 
+        if let (Some(settings), Some(request), Some(query_sql)) = (
+            &configuration.request_recording,
+            recorded_request,
+            recorded_sql,
+        ) {
+            recording::record(
+                settings,
+                recording::Endpoint::Query,
+                request,
+                &[query_sql],
+                started.elapsed(),
+            );
+        }
+
+        if let Some(usage_statistics) = &state.usage_statistics {
+            let rows = match &result {
+                JsonResponse::Serialized(bytes) => query_engine_execution::query::count_rows(bytes),
+                JsonResponse::Value(response) => response
+                    .0
+                    .iter()
+                    .filter_map(|row_set| row_set.rows.as_ref())
+                    .map(|rows| u64::try_from(rows.len()).unwrap_or(u64::MAX))
+                    .sum(),
+            };
+            usage_statistics.record_query(collection_name, rows, started.elapsed());
+        }
+
         state.query_metrics.record_successful_query();
         Ok(result)
     }
-    .instrument(info_span!("/query"))
+    .instrument(info_span!(
+        "/query",
+        configuration.fingerprint = configuration.fingerprint()
+    ))
     .await;
 
-    timer.complete_with(result)
+    if result.is_err() {
+        state
+            .query_metrics
+            .record_failed_query_for_collection(collection_label);
+    }
+    timer.complete_with(collection_timer.complete_with(result))
+}
+
+/// Metrics of queries against collections which are not in the configuration are all recorded
+/// under this label.
+const UNKNOWN_COLLECTION_LABEL: &str = "(unknown)";
+
+/// The label to record the metrics of queries against a collection under. Only the collections
+/// of the configuration get their own label, so that requests cannot create any number of
+/// series.
+fn collection_metrics_label<'a>(
+    configuration: &configuration::Configuration,
+    collection: &'a models::CollectionName,
+) -> &'a str {
+    let metadata = &configuration.metadata;
+    if metadata.tables.0.contains_key(collection)
+        || metadata
+            .native_operations
+            .queries
+            .0
+            .contains_key(collection)
+    {
+        collection.as_str()
+    } else {
+        UNKNOWN_COLLECTION_LABEL
+    }
 }
 
 fn plan_query(
@@ -72,20 +157,83 @@ fn plan_query(
 ) -> Result<sql::execution_plan::ExecutionPlan<sql::execution_plan::Query>, translation::error::Error>
 {
     let timer = state.query_metrics.time_query_plan();
-    let result = translation::query::translate(&configuration.metadata, query_request);
+    let mut options = translation::options::Options::from_configuration(configuration);
+    // Planner hints are only understood when `pg_hint_plan` is loaded.
+    if !state.supports_planner_hints {
+        options.planner_hints.clear();
+    }
+    let result = translation::query::translate(&configuration.metadata, &options, query_request)
+        .map(|mut plan| {
+            plan.query.annotations = annotations::annotations(configuration, "/query");
+            annotations::tag_query(configuration, &mut plan);
+            plan
+        });
     timer.complete_with(result)
 }
 
+/// The collections a query request refers to: its own, and those of its relationships.
+fn collections(query_request: &models::QueryRequest) -> BTreeSet<&models::CollectionName> {
+    std::iter::once(&query_request.collection)
+        .chain(
+            query_request
+                .collection_relationships
+                .values()
+                .map(|relationship| &relationship.target_collection),
+        )
+        .collect()
+}
+
+/// The connection target to run a query against.
+fn query_target<'s>(
+    configuration: &configuration::Configuration,
+    state: &'s state::State,
+    query_request: &models::QueryRequest,
+) -> Result<Option<&'s state::Target>, connector::ErrorResponse> {
+    targets::target(&configuration.metadata, collections(query_request))
+        .and_then(|target| state.target(target))
+        .map_err(|err| {
+            record::target_error(&err, &state.query_metrics);
+            convert::target_error_to_response(&err)
+        })
+}
+
 async fn execute_query(
+    configuration: &configuration::Configuration,
     state: &state::State,
+    target: Option<&state::Target>,
     plan: sql::execution_plan::ExecutionPlan<sql::execution_plan::Query>,
 ) -> Result<JsonResponse<models::QueryResponse>, query_engine_execution::error::Error> {
+    // A snapshot is exported by a transaction on the primary database, so it cannot be imported
+    // on a replica.
+    let pool = if plan.query.reads_from_snapshot {
+        state.pool(target)
+    } else {
+        state.read_pool(target)
+    };
+    if configuration.verify_queries {
+        query_engine_execution::query::verify(pool, &plan).await?;
+    }
     query_engine_execution::query::execute(
-        &state.pool,
-        &state.database_info,
+        pool,
+        state.database_info(target),
         &state.query_metrics,
+        slow_query_log(configuration).as_ref(),
+        configuration_mapping::convert_query_timeout(configuration.query_timeout_ms),
+        &configuration_mapping::convert_retry_policy(configuration.retry_policy),
         plan,
     )
     .await
     .map(JsonResponse::Serialized)
 }
+
+/// How to log slow queries, if at all.
+fn slow_query_log(
+    configuration: &configuration::Configuration,
+) -> Option<query_engine_execution::query::SlowQueryLog> {
+    configuration.slow_query_threshold_ms.map(|threshold_ms| {
+        query_engine_execution::query::SlowQueryLog {
+            threshold: std::time::Duration::from_millis(threshold_ms),
+            include_parameters: configuration.log_slow_query_parameters,
+        }
+    })
+}
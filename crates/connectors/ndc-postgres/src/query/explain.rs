@@ -10,9 +10,11 @@ use tracing::{info_span, Instrument};
 use ndc_postgres_configuration as configuration;
 use ndc_sdk::connector;
 use ndc_sdk::models;
+use query_engine_sql::sql;
 
 use crate::error::convert;
 use crate::error::record;
+use crate::recording;
 use crate::state;
 
 /// Explain a query by creating an execution plan
@@ -24,12 +26,22 @@ pub async fn explain(
     state: &state::State,
     query_request: models::QueryRequest,
 ) -> Result<models::ExplainResponse, connector::ErrorResponse> {
-    async move {
+    let timer = state.query_metrics.time_explain_total();
+    let started = std::time::Instant::now();
+
+    let result = async move {
         tracing::info!(
             query_request_json = serde_json::to_string(&query_request).unwrap(),
             query_request = ?query_request
         );
 
+        let target = super::query_target(configuration, state, &query_request)?;
+
+        let recorded_request = configuration
+            .request_recording
+            .as_ref()
+            .map(|settings| recording::recorded_request(settings, &query_request));
+
         // Compile the query.
         let plan = async {
             super::plan_query(configuration, state, query_request).map_err(|err| {
@@ -40,11 +52,14 @@ pub async fn explain(
         .instrument(info_span!("Plan query"))
         .await?;
 
+        let summary = sql::summary::summarize(&plan.query.query).to_string();
+        let recorded_sql = recorded_request.as_ref().map(|_| plan.query.query_sql());
+
         // Execute an explain query.
         let (query, plan) = async {
             query_engine_execution::query::explain(
-                &state.pool,
-                &state.database_info,
+                state.pool(target),
+                state.database_info(target),
                 &state.query_metrics,
                 plan,
             )
@@ -59,11 +74,33 @@ pub async fn explain(
 
         state.query_metrics.record_successful_explain();
 
-        let details =
-            BTreeMap::from_iter([("SQL Query".into(), query), ("Execution Plan".into(), plan)]);
+        if let (Some(settings), Some(request), Some(query_sql)) = (
+            &configuration.request_recording,
+            recorded_request,
+            recorded_sql,
+        ) {
+            recording::record(
+                settings,
+                recording::Endpoint::QueryExplain,
+                request,
+                &[query_sql],
+                started.elapsed(),
+            );
+        }
+
+        let details = BTreeMap::from_iter([
+            ("SQL Query".into(), query),
+            ("Execution Plan".into(), plan),
+            ("Translation Summary".into(), summary),
+        ]);
 
         Ok(models::ExplainResponse { details })
     }
-    .instrument(info_span!("/explain"))
-    .await
+    .instrument(info_span!(
+        "/explain",
+        configuration.fingerprint = configuration.fingerprint()
+    ))
+    .await;
+
+    timer.complete_with(result)
 }
@@ -0,0 +1,278 @@
+//! Recordings of the requests the connector serves.
+//!
+//! When enabled, the connector writes a file for each request it serves successfully to the
+//! configured directory, with the endpoint, the request, the SQL it was translated to along with
+//! its parameters, and how long it took. Unless the settings say otherwise, the literal values of
+//! the request and the parameters are redacted. The recordings can be replayed with the tools in
+//! `tests-common`, to see how a change to the translation affects the requests.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, SystemTime};
+
+use tokio::sync::Semaphore;
+
+use ndc_postgres_configuration::RequestRecordingSettings;
+use query_engine_sql::sql;
+
+/// The number of recordings taken so far, to tell apart those written in the same millisecond,
+/// and to stop once there are as many as the settings allow.
+static RECORDINGS: AtomicU64 = AtomicU64::new(0);
+
+/// The most recordings written at once. Recordings are written in the background, so that
+/// requests do not wait for them, and are dropped when the disk cannot keep up.
+static WRITES: Semaphore = Semaphore::const_new(16);
+
+/// The endpoints whose requests are recorded.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Endpoint {
+    Query,
+    QueryExplain,
+    Mutation,
+    MutationExplain,
+}
+
+impl Endpoint {
+    /// The path the request was sent to, which it is sent to again when it is replayed.
+    fn path(self) -> &'static str {
+        match self {
+            Endpoint::Query => "/query",
+            Endpoint::QueryExplain => "/query/explain",
+            Endpoint::Mutation => "/mutation",
+            Endpoint::MutationExplain => "/mutation/explain",
+        }
+    }
+}
+
+/// The request as it will be recorded. This is taken before the request is translated, which
+/// consumes it.
+pub fn recorded_request(
+    settings: &RequestRecordingSettings,
+    request: &impl serde::Serialize,
+) -> serde_json::Value {
+    let mut request = serde_json::to_value(request).unwrap_or(serde_json::Value::Null);
+    if !settings.include_values {
+        redact_request(&mut request);
+    }
+    request
+}
+
+/// Write a recording of a request which was served, along with the SQL of the statements it was
+/// translated to, in the background. Recordings beyond the number or the size the settings
+/// allow are skipped, and recordings which cannot be written are logged rather than failing the
+/// request.
+pub fn record(
+    settings: &RequestRecordingSettings,
+    endpoint: Endpoint,
+    request: serde_json::Value,
+    statements: &[sql::string::SQL],
+    elapsed: Duration,
+) {
+    let sequence = RECORDINGS.fetch_add(1, Ordering::Relaxed);
+    if sequence >= settings.max_recordings {
+        if sequence == settings.max_recordings {
+            tracing::warn!(
+                max_recordings = settings.max_recordings,
+                "Stopped recording requests, as the most recordings have been written"
+            );
+        }
+        return;
+    }
+
+    let recording = serde_json::json!({
+        "endpoint": endpoint.path(),
+        "request": request,
+        "statements": statements
+            .iter()
+            .map(|statement| serde_json::json!({
+                "sql": statement.sql,
+                "parameters": statement
+                    .params
+                    .iter()
+                    .map(|param| recorded_param(settings, param))
+                    .collect::<Vec<_>>(),
+            }))
+            .collect::<Vec<_>>(),
+        "elapsedMs": u64::try_from(elapsed.as_millis()).unwrap_or(u64::MAX),
+    });
+    let contents = serde_json::to_vec_pretty(&recording).unwrap_or_default();
+    if u64::try_from(contents.len()).unwrap_or(u64::MAX) > settings.max_recording_bytes {
+        tracing::debug!(
+            bytes = contents.len(),
+            "Skipped the recording of a request, as it is too large"
+        );
+        return;
+    }
+    let Ok(permit) = WRITES.try_acquire() else {
+        tracing::debug!("Skipped the recording of a request, as too many are being written");
+        return;
+    };
+
+    let timestamp = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis();
+    let directory = settings.directory.clone();
+    let path = directory.join(format!("{timestamp}-{sequence:06}.json"));
+    tokio::spawn(async move {
+        let written = async {
+            tokio::fs::create_dir_all(&directory).await?;
+            tokio::fs::write(&path, contents).await
+        }
+        .await;
+        if let Err(err) = written {
+            tracing::warn!(
+                path = %path.display(),
+                error = %err,
+                "Failed to write the recording of a request"
+            );
+        }
+        drop(permit);
+    });
+}
+
+/// A parameter of the SQL as it will be recorded. The names of variables are kept, as they are
+/// part of the request.
+fn recorded_param(
+    settings: &RequestRecordingSettings,
+    param: &sql::string::Param,
+) -> serde_json::Value {
+    let mut value = match param {
+        sql::string::Param::Variable(name) => return serde_json::json!({ "variable": name }),
+        sql::string::Param::String(string) => serde_json::Value::String(string.clone()),
+        sql::string::Param::Value(value) => value.clone(),
+        sql::string::Param::Typed(sql::string::TypedValue::Int8(int)) => serde_json::json!(int),
+        sql::string::Param::Typed(sql::string::TypedValue::Numeric(numeric)) => {
+            serde_json::Value::String(numeric.clone())
+        }
+    };
+    if !settings.include_values {
+        redact_value(&mut value);
+    }
+    value
+}
+
+/// Replace the literal values of a request, the values of its variables and the arguments of
+/// its procedures with placeholders.
+fn redact_request(request: &mut serde_json::Value) {
+    if let Some(variables) = request.get_mut("variables") {
+        redact_value(variables);
+    }
+    if let Some(operations) = request
+        .get_mut("operations")
+        .and_then(serde_json::Value::as_array_mut)
+    {
+        for arguments in operations
+            .iter_mut()
+            .filter_map(|operation| operation.get_mut("arguments"))
+            .filter_map(serde_json::Value::as_object_mut)
+        {
+            for argument in arguments.values_mut() {
+                // Arguments such as `pre_check` are predicates, whose column names and
+                // operators are kept.
+                if argument.get("type").is_some() {
+                    redact_literals(argument);
+                } else {
+                    redact_value(argument);
+                }
+            }
+        }
+    }
+    redact_literals(request);
+}
+
+/// Replace the values of literal arguments and scalar comparison values with placeholders.
+fn redact_literals(value: &mut serde_json::Value) {
+    match value {
+        serde_json::Value::Object(object) => {
+            let is_literal = matches!(
+                object.get("type").and_then(serde_json::Value::as_str),
+                Some("literal" | "scalar")
+            );
+            for (key, value) in object.iter_mut() {
+                if is_literal && key == "value" {
+                    redact_value(value);
+                } else {
+                    redact_literals(value);
+                }
+            }
+        }
+        serde_json::Value::Array(values) => values.iter_mut().for_each(redact_literals),
+        _ => {}
+    }
+}
+
+/// Replace a value with a placeholder of the same type: an empty string, zero or `false`. The
+/// elements of arrays and the fields of objects are replaced each, so that the placeholder has
+/// the same shape, and requests can often still be replayed.
+fn redact_value(value: &mut serde_json::Value) {
+    match value {
+        serde_json::Value::Null => {}
+        serde_json::Value::Bool(bool) => *bool = false,
+        serde_json::Value::Number(_) => *value = serde_json::Value::from(0),
+        serde_json::Value::String(string) => string.clear(),
+        serde_json::Value::Array(values) => values.iter_mut().for_each(redact_value),
+        serde_json::Value::Object(object) => object.values_mut().for_each(redact_value),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn redacts_literals_and_variables() {
+        let mut request = serde_json::json!({
+            "collection": "Album",
+            "arguments": { "id": { "type": "literal", "value": 1 } },
+            "query": {
+                "predicate": {
+                    "type": "binary_comparison_operator",
+                    "column": { "type": "column", "name": "Title", "path": [] },
+                    "operator": "_eq",
+                    "value": { "type": "scalar", "value": "Balls to the Wall" }
+                }
+            },
+            "variables": [{ "title": "Restless and Wild", "in_stock": true }]
+        });
+
+        redact_request(&mut request);
+
+        assert_eq!(request["arguments"]["id"]["value"], 0);
+        assert_eq!(request["query"]["predicate"]["value"]["value"], "");
+        assert_eq!(request["query"]["predicate"]["column"]["name"], "Title");
+        assert_eq!(request["variables"][0]["title"], "");
+        assert_eq!(request["variables"][0]["in_stock"], false);
+    }
+
+    #[test]
+    fn redacts_the_arguments_of_procedures() {
+        let mut request = serde_json::json!({
+            "operations": [{
+                "type": "procedure",
+                "name": "v2_insert_Artist",
+                "arguments": {
+                    "objects": [{ "ArtistId": 276, "Name": "Olodum" }],
+                    "post_check": {
+                        "type": "binary_comparison_operator",
+                        "column": { "type": "column", "name": "Name", "path": [] },
+                        "operator": "_neq",
+                        "value": { "type": "scalar", "value": "Nobody" }
+                    }
+                }
+            }],
+            "collection_relationships": {}
+        });
+
+        redact_request(&mut request);
+
+        let arguments = &request["operations"][0]["arguments"];
+        assert_eq!(
+            arguments["objects"],
+            serde_json::json!([{ "ArtistId": 0, "Name": "" }])
+        );
+        assert_eq!(arguments["post_check"]["column"]["name"], "Name");
+        assert_eq!(arguments["post_check"]["operator"], "_neq");
+        assert_eq!(arguments["post_check"]["value"]["value"], "");
+        assert_eq!(request["operations"][0]["name"], "v2_insert_Artist");
+    }
+}
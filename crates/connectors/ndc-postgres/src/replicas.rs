@@ -0,0 +1,284 @@
+//! Read replicas of the database.
+//!
+//! Queries are spread across the replicas in turn. A background task measures how far each
+//! replica lags behind the primary, and leaves replicas that lag too far behind out of the
+//! rotation until they catch up, so that queries never read data that is too stale.
+//!
+//! Replicas may not have caught up with a mutation by the time the next query arrives, so queries
+//! can be sent to the primary for a while after each mutation instead.
+
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Weak};
+use std::time::{Duration, Instant};
+
+use sqlx::postgres::PgPool;
+use tracing::{info_span, Instrument};
+
+use query_engine_execution::metrics;
+
+/// The replicas of the database that we run queries against.
+#[derive(Debug)]
+pub struct ReadReplicas {
+    replicas: Vec<Replica>,
+    /// The replica to try first for the next query.
+    next: AtomicUsize,
+    max_replication_lag: Duration,
+    /// How long to wait for a replica to report its lag before treating it as unreachable.
+    lag_check_timeout: Duration,
+    /// How long to read from the primary after a mutation, if at all.
+    read_your_writes_window: Option<Duration>,
+    /// The instant which `primary_pinned_until` is measured from.
+    created_at: Instant,
+    /// Until when queries should read from the primary, in milliseconds since `created_at`.
+    primary_pinned_until: AtomicU64,
+}
+
+#[derive(Debug)]
+struct Replica {
+    pool: PgPool,
+    /// Whether the replica was reachable and caught up when we last checked.
+    healthy: AtomicBool,
+}
+
+/// The replication lag of a replica, in seconds.
+/// A replica which is streaming from the primary and has replayed everything it has received is
+/// not lagging, even if the primary has not written anything for a while. A replica which has
+/// lost its connection to the primary lags by however long ago it last replayed a transaction,
+/// and by an unknown amount (`NULL`) if it never has.
+const REPLICATION_LAG_QUERY: &str = "
+    SELECT
+      CASE
+        WHEN EXISTS (SELECT FROM pg_stat_wal_receiver WHERE status = 'streaming')
+          AND pg_last_wal_receive_lsn() = pg_last_wal_replay_lsn() THEN 0
+        ELSE extract(epoch FROM now() - pg_last_xact_replay_timestamp())
+      END::float8";
+
+impl ReadReplicas {
+    pub fn new(
+        pools: Vec<PgPool>,
+        max_replication_lag: Duration,
+        lag_check_timeout: Duration,
+        read_your_writes_window: Option<Duration>,
+    ) -> Self {
+        Self {
+            replicas: pools
+                .into_iter()
+                .map(|pool| Replica {
+                    pool,
+                    healthy: AtomicBool::new(false),
+                })
+                .collect(),
+            next: AtomicUsize::new(0),
+            max_replication_lag,
+            lag_check_timeout,
+            read_your_writes_window,
+            created_at: Instant::now(),
+            primary_pinned_until: AtomicU64::new(0),
+        }
+    }
+
+    /// The pool of the next healthy replica, if there are any, and queries need not read from
+    /// the primary.
+    pub fn next_healthy_pool(&self) -> Option<&PgPool> {
+        self.next_healthy_pool_at(Instant::now())
+    }
+
+    fn next_healthy_pool_at(&self, now: Instant) -> Option<&PgPool> {
+        if self.millis_since_created(now) < self.primary_pinned_until.load(Ordering::Relaxed) {
+            return None;
+        }
+        let start = self.next.fetch_add(1, Ordering::Relaxed);
+        (0..self.replicas.len())
+            .map(|offset| &self.replicas[(start + offset) % self.replicas.len()])
+            .find(|replica| replica.healthy.load(Ordering::Relaxed))
+            .map(|replica| &replica.pool)
+    }
+
+    /// Note that the primary has been written to, so that queries read from it for the
+    /// read-your-writes window, if there is one.
+    pub fn record_write(&self) {
+        if let Some(window) = self.read_your_writes_window {
+            let until = self.millis_since_created(Instant::now() + window);
+            self.primary_pinned_until
+                .fetch_max(until, Ordering::Relaxed);
+        }
+    }
+
+    fn millis_since_created(&self, instant: Instant) -> u64 {
+        u64::try_from(
+            instant
+                .saturating_duration_since(self.created_at)
+                .as_millis(),
+        )
+        .unwrap_or(u64::MAX)
+    }
+
+    /// Measure the replication lag of every replica, and update which of them are healthy.
+    ///
+    /// The replicas are checked at the same time, so that one which is slow to answer does not
+    /// hold up the others.
+    pub async fn check_replication_lag(&self, metrics: &metrics::Metrics) {
+        let checks: Vec<_> = self
+            .replicas
+            .iter()
+            .map(|replica| {
+                let pool = replica.pool.clone();
+                tokio::spawn(tokio::time::timeout(self.lag_check_timeout, async move {
+                    sqlx::query_scalar::<_, Option<f64>>(REPLICATION_LAG_QUERY)
+                        .fetch_one(&pool)
+                        .await
+                }))
+            })
+            .collect();
+
+        let mut healthy_count = 0;
+        for (index, (replica, check)) in self.replicas.iter().zip(checks).enumerate() {
+            let healthy = match check.await {
+                Ok(Ok(Ok(Some(lag)))) if lag <= self.max_replication_lag.as_secs_f64() => true,
+                Ok(Ok(Ok(Some(lag)))) => {
+                    tracing::warn!(replica = index, lag, "Read replica is lagging behind");
+                    false
+                }
+                Ok(Ok(Ok(None))) => {
+                    tracing::warn!(
+                        replica = index,
+                        "Read replica is not streaming from the primary and has not replayed anything"
+                    );
+                    false
+                }
+                Ok(Ok(Err(error))) => {
+                    tracing::warn!(replica = index, %error, "Read replica is unreachable");
+                    false
+                }
+                Ok(Err(_)) => {
+                    tracing::warn!(
+                        replica = index,
+                        timeout = ?self.lag_check_timeout,
+                        "Read replica did not report its lag in time"
+                    );
+                    false
+                }
+                Err(error) => {
+                    tracing::warn!(replica = index, %error, "Checking a read replica failed");
+                    false
+                }
+            };
+            let was_healthy = replica.healthy.swap(healthy, Ordering::Relaxed);
+            if healthy && !was_healthy {
+                tracing::info!(replica = index, "Read replica added to the rotation");
+            }
+            if healthy {
+                healthy_count += 1;
+            }
+        }
+        metrics.set_healthy_read_replicas(healthy_count);
+    }
+}
+
+/// Periodically check the replication lag of the replicas, starting one interval from now.
+/// The first check should have been made before the replicas are used.
+///
+/// The task only holds on to the replicas weakly, and stops once they are dropped along with
+/// the rest of the connector state.
+pub fn spawn_replication_lag_monitor(
+    replicas: &Arc<ReadReplicas>,
+    metrics: metrics::Metrics,
+    interval: Duration,
+) {
+    let replicas: Weak<ReadReplicas> = Arc::downgrade(replicas);
+    tokio::spawn(async move {
+        let mut interval =
+            tokio::time::interval_at(tokio::time::Instant::now() + interval, interval);
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            interval.tick().await;
+            let Some(replicas) = replicas.upgrade() else {
+                break;
+            };
+            replicas
+                .check_replication_lag(&metrics)
+                .instrument(info_span!("Check replication lag"))
+                .await;
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use sqlx::postgres::PgPoolOptions;
+
+    use super::*;
+
+    /// Replicas whose pools never connect, with the given health.
+    fn replicas(healthy: &[bool]) -> ReadReplicas {
+        let replicas = ReadReplicas::new(
+            healthy
+                .iter()
+                .map(|_| {
+                    PgPoolOptions::new()
+                        .connect_lazy("postgresql://replica.invalid/db")
+                        .unwrap()
+                })
+                .collect(),
+            Duration::from_secs(10),
+            Duration::from_secs(1),
+            Some(Duration::from_secs(5)),
+        );
+        for (replica, healthy) in replicas.replicas.iter().zip(healthy) {
+            replica.healthy.store(*healthy, Ordering::Relaxed);
+        }
+        replicas
+    }
+
+    fn next_index(replicas: &ReadReplicas) -> Option<usize> {
+        let pool = replicas.next_healthy_pool()?;
+        replicas
+            .replicas
+            .iter()
+            .position(|replica| std::ptr::eq(&replica.pool, pool))
+    }
+
+    #[tokio::test]
+    async fn healthy_replicas_are_used_in_turn() {
+        let replicas = replicas(&[true, true, true]);
+        let order: Vec<_> = (0..4).map(|_| next_index(&replicas)).collect();
+        assert_eq!(order, vec![Some(0), Some(1), Some(2), Some(0)]);
+    }
+
+    #[tokio::test]
+    async fn unhealthy_replicas_are_skipped() {
+        let replicas = replicas(&[true, false, true]);
+        let order: Vec<_> = (0..4).map(|_| next_index(&replicas)).collect();
+        assert_eq!(order, vec![Some(0), Some(2), Some(2), Some(0)]);
+    }
+
+    #[tokio::test]
+    async fn the_primary_is_used_when_no_replica_is_healthy() {
+        let replicas = replicas(&[false, false]);
+        assert_eq!(next_index(&replicas), None);
+    }
+
+    #[tokio::test]
+    async fn the_primary_is_used_within_the_read_your_writes_window() {
+        let replicas = replicas(&[true, true]);
+        replicas.record_write();
+        let written_at = Instant::now();
+        assert!(replicas.next_healthy_pool_at(written_at).is_none());
+        assert!(replicas
+            .next_healthy_pool_at(written_at + Duration::from_secs(4))
+            .is_none());
+        assert!(replicas
+            .next_healthy_pool_at(written_at + Duration::from_secs(6))
+            .is_some());
+    }
+
+    #[tokio::test]
+    async fn the_replicas_are_used_after_writes_without_a_window() {
+        let replicas = ReadReplicas {
+            read_your_writes_window: None,
+            ..replicas(&[true])
+        };
+        replicas.record_write();
+        assert_eq!(next_index(&replicas), Some(0));
+    }
+}
@@ -1,5 +1,7 @@
 //! Helper functions for generating ndc-spec schema objects.
 
+use std::collections::{BTreeMap, BTreeSet};
+
 use ndc_sdk::models;
 
 use query_engine_metadata::metadata;
@@ -24,6 +26,37 @@ pub fn readonly_column_to_type(column: &metadata::ReadOnlyColumnInfo) -> models:
     }
 }
 
+/// Extract the models::Type representation of a composite type field.
+pub fn field_to_type(field: &metadata::FieldInfo) -> models::Type {
+    match &field.nullable {
+        metadata::Nullable::NonNullable => type_to_type(&field.r#type),
+        metadata::Nullable::Nullable => models::Type::Nullable {
+            underlying_type: Box::new(type_to_type(&field.r#type)),
+        },
+    }
+}
+
+/// Extract the models::Type representation of a computed field, which is always nullable.
+pub fn computed_field_to_type(computed_field: &metadata::ComputedField) -> models::Type {
+    models::Type::Nullable {
+        underlying_type: Box::new(type_to_type(&computed_field.r#type)),
+    }
+}
+
+/// The field of the object type of a table which a computed field adds.
+pub fn computed_field_to_field(
+    (field_name, computed_field): (&models::FieldName, &metadata::ComputedField),
+) -> (models::FieldName, models::ObjectField) {
+    (
+        field_name.clone(),
+        models::ObjectField {
+            description: computed_field.description.clone(),
+            r#type: computed_field_to_type(computed_field),
+            arguments: BTreeMap::new(),
+        },
+    )
+}
+
 pub fn type_to_type(typ: &metadata::Type) -> models::Type {
     match typ {
         metadata::Type::ArrayType(typ) => models::Type::Array {
@@ -37,3 +70,59 @@ pub fn type_to_type(typ: &metadata::Type) -> models::Type {
         metadata::Type::CompositeType(t) => models::Type::Named { name: t.clone() },
     }
 }
+
+/// The scalar types of the elements of array columns and fields, which can be compared with the
+/// operators PostgreSQL defines for all arrays.
+pub fn array_element_types(metadata: &metadata::Metadata) -> BTreeSet<&models::ScalarTypeName> {
+    let table_columns = metadata
+        .tables
+        .0
+        .values()
+        .flat_map(|table| table.columns.values().map(|column| &column.r#type));
+    let native_query_columns = metadata
+        .native_operations
+        .queries
+        .0
+        .values()
+        .flat_map(|native_query| native_query.columns.values().map(|column| &column.r#type));
+    let composite_type_fields = metadata
+        .composite_types
+        .0
+        .values()
+        .flat_map(|composite_type| composite_type.fields.values().map(|field| &field.r#type));
+    table_columns
+        .chain(native_query_columns)
+        .chain(composite_type_fields)
+        .filter_map(|typ| match typ {
+            metadata::Type::ArrayType(element_type) => match element_type.as_ref() {
+                metadata::Type::ScalarType(scalar_type) => Some(scalar_type),
+                _ => None,
+            },
+            _ => None,
+        })
+        .collect()
+}
+
+/// The comparison operators on arrays of a scalar type, whose arguments are arrays of the same
+/// type.
+pub fn array_comparison_operators(
+    element_type: &models::ScalarTypeName,
+) -> impl Iterator<
+    Item = (
+        models::ComparisonOperatorName,
+        models::ComparisonOperatorDefinition,
+    ),
+> + '_ {
+    metadata::ARRAY_COMPARISON_OPERATORS
+        .iter()
+        .map(move |(exposed_name, _)| {
+            (
+                (*exposed_name).into(),
+                models::ComparisonOperatorDefinition::Custom {
+                    argument_type: type_to_type(&metadata::Type::ArrayType(Box::new(
+                        metadata::Type::ScalarType(element_type.clone()),
+                    ))),
+                },
+            )
+        })
+}
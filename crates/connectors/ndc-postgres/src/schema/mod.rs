@@ -15,6 +15,10 @@ use helpers::*;
 use ndc_postgres_configuration as configuration;
 use query_engine_metadata::metadata;
 use query_engine_translation::translation::helpers::Env;
+use query_engine_translation::translation::mutation::returning_aggregates;
+use query_engine_translation::translation::mutation::sequences;
+use query_engine_translation::translation::options::Options;
+use query_engine_translation::translation::query::root;
 
 /// Get the connector's schema.
 ///
@@ -83,13 +87,39 @@ pub fn get_schema(
         })
         .collect();
 
-    let collections_by_identifier: BTreeMap<(&str, &str), &str> = metadata
+    // Array columns are compared using the operators on their element types.
+    for element_type in array_element_types(metadata) {
+        if let Some(scalar_type) = scalar_types.get_mut(element_type) {
+            for (name, definition) in array_comparison_operators(element_type) {
+                scalar_type
+                    .comparison_operators
+                    .entry(name)
+                    .or_insert(definition);
+            }
+        }
+    }
+
+    // The columns a table can be made distinct on are named by values of an enum of their own.
+    if config.distinct_on {
+        for (collection_name, table) in &metadata.tables.0 {
+            scalar_types
+                .entry(distinct_on_column_type_name(collection_name).into())
+                .or_insert_with(|| distinct_on_column_type(table));
+        }
+    }
+
+    // tables can only refer to tables in the same database, so the target is part of the key.
+    let collections_by_identifier: BTreeMap<(Option<&str>, &str, &str), &str> = metadata
         .tables
         .0
         .iter()
         .map(|(collection_name, table)| {
             (
-                (table.schema_name.as_ref(), table.table_name.as_ref()),
+                (
+                    table.target.as_deref(),
+                    table.schema_name.as_ref(),
+                    table.table_name.as_ref(),
+                ),
                 collection_name.as_str(),
             )
         })
@@ -99,91 +129,77 @@ pub fn get_schema(
         .tables
         .0
         .iter()
-        .map(|(collection_name, table)| models::CollectionInfo {
-            name: collection_name.clone(),
-            description: table.description.clone(),
-            arguments: BTreeMap::new(),
-            collection_type: collection_name.as_str().into(),
-            uniqueness_constraints: table
-                .uniqueness_constraints
-                .0
-                .iter()
-                .map(
-                    |(constraint_name, metadata::UniquenessConstraint(constraint_columns))| {
-                        (
-                            constraint_name.clone(),
-                            models::UniquenessConstraint {
-                                unique_columns: constraint_columns.values().cloned().collect(),
-                            },
-                        )
-                    },
-                )
-                .collect(),
-            foreign_keys: table
-                .foreign_relations
-                .0
-                .iter()
-                .map(
-                    |(
-                        constraint_name,
-                        metadata::ForeignRelation {
-                            foreign_schema,
-                            foreign_table,
-                            column_mapping,
+        .map(|(collection_name, table)| {
+            Ok(models::CollectionInfo {
+                name: collection_name.clone(),
+                description: table_description(collection_name, table, config),
+                arguments: table_arguments(metadata, collection_name, table, config),
+                collection_type: collection_name.as_str().into(),
+                // constraints over columns which are not exposed cannot identify a row
+                uniqueness_constraints: table
+                    .row_identifiers()
+                    .filter(|(_, metadata::UniquenessConstraint(constraint_columns))| {
+                        constraint_columns.values().all(|field_name| {
+                            table
+                                .columns
+                                .get(field_name)
+                                .is_some_and(|column| column.selectable)
+                        })
+                    })
+                    .map(
+                        |(constraint_name, metadata::UniquenessConstraint(constraint_columns))| {
+                            (
+                                constraint_name.clone(),
+                                models::UniquenessConstraint {
+                                    unique_columns: constraint_columns.values().cloned().collect(),
+                                },
+                            )
                         },
-                    )| {
-                        (
-                            constraint_name.clone(),
-                            models::ForeignKeyConstraint {
-                                foreign_collection: (*collections_by_identifier
-                                    .get(&(
-                                        // the foreign schema used to be implied, so if it is not
-                                        // provided, we need to default back to the originating
-                                        // table's schema
-                                        foreign_schema.as_ref().unwrap_or(&table.schema_name),
-                                        foreign_table,
-                                    ))
-                                    .unwrap_or_else(|| {
-                                        panic!(
-                                            "Unknown foreign table: {foreign_schema:?}.{foreign_table:?}"
-                                        )
-                                    }))
-                                .into(),
-                                column_mapping: column_mapping.clone(),
-                            },
-                        )
-                    },
-                )
-                .collect(),
+                    )
+                    .collect(),
+                foreign_keys: foreign_keys(
+                    &collections_by_identifier,
+                    &table.foreign_relations,
+                    table.target.as_deref(),
+                    &table.schema_name,
+                )?,
+            })
         })
-        .collect();
+        .collect::<Result<_, connector::ErrorResponse>>()?;
 
     let native_queries: Vec<models::CollectionInfo> = metadata
         .native_operations
         .queries
         .0
         .iter()
-        .map(|(name, info)| models::CollectionInfo {
-            name: name.clone(),
-            description: info.description.clone(),
-            arguments: info
-                .arguments
-                .iter()
-                .map(|(name, readonly_column_info)| {
-                    (
-                        name.clone(),
-                        models::ArgumentInfo {
-                            description: readonly_column_info.description.clone(),
-                            argument_type: readonly_column_to_type(readonly_column_info),
-                        },
-                    )
-                })
-                .collect(),
-            collection_type: name.as_str().into(),
-            uniqueness_constraints: BTreeMap::new(),
-            foreign_keys: BTreeMap::new(),
+        .map(|(name, info)| {
+            Ok(models::CollectionInfo {
+                name: name.clone(),
+                description: info.description.clone(),
+                arguments: info
+                    .arguments
+                    .iter()
+                    .map(|(name, readonly_column_info)| {
+                        (
+                            name.clone(),
+                            models::ArgumentInfo {
+                                description: readonly_column_info.description.clone(),
+                                argument_type: readonly_column_to_type(readonly_column_info),
+                            },
+                        )
+                    })
+                    .collect(),
+                collection_type: name.as_str().into(),
+                uniqueness_constraints: BTreeMap::new(),
+                foreign_keys: foreign_keys(
+                    &collections_by_identifier,
+                    &info.foreign_relations,
+                    None,
+                    metadata::NATIVE_QUERY_DEFAULT_FOREIGN_SCHEMA,
+                )?,
+            })
         })
-        .collect();
+        .collect::<Result<_, connector::ErrorResponse>>()?;
 
     let mut collections = tables;
     collections.extend(native_queries);
@@ -195,19 +211,23 @@ pub fn get_schema(
         .map(|(collection_name, table)| {
             let object_type = models::ObjectType {
                 description: table.description.clone(),
+                // columns which may not be selected are not part of the rows of the table, nor
+                // of those returned by its mutations.
                 fields: table
                     .columns
                     .iter()
+                    .filter(|(_, column_info)| column_info.selectable)
                     .map(|(column_name, column_info)| {
                         (
                             column_name.clone(),
                             models::ObjectField {
-                                description: column_info.description.clone(),
+                                description: column_description(column_info),
                                 r#type: column_to_type(column_info),
                                 arguments: BTreeMap::new(),
                             },
                         )
                     })
+                    .chain(table.computed_fields.iter().map(computed_field_to_field))
                     .collect(),
             };
             (collection_name.as_str().into(), object_type)
@@ -283,9 +303,7 @@ pub fn get_schema(
                             field_name.clone(),
                             models::ObjectField {
                                 description: field_info.description.clone(),
-                                r#type: models::Type::Nullable {
-                                    underlying_type: Box::new(type_to_type(&field_info.r#type)),
-                                },
+                                r#type: field_to_type(field_info),
                                 arguments: BTreeMap::new(),
                             },
                         )
@@ -301,6 +319,9 @@ pub fn get_schema(
     object_types.extend(native_mutations_types);
     object_types.extend(composite_types);
 
+    let options = Options::from_configuration(config);
+    let env = Env::new(metadata, BTreeMap::new(), None, &options);
+
     let mut procedures: Vec<models::ProcedureInfo> = metadata
         .native_operations
         .mutations
@@ -326,6 +347,7 @@ pub fn get_schema(
                 models::Type::Named {
                     name: nq_name.as_str().into(),
                 },
+                returning_aggregates::of_native_mutation(&env, nq_name),
                 &mut object_types,
                 &mut scalar_types,
             )
@@ -333,24 +355,64 @@ pub fn get_schema(
         .collect();
 
     let mut more_object_types = BTreeMap::new();
-    let env = Env::new(
-        metadata,
-        BTreeMap::new(),
-        config.mutations_version,
-        config.mutations_prefix.clone(),
-        None,
-    );
     let generated_procedures: Vec<models::ProcedureInfo> =
         query_engine_translation::translation::mutation::generate::generate(&env)
             .iter()
             .map(|(name, mutation)| {
-                mutation::to_procedure(name, mutation, &mut more_object_types, &mut scalar_types)
+                mutation::to_procedure(
+                    &env,
+                    name,
+                    mutation,
+                    &mut more_object_types,
+                    &mut scalar_types,
+                )
             })
             .collect();
 
     procedures.extend(generated_procedures);
+
+    let sequence_procedures: Vec<models::ProcedureInfo> = sequences::procedures(&env)
+        .into_iter()
+        .map(|(name, sequence)| {
+            // values of sequences are int8, which might not be in the schema otherwise.
+            scalar_types
+                .entry(sequences::SEQUENCE_VALUE_TYPE.into())
+                .or_insert(models::ScalarType {
+                    representation: Some(models::TypeRepresentation::Int64),
+                    aggregate_functions: BTreeMap::new(),
+                    comparison_operators: BTreeMap::new(),
+                });
+            models::ProcedureInfo {
+                name,
+                description: Some(sequences::description(&sequence)),
+                arguments: BTreeMap::new(),
+                result_type: models::Type::Named {
+                    name: sequences::SEQUENCE_VALUE_TYPE.into(),
+                },
+            }
+        })
+        .collect();
+    procedures.extend(sequence_procedures);
     object_types.extend(more_object_types);
 
+    // an operation which fails under the best-effort policy returns a null result, so every
+    // procedure must say that it might.
+    if config.mutation_failure_policy == configuration::MutationFailurePolicy::BestEffort {
+        for procedure in &mut procedures {
+            if !matches!(procedure.result_type, models::Type::Nullable { .. }) {
+                let result_type = std::mem::replace(
+                    &mut procedure.result_type,
+                    models::Type::Named {
+                        name: String::new().into(),
+                    },
+                );
+                procedure.result_type = models::Type::Nullable {
+                    underlying_type: Box::new(result_type),
+                };
+            }
+        }
+    }
+
     Ok(models::SchemaResponse {
         collections,
         procedures,
@@ -388,9 +450,264 @@ fn map_type_representation(
         metadata::TypeRepresentation::Geometry => models::TypeRepresentation::Geometry,
         metadata::TypeRepresentation::Geography => models::TypeRepresentation::Geography,
         metadata::TypeRepresentation::UUID => models::TypeRepresentation::UUID,
+        // Hstore returns an object of strings.
+        metadata::TypeRepresentation::Hstore => models::TypeRepresentation::JSON,
+        metadata::TypeRepresentation::Bytes => models::TypeRepresentation::Bytes,
         metadata::TypeRepresentation::Json => models::TypeRepresentation::JSON,
         metadata::TypeRepresentation::Enum(variants) => models::TypeRepresentation::Enum {
             one_of: variants.clone(),
         },
     }
 }
+
+/// The description of a table, followed by a note that its star counts may be estimated if they
+/// can be, as the aggregates of such queries have an `is_estimate` field which was not asked for.
+fn table_description(
+    collection_name: &models::CollectionName,
+    table: &metadata::TableInfo,
+    config: &configuration::Configuration,
+) -> Option<String> {
+    let estimable = table.row_expiry.is_none()
+        && table.soft_delete.is_none()
+        && !config.collection_policies.contains_key(collection_name);
+    let Some(count_estimation) = config.count_estimation.filter(|_| estimable) else {
+        return table.description.clone();
+    };
+    let note = format!(
+        "Star counts over all of its rows are estimated from the statistics of the table when those put it at {} rows or more. The aggregates of such queries include `{}`, which is true when they were estimated and false when the rows were counted.",
+        count_estimation.min_rows,
+        query_engine_translation::translation::query::aggregates::IS_ESTIMATE_FIELD,
+    );
+    Some(match &table.description {
+        None => note,
+        Some(description) => format!("{description}\n\n{note}"),
+    })
+}
+
+/// The description of a column, followed by the expression of its default if it has one, as the
+/// schema has nowhere else to tell clients about it. The description is closed with a full stop
+/// before the default when it does not already end in punctuation.
+fn column_description(column_info: &metadata::ColumnInfo) -> Option<String> {
+    let Some(default_expression) = &column_info.default_expression else {
+        return column_info.description.clone();
+    };
+    let default = format!("Defaults to `{default_expression}`.");
+    match column_info.description.as_deref().map(str::trim_end) {
+        None | Some("") => Some(default),
+        Some(description) => {
+            let separator = if description.ends_with(['.', '!', '?']) {
+                ""
+            } else {
+                "."
+            };
+            Some(format!("{description}{separator}\n\n{default}"))
+        }
+    }
+}
+
+/// The arguments table collections accept.
+///
+/// Tables take an optional limit of the rows to compute aggregates over, as an `int4`. With
+/// `distinctOn` enabled, they take a list of columns to return only distinct rows for, named by
+/// their own enum type. Tables with composite columns also take a list of those columns to return
+/// as single JSON values, and with snapshot reads enabled, tables take the snapshot to read from.
+/// These column names and snapshots are passed as `text`. Each argument is only offered when the
+/// type it is passed as is known.
+fn table_arguments(
+    metadata: &metadata::Metadata,
+    collection_name: &models::CollectionName,
+    table: &metadata::TableInfo,
+    config: &configuration::Configuration,
+) -> BTreeMap<models::ArgumentName, models::ArgumentInfo> {
+    let mut arguments = BTreeMap::new();
+    if metadata.scalar_types.0.contains_key("int4") {
+        arguments.insert(
+            root::AGGREGATES_LIMIT_ARGUMENT.into(),
+            models::ArgumentInfo {
+                description: Some(
+                    "Compute the aggregates over at most this many rows, regardless of the limit and offset of the rows returned, or over all of them if null.".to_string(),
+                ),
+                argument_type: models::Type::Nullable {
+                    underlying_type: Box::new(models::Type::Named {
+                        name: "int4".into(),
+                    }),
+                },
+            },
+        );
+    }
+    if config.distinct_on {
+        arguments.insert(
+            root::DISTINCT_ON_ARGUMENT.into(),
+            models::ArgumentInfo {
+                description: Some(
+                    "Return only the first row of each group of rows with equal values in these columns.".to_string(),
+                ),
+                argument_type: models::Type::Nullable {
+                    underlying_type: Box::new(models::Type::Array {
+                        element_type: Box::new(models::Type::Named {
+                            name: distinct_on_column_type_name(collection_name).into(),
+                        }),
+                    }),
+                },
+            },
+        );
+    }
+    if !metadata.scalar_types.0.contains_key("text") {
+        return arguments;
+    }
+    let column_names = |description: &str| models::ArgumentInfo {
+        description: Some(description.to_string()),
+        argument_type: models::Type::Nullable {
+            underlying_type: Box::new(models::Type::Array {
+                element_type: Box::new(models::Type::Named {
+                    name: "text".into(),
+                }),
+            }),
+        },
+    };
+
+    let has_composite_columns = table.columns.values().any(|column| match &column.r#type {
+        metadata::Type::CompositeType(_) => true,
+        metadata::Type::ArrayType(element_type) => {
+            matches!(**element_type, metadata::Type::CompositeType(_))
+        }
+        metadata::Type::ScalarType(_) => false,
+    });
+    if has_composite_columns {
+        arguments.insert(
+            root::COMPOSITES_AS_JSON_ARGUMENT.into(),
+            column_names(
+                "Return these composite columns as single JSON values, without selecting each of their fields.",
+            ),
+        );
+    }
+    if config.snapshot_reads {
+        arguments.insert(
+            root::SNAPSHOT_ARGUMENT.into(),
+            models::ArgumentInfo {
+                description: Some(
+                    "Read the rows as they were in this snapshot, or at this system time, rather than as they are now.".to_string(),
+                ),
+                argument_type: models::Type::Nullable {
+                    underlying_type: Box::new(models::Type::Named {
+                        name: "text".into(),
+                    }),
+                },
+            },
+        );
+    }
+    arguments
+}
+
+/// The name of the enum type of the columns a table can be made distinct on.
+fn distinct_on_column_type_name(collection_name: &models::CollectionName) -> String {
+    format!("{collection_name}_distinct_on_column")
+}
+
+/// The enum type of the columns a table can be made distinct on, which are the columns it can be
+/// selected and sorted by.
+fn distinct_on_column_type(table: &metadata::TableInfo) -> models::ScalarType {
+    models::ScalarType {
+        representation: Some(models::TypeRepresentation::Enum {
+            one_of: table
+                .columns
+                .iter()
+                .filter(|(_, column)| column.selectable && column.sortable)
+                .map(|(column_name, _)| column_name.to_string())
+                .collect(),
+        }),
+        aggregate_functions: BTreeMap::new(),
+        comparison_operators: BTreeMap::new(),
+    }
+}
+
+/// The foreign keys of a collection, referring to the collections of the foreign tables.
+fn foreign_keys(
+    collections_by_identifier: &BTreeMap<(Option<&str>, &str, &str), &str>,
+    foreign_relations: &metadata::ForeignRelations,
+    target: Option<&str>,
+    default_schema: &str,
+) -> Result<BTreeMap<String, models::ForeignKeyConstraint>, connector::ErrorResponse> {
+    foreign_relations
+        .0
+        .iter()
+        .map(
+            |(
+                constraint_name,
+                metadata::ForeignRelation {
+                    foreign_schema,
+                    foreign_table,
+                    column_mapping,
+                },
+            )| {
+                // the foreign schema used to be implied, so if it is not provided, we need to
+                // default back to the originating table's schema
+                let foreign_schema = foreign_schema.as_deref().unwrap_or(default_schema);
+                let foreign_collection = collections_by_identifier
+                    .get(&(target, foreign_schema, foreign_table.as_str()))
+                    .ok_or_else(|| {
+                        connector::ErrorResponse::new_internal_with_details(
+                            serde_json::Value::String(format!(
+                                "Unknown foreign table: {foreign_schema}.{foreign_table}"
+                            )),
+                        )
+                    })?;
+                Ok((
+                    constraint_name.clone(),
+                    models::ForeignKeyConstraint {
+                        foreign_collection: (*foreign_collection).into(),
+                        column_mapping: column_mapping.clone(),
+                    },
+                ))
+            },
+        )
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn column_with_default(description: Option<&str>) -> metadata::ColumnInfo {
+        metadata::ColumnInfo {
+            name: "created_at".to_string(),
+            r#type: metadata::Type::ScalarType("timestamptz".into()),
+            nullable: metadata::Nullable::NonNullable,
+            has_default: metadata::HasDefault::HasDefault,
+            default_expression: Some("now()".to_string()),
+            is_identity: metadata::IsIdentity::NotIdentity,
+            is_generated: metadata::IsGenerated::NotGenerated,
+            generates_uuid: false,
+            selectable: true,
+            insertable: true,
+            updatable: true,
+            filterable: true,
+            sortable: true,
+            description: description.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn the_default_of_a_column_without_a_description_is_described_alone() {
+        assert_eq!(
+            column_description(&column_with_default(None)).as_deref(),
+            Some("Defaults to `now()`.")
+        );
+    }
+
+    #[test]
+    fn the_default_follows_a_description_ending_in_a_full_stop() {
+        assert_eq!(
+            column_description(&column_with_default(Some("When the row was created."))).as_deref(),
+            Some("When the row was created.\n\nDefaults to `now()`.")
+        );
+    }
+
+    #[test]
+    fn a_description_without_a_full_stop_is_closed_before_the_default() {
+        assert_eq!(
+            column_description(&column_with_default(Some("When the row was created"))).as_deref(),
+            Some("When the row was created.\n\nDefaults to `now()`.")
+        );
+    }
+}
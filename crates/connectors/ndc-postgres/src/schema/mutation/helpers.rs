@@ -6,6 +6,7 @@ use ndc_sdk::models;
 
 use super::super::helpers::*;
 use query_engine_metadata::metadata;
+use query_engine_translation::translation::mutation::returning_aggregates::ReturningAggregate;
 
 /// Build a `ProcedureInfo` type from the given parameters.
 ///
@@ -14,11 +15,15 @@ use query_engine_metadata::metadata;
 /// also have to include the `int4` scalar type (if it isn't included for another reason elsewhere
 /// in the schema). So, this function creates that object type, optionally adds that scalar type,
 /// and then returns a `ProcedureInfo` that points to the correct object type.
+///
+/// The object type also has a field for each aggregate over the returned rows, such as the sum
+/// of one of their columns.
 pub fn make_procedure_type(
     name: models::ProcedureName,
     description: Option<String>,
     arguments: BTreeMap<models::ArgumentName, models::ArgumentInfo>,
     result_type: models::Type,
+    returning_aggregates: BTreeMap<models::FieldName, ReturningAggregate>,
     object_types: &mut BTreeMap<models::ObjectTypeName, models::ObjectType>,
     scalar_types: &mut BTreeMap<models::ScalarTypeName, models::ScalarType>,
 ) -> models::ProcedureInfo {
@@ -60,6 +65,25 @@ pub fn make_procedure_type(
         },
     );
 
+    // Aggregates of no rows are null.
+    for (field_name, aggregate) in returning_aggregates {
+        fields.insert(
+            field_name,
+            models::ObjectField {
+                description: Some(format!(
+                    "The {} of the '{}' column of the rows affected by the mutation",
+                    aggregate.function, aggregate.column
+                )),
+                r#type: models::Type::Nullable {
+                    underlying_type: Box::new(models::Type::Named {
+                        name: aggregate.return_type,
+                    }),
+                },
+                arguments: BTreeMap::new(),
+            },
+        );
+    }
+
     object_types.insert(
         object_type_name.clone(),
         models::ObjectType {
@@ -83,7 +107,7 @@ pub fn make_insert_objects_type(
     columns: &BTreeMap<models::FieldName, metadata::database::ColumnInfo>,
 ) -> models::ObjectType {
     let mut fields = BTreeMap::new();
-    for (name, column) in columns {
+    for (name, column) in columns.iter().filter(|(_, column)| column.insertable) {
         // Add the column if it is not generated.
         match column_to_insert_type(column, &WrapDefaultInNullable::Wrap) {
             None => {}
@@ -111,6 +135,10 @@ pub fn make_update_column_type(
     column_name: &models::FieldName,
     column_info: &metadata::database::ColumnInfo,
 ) -> Option<(models::ObjectTypeName, models::ObjectType)> {
+    // Columns we are not allowed to update are left out.
+    if !column_info.updatable {
+        return None;
+    }
     // Return an update column if it is not generated.
     match column_to_insert_type(column_info, &WrapDefaultInNullable::NoWrap) {
         None => None,
@@ -151,7 +179,8 @@ enum WrapDefaultInNullable {
 }
 
 /// For a column, build a matching `models::Type` that is will be used as input for insert and updates.
-/// If the column is generated, don't return any type, and if it has a default, mark it as nullable.
+/// If the column is generated, don't return any type, and if it has a default or generates a UUID,
+/// mark it as nullable.
 fn column_to_insert_type(
     column: &metadata::database::ColumnInfo,
     wrap_in_null: &WrapDefaultInNullable,
@@ -169,6 +198,10 @@ fn column_to_insert_type(
         metadata::database::ColumnInfo {
             has_default: metadata::database::HasDefault::HasDefault,
             ..
+        }
+        | metadata::database::ColumnInfo {
+            generates_uuid: true,
+            ..
         } => {
             Some({
                 let typ = column_to_type(column);
@@ -4,13 +4,16 @@ use std::collections::BTreeMap;
 
 use ndc_sdk::models;
 
+use query_engine_translation::translation::helpers::Env;
 use query_engine_translation::translation::mutation;
+use query_engine_translation::translation::mutation::returning_aggregates;
 
 use super::super::helpers::*;
 use super::helpers::*;
 
 /// given a v1 `DeleteMutation`, turn it into a `ProcedureInfo` to be output in the schema
 pub fn delete_to_procedure(
+    env: &Env,
     name: &models::ProcedureName,
     delete: &mutation::v1::delete::DeleteMutation,
     object_types: &mut BTreeMap<models::ObjectTypeName, models::ObjectType>,
@@ -40,6 +43,7 @@ pub fn delete_to_procedure(
                 models::Type::Named {
                     name: collection_name.as_str().into(),
                 },
+                returning_aggregates::of_collection(env, collection_name),
                 object_types,
                 scalar_types,
             )
@@ -49,6 +53,7 @@ pub fn delete_to_procedure(
 
 /// Given a v1 `InsertMutation`, turn it into a `ProcedureInfo` to be output in the schema.
 pub fn insert_to_procedure(
+    env: &Env,
     name: &models::ProcedureName,
     insert: &mutation::v1::insert::InsertMutation,
     object_types: &mut BTreeMap<models::ObjectTypeName, models::ObjectType>,
@@ -76,6 +81,7 @@ pub fn insert_to_procedure(
         models::Type::Named {
             name: insert.collection_name.as_str().into(),
         },
+        returning_aggregates::of_collection(env, &insert.collection_name),
         object_types,
         scalar_types,
     )
@@ -4,13 +4,16 @@ use std::collections::BTreeMap;
 
 use ndc_sdk::models;
 
+use query_engine_translation::translation::helpers::Env;
 use query_engine_translation::translation::mutation;
+use query_engine_translation::translation::mutation::returning_aggregates;
 
 use super::super::helpers::*;
 use super::helpers::*;
 
 /// given an v2 `DeleteMutation`, turn it into a `ProcedureInfo` to be output in the schema
 pub fn delete_to_procedure(
+    env: &Env,
     name: &models::ProcedureName,
     delete: &mutation::v2::delete::DeleteMutation,
     object_types: &mut BTreeMap<models::ObjectTypeName, models::ObjectType>,
@@ -49,6 +52,7 @@ pub fn delete_to_procedure(
         models::Type::Named {
             name: delete_by_key.collection_name.as_str().into(),
         },
+        returning_aggregates::of_collection(env, &delete_by_key.collection_name),
         object_types,
         scalar_types,
     )
@@ -56,6 +60,7 @@ pub fn delete_to_procedure(
 
 /// Given an v2 `UpdateMutation`, turn it into a `ProcedureInfo` to be output in the schema.
 pub fn update_to_procedure(
+    env: &Env,
     procedure_name: &models::ProcedureName,
     update: &mutation::v2::update::UpdateMutation,
     object_types: &mut BTreeMap<models::ObjectTypeName, models::ObjectType>,
@@ -169,6 +174,57 @@ pub fn update_to_procedure(
         },
     );
 
+    // if matched argument.
+    // Is of the form if_matched: { <column_name>: <value>, ... }, all of them optional.
+    // It only depends on the collection, so the update procedures of a collection share it.
+    let if_matched_object_type_name: models::ObjectTypeName = format!(
+        "{}_{}",
+        update_by_key.if_matched.argument_name, update_by_key.collection_name
+    )
+    .into();
+
+    object_types.insert(
+        if_matched_object_type_name.clone(),
+        models::ObjectType {
+            description: Some(format!(
+                "The expected values of the columns of the '{}' collection",
+                update_by_key.collection_name
+            )),
+            // comparing a column reads it, so columns which may not be selected are left out.
+            fields: update_by_key
+                .table_columns
+                .iter()
+                .filter(|(_, column_info)| column_info.selectable)
+                .map(|(column_name, column_info)| {
+                    (
+                        column_name.clone(),
+                        models::ObjectField {
+                            description: Some(format!(
+                                "The expected value of the '{column_name}' column."
+                            )),
+                            r#type: models::Type::Nullable {
+                                underlying_type: Box::new(type_to_type(&column_info.r#type)),
+                            },
+                            arguments: BTreeMap::new(),
+                        },
+                    )
+                })
+                .collect(),
+        },
+    );
+
+    arguments.insert(
+        update_by_key.if_matched.argument_name.clone(),
+        models::ArgumentInfo {
+            argument_type: models::Type::Nullable {
+                underlying_type: Box::new(models::Type::Named {
+                    name: if_matched_object_type_name.as_str().into(),
+                }),
+            },
+            description: Some(update_by_key.if_matched.description.clone()),
+        },
+    );
+
     // Make a type for the procedure.
     make_procedure_type(
         procedure_name.clone(),
@@ -177,6 +233,7 @@ pub fn update_to_procedure(
         models::Type::Named {
             name: update_by_key.collection_name.as_str().into(),
         },
+        returning_aggregates::of_collection(env, &update_by_key.collection_name),
         object_types,
         scalar_types,
     )
@@ -184,6 +241,7 @@ pub fn update_to_procedure(
 
 /// Given an v2 `InsertMutation`, turn it into a `ProcedureInfo` to be output in the schema.
 pub fn insert_to_procedure(
+    env: &Env,
     name: &models::ProcedureName,
     insert: &mutation::v2::insert::InsertMutation,
     object_types: &mut BTreeMap<models::ObjectTypeName, models::ObjectType>,
@@ -225,6 +283,7 @@ pub fn insert_to_procedure(
         models::Type::Named {
             name: insert.collection_name.as_str().into(),
         },
+        returning_aggregates::of_collection(env, &insert.collection_name),
         object_types,
         scalar_types,
     )
@@ -6,32 +6,140 @@ use ndc_postgres_configuration::get_connect_options;
 use percent_encoding::percent_decode_str;
 use sqlx::postgres::{PgPool, PgPoolOptions, PgRow};
 use sqlx::{Connection, Row};
+use std::collections::BTreeMap;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
 use thiserror::Error;
 use tracing::{info_span, Instrument};
 use url::Url;
 
 use ndc_postgres_configuration::environment::Environment;
+use ndc_postgres_configuration::Configuration;
 use ndc_postgres_configuration::ConnectionUri;
 use ndc_postgres_configuration::PoolSettings;
+use ndc_postgres_configuration::ReadReplicas;
+use ndc_postgres_configuration::UsageStatisticsSettings;
 use query_engine_execution::database_info::{self, DatabaseInfo, DatabaseVersion};
 use query_engine_execution::metrics;
 
+use crate::replicas;
+use crate::targets;
+use crate::usage;
+
 /// State for our connector.
 #[derive(Debug)]
 pub struct State {
     pub pool: PgPool,
     pub database_info: DatabaseInfo,
+    /// Whether the `pg_hint_plan` extension is loaded, so we can send it planner hints.
+    pub supports_planner_hints: bool,
+    pub read_replicas: Option<Arc<replicas::ReadReplicas>>,
+    /// The other databases tables are served from, by target name.
+    pub targets: BTreeMap<String, Target>,
     pub query_metrics: metrics::Metrics,
     pub configuration_metrics: ndc_postgres_configuration::Metrics,
+    /// How each collection has been used recently, if usage statistics are enabled.
+    pub usage_statistics: Option<usage::UsageStatistics>,
+    /// The configuration with the SQL of its Native Operations read again from their files,
+    /// once they have changed. See [crate::watch].
+    pub reloaded_configuration: RwLock<Option<Arc<Configuration>>>,
+}
+
+/// A database tables are served from, other than the primary one.
+#[derive(Debug)]
+pub struct Target {
+    pub pool: PgPool,
+    pub database_info: DatabaseInfo,
+}
+
+impl State {
+    /// The database a connection target refers to, or `None` for the primary database.
+    pub fn target(&self, target: Option<&str>) -> Result<Option<&Target>, targets::Error> {
+        target
+            .map(|target| {
+                self.targets
+                    .get(target)
+                    .ok_or_else(|| targets::Error::UnknownTarget {
+                        target: target.to_string(),
+                    })
+            })
+            .transpose()
+    }
+
+    /// The pool to run mutations against: that of the target, or the primary database if there
+    /// is none.
+    pub fn pool(&self, target: Option<&Target>) -> &PgPool {
+        target.map_or(&self.pool, |target| &target.pool)
+    }
+
+    /// The pool to run queries against. Queries against the primary database run against the
+    /// next healthy read replica if there is one.
+    pub fn read_pool(&self, target: Option<&Target>) -> &PgPool {
+        match target {
+            None => self
+                .read_replicas
+                .as_ref()
+                .and_then(|read_replicas| read_replicas.next_healthy_pool())
+                .unwrap_or(&self.pool),
+            Some(target) => &target.pool,
+        }
+    }
+
+    /// Information about the database of the target, or the primary database if there is none.
+    pub fn database_info(&self, target: Option<&Target>) -> &DatabaseInfo {
+        target.map_or(&self.database_info, |target| &target.database_info)
+    }
+
+    /// The configuration to serve requests with: the one the connector started with, unless its
+    /// Native Operations have been reloaded since.
+    pub fn configuration(&self, configuration: &Arc<Configuration>) -> Arc<Configuration> {
+        self.reloaded_configuration
+            .read()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+            .clone()
+            .unwrap_or_else(|| configuration.clone())
+    }
+}
+
+/// The first version of PostgreSQL with `JSON_TABLE`, in the format of `server_version_num`.
+const JSON_TABLE_SERVER_VERSION: u32 = 170_000;
+
+/// Check that the databases JSON tables are in can make their rows with `JSON_TABLE`. Databases
+/// which do not report their version are assumed to.
+pub fn check_json_tables(
+    state: &State,
+    configuration: &Configuration,
+) -> Result<(), InitializationError> {
+    for collection in &configuration.json_tables {
+        let target = targets::collection_target(&configuration.metadata, collection.as_str())
+            .and_then(|target| state.targets.get(target));
+        if let Some(version) = state
+            .database_info(target)
+            .system_version
+            .number
+            .filter(|version| *version < JSON_TABLE_SERVER_VERSION)
+        {
+            return Err(InitializationError::JsonTableNotSupported {
+                collection: collection.clone(),
+                version,
+            });
+        }
+    }
+    Ok(())
 }
 
 /// Create a connection pool and wrap it inside a connector State.
+#[allow(clippy::too_many_arguments)]
 pub async fn create_state(
     connection_uri: &str,
     environment: &impl Environment,
     pool_settings: &PoolSettings,
+    read_replicas: Option<&ReadReplicas>,
+    targets: &BTreeMap<String, String>,
+    usage_statistics: Option<&UsageStatisticsSettings>,
     metrics_registry: &mut prometheus::Registry,
     version_tag: ndc_postgres_configuration::VersionTag,
+    configuration_hash: &str,
 ) -> Result<State, InitializationError> {
     let connection_url: Url = connection_uri
         .parse()
@@ -43,7 +151,7 @@ pub async fn create_state(
         ))
         .await?;
 
-    let database_version = {
+    let (database_version, supports_planner_hints) = {
         let mut connection = pool
             .acquire()
             .await
@@ -57,11 +165,20 @@ pub async fn create_state(
             .ok();
         // Extract the database version number.
         let number = connection.server_version_num();
-        DatabaseVersion { string, number }
+        // `pg_hint_plan` only defines its settings once it is loaded.
+        let supports_planner_hints =
+            sqlx::query("SELECT current_setting('pg_hint_plan.enable_hint', true) = 'on'")
+                .map(|row: PgRow| row.get::<Option<bool>, _>(0))
+                .fetch_one(connection.as_mut())
+                .await
+                .ok()
+                .flatten()
+                .unwrap_or(false);
+        (DatabaseVersion { string, number }, supports_planner_hints)
     };
     let database_info = parse_database_info(&connection_url, database_version);
 
-    let (query_metrics, configuration_metrics) = async {
+    let (query_metrics, configuration_metrics, usage_statistics) = async {
         let query_metrics_inner = metrics::Metrics::initialize(metrics_registry)
             .map_err(InitializationError::MetricsError)?;
         query_metrics_inner.set_pool_options_metrics(pool.options());
@@ -70,18 +187,86 @@ pub async fn create_state(
             ndc_postgres_configuration::Metrics::initialize(metrics_registry)
                 .map_err(InitializationError::MetricsError)?;
 
-        Ok((query_metrics_inner, configuration_metrics_inner))
+        let usage_statistics_inner = usage_statistics
+            .map(|settings| usage::UsageStatistics::initialize(metrics_registry, settings))
+            .transpose()
+            .map_err(InitializationError::MetricsError)?;
+
+        Ok((
+            query_metrics_inner,
+            configuration_metrics_inner,
+            usage_statistics_inner,
+        ))
     }
     .instrument(info_span!("Setup metrics"))
     .await?;
 
     configuration_metrics.set_configuration_version(version_tag);
+    configuration_metrics.set_configuration_hash(configuration_hash);
+    configuration_metrics.set_database_version(database_info.system_version.number);
+
+    let read_replicas = match read_replicas {
+        None => None,
+        Some(settings) => {
+            let pools = settings
+                .connection_uris
+                .iter()
+                .map(|connection_uri| create_lazy_pool(connection_uri, environment, pool_settings))
+                .collect::<Result<Vec<_>, _>>()?;
+            let read_replicas = Arc::new(replicas::ReadReplicas::new(
+                pools,
+                Duration::from_secs(settings.max_replication_lag),
+                Duration::from_secs(settings.lag_check_interval),
+                settings.read_your_writes_window.map(Duration::from_secs),
+            ));
+            // Measure the lag of the replicas before serving, so that queries are spread across
+            // the healthy ones from the start.
+            read_replicas
+                .check_replication_lag(&query_metrics)
+                .instrument(info_span!("Check replication lag"))
+                .await;
+            replicas::spawn_replication_lag_monitor(
+                &read_replicas,
+                query_metrics.clone(),
+                Duration::from_secs(settings.lag_check_interval),
+            );
+            Some(read_replicas)
+        }
+    };
+
+    let targets = targets
+        .iter()
+        .map(|(name, connection_uri)| {
+            let connection_url: Url = connection_uri
+                .parse()
+                .map_err(InitializationError::InvalidConnectionUri)?;
+            Ok((
+                name.clone(),
+                Target {
+                    pool: create_lazy_pool(connection_uri, environment, pool_settings)?,
+                    // the pool does not connect on startup, so the version is not known.
+                    database_info: parse_database_info(
+                        &connection_url,
+                        DatabaseVersion {
+                            string: None,
+                            number: None,
+                        },
+                    ),
+                },
+            ))
+        })
+        .collect::<Result<_, InitializationError>>()?;
 
     Ok(State {
         pool,
         database_info,
+        supports_planner_hints,
+        read_replicas,
+        targets,
         query_metrics,
         configuration_metrics,
+        usage_statistics,
+        reloaded_configuration: RwLock::new(None),
     })
 }
 
@@ -92,10 +277,41 @@ async fn create_pool(
     environment: impl Environment,
     pool_settings: &PoolSettings,
 ) -> Result<PgPool, InitializationError> {
+    let pool_options = make_pool_options(pool_settings, &environment)?;
     let connect_options = get_connect_options(&ConnectionUri::from(connection_url), environment)
         .map_err(InitializationError::InvalidConnectOptions)?;
 
-    let pool_options = match pool_settings.check_connection_after_idle {
+    pool_options
+        .connect_with(connect_options)
+        .await
+        .map_err(InitializationError::UnableToCreatePool)
+}
+
+/// Create a connection pool which does not connect until it is first used.
+/// An unreachable read replica or target should not prevent the connector from starting.
+fn create_lazy_pool(
+    connection_url: &str,
+    environment: impl Environment,
+    pool_settings: &PoolSettings,
+) -> Result<PgPool, InitializationError> {
+    let pool_options = make_pool_options(pool_settings, &environment)?;
+    let connect_options = get_connect_options(&ConnectionUri::from(connection_url), environment)
+        .map_err(InitializationError::InvalidConnectOptions)?;
+
+    Ok(pool_options.connect_lazy_with(connect_options))
+}
+
+/// The pool options corresponding to the pool settings, reading those which refer to the
+/// environment.
+fn make_pool_options(
+    pool_settings: &PoolSettings,
+    environment: impl Environment,
+) -> Result<PgPoolOptions, InitializationError> {
+    let check_connection_after_idle = pool_settings
+        .check_connection_after_idle
+        .resolve(&environment)
+        .map_err(InitializationError::InvalidPoolSettings)?;
+    let pool_options = match check_connection_after_idle {
         // Unless specified otherwise, sqlx will always ping on acquire.
         None => PgPoolOptions::new(),
         // If the user set a time, only check after being idle for that time.
@@ -112,22 +328,33 @@ async fn create_pool(
             }),
     };
 
-    pool_options
-        .max_connections(pool_settings.max_connections)
-        .acquire_timeout(std::time::Duration::from_secs(pool_settings.pool_timeout))
+    Ok(pool_options
+        .max_connections(
+            pool_settings
+                .max_connections
+                .resolve(&environment)
+                .map_err(InitializationError::InvalidPoolSettings)?,
+        )
+        .acquire_timeout(std::time::Duration::from_secs(
+            pool_settings
+                .pool_timeout
+                .resolve(&environment)
+                .map_err(InitializationError::InvalidPoolSettings)?,
+        ))
         .idle_timeout(
             pool_settings
                 .idle_timeout
+                .resolve(&environment)
+                .map_err(InitializationError::InvalidPoolSettings)?
                 .map(std::time::Duration::from_secs),
         )
         .max_lifetime(
             pool_settings
                 .connection_lifetime
+                .resolve(&environment)
+                .map_err(InitializationError::InvalidPoolSettings)?
                 .map(std::time::Duration::from_secs),
-        )
-        .connect_with(connect_options)
-        .await
-        .map_err(InitializationError::UnableToCreatePool)
+        ))
 }
 
 /// Parses the database information from the connection string.
@@ -173,12 +400,19 @@ pub enum InitializationError {
     InvalidConnectionUri(url::ParseError),
     #[error("Invalid connect options: {0}")]
     InvalidConnectOptions(anyhow::Error),
+    #[error("invalid pool settings: {0}")]
+    InvalidPoolSettings(ndc_postgres_configuration::EnvironmentValueError),
     #[error("unable to initialize connection pool: {0}")]
     UnableToCreatePool(sqlx::Error),
     #[error("unable to connect to the database: {0}")]
     UnableToConnect(sqlx::Error),
     #[error("error initializing metrics: {0}")]
     MetricsError(prometheus::Error),
+    #[error("the JSON table {collection} needs PostgreSQL 17 or later, but its database is version {version}")]
+    JsonTableNotSupported {
+        collection: ndc_sdk::models::CollectionName,
+        version: u32,
+    },
 }
 
 #[cfg(test)]
@@ -0,0 +1,161 @@
+//! Route requests to the database which the collections they refer to are in.
+
+use ndc_sdk::models;
+use thiserror::Error;
+
+use query_engine_metadata::metadata;
+
+/// Find the connection target of the collections a request refers to, or `None` for the primary
+/// database.
+pub fn target<'a>(
+    metadata: &'a metadata::Metadata,
+    collections: impl IntoIterator<Item = impl AsRef<str>>,
+) -> Result<Option<&'a str>, Error> {
+    single_target(collections.into_iter().map(|collection| {
+        let collection = collection.as_ref();
+        (
+            collection.to_string(),
+            collection_target(metadata, collection),
+        )
+    }))
+}
+
+/// The connection target of a collection. Native Queries are in the primary database, unless
+/// they describe a JSON table of a table in another one.
+pub fn collection_target<'a>(
+    metadata: &'a metadata::Metadata,
+    collection: &str,
+) -> Option<&'a str> {
+    let collection = models::CollectionName::from(collection);
+    match metadata.tables.0.get(&collection) {
+        Some(table) => table.target.as_deref(),
+        None => metadata
+            .native_operations
+            .queries
+            .0
+            .get(&collection)
+            .and_then(|native_query| native_query.target.as_deref()),
+    }
+}
+
+/// A request runs against a single database, so the targets of everything it refers to must be
+/// the same. Each target is given with the name of what it is the target of, for the error.
+pub fn single_target<'a>(
+    targets: impl IntoIterator<Item = (String, Option<&'a str>)>,
+) -> Result<Option<&'a str>, Error> {
+    let mut found: Option<(String, Option<&str>)> = None;
+    for (name, target) in targets {
+        match &found {
+            None => found = Some((name, target)),
+            Some((first, first_target)) if *first_target != target => {
+                return Err(Error::DifferentTargets {
+                    first: first.clone(),
+                    second: name,
+                })
+            }
+            Some(_) => {}
+        }
+    }
+    Ok(found.and_then(|(_, target)| target))
+}
+
+/// A request which cannot be routed to a single database.
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("'{first}' and '{second}' are in different databases, so they cannot be used in the same request")]
+    DifferentTargets { first: String, second: String },
+    #[error("there is no connection to the target '{target}'")]
+    UnknownTarget { target: String },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn example_metadata() -> metadata::Metadata {
+        let table = |target: Option<&str>| metadata::TableInfo {
+            schema_name: "public".to_string(),
+            table_name: "table".to_string(),
+            columns: std::collections::BTreeMap::new(),
+            uniqueness_constraints: metadata::UniquenessConstraints::default(),
+            foreign_relations: metadata::ForeignRelations::default(),
+            description: None,
+            inherited_rows: metadata::InheritedRows::default(),
+            target: target.map(str::to_string),
+            statement_timeout_ms: None,
+            logical_keys: metadata::UniquenessConstraints::default(),
+            row_expiry: None,
+            soft_delete: None,
+            audit_columns: std::collections::BTreeMap::new(),
+            computed_relationships: std::collections::BTreeMap::new(),
+            computed_fields: std::collections::BTreeMap::new(),
+            insertable: true,
+            updatable: true,
+            deletable: true,
+            distribution: None,
+        };
+        let json_table = metadata::NativeQueryInfo {
+            sql: metadata::NativeQuerySqlEither::NativeQuerySql(metadata::NativeQuerySql::Inline {
+                sql: metadata::NativeQueryParts(vec![]),
+            }),
+            columns: std::collections::BTreeMap::new(),
+            arguments: std::collections::BTreeMap::new(),
+            description: None,
+            foreign_relations: metadata::ForeignRelations::default(),
+            target: Some("analytics".to_string()),
+        };
+        metadata::Metadata {
+            tables: metadata::TablesInfo(
+                [
+                    ("albums".into(), table(None)),
+                    ("artists".into(), table(None)),
+                    ("events".into(), table(Some("analytics"))),
+                    ("sessions".into(), table(Some("analytics"))),
+                ]
+                .into(),
+            ),
+            native_operations: metadata::NativeOperations {
+                queries: metadata::NativeQueries([("event_tags".into(), json_table)].into()),
+                mutations: metadata::NativeMutations::default(),
+            },
+            ..metadata::Metadata::default()
+        }
+    }
+
+    #[test]
+    fn collections_in_the_primary_database_have_no_target() {
+        let metadata = example_metadata();
+        assert_eq!(
+            target(&metadata, ["albums", "artists", "a_native_query"]).unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn collections_in_a_target_are_routed_to_it() {
+        let metadata = example_metadata();
+        assert_eq!(
+            target(&metadata, ["events", "sessions"]).unwrap(),
+            Some("analytics")
+        );
+    }
+
+    #[test]
+    fn json_tables_are_routed_to_the_target_of_their_table() {
+        let metadata = example_metadata();
+        assert_eq!(
+            target(&metadata, ["event_tags", "events"]).unwrap(),
+            Some("analytics")
+        );
+    }
+
+    #[test]
+    fn collections_in_different_databases_are_rejected() {
+        let metadata = example_metadata();
+        assert!(matches!(
+            target(&metadata, ["events", "albums"]),
+            Err(Error::DifferentTargets { first, second })
+                if first == "events" && second == "albums"
+        ));
+    }
+}
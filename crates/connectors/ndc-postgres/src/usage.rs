@@ -0,0 +1,334 @@
+//! Statistics of how each collection is used.
+//!
+//! When enabled, the connector counts the queries and mutations of each collection, the rows
+//! its queries return, and how long they take, in buckets of one interval each. A background
+//! task slides the window along by a bucket every interval, and exports the totals over the
+//! window as metrics, including zeros for the tables which were not used at all.
+
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::{Arc, Mutex, Weak};
+use std::time::Duration;
+
+use prometheus::{GaugeVec, IntGaugeVec, Registry};
+
+use ndc_postgres_configuration::UsageStatisticsSettings;
+
+use crate::state::State;
+
+/// How a collection was used.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CollectionUsage {
+    pub queries: u64,
+    pub mutations: u64,
+    /// The rows returned by the queries.
+    pub rows: u64,
+    /// The time taken by the queries and mutations together.
+    pub latency: Duration,
+}
+
+impl CollectionUsage {
+    /// The time taken by a query or mutation on average, if there were any.
+    pub fn average_latency(&self) -> Option<Duration> {
+        u32::try_from(self.queries + self.mutations)
+            .ok()
+            .and_then(|requests| self.latency.checked_div(requests))
+    }
+
+    fn add(&mut self, other: &CollectionUsage) {
+        self.queries += other.queries;
+        self.mutations += other.mutations;
+        self.rows += other.rows;
+        self.latency += other.latency;
+    }
+}
+
+/// The usage of the collections over a sliding window.
+#[derive(Debug)]
+pub struct UsageStatistics {
+    /// The usage of each collection in each interval of the window, the current one last.
+    buckets: Mutex<VecDeque<BTreeMap<String, CollectionUsage>>>,
+    bucket_count: usize,
+    queries: IntGaugeVec,
+    mutations: IntGaugeVec,
+    rows: IntGaugeVec,
+    average_latency: GaugeVec,
+}
+
+impl UsageStatistics {
+    /// Set up the statistics, and the gauges they are exported as.
+    pub fn initialize(
+        metrics_registry: &mut Registry,
+        settings: &UsageStatisticsSettings,
+    ) -> Result<Self, prometheus::Error> {
+        let queries = add_int_gauge_vec_metric(
+            metrics_registry,
+            "ndc_postgres_collection_usage_queries",
+            "Successful queries over the usage statistics window, by collection.",
+        )?;
+        let mutations = add_int_gauge_vec_metric(
+            metrics_registry,
+            "ndc_postgres_collection_usage_mutations",
+            "Successful mutation operations over the usage statistics window, by collection.",
+        )?;
+        let rows = add_int_gauge_vec_metric(
+            metrics_registry,
+            "ndc_postgres_collection_usage_rows",
+            "Rows returned by queries over the usage statistics window, by collection.",
+        )?;
+        let average_latency = add_gauge_vec_metric(
+            metrics_registry,
+            "ndc_postgres_collection_usage_average_latency",
+            "Average time taken by queries and mutations over the usage statistics window, in seconds, by collection.",
+        )?;
+
+        Ok(UsageStatistics::new(
+            settings,
+            queries,
+            mutations,
+            rows,
+            average_latency,
+        ))
+    }
+
+    fn new(
+        settings: &UsageStatisticsSettings,
+        queries: IntGaugeVec,
+        mutations: IntGaugeVec,
+        rows: IntGaugeVec,
+        average_latency: GaugeVec,
+    ) -> Self {
+        let bucket_count = usize::try_from(settings.window.div_ceil(settings.interval.max(1)))
+            .unwrap_or(usize::MAX)
+            .max(1);
+        UsageStatistics {
+            buckets: Mutex::new(VecDeque::from([BTreeMap::new()])),
+            bucket_count,
+            queries,
+            mutations,
+            rows,
+            average_latency,
+        }
+    }
+
+    /// Count a successful query of a collection.
+    pub fn record_query(&self, collection: &str, rows: u64, latency: Duration) {
+        self.record(
+            collection,
+            &CollectionUsage {
+                queries: 1,
+                mutations: 0,
+                rows,
+                latency,
+            },
+        );
+    }
+
+    /// Count a successful mutation operation on a collection.
+    pub fn record_mutation(&self, collection: &str, latency: Duration) {
+        self.record(
+            collection,
+            &CollectionUsage {
+                queries: 0,
+                mutations: 1,
+                rows: 0,
+                latency,
+            },
+        );
+    }
+
+    fn record(&self, collection: &str, usage: &CollectionUsage) {
+        let mut buckets = self
+            .buckets
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+        if let Some(bucket) = buckets.back_mut() {
+            bucket.entry(collection.to_string()).or_default().add(usage);
+        }
+    }
+
+    /// The usage of each collection over the window.
+    pub fn totals(&self) -> BTreeMap<String, CollectionUsage> {
+        let buckets = self
+            .buckets
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+        let mut totals = BTreeMap::<String, CollectionUsage>::new();
+        for bucket in buckets.iter() {
+            for (collection, usage) in bucket {
+                totals.entry(collection.clone()).or_default().add(usage);
+            }
+        }
+        totals
+    }
+
+    /// Start a new interval, forgetting the oldest one once the window is full.
+    fn slide(&self) {
+        let mut buckets = self
+            .buckets
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+        buckets.push_back(BTreeMap::new());
+        while buckets.len() > self.bucket_count {
+            buckets.pop_front();
+        }
+    }
+
+    /// Set the gauges to the usage over the window of the given collections, and of any other
+    /// collections which were used.
+    fn export(&self, collections: &[String]) -> BTreeMap<String, CollectionUsage> {
+        let mut totals = self.totals();
+        for collection in collections {
+            totals.entry(collection.clone()).or_default();
+        }
+
+        self.queries.reset();
+        self.mutations.reset();
+        self.rows.reset();
+        self.average_latency.reset();
+        for (collection, usage) in &totals {
+            let labels = [collection.as_str()];
+            self.queries
+                .with_label_values(&labels)
+                .set(i64::try_from(usage.queries).unwrap_or(i64::MAX));
+            self.mutations
+                .with_label_values(&labels)
+                .set(i64::try_from(usage.mutations).unwrap_or(i64::MAX));
+            self.rows
+                .with_label_values(&labels)
+                .set(i64::try_from(usage.rows).unwrap_or(i64::MAX));
+            self.average_latency.with_label_values(&labels).set(
+                usage
+                    .average_latency()
+                    .map_or(0.0, |latency| latency.as_secs_f64()),
+            );
+        }
+        totals
+    }
+}
+
+/// Export the usage statistics as metrics every interval, and slide their window along.
+///
+/// Like the health monitor, the task only holds on to the state weakly, and stops once it is
+/// dropped.
+pub fn spawn_usage_reporter(
+    state: &Arc<State>,
+    settings: &UsageStatisticsSettings,
+    collections: Vec<String>,
+) {
+    let state: Weak<State> = Arc::downgrade(state);
+    let interval = Duration::from_secs(settings.interval.max(1));
+    tokio::spawn(async move {
+        let mut interval =
+            tokio::time::interval_at(tokio::time::Instant::now() + interval, interval);
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            interval.tick().await;
+            let Some(state) = state.upgrade() else {
+                break;
+            };
+            let Some(usage) = &state.usage_statistics else {
+                break;
+            };
+            let totals = usage.export(&collections);
+            usage.slide();
+            let unused = totals
+                .values()
+                .filter(|usage| usage.queries == 0 && usage.mutations == 0)
+                .count();
+            tracing::info!(
+                collections = totals.len(),
+                unused_collections = unused,
+                "Updated the usage statistics"
+            );
+        }
+    });
+}
+
+/// Create a new int gauge metric labelled by collection and register it with the provided
+/// Prometheus Registry.
+fn add_int_gauge_vec_metric(
+    metrics_registry: &mut Registry,
+    metric_name: &str,
+    metric_description: &str,
+) -> Result<IntGaugeVec, prometheus::Error> {
+    let int_gauge_vec = IntGaugeVec::new(
+        prometheus::Opts::new(metric_name, metric_description),
+        &["collection"],
+    )?;
+    metrics_registry.register(Box::new(int_gauge_vec.clone()))?;
+    Ok(int_gauge_vec)
+}
+
+/// Create a new gauge metric labelled by collection and register it with the provided
+/// Prometheus Registry.
+fn add_gauge_vec_metric(
+    metrics_registry: &mut Registry,
+    metric_name: &str,
+    metric_description: &str,
+) -> Result<GaugeVec, prometheus::Error> {
+    let gauge_vec = GaugeVec::new(
+        prometheus::Opts::new(metric_name, metric_description),
+        &["collection"],
+    )?;
+    metrics_registry.register(Box::new(gauge_vec.clone()))?;
+    Ok(gauge_vec)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    fn usage_statistics(window: u64, interval: u64) -> UsageStatistics {
+        UsageStatistics::initialize(
+            &mut Registry::new(),
+            &UsageStatisticsSettings { window, interval },
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn counts_requests_over_the_window() {
+        let usage = usage_statistics(120, 60);
+        usage.record_query("Album", 3, Duration::from_millis(10));
+        usage.slide();
+        usage.record_query("Album", 2, Duration::from_millis(30));
+        usage.record_mutation("Artist", Duration::from_millis(5));
+
+        let totals = usage.totals();
+        assert_eq!(
+            totals["Album"],
+            CollectionUsage {
+                queries: 2,
+                mutations: 0,
+                rows: 5,
+                latency: Duration::from_millis(40),
+            }
+        );
+        assert_eq!(
+            totals["Album"].average_latency(),
+            Some(Duration::from_millis(20))
+        );
+        assert_eq!(totals["Artist"].mutations, 1);
+
+        // the first interval falls out of the window.
+        usage.slide();
+        assert_eq!(usage.totals()["Album"].rows, 2);
+        usage.slide();
+        assert!(usage.totals().is_empty());
+    }
+
+    #[test]
+    fn exports_unused_collections() {
+        let usage = usage_statistics(60, 60);
+        usage.record_query("Album", 1, Duration::from_millis(10));
+
+        let totals = usage.export(&["Album".to_string(), "Track".to_string()]);
+
+        assert_eq!(totals["Track"], CollectionUsage::default());
+        assert_eq!(totals["Track"].average_latency(), None);
+        assert_eq!(usage.rows.with_label_values(&["Album"]).get(), 1);
+        assert_eq!(usage.queries.with_label_values(&["Track"]).get(), 0);
+    }
+}
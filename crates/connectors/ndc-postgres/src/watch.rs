@@ -0,0 +1,79 @@
+//! Reload the SQL of Native Operations when the files it is read from change.
+//!
+//! This is only done when the configuration asks for it, as it is meant for development: the
+//! files are polled in the background, and requests are served with the new SQL once it has been
+//! read and the configuration has been checked with it, as `validate` does. Invalid SQL, such as
+//! SQL which refers to parameters which are not arguments, is rejected, and the previous SQL is
+//! kept.
+
+use std::sync::{Arc, Weak};
+
+use ndc_postgres_configuration::watch::{NativeQueryFiles, POLL_INTERVAL};
+use ndc_postgres_configuration::Configuration;
+
+use crate::state::State;
+
+/// Watch the SQL files of the Native Operations of the configuration, if it asks for it, and
+/// reload them into the state when they change.
+///
+/// Like the health monitor, the task only holds on to the state weakly, and stops once it is
+/// dropped.
+pub fn spawn_native_query_watcher(state: &Arc<State>, configuration: &Arc<Configuration>) {
+    if !configuration.watch_native_query_files {
+        return;
+    }
+    let Some(configuration_directory) = &configuration.configuration_directory else {
+        tracing::warn!("The configuration directory is unknown, so SQL files are not watched");
+        return;
+    };
+    let mut files = NativeQueryFiles::new(
+        configuration_directory,
+        &configuration.metadata.native_operations,
+    );
+    if files.is_empty() {
+        return;
+    }
+    tracing::info!("Watching Native Operation SQL files for changes");
+
+    let state: Weak<State> = Arc::downgrade(state);
+    let configuration = configuration.clone();
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(POLL_INTERVAL);
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            interval.tick().await;
+            let Some(state) = state.upgrade() else {
+                break;
+            };
+            let changed = files.changed();
+            if changed.is_empty() {
+                continue;
+            }
+            match files.reload(&configuration) {
+                Ok(reloaded) => {
+                    let mut current = state
+                        .reloaded_configuration
+                        .write()
+                        .unwrap_or_else(std::sync::PoisonError::into_inner);
+                    state.configuration_metrics.replace_configuration_hash(
+                        current.as_ref().unwrap_or(&configuration).fingerprint(),
+                        reloaded.fingerprint(),
+                    );
+                    tracing::info!(
+                        files = ?changed,
+                        configuration.fingerprint = reloaded.fingerprint(),
+                        "Reloaded Native Operation SQL files"
+                    );
+                    *current = Some(Arc::new(reloaded));
+                }
+                Err(errors) => {
+                    tracing::error!(
+                        files = ?changed,
+                        ?errors,
+                        "Native Operation SQL files were not reloaded, as they are invalid"
+                    );
+                }
+            }
+        }
+    });
+}
@@ -5,6 +5,8 @@ pub enum Error {
     Query(QueryError),
     #[error("{0}")]
     DB(sqlx::Error),
+    #[error("The request did not finish within {} milliseconds and was cancelled.", .0.as_millis())]
+    Timeout(std::time::Duration),
 }
 
 /// Query planning error.
@@ -16,10 +18,80 @@ pub enum QueryError {
     NotSupported(String),
     #[error("{0}")]
     DBError(sqlx::Error),
-    #[error("{0}")]
-    DBConstraintError(sqlx::Error),
+    /// A constraint violation, with the index of the operation of the mutation request which
+    /// raised it, where known.
+    #[error("{error}")]
+    DBConstraintError {
+        error: sqlx::Error,
+        operation: Option<usize>,
+    },
     #[error("Mutation constraint failed.")]
     MutationConstraintFailed,
+    #[error("The row was not updated, as its values do not match those given in 'if_matched'. It may have been changed since it was read.")]
+    MutationVersionMismatch,
+}
+
+impl Error {
+    /// Is this an error that may not happen again if we retry on a different connection,
+    /// such as the connection dropping, the server shutting down, a replica cancelling
+    /// the query because it conflicts with recovery, or a conflict with another transaction?
+    pub fn is_transient(&self) -> bool {
+        match self {
+            Error::Query(_) | Error::Timeout(_) => false,
+            Error::DB(err) => match err {
+                sqlx::Error::Io(_) => true,
+                sqlx::Error::Database(db_err) => db_err
+                    .try_downcast_ref::<sqlx::postgres::PgDatabaseError>()
+                    .map(sqlx::postgres::PgDatabaseError::code)
+                    .is_some_and(|code| {
+                        // https://www.postgresql.org/docs/current/errcodes-appendix.html
+                        code.starts_with("08")
+                            || matches!(code, "40001" | "40P01" | "57P01" | "57P02" | "57P03")
+                    }),
+                _ => false,
+            },
+        }
+    }
+
+    /// Did the database abort the transaction because it conflicted with another one, as in a
+    /// serialization failure or a deadlock? Nothing it did was committed, so it is safe to run
+    /// it again, even if it writes.
+    pub fn is_transaction_conflict(&self) -> bool {
+        match self {
+            Error::DB(sqlx::Error::Database(db_err)) => db_err
+                .try_downcast_ref::<sqlx::postgres::PgDatabaseError>()
+                .map(sqlx::postgres::PgDatabaseError::code)
+                .is_some_and(|code| matches!(code, "40001" | "40P01")),
+            _ => false,
+        }
+    }
+
+    /// Did a mutation fail on its own, such as by violating a constraint, rather than because of
+    /// its connection or its transaction? Only then can it be rolled back to a savepoint, leaving
+    /// the rest of the transaction to be committed.
+    pub fn is_operation_failure(&self) -> bool {
+        match self {
+            Error::Query(_) => true,
+            Error::DB(sqlx::Error::Database(_)) => !self.is_transient(),
+            Error::DB(_) | Error::Timeout(_) => false,
+        }
+    }
+
+    /// Attribute a constraint violation to the operation of a mutation request at this index, so
+    /// that it can be described in terms of that operation.
+    #[must_use]
+    pub fn in_operation(self, index: usize) -> Self {
+        match self {
+            Error::Query(QueryError::DBConstraintError {
+                error,
+                operation: None,
+            }) => Error::Query(QueryError::DBConstraintError {
+                error,
+                operation: Some(index),
+            }),
+            err => err,
+        }
+    }
 }
 
 impl From<sqlx::Error> for Error {
@@ -36,7 +108,10 @@ impl From<sqlx::Error> for Error {
                 if code.starts_with("22") {
                     Error::Query(QueryError::DBError(err))
                 } else if code.starts_with("23") {
-                    Error::Query(QueryError::DBConstraintError(err))
+                    Error::Query(QueryError::DBConstraintError {
+                        error: err,
+                        operation: None,
+                    })
                 } else {
                     Error::DB(err)
                 }
@@ -44,3 +119,44 @@ impl From<sqlx::Error> for Error {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dropped_connections_are_transient() {
+        let err = Error::DB(sqlx::Error::Io(std::io::Error::from(
+            std::io::ErrorKind::ConnectionReset,
+        )));
+        assert!(err.is_transient());
+    }
+
+    #[test]
+    fn timeouts_and_query_errors_are_not_transient() {
+        assert!(!Error::Timeout(std::time::Duration::from_secs(1)).is_transient());
+        assert!(!Error::Query(QueryError::MutationConstraintFailed).is_transient());
+        assert!(!Error::DB(sqlx::Error::RowNotFound).is_transient());
+    }
+
+    #[test]
+    fn constraint_violations_are_attributed_to_their_operation() {
+        let err = Error::Query(QueryError::DBConstraintError {
+            error: sqlx::Error::RowNotFound,
+            operation: None,
+        })
+        .in_operation(2)
+        .in_operation(3);
+        assert!(matches!(
+            err,
+            Error::Query(QueryError::DBConstraintError {
+                operation: Some(2),
+                ..
+            })
+        ));
+        assert!(matches!(
+            Error::Query(QueryError::MutationConstraintFailed).in_operation(2),
+            Error::Query(QueryError::MutationConstraintFailed)
+        ));
+    }
+}
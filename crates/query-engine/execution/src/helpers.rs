@@ -1,7 +1,12 @@
 //! Helper functions for working with the database, shared between query and mutation operations.
 
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+
 use sqlx::pool::PoolConnection;
-use sqlx::postgres::Postgres;
+use sqlx::postgres::{PgConnectOptions, PgConnection, Postgres};
+use sqlx::Connection;
 
 use query_engine_sql::sql;
 
@@ -12,8 +17,8 @@ pub(crate) async fn execute_statement(
     connection: &mut PoolConnection<Postgres>,
     sql::string::Statement(statement): &sql::string::Statement,
 ) -> Result<(), Error> {
-    tracing::info!(
-        statement = statement.sql,
+    tracing::info!(statement = statement.sql);
+    tracing::debug!(
         params = ?&statement.params,
     );
     sqlx::query(&statement.sql)
@@ -22,6 +27,37 @@ pub(crate) async fn execute_statement(
     Ok(())
 }
 
+/// A `numeric` parameter in its textual form, as the value it is bound as, so that the database
+/// receives it as a `numeric` rather than as text.
+pub(crate) fn numeric(numeric: &str) -> Result<sqlx::types::BigDecimal, Error> {
+    numeric
+        .parse()
+        .map_err(|err| Error::DB(sqlx::Error::Encode(Box::new(err))))
+}
+
+/// Acquire a connection from the pool which is known to be alive, by pinging it first.
+///
+/// When a connection drops because the database restarted or failed over, the idle connections
+/// of the pool have usually been dropped as well, and the pool only checks those which have been
+/// idle for a while. We discard every connection which does not answer, so that the pool opens a
+/// fresh one once none of its idle connections are left.
+pub(crate) async fn acquire_live_connection(
+    pool: &sqlx::PgPool,
+) -> Result<PoolConnection<Postgres>, Error> {
+    let mut remaining_idle_connections = pool.num_idle();
+    loop {
+        let mut connection = pool.acquire().await?;
+        match connection.ping().await {
+            Ok(()) => return Ok(connection),
+            Err(err) if remaining_idle_connections == 0 => return Err(err.into()),
+            Err(_) => {
+                connection.close_on_drop();
+                remaining_idle_connections -= 1;
+            }
+        }
+    }
+}
+
 /// Match on the result and execute a rollback statement against the database if we run into an
 /// error.
 ///
@@ -36,3 +72,97 @@ pub(crate) async fn rollback_on_exception<T>(
     }
     result
 }
+
+/// A connection whose running statement is cancelled if we stop waiting for it, because the
+/// deadline passed or the client went away and the request future was dropped.
+///
+/// `pg_cancel_backend` cancels whatever the backend is running at the time, so the connection
+/// is held until the cancellation has been sent and is then closed rather than being handed to
+/// another request. The cancellation is sent over a connection of its own rather than one from
+/// the pool, which may have none to spare.
+pub(crate) struct CancelOnDrop {
+    connect_options: Arc<PgConnectOptions>,
+    backend_pid: Option<i32>,
+    connection: Option<PoolConnection<Postgres>>,
+}
+
+impl CancelOnDrop {
+    pub(crate) fn new(pool: &sqlx::PgPool, connection: PoolConnection<Postgres>) -> Self {
+        CancelOnDrop {
+            connect_options: pool.connect_options(),
+            backend_pid: None,
+            connection: Some(connection),
+        }
+    }
+
+    /// Look up the backend process of the connection, so that what runs on it can be cancelled.
+    /// sqlx does not expose the process ID the database sends when connecting, so we ask for it.
+    /// Until then, dropping the guard only closes the connection.
+    pub(crate) async fn look_up_backend(&mut self) -> Result<(), Error> {
+        let backend_pid = sqlx::query_scalar("SELECT pg_backend_pid()")
+            .fetch_one(self.connection().as_mut())
+            .await?;
+        self.backend_pid = Some(backend_pid);
+        Ok(())
+    }
+
+    pub(crate) fn connection(&mut self) -> &mut PoolConnection<Postgres> {
+        self.connection
+            .as_mut()
+            .expect("the connection is only taken when the guard is consumed")
+    }
+
+    /// Stop guarding the connection, once nothing runs on it any more.
+    pub(crate) fn release(mut self) -> PoolConnection<Postgres> {
+        self.connection
+            .take()
+            .expect("the connection is only taken when the guard is consumed")
+    }
+}
+
+impl Drop for CancelOnDrop {
+    fn drop(&mut self) {
+        let Some(mut connection) = self.connection.take() else {
+            return;
+        };
+        connection.close_on_drop();
+        let Some(backend_pid) = self.backend_pid else {
+            return;
+        };
+        let connect_options = self.connect_options.clone();
+        tokio::spawn(async move {
+            tracing::warn!(backend_pid, "Cancelling an abandoned statement");
+            if let Err(err) = cancel_backend(&connect_options, backend_pid).await {
+                tracing::error!(backend_pid, error = %err, "Failed to cancel a statement");
+            }
+            drop(connection);
+        });
+    }
+}
+
+/// Cancel whatever a backend is running, over a new connection.
+async fn cancel_backend(
+    connect_options: &PgConnectOptions,
+    backend_pid: i32,
+) -> Result<(), sqlx::Error> {
+    let mut connection = PgConnection::connect_with(connect_options).await?;
+    sqlx::query("SELECT pg_cancel_backend($1)")
+        .bind(backend_pid)
+        .execute(&mut connection)
+        .await?;
+    connection.close().await
+}
+
+/// Run an operation against the database, giving up on it when the timeout passes, if there is
+/// one.
+pub(crate) async fn with_timeout<T>(
+    timeout: Option<Duration>,
+    operation: impl Future<Output = Result<T, Error>>,
+) -> Result<T, Error> {
+    match timeout {
+        None => operation.await,
+        Some(timeout) => tokio::time::timeout(timeout, operation)
+            .await
+            .unwrap_or(Err(Error::Timeout(timeout))),
+    }
+}
@@ -6,5 +6,6 @@ pub mod error;
 pub mod metrics;
 pub mod mutation;
 pub mod query;
+pub mod retry;
 
 mod helpers;
@@ -2,7 +2,9 @@
 
 use std::time::Duration;
 
-use prometheus::{Gauge, Histogram, HistogramTimer, IntCounter, IntGauge, Registry};
+use prometheus::{
+    Gauge, Histogram, HistogramTimer, HistogramVec, IntCounter, IntCounterVec, IntGauge, Registry,
+};
 
 /// The collection of all metrics exposed through the `/metrics` endpoint.
 #[derive(Debug, Clone)]
@@ -11,6 +13,9 @@ pub struct Metrics {
     explain_total: IntCounter,
     mutation_total: IntCounter,
     query_total_time: Histogram,
+    query_collection_time: HistogramVec,
+    query_collection_errors: IntCounterVec,
+    explain_total_time: Histogram,
     query_plan_time: Histogram,
     query_execution_time: Histogram,
     mutation_total_time: Histogram,
@@ -25,6 +30,7 @@ pub struct Metrics {
     pool_acquire_timeout: Gauge,
     pool_max_lifetime: Gauge,
     pool_idle_timeout: Gauge,
+    healthy_read_replicas: IntGauge,
     pub error_metrics: ErrorMetrics,
 }
 
@@ -55,6 +61,26 @@ impl Metrics {
             "Total time taken to plan and execute a query, in seconds",
         )?;
 
+        let query_collection_time = add_histogram_vec_metric(
+            metrics_registry,
+            "ndc_postgres_query_collection_time",
+            "Total time taken to plan and execute a successful query, in seconds, by collection. Collections which are not in the configuration are labelled '(unknown)'.",
+            &["collection"],
+        )?;
+
+        let query_collection_errors = add_int_counter_vec_metric(
+            metrics_registry,
+            "ndc_postgres_query_collection_error_total_count",
+            "Total failed queries, by collection.",
+            &["collection"],
+        )?;
+
+        let explain_total_time = add_histogram_metric(
+            metrics_registry,
+            "ndc_postgres_explain_total_time",
+            "Total time taken to plan and explain a query or a mutation, in seconds.",
+        )?;
+
         let query_plan_time = add_histogram_metric(
             metrics_registry,
             "ndc_postgres_query_plan_time",
@@ -139,6 +165,12 @@ impl Metrics {
             "Get the maximum lifetime of individual connections, in seconds.",
         )?;
 
+        let healthy_read_replicas = add_int_gauge_metric(
+            metrics_registry,
+            "ndc_postgres_healthy_read_replicas",
+            "The number of read replicas that queries are currently sent to.",
+        )?;
+
         let error_metrics = ErrorMetrics::initialize(metrics_registry)?;
 
         Ok(Self {
@@ -146,6 +178,9 @@ impl Metrics {
             explain_total,
             mutation_total,
             query_total_time,
+            query_collection_time,
+            query_collection_errors,
+            explain_total_time,
             query_plan_time,
             query_execution_time,
             mutation_total_time,
@@ -160,6 +195,7 @@ impl Metrics {
             pool_acquire_timeout,
             pool_max_lifetime,
             pool_idle_timeout,
+            healthy_read_replicas,
             error_metrics,
         })
     }
@@ -180,6 +216,25 @@ impl Metrics {
         Timer(self.query_total_time.start_timer())
     }
 
+    /// Time a query against a collection, which is recorded under the collection's name.
+    pub fn time_query_for_collection(&self, collection: &str) -> Timer {
+        Timer(
+            self.query_collection_time
+                .with_label_values(&[collection])
+                .start_timer(),
+        )
+    }
+
+    pub fn record_failed_query_for_collection(&self, collection: &str) {
+        self.query_collection_errors
+            .with_label_values(&[collection])
+            .inc();
+    }
+
+    pub fn time_explain_total(&self) -> Timer {
+        Timer(self.explain_total_time.start_timer())
+    }
+
     pub fn time_query_plan(&self) -> Timer {
         Timer(self.query_plan_time.start_timer())
     }
@@ -243,6 +298,11 @@ impl Metrics {
         let pool_active: i64 = pool_size - pool_idle;
         self.pool_active_count.set(pool_active);
     }
+
+    pub fn set_healthy_read_replicas(&self, count: usize) {
+        self.healthy_read_replicas
+            .set(count.try_into().unwrap_or(i64::MAX));
+    }
 }
 
 /// Create a new int counter metric and register it with the provided Prometheus Registry
@@ -256,6 +316,21 @@ fn add_int_counter_metric(
     register_collector(metrics_registry, int_counter)
 }
 
+/// Create a new int counter metric with labels and register it with the provided Prometheus
+/// Registry
+fn add_int_counter_vec_metric(
+    metrics_registry: &mut Registry,
+    metric_name: &str,
+    metric_description: &str,
+    labels: &[&str],
+) -> Result<IntCounterVec, prometheus::Error> {
+    let int_counter_vec = IntCounterVec::new(
+        prometheus::Opts::new(metric_name, metric_description),
+        labels,
+    )?;
+    register_collector(metrics_registry, int_counter_vec)
+}
+
 /// Create a new int gauge metric and register it with the provided Prometheus Registry
 fn add_int_gauge_metric(
     metrics_registry: &mut Registry,
@@ -290,6 +365,21 @@ fn add_histogram_metric(
     register_collector(metrics_registry, histogram)
 }
 
+/// Create a new histogram metric with labels using the default buckets, and register it with
+/// the provided Prometheus Registry.
+fn add_histogram_vec_metric(
+    metrics_registry: &mut prometheus::Registry,
+    metric_name: &str,
+    metric_description: &str,
+    labels: &[&str],
+) -> Result<HistogramVec, prometheus::Error> {
+    let histogram_vec = HistogramVec::new(
+        prometheus::HistogramOpts::new(metric_name, metric_description),
+        labels,
+    )?;
+    register_collector(metrics_registry, histogram_vec)
+}
+
 /// Register a new collector with the registry, and returns it for later use.
 fn register_collector<Collector: prometheus::core::Collector + std::clone::Clone + 'static>(
     metrics_registry: &mut Registry,
@@ -329,12 +419,20 @@ pub struct ErrorMetrics {
     /// the connector could not fulfill a request because it does not support
     /// certain features (which are not described as capabilities).
     unsupported_feature_total: IntCounter,
+    /// the features which requests needed and the connector does not support, by feature.
+    unsupported_feature_uses: IntCounterVec,
     /// the connector had an internal error.
     connector_error_total: IntCounter,
     /// the database emmited an error.
     database_error_total: IntCounter,
     /// we failed to acquire a database connection from the pool
     connection_acquisition_error_total: IntCounter,
+    /// we ran a query again after a transient database error
+    query_retry_total: IntCounter,
+    /// we ran mutations again after their transaction conflicted with another one
+    mutation_retry_total: IntCounter,
+    /// we cancelled a request which ran for longer than the query timeout
+    query_timeout_total: IntCounter,
 }
 
 impl ErrorMetrics {
@@ -360,6 +458,13 @@ impl ErrorMetrics {
             "Total number of invalid requests with unsupported capabilities encountered.",
         )?;
 
+        let unsupported_feature_uses = add_int_counter_vec_metric(
+            metrics_registry,
+            "ndc_postgres_error_unsupported_feature_total_count",
+            "Total number of requests which needed a feature the connector does not support, by feature.",
+            &["feature"],
+        )?;
+
         let connector_error_total = add_int_counter_metric(
             metrics_registry,
             "ndc_postgres_error_connector_error_total_count",
@@ -378,13 +483,35 @@ impl ErrorMetrics {
             "Total number of failures to acquire a database connection.",
         )?;
 
+        let query_retry_total = add_int_counter_metric(
+            metrics_registry,
+            "ndc_postgres_error_query_retry_total_count",
+            "Total number of queries retried after a transient database error.",
+        )?;
+
+        let mutation_retry_total = add_int_counter_metric(
+            metrics_registry,
+            "ndc_postgres_error_mutation_retry_total_count",
+            "Total number of mutations retried after a serialization failure or deadlock.",
+        )?;
+
+        let query_timeout_total = add_int_counter_metric(
+            metrics_registry,
+            "ndc_postgres_error_query_timeout_total_count",
+            "Total number of requests cancelled for running longer than the query timeout.",
+        )?;
+
         Ok(ErrorMetrics {
             invalid_request_total,
             unsupported_capability_total,
             unsupported_feature_total,
+            unsupported_feature_uses,
             connector_error_total,
             database_error_total,
             connection_acquisition_error_total,
+            query_retry_total,
+            mutation_retry_total,
+            query_timeout_total,
         })
     }
 
@@ -397,6 +524,13 @@ impl ErrorMetrics {
     pub fn record_unsupported_feature(&self) {
         self.unsupported_feature_total.inc();
     }
+    /// Record a request which needed a feature the connector does not support, under the
+    /// feature's name.
+    pub fn record_unsupported_feature_use(&self, feature: &str) {
+        self.unsupported_feature_uses
+            .with_label_values(&[feature])
+            .inc();
+    }
     pub fn record_connector_error(&self) {
         self.connector_error_total.inc();
     }
@@ -406,4 +540,13 @@ impl ErrorMetrics {
     pub fn record_connection_acquisition_error(&self) {
         self.connection_acquisition_error_total.inc();
     }
+    pub fn record_query_retry(&self) {
+        self.query_retry_total.inc();
+    }
+    pub fn record_mutation_retry(&self) {
+        self.mutation_retry_total.inc();
+    }
+    pub fn record_query_timeout(&self) {
+        self.query_timeout_total.inc();
+    }
 }
@@ -1,5 +1,7 @@
 //! Execute a mutation execution plan against the database.
 
+use std::time::{Duration, Instant};
+
 use bytes::{BufMut, Bytes, BytesMut};
 use sqlx::pool::PoolConnection;
 use sqlx::postgres::Postgres;
@@ -10,16 +12,64 @@ use query_engine_sql::sql;
 
 use crate::database_info::DatabaseInfo;
 use crate::error::{Error, QueryError};
-use crate::helpers::{execute_statement, rollback_on_exception};
+use crate::helpers::{
+    execute_statement, numeric, rollback_on_exception, with_timeout, CancelOnDrop,
+};
 use crate::metrics;
+use crate::retry::{retry, RetryPolicy};
+
+/// The response to mutations, and how long each of them took to run.
+#[derive(Debug)]
+pub struct MutationsResponse {
+    /// The JSON of the `MutationResponse`.
+    pub response: Bytes,
+    /// How long each mutation took to run, in the order of the plan.
+    pub durations: Vec<Duration>,
+}
 
 /// Execute mutations against postgres.
+///
+/// If their transaction is aborted because it conflicts with another one, nothing was written,
+/// so we run them again, following the retry policy. We do not retry on other transient errors:
+/// if the connection drops, the transaction may have been committed.
 pub async fn execute(
     pool: &sqlx::PgPool,
     database_info: &DatabaseInfo,
     metrics: &metrics::Metrics,
+    timeout: Option<Duration>,
+    retry_policy: &RetryPolicy,
     plan: sql::execution_plan::ExecutionPlan<sql::execution_plan::Mutations>,
-) -> Result<Bytes, Error> {
+) -> Result<MutationsResponse, Error> {
+    let plan = &plan;
+    retry(
+        retry_policy,
+        Error::is_transaction_conflict,
+        |err, attempt, backoff| {
+            tracing::warn!(
+                error = %err,
+                attempt,
+                backoff_ms = backoff.as_millis(),
+                "Retrying mutations after a conflict with another transaction"
+            );
+            metrics.error_metrics.record_mutation_retry();
+        },
+        |_attempt| execute_attempt(pool, database_info, metrics, timeout, plan),
+    )
+    .await
+}
+
+/// Run mutations once on a connection from the pool, rolling back on errors.
+///
+/// The mutations are cancelled on the database when the request is abandoned and this future is
+/// dropped, or when they run for longer than the timeout, if there is one. Their transaction is
+/// then rolled back as the connection closes.
+async fn execute_attempt(
+    pool: &sqlx::PgPool,
+    database_info: &DatabaseInfo,
+    metrics: &metrics::Metrics,
+    timeout: Option<Duration>,
+    plan: &sql::execution_plan::ExecutionPlan<sql::execution_plan::Mutations>,
+) -> Result<MutationsResponse, Error> {
     let acquisition_timer = metrics.time_connection_acquisition_wait();
     let connection_result = pool
         .acquire()
@@ -28,18 +78,25 @@ pub async fn execute(
             internal.visibility = "user",
         ))
         .await;
-    let mut connection = acquisition_timer
+    let connection = acquisition_timer
         .complete_with(connection_result)
         .inspect_err(|_err| {
             metrics.error_metrics.record_connection_acquisition_error();
         })?;
 
     let query_timer = metrics.time_query_execution();
-    let rows_result = rollback_on_exception(
-        execute_mutations(&mut connection, database_info, plan).await,
-        connection,
-    )
+    let mut guarded = CancelOnDrop::new(pool, connection);
+    let rows_result = with_timeout(timeout, async {
+        guarded.look_up_backend().await?;
+        execute_mutations(guarded.connection(), database_info, plan).await
+    })
     .await;
+    let rows_result = if matches!(rows_result, Err(Error::Timeout(_))) {
+        // Dropping the guard cancels the mutations and closes the connection.
+        rows_result
+    } else {
+        rollback_on_exception(rows_result, guarded.release()).await
+    };
     query_timer.complete_with(rows_result)
 }
 
@@ -50,10 +107,10 @@ pub async fn execute(
 async fn execute_mutations(
     connection: &mut PoolConnection<Postgres>,
     database_info: &DatabaseInfo,
-    plan: sql::execution_plan::ExecutionPlan<sql::execution_plan::Mutations>,
-) -> Result<Bytes, Error> {
-    for statement in plan.pre {
-        execute_statement(connection, &statement).await?;
+    plan: &sql::execution_plan::ExecutionPlan<sql::execution_plan::Mutations>,
+) -> Result<MutationsResponse, Error> {
+    for statement in &plan.pre {
+        execute_statement(connection, statement).await?;
     }
 
     // this buffer represents the JSON response
@@ -63,49 +120,92 @@ async fn execute_mutations(
     buffer.put(&[b'['][..]); // open the responses array
 
     // iterate over mutations
-    let mut i = plan.query.0.iter();
-    if let Some(mutation) = i.next() {
-        let mutation_sql = mutation.query_sql();
-
-        tracing::info!(
-            generated_sql = mutation_sql.sql,
-            params = ?&mutation_sql.params,
-        );
-
-        execute_query(connection, database_info, &mutation_sql, &mut buffer).await?;
-        for mutation in i {
+    let mut durations = Vec::with_capacity(plan.query.0.len());
+    for (index, mutation) in plan.query.0.iter().enumerate() {
+        if index > 0 {
             buffer.put(&[b','][..]); // each result, except the first, is prefixed by a ','
-
-            let mutation_sql = mutation.query_sql();
-
-            tracing::info!(
-                generated_sql = mutation_sql.sql,
-                params = ?&mutation_sql.params,
-            );
-
-            execute_query(connection, database_info, &mutation_sql, &mut buffer).await?;
         }
+        let started = Instant::now();
+        execute_mutation(connection, database_info, mutation, &mut buffer)
+            .await
+            .map_err(|err| err.in_operation(index))?;
+        durations.push(started.elapsed());
     }
 
     buffer.put(&[b']'][..]); // we end by closing the array
     buffer.put(&[b'}'][..]); // and then the object
 
-    for statement in plan.post {
-        execute_statement(connection, &statement).await?;
+    for statement in &plan.post {
+        execute_statement(connection, statement).await?;
     }
 
-    Ok(buffer.freeze())
+    Ok(MutationsResponse {
+        response: buffer.freeze(),
+        durations,
+    })
+}
+
+/// Execute a mutation, and append its result to the given buffer.
+///
+/// A mutation with a savepoint of its own which fails is rolled back to it, and its result is
+/// null, so that the other mutations can still be committed.
+async fn execute_mutation(
+    connection: &mut PoolConnection<Postgres>,
+    database_info: &DatabaseInfo,
+    mutation: &sql::execution_plan::Mutation,
+    buffer: &mut BytesMut,
+) -> Result<(), Error> {
+    let mutation_sql = mutation.query_sql();
+
+    tracing::info!(generated_sql = mutation_sql.sql);
+    tracing::debug!(
+        params = ?&mutation_sql.params,
+    );
+
+    let Some(savepoint) = &mutation.savepoint else {
+        return execute_query(connection, database_info, mutation, &mutation_sql, buffer).await;
+    };
+
+    execute_statement(connection, &savepoint.begin).await?;
+    // the result is only kept if the mutation succeeds.
+    let mut result = BytesMut::new();
+    match execute_query(
+        connection,
+        database_info,
+        mutation,
+        &mutation_sql,
+        &mut result,
+    )
+    .await
+    {
+        Ok(()) => {
+            execute_statement(connection, &savepoint.release).await?;
+            buffer.put(result);
+        }
+        Err(err) if err.is_operation_failure() => {
+            tracing::warn!(
+                error = %err,
+                root_field = %mutation.root_field,
+                "Rolling back a failed mutation operation"
+            );
+            execute_statement(connection, &savepoint.rollback).await?;
+            buffer.put(&b"{\"type\":\"procedure\",\"result\":null}"[..]);
+        }
+        Err(err) => return Err(err),
+    }
+    Ok(())
 }
 
 /// Execute the query, and append the result to the given buffer.
 async fn execute_query(
     connection: &mut PoolConnection<Postgres>,
     database_info: &DatabaseInfo,
+    mutation: &sql::execution_plan::Mutation,
     query: &sql::string::SQL,
     buffer: &mut (impl BufMut + Send),
 ) -> Result<(), Error> {
     // build query
-    let sqlx_query = async { build_query_with_params(query) }
+    let sqlx_query = async { build_query_with_params(query, mutation.annotations.is_empty()) }
         .instrument(info_span!(
             "Build query with params",
             internal.visibility = "user",
@@ -113,7 +213,7 @@ async fn execute_query(
         .await?;
 
     // run and fetch from the database
-    let check_constraint = sqlx_query
+    let (check_constraint, version_mismatch) = sqlx_query
         .try_map(|row: sqlx::postgres::PgRow| {
             let mut results_bytes = row.try_get_raw(0)?.as_bytes().unwrap();
             // If the result is JSONB, PostgreSQL adds a 0x01 at the start of
@@ -125,12 +225,14 @@ async fn execute_query(
             buffer.put(results_bytes);
 
             // if our mutation also includes a check constraint, return it.
-            if row.len() == 2 {
-                let check_constraint = row.try_get::<bool, usize>(1)?;
-                Ok(Some(check_constraint))
+            let check_constraint = if row.len() >= 2 {
+                Some(row.try_get::<bool, usize>(1)?)
             } else {
-                Ok(None)
-            }
+                None
+            };
+            // and whether it left a row alone because it did not have the expected values.
+            let version_mismatch = row.len() >= 3 && row.try_get::<bool, usize>(2)?;
+            Ok((check_constraint, version_mismatch))
         })
         .fetch_one(connection.as_mut())
         .instrument(info_span!(
@@ -149,22 +251,35 @@ async fn execute_query(
     match check_constraint {
         // If we have a check constraint and it failed, throw an error.
         Some(false) => Err(Error::Query(QueryError::MutationConstraintFailed)),
+        // If the row to update did not have the expected values, throw an error too, so that
+        // it is not mistaken for a missing row.
+        None | Some(true) if version_mismatch => {
+            Err(Error::Query(QueryError::MutationVersionMismatch))
+        }
         // If we don't or it succeeded, all is well.
         None | Some(true) => Ok(()),
     }
 }
 
 /// Create a SQLx query based on our SQL query and bind our parameters to it.
+///
+/// Annotated mutations differ from one request to the next, so they are not kept as prepared
+/// statements.
 fn build_query_with_params(
     query: &sql::string::SQL,
+    persistent: bool,
 ) -> Result<sqlx::query::Query<'_, sqlx::Postgres, sqlx::postgres::PgArguments>, Error> {
-    let initial_query = sqlx::query(&query.sql);
+    let initial_query = sqlx::query(&query.sql).persistent(persistent);
     query
         .params
         .iter()
         .try_fold(initial_query, |sqlx_query, param| match param {
             sql::string::Param::String(s) => Ok(sqlx_query.bind(s)),
             sql::string::Param::Value(v) => Ok(sqlx_query.bind(v)),
+            sql::string::Param::Typed(sql::string::TypedValue::Int8(i)) => Ok(sqlx_query.bind(i)),
+            sql::string::Param::Typed(sql::string::TypedValue::Numeric(n)) => {
+                Ok(sqlx_query.bind(numeric(n)?))
+            }
             sql::string::Param::Variable(_) => Err(Error::Query(QueryError::NotSupported(
                 "Variables in mutations".to_string(),
             ))),
@@ -194,18 +309,21 @@ pub async fn explain(
                     internal.visibility = "user",
                 ))
                 .await;
-            let mut connection = acquisition_timer
+            let connection = acquisition_timer
                 .complete_with(connection_result)
                 .inspect_err(|_err| {
                     metrics.error_metrics.record_connection_acquisition_error();
                 })?;
+            // Explaining a large mutation is not free either, so it stops with the request.
+            let mut guarded = CancelOnDrop::new(pool, connection);
+            guarded.look_up_backend().await?;
 
-            tracing::info!(
-                generated_sql = query_sql.sql,
+            tracing::info!(generated_sql = query_sql.sql);
+            tracing::debug!(
                 params = ?&query_sql.params,
             );
 
-            let sqlx_query = async { build_query_with_params(&query_sql) }
+            let sqlx_query = async { build_query_with_params(&query_sql, true) }
                 .instrument(info_span!(
                     "Build mutation with params",
                     internal.visibility = "user",
@@ -215,7 +333,7 @@ pub async fn explain(
             let rows: Vec<sqlx::postgres::PgRow> = {
                 // run and fetch from the database
                 sqlx_query
-                    .fetch_all(connection.as_mut())
+                    .fetch_all(guarded.connection().as_mut())
                     .instrument(info_span!(
                         "Database request",
                         internal.visibility = "user",
@@ -229,6 +347,7 @@ pub async fn explain(
                     ))
                     .await?
             };
+            drop(guarded.release());
 
             let mut results: Vec<String> = vec![];
             for row in rows {
@@ -1,6 +1,7 @@
 //! Execute an execution plan against the database.
 
 use std::collections::BTreeMap;
+use std::time::{Duration, Instant};
 
 use bytes::{BufMut, Bytes, BytesMut};
 use sqlx::pool::PoolConnection;
@@ -13,38 +14,205 @@ use query_engine_sql::sql;
 
 use crate::database_info::DatabaseInfo;
 use crate::error::{Error, QueryError};
-use crate::helpers::{execute_statement, rollback_on_exception};
+use crate::helpers::{
+    acquire_live_connection, execute_statement, numeric, rollback_on_exception, with_timeout,
+    CancelOnDrop,
+};
 use crate::metrics;
+use crate::retry::{retry, RetryPolicy};
+
+/// Log the SQL of queries which take a long time to run against the database.
+#[derive(Debug, Clone, Copy)]
+pub struct SlowQueryLog {
+    /// Queries which take longer than this are logged.
+    pub threshold: Duration,
+    /// Whether to log the values of the parameters, which may be sensitive. They are otherwise
+    /// only logged at the debug level, along with every other statement's.
+    pub include_parameters: bool,
+}
+
+impl SlowQueryLog {
+    fn record(
+        &self,
+        query: &sql::execution_plan::Query,
+        query_sql: &sql::string::SQL,
+        elapsed: Duration,
+    ) {
+        if elapsed < self.threshold {
+            return;
+        }
+        let elapsed_ms = elapsed.as_millis();
+        if self.include_parameters {
+            tracing::warn!(
+                collection = query.root_field.as_str(),
+                elapsed_ms,
+                generated_sql = query_sql.sql,
+                params = ?query_sql.params,
+                variables = ?query.variables,
+                "Slow query"
+            );
+        } else {
+            tracing::warn!(
+                collection = query.root_field.as_str(),
+                elapsed_ms,
+                generated_sql = query_sql.sql,
+                params = query_sql.params.len(),
+                variable_sets = query.variables.as_ref().map(Vec::len),
+                "Slow query"
+            );
+        }
+    }
+}
 
 /// Execute a query against postgres.
+///
+/// Queries only read, so if the connection drops, a replica cancels the query because
+/// of a recovery conflict, or the query conflicts with another transaction, we run it again on a
+/// different connection from the pool which answers a ping, following the retry policy.
+///
+/// Each attempt is cancelled on the database when the request is abandoned and this future is
+/// dropped, or when it runs for longer than the timeout, if there is one.
 pub async fn execute(
     pool: &sqlx::PgPool,
     database_info: &DatabaseInfo,
     metrics: &metrics::Metrics,
+    slow_query_log: Option<&SlowQueryLog>,
+    timeout: Option<Duration>,
+    retry_policy: &RetryPolicy,
     plan: sql::execution_plan::ExecutionPlan<sql::execution_plan::Query>,
+) -> Result<Bytes, Error> {
+    // A query known to return no rows is answered without asking the database.
+    if let Some(result) = &plan.query.empty_result {
+        return Ok(Bytes::from(result.to_string()));
+    }
+
+    let plan = &plan;
+    retry(
+        retry_policy,
+        Error::is_transient,
+        |err, attempt, backoff| {
+            tracing::warn!(
+                error = %err,
+                attempt,
+                backoff_ms = backoff.as_millis(),
+                "Retrying query after a transient database error"
+            );
+            metrics.error_metrics.record_query_retry();
+        },
+        |attempt| {
+            execute_attempt(
+                pool,
+                database_info,
+                metrics,
+                slow_query_log,
+                timeout,
+                attempt,
+                plan,
+            )
+        },
+    )
+    .await
+}
+
+/// Run a query once on a connection from the pool. Retries are given a connection which answers a
+/// ping, as the connections of the pool may have failed along with the one we used.
+async fn execute_attempt(
+    pool: &sqlx::PgPool,
+    database_info: &DatabaseInfo,
+    metrics: &metrics::Metrics,
+    slow_query_log: Option<&SlowQueryLog>,
+    timeout: Option<Duration>,
+    attempt: u32,
+    plan: &sql::execution_plan::ExecutionPlan<sql::execution_plan::Query>,
 ) -> Result<Bytes, Error> {
     let acquisition_timer = metrics.time_connection_acquisition_wait();
-    let connection_result = pool
-        .acquire()
-        .instrument(info_span!(
-            "Acquire connection",
-            internal.visibility = "user",
-        ))
-        .await;
-    let mut connection = acquisition_timer
+    let connection_result = async {
+        if attempt == 1 {
+            pool.acquire().await.map_err(Error::from)
+        } else {
+            acquire_live_connection(pool).await
+        }
+    }
+    .instrument(info_span!(
+        "Acquire connection",
+        internal.visibility = "user",
+    ))
+    .await;
+    let connection = acquisition_timer
         .complete_with(connection_result)
         .inspect_err(|_err| {
             metrics.error_metrics.record_connection_acquisition_error();
         })?;
 
     let query_timer = metrics.time_query_execution();
-    let rows_result = rollback_on_exception(
-        execute_query(&mut connection, database_info, plan).await,
-        connection,
-    )
+    let mut guarded = CancelOnDrop::new(pool, connection);
+    let rows_result = with_timeout(timeout, async {
+        guarded.look_up_backend().await?;
+        execute_query(guarded.connection(), database_info, slow_query_log, plan).await
+    })
     .await;
+    if matches!(rows_result, Err(Error::Timeout(_))) {
+        // Dropping the guard cancels the query and closes the connection.
+        return query_timer.complete_with(rows_result);
+    }
+    let mut connection = guarded.release();
+    let rows_result = query_timer.complete_with(rows_result);
 
-    query_timer.complete_with(rows_result)
+    match rows_result {
+        Err(err) if err.is_transient() => {
+            // The connection may be broken, so do not hand it back to the pool.
+            connection.close_on_drop();
+            Err(err)
+        }
+        rows_result => rollback_on_exception(rows_result, connection).await,
+    }
+}
+
+/// Check that the database accepts a query without running it, by planning it with `EXPLAIN`
+/// in a transaction which is then rolled back. This catches queries which refer to tables,
+/// columns or functions which do not exist, or whose types do not match.
+pub async fn verify(
+    pool: &sqlx::PgPool,
+    plan: &sql::execution_plan::ExecutionPlan<sql::execution_plan::Query>,
+) -> Result<(), Error> {
+    let mut connection = pool.acquire().await?;
+    let query = &plan.query;
+
+    // Temporary tables cannot be created in a read-only transaction, so the variables are copied
+    // before it begins.
+    if query.variables_source == sql::execution_plan::VariablesSource::TemporaryTable {
+        copy_variables_to_table(&mut connection, query.variables.as_deref()).await?;
+    }
+
+    let result = async {
+        // The statements which set up the query may begin a transaction of their own.
+        if plan.pre.is_empty() {
+            for statement in sql::helpers::begin(
+                sql::ast::transaction::IsolationLevel::default(),
+                sql::ast::transaction::TransactionMode::ReadOnly,
+            ) {
+                execute_statement(&mut connection, &statement).await?;
+            }
+        }
+        for statement in &plan.pre {
+            execute_statement(&mut connection, statement).await?;
+        }
+
+        let query_sql = query.explain_query_sql();
+        build_query_with_params(&query_sql, query.variables.as_deref(), true)?
+            .fetch_all(connection.as_mut())
+            .await?;
+        Ok(())
+    }
+    .instrument(info_span!("Verify query", internal.visibility = "user",))
+    .await;
+
+    // Nothing the query set up is kept, whether the database accepted it or not.
+    execute_statement(&mut connection, &sql::helpers::transaction_rollback()).await?;
+    if query.variables_source == sql::execution_plan::VariablesSource::TemporaryTable {
+        drop_variables_table(&mut connection).await?;
+    }
+    result
 }
 
 /// Convert a query to an EXPLAIN query and execute it against postgres.
@@ -65,8 +233,8 @@ pub async fn explain(
         // In this case, we do not run an EXPLAIN query against postgres -
         // we just return the generated SQL.
         if query.variables.is_some() && query.variables.as_ref().unwrap().is_empty() {
-            tracing::info!(
-                generated_sql = query_sql.sql,
+            tracing::info!(generated_sql = query_sql.sql);
+            tracing::debug!(
                 params = ?&query_sql.params,
                 variables = ?&query.variables,
             );
@@ -82,33 +250,50 @@ pub async fn explain(
                     internal.visibility = "user",
                 ))
                 .await;
-            let mut connection = acquisition_timer
+            let connection = acquisition_timer
                 .complete_with(connection_result)
                 .inspect_err(|_err| {
                     metrics.error_metrics.record_connection_acquisition_error();
                 })?;
+            // Planning can take a while too, so it is cancelled if the request is abandoned.
+            let mut guarded = CancelOnDrop::new(pool, connection);
+            guarded.look_up_backend().await?;
+            let connection = guarded.connection();
 
-            for statement in plan.pre {
-                execute_statement(&mut connection, &statement).await?;
+            if query.variables_source == sql::execution_plan::VariablesSource::TemporaryTable {
+                copy_variables_to_table(connection, query.variables.as_deref()).await?;
             }
 
-            tracing::info!(
-                generated_sql = query_sql.sql,
-                params = ?&query_sql.params,
-                variables = ?&query.variables,
-            );
+            let result = async {
+                // The statements which set up the query may begin a transaction of their own.
+                if plan.pre.is_empty() {
+                    for statement in sql::helpers::begin(
+                        sql::ast::transaction::IsolationLevel::default(),
+                        sql::ast::transaction::TransactionMode::ReadOnly,
+                    ) {
+                        execute_statement(connection, &statement).await?;
+                    }
+                }
+                for statement in &plan.pre {
+                    execute_statement(connection, statement).await?;
+                }
 
-            let sqlx_query =
-                async { build_query_with_params(&query_sql, query.variables.as_deref()) }
-                    .instrument(info_span!(
-                        "Build query with params",
-                        internal.visibility = "user",
-                    ))
-                    .await?;
+                tracing::info!(generated_sql = query_sql.sql);
+                tracing::debug!(
+                    params = ?&query_sql.params,
+                    variables = ?&query.variables,
+                );
+
+                let sqlx_query =
+                    async { build_query_with_params(&query_sql, query.variables.as_deref(), true) }
+                        .instrument(info_span!(
+                            "Build query with params",
+                            internal.visibility = "user",
+                        ))
+                        .await?;
 
-            let rows: Vec<sqlx::postgres::PgRow> = {
                 // run and fetch from the database
-                sqlx_query
+                let rows: Vec<sqlx::postgres::PgRow> = sqlx_query
                     .fetch_all(connection.as_mut())
                     .instrument(info_span!(
                         "Database request",
@@ -121,23 +306,29 @@ pub async fn explain(
                         server.address = database_info.server_host,
                         server.port = database_info.server_port,
                     ))
-                    .await?
-            };
-
-            let mut results: Vec<String> = vec![];
-            for row in rows {
-                match row.get(0) {
-                    None => {}
-                    Some(col) => {
-                        results.push(col);
+                    .await?;
+
+                let mut results: Vec<String> = vec![];
+                for row in rows {
+                    match row.get(0) {
+                        None => {}
+                        Some(col) => {
+                            results.push(col);
+                        }
                     }
                 }
+                Ok::<String, Error>(results.join("\n"))
             }
+            .await;
 
-            for statement in plan.post {
-                execute_statement(&mut connection, &statement).await?;
+            // Explaining a query does not run it, so nothing it set up is kept, whether the
+            // database accepted it or not.
+            execute_statement(connection, &sql::helpers::transaction_rollback()).await?;
+            if query.variables_source == sql::execution_plan::VariablesSource::TemporaryTable {
+                drop_variables_table(connection).await?;
             }
-            Ok::<String, Error>(results.join("\n"))
+            drop(guarded.release());
+            result
         }
     }?;
 
@@ -154,17 +345,23 @@ pub async fn explain(
 async fn execute_query(
     connection: &mut PoolConnection<Postgres>,
     database_info: &DatabaseInfo,
-    plan: sql::execution_plan::ExecutionPlan<sql::execution_plan::Query>,
+    slow_query_log: Option<&SlowQueryLog>,
+    plan: &sql::execution_plan::ExecutionPlan<sql::execution_plan::Query>,
 ) -> Result<Bytes, Error> {
-    for statement in plan.pre {
-        execute_statement(connection, &statement).await?;
+    let query = &plan.query;
+
+    if query.variables_source == sql::execution_plan::VariablesSource::TemporaryTable {
+        copy_variables_to_table(connection, query.variables.as_deref()).await?;
+    }
+
+    for statement in &plan.pre {
+        execute_statement(connection, statement).await?;
     }
 
-    let query = plan.query;
     let query_sql = query.query_sql();
 
-    tracing::info!(
-        generated_sql = query_sql.sql,
+    tracing::info!(generated_sql = query_sql.sql);
+    tracing::debug!(
         params = ?query_sql.params,
         variables = ?query.variables,
     );
@@ -172,14 +369,21 @@ async fn execute_query(
     let mut buffer = BytesMut::new();
 
     // build query
-    let sqlx_query = async { build_query_with_params(&query_sql, query.variables.as_deref()) }
-        .instrument(info_span!(
-            "Build query with params",
-            internal.visibility = "user",
-        ))
-        .await?;
+    let sqlx_query = async {
+        build_query_with_params(
+            &query_sql,
+            query.variables.as_deref(),
+            query.annotations.is_empty(),
+        )
+    }
+    .instrument(info_span!(
+        "Build query with params",
+        internal.visibility = "user",
+    ))
+    .await?;
 
     // run and fetch from the database
+    let start = Instant::now();
     sqlx_query
         .try_map(|row: sqlx::postgres::PgRow| {
             let mut bytes = row.try_get_raw(0)?.as_bytes().unwrap();
@@ -206,25 +410,108 @@ async fn execute_query(
         ))
         .await?;
 
-    for statement in plan.post {
-        execute_statement(connection, &statement).await?;
+    if let Some(slow_query_log) = slow_query_log {
+        slow_query_log.record(query, &query_sql, start.elapsed());
+    }
+
+    for statement in &plan.post {
+        execute_statement(connection, statement).await?;
+    }
+
+    if query.variables_source == sql::execution_plan::VariablesSource::TemporaryTable {
+        drop_variables_table(connection).await?;
     }
 
     Ok(buffer.freeze())
 }
 
+/// Copy the variable sets into the session-temporary table the query reads them from.
+///
+/// A table left behind by an earlier query which failed on the same connection is replaced.
+async fn copy_variables_to_table(
+    connection: &mut PoolConnection<Postgres>,
+    variables: Option<&[BTreeMap<models::VariableName, serde_json::Value>]>,
+) -> Result<(), Error> {
+    let variables = variables.unwrap_or_default();
+    let table = sql::helpers::VARIABLES_TABLE_NAME;
+    let order_field = sql::helpers::VARIABLE_ORDER_FIELD;
+    let variables_field = sql::helpers::VARIABLES_FIELD;
+
+    async {
+        sqlx::query(&format!(r#"DROP TABLE IF EXISTS pg_temp."{table}""#))
+            .execute(connection.as_mut())
+            .await?;
+        sqlx::query(&format!(
+            r#"CREATE TEMPORARY TABLE "{table}" ("{order_field}" int4, "{variables_field}" jsonb)"#
+        ))
+        .execute(connection.as_mut())
+        .await?;
+
+        let mut copy = connection
+            .as_mut()
+            .copy_in_raw(&format!(
+                r#"COPY pg_temp."{table}" ("{order_field}", "{variables_field}") FROM STDIN"#
+            ))
+            .await?;
+        copy.send(copy_rows(variables)).await?;
+        copy.finish().await?;
+        Ok::<(), Error>(())
+    }
+    .instrument(info_span!(
+        "Copy variables to a temporary table",
+        internal.visibility = "user",
+        variable_sets = variables.len(),
+    ))
+    .await
+}
+
+/// The variable sets as rows in the text format of COPY: tab-separated columns, with backslashes
+/// escaped. Compact JSON never contains tabs or line breaks outside of escape sequences.
+fn copy_rows(variables: &[BTreeMap<models::VariableName, serde_json::Value>]) -> Bytes {
+    let mut rows = BytesMut::new();
+    for (index, variable_set) in variables.iter().enumerate() {
+        let value = serde_json::Value::Object(
+            variable_set
+                .iter()
+                .map(|(name, value)| (name.to_string(), value.clone()))
+                .collect(),
+        );
+        rows.put(format!("{index}\t").as_bytes());
+        rows.put(value.to_string().replace('\\', "\\\\").as_bytes());
+        rows.put_u8(b'\n');
+    }
+    rows.freeze()
+}
+
+/// Drop the temporary table of variables, so that it does not linger on the pooled connection.
+async fn drop_variables_table(connection: &mut PoolConnection<Postgres>) -> Result<(), Error> {
+    let table = sql::helpers::VARIABLES_TABLE_NAME;
+    sqlx::query(&format!(r#"DROP TABLE IF EXISTS pg_temp."{table}""#))
+        .execute(connection.as_mut())
+        .await?;
+    Ok(())
+}
+
 /// Create a SQLx query based on our SQL query and bind our parameters and variables to it.
+///
+/// Annotated queries differ from one request to the next, so they are not kept as prepared
+/// statements, which would only crowd the others out of the statement cache.
 fn build_query_with_params<'a>(
     query: &'a sql::string::SQL,
     variables: Option<&'a [BTreeMap<models::VariableName, serde_json::Value>]>,
+    persistent: bool,
 ) -> Result<sqlx::query::Query<'a, sqlx::Postgres, sqlx::postgres::PgArguments>, Error> {
-    let initial_query = sqlx::query(&query.sql);
+    let initial_query = sqlx::query(&query.sql).persistent(persistent);
     query
         .params
         .iter()
         .try_fold(initial_query, |sqlx_query, param| match param {
             sql::string::Param::String(s) => Ok(sqlx_query.bind(s)),
             sql::string::Param::Value(v) => Ok(sqlx_query.bind(v)),
+            sql::string::Param::Typed(sql::string::TypedValue::Int8(i)) => Ok(sqlx_query.bind(i)),
+            sql::string::Param::Typed(sql::string::TypedValue::Numeric(n)) => {
+                Ok(sqlx_query.bind(numeric(n)?))
+            }
             sql::string::Param::Variable(var)
                 if var == sql::helpers::VARIABLES_OBJECT_PLACEHOLDER =>
             {
@@ -271,3 +558,119 @@ fn variables_to_json(
             .collect::<Result<Vec<serde_json::Value>, Error>>()?,
     ))
 }
+
+/// Count the rows of the row sets of a query response. The response is read through once, and
+/// none of its values are kept. A response which cannot be read counts as no rows.
+pub fn count_rows(response: &[u8]) -> u64 {
+    let mut deserializer = serde_json::Deserializer::from_slice(response);
+    serde::Deserializer::deserialize_seq(&mut deserializer, RowSetsVisitor).unwrap_or(0)
+}
+
+/// Sums the rows of an array of row sets.
+struct RowSetsVisitor;
+
+impl<'de> serde::de::Visitor<'de> for RowSetsVisitor {
+    type Value = u64;
+
+    fn expecting(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        formatter.write_str("an array of row sets")
+    }
+
+    fn visit_seq<A: serde::de::SeqAccess<'de>>(self, mut row_sets: A) -> Result<u64, A::Error> {
+        let mut rows = 0;
+        while let Some(RowSetRows(row_set_rows)) = row_sets.next_element()? {
+            rows += row_set_rows;
+        }
+        Ok(rows)
+    }
+}
+
+/// The number of rows of a row set, which has none if it only has aggregates.
+struct RowSetRows(u64);
+
+impl<'de> serde::Deserialize<'de> for RowSetRows {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_map(RowSetRowsVisitor)
+    }
+}
+
+struct RowSetRowsVisitor;
+
+impl<'de> serde::de::Visitor<'de> for RowSetRowsVisitor {
+    type Value = RowSetRows;
+
+    fn expecting(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        formatter.write_str("a row set")
+    }
+
+    fn visit_map<A: serde::de::MapAccess<'de>>(
+        self,
+        mut row_set: A,
+    ) -> Result<RowSetRows, A::Error> {
+        let mut rows = 0;
+        while let Some(key) = row_set.next_key::<String>()? {
+            if key == "rows" {
+                rows = row_set
+                    .next_value::<Option<Rows>>()?
+                    .map_or(0, |Rows(rows)| rows);
+            } else {
+                row_set.next_value::<serde::de::IgnoredAny>()?;
+            }
+        }
+        Ok(RowSetRows(rows))
+    }
+}
+
+/// The number of rows in an array of them, which are skipped over.
+struct Rows(u64);
+
+impl<'de> serde::Deserialize<'de> for Rows {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_seq(RowsVisitor)
+    }
+}
+
+struct RowsVisitor;
+
+impl<'de> serde::de::Visitor<'de> for RowsVisitor {
+    type Value = Rows;
+
+    fn expecting(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        formatter.write_str("an array of rows")
+    }
+
+    fn visit_seq<A: serde::de::SeqAccess<'de>>(self, mut rows: A) -> Result<Rows, A::Error> {
+        let mut count = 0;
+        while rows.next_element::<serde::de::IgnoredAny>()?.is_some() {
+            count += 1;
+        }
+        Ok(Rows(count))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::{copy_rows, count_rows};
+
+    #[test]
+    fn variable_sets_are_copied_as_escaped_text_rows() {
+        let variables = vec![
+            BTreeMap::from([("id".into(), serde_json::json!(1))]),
+            BTreeMap::from([("name".into(), serde_json::json!("tab\there \\ there"))]),
+        ];
+        assert_eq!(
+            copy_rows(&variables),
+            "0\t{\"id\":1}\n1\t{\"name\":\"tab\\\\there \\\\\\\\ there\"}\n"
+        );
+    }
+
+    #[test]
+    fn counts_the_rows_of_a_response() {
+        let response =
+            br#"[{"rows":[{"id":1},{"id":[2,3]}]},{"rows":null},{"aggregates":{"count":3}}]"#;
+        assert_eq!(count_rows(response), 2);
+        assert_eq!(count_rows(b"not json"), 0);
+    }
+}
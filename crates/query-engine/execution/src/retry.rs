@@ -0,0 +1,172 @@
+//! Retrying operations which fail for transient reasons.
+
+use std::future::Future;
+use std::hash::{BuildHasher, RandomState};
+use std::time::{Duration, Instant};
+
+/// How to retry operations which fail for reasons which may go away on their own.
+#[derive(Debug, Clone, Copy)]
+pub struct RetryPolicy {
+    /// How many times to attempt an operation, including the first attempt.
+    pub max_attempts: u32,
+    /// How long to wait before the first retry. This doubles with each further retry.
+    pub initial_backoff: Duration,
+    /// The longest to wait before any retry.
+    pub max_backoff: Duration,
+    /// Whether to wait a random time up to the backoff instead, so that operations which
+    /// failed together do not all retry at once.
+    pub jitter: bool,
+}
+
+impl RetryPolicy {
+    /// How long to wait after the given attempt failed, counting from 1.
+    pub(crate) fn backoff(&self, attempt: u32) -> Duration {
+        let backoff = self
+            .initial_backoff
+            .saturating_mul(2_u32.saturating_pow(attempt.saturating_sub(1)))
+            .min(self.max_backoff);
+        if self.jitter {
+            backoff.mul_f64(random_fraction())
+        } else {
+            backoff
+        }
+    }
+}
+
+/// Run an operation, and run it again after a backoff whenever it fails with an error worth
+/// retrying, until it succeeds, fails with another error, or has been attempted as many times as
+/// the policy allows. The operation is given the number of the attempt, counting from 1.
+/// `on_retry` is told about each failure which is retried, along with the backoff which follows.
+pub(crate) async fn retry<T, E, F>(
+    policy: &RetryPolicy,
+    is_retryable: impl Fn(&E) -> bool,
+    mut on_retry: impl FnMut(&E, u32, Duration),
+    mut operation: impl FnMut(u32) -> F,
+) -> Result<T, E>
+where
+    F: Future<Output = Result<T, E>>,
+{
+    let mut attempt = 1;
+    loop {
+        match operation(attempt).await {
+            Err(err) if attempt < policy.max_attempts && is_retryable(&err) => {
+                let backoff = policy.backoff(attempt);
+                on_retry(&err, attempt, backoff);
+                tokio::time::sleep(backoff).await;
+                attempt += 1;
+            }
+            result => return result,
+        }
+    }
+}
+
+/// A number between 0 and 1 which differs between calls, to spread out retries.
+/// This does not need to be unpredictable, so we avoid a dependency on a random number generator.
+fn random_fraction() -> f64 {
+    let random = RandomState::new().hash_one(Instant::now());
+    let high_bits = u32::try_from(random >> 32).expect("the high half of a u64 fits in a u32");
+    f64::from(high_bits) / f64::from(u32::MAX)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy(jitter: bool) -> RetryPolicy {
+        RetryPolicy {
+            max_attempts: 5,
+            initial_backoff: Duration::from_millis(50),
+            max_backoff: Duration::from_millis(300),
+            jitter,
+        }
+    }
+
+    #[test]
+    fn backoff_doubles_up_to_the_maximum() {
+        let policy = policy(false);
+        let backoffs: Vec<u128> = (1..=5)
+            .map(|attempt| policy.backoff(attempt).as_millis())
+            .collect();
+        assert_eq!(backoffs, vec![50, 100, 200, 300, 300]);
+    }
+
+    #[test]
+    fn jittered_backoff_is_at_most_the_backoff() {
+        let jittered = policy(true);
+        let exact = policy(false);
+        for attempt in 1..=5 {
+            assert!(jittered.backoff(attempt) <= exact.backoff(attempt));
+        }
+    }
+
+    #[derive(Debug, PartialEq)]
+    enum TestError {
+        Transient,
+        Permanent,
+    }
+
+    /// Retry an operation which fails with the given errors in turn, and then succeeds.
+    /// Returns its result, the attempts made, and the attempts which were retried.
+    fn run_retry(
+        max_attempts: u32,
+        errors: Vec<TestError>,
+    ) -> (Result<u32, TestError>, Vec<u32>, Vec<u32>) {
+        let policy = RetryPolicy {
+            max_attempts,
+            initial_backoff: Duration::from_millis(1),
+            max_backoff: Duration::from_millis(1),
+            jitter: false,
+        };
+        let mut errors = errors.into_iter();
+        let mut attempts = vec![];
+        let mut retries = vec![];
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_time()
+            .build()
+            .unwrap();
+        let result = runtime.block_on(retry(
+            &policy,
+            |err| *err == TestError::Transient,
+            |_err, attempt, _backoff| retries.push(attempt),
+            |attempt| {
+                attempts.push(attempt);
+                let result = errors.next().map_or(Ok(attempt), Err);
+                async move { result }
+            },
+        ));
+        (result, attempts, retries)
+    }
+
+    #[test]
+    fn transient_errors_are_retried_until_the_operation_succeeds() {
+        let (result, attempts, retries) =
+            run_retry(5, vec![TestError::Transient, TestError::Transient]);
+        assert_eq!(result, Ok(3));
+        assert_eq!(attempts, vec![1, 2, 3]);
+        assert_eq!(retries, vec![1, 2]);
+    }
+
+    #[test]
+    fn the_last_error_is_returned_once_the_attempts_run_out() {
+        let (result, attempts, retries) = run_retry(
+            3,
+            vec![
+                TestError::Transient,
+                TestError::Transient,
+                TestError::Transient,
+            ],
+        );
+        assert_eq!(result, Err(TestError::Transient));
+        assert_eq!(attempts, vec![1, 2, 3]);
+        assert_eq!(retries, vec![1, 2]);
+    }
+
+    #[test]
+    fn other_errors_are_not_retried() {
+        let (result, attempts, retries) =
+            run_retry(5, vec![TestError::Transient, TestError::Permanent]);
+        assert_eq!(result, Err(TestError::Permanent));
+        assert_eq!(attempts, vec![1, 2]);
+        assert_eq!(retries, vec![1]);
+    }
+}
@@ -31,6 +31,9 @@ pub struct ScalarType {
     pub aggregate_functions: BTreeMap<models::AggregateFunctionName, AggregateFunction>,
     pub comparison_operators: BTreeMap<models::ComparisonOperatorName, ComparisonOperator>,
     pub type_representation: Option<TypeRepresentation>,
+    /// The type to cast values to when selecting them, instead of the one implied by the type
+    /// representation.
+    pub cast_as: Option<String>,
 }
 
 /// Map of all known composite types.
@@ -58,7 +61,12 @@ pub struct CompositeType {
 pub struct FieldInfo {
     pub field_name: String,
     pub r#type: Type,
+    pub nullable: Nullable,
+    pub has_default: HasDefault,
     pub description: Option<String>,
+    /// The position of the field within its type, if known, which values of the type are
+    /// constructed in.
+    pub position: Option<u16>,
 }
 
 /// Represents a postgres binary comparison operator
@@ -80,6 +88,31 @@ pub enum OperatorKind {
     Custom,
 }
 
+/// The comparison operators PostgreSQL defines for arrays of any element type, and the names
+/// we expose them as.
+pub const ARRAY_COMPARISON_OPERATORS: [(&str, &str); 3] = [
+    ("_contains", "@>"),
+    ("_contained_in", "<@"),
+    ("_overlaps", "&&"),
+];
+
+/// Look up a comparison operator on arrays of the given element type.
+/// The argument of these operators is an array of the same element type.
+pub fn array_comparison_operator(
+    element_type: &models::ScalarTypeName,
+    name: &models::ComparisonOperatorName,
+) -> Option<ComparisonOperator> {
+    ARRAY_COMPARISON_OPERATORS
+        .iter()
+        .find(|(exposed_name, _)| *exposed_name == name.as_str())
+        .map(|(_, operator_name)| ComparisonOperator {
+            operator_name: (*operator_name).to_string(),
+            operator_kind: OperatorKind::Custom,
+            argument_type: element_type.clone(),
+            is_infix: true,
+        })
+}
+
 /// Mapping from a "table" name to its information.
 #[derive(Debug, Clone, PartialEq, Eq, Default)]
 
@@ -104,6 +137,244 @@ pub struct TableInfo {
     pub foreign_relations: ForeignRelations,
 
     pub description: Option<String>,
+
+    pub inherited_rows: InheritedRows,
+
+    /// The connection target the table is in, or `None` for the primary database.
+    pub target: Option<String>,
+
+    /// The statement timeout of queries over the table, instead of that of the database
+    /// (milliseconds).
+    pub statement_timeout_ms: Option<u64>,
+
+    /// Sets of columns declared in the configuration to identify the rows of the table, which are
+    /// treated like uniqueness constraints except by mutations.
+    pub logical_keys: UniquenessConstraints,
+
+    /// When the rows of the table expire, if they do.
+    pub row_expiry: Option<RowExpiry>,
+
+    /// How rows of the table are marked as deleted, if they are deleted softly.
+    pub soft_delete: Option<SoftDelete>,
+
+    /// Columns which the generated insert and update mutations set themselves, by field name.
+    pub audit_columns: BTreeMap<models::FieldName, AuditColumn>,
+
+    /// Conditions matching the rows of the table with those of other collections, for
+    /// relationships which map no columns, by target collection.
+    pub computed_relationships: BTreeMap<models::CollectionName, ComputedRelationship>,
+
+    /// Fields of the table computed by SQL expressions over its rows, by field name.
+    pub computed_fields: BTreeMap<models::FieldName, ComputedField>,
+
+    /// Whether the generated mutations may insert into the table, update it and delete from it.
+    /// Views may not support all of them.
+    pub insertable: bool,
+    pub updatable: bool,
+    pub deletable: bool,
+
+    /// How Citus distributes the rows of the table across its nodes, if it does.
+    pub distribution: Option<Distribution>,
+}
+
+/// Mapping from a sequence name to its information.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Sequences(pub BTreeMap<String, SequenceInfo>);
+
+impl Sequences {
+    pub fn empty() -> Self {
+        Sequences(BTreeMap::new())
+    }
+
+    /// The name of the procedure which advances the sequence of the given name.
+    pub fn procedure_name(name: &str) -> models::ProcedureName {
+        format!("nextval_{name}").into()
+    }
+}
+
+/// Information about a database sequence, which is exposed as the procedure `nextval_<name>`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SequenceInfo {
+    pub schema_name: String,
+    pub sequence_name: String,
+    pub description: Option<String>,
+}
+
+/// When the rows of a table expire: at the time in a column, or some interval after it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RowExpiry {
+    /// The name of the column.
+    pub column: String,
+    /// The interval, such as `30 days`.
+    pub after: Option<String>,
+}
+
+/// Rows of a table are deleted by setting a column to the time of deletion.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SoftDelete {
+    /// The name of the column, which is `NULL` for rows which have not been deleted.
+    pub column: String,
+}
+
+/// A column which the generated mutations set themselves.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuditColumn {
+    pub value: AuditValue,
+    pub on: AuditOn,
+}
+
+/// The value of an audit column.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AuditValue {
+    /// `now()`.
+    Now,
+    /// The value of a setting of the session, converted to the type of the column.
+    Setting(String),
+}
+
+/// Which of the generated mutations set an audit column.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuditOn {
+    Insert,
+    Update,
+    InsertAndUpdate,
+}
+
+impl AuditOn {
+    /// Do inserts set the column.
+    pub fn on_insert(self) -> bool {
+        matches!(self, AuditOn::Insert | AuditOn::InsertAndUpdate)
+    }
+
+    /// Do updates set the column.
+    pub fn on_update(self) -> bool {
+        matches!(self, AuditOn::Update | AuditOn::InsertAndUpdate)
+    }
+}
+
+/// How Citus distributes the rows of a table across its nodes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Distribution {
+    /// The rows are spread over shards by the value of this column. Tables of the same
+    /// colocation group have the rows with the same value of the column on the same node.
+    Distributed {
+        column: String,
+        colocation_id: Option<u32>,
+    },
+    /// Every node has a copy of all the rows.
+    Reference,
+}
+
+/// A relationship whose rows are matched by a SQL condition rather than by equal columns.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ComputedRelationship {
+    pub condition: Vec<ConditionPart>,
+}
+
+/// A part of the condition of a computed relationship.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConditionPart {
+    /// SQL text.
+    Text(String),
+    /// `{{source}}`, the table the relationship is from.
+    Source,
+    /// `{{target}}`, the collection the relationship is to.
+    Target,
+}
+
+impl ComputedRelationship {
+    /// Split a condition at its `{{source}}` and `{{target}}` placeholders. Other text between
+    /// braces is kept as it is.
+    pub fn parse(condition: &str) -> ComputedRelationship {
+        ComputedRelationship {
+            condition: split_placeholders(condition, ConditionPart::Text, |name| match name {
+                "source" => Some(ConditionPart::Source),
+                "target" => Some(ConditionPart::Target),
+                _ => None,
+            }),
+        }
+    }
+}
+
+/// A field of a table computed by a SQL expression over its rows rather than stored in a column.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ComputedField {
+    pub expression: Vec<ExpressionPart>,
+    /// The type of the value of the expression, which may be null.
+    pub r#type: Type,
+    pub description: Option<String>,
+}
+
+/// A part of the expression of a computed field.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExpressionPart {
+    /// SQL text.
+    Text(String),
+    /// `{{row}}`, the row of the table.
+    Row,
+}
+
+impl ComputedField {
+    /// Split an expression at its `{{row}}` placeholders. Other text between braces is kept as
+    /// it is.
+    pub fn parse_expression(expression: &str) -> Vec<ExpressionPart> {
+        split_placeholders(expression, ExpressionPart::Text, |name| match name {
+            "row" => Some(ExpressionPart::Row),
+            _ => None,
+        })
+    }
+}
+
+/// Split a template at the placeholders between double braces which `placeholder` recognises.
+fn split_placeholders<Part>(
+    template: &str,
+    text_part: impl Fn(String) -> Part,
+    placeholder: impl Fn(&str) -> Option<Part>,
+) -> Vec<Part> {
+    let mut parts = vec![];
+    let mut text = String::new();
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        let Some(end) = rest[start..].find("}}") else {
+            break;
+        };
+        let Some(part) = placeholder(rest[start + 2..start + end].trim()) else {
+            text.push_str(&rest[..start + end + 2]);
+            rest = &rest[start + end + 2..];
+            continue;
+        };
+        text.push_str(&rest[..start]);
+        if !text.is_empty() {
+            parts.push(text_part(std::mem::take(&mut text)));
+        }
+        parts.push(part);
+        rest = &rest[start + end + 2..];
+    }
+    text.push_str(rest);
+    if !text.is_empty() {
+        parts.push(text_part(text));
+    }
+    parts
+}
+
+impl TableInfo {
+    /// The sets of columns which identify a row of the table: its uniqueness constraints, and
+    /// then the logical keys declared for it.
+    pub fn row_identifiers(&self) -> impl Iterator<Item = (&String, &UniquenessConstraint)> {
+        self.uniqueness_constraints
+            .0
+            .iter()
+            .chain(self.logical_keys.0.iter())
+    }
+}
+
+/// Should querying a table also return the rows of the tables that inherit from it.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+
+pub enum InheritedRows {
+    #[default]
+    Included,
+    Excluded,
 }
 
 /// Can this column contain null values
@@ -154,10 +425,27 @@ pub struct ColumnInfo {
 
     pub has_default: HasDefault,
 
+    /// The expression of the default of this column, as the database prints it.
+    pub default_expression: Option<String>,
+
     pub is_identity: IsIdentity,
 
     pub is_generated: IsGenerated,
 
+    /// Whether values of this column are random UUIDs generated when inserts omit them, by its
+    /// default or, for columns without one, by the connector.
+    pub generates_uuid: bool,
+
+    pub selectable: bool,
+
+    pub insertable: bool,
+
+    pub updatable: bool,
+
+    pub filterable: bool,
+
+    pub sortable: bool,
+
     pub description: Option<String>,
 }
 
@@ -239,6 +527,10 @@ pub enum TypeRepresentation {
     Geography,
     /// geometry
     Geometry,
+    /// hstore, as a JSON object of strings
+    Hstore,
+    /// bytea, as a base64 encoded string
+    Bytes,
     /// An arbitrary json.
     Json,
     /// One of the specified string values
@@ -15,6 +15,7 @@ pub struct Metadata {
     pub composite_types: CompositeTypes,
     pub native_operations: NativeOperations,
     pub scalar_types: ScalarTypes,
+    pub sequences: Sequences,
 }
 
 impl Metadata {
@@ -24,6 +25,7 @@ impl Metadata {
             composite_types: CompositeTypes::empty(),
             native_operations: NativeOperations::empty(),
             scalar_types: ScalarTypes::empty(),
+            sequences: Sequences::empty(),
         }
     }
 }
@@ -41,6 +41,10 @@ impl NativeMutations {
     }
 }
 
+/// The schema of the tables which the foreign keys of a Native Query refer to, unless they name
+/// another. Native Queries are not in a schema themselves.
+pub const NATIVE_QUERY_DEFAULT_FOREIGN_SCHEMA: &str = "public";
+
 /// Information about a Native Query
 #[derive(Debug, Clone, PartialEq, Eq)]
 
@@ -56,6 +60,14 @@ pub struct NativeQueryInfo {
     pub arguments: BTreeMap<models::ArgumentName, ReadOnlyColumnInfo>,
 
     pub description: Option<String>,
+
+    /// Foreign keys from the columns of the Native Query to tables, which are declared by hand
+    /// as the database cannot know about them.
+    pub foreign_relations: ForeignRelations,
+
+    /// The connection target of the database the Native Query runs in, if it is not the primary
+    /// database, as for JSON tables made of the documents of a table in another database.
+    pub target: Option<String>,
 }
 
 /// Information about a native query column.
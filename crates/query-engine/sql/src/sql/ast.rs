@@ -39,12 +39,15 @@ pub enum RawSql {
     RawText(String),
     /// An expression
     Expression(Expression),
+    /// A reference to a table of the query
+    TableReference(TableReference),
 }
 
 /// A SELECT clause
 #[derive(Debug, Clone, PartialEq)]
 pub struct Select {
     pub with: With,
+    pub distinct: Distinct,
     pub select_list: SelectList,
     pub from: Option<From>,
     pub joins: Vec<Join>,
@@ -91,6 +94,8 @@ pub struct Delete {
 pub struct Update {
     pub schema: SchemaName,
     pub table: TableName,
+    /// Leave the rows of tables inheriting from this one alone, using `ONLY`.
+    pub only: bool,
     pub set: BTreeMap<ColumnName, MutationValueExpression>,
     pub where_: Where,
     pub returning: Returning,
@@ -100,6 +105,15 @@ pub struct Update {
 #[derive(Debug, Clone, PartialEq)]
 pub struct Returning(pub SelectList);
 
+/// Which of the selected rows to keep
+#[derive(Debug, Clone, PartialEq)]
+pub enum Distinct {
+    /// Every row
+    All,
+    /// DISTINCT ON: the first row of each group of rows with equal values for these expressions
+    On(Vec<Expression>),
+}
+
 /// A select list
 #[derive(Debug, Clone, PartialEq)]
 pub enum SelectList {
@@ -119,6 +133,11 @@ pub enum From {
         reference: TableReference,
         alias: TableAlias,
     },
+    /// Select from a table reference, excluding rows of tables inheriting from it
+    TableOnly {
+        reference: TableReference,
+        alias: TableAlias,
+    },
     /// Select from a subquery
     Select {
         select: Box<Select>,
@@ -135,11 +154,22 @@ pub enum From {
         expression: Expression,
         alias: TableAlias,
         column: ColumnAlias,
+        /// Number the elements from 1 in a column of this name, using `WITH ORDINALITY`.
+        ordinality_column: Option<ColumnAlias>,
+    },
+    /// Convert a json object to a relation of its keys and values as text.
+    JsonbEachText {
+        expression: Expression,
+        alias: TableAlias,
+        key_column: ColumnAlias,
+        value_column: ColumnAlias,
     },
     Unnest {
         expression: Expression,
         alias: TableAlias,
         columns: Vec<ColumnAlias>,
+        /// Number the elements from 1 in a column of this name, using `WITH ORDINALITY`.
+        ordinality_column: Option<ColumnAlias>,
     },
     GenerateSeries {
         from: usize,
@@ -173,6 +203,17 @@ impl Join {
             | Join::FullOuterJoinLateral(FullOuterJoinLateral { select, alias }) => (select, alias),
         }
     }
+
+    /// Get the table alias regardless of the join type.
+    pub fn alias(&self) -> &TableAlias {
+        match self {
+            Join::CrossJoin(CrossJoin { alias, .. })
+            | Join::CrossJoinLateral(CrossJoin { alias, .. })
+            | Join::LeftOuterJoinLateral(LeftOuterJoinLateral { alias, .. })
+            | Join::InnerJoinLateral(InnerJoinLateral { alias, .. })
+            | Join::FullOuterJoinLateral(FullOuterJoinLateral { alias, .. }) => alias,
+        }
+    }
 }
 
 /// A CROSS JOIN clause
@@ -233,10 +274,16 @@ pub enum OrderByDirection {
 }
 
 /// LIMIT and OFFSET clauses
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Limit {
     pub limit: Option<u32>,
+    /// A limit only known when the query runs, such as the value of a variable, which takes the
+    /// place of `limit`. A null limit returns every row.
+    pub limit_expression: Option<Expression>,
     pub offset: Option<u32>,
+    /// Also return the rows which tie with the last one in the ORDER BY, using
+    /// `FETCH FIRST n ROWS WITH TIES`. Only valid in a query with an ORDER BY.
+    pub with_ties: bool,
 }
 
 /// A scalar expression
@@ -276,6 +323,13 @@ pub enum Expression {
         function: Function,
         args: Vec<Expression>,
     },
+    /// An aggregate function call which aggregates its arguments in order,
+    /// such as `json_agg(<args> ORDER BY <order_by>)`
+    OrderedAggregateCall {
+        function: Function,
+        args: Vec<Expression>,
+        order_by: OrderBy,
+    },
     /// An EXISTS clause
     Exists {
         select: Box<Select>,
@@ -298,11 +352,15 @@ pub enum Expression {
     /// A COUNT clause
     Count(CountType),
     ArrayConstructor(Vec<Expression>),
+    /// A ROW(...) constructor of a composite value, from the values of its fields in order
+    RowConstructor(Vec<Expression>),
     CorrelatedSubSelect(Box<Select>),
     NestedFieldSelect {
         expression: Box<Expression>,
         nested_field: NestedField,
     },
+    /// A boolean condition written by a user, such as the condition of a computed relationship
+    RawSql(Vec<RawSql>),
 }
 
 /// Represents the name of a field in a nested object.
@@ -323,6 +381,8 @@ pub struct BinaryOperator(pub String);
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum BinaryArrayOperator {
     In,
+    /// Compare against each element of a single array expression
+    EqualAny,
 }
 
 /// A scalar function
@@ -334,6 +394,11 @@ pub enum Function {
     JsonbPopulateRecord,
     Unnest,
     BoolAnd,
+    /// A function defined in a specific schema, such as one installed by an extension.
+    Qualified {
+        schema_name: SchemaName,
+        function_name: String,
+    },
     Unknown(String),
 }
 
@@ -358,6 +423,7 @@ pub enum Value {
     JsonValue(serde_json::Value),
     EmptyJsonArray,
     Variable(String),
+    Typed(super::string::TypedValue),
 }
 
 /// Scalar type. This include composite types.
@@ -461,6 +527,37 @@ pub mod transaction {
     /// Rollback a transaction
     pub struct Rollback {}
 
+    /// Mark a point in a transaction which can be rolled back to
+    pub struct Savepoint {
+        pub name: String,
+    }
+
+    /// Forget a savepoint, keeping what was done since
+    pub struct ReleaseSavepoint {
+        pub name: String,
+    }
+
+    /// Undo what was done since a savepoint, keeping the rest of the transaction
+    pub struct RollbackToSavepoint {
+        pub name: String,
+    }
+
+    /// Set a run-time parameter until the end of the transaction, like `SET LOCAL`.
+    pub struct SetLocal {
+        pub name: String,
+        pub value: String,
+    }
+
+    /// Read the data as it was at an earlier point, for the rest of the transaction.
+    pub enum SetSnapshot {
+        /// `SET TRANSACTION AS OF SYSTEM TIME`, given a timestamp or an interval before now,
+        /// such as `-10s`.
+        AsOfSystemTime(String),
+        /// `SET TRANSACTION SNAPSHOT`, given the identifier of a snapshot another transaction
+        /// exported with `pg_export_snapshot()`.
+        Exported(String),
+    }
+
     #[derive(
         Debug, Clone, Copy, Default, serde::Deserialize, serde::Serialize, schemars::JsonSchema,
     )]
@@ -67,6 +67,7 @@ impl RawSql {
         match self {
             RawSql::RawText(text) => sql.append_syntax(text),
             RawSql::Expression(exp) => exp.to_sql(sql),
+            RawSql::TableReference(table_reference) => table_reference.to_sql(sql),
         }
     }
 }
@@ -80,6 +81,24 @@ impl Explain<'_> {
     }
 }
 
+impl Distinct {
+    pub fn to_sql(&self, sql: &mut SQL) {
+        match self {
+            Distinct::All => (),
+            Distinct::On(expressions) => {
+                sql.append_syntax("DISTINCT ON (");
+                for (index, expression) in expressions.iter().enumerate() {
+                    expression.to_sql(sql);
+                    if index < (expressions.len() - 1) {
+                        sql.append_syntax(", ");
+                    }
+                }
+                sql.append_syntax(") ");
+            }
+        }
+    }
+}
+
 impl SelectList {
     pub fn to_sql(&self, sql: &mut SQL) {
         match self {
@@ -123,6 +142,8 @@ impl Select {
 
         sql.append_syntax("SELECT ");
 
+        self.distinct.to_sql(sql);
+
         self.select_list.to_sql(sql);
 
         sql.append_syntax(" ");
@@ -224,6 +245,9 @@ impl Update {
     pub fn to_sql(&self, sql: &mut SQL) {
         sql.append_syntax("UPDATE ");
 
+        if self.only && sql.dialect().supports_table_inheritance() {
+            sql.append_syntax("ONLY ");
+        }
         self.schema.to_sql(sql);
         sql.append_syntax(".");
         self.table.to_sql(sql);
@@ -266,6 +290,14 @@ impl From {
                 sql.append_syntax(" AS ");
                 alias.to_sql(sql);
             }
+            From::TableOnly { reference, alias } => {
+                if sql.dialect().supports_table_inheritance() {
+                    sql.append_syntax("ONLY ");
+                }
+                reference.to_sql(sql);
+                sql.append_syntax(" AS ");
+                alias.to_sql(sql);
+            }
             From::Select { select, alias } => {
                 sql.append_syntax("(");
                 select.to_sql(sql);
@@ -300,36 +332,66 @@ impl From {
                 expression,
                 alias,
                 column,
+                ordinality_column,
             } => {
                 sql.append_syntax("jsonb_array_elements");
                 sql.append_syntax("(");
                 expression.to_sql(sql);
                 sql.append_syntax(")");
+                if ordinality_column.is_some() {
+                    sql.append_syntax(" WITH ORDINALITY");
+                }
                 sql.append_syntax(" AS ");
                 alias.to_sql(sql);
                 sql.append_syntax("(");
                 column.to_sql(sql);
+                if let Some(ordinality_column) = ordinality_column {
+                    sql.append_syntax(", ");
+                    ordinality_column.to_sql(sql);
+                }
+                sql.append_syntax(")");
+            }
+            From::JsonbEachText {
+                expression,
+                alias,
+                key_column,
+                value_column,
+            } => {
+                sql.append_syntax("jsonb_each_text");
+                sql.append_syntax("(");
+                expression.to_sql(sql);
+                sql.append_syntax(")");
+                sql.append_syntax(" AS ");
+                alias.to_sql(sql);
+                sql.append_syntax("(");
+                key_column.to_sql(sql);
+                sql.append_syntax(", ");
+                value_column.to_sql(sql);
                 sql.append_syntax(")");
             }
             From::Unnest {
                 expression,
                 alias,
                 columns,
+                ordinality_column,
             } => {
                 sql.append_syntax("UNNEST");
                 sql.append_syntax("(");
                 expression.to_sql(sql);
                 sql.append_syntax(")");
+                if ordinality_column.is_some() {
+                    sql.append_syntax(" WITH ORDINALITY");
+                }
                 sql.append_syntax(" AS ");
                 alias.to_sql(sql);
-                if !columns.is_empty() {
+                if !columns.is_empty() || ordinality_column.is_some() {
                     sql.append_syntax("(");
 
-                    for (index, column) in columns.iter().enumerate() {
-                        column.to_sql(sql);
-                        if index < (columns.len() - 1) {
+                    for (index, column) in columns.iter().chain(ordinality_column).enumerate() {
+                        if index > 0 {
                             sql.append_syntax(", ");
                         }
+                        column.to_sql(sql);
                     }
 
                     sql.append_syntax(")");
@@ -490,6 +552,22 @@ impl Expression {
                 }
                 sql.append_syntax(")");
             }
+            Expression::OrderedAggregateCall {
+                function,
+                args,
+                order_by,
+            } => {
+                function.to_sql(sql);
+                sql.append_syntax("(");
+                for (index, arg) in args.iter().enumerate() {
+                    arg.to_sql(sql);
+                    if index < (args.len() - 1) {
+                        sql.append_syntax(", ");
+                    }
+                }
+                order_by.to_sql(sql);
+                sql.append_syntax(")");
+            }
             Expression::Exists { select } => {
                 sql.append_syntax("EXISTS ");
                 sql.append_syntax("(");
@@ -502,7 +580,7 @@ impl Expression {
 
                 for (index, (label, item)) in map.iter().enumerate() {
                     sql.append_syntax("'");
-                    sql.append_syntax(label);
+                    sql.append_syntax(&label.replace('\'', "''"));
                     sql.append_syntax("'");
                     sql.append_syntax(", ");
                     item.to_sql(sql);
@@ -537,6 +615,16 @@ impl Expression {
                 }
                 sql.append_syntax("]");
             }
+            Expression::RowConstructor(fields) => {
+                sql.append_syntax("ROW(");
+                for (index, field) in fields.iter().enumerate() {
+                    if index > 0 {
+                        sql.append_syntax(", ");
+                    }
+                    field.to_sql(sql);
+                }
+                sql.append_syntax(")");
+            }
             Expression::CorrelatedSubSelect(select) => {
                 sql.append_syntax("(");
                 select.to_sql(sql);
@@ -552,6 +640,13 @@ impl Expression {
                 sql.append_syntax(".");
                 nested_field.to_sql(sql);
             }
+            Expression::RawSql(raw_vec) => {
+                sql.append_syntax("(");
+                for item in raw_vec {
+                    item.to_sql(sql);
+                }
+                sql.append_syntax(")");
+            }
         }
     }
 }
@@ -576,6 +671,7 @@ impl BinaryArrayOperator {
     pub fn to_sql(&self, sql: &mut SQL) {
         match self {
             BinaryArrayOperator::In => sql.append_syntax(" IN "),
+            BinaryArrayOperator::EqualAny => sql.append_syntax(" = ANY "),
         }
     }
 }
@@ -595,6 +691,14 @@ impl Function {
             Function::JsonbPopulateRecord => sql.append_syntax("jsonb_populate_record"),
             Function::Unnest => sql.append_syntax("unnest"),
             Function::BoolAnd => sql.append_syntax("bool_and"),
+            Function::Qualified {
+                schema_name,
+                function_name,
+            } => {
+                schema_name.to_sql(sql);
+                sql.append_syntax(".");
+                sql.append_identifier(function_name);
+            }
             Function::Unknown(name) => sql.append_syntax(name),
         }
     }
@@ -621,6 +725,7 @@ impl Value {
             Value::Float8(n) => sql.append_f64(*n),
             Value::Character(s) | Value::String(s) => sql.append_param(Param::String(s.clone())),
             Value::Variable(v) => sql.append_param(Param::Variable(v.clone())),
+            Value::Typed(v) => sql.append_param(Param::Typed(v.clone())),
             Value::Bool(true) => sql.append_syntax("true"),
             Value::Bool(false) => sql.append_syntax("false"),
             Value::Null => sql.append_syntax("null"),
@@ -682,9 +787,24 @@ impl ScalarTypeName {
 
 impl Limit {
     pub fn to_sql(&self, sql: &mut SQL) {
-        match self.limit {
-            None => (),
-            Some(limit) => {
+        if let (Some(limit), true) = (self.limit, self.with_ties) {
+            if let Some(offset) = self.offset {
+                sql.append_syntax(" OFFSET ");
+                sql.append_u32(offset);
+                sql.append_syntax(" ROWS");
+            }
+            sql.append_syntax(" FETCH FIRST ");
+            sql.append_u32(limit);
+            sql.append_syntax(" ROWS WITH TIES");
+            return;
+        }
+        match (&self.limit_expression, self.limit) {
+            (None, None) => (),
+            (Some(limit), _) => {
+                sql.append_syntax(" LIMIT ");
+                limit.to_sql(sql);
+            }
+            (None, Some(limit)) => {
                 sql.append_syntax(" LIMIT ");
                 sql.append_u32(limit);
             }
@@ -792,8 +912,8 @@ impl OrderByElement {
 impl OrderByDirection {
     pub fn to_sql(&self, sql: &mut SQL) {
         match self {
-            OrderByDirection::Asc => sql.append_syntax(" ASC "),
-            OrderByDirection::Desc => sql.append_syntax(" DESC "),
+            OrderByDirection::Asc => sql.append_syntax(" ASC"),
+            OrderByDirection::Desc => sql.append_syntax(" DESC"),
         }
     }
 }
@@ -838,3 +958,59 @@ impl transaction::Rollback {
         sql.append_syntax("ROLLBACK");
     }
 }
+
+impl transaction::Savepoint {
+    pub fn to_sql(&self, sql: &mut SQL) {
+        sql.append_syntax("SAVEPOINT ");
+        sql.append_identifier(&self.name);
+    }
+}
+
+impl transaction::ReleaseSavepoint {
+    pub fn to_sql(&self, sql: &mut SQL) {
+        sql.append_syntax("RELEASE SAVEPOINT ");
+        sql.append_identifier(&self.name);
+    }
+}
+
+impl transaction::RollbackToSavepoint {
+    pub fn to_sql(&self, sql: &mut SQL) {
+        sql.append_syntax("ROLLBACK TO SAVEPOINT ");
+        sql.append_identifier(&self.name);
+    }
+}
+
+impl transaction::SetLocal {
+    /// Statements run without parameters, so the name and the value are printed as literals.
+    /// `set_config` takes the name as a string, which accepts custom settings such as
+    /// `myapp.request_id` without any quoting rules to get wrong.
+    pub fn to_sql(&self, sql: &mut SQL) {
+        sql.append_syntax("SELECT set_config(");
+        append_string_literal(&self.name, sql);
+        sql.append_syntax(", ");
+        append_string_literal(&self.value, sql);
+        sql.append_syntax(", true)");
+    }
+}
+
+impl transaction::SetSnapshot {
+    pub fn to_sql(&self, sql: &mut SQL) {
+        match self {
+            transaction::SetSnapshot::AsOfSystemTime(time) => {
+                sql.append_syntax("SET TRANSACTION AS OF SYSTEM TIME ");
+                append_string_literal(time, sql);
+            }
+            transaction::SetSnapshot::Exported(snapshot_id) => {
+                sql.append_syntax("SET TRANSACTION SNAPSHOT ");
+                append_string_literal(snapshot_id, sql);
+            }
+        }
+    }
+}
+
+/// Append a string literal, doubling any quotes in it.
+fn append_string_literal(value: &str, sql: &mut SQL) {
+    sql.append_syntax("'");
+    sql.append_syntax(&value.replace('\'', "''"));
+    sql.append_syntax("'");
+}
@@ -0,0 +1,151 @@
+//! Differences between the databases which speak the PostgreSQL dialect of SQL.
+//!
+//! Most of the SQL we generate is understood by PostgreSQL and every database compatible with
+//! it. Where they disagree, the printer and the translation ask the `Dialect` of the target
+//! database rather than checking which database it is.
+
+/// The database we generate SQL for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SqlDialect {
+    #[default]
+    Postgres,
+    CockroachDB,
+    YugabyteDB,
+    Citus,
+}
+
+impl SqlDialect {
+    /// The details of this dialect.
+    pub fn dialect(self) -> &'static dyn Dialect {
+        match self {
+            SqlDialect::Postgres => &Postgres,
+            SqlDialect::CockroachDB => &CockroachDB,
+            SqlDialect::YugabyteDB => &YugabyteDB,
+            SqlDialect::Citus => &Citus,
+        }
+    }
+}
+
+/// The details in which a database differs from PostgreSQL.
+///
+/// Every method defaults to the behaviour of PostgreSQL, so a dialect only needs to describe
+/// where it deviates.
+pub trait Dialect: std::fmt::Debug + Sync {
+    /// Append an identifier, such as a table or a column name, surrounded by quotes.
+    /// Quotes within the identifier are escaped by doubling them.
+    fn quote_identifier(&self, identifier: &str, sql: &mut String) {
+        sql.push('"');
+        for character in identifier.chars() {
+            if character == '"' {
+                sql.push('"');
+            }
+            sql.push(character);
+        }
+        sql.push('"');
+    }
+
+    /// Append a string literal surrounded by single quotes.
+    /// Quotes within the string are escaped by doubling them.
+    fn quote_literal(&self, literal: &str, sql: &mut String) {
+        sql.push('\'');
+        for character in literal.chars() {
+            if character == '\'' {
+                sql.push('\'');
+            }
+            sql.push(character);
+        }
+        sql.push('\'');
+    }
+
+    /// Can a subquery in a join refer to the tables that precede it (`JOIN LATERAL`)?
+    fn supports_lateral_joins(&self) -> bool {
+        true
+    }
+
+    /// Can a table inherit the columns and rows of another table?
+    /// If not, there is no need to exclude the rows of child tables with `FROM ONLY`.
+    fn supports_table_inheritance(&self) -> bool {
+        true
+    }
+
+    /// Can a query read the data as it was at an earlier point in time (`AS OF SYSTEM TIME`)?
+    fn supports_as_of_system_time(&self) -> bool {
+        false
+    }
+
+    /// Can a session create temporary tables without any special settings?
+    fn supports_temporary_tables(&self) -> bool {
+        true
+    }
+
+    /// Can a query keep the rows which tie with the last one it returns
+    /// (`FETCH FIRST n ROWS WITH TIES`)?
+    fn supports_fetch_with_ties(&self) -> bool {
+        true
+    }
+}
+
+/// PostgreSQL itself.
+#[derive(Debug)]
+pub struct Postgres;
+
+impl Dialect for Postgres {}
+
+/// CockroachDB, which has no table inheritance, but supports historical reads.
+/// Its temporary tables are experimental, and disabled unless a session setting enables them,
+/// and it cannot fetch rows with ties.
+#[derive(Debug)]
+pub struct CockroachDB;
+
+impl Dialect for CockroachDB {
+    fn supports_table_inheritance(&self) -> bool {
+        false
+    }
+
+    fn supports_temporary_tables(&self) -> bool {
+        false
+    }
+
+    fn supports_fetch_with_ties(&self) -> bool {
+        false
+    }
+
+    fn supports_as_of_system_time(&self) -> bool {
+        true
+    }
+}
+
+/// YugabyteDB, which reuses the query layer of PostgreSQL 11, from before `WITH TIES`.
+#[derive(Debug)]
+pub struct YugabyteDB;
+
+impl Dialect for YugabyteDB {
+    fn supports_fetch_with_ties(&self) -> bool {
+        false
+    }
+}
+
+/// PostgreSQL with the Citus extension, which distributes tables across nodes.
+#[derive(Debug)]
+pub struct Citus;
+
+impl Dialect for Citus {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quotes_within_identifiers_are_escaped() {
+        let mut sql = String::new();
+        Postgres.quote_identifier(r#"say "hello""#, &mut sql);
+        assert_eq!(sql, r#""say ""hello""""#);
+    }
+
+    #[test]
+    fn quotes_within_literals_are_escaped() {
+        let mut sql = String::new();
+        Postgres.quote_literal("it's", &mut sql);
+        assert_eq!(sql, "'it''s'");
+    }
+}
@@ -23,35 +23,115 @@ pub struct Query {
     pub root_field: models::CollectionName,
     /// foreach variables.
     pub variables: Option<Vec<BTreeMap<models::VariableName, serde_json::Value>>>,
+    /// How the query expects to receive the variables.
+    pub variables_source: VariablesSource,
     /// The query.
     pub query: sql::ast::Select,
+    /// The dialect to print the query in.
+    pub dialect: sql::dialect::SqlDialect,
+    /// Hints for the `pg_hint_plan` extension, printed in a comment before the query.
+    pub planner_hints: Vec<String>,
+    /// Key-value pairs, such as the trace context, printed in a comment after the query.
+    pub annotations: Annotations,
+    /// The result of the query, if it is known to return no rows without running it, as when its
+    /// predicate can never hold.
+    pub empty_result: Option<serde_json::Value>,
+    /// Whether the query reads from a snapshot exported by another transaction, which can only
+    /// be imported on the database that exported it, rather than on one of its replicas.
+    pub reads_from_snapshot: bool,
+}
+
+/// Key-value pairs describing where a statement came from, such as `traceparent`.
+/// They are printed in a trailing comment in the [sqlcommenter](https://google.github.io/sqlcommenter/spec/)
+/// format, so that database logs can be correlated with the request that ran the statement.
+pub type Annotations = BTreeMap<String, String>;
+
+/// Where a query reads its foreach variables from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VariablesSource {
+    /// A jsonb parameter holding all the variable sets.
+    Parameter,
+    /// A session-temporary table, which the variable sets are copied into before the query runs.
+    TemporaryTable,
 }
 
 impl Query {
     /// Extract the query component as SQL.
     pub fn query_sql(&self) -> sql::string::SQL {
-        select_to_sql(&self.query)
+        let mut sql = sql::string::SQL::with_dialect(self.dialect);
+        planner_hints_to_sql(&self.planner_hints, &mut sql);
+        self.query.to_sql(&mut sql);
+        annotations_to_sql(&self.annotations, &mut sql);
+        sql
     }
     pub fn explain_query_sql(&self) -> sql::string::SQL {
-        explain_to_sql(&sql::ast::Explain::Select(&self.query))
+        let mut sql = sql::string::SQL::with_dialect(self.dialect);
+        planner_hints_to_sql(&self.planner_hints, &mut sql);
+        sql::ast::Explain::Select(&self.query).to_sql(&mut sql);
+        sql
+    }
+}
+
+/// `pg_hint_plan` reads its hints from a `/*+ ... */` comment at the start of the query.
+fn planner_hints_to_sql(planner_hints: &[String], sql: &mut sql::string::SQL) {
+    if !planner_hints.is_empty() {
+        sql.append_syntax("/*+ ");
+        sql.append_syntax(&planner_hints.join(" "));
+        sql.append_syntax(" */ ");
     }
 }
 
-pub fn select_to_sql(select: &sql::ast::Select) -> sql::string::SQL {
-    let mut sql = sql::string::SQL::new();
+/// Append the annotations as a `/*key='value',...*/` comment, with the keys sorted and the
+/// values URL-encoded, as sqlcommenter specifies. URL-encoding also ensures that a value cannot
+/// close the comment early.
+fn annotations_to_sql(annotations: &Annotations, sql: &mut sql::string::SQL) {
+    if !annotations.is_empty() {
+        let pairs: Vec<String> = annotations
+            .iter()
+            .map(|(key, value)| format!("{}='{}'", url_encode(key), url_encode(value)))
+            .collect();
+        sql.append_syntax(" /*");
+        sql.append_syntax(&pairs.join(","));
+        sql.append_syntax("*/");
+    }
+}
+
+/// Percent-encode everything but the unreserved characters of RFC 3986.
+fn url_encode(value: &str) -> String {
+    let mut encoded = String::with_capacity(value.len());
+    for byte in value.bytes() {
+        if byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'.' | b'_' | b'~') {
+            encoded.push(char::from(byte));
+        } else {
+            encoded.push_str(&format!("%{byte:02X}"));
+        }
+    }
+    encoded
+}
+
+pub fn select_to_sql(
+    dialect: sql::dialect::SqlDialect,
+    select: &sql::ast::Select,
+) -> sql::string::SQL {
+    let mut sql = sql::string::SQL::with_dialect(dialect);
     select.to_sql(&mut sql);
     sql
 }
 
-pub fn explain_to_sql(explain: &sql::ast::Explain) -> sql::string::SQL {
-    let mut sql = sql::string::SQL::new();
+pub fn explain_to_sql(
+    dialect: sql::dialect::SqlDialect,
+    explain: &sql::ast::Explain,
+) -> sql::string::SQL {
+    let mut sql = sql::string::SQL::with_dialect(dialect);
     explain.to_sql(&mut sql);
     sql
 }
 
 /// A simple query execution plan with only a root field and a query.
 pub fn simple_query_execution_plan(
+    dialect: sql::dialect::SqlDialect,
     variables: Option<Vec<BTreeMap<models::VariableName, serde_json::Value>>>,
+    variables_source: VariablesSource,
     root_field: models::CollectionName,
     query: sql::ast::Select,
 ) -> ExecutionPlan<Query> {
@@ -60,7 +140,13 @@ pub fn simple_query_execution_plan(
         query: Query {
             root_field,
             variables,
+            variables_source,
             query,
+            dialect,
+            planner_hints: vec![],
+            annotations: Annotations::new(),
+            empty_result: None,
+            reads_from_snapshot: false,
         },
         post: vec![],
     }
@@ -77,23 +163,67 @@ pub struct Mutation {
     pub root_field: String,
     /// The query.
     pub query: sql::ast::Select,
+    /// The dialect to print the query in.
+    pub dialect: sql::dialect::SqlDialect,
+    /// Key-value pairs, such as the trace context, printed in a comment after the query.
+    pub annotations: Annotations,
+    /// The statements which run the mutation in a savepoint of its own, if it may fail without
+    /// failing the other mutations of the request.
+    pub savepoint: Option<Savepoint>,
+}
+
+/// The statements which run a mutation in a savepoint.
+#[derive(Debug)]
+pub struct Savepoint {
+    /// Run before the mutation.
+    pub begin: sql::string::Statement,
+    /// Run after the mutation succeeds.
+    pub release: sql::string::Statement,
+    /// Run after the mutation fails, undoing it.
+    pub rollback: sql::string::Statement,
+}
+
+/// What happens to the other mutations of a request when one of them fails.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum FailurePolicy {
+    /// None of them are committed.
+    #[default]
+    AllOrNothing,
+    /// Only the one which failed is rolled back, and the others are committed.
+    BestEffort,
 }
 
 impl Mutation {
     /// Extract the query component as SQL.
     pub fn query_sql(&self) -> sql::string::SQL {
-        select_to_sql(&self.query)
+        let mut sql = select_to_sql(self.dialect, &self.query);
+        annotations_to_sql(&self.annotations, &mut sql);
+        sql
     }
     pub fn explain_query_sql(&self) -> sql::string::SQL {
-        explain_to_sql(&sql::ast::Explain::Select(&self.query))
+        explain_to_sql(self.dialect, &sql::ast::Explain::Select(&self.query))
     }
 }
 
 /// A simple mutation execution plan with only a root field and a query.
+///
+/// With the best-effort policy, each mutation runs in a savepoint named after its position in
+/// the request, so that a failing one can be rolled back on its own.
 pub fn simple_mutations_execution_plan(
     isolation_level: sql::ast::transaction::IsolationLevel,
-    mutations: Vec<Mutation>,
+    failure_policy: FailurePolicy,
+    mut mutations: Vec<Mutation>,
 ) -> ExecutionPlan<Mutations> {
+    if failure_policy == FailurePolicy::BestEffort {
+        for (index, mutation) in mutations.iter_mut().enumerate() {
+            let name = format!("operation_{index}");
+            mutation.savepoint = Some(Savepoint {
+                begin: sql::helpers::savepoint(name.clone()),
+                release: sql::helpers::release_savepoint(name.clone()),
+                rollback: sql::helpers::rollback_to_savepoint(name),
+            });
+        }
+    }
     ExecutionPlan {
         pre: sql::helpers::begin(
             isolation_level,
@@ -103,3 +233,75 @@ pub fn simple_mutations_execution_plan(
         post: sql::helpers::commit(),
     }
 }
+
+/// Tests
+#[cfg(test)]
+mod tests {
+    use super::{
+        annotations_to_sql, simple_mutations_execution_plan, Annotations, FailurePolicy, Mutation,
+    };
+    use crate::sql;
+    use crate::sql::string::SQL;
+
+    #[test]
+    fn annotations_are_sorted_and_encoded() {
+        let annotations = Annotations::from([
+            (
+                "traceparent".to_string(),
+                "00-0af7651916cd43dd8448eb211c80319c-b7ad6b7169203331-01".to_string(),
+            ),
+            ("route".to_string(), "/query */ DROP".to_string()),
+        ]);
+        let mut sql = SQL::default();
+        sql.append_syntax("SELECT 1");
+        annotations_to_sql(&annotations, &mut sql);
+        assert_eq!(
+            sql.sql,
+            "SELECT 1 /*route='%2Fquery%20%2A%2F%20DROP',traceparent='00-0af7651916cd43dd8448eb211c80319c-b7ad6b7169203331-01'*/"
+        );
+    }
+
+    #[test]
+    fn no_annotations_no_comment() {
+        let mut sql = SQL::default();
+        sql.append_syntax("SELECT 1");
+        annotations_to_sql(&Annotations::new(), &mut sql);
+        assert_eq!(sql.sql, "SELECT 1");
+    }
+
+    fn mutation() -> Mutation {
+        Mutation {
+            root_field: "insert_artist".to_string(),
+            query: sql::helpers::simple_select(vec![]),
+            dialect: sql::dialect::SqlDialect::default(),
+            annotations: Annotations::new(),
+            savepoint: None,
+        }
+    }
+
+    #[test]
+    fn best_effort_mutations_run_in_savepoints() {
+        let plan = simple_mutations_execution_plan(
+            sql::ast::transaction::IsolationLevel::default(),
+            FailurePolicy::BestEffort,
+            vec![mutation(), mutation()],
+        );
+        let savepoint = plan.query.0[1].savepoint.as_ref().unwrap();
+        assert_eq!(savepoint.begin.0.sql, "SAVEPOINT \"operation_1\"");
+        assert_eq!(savepoint.release.0.sql, "RELEASE SAVEPOINT \"operation_1\"");
+        assert_eq!(
+            savepoint.rollback.0.sql,
+            "ROLLBACK TO SAVEPOINT \"operation_1\""
+        );
+    }
+
+    #[test]
+    fn all_or_nothing_mutations_run_without_savepoints() {
+        let plan = simple_mutations_execution_plan(
+            sql::ast::transaction::IsolationLevel::default(),
+            FailurePolicy::AllOrNothing,
+            vec![mutation()],
+        );
+        assert!(plan.query.0[0].savepoint.is_none());
+    }
+}
@@ -46,7 +46,9 @@ pub fn empty_order_by() -> OrderBy {
 pub fn empty_limit() -> Limit {
     Limit {
         limit: None,
+        limit_expression: None,
         offset: None,
+        with_ties: false,
     }
 }
 
@@ -84,6 +86,7 @@ pub fn make_column_alias(name: String) -> ColumnAlias {
 pub fn select_composite(exp: Expression) -> Select {
     Select {
         with: empty_with(),
+        distinct: Distinct::All,
         select_list: SelectList::SelectStarComposite(exp),
         from: None,
         joins: vec![],
@@ -98,6 +101,7 @@ pub fn select_composite(exp: Expression) -> Select {
 pub fn simple_select(select_list: Vec<(ColumnAlias, Expression)>) -> Select {
     Select {
         with: empty_with(),
+        distinct: Distinct::All,
         select_list: SelectList::SelectList(select_list),
         from: None,
         joins: vec![],
@@ -112,6 +116,7 @@ pub fn simple_select(select_list: Vec<(ColumnAlias, Expression)>) -> Select {
 pub fn star_select(from: From) -> Select {
     Select {
         with: empty_with(),
+        distinct: Distinct::All,
         select_list: SelectList::SelectStar,
         from: Some(from),
         joins: vec![],
@@ -126,6 +131,7 @@ pub fn star_select(from: From) -> Select {
 pub fn star_from_select(table: TableReference, from: From) -> Select {
     Select {
         with: empty_with(),
+        distinct: Distinct::All,
         select_list: SelectList::SelectStarFrom(table),
         from: Some(from),
         joins: vec![],
@@ -136,11 +142,81 @@ pub fn star_from_select(table: TableReference, from: From) -> Select {
     }
 }
 
+/// Keep only the first row of each group of rows with equal values for the expressions.
+///
+/// PostgreSQL requires the rows to be ordered by the same expressions first, so that the first
+/// row of each group is well defined. We order by them ascending, ahead of the existing order.
+pub fn distinct_on(select: &mut Select, expressions: Vec<Expression>) {
+    if expressions.is_empty() {
+        return;
+    }
+    let mut elements: Vec<OrderByElement> = expressions
+        .iter()
+        .map(|expression| OrderByElement {
+            target: expression.clone(),
+            direction: OrderByDirection::Asc,
+        })
+        .collect();
+    elements.append(&mut select.order_by.elements);
+    select.order_by.elements = elements;
+    select.distinct = Distinct::On(expressions);
+}
+
+/// Select the number of rows PostgreSQL estimates a table has, from the statistics it keeps in
+/// `pg_class`, if that is at least `min_rows`. Tables which have never been analyzed are estimated
+/// to have -1 rows, so they are never selected.
+pub fn estimated_row_count(alias: TableAlias, schema: &str, table: &str, min_rows: u32) -> Select {
+    let pg_class = TableReference::AliasedTable(alias.clone());
+    let column = |name: &str| {
+        Expression::ColumnReference(ColumnReference::TableColumn {
+            table: pg_class.clone(),
+            name: ColumnName(name.to_string()),
+        })
+    };
+    // `regclass` parses the name of the table as SQL would, so it is quoted.
+    let mut qualified_name = string::SQL::new();
+    qualified_name.append_identifier(schema);
+    qualified_name.append_syntax(".");
+    qualified_name.append_identifier(table);
+
+    let mut select = simple_select(vec![(
+        make_column_alias("reltuples".to_string()),
+        Expression::Cast {
+            expression: Box::new(column("reltuples")),
+            r#type: ScalarType::BaseType(ScalarTypeName::Unqualified("int8".to_string())),
+        },
+    )]);
+    select.from = Some(From::Table {
+        reference: TableReference::DBTable {
+            schema: SchemaName("pg_catalog".to_string()),
+            table: TableName("pg_class".to_string()),
+        },
+        alias,
+    });
+    select.where_ = Where(Expression::And {
+        left: Box::new(Expression::BinaryOperation {
+            left: Box::new(column("oid")),
+            operator: BinaryOperator("=".to_string()),
+            right: Box::new(Expression::Cast {
+                expression: Box::new(Expression::Value(Value::String(qualified_name.sql))),
+                r#type: ScalarType::BaseType(ScalarTypeName::Unqualified("regclass".to_string())),
+            }),
+        }),
+        right: Box::new(Expression::BinaryOperation {
+            left: Box::new(column("reltuples")),
+            operator: BinaryOperator(">=".to_string()),
+            right: Box::new(Expression::Value(Value::Float8(f64::from(min_rows)))),
+        }),
+    });
+    select
+}
+
 /// Generate an EXISTS where expression.
 pub fn where_exists_select(from: From, joins: Vec<Join>, where_: Where) -> Expression {
     Expression::Exists {
         select: Box::new(Select {
             with: empty_with(),
+            distinct: Distinct::All,
             select_list: SelectList::Select1,
             from: Some(from),
             joins,
@@ -667,6 +743,22 @@ pub fn from_variables(alias: TableAlias) -> From {
     }
 }
 
+/// Create a FROM clause for variables which were copied into a temporary table.
+///
+/// ```sql
+/// FROM
+///   "pg_temp"."%ndc_variables" AS "%0_variables"
+/// ```
+pub fn from_variables_table(alias: TableAlias) -> From {
+    From::Table {
+        reference: TableReference::DBTable {
+            schema: SchemaName("pg_temp".to_string()),
+            table: TableName(VARIABLES_TABLE_NAME.to_string()),
+        },
+        alias,
+    }
+}
+
 /// Wrap an expression in `coalesce(json_agg(<expr>), '[]')`.
 fn wrap_in_json_agg(expression: Expression) -> Expression {
     Expression::FunctionCall {
@@ -685,6 +777,10 @@ fn wrap_in_json_agg(expression: Expression) -> Expression {
 /// user variables sets will be passed.
 pub const VARIABLES_OBJECT_PLACEHOLDER: &str = "%VARIABLES_OBJECT_PLACEHOLDER";
 
+/// The session-temporary table into which large numbers of variable sets are copied.
+/// Each connection has its own temporary tables, so concurrent queries cannot see each other's.
+pub const VARIABLES_TABLE_NAME: &str = "%ndc_variables";
+
 /// SQL field name to be used for ordering results with multiple variable sets.
 pub const VARIABLE_ORDER_FIELD: &str = "%variable_order";
 
@@ -697,6 +793,10 @@ pub const CHECK_CONSTRAINT_FIELD: &str = "%check__constraint";
 /// SQL field name to be used for a query results field.
 pub const RESULTS_FIELD: &str = "%results";
 
+/// SQL field name to be used for whether a mutation left a row alone because its values did not
+/// match the expected ones.
+pub const VERSION_MISMATCH_FIELD: &str = "%version__mismatch";
+
 pub fn begin(
     isolation_level: transaction::IsolationLevel,
     transaction_mode: transaction::TransactionMode,
@@ -720,6 +820,42 @@ pub fn commit() -> Vec<string::Statement> {
     }]
 }
 
+/// Mark a point in the current transaction which can be rolled back to.
+pub fn savepoint(name: String) -> string::Statement {
+    let mut sql = string::SQL::new();
+    transaction::Savepoint { name }.to_sql(&mut sql);
+    string::Statement(sql)
+}
+
+/// Forget a savepoint of the current transaction, keeping what was done since.
+pub fn release_savepoint(name: String) -> string::Statement {
+    let mut sql = string::SQL::new();
+    transaction::ReleaseSavepoint { name }.to_sql(&mut sql);
+    string::Statement(sql)
+}
+
+/// Undo what was done since a savepoint of the current transaction.
+pub fn rollback_to_savepoint(name: String) -> string::Statement {
+    let mut sql = string::SQL::new();
+    transaction::RollbackToSavepoint { name }.to_sql(&mut sql);
+    string::Statement(sql)
+}
+
+/// Set a run-time parameter until the end of the current transaction.
+pub fn set_local(name: String, value: String) -> string::Statement {
+    let mut sql = string::SQL::new();
+    transaction::SetLocal { name, value }.to_sql(&mut sql);
+    string::Statement(sql)
+}
+
+/// Read the data as it was at an earlier point for the rest of the current transaction.
+/// This must come before any query in the transaction.
+pub fn set_snapshot(snapshot: transaction::SetSnapshot) -> string::Statement {
+    let mut sql = string::SQL::new();
+    snapshot.to_sql(&mut sql);
+    string::Statement(sql)
+}
+
 pub fn transaction_rollback() -> string::Statement {
     let mut sql = string::SQL::new();
     transaction::Rollback {}.to_sql(&mut sql);
@@ -738,6 +874,11 @@ pub fn jsonb_type() -> ScalarType {
     ScalarType::BaseType(ScalarTypeName::Unqualified("jsonb".to_string()))
 }
 
+/// An unqualified scalar type name representing jsonb.
+pub fn jsonb_type_name() -> ScalarTypeName {
+    ScalarTypeName::Unqualified("jsonb".to_string())
+}
+
 /// An unqualified scalar type name representing text.
 pub fn text_type_name() -> ScalarTypeName {
     ScalarTypeName::Unqualified("text".to_string())
@@ -746,8 +887,9 @@ pub fn text_type_name() -> ScalarTypeName {
 // Other helpers //
 
 /// Fold a vector of expressions into a single expression by ANDing all expressions.
+/// Expressions which appear more than once are only kept the first time.
 pub fn fold_and(expressions: Vec<Expression>) -> Expression {
-    expressions
+    without_duplicates(expressions)
         .into_iter()
         .fold(true_expr(), |acc, expression| Expression::And {
             left: Box::new(acc),
@@ -756,8 +898,9 @@ pub fn fold_and(expressions: Vec<Expression>) -> Expression {
 }
 
 /// Fold a vector of expressions into a single expression by ORing all expressions.
+/// Expressions which appear more than once are only kept the first time.
 pub fn fold_or(expressions: Vec<Expression>) -> Expression {
-    expressions
+    without_duplicates(expressions)
         .into_iter()
         .fold(false_expr(), |acc, expression| Expression::Or {
             left: Box::new(acc),
@@ -765,7 +908,94 @@ pub fn fold_or(expressions: Vec<Expression>) -> Expression {
         })
 }
 
+/// Drop the expressions which are equal to an earlier one. We only compare the handful of
+/// expressions combined in one place, so we do not mind comparing each to each.
+fn without_duplicates(expressions: Vec<Expression>) -> Vec<Expression> {
+    let mut unique: Vec<Expression> = Vec::with_capacity(expressions.len());
+    for expression in expressions {
+        if !unique.contains(&expression) {
+            unique.push(expression);
+        }
+    }
+    unique
+}
+
 /// The postgres operator for json extraction.
 pub fn json_extract_operator() -> BinaryOperator {
     BinaryOperator("->".to_string())
 }
+
+/// Encode a bytea expression as base64 text. `encode` breaks its output into lines of 76
+/// characters, so we remove the line breaks again:
+/// ```sql
+/// replace(encode(<expression>, 'base64'), chr(10), '')
+/// ```
+pub fn encode_base64(expression: Expression) -> Expression {
+    Expression::FunctionCall {
+        function: Function::Unknown("replace".to_string()),
+        args: vec![
+            Expression::FunctionCall {
+                function: Function::Unknown("encode".to_string()),
+                args: vec![
+                    expression,
+                    Expression::Value(Value::String("base64".to_string())),
+                ],
+            },
+            Expression::FunctionCall {
+                function: Function::Unknown("chr".to_string()),
+                args: vec![Expression::Value(Value::Int4(10))],
+            },
+            Expression::Value(Value::String(String::new())),
+        ],
+    }
+}
+
+/// Decode a base64 text expression into a bytea.
+pub fn decode_base64(expression: Expression) -> Expression {
+    Expression::FunctionCall {
+        function: Function::Unknown("decode".to_string()),
+        args: vec![
+            expression,
+            Expression::Value(Value::String("base64".to_string())),
+        ],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn column_is(name: &str, value: i32) -> Expression {
+        Expression::BinaryOperation {
+            left: Box::new(Expression::ColumnReference(ColumnReference::TableColumn {
+                table: TableReference::DBTable {
+                    schema: SchemaName("public".to_string()),
+                    table: TableName("table".to_string()),
+                },
+                name: ColumnName(name.to_string()),
+            })),
+            operator: BinaryOperator("=".to_string()),
+            right: Box::new(Expression::Value(Value::Int4(value))),
+        }
+    }
+
+    #[test]
+    fn duplicate_conjuncts_are_dropped() {
+        let expr = fold_and(vec![
+            column_is("a", 1),
+            column_is("b", 2),
+            column_is("a", 1),
+        ]);
+        assert_eq!(expr, fold_and(vec![column_is("a", 1), column_is("b", 2)]));
+    }
+
+    #[test]
+    fn duplicate_disjuncts_are_dropped() {
+        let expr = fold_or(vec![
+            column_is("a", 1),
+            column_is("a", 1),
+            column_is("a", 2),
+        ]);
+        assert_eq!(expr, fold_or(vec![column_is("a", 1), column_is("a", 2)]));
+    }
+}
@@ -2,7 +2,9 @@
 
 pub mod ast;
 pub mod convert;
+pub mod dialect;
 pub mod execution_plan;
 pub mod helpers;
 pub mod rewrites;
 pub mod string;
+pub mod summary;
@@ -1,6 +1,10 @@
 //! Simple constant expressions folding.
 //! We won't work very hard here because we assume PostgreSQL has
-//! similar rewrites.
+//! similar rewrites. We do remove some noise from the boolean expressions
+//! we generate though, to keep the SQL text small.
+//!
+//! Duplicate operands of `AND` and `OR` are dropped when the expressions are
+//! put together, see `sql::helpers::fold_and` and `sql::helpers::fold_or`.
 use crate::sql::ast::*;
 
 /// Normalize all expressions in select.
@@ -13,6 +17,14 @@ pub fn normalize_select(mut select: Select) -> Select {
         .map(normalize_cte)
         .collect();
 
+    // distinct
+    select.distinct = match select.distinct {
+        Distinct::All => Distinct::All,
+        Distinct::On(expressions) => {
+            Distinct::On(expressions.into_iter().map(normalize_expr).collect())
+        }
+    };
+
     // select list
     select.select_list = normalize_select_list(select.select_list);
 
@@ -109,19 +121,23 @@ pub fn normalize_order_by_element(mut element: OrderByElement) -> OrderByElement
     element
 }
 
+/// Normalize the expressions in raw SQL.
+fn normalize_raw_sqls(raw_sqls: Vec<RawSql>) -> Vec<RawSql> {
+    raw_sqls
+        .into_iter()
+        .map(|raw_sql| match raw_sql {
+            RawSql::RawText(string) => RawSql::RawText(string),
+            RawSql::Expression(expr) => RawSql::Expression(normalize_expr(expr)),
+            RawSql::TableReference(table_reference) => RawSql::TableReference(table_reference),
+        })
+        .collect()
+}
+
 /// Normalize the expression in a common table expression.
 pub fn normalize_cte(mut cte: CommonTableExpression) -> CommonTableExpression {
     cte.select = match cte.select {
         CTExpr::Select(select) => CTExpr::Select(normalize_select(select)),
-        CTExpr::RawSql(raw_sqls) => CTExpr::RawSql(
-            raw_sqls
-                .into_iter()
-                .map(|raw_sql| match raw_sql {
-                    RawSql::RawText(string) => RawSql::RawText(string),
-                    RawSql::Expression(expr) => RawSql::Expression(normalize_expr(expr)),
-                })
-                .collect(),
-        ),
+        CTExpr::RawSql(raw_sqls) => CTExpr::RawSql(normalize_raw_sqls(raw_sqls)),
         CTExpr::Delete(delete) => CTExpr::Delete(normalize_delete(delete)),
         CTExpr::Insert(insert) => CTExpr::Insert(normalize_insert(insert)),
         CTExpr::Update(update) => CTExpr::Update(normalize_update(update)),
@@ -193,7 +209,9 @@ fn normalize_insert_expression(expr: MutationValueExpression) -> MutationValueEx
 /// this rewrite to their Expressions.
 pub fn normalize_expr(expr: Expression) -> Expression {
     match expr {
-        // 'true' as a unit element for 'And'
+        // 'true' as a unit element for 'And', and 'false' as an absorbing element.
+        // The operands are normalized first, so a 'false' anywhere in a chain of 'And'
+        // reaches the top of the chain one level at a time.
         Expression::And { left, right } => {
             let oleft = match normalize_expr(*left) {
                 Expression::Value(Value::Bool(true)) => None,
@@ -204,6 +222,10 @@ pub fn normalize_expr(expr: Expression) -> Expression {
                 e => Some(e),
             };
             match (oleft, oright) {
+                (Some(Expression::Value(Value::Bool(false))), _)
+                | (_, Some(Expression::Value(Value::Bool(false)))) => {
+                    Expression::Value(Value::Bool(false))
+                }
                 (Some(left), Some(right)) => Expression::And {
                     left: Box::new(left),
                     right: Box::new(right),
@@ -214,7 +236,7 @@ pub fn normalize_expr(expr: Expression) -> Expression {
                 (None, None) => Expression::Value(Value::Bool(true)),
             }
         }
-        // 'false' as a unit element for 'Or'
+        // 'false' as a unit element for 'Or', and 'true' as an absorbing element.
         Expression::Or { left, right } => {
             // none means false, some means expression
             let oleft = match normalize_expr(*left) {
@@ -226,6 +248,10 @@ pub fn normalize_expr(expr: Expression) -> Expression {
                 e => Some(e),
             };
             match (oleft, oright) {
+                (Some(Expression::Value(Value::Bool(true))), _)
+                | (_, Some(Expression::Value(Value::Bool(true)))) => {
+                    Expression::Value(Value::Bool(true))
+                }
                 (Some(left), Some(right)) => Expression::Or {
                     left: Box::new(left),
                     right: Box::new(right),
@@ -257,6 +283,12 @@ pub fn normalize_expr(expr: Expression) -> Expression {
             operator,
             right: Box::new(normalize_expr(*right)),
         },
+        // Nothing is IN an empty list, which is not valid SQL either.
+        Expression::BinaryArrayOperation {
+            operator: BinaryArrayOperator::In,
+            right,
+            ..
+        } if right.is_empty() => Expression::Value(Value::Bool(false)),
         // Apply inner
         Expression::BinaryArrayOperation {
             left,
@@ -281,6 +313,16 @@ pub fn normalize_expr(expr: Expression) -> Expression {
             args: args.into_iter().map(normalize_expr).collect(),
         },
         // Apply inner
+        Expression::OrderedAggregateCall {
+            function,
+            args,
+            order_by,
+        } => Expression::OrderedAggregateCall {
+            function,
+            args: args.into_iter().map(normalize_expr).collect(),
+            order_by,
+        },
+        // Apply inner
         Expression::JsonBuildObject(object) => Expression::JsonBuildObject(
             object
                 .into_iter()
@@ -300,6 +342,10 @@ pub fn normalize_expr(expr: Expression) -> Expression {
             Expression::ArrayConstructor(array.into_iter().map(normalize_expr).collect())
         }
         // Apply inner
+        Expression::RowConstructor(fields) => {
+            Expression::RowConstructor(fields.into_iter().map(normalize_expr).collect())
+        }
+        // Apply inner
         Expression::CorrelatedSubSelect(select) => {
             Expression::CorrelatedSubSelect(Box::new(normalize_select(*select)))
         }
@@ -311,6 +357,8 @@ pub fn normalize_expr(expr: Expression) -> Expression {
             expression: Box::new(normalize_expr(*expression)),
             nested_field,
         },
+        // Apply inner
+        Expression::RawSql(raw_sqls) => Expression::RawSql(normalize_raw_sqls(raw_sqls)),
         // Nothing to do.
         Expression::RowToJson(_)
         | Expression::ColumnReference(_)
@@ -429,6 +477,29 @@ mod tests {
         assert_eq!(normalize_expr(expr.clone()), expr);
     }
 
+    #[test]
+    fn false_and_anything_is_false() {
+        let eq_expr = expr_eq(expr_seven(), expr_seven());
+        let expr = expr_and(eq_expr.clone(), expr_and(eq_expr, expr_false()));
+        assert_eq!(normalize_expr(expr), expr_false());
+    }
+
+    #[test]
+    fn true_or_anything_is_true() {
+        let eq_expr = expr_eq(expr_seven(), expr_seven());
+        let expr = expr_or(expr_or(expr_true(), eq_expr.clone()), eq_expr);
+        assert_eq!(normalize_expr(expr), expr_true());
+    }
+
+    #[test]
+    fn nesting_is_kept_without_duplicates() {
+        let eq_seven = expr_eq(expr_seven(), expr_seven());
+        let eq_true = expr_eq(expr_true(), expr_true());
+        let eq_false = expr_eq(expr_false(), expr_false());
+        let expr = expr_and(eq_seven, expr_and(eq_true, eq_false));
+        assert_eq!(normalize_expr(expr.clone()), expr);
+    }
+
     #[test]
     fn not_false_is_removed() {
         let eq_expr = expr_eq(expr_seven(), expr_seven());
@@ -438,4 +509,14 @@ mod tests {
         let expr = expr_and(left_side, right_side);
         assert_eq!(normalize_expr(expr), eq_expr);
     }
+
+    #[test]
+    fn in_empty_list_is_false() {
+        let expr = expr_not(Expression::BinaryArrayOperation {
+            left: Box::new(expr_seven()),
+            operator: BinaryArrayOperator::In,
+            right: vec![],
+        });
+        assert_eq!(normalize_expr(expr), expr_true());
+    }
 }
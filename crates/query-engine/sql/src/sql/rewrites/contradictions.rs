@@ -0,0 +1,257 @@
+//! Detect boolean expressions that can never be true.
+//!
+//! Predicates coming from different sources (for example, stacked permission
+//! filters and a user supplied filter) are combined with `AND`, and sometimes
+//! end up requiring a column to be equal to two different literals at once.
+//! We only look for a handful of obvious contradictions in a conjunction,
+//! and will err on the side of reporting nothing.
+use crate::sql::ast::*;
+
+/// A fact we learned about a column from one side of a conjunction.
+enum ColumnFact<'a> {
+    /// The column is equal to this literal.
+    EqualTo(&'a Value),
+    /// The column is null.
+    IsNull,
+    /// The column is not null.
+    IsNotNull,
+}
+
+/// Check whether an expression is guaranteed to evaluate to false (or null),
+/// regardless of the data it is evaluated against.
+pub fn is_contradiction(expr: &Expression) -> bool {
+    let mut conjuncts = vec![];
+    collect_conjuncts(expr, &mut conjuncts);
+
+    let mut facts: Vec<(&ColumnReference, ColumnFact)> = vec![];
+
+    for conjunct in conjuncts {
+        if let Expression::Value(Value::Bool(false) | Value::Null) = conjunct {
+            return true;
+        }
+
+        if let Some((column, fact)) = column_fact(conjunct) {
+            let conflicts = facts
+                .iter()
+                .filter(|(other_column, _)| *other_column == column)
+                .any(|(_, other_fact)| facts_conflict(&fact, other_fact));
+            if conflicts {
+                return true;
+            }
+            facts.push((column, fact));
+        }
+    }
+
+    false
+}
+
+/// Flatten nested `AND` expressions into a list of their operands.
+fn collect_conjuncts<'a>(expr: &'a Expression, conjuncts: &mut Vec<&'a Expression>) {
+    match expr {
+        Expression::And { left, right } => {
+            collect_conjuncts(left, conjuncts);
+            collect_conjuncts(right, conjuncts);
+        }
+        _ => conjuncts.push(expr),
+    }
+}
+
+/// Extract a fact about a single column from an expression, if it has a shape we understand.
+fn column_fact(expr: &Expression) -> Option<(&ColumnReference, ColumnFact)> {
+    match expr {
+        Expression::BinaryOperation {
+            left,
+            operator: BinaryOperator(operator),
+            right,
+        } if operator == "=" => match (left.as_ref(), right.as_ref()) {
+            (Expression::ColumnReference(column), literal)
+            | (literal, Expression::ColumnReference(column)) => {
+                comparable_literal(literal).map(|value| (column, ColumnFact::EqualTo(value)))
+            }
+            _ => None,
+        },
+        Expression::UnaryOperation {
+            expression,
+            operator: UnaryOperator::IsNull,
+        } => match expression.as_ref() {
+            Expression::ColumnReference(column) => Some((column, ColumnFact::IsNull)),
+            _ => None,
+        },
+        Expression::Not(expr) => match expr.as_ref() {
+            Expression::UnaryOperation {
+                expression,
+                operator: UnaryOperator::IsNull,
+            } => match expression.as_ref() {
+                Expression::ColumnReference(column) => Some((column, ColumnFact::IsNotNull)),
+                _ => None,
+            },
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Literals for which structural inequality implies that they denote different values.
+///
+/// Strings are deliberately excluded, as they are usually cast to a column type
+/// (`'1.0'::numeric` and `'1'::numeric` are equal), or compared using a collation.
+fn comparable_literal(expr: &Expression) -> Option<&Value> {
+    match expr {
+        Expression::Value(value @ (Value::Int4(_) | Value::Bool(_))) => Some(value),
+        Expression::Value(value @ Value::Float8(float)) if !float.is_nan() => Some(value),
+        _ => None,
+    }
+}
+
+/// Can both facts hold for the same column at the same time?
+fn facts_conflict(fact: &ColumnFact, other: &ColumnFact) -> bool {
+    match (fact, other) {
+        (ColumnFact::EqualTo(value), ColumnFact::EqualTo(other_value)) => {
+            !literals_equal(value, other_value)
+        }
+        (ColumnFact::IsNull, ColumnFact::IsNull)
+        | (
+            ColumnFact::EqualTo(_) | ColumnFact::IsNotNull,
+            ColumnFact::EqualTo(_) | ColumnFact::IsNotNull,
+        ) => false,
+        (ColumnFact::IsNull, _) | (_, ColumnFact::IsNull) => true,
+    }
+}
+
+/// Compare two literals, treating numbers of different representations as equal.
+#[allow(clippy::float_cmp)] // we want exact equality, the same as PostgreSQL would check
+fn literals_equal(value: &Value, other: &Value) -> bool {
+    match (value, other) {
+        (Value::Int4(int), Value::Float8(float)) | (Value::Float8(float), Value::Int4(int)) => {
+            f64::from(*int) == *float
+        }
+        _ => value == other,
+    }
+}
+
+/// Tests
+#[cfg(test)]
+mod tests {
+    use super::is_contradiction;
+    use crate::sql::ast::*;
+
+    fn column(name: &str) -> Expression {
+        Expression::ColumnReference(ColumnReference::TableColumn {
+            table: TableReference::DBTable {
+                schema: SchemaName("public".to_string()),
+                table: TableName("Album".to_string()),
+            },
+            name: ColumnName(name.to_string()),
+        })
+    }
+
+    fn expr_eq(left: Expression, right: Expression) -> Expression {
+        Expression::BinaryOperation {
+            left: Box::new(left),
+            operator: BinaryOperator("=".to_string()),
+            right: Box::new(right),
+        }
+    }
+
+    fn expr_and(left: Expression, right: Expression) -> Expression {
+        Expression::And {
+            left: Box::new(left),
+            right: Box::new(right),
+        }
+    }
+
+    fn expr_is_null(expr: Expression) -> Expression {
+        Expression::UnaryOperation {
+            expression: Box::new(expr),
+            operator: UnaryOperator::IsNull,
+        }
+    }
+
+    fn number(n: f64) -> Expression {
+        Expression::Value(Value::Float8(n))
+    }
+
+    #[test]
+    fn equal_to_two_different_numbers() {
+        let expr = expr_and(
+            expr_eq(column("AlbumId"), number(1.0)),
+            expr_eq(column("AlbumId"), number(2.0)),
+        );
+        assert!(is_contradiction(&expr));
+    }
+
+    #[test]
+    fn equal_to_the_same_number_twice() {
+        let expr = expr_and(
+            expr_eq(column("AlbumId"), number(1.0)),
+            expr_eq(number(1.0), column("AlbumId")),
+        );
+        assert!(!is_contradiction(&expr));
+    }
+
+    #[test]
+    fn different_columns_are_independent() {
+        let expr = expr_and(
+            expr_eq(column("AlbumId"), number(1.0)),
+            expr_eq(column("ArtistId"), number(2.0)),
+        );
+        assert!(!is_contradiction(&expr));
+    }
+
+    #[test]
+    fn nested_conjunctions_are_flattened() {
+        let expr = expr_and(
+            expr_and(
+                expr_eq(column("ArtistId"), number(2.0)),
+                expr_eq(column("AlbumId"), number(1.0)),
+            ),
+            expr_and(
+                expr_eq(column("Title"), number(3.0)),
+                expr_eq(column("AlbumId"), number(4.0)),
+            ),
+        );
+        assert!(is_contradiction(&expr));
+    }
+
+    #[test]
+    fn null_and_equal_to_a_number() {
+        let expr = expr_and(
+            expr_is_null(column("AlbumId")),
+            expr_eq(column("AlbumId"), number(1.0)),
+        );
+        assert!(is_contradiction(&expr));
+    }
+
+    #[test]
+    fn null_and_not_null() {
+        let expr = expr_and(
+            Expression::Not(Box::new(expr_is_null(column("AlbumId")))),
+            expr_is_null(column("AlbumId")),
+        );
+        assert!(is_contradiction(&expr));
+    }
+
+    #[test]
+    fn disjunctions_are_not_inspected() {
+        let expr = Expression::Or {
+            left: Box::new(expr_eq(column("AlbumId"), number(1.0))),
+            right: Box::new(expr_eq(column("AlbumId"), number(2.0))),
+        };
+        assert!(!is_contradiction(&expr));
+    }
+
+    #[test]
+    fn strings_are_not_compared() {
+        let expr = expr_and(
+            expr_eq(
+                column("Title"),
+                Expression::Value(Value::String("a".to_string())),
+            ),
+            expr_eq(
+                column("Title"),
+                Expression::Value(Value::String("b".to_string())),
+            ),
+        );
+        assert!(!is_contradiction(&expr));
+    }
+}
@@ -1,2 +1,3 @@
 //! Various rewrites and optimizations over the SQL AST.
 pub mod constant_folding;
+pub mod contradictions;
@@ -1,10 +1,14 @@
 //! Type definitions of a low-level SQL string representation.
 
+use super::dialect::{Dialect, SqlDialect};
+
 /// A low-level builder for SQL.
 #[derive(Debug, PartialEq, Eq)]
 pub struct SQL {
     pub sql: String,
     pub params: Vec<Param>,
+    /// The dialect of the database we generate SQL for.
+    pub dialect: SqlDialect,
 }
 
 impl Default for SQL {
@@ -22,6 +26,18 @@ pub enum Param {
     Value(serde_json::Value),
     /// A variable name to look up in the `variables` field in a `QueryRequest`.
     Variable(String),
+    /// A value bound as the Postgres type it represents rather than as text or json.
+    Typed(TypedValue),
+}
+
+/// A parameter value which is bound with a type of its own, so that the database receives it
+/// exactly as it was written.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TypedValue {
+    /// An `int8`
+    Int8(i64),
+    /// A `numeric`, in its textual form, which is bound as a `numeric`
+    Numeric(String),
 }
 
 /// A statement.
@@ -30,12 +46,23 @@ pub struct Statement(pub SQL);
 
 impl SQL {
     pub fn new() -> SQL {
+        SQL::with_dialect(SqlDialect::default())
+    }
+
+    /// Build SQL for a specific database.
+    pub fn with_dialect(dialect: SqlDialect) -> SQL {
         SQL {
             sql: String::new(),
             params: vec![],
+            dialect,
         }
     }
 
+    /// The details of the dialect we generate SQL for.
+    pub fn dialect(&self) -> &'static dyn Dialect {
+        self.dialect.dialect()
+    }
+
     /// Append regular SQL syntax like a keyword (like `SELECT`), punctuation, etc.
     pub fn append_syntax(&mut self, sql: &str) {
         self.sql.push_str(sql);
@@ -44,10 +71,7 @@ impl SQL {
     /// Append a SQL identifier like a column or a table name, which will be
     /// inserted surrounded by quotes.
     pub fn append_identifier(&mut self, sql: &str) {
-        // todo: sanitize
-        self.sql.push('"');
-        self.sql.push_str(sql);
-        self.sql.push('"');
+        self.dialect().quote_identifier(sql, &mut self.sql);
     }
 
     /// Append a parameter to a parameterized query. Will be represented as $1, $2, and so on,
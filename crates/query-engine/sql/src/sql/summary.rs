@@ -0,0 +1,342 @@
+//! Summarize the shape of a translated query for explain responses.
+//!
+//! The summary describes what the SQL we generated is made of — the tables and native queries
+//! it reads, how its subqueries are joined, and how many conditions it checks — so that users
+//! can tell why a request produced the SQL it did without reading all of it.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
+use crate::sql::ast::*;
+
+/// A summary of a translated query.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Summary {
+    /// The tables read or written, as `schema.table`.
+    pub tables: BTreeSet<String>,
+    /// The names of the native queries used.
+    pub native_queries: BTreeSet<String>,
+    /// How many joins of each kind there are.
+    pub joins: BTreeMap<&'static str, usize>,
+    /// How many correlated subqueries there are, such as those selecting relationships when
+    /// lateral joins are not used.
+    pub correlated_subqueries: usize,
+    /// How many conditions are checked in WHERE clauses, not counting `AND`, `OR` and `NOT`.
+    pub predicates: usize,
+}
+
+/// Summarize a query.
+pub fn summarize(select: &Select) -> Summary {
+    let mut summary = Summary::default();
+    summary.select(select);
+    summary
+}
+
+impl Summary {
+    fn select(&mut self, select: &Select) {
+        for cte in &select.with.common_table_expressions {
+            self.common_table_expression(cte);
+        }
+        for (_, expression) in select_list_expressions(&select.select_list) {
+            self.expression(expression);
+        }
+        if let Some(from) = &select.from {
+            self.from(from);
+        }
+        for join in &select.joins {
+            let (kind, select) = match join {
+                Join::LeftOuterJoinLateral(LeftOuterJoinLateral { select, .. }) => {
+                    ("LEFT OUTER JOIN LATERAL", select)
+                }
+                Join::InnerJoinLateral(InnerJoinLateral { select, .. }) => {
+                    ("INNER JOIN LATERAL", select)
+                }
+                Join::FullOuterJoinLateral(FullOuterJoinLateral { select, .. }) => {
+                    ("FULL OUTER JOIN LATERAL", select)
+                }
+                Join::CrossJoinLateral(CrossJoin { select, .. }) => ("CROSS JOIN LATERAL", select),
+                Join::CrossJoin(CrossJoin { select, .. }) => ("CROSS JOIN", select),
+            };
+            *self.joins.entry(kind).or_default() += 1;
+            self.select(select);
+        }
+        self.where_(&select.where_);
+        for element in &select.order_by.elements {
+            self.expression(&element.target);
+        }
+    }
+
+    fn common_table_expression(&mut self, cte: &CommonTableExpression) {
+        match &cte.select {
+            CTExpr::Select(select) => self.select(select),
+            CTExpr::RawSql(_) => {
+                let name = cte.alias.name.as_str();
+                self.native_queries.insert(
+                    name.strip_prefix("NATIVE_QUERY_")
+                        .unwrap_or(name)
+                        .to_string(),
+                );
+            }
+            CTExpr::Delete(delete) => {
+                self.from(&delete.from);
+                self.where_(&delete.where_);
+            }
+            CTExpr::Insert(insert) => {
+                self.table(&insert.schema, &insert.table);
+                if let InsertFrom::Select(select) = &insert.from {
+                    self.select(select);
+                }
+            }
+            CTExpr::Update(update) => {
+                self.table(&update.schema, &update.table);
+                self.where_(&update.where_);
+            }
+        }
+    }
+
+    fn from(&mut self, from: &From) {
+        match from {
+            From::Table { reference, .. } | From::TableOnly { reference, .. } => {
+                if let TableReference::DBTable { schema, table } = reference {
+                    self.table(schema, table);
+                }
+            }
+            From::Select { select, .. } => self.select(select),
+            From::JsonbToRecordset { expression, .. }
+            | From::JsonbArrayElements { expression, .. }
+            | From::JsonbEachText { expression, .. }
+            | From::Unnest { expression, .. } => self.expression(expression),
+            From::GenerateSeries { .. } => {}
+        }
+    }
+
+    fn table(&mut self, SchemaName(schema): &SchemaName, TableName(table): &TableName) {
+        self.tables.insert(format!("{schema}.{table}"));
+    }
+
+    fn where_(&mut self, Where(expression): &Where) {
+        self.predicates += count_predicates(expression);
+        self.expression(expression);
+    }
+
+    /// Look for subqueries in an expression.
+    fn expression(&mut self, expression: &Expression) {
+        match expression {
+            Expression::And { left, right }
+            | Expression::Or { left, right }
+            | Expression::BinaryOperation { left, right, .. } => {
+                self.expression(left);
+                self.expression(right);
+            }
+            Expression::BinaryArrayOperation { left, right, .. } => {
+                self.expression(left);
+                for expression in right {
+                    self.expression(expression);
+                }
+            }
+            Expression::Not(expression)
+            | Expression::UnaryOperation { expression, .. }
+            | Expression::Cast { expression, .. }
+            | Expression::NestedFieldSelect { expression, .. } => self.expression(expression),
+            Expression::FunctionCall { args, .. }
+            | Expression::OrderedAggregateCall { args, .. }
+            | Expression::ArrayConstructor(args)
+            | Expression::RowConstructor(args) => {
+                for expression in args {
+                    self.expression(expression);
+                }
+            }
+            Expression::JsonBuildObject(fields) => {
+                for expression in fields.values() {
+                    self.expression(expression);
+                }
+            }
+            Expression::Exists { select } => self.select(select),
+            Expression::CorrelatedSubSelect(select) => {
+                self.correlated_subqueries += 1;
+                self.select(select);
+            }
+            Expression::RawSql(parts) => {
+                for part in parts {
+                    if let RawSql::Expression(expression) = part {
+                        self.expression(expression);
+                    }
+                }
+            }
+            Expression::RowToJson(_)
+            | Expression::ColumnReference(_)
+            | Expression::Value(_)
+            | Expression::Count(_) => {}
+        }
+    }
+}
+
+/// The expressions of a select list.
+fn select_list_expressions(select_list: &SelectList) -> Vec<&(ColumnAlias, Expression)> {
+    match select_list {
+        SelectList::SelectList(expressions) => expressions.iter().collect(),
+        SelectList::SelectListComposite(left, right) => {
+            let mut expressions = select_list_expressions(left);
+            expressions.extend(select_list_expressions(right));
+            expressions
+        }
+        SelectList::SelectStar
+        | SelectList::SelectStarFrom(_)
+        | SelectList::SelectStarComposite(_)
+        | SelectList::Select1 => vec![],
+    }
+}
+
+/// Count the conditions of a boolean expression, looking through `AND`, `OR` and `NOT`.
+/// A literal `true`, as in a WHERE clause without a filter, is not a condition.
+fn count_predicates(expression: &Expression) -> usize {
+    match expression {
+        Expression::And { left, right } | Expression::Or { left, right } => {
+            count_predicates(left) + count_predicates(right)
+        }
+        Expression::Not(expression) => count_predicates(expression),
+        Expression::Value(Value::Bool(true)) => 0,
+        _ => 1,
+    }
+}
+
+impl fmt::Display for Summary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fn list<T: fmt::Display>(items: impl IntoIterator<Item = T>) -> String {
+            let items = items
+                .into_iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>();
+            if items.is_empty() {
+                "none".to_string()
+            } else {
+                items.join(", ")
+            }
+        }
+
+        writeln!(f, "Tables: {}", list(&self.tables))?;
+        writeln!(f, "Native queries: {}", list(&self.native_queries))?;
+        writeln!(
+            f,
+            "Joins: {}",
+            list(
+                self.joins
+                    .iter()
+                    .map(|(kind, count)| format!("{count} {kind}"))
+            )
+        )?;
+        writeln!(f, "Correlated subqueries: {}", self.correlated_subqueries)?;
+        write!(f, "Predicates: {}", self.predicates)
+    }
+}
+
+/// Tests
+#[cfg(test)]
+mod tests {
+    use super::summarize;
+    use crate::sql::ast::*;
+    use crate::sql::helpers::*;
+
+    fn table(name: &str, index: u64) -> (TableReference, TableAlias) {
+        (
+            TableReference::DBTable {
+                schema: SchemaName("public".to_string()),
+                table: TableName(name.to_string()),
+            },
+            TableAlias {
+                unique_index: index,
+                name: name.to_string(),
+            },
+        )
+    }
+
+    fn column_equals(alias: &TableAlias, name: &str, value: Expression) -> Expression {
+        Expression::BinaryOperation {
+            left: Box::new(Expression::ColumnReference(ColumnReference::TableColumn {
+                table: TableReference::AliasedTable(alias.clone()),
+                name: ColumnName(name.to_string()),
+            })),
+            operator: BinaryOperator("=".to_string()),
+            right: Box::new(value),
+        }
+    }
+
+    #[test]
+    fn summarizes_tables_joins_and_predicates() {
+        let (album, album_alias) = table("Album", 0);
+        let (artist, artist_alias) = table("Artist", 1);
+
+        let mut relationship = simple_select(vec![]);
+        relationship.from = Some(From::Table {
+            reference: artist,
+            alias: artist_alias.clone(),
+        });
+        relationship.where_ = Where(column_equals(
+            &artist_alias,
+            "ArtistId",
+            Expression::Value(Value::Int4(1)),
+        ));
+
+        let mut select = simple_select(vec![]);
+        select.from = Some(From::Table {
+            reference: album,
+            alias: album_alias.clone(),
+        });
+        select.joins = vec![Join::LeftOuterJoinLateral(LeftOuterJoinLateral {
+            select: Box::new(relationship),
+            alias: TableAlias {
+                unique_index: 2,
+                name: "RELATIONSHIP_Artist".to_string(),
+            },
+        })];
+        select.where_ = Where(Expression::Or {
+            left: Box::new(column_equals(
+                &album_alias,
+                "Title",
+                Expression::Value(Value::String("Facelift".to_string())),
+            )),
+            right: Box::new(Expression::Not(Box::new(column_equals(
+                &album_alias,
+                "AlbumId",
+                Expression::Value(Value::Int4(2)),
+            )))),
+        });
+
+        let summary = summarize(&select);
+
+        assert_eq!(
+            summary.to_string(),
+            "Tables: public.Album, public.Artist\n\
+             Native queries: none\n\
+             Joins: 1 LEFT OUTER JOIN LATERAL\n\
+             Correlated subqueries: 0\n\
+             Predicates: 3"
+        );
+    }
+
+    #[test]
+    fn summarizes_native_queries() {
+        let mut select = simple_select(vec![]);
+        select.with = With {
+            common_table_expressions: vec![CommonTableExpression {
+                alias: TableAlias {
+                    unique_index: 0,
+                    name: "NATIVE_QUERY_artist_below_id".to_string(),
+                },
+                column_names: None,
+                select: CTExpr::RawSql(vec![RawSql::RawText(
+                    "SELECT * FROM public.\"Artist\"".to_string(),
+                )]),
+            }],
+        };
+
+        let summary = summarize(&select);
+
+        assert_eq!(
+            summary.native_queries.into_iter().collect::<Vec<_>>(),
+            vec!["artist_below_id".to_string()]
+        );
+        assert!(summary.tables.is_empty());
+        assert_eq!(summary.predicates, 0);
+    }
+}
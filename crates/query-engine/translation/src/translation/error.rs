@@ -20,6 +20,7 @@ pub enum Error {
         r#type: database::Type,
     },
     RelationshipArgumentWasOverriden(models::ArgumentName),
+    ColumnArgumentsToTable(models::CollectionName),
     EmptyPathForOrderByAggregate,
     MissingAggregateForArrayRelationOrdering,
     TypeMismatch(serde_json::Value, models::ScalarTypeName),
@@ -28,6 +29,19 @@ pub enum Error {
     UnableToDeserializeNumberAsF64(serde_json::Number),
     ColumnIsGenerated(models::FieldName),
     ColumnIsIdentityAlways(models::FieldName),
+    ColumnNotSelectable(models::FieldName, models::CollectionName),
+    ColumnNotFilterable(models::FieldName, models::CollectionName),
+    ColumnNotSortable(models::FieldName, models::CollectionName),
+    ColumnNotWritable {
+        collection: models::CollectionName,
+        column_name: models::FieldName,
+        operation: String,
+    },
+    AuditColumnNotWritable {
+        collection: models::CollectionName,
+        column_name: models::FieldName,
+        operation: String,
+    },
     MissingColumnInMutation {
         collection: models::CollectionName,
         column_name: models::FieldName,
@@ -54,6 +68,39 @@ pub enum Error {
         field_name: models::FieldName,
         actual_type: Type,
     },
+    RelationshipInJsonField {
+        field_name: models::FieldName,
+    },
+    UnknownInputField {
+        path: String,
+        type_name: models::TypeName,
+    },
+    InputTypeMismatch {
+        path: String,
+        expected: Type,
+    },
+    QueryLimitExceeded {
+        kind: LimitKind,
+        limit: u32,
+    },
+}
+
+/// The limits on the size of a query. See `query::limits::Limits`.
+#[derive(Debug, Clone, Copy)]
+pub enum LimitKind {
+    RelationshipDepth,
+    Joins,
+    PredicateNodes,
+}
+
+impl std::fmt::Display for LimitKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            LimitKind::RelationshipDepth => write!(f, "relationships nested more than"),
+            LimitKind::Joins => write!(f, "more relationships than"),
+            LimitKind::PredicateNodes => write!(f, "more predicate nodes than"),
+        }
+    }
 }
 
 /// Capabilities we don't currently support.
@@ -105,6 +152,9 @@ impl std::fmt::Display for Error {
             Error::RelationshipArgumentWasOverriden(key) => {
                 write!(f, "The relationship argument '{key}' was defined as part of the relationship, but was overriden.")
             }
+            Error::ColumnArgumentsToTable(collection) => {
+                write!(f, "The collection '{collection}' is not a native query, so it cannot take arguments from the columns of a relationship.")
+            }
             Error::EmptyPathForOrderByAggregate => {
                 write!(f, "No path elements supplied for order by aggregate.")
             }
@@ -132,6 +182,34 @@ impl std::fmt::Display for Error {
             Error::ColumnIsIdentityAlways(column) => {
                 write!(f, "Unable to insert into the identity column '{column}'.")
             }
+            Error::ColumnNotSelectable(column_name, collection_name) => write!(
+                f,
+                "Column '{column_name}' in collection '{collection_name}' cannot be selected."
+            ),
+            Error::ColumnNotFilterable(column_name, collection_name) => write!(
+                f,
+                "Column '{column_name}' in collection '{collection_name}' cannot be filtered by."
+            ),
+            Error::ColumnNotSortable(column_name, collection_name) => write!(
+                f,
+                "Column '{column_name}' in collection '{collection_name}' cannot be ordered by."
+            ),
+            Error::ColumnNotWritable {
+                collection,
+                column_name,
+                operation,
+            } => write!(
+                f,
+                "Unable to {operation} the column '{column_name}' in collection '{collection}'."
+            ),
+            Error::AuditColumnNotWritable {
+                collection,
+                column_name,
+                operation,
+            } => write!(
+                f,
+                "The audit column '{column_name}' in collection '{collection}' is set on {operation}, but the column cannot be written to."
+            ),
             Error::MissingColumnInMutation {
                 column_name,
                 collection: procedure_name,
@@ -199,6 +277,46 @@ impl std::fmt::Display for Error {
                     "Nested field '{field_name}' not of array type. Actual type: {actual_type:?}"
                 )
             }
+            Error::RelationshipInJsonField { field_name } => {
+                write!(
+                    f,
+                    "Relationships cannot be selected inside the json field '{field_name}'."
+                )
+            }
+            Error::UnknownInputField { path, type_name } => {
+                write!(
+                    f,
+                    "Unknown field at '{path}': type '{type_name}' has no such field."
+                )
+            }
+            Error::InputTypeMismatch { path, expected } => {
+                write!(
+                    f,
+                    "Value at '{path}' is not of the expected type {expected:?}."
+                )
+            }
+            Error::QueryLimitExceeded { kind, limit } => {
+                write!(f, "The query has {kind} the allowed {limit}.")
+            }
+        }
+    }
+}
+
+impl Error {
+    /// The feature which the request needed and the connector does not support, if that is why
+    /// it failed. This is used to label metrics, so it never contains anything from the request.
+    pub fn unsupported_feature(&self) -> Option<&str> {
+        match self {
+            Error::CapabilityNotSupported(UnsupportedCapabilities::FieldArguments) => {
+                Some("field arguments")
+            }
+            // the descriptions of features which are not implemented are fixed in the code.
+            Error::NotImplementedYet(feature) => Some(feature),
+            Error::NestedArrayTypesNotSupported | Error::NestedArraysNotSupported { .. } => {
+                Some("nested arrays")
+            }
+            Error::OperatorNotFound { .. } => Some("comparison operators"),
+            _ => None,
         }
     }
 }
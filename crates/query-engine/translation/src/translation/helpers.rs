@@ -4,7 +4,12 @@ use std::collections::BTreeMap;
 
 use ndc_models as models;
 
-use super::error::Error;
+use super::error::{Error, LimitKind};
+use super::options::Options;
+use super::query::aggregates::CountEstimation;
+use super::query::distribution::DistributedQueryRouting;
+use super::query::limits::{self, Limits};
+use super::query::policies::CollectionPolicy;
 use query_engine_metadata::metadata;
 use query_engine_sql::sql;
 
@@ -13,9 +18,8 @@ use query_engine_sql::sql;
 pub struct Env<'request> {
     pub(crate) metadata: &'request metadata::Metadata,
     relationships: BTreeMap<models::RelationshipName, models::Relationship>,
-    pub(crate) mutations_version: Option<metadata::mutations::MutationsVersion>,
-    pub(crate) mutations_prefix: Option<String>,
     variables_table: Option<sql::ast::TableReference>,
+    pub(crate) options: &'request Options,
 }
 
 #[derive(Debug)]
@@ -23,6 +27,20 @@ pub struct Env<'request> {
 pub struct State {
     native_queries: NativeQueries,
     global_table_index: TableAliasIndex,
+    limits: Limits,
+    /// How deeply nested the relationship we are translating is.
+    relationship_depth: u32,
+    /// How many relationships we have joined so far.
+    join_count: u32,
+    /// How many predicate nodes we have translated so far.
+    predicate_node_count: u32,
+    /// Whether the predicate of the root collection can never hold, so that the query
+    /// returns no rows.
+    root_predicate_never_holds: bool,
+    /// Hints for the query planner about the collections we scan.
+    planner_hints: Vec<String>,
+    /// The longest statement timeout of the collections we scan, if any of them have one.
+    statement_timeout_ms: Option<u64>,
 }
 
 #[derive(Debug)]
@@ -205,30 +223,100 @@ impl<'request> Env<'request> {
         F: FnOnce(Env) -> R,
     {
         let temp_metadata = metadata::Metadata::empty();
+        let temp_options = Options {
+            use_lateral_joins: true,
+            ..Options::default()
+        };
         let temp_env = Env {
             metadata: &temp_metadata,
             relationships: BTreeMap::new(),
-            mutations_version: None,
-            mutations_prefix: None,
             variables_table: None,
+            options: &temp_options,
         };
         f(temp_env)
     }
 
-    /// Create a new Env by supplying the metadata and relationships.
+    /// Create a new Env by supplying the metadata, relationships, and the options of the
+    /// translation.
     pub fn new(
         metadata: &'request metadata::Metadata,
         relationships: BTreeMap<models::RelationshipName, models::Relationship>,
-        mutations_version: Option<metadata::mutations::MutationsVersion>,
-        mutations_prefix: Option<String>,
         variables_table: Option<sql::ast::TableReference>,
+        options: &'request Options,
     ) -> Self {
         Env {
             metadata,
             relationships,
-            mutations_version,
-            mutations_prefix,
             variables_table,
+            options,
+        }
+    }
+
+    /// The database we generate SQL for.
+    pub fn dialect(&self) -> sql::dialect::SqlDialect {
+        self.options.dialect
+    }
+
+    /// Should relationships be joined using `LEFT OUTER JOIN LATERAL`, rather than selected
+    /// using correlated subqueries?
+    pub fn use_lateral_joins(&self) -> bool {
+        self.options.use_lateral_joins && self.dialect().dialect().supports_lateral_joins()
+    }
+
+    /// Should the limit of ordered queries also keep the rows which tie with the last one,
+    /// using `FETCH FIRST n ROWS WITH TIES`?
+    pub fn limit_with_ties(&self) -> bool {
+        self.options.limit_with_ties && self.dialect().dialect().supports_fetch_with_ties()
+    }
+
+    /// Should comparisons of boolean columns with `true` or `false` be kept as they are, rather
+    /// than shortened to the column itself or its negation?
+    pub fn strict_boolean_comparisons(&self) -> bool {
+        self.options.strict_boolean_comparisons
+    }
+
+    /// How queries over tables which Citus distributes across nodes are planned.
+    pub fn distributed_query_routing(&self) -> DistributedQueryRouting {
+        self.options.distributed_query_routing
+    }
+
+    /// Whether star counts over large tables are answered with estimates, and from how many rows.
+    pub fn count_estimation(&self) -> Option<CountEstimation> {
+        self.options.count_estimation
+    }
+
+    /// The hint for the query planner configured for a collection, if any.
+    pub fn lookup_planner_hint(&self, collection_name: &models::CollectionName) -> Option<&str> {
+        self.options
+            .planner_hints
+            .get(collection_name)
+            .map(String::as_str)
+    }
+
+    /// The policy restricting the rows of a collection, if any.
+    pub fn lookup_collection_policy(&self, source: &TableSource) -> Option<&CollectionPolicy> {
+        match source {
+            TableSource::Collection(collection_name) => {
+                self.options.collection_policies.0.get(collection_name)
+            }
+            TableSource::NestedField { .. } => None,
+        }
+    }
+
+    /// The computed relationship from a table to a collection, if one is configured.
+    pub fn lookup_computed_relationship(
+        &self,
+        source: &TableSource,
+        target_collection: &models::CollectionName,
+    ) -> Option<&metadata::ComputedRelationship> {
+        match source {
+            TableSource::Collection(collection_name) => self
+                .metadata
+                .tables
+                .0
+                .get(collection_name.as_str())
+                .and_then(|table| table.computed_relationships.get(target_collection)),
+            TableSource::NestedField { .. } => None,
         }
     }
 
@@ -399,20 +487,44 @@ impl<'request> Env<'request> {
     }
 
     /// Looks up the binary comparison operator's PostgreSQL name and arguments' type in the metadata.
+    ///
+    /// Operators on scalar types are introspected, while arrays of scalar types share the
+    /// operators PostgreSQL defines for all arrays. For the latter, the argument type is the
+    /// element type of the array.
     pub fn lookup_comparison_operator(
         &self,
-        scalar_type: &models::ScalarTypeName,
+        typ: &metadata::Type,
         name: &models::ComparisonOperatorName,
-    ) -> Result<&'request metadata::ComparisonOperator, Error> {
-        self.metadata
-            .scalar_types
-            .0
-            .get(scalar_type)
-            .and_then(|t| t.comparison_operators.get(name))
-            .ok_or(Error::OperatorNotFound {
-                operator_name: name.clone(),
-                type_name: scalar_type.clone(),
-            })
+    ) -> Result<metadata::ComparisonOperator, Error> {
+        match typ {
+            metadata::Type::ScalarType(scalar_type) => self
+                .metadata
+                .scalar_types
+                .0
+                .get(scalar_type)
+                .and_then(|t| t.comparison_operators.get(name))
+                .cloned()
+                .ok_or(Error::OperatorNotFound {
+                    operator_name: name.clone(),
+                    type_name: scalar_type.clone(),
+                }),
+            metadata::Type::ArrayType(element_type) => match element_type.as_ref() {
+                metadata::Type::ScalarType(scalar_type) => {
+                    metadata::array_comparison_operator(scalar_type, name).ok_or(
+                        Error::OperatorNotFound {
+                            operator_name: name.clone(),
+                            type_name: scalar_type.clone(),
+                        },
+                    )
+                }
+                _ => Err(Error::NonScalarTypeUsedInOperator {
+                    r#type: typ.clone(),
+                }),
+            },
+            metadata::Type::CompositeType(_) => Err(Error::NonScalarTypeUsedInOperator {
+                r#type: typ.clone(),
+            }),
+        }
     }
 
     /// Lookup type representation of a type.
@@ -449,20 +561,48 @@ impl<'request> Env<'request> {
     }
 }
 
-impl FieldsInfo<'_> {
+impl<'env> FieldsInfo<'env> {
+    /// Lookup a computed field of a table.
+    pub fn lookup_computed_field(
+        &self,
+        field_name: &models::FieldName,
+    ) -> Option<&'env metadata::ComputedField> {
+        match self {
+            FieldsInfo::Table { info, .. } => info.computed_fields.get(field_name),
+            FieldsInfo::NativeQuery { .. } | FieldsInfo::CompositeType { .. } => None,
+        }
+    }
+
+    /// Lookup the type of a column or a computed field in a collection.
+    pub fn lookup_field_type(
+        &self,
+        field_name: &models::FieldName,
+    ) -> Result<metadata::Type, Error> {
+        match self.lookup_computed_field(field_name) {
+            Some(computed_field) => Ok(computed_field.r#type.clone()),
+            None => Ok(self.lookup_column(field_name)?.r#type),
+        }
+    }
+
     /// Lookup a column in a collection.
     pub fn lookup_column(&self, column_name: &models::FieldName) -> Result<ColumnInfo, Error> {
         match self {
-            FieldsInfo::Table { name, info } => info
-                .columns
-                .get(column_name.as_str())
-                .map(|column_info| ColumnInfo {
+            FieldsInfo::Table { name, info } => {
+                let column_info = info.columns.get(column_name.as_str()).ok_or_else(|| {
+                    Error::ColumnNotFoundInCollection(column_name.clone(), (*name).clone())
+                })?;
+                // Columns we may not select cannot be referenced in filters or orderings either.
+                if !column_info.selectable {
+                    return Err(Error::ColumnNotSelectable(
+                        column_name.clone(),
+                        (*name).clone(),
+                    ));
+                }
+                Ok(ColumnInfo {
                     name: sql::ast::ColumnName(column_info.name.clone()),
                     r#type: column_info.r#type.clone(),
                 })
-                .ok_or_else(|| {
-                    Error::ColumnNotFoundInCollection(column_name.clone(), (*name).clone())
-                }),
+            }
             FieldsInfo::NativeQuery { name, info } => info
                 .columns
                 .get(column_name)
@@ -485,6 +625,46 @@ impl FieldsInfo<'_> {
                 }),
         }
     }
+
+    /// Lookup a column in a collection to filter by.
+    pub fn lookup_filterable_column(
+        &self,
+        column_name: &models::FieldName,
+    ) -> Result<ColumnInfo, Error> {
+        if let FieldsInfo::Table { name, info } = self {
+            if info
+                .columns
+                .get(column_name.as_str())
+                .is_some_and(|column_info| !column_info.filterable)
+            {
+                return Err(Error::ColumnNotFilterable(
+                    column_name.clone(),
+                    (*name).clone(),
+                ));
+            }
+        }
+        self.lookup_column(column_name)
+    }
+
+    /// Lookup a column in a collection to order by.
+    pub fn lookup_sortable_column(
+        &self,
+        column_name: &models::FieldName,
+    ) -> Result<ColumnInfo, Error> {
+        if let FieldsInfo::Table { name, info } = self {
+            if info
+                .columns
+                .get(column_name.as_str())
+                .is_some_and(|column_info| !column_info.sortable)
+            {
+                return Err(Error::ColumnNotSortable(
+                    column_name.clone(),
+                    (*name).clone(),
+                ));
+            }
+        }
+        self.lookup_column(column_name)
+    }
 }
 
 impl CollectionInfo<'_> {
@@ -495,6 +675,11 @@ impl CollectionInfo<'_> {
 }
 
 impl CompositeTypeInfo<'_> {
+    /// Lookup a field of a composite type.
+    pub fn lookup_column(&self, column_name: &models::FieldName) -> Result<ColumnInfo, Error> {
+        FieldsInfo::from(self).lookup_column(column_name)
+    }
+
     pub fn type_name(&self) -> &str {
         match self {
             CompositeTypeInfo::Table { name, .. } => name.as_str(),
@@ -525,6 +710,48 @@ impl CompositeTypeInfo<'_> {
                 .collect::<Vec<_>>(),
         }
     }
+
+    /// Whether a field may be null, if the field exists.
+    ///
+    /// Tables only constrain their own rows, not values of their row type, so the fields of
+    /// those are always nullable.
+    pub fn field_nullable(&self, field_name: &models::FieldName) -> Option<&metadata::Nullable> {
+        match self {
+            CompositeTypeInfo::CompositeType { name: _, info } => {
+                info.fields.get(field_name).map(|field| &field.nullable)
+            }
+            CompositeTypeInfo::Table { name: _, info } => info
+                .columns
+                .get(field_name.as_str())
+                .map(|_| &metadata::Nullable::Nullable),
+        }
+    }
+
+    /// The fields in the order the type declares them, along with their types, if the positions
+    /// of all of them are known.
+    pub fn fields_in_order(&self) -> Option<Vec<(models::FieldName, &metadata::Type)>> {
+        match self {
+            CompositeTypeInfo::CompositeType { name: _, info } => {
+                let mut fields = info
+                    .fields
+                    .iter()
+                    .map(|(name, field)| {
+                        field
+                            .position
+                            .map(|position| (position, name.clone(), &field.r#type))
+                    })
+                    .collect::<Option<Vec<_>>>()?;
+                fields.sort_by_key(|(position, _, _)| *position);
+                Some(
+                    fields
+                        .into_iter()
+                        .map(|(_, name, r#type)| (name, r#type))
+                        .collect(),
+                )
+            }
+            CompositeTypeInfo::Table { .. } => None,
+        }
+    }
 }
 
 impl Default for State {
@@ -532,6 +759,13 @@ impl Default for State {
         State {
             native_queries: NativeQueries::new(),
             global_table_index: TableAliasIndex(0),
+            limits: Limits::default(),
+            relationship_depth: 0,
+            join_count: 0,
+            predicate_node_count: 0,
+            root_predicate_never_holds: false,
+            planner_hints: Vec::new(),
+            statement_timeout_ms: None,
         }
     }
 }
@@ -542,11 +776,86 @@ impl State {
         State::default()
     }
 
+    /// Build a new state which enforces the given limits on the size of the query.
+    pub fn with_limits(limits: Limits) -> State {
+        State {
+            limits,
+            ..State::default()
+        }
+    }
+
+    /// Enter a relationship selection, checking how deeply it is nested and how many
+    /// relationships we have joined. Must be paired with `exit_relationship`.
+    pub fn enter_relationship(&mut self) -> Result<(), Error> {
+        self.relationship_depth += 1;
+        check_limit(
+            LimitKind::RelationshipDepth,
+            self.relationship_depth,
+            self.limits.max_relationship_depth,
+        )?;
+        self.count_join()
+    }
+
+    /// Count a collection joined to the query, whether to select, filter or order by it, towards
+    /// the limit for the whole query.
+    pub fn count_join(&mut self) -> Result<(), Error> {
+        self.join_count += 1;
+        check_limit(LimitKind::Joins, self.join_count, self.limits.max_joins)
+    }
+
+    /// Leave a relationship selection entered with `enter_relationship`.
+    pub fn exit_relationship(&mut self) {
+        self.relationship_depth -= 1;
+    }
+
+    /// Count the nodes of a predicate towards the limit for the whole query.
+    pub fn count_predicate(&mut self, predicate: &models::Expression) -> Result<(), Error> {
+        self.predicate_node_count += limits::count_predicate_nodes(predicate);
+        check_limit(
+            LimitKind::PredicateNodes,
+            self.predicate_node_count,
+            self.limits.max_predicate_nodes,
+        )
+    }
+
+    /// Record that the predicate of the root collection can never hold.
+    pub fn set_root_predicate_never_holds(&mut self) {
+        self.root_predicate_never_holds = true;
+    }
+
+    /// Whether the predicate of the root collection can never hold, so that the query returns
+    /// no rows.
+    pub fn root_predicate_never_holds(&self) -> bool {
+        self.root_predicate_never_holds
+    }
+
+    /// Attach a hint for the query planner to the query.
+    pub fn add_planner_hint(&mut self, planner_hint: String) {
+        self.planner_hints.push(planner_hint);
+    }
+
+    /// Take the hints for the query planner collected so far.
+    pub fn take_planner_hints(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.planner_hints)
+    }
+
+    /// Record the statement timeout of a collection we scan. The query runs with the longest one,
+    /// so that every collection it reads has at least the budget it was given.
+    pub fn add_statement_timeout(&mut self, statement_timeout_ms: u64) {
+        self.statement_timeout_ms = self.statement_timeout_ms.max(Some(statement_timeout_ms));
+    }
+
+    /// The longest statement timeout of the collections scanned so far, if any.
+    pub fn statement_timeout_ms(&self) -> Option<u64> {
+        self.statement_timeout_ms
+    }
+
     /// When variables are passed to the query, create an alias for the variables table and
-    /// a from clause.
+    /// a from clause reading from where the variables will be passed.
     pub fn make_variables_table(
         &mut self,
         variables: &Option<Vec<BTreeMap<models::VariableName, serde_json::Value>>>,
+        variables_source: sql::execution_plan::VariablesSource,
     ) -> Option<(sql::ast::From, sql::ast::TableReference)> {
         if variables.is_none() {
             None
@@ -554,10 +863,15 @@ impl State {
             let variables_table_alias = self.make_table_alias("%variables_table".to_string());
             let table_reference =
                 sql::ast::TableReference::AliasedTable(variables_table_alias.clone());
-            Some((
-                sql::helpers::from_variables(variables_table_alias),
-                table_reference,
-            ))
+            let from = match variables_source {
+                sql::execution_plan::VariablesSource::Parameter => {
+                    sql::helpers::from_variables(variables_table_alias)
+                }
+                sql::execution_plan::VariablesSource::TemporaryTable => {
+                    sql::helpers::from_variables_table(variables_table_alias)
+                }
+            };
+            Some((from, table_reference))
         }
     }
 
@@ -677,3 +991,10 @@ pub fn wrap_in_field_path(
         }
     })
 }
+
+fn check_limit(kind: LimitKind, value: u32, limit: Option<u32>) -> Result<(), Error> {
+    match limit {
+        Some(limit) if value > limit => Err(Error::QueryLimitExceeded { kind, limit }),
+        _ => Ok(()),
+    }
+}
@@ -3,4 +3,6 @@
 pub mod error;
 pub mod helpers;
 pub mod mutation;
+pub mod options;
+pub mod plan;
 pub mod query;
@@ -0,0 +1,92 @@
+//! Set the audit columns of a table, such as `updated_at`, in the generated mutations.
+
+use crate::translation::error::Error;
+use crate::translation::helpers::Env;
+use crate::translation::query::values;
+use ndc_models as models;
+use query_engine_metadata::metadata::database;
+use query_engine_sql::sql;
+use std::collections::BTreeMap;
+
+/// Which kind of mutation is setting the columns.
+#[derive(Debug, Clone, Copy)]
+pub enum Operation {
+    Insert,
+    Update,
+}
+
+/// Set the audit columns the operation sets, overriding any values the user gave for them.
+pub fn set_audit_columns(
+    env: &Env,
+    operation: Operation,
+    table_columns: &BTreeMap<models::FieldName, database::ColumnInfo>,
+    audit_columns: &BTreeMap<models::FieldName, database::AuditColumn>,
+    collection: &models::CollectionName,
+    columns_to_values: &mut BTreeMap<sql::ast::ColumnName, sql::ast::MutationValueExpression>,
+) -> Result<(), Error> {
+    for (name, audit_column) in audit_columns {
+        let applies = match operation {
+            Operation::Insert => audit_column.on.on_insert(),
+            Operation::Update => audit_column.on.on_update(),
+        };
+        if !applies {
+            continue;
+        }
+        let column_info = table_columns
+            .get(name)
+            .ok_or_else(|| Error::ColumnNotFoundInCollection(name.clone(), collection.clone()))?;
+        // The request does not set the column, so say why it is being written to.
+        let (writable, operation_name) = match operation {
+            Operation::Insert => (column_info.insertable, "insert"),
+            Operation::Update => (column_info.updatable, "update"),
+        };
+        if !writable {
+            return Err(Error::AuditColumnNotWritable {
+                collection: collection.clone(),
+                column_name: name.clone(),
+                operation: operation_name.to_string(),
+            });
+        }
+        columns_to_values.insert(
+            sql::ast::ColumnName(column_info.name.clone()),
+            sql::ast::MutationValueExpression::Expression(translate_audit_value(
+                env,
+                &audit_column.value,
+                &column_info.r#type,
+            )?),
+        );
+    }
+    Ok(())
+}
+
+/// `now()`, or `nullif(current_setting('<setting>', true), '')` cast to the type of the column.
+/// Settings which are not set give `NULL` rather than an error, and so do settings which were
+/// set and then reset, which give `''`.
+fn translate_audit_value(
+    env: &Env,
+    value: &database::AuditValue,
+    r#type: &database::Type,
+) -> Result<sql::ast::Expression, Error> {
+    Ok(match value {
+        database::AuditValue::Now => sql::ast::Expression::FunctionCall {
+            function: sql::ast::Function::Unknown("now".to_string()),
+            args: vec![],
+        },
+        database::AuditValue::Setting(setting) => sql::ast::Expression::Cast {
+            expression: Box::new(sql::ast::Expression::FunctionCall {
+                function: sql::ast::Function::Unknown("nullif".to_string()),
+                args: vec![
+                    sql::ast::Expression::FunctionCall {
+                        function: sql::ast::Function::Unknown("current_setting".to_string()),
+                        args: vec![
+                            sql::ast::Expression::Value(sql::ast::Value::String(setting.clone())),
+                            sql::helpers::true_expr(),
+                        ],
+                    },
+                    sql::ast::Expression::Value(sql::ast::Value::String(String::new())),
+                ],
+            }),
+            r#type: values::type_to_ast_scalar_type(env, r#type)?,
+        },
+    })
+}
@@ -15,6 +15,28 @@ pub fn check_columns(
     check_missing: &CheckMissingColumns,
 ) -> Result<(), Error> {
     for (name, column) in table_columns {
+        // columns the connecting role may not write to must not be set, nor are they required.
+        let writable = match check_missing {
+            CheckMissingColumns::Yes => column.insertable,
+            CheckMissingColumns::No => column.updatable,
+        };
+        if !writable {
+            let value = user_columns.get(&sql::ast::ColumnName(column.name.clone()));
+            match value {
+                Some(sql::ast::MutationValueExpression::Expression(_)) => {
+                    Err(Error::ColumnNotWritable {
+                        collection: collection.to_owned(),
+                        column_name: name.clone(),
+                        operation: match check_missing {
+                            CheckMissingColumns::No => "update".to_string(),
+                            CheckMissingColumns::Yes => "insert into".to_string(),
+                        },
+                    })
+                }
+                Some(sql::ast::MutationValueExpression::Default) | None => Ok(()),
+            }?;
+            continue;
+        }
         match column {
             // nullable, default, and identity by default columns can be inserted into or omitted.
             database::ColumnInfo {
@@ -1,7 +1,9 @@
 //! Given introspection data, generate a set of standard mutation procedures
 
 use ndc_models as models;
+use query_engine_metadata::metadata::database;
 use query_engine_metadata::metadata::mutations;
+use query_engine_sql::sql;
 use std::collections::BTreeMap;
 
 use crate::translation::helpers::Env;
@@ -15,15 +17,73 @@ pub enum Mutation {
     V2(v2::Mutation),
 }
 
+impl Mutation {
+    /// The description of the generated procedure.
+    pub fn description(&self) -> &str {
+        match self {
+            Mutation::V1(v1::Mutation::DeleteMutation(
+                v1::delete::DeleteMutation::DeleteByKey { description, .. },
+            )) => description,
+            Mutation::V1(v1::Mutation::InsertMutation(insert)) => &insert.description,
+            Mutation::V2(v2::Mutation::DeleteMutation(
+                v2::delete::DeleteMutation::DeleteByKey(delete_by_key),
+            )) => &delete_by_key.description,
+            Mutation::V2(v2::Mutation::InsertMutation(insert)) => &insert.description,
+            Mutation::V2(v2::Mutation::UpdateMutation(
+                v2::update::UpdateMutation::UpdateByKey(update_by_key),
+            )) => &update_by_key.description,
+        }
+    }
+
+    /// The collection the generated procedure mutates.
+    pub fn collection_name(&self) -> &models::CollectionName {
+        match self {
+            Mutation::V1(v1::Mutation::DeleteMutation(
+                v1::delete::DeleteMutation::DeleteByKey {
+                    collection_name, ..
+                },
+            )) => collection_name,
+            Mutation::V1(v1::Mutation::InsertMutation(insert)) => &insert.collection_name,
+            Mutation::V2(v2::Mutation::DeleteMutation(
+                v2::delete::DeleteMutation::DeleteByKey(delete_by_key),
+            )) => &delete_by_key.collection_name,
+            Mutation::V2(v2::Mutation::InsertMutation(insert)) => &insert.collection_name,
+            Mutation::V2(v2::Mutation::UpdateMutation(
+                v2::update::UpdateMutation::UpdateByKey(update_by_key),
+            )) => &update_by_key.collection_name,
+        }
+    }
+
+    /// The schema and table the generated procedure inserts into, and the columns of the
+    /// table, if it is an insert.
+    pub fn insert_target(
+        &self,
+    ) -> Option<(
+        &sql::ast::SchemaName,
+        &sql::ast::TableName,
+        &BTreeMap<models::FieldName, database::ColumnInfo>,
+    )> {
+        match self {
+            Mutation::V1(v1::Mutation::InsertMutation(insert)) => {
+                Some((&insert.schema_name, &insert.table_name, &insert.columns))
+            }
+            Mutation::V2(v2::Mutation::InsertMutation(insert)) => {
+                Some((&insert.schema_name, &insert.table_name, &insert.columns))
+            }
+            _ => None,
+        }
+    }
+}
+
 /// Given our introspection data, work out all the mutations we can generate
 pub fn generate(env: &Env) -> BTreeMap<models::ProcedureName, Mutation> {
-    match env.mutations_version {
+    match env.options.mutations_version {
         Some(mutations::MutationsVersion::V1) => v1::generate(env)
             .into_iter()
             .map(|(name, mutation)| (name, Mutation::V1(mutation)))
             .collect(),
         Some(mutations::MutationsVersion::V2) => {
-            v2::generate(&env.metadata.tables, env.mutations_prefix.as_ref())
+            v2::generate(&env.metadata.tables, env.options.mutations_prefix.as_ref())
                 .into_iter()
                 .map(|(name, mutation)| (name, Mutation::V2(mutation)))
                 .collect()
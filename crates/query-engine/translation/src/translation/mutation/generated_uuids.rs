@@ -0,0 +1,31 @@
+//! Generate random UUIDs for the columns the connector generates them for, in the generated
+//! inserts.
+
+use ndc_models as models;
+use query_engine_metadata::metadata::database;
+use query_engine_sql::sql;
+use std::collections::BTreeMap;
+
+/// The columns whose UUIDs the connector generates when an insert omits them: those which
+/// generate UUIDs but have no default to do so. Columns whose default generates them are left to
+/// the database.
+pub fn generated_uuid_columns(
+    columns: &BTreeMap<models::FieldName, database::ColumnInfo>,
+) -> impl Iterator<Item = sql::ast::ColumnName> + '_ {
+    columns
+        .values()
+        .filter(|column_info| {
+            column_info.generates_uuid
+                && column_info.has_default == database::HasDefault::NoDefault
+                && column_info.insertable
+        })
+        .map(|column_info| sql::ast::ColumnName(column_info.name.clone()))
+}
+
+/// `gen_random_uuid()`
+pub fn generate_uuid() -> sql::ast::MutationValueExpression {
+    sql::ast::MutationValueExpression::Expression(sql::ast::Expression::FunctionCall {
+        function: sql::ast::Function::Unknown("gen_random_uuid".to_string()),
+        args: vec![],
+    })
+}
@@ -1,5 +1,9 @@
+pub mod audit_columns;
 pub mod check_columns;
 pub mod generate;
+pub mod generated_uuids;
+pub mod returning_aggregates;
+pub mod sequences;
 pub mod translate;
 pub mod v1;
 pub mod v2;
@@ -0,0 +1,175 @@
+//! Aggregates over the rows a mutation returns, which procedures offer as fields of their
+//! response next to `affected_rows` and `returning`.
+
+use std::collections::BTreeMap;
+
+use ndc_models as models;
+use query_engine_metadata::metadata;
+
+use crate::translation::helpers::Env;
+
+/// An aggregate function applied to a column of the rows a mutation returns.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReturningAggregate {
+    pub column: models::FieldName,
+    pub function: models::AggregateFunctionName,
+    pub return_type: models::TypeName,
+}
+
+/// The aggregates over the rows returned by the mutations of a table, if they are enabled.
+pub fn of_collection(
+    env: &Env,
+    collection_name: &models::CollectionName,
+) -> BTreeMap<models::FieldName, ReturningAggregate> {
+    if !env.options.mutations_returning_aggregates {
+        return BTreeMap::new();
+    }
+    env.metadata
+        .tables
+        .0
+        .get(collection_name)
+        .map(|table| {
+            returning_aggregates(
+                env.metadata,
+                table
+                    .columns
+                    .iter()
+                    .filter(|(_, column)| column.selectable)
+                    .map(|(name, column)| (name, &column.r#type)),
+            )
+        })
+        .unwrap_or_default()
+}
+
+/// The aggregates over the rows returned by a native mutation, if they are enabled.
+pub fn of_native_mutation(
+    env: &Env,
+    procedure_name: &models::ProcedureName,
+) -> BTreeMap<models::FieldName, ReturningAggregate> {
+    if !env.options.mutations_returning_aggregates {
+        return BTreeMap::new();
+    }
+    env.metadata
+        .native_operations
+        .mutations
+        .0
+        .get(procedure_name)
+        .map(|native_mutation| {
+            returning_aggregates(
+                env.metadata,
+                native_mutation
+                    .columns
+                    .iter()
+                    .map(|(name, column)| (name, &column.r#type)),
+            )
+        })
+        .unwrap_or_default()
+}
+
+/// Every aggregate function of the type of every scalar column, by the name of the field of the
+/// procedure response which selects it: `<column>_<function>`. Names which are taken, by another
+/// field of the response or another aggregate, are skipped.
+fn returning_aggregates<'a>(
+    metadata: &metadata::Metadata,
+    columns: impl Iterator<Item = (&'a models::FieldName, &'a metadata::Type)>,
+) -> BTreeMap<models::FieldName, ReturningAggregate> {
+    let mut aggregates = BTreeMap::new();
+    for (column, r#type) in columns {
+        let metadata::Type::ScalarType(scalar_type_name) = r#type else {
+            continue;
+        };
+        let Some(scalar_type) = metadata.scalar_types.0.get(scalar_type_name) else {
+            continue;
+        };
+        for (function, definition) in &scalar_type.aggregate_functions {
+            let field_name: models::FieldName = format!("{column}_{function}").into();
+            if field_name.as_str() == "affected_rows" || field_name.as_str() == "returning" {
+                continue;
+            }
+            aggregates
+                .entry(field_name)
+                .or_insert_with(|| ReturningAggregate {
+                    column: column.clone(),
+                    function: function.clone(),
+                    return_type: definition.return_type.clone(),
+                });
+        }
+    }
+    aggregates
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use ndc_models as models;
+    use query_engine_metadata::metadata;
+
+    use super::{returning_aggregates, ReturningAggregate};
+
+    fn scalar_type(aggregate_functions: &[(&str, &str)]) -> metadata::ScalarType {
+        metadata::ScalarType {
+            type_name: String::new(),
+            schema_name: None,
+            description: None,
+            aggregate_functions: aggregate_functions
+                .iter()
+                .map(|(function, return_type)| {
+                    (
+                        (*function).into(),
+                        metadata::AggregateFunction {
+                            return_type: (*return_type).into(),
+                        },
+                    )
+                })
+                .collect(),
+            comparison_operators: BTreeMap::new(),
+            type_representation: None,
+            cast_as: None,
+        }
+    }
+
+    #[test]
+    fn scalar_columns_are_aggregated_with_the_functions_of_their_type() {
+        let mut metadata = metadata::Metadata::empty();
+        metadata.scalar_types.0.insert(
+            "int4".into(),
+            scalar_type(&[("max", "int4"), ("sum", "int8")]),
+        );
+        let columns: [(models::FieldName, metadata::Type); 3] = [
+            ("balance".into(), metadata::Type::ScalarType("int4".into())),
+            (
+                "balances".into(),
+                metadata::Type::ArrayType(Box::new(metadata::Type::ScalarType("int4".into()))),
+            ),
+            ("name".into(), metadata::Type::ScalarType("text".into())),
+        ];
+
+        let aggregates = returning_aggregates(
+            &metadata,
+            columns.iter().map(|(name, r#type)| (name, r#type)),
+        );
+
+        assert_eq!(
+            aggregates,
+            BTreeMap::from([
+                (
+                    "balance_max".into(),
+                    ReturningAggregate {
+                        column: "balance".into(),
+                        function: "max".into(),
+                        return_type: "int4".into(),
+                    }
+                ),
+                (
+                    "balance_sum".into(),
+                    ReturningAggregate {
+                        column: "balance".into(),
+                        function: "sum".into(),
+                        return_type: "int8".into(),
+                    }
+                ),
+            ])
+        );
+    }
+}
@@ -0,0 +1,94 @@
+//! Procedures which advance sequences, so that ids can be allocated before inserting the rows
+//! which use them.
+
+use std::collections::BTreeMap;
+
+use ndc_models as models;
+use query_engine_metadata::metadata;
+use query_engine_sql::sql;
+
+use crate::translation::helpers::Env;
+
+/// The description of the procedure advancing a sequence, followed by that of the sequence, if
+/// it has one.
+pub fn description(sequence: &metadata::SequenceInfo) -> String {
+    let description = format!(
+        "Advance the sequence '{}.{}' and return its new value",
+        sequence.schema_name, sequence.sequence_name
+    );
+    match &sequence.description {
+        None => description,
+        Some(sequence_description) => format!("{description}. {sequence_description}"),
+    }
+}
+
+/// The scalar type of the values of sequences.
+pub const SEQUENCE_VALUE_TYPE: &str = "int8";
+
+/// The procedures of the sequences in the metadata, `nextval_<name>`.
+pub fn procedures(env: &Env) -> BTreeMap<models::ProcedureName, metadata::SequenceInfo> {
+    env.metadata
+        .sequences
+        .0
+        .iter()
+        .map(|(name, sequence)| (metadata::Sequences::procedure_name(name), sequence.clone()))
+        .collect()
+}
+
+/// Translate a procedure advancing a sequence into a mutation of the form:
+///
+/// ```sql
+/// SELECT json_build_object('type', 'procedure', 'result', nextval('"schema"."sequence"'::regclass))
+/// ```
+pub fn translate(
+    env: &Env,
+    procedure_name: &models::ProcedureName,
+    sequence: &metadata::SequenceInfo,
+) -> sql::execution_plan::Mutation {
+    let dialect = env.dialect().dialect();
+    let mut qualified_name = String::new();
+    dialect.quote_identifier(&sequence.schema_name, &mut qualified_name);
+    qualified_name.push('.');
+    dialect.quote_identifier(&sequence.sequence_name, &mut qualified_name);
+    let value = sql::ast::Expression::FunctionCall {
+        function: sql::ast::Function::Unknown("nextval".to_string()),
+        args: vec![sql::ast::Expression::Cast {
+            expression: Box::new(sql::ast::Expression::Value(sql::ast::Value::String(
+                qualified_name,
+            ))),
+            r#type: sql::ast::ScalarType::BaseType(sql::ast::ScalarTypeName::Unqualified(
+                "regclass".to_string(),
+            )),
+        }],
+    };
+    // values of sequences are represented like other values of their type.
+    let value = match crate::translation::query::fields::type_representation_cast(
+        env,
+        &SEQUENCE_VALUE_TYPE.into(),
+    ) {
+        None => value,
+        Some(cast_type) => sql::ast::Expression::Cast {
+            expression: Box::new(value),
+            r#type: sql::ast::ScalarType::BaseType(cast_type),
+        },
+    };
+
+    let select = sql::helpers::simple_select(vec![(
+        sql::helpers::make_column_alias(sql::helpers::RESULTS_FIELD.to_string()),
+        sql::ast::Expression::JsonBuildObject(BTreeMap::from([
+            (
+                "type".to_string(),
+                sql::ast::Expression::Value(sql::ast::Value::String("procedure".to_string())),
+            ),
+            ("result".to_string(), value),
+        ])),
+    )]);
+
+    sql::execution_plan::Mutation {
+        root_field: procedure_name.to_string(),
+        query: select,
+        dialect: env.dialect(),
+        annotations: sql::execution_plan::Annotations::new(),
+        savepoint: None,
+    }
+}
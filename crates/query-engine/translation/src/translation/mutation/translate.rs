@@ -1,33 +1,29 @@
 //! Translate an incoming `MutationRequest`.
 
-use indexmap::{indexmap, IndexMap};
+use indexmap::IndexMap;
 use std::collections::BTreeMap;
 
 use ndc_models as models;
 
 use crate::translation::error::Error;
 use crate::translation::helpers::{Env, State};
+use crate::translation::options::Options;
 use query_engine_metadata::metadata;
 use query_engine_sql::sql;
 
+use super::returning_aggregates;
+use super::sequences;
 use super::v1;
 use super::v2;
 
 /// Translate the incoming MutationOperation to an ExecutionPlan (SQL) to be run against the database.
 pub fn translate(
     metadata: &metadata::Metadata,
+    options: &Options,
     operation: models::MutationOperation,
     collection_relationships: BTreeMap<models::RelationshipName, models::Relationship>,
-    mutations_version: Option<metadata::mutations::MutationsVersion>,
-    mutations_prefix: Option<String>,
 ) -> Result<sql::execution_plan::Mutation, Error> {
-    let env = Env::new(
-        metadata,
-        collection_relationships,
-        mutations_version,
-        mutations_prefix,
-        None,
-    );
+    let env = Env::new(metadata, collection_relationships, None, options);
 
     match operation {
         models::MutationOperation::Procedure {
@@ -40,10 +36,12 @@ pub fn translate(
                 Ok(native_query) => {
                     translate_native_query(&env, &name, fields, arguments, native_query)
                 }
-                Err(_) =>
-                // and failing that, try a generated mutation
-                {
-                    translate_mutation(&env, &name, fields, &arguments)
+                Err(_) => {
+                    // then the procedure of a sequence, and failing that, a generated mutation
+                    match sequences::procedures(&env).get(&name) {
+                        Some(sequence) => Ok(sequences::translate(&env, &name, sequence)),
+                        None => translate_mutation(&env, &name, fields, &arguments),
+                    }
                 }
             }
         }
@@ -63,7 +61,13 @@ fn translate_mutation(
     // insert the procedure as a CTE and get a reference to it.
     let cte_table_alias = state.make_table_alias("generated_mutation".to_string());
 
-    let (aggregates, (returning_alias, fields)) = parse_procedure_fields(fields)?;
+    let (return_collection, cte_expr, check_constraint_alias, row_exists) =
+        translate_mutation_expr(env, &mut state, procedure_name, arguments)?;
+
+    let (aggregates, (returning_alias, fields)) = parse_procedure_fields(
+        fields,
+        &returning_aggregates::of_collection(env, &return_collection),
+    )?;
 
     // define the query selecting from the CTE,
     // selecting the affected_rows as aggregate and the fields.
@@ -76,8 +80,19 @@ fn translate_mutation(
         predicate: None,
     };
 
-    let (return_collection, cte_expr, check_constraint_alias) =
-        translate_mutation_expr(env, &mut state, procedure_name, arguments)?;
+    // Counting the affected rows does not need their values, so leave them out of the
+    // `RETURNING` clause when nothing else is selected from them.
+    let returns_rows = query.fields.is_some()
+        || query
+            .aggregates
+            .iter()
+            .flatten()
+            .any(|(_, aggregate)| !matches!(aggregate, models::Aggregate::StarCount {}));
+    let cte_expr = if returns_rows {
+        cte_expr
+    } else {
+        without_returned_rows(cte_expr)
+    };
 
     let select_set = crate::translation::query::root::translate_query(
         env,
@@ -143,15 +158,34 @@ fn translate_mutation(
         query
     };
 
+    // When the mutation only changes a row whose values match the expected ones, tell whether
+    // the row was left alone because they did not match, rather than because it does not exist:
+    //
+    // > NOT EXISTS (SELECT 1 FROM <generated_mutation> AS <alias>) AND <row exists>
+    let version_mismatch = row_exists.map(|row_exists| sql::ast::Expression::And {
+        left: Box::new(sql::ast::Expression::Not(Box::new(
+            sql::helpers::where_exists_select(
+                sql::ast::From::Table {
+                    reference: sql::ast::TableReference::AliasedTable(cte_table_alias.clone()),
+                    alias: state.make_table_alias(procedure_name.to_string()),
+                },
+                vec![],
+                sql::ast::Where(sql::helpers::true_expr()),
+            ),
+        ))),
+        right: Box::new(row_exists),
+    });
+
     // We return a select with two subselects that each return one row.
     // - The first column returns the results of the mutation.
     // - The second column returns whether all constraints passed or failed.
+    // - A third column, if any, returns whether the row did not match the expected values.
     //
     // > SELECT (<query subselect>), (<constraint check subselect>)
     //
     // In the execution stage, we will use the constraint check to determine whether to
     // rollback the transaction or not.
-    let mut select = sql::helpers::simple_select(vec![
+    let mut select_list = vec![
         (
             sql::helpers::make_column_alias(sql::helpers::RESULTS_FIELD.to_string()),
             sql::ast::Expression::CorrelatedSubSelect(Box::new(query_select)),
@@ -160,7 +194,14 @@ fn translate_mutation(
             sql::helpers::make_column_alias(sql::helpers::CHECK_CONSTRAINT_FIELD.to_string()),
             sql::ast::Expression::CorrelatedSubSelect(Box::new(constraint_select)),
         ),
-    ]);
+    ];
+    if let Some(version_mismatch) = version_mismatch {
+        select_list.push((
+            sql::helpers::make_column_alias(sql::helpers::VERSION_MISMATCH_FIELD.to_string()),
+            version_mismatch,
+        ));
+    }
+    let mut select = sql::helpers::simple_select(select_list);
 
     let common_table_expression = sql::ast::CommonTableExpression {
         alias: cte_table_alias,
@@ -179,9 +220,40 @@ fn translate_mutation(
     Ok(sql::execution_plan::Mutation {
         root_field: procedure_name.to_string(),
         query: select,
+        dialect: env.dialect(),
+        annotations: sql::execution_plan::Annotations::new(),
+        savepoint: None,
     })
 }
 
+/// Leave the rows out of the `RETURNING *, <columns>` clause of a mutation, keeping the other
+/// columns it returns, such as whether the check constraint passed.
+fn without_returned_rows(cte_expr: sql::ast::CTExpr) -> sql::ast::CTExpr {
+    let without_rows = |returning: sql::ast::Returning| match returning.0 {
+        sql::ast::SelectList::SelectListComposite(rows, columns)
+            if *rows == sql::ast::SelectList::SelectStar =>
+        {
+            sql::ast::Returning(*columns)
+        }
+        select_list => sql::ast::Returning(select_list),
+    };
+    match cte_expr {
+        sql::ast::CTExpr::Insert(mut insert) => {
+            insert.returning = without_rows(insert.returning);
+            sql::ast::CTExpr::Insert(insert)
+        }
+        sql::ast::CTExpr::Update(mut update) => {
+            update.returning = without_rows(update.returning);
+            sql::ast::CTExpr::Update(update)
+        }
+        sql::ast::CTExpr::Delete(mut delete) => {
+            delete.returning = without_rows(delete.returning);
+            sql::ast::CTExpr::Delete(delete)
+        }
+        sql::ast::CTExpr::Select(_) | sql::ast::CTExpr::RawSql(_) => cte_expr,
+    }
+}
+
 /// Translate a Native Query mutation into an ExecutionPlan (SQL) to be run against the database.
 fn translate_native_query(
     env: &Env,
@@ -206,7 +278,10 @@ fn translate_native_query(
         arguments,
     );
 
-    let (aggregates, (returning_alias, fields)) = parse_procedure_fields(fields)?;
+    let (aggregates, (returning_alias, fields)) = parse_procedure_fields(
+        fields,
+        &returning_aggregates::of_native_mutation(env, procedure_name),
+    )?;
 
     // define the query selecting from the native query,
     // selecting the affected_rows as aggregate and the fields.
@@ -258,6 +333,9 @@ fn translate_native_query(
     Ok(sql::execution_plan::Mutation {
         root_field: procedure_name.to_string(),
         query: select,
+        dialect: env.dialect(),
+        annotations: sql::execution_plan::Annotations::new(),
+        savepoint: None,
     })
 }
 
@@ -265,10 +343,13 @@ fn translate_native_query(
 ///     * affected_rows, the integer number of rows affected by the operation
 ///     * returning, the nested array object of rows returned
 ///
-/// The user must supply at least one of these two structures, and otherwise we'll throw an error.
+/// The user must supply at least one of these two structures, or one of the aggregates over the
+/// returned rows, and otherwise we'll throw an error.
+/// A request which gives no fields at all only gets the number of affected rows.
 #[allow(clippy::type_complexity)]
 pub fn parse_procedure_fields(
     fields: Option<models::NestedField>,
+    returning_aggregates: &BTreeMap<models::FieldName, returning_aggregates::ReturningAggregate>,
 ) -> Result<
     (
         Option<IndexMap<models::FieldName, models::Aggregate>>, // Contains "affected_rows"
@@ -281,7 +362,7 @@ pub fn parse_procedure_fields(
 > {
     match fields {
         Some(models::NestedField::Object(models::NestedObject { fields })) => {
-            let mut affected_rows = None;
+            let mut aggregates = IndexMap::new();
             let mut returning = ("returning".into(), None);
 
             for (alias, field) in fields {
@@ -291,7 +372,22 @@ pub fn parse_procedure_fields(
                         fields: _,
                         arguments,
                     } if column == "affected_rows".into() && arguments.is_empty() => {
-                        affected_rows = Some(indexmap!(alias => models::Aggregate::StarCount {}));
+                        aggregates.insert(alias, models::Aggregate::StarCount {});
+                    }
+                    models::Field::Column {
+                        column,
+                        fields: None,
+                        arguments,
+                    } if arguments.is_empty() && returning_aggregates.contains_key(&column) => {
+                        let aggregate = &returning_aggregates[&column];
+                        aggregates.insert(
+                            alias,
+                            models::Aggregate::SingleColumn {
+                                column: aggregate.column.clone(),
+                                field_path: None,
+                                function: aggregate.function.clone(),
+                            },
+                        );
                     }
                     models::Field::Column {
                         column,
@@ -328,17 +424,23 @@ pub fn parse_procedure_fields(
                 }
             }
 
-            if affected_rows.is_none() && returning.1.is_none() {
+            if aggregates.is_empty() && returning.1.is_none() {
                 Err(Error::NoProcedureResultFieldsRequested)?;
             }
 
-            Ok((affected_rows, returning))
+            Ok(((!aggregates.is_empty()).then_some(aggregates), returning))
         }
 
         Some(models::NestedField::Array(_)) => {
             Err(Error::NotImplementedYet("nested array fields".to_string()))
         }
-        None => Err(Error::NoProcedureResultFieldsRequested)?,
+        None => Ok((
+            Some(IndexMap::from([(
+                "affected_rows".into(),
+                models::Aggregate::StarCount {},
+            )])),
+            ("returning".into(), None),
+        )),
     }
 }
 
@@ -353,13 +455,18 @@ fn translate_mutation_expr(
         models::CollectionName,
         sql::ast::CTExpr,
         sql::ast::ColumnAlias,
+        Option<sql::ast::Expression>,
     ),
     Error,
 > {
-    match env.mutations_version {
+    match env.options.mutations_version {
         None => todo!(),
         Some(metadata::mutations::MutationsVersion::V1) => {
-            v1::translate(env, state, procedure_name, arguments)
+            v1::translate(env, state, procedure_name, arguments).map(
+                |(collection_name, cte_expr, check_constraint_alias)| {
+                    (collection_name, cte_expr, check_constraint_alias, None)
+                },
+            )
         }
         Some(metadata::mutations::MutationsVersion::V2) => {
             v2::translate(env, state, procedure_name, arguments)
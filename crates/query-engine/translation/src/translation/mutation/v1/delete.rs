@@ -1,6 +1,8 @@
 //! Auto-generate delete mutations and translate them into sql ast.
 
 use crate::translation::error::Error;
+use crate::translation::helpers::{TableSource, TableSourceAndReference};
+use crate::translation::query::root;
 use crate::translation::query::values;
 use ndc_models as models;
 use query_engine_metadata::metadata;
@@ -10,7 +12,8 @@ use std::collections::BTreeMap;
 
 /// A representation of an auto-generated delete mutation.
 ///
-/// This can get us `DELETE FROM <table> WHERE column = <column_name_arg>`.
+/// This can get us `DELETE FROM <table> WHERE column = <column_name_arg>`, or, for tables which
+/// are deleted softly, `UPDATE <table> SET deleted_at = now() WHERE column = <column_name_arg>`.
 #[derive(Debug, Clone)]
 pub enum DeleteMutation {
     DeleteByKey {
@@ -19,6 +22,7 @@ pub enum DeleteMutation {
         schema_name: sql::ast::SchemaName,
         table_name: sql::ast::TableName,
         by_column: metadata::database::ColumnInfo,
+        soft_delete: Option<metadata::SoftDelete>,
     },
 }
 
@@ -63,6 +67,7 @@ pub fn generate_delete_by_unique(
                 by_column: unique_column.clone(),
                 collection_name: collection_name.clone(),
                 description,
+                soft_delete: table_info.soft_delete.clone(),
             };
 
             (name, delete_mutation)
@@ -76,12 +81,14 @@ pub fn translate_delete(
     state: &mut crate::translation::helpers::State,
     delete: &DeleteMutation,
     arguments: &BTreeMap<models::ArgumentName, serde_json::Value>,
-) -> Result<(sql::ast::Delete, sql::ast::ColumnAlias), Error> {
+) -> Result<(sql::ast::CTExpr, sql::ast::ColumnAlias), Error> {
     match delete {
         DeleteMutation::DeleteByKey {
             schema_name,
             table_name,
             by_column,
+            collection_name,
+            soft_delete,
             ..
         } => {
             // The root table we are going to be deleting from.
@@ -92,9 +99,10 @@ pub fn translate_delete(
 
             let table_alias = state.make_table_alias(table_name.0.clone());
 
-            let from = sql::ast::From::Table {
-                reference: table,
-                alias: table_alias.clone(),
+            // An UPDATE refers to the table by name, like the update mutations do.
+            let reference = match soft_delete {
+                None => sql::ast::TableReference::AliasedTable(table_alias.clone()),
+                Some(_) => table.clone(),
             };
 
             // Build the `UNIQUE_KEY = <value>` boolean expression.
@@ -102,12 +110,12 @@ pub fn translate_delete(
                 .get(by_column.name.as_str())
                 .ok_or(Error::ArgumentNotFound(by_column.name.clone().into()))?;
 
-            let key_value = values::translate(env, state, unique_key, &by_column.r#type).unwrap();
+            let key_value = values::translate(env, state, unique_key, &by_column.r#type)?;
 
             let unique_expression = sql::ast::Expression::BinaryOperation {
                 left: Box::new(sql::ast::Expression::ColumnReference(
                     sql::ast::ColumnReference::TableColumn {
-                        table: sql::ast::TableReference::AliasedTable(table_alias),
+                        table: reference.clone(),
                         name: sql::ast::ColumnName(by_column.name.clone()),
                     },
                 )),
@@ -115,26 +123,69 @@ pub fn translate_delete(
                 operator: sql::ast::BinaryOperator("=".to_string()),
             };
 
+            // Rows the collection hides from queries, including those already marked as deleted,
+            // may not be deleted either.
+            let restrictions = root::translate_collection_restrictions(
+                env,
+                state,
+                &TableSourceAndReference {
+                    source: TableSource::Collection(collection_name.clone()),
+                    reference,
+                },
+            )?;
+            let where_ = if restrictions.is_empty() {
+                unique_expression
+            } else {
+                sql::helpers::fold_and(
+                    std::iter::once(unique_expression)
+                        .chain(restrictions)
+                        .collect(),
+                )
+            };
+
             // We add an always true constraint check to unify the mutations interface.
             let check_constraint_alias =
                 sql::helpers::make_column_alias(sql::helpers::CHECK_CONSTRAINT_FIELD.to_string());
             let check_constraint_value = sql::helpers::true_expr();
 
-            Ok((
-                sql::ast::Delete {
-                    from,
-                    where_: sql::ast::Where(unique_expression),
-                    // RETURNING *, true
-                    returning: sql::ast::Returning(sql::ast::SelectList::SelectListComposite(
-                        Box::new(sql::ast::SelectList::SelectStar),
-                        Box::new(sql::ast::SelectList::SelectList(vec![(
-                            check_constraint_alias.clone(),
-                            check_constraint_value,
-                        )])),
-                    )),
-                },
-                check_constraint_alias,
-            ))
+            // RETURNING *, true
+            let returning = sql::ast::Returning(sql::ast::SelectList::SelectListComposite(
+                Box::new(sql::ast::SelectList::SelectStar),
+                Box::new(sql::ast::SelectList::SelectList(vec![(
+                    check_constraint_alias.clone(),
+                    check_constraint_value,
+                )])),
+            ));
+
+            let cte = match soft_delete {
+                None => sql::ast::CTExpr::Delete(sql::ast::Delete {
+                    from: sql::ast::From::Table {
+                        reference: table,
+                        alias: table_alias,
+                    },
+                    where_: sql::ast::Where(where_),
+                    returning,
+                }),
+                // Mark the row as deleted instead.
+                Some(soft_delete) => sql::ast::CTExpr::Update(sql::ast::Update {
+                    schema: schema_name.clone(),
+                    table: table_name.clone(),
+                    only: false,
+                    set: BTreeMap::from([(
+                        sql::ast::ColumnName(soft_delete.column.clone()),
+                        sql::ast::MutationValueExpression::Expression(
+                            sql::ast::Expression::FunctionCall {
+                                function: sql::ast::Function::Unknown("now".to_string()),
+                                args: vec![],
+                            },
+                        ),
+                    )]),
+                    where_: sql::ast::Where(where_),
+                    returning,
+                }),
+            };
+
+            Ok((cte, check_constraint_alias))
         }
     }
 }
@@ -155,14 +206,22 @@ mod tests {
             schema_name: sql::ast::SchemaName("public".to_string()),
             table_name: sql::ast::TableName("User".to_string()),
             collection_name: "User".into(),
+            soft_delete: None,
             by_column: metadata::ColumnInfo {
                 name: "user_id".to_string(),
                 description: None,
                 r#type: metadata::Type::ScalarType("int4".into()),
                 nullable: metadata::Nullable::NonNullable,
                 has_default: metadata::HasDefault::NoDefault,
+                default_expression: None,
                 is_identity: metadata::IsIdentity::NotIdentity,
                 is_generated: metadata::IsGenerated::NotGenerated,
+                generates_uuid: false,
+                selectable: true,
+                insertable: true,
+                updatable: true,
+                filterable: true,
+                sortable: true,
             },
             description: String::new(),
         }
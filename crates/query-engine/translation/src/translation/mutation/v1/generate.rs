@@ -18,13 +18,17 @@ pub fn generate(
 ) -> BTreeMap<models::ProcedureName, Mutation> {
     let mut mutations = BTreeMap::new();
     for (collection_name, table_info) in &env.metadata.tables.0 {
-        let delete_mutations = generate_delete_by_unique(collection_name, table_info);
+        if table_info.deletable {
+            let delete_mutations = generate_delete_by_unique(collection_name, table_info);
 
-        for (name, delete_mutation) in delete_mutations {
-            mutations.insert(name, Mutation::DeleteMutation(delete_mutation));
+            for (name, delete_mutation) in delete_mutations {
+                mutations.insert(name, Mutation::DeleteMutation(delete_mutation));
+            }
+        }
+        if table_info.insertable {
+            let (name, insert_mutation) = insert::generate(collection_name, table_info);
+            mutations.insert(name, Mutation::InsertMutation(insert_mutation));
         }
-        let (name, insert_mutation) = insert::generate(collection_name, table_info);
-        mutations.insert(name, Mutation::InsertMutation(insert_mutation));
     }
     mutations
 }
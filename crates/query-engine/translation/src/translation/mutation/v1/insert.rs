@@ -1,6 +1,9 @@
 //! Auto-generate insert mutations and translate them into sql ast.
 
 use crate::translation::error::Error;
+use crate::translation::helpers::{TableSource, TableSourceAndReference};
+use crate::translation::mutation::generated_uuids;
+use crate::translation::query::root;
 use crate::translation::query::values;
 use ndc_models as models;
 use query_engine_metadata::metadata;
@@ -71,13 +74,31 @@ pub fn translate(
         }
         _ => todo!(),
     };
+    // Columns the connector generates UUIDs for are filled in unless the object sets them.
+    for column in generated_uuids::generated_uuid_columns(&mutation.columns) {
+        if !columns.contains(&column) {
+            columns.push(column);
+            values.push(generated_uuids::generate_uuid());
+        }
+    }
 
     check_columns(&mutation.columns, &columns, &mutation.collection_name)?;
 
-    // We add an always true constraint check to unify the mutations interface.
+    // We add a constraint check to unify the mutations interface. It always holds, unless the
+    // collection hides rows from queries, which may not be inserted either.
     let check_constraint_alias =
         sql::helpers::make_column_alias(sql::helpers::CHECK_CONSTRAINT_FIELD.to_string());
-    let check_constraint_value = sql::helpers::true_expr();
+    let check_constraint_value = sql::helpers::fold_and(root::translate_collection_restrictions(
+        env,
+        state,
+        &TableSourceAndReference {
+            source: TableSource::Collection(mutation.collection_name.clone()),
+            reference: sql::ast::TableReference::DBTable {
+                schema: mutation.schema_name.clone(),
+                table: mutation.table_name.clone(),
+            },
+        },
+    )?);
 
     let insert = sql::ast::Insert {
         schema: mutation.schema_name.clone(),
@@ -105,6 +126,17 @@ fn check_columns(
     insert_name: &models::CollectionName,
 ) -> Result<(), Error> {
     for (name, column) in columns {
+        // columns the connecting role may not insert into must not be inserted, nor are they required.
+        if !column.insertable {
+            if inserted_columns.contains(&sql::ast::ColumnName(column.name.clone())) {
+                return Err(Error::ColumnNotWritable {
+                    collection: insert_name.clone(),
+                    column_name: name.clone(),
+                    operation: "insert into".to_string(),
+                });
+            }
+            continue;
+        }
         match column {
             // nullable, default, and identity by default columns can be inserted into or omitted.
             database::ColumnInfo {
@@ -35,11 +35,7 @@ pub fn translate(
             };
             let (delete_cte, check_constraint_alias) =
                 super::delete::translate_delete(env, state, &delete, arguments)?;
-            (
-                return_collection,
-                sql::ast::CTExpr::Delete(delete_cte),
-                check_constraint_alias,
-            )
+            (return_collection, delete_cte, check_constraint_alias)
         }
         super::generate::Mutation::InsertMutation(insert) => {
             let return_collection = insert.collection_name.clone();
@@ -3,6 +3,7 @@
 use crate::translation::error::Error;
 use crate::translation::helpers::{self, TableSourceAndReference};
 use crate::translation::query::filtering;
+use crate::translation::query::root;
 use crate::translation::query::values;
 use ndc_models as models;
 use nonempty::NonEmpty;
@@ -15,7 +16,8 @@ use super::common::{self, get_nullable_predicate_argument, CheckArgument};
 
 /// A representation of an auto-generated delete mutation.
 ///
-/// This can get us `DELETE FROM <table> WHERE column = <column_name_arg>, ...`.
+/// This can get us `DELETE FROM <table> WHERE column = <column_name_arg>, ...`, or, for tables
+/// which are deleted softly, `UPDATE <table> SET deleted_at = now() WHERE ...`.
 #[derive(Debug, Clone)]
 pub enum DeleteMutation {
     DeleteByKey(DeleteByKey),
@@ -31,6 +33,8 @@ pub struct DeleteByKey {
     pub by_columns: NonEmpty<metadata::database::ColumnInfo>,
     pub columns_prefix: String,
     pub pre_check: CheckArgument,
+    pub inherited_rows: metadata::InheritedRows,
+    pub soft_delete: Option<metadata::SoftDelete>,
 }
 
 /// generate a delete for each simple unique constraint on this table
@@ -77,6 +81,8 @@ pub fn generate_delete_by_unique(
                     ),
                 },
                 description,
+                inherited_rows: table_info.inherited_rows.clone(),
+                soft_delete: table_info.soft_delete.clone(),
             });
 
             Some((name, delete_mutation))
@@ -90,7 +96,7 @@ pub fn translate(
     state: &mut crate::translation::helpers::State,
     mutation: &DeleteMutation,
     arguments: &BTreeMap<models::ArgumentName, serde_json::Value>,
-) -> Result<(sql::ast::Delete, sql::ast::ColumnAlias), Error> {
+) -> Result<(sql::ast::CTExpr, sql::ast::ColumnAlias), Error> {
     match mutation {
         DeleteMutation::DeleteByKey(mutation) => {
             // The root table we are going to be deleting from.
@@ -101,14 +107,15 @@ pub fn translate(
 
             let table_alias = state.make_table_alias(mutation.table_name.0.clone());
 
-            let table_name_and_reference = TableSourceAndReference {
-                source: helpers::TableSource::Collection(mutation.collection_name.clone()),
-                reference: sql::ast::TableReference::AliasedTable(table_alias.clone()),
+            // An UPDATE refers to the table by name, like the update mutations do.
+            let reference = match mutation.soft_delete {
+                None => sql::ast::TableReference::AliasedTable(table_alias.clone()),
+                Some(_) => table.clone(),
             };
 
-            let from = sql::ast::From::Table {
-                reference: table,
-                alias: table_alias.clone(),
+            let table_name_and_reference = TableSourceAndReference {
+                source: helpers::TableSource::Collection(mutation.collection_name.clone()),
+                reference: reference.clone(),
             };
 
             // Build the `UNIQUE_KEY = <value>, ...` boolean expression.
@@ -122,13 +129,12 @@ pub fn translate(
                         .get(&argument_name)
                         .ok_or(Error::ArgumentNotFound(argument_name))?;
 
-                    let key_value =
-                        values::translate(env, state, unique_key, &by_column.r#type).unwrap();
+                    let key_value = values::translate(env, state, unique_key, &by_column.r#type)?;
 
                     let unique_expression = sql::ast::Expression::BinaryOperation {
                         left: Box::new(sql::ast::Expression::ColumnReference(
                             sql::ast::ColumnReference::TableColumn {
-                                table: sql::ast::TableReference::AliasedTable(table_alias.clone()),
+                                table: reference.clone(),
                                 name: sql::ast::ColumnName(by_column.name.clone()),
                             },
                         )),
@@ -148,36 +154,78 @@ pub fn translate(
                 state,
                 &helpers::RootAndCurrentTables {
                     root_table: table_name_and_reference.clone(),
-                    current_table: table_name_and_reference,
+                    current_table: table_name_and_reference.clone(),
                 },
                 &predicate,
             )?;
 
-            let where_ = sql::ast::Expression::And {
-                left: Box::new(sql::helpers::fold_and(unique_expressions)),
-                right: Box::new(predicate_expression),
-            };
+            // Rows the collection hides from queries, including those already marked as deleted,
+            // may not be deleted either.
+            let restrictions =
+                root::translate_collection_restrictions(env, state, &table_name_and_reference)?;
+
+            let where_ = sql::helpers::fold_and(
+                vec![
+                    sql::helpers::fold_and(unique_expressions),
+                    predicate_expression,
+                ]
+                .into_iter()
+                .chain(restrictions)
+                .collect(),
+            );
 
             // We add an always true constraint check to unify the mutations interface.
             let check_constraint_alias =
                 sql::helpers::make_column_alias(sql::helpers::CHECK_CONSTRAINT_FIELD.to_string());
             let check_constraint_value = sql::helpers::true_expr();
 
-            Ok((
-                sql::ast::Delete {
-                    from,
+            // RETURNING *, true
+            let returning = sql::ast::Returning(sql::ast::SelectList::SelectListComposite(
+                Box::new(sql::ast::SelectList::SelectStar),
+                Box::new(sql::ast::SelectList::SelectList(vec![(
+                    check_constraint_alias.clone(),
+                    check_constraint_value,
+                )])),
+            ));
+
+            let cte = match &mutation.soft_delete {
+                None => {
+                    let from = match mutation.inherited_rows {
+                        metadata::InheritedRows::Included => sql::ast::From::Table {
+                            reference: table,
+                            alias: table_alias,
+                        },
+                        metadata::InheritedRows::Excluded => sql::ast::From::TableOnly {
+                            reference: table,
+                            alias: table_alias,
+                        },
+                    };
+                    sql::ast::CTExpr::Delete(sql::ast::Delete {
+                        from,
+                        where_: sql::ast::Where(where_),
+                        returning,
+                    })
+                }
+                // Mark the row as deleted instead.
+                Some(soft_delete) => sql::ast::CTExpr::Update(sql::ast::Update {
+                    schema: mutation.schema_name.clone(),
+                    table: mutation.table_name.clone(),
+                    only: matches!(mutation.inherited_rows, metadata::InheritedRows::Excluded),
+                    set: BTreeMap::from([(
+                        sql::ast::ColumnName(soft_delete.column.clone()),
+                        sql::ast::MutationValueExpression::Expression(
+                            sql::ast::Expression::FunctionCall {
+                                function: sql::ast::Function::Unknown("now".to_string()),
+                                args: vec![],
+                            },
+                        ),
+                    )]),
                     where_: sql::ast::Where(where_),
-                    // RETURNING *, true
-                    returning: sql::ast::Returning(sql::ast::SelectList::SelectListComposite(
-                        Box::new(sql::ast::SelectList::SelectStar),
-                        Box::new(sql::ast::SelectList::SelectList(vec![(
-                            check_constraint_alias.clone(),
-                            check_constraint_value,
-                        )])),
-                    )),
-                },
-                check_constraint_alias,
-            ))
+                    returning,
+                }),
+            };
+
+            Ok((cte, check_constraint_alias))
         }
     }
 }
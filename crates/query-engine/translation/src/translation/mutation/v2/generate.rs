@@ -22,23 +22,33 @@ pub fn generate(
 ) -> BTreeMap<models::ProcedureName, Mutation> {
     let mut mutations = BTreeMap::new();
     for (collection_name, table_info) in &tables_info.0 {
-        // Delete mutations.
-        let delete_mutations =
-            generate_delete_by_unique(collection_name, table_info, mutations_prefix);
-        for (name, delete_mutation) in delete_mutations {
-            mutations.insert(name, Mutation::DeleteMutation(delete_mutation));
+        // Delete mutations. Soft deletion updates rows rather than deleting them.
+        let deletable = match table_info.soft_delete {
+            None => table_info.deletable,
+            Some(_) => table_info.updatable,
+        };
+        if deletable {
+            let delete_mutations =
+                generate_delete_by_unique(collection_name, table_info, mutations_prefix);
+            for (name, delete_mutation) in delete_mutations {
+                mutations.insert(name, Mutation::DeleteMutation(delete_mutation));
+            }
         }
 
         // Insert mutations.
-        let (name, insert_mutation) =
-            insert::generate(collection_name, table_info, mutations_prefix);
-        mutations.insert(name, Mutation::InsertMutation(insert_mutation));
+        if table_info.insertable {
+            let (name, insert_mutation) =
+                insert::generate(collection_name, table_info, mutations_prefix);
+            mutations.insert(name, Mutation::InsertMutation(insert_mutation));
+        }
 
         // Update mutations.
-        let update_mutations =
-            generate_update_by_unique(collection_name, table_info, mutations_prefix);
-        for (name, update_mutation) in update_mutations {
-            mutations.insert(name, Mutation::UpdateMutation(update_mutation));
+        if table_info.updatable {
+            let update_mutations =
+                generate_update_by_unique(collection_name, table_info, mutations_prefix);
+            for (name, update_mutation) in update_mutations {
+                mutations.insert(name, Mutation::UpdateMutation(update_mutation));
+            }
         }
     }
     mutations
@@ -2,8 +2,9 @@
 
 use crate::translation::error::Error;
 use crate::translation::helpers::{self, TableSourceAndReference};
-use crate::translation::mutation::check_columns;
+use crate::translation::mutation::{audit_columns, check_columns, generated_uuids};
 use crate::translation::query::filtering;
+use crate::translation::query::root;
 use crate::translation::query::values;
 use ndc_models as models;
 use query_engine_metadata::metadata;
@@ -24,6 +25,7 @@ pub struct InsertMutation {
     pub table_name: sql::ast::TableName,
     pub objects_argument_name: models::ArgumentName,
     pub columns: BTreeMap<models::FieldName, metadata::database::ColumnInfo>,
+    pub audit_columns: BTreeMap<models::FieldName, metadata::database::AuditColumn>,
     pub post_check: CheckArgument,
 }
 
@@ -47,6 +49,7 @@ pub fn generate(
         schema_name: sql::ast::SchemaName(table_info.schema_name.clone()),
         table_name: sql::ast::TableName(table_info.table_name.clone()),
         columns: table_info.columns.clone(),
+        audit_columns: table_info.audit_columns.clone(),
         objects_argument_name: "objects".into(),
         post_check: CheckArgument {
             argument_name: "post_check".into(),
@@ -88,7 +91,20 @@ fn translate_object_into_columns_and_values(
                     )?),
                 );
             }
-            Ok(())
+            // Columns the connector generates UUIDs for are filled in unless the object sets them.
+            for column in generated_uuids::generated_uuid_columns(&mutation.columns) {
+                columns_to_values
+                    .entry(column)
+                    .or_insert_with(generated_uuids::generate_uuid);
+            }
+            audit_columns::set_audit_columns(
+                env,
+                audit_columns::Operation::Insert,
+                &mutation.columns,
+                &mutation.audit_columns,
+                &mutation.collection_name,
+                &mut columns_to_values,
+            )
         }
         serde_json::Value::Array(_) => Err(Error::UnexpectedStructure(format!(
             "array of arrays structure in insert {} argument. Expecting an array of objects.",
@@ -234,11 +250,20 @@ pub fn translate(
         state,
         &helpers::RootAndCurrentTables {
             root_table: table_name_and_reference.clone(),
-            current_table: table_name_and_reference,
+            current_table: table_name_and_reference.clone(),
         },
         &predicate,
     )?;
 
+    // Rows the collection hides from queries may not be inserted either.
+    let mut checks = vec![predicate_expression];
+    checks.extend(root::translate_collection_restrictions(
+        env,
+        state,
+        &table_name_and_reference,
+    )?);
+    let predicate_expression = sql::helpers::fold_and(checks);
+
     let post_check_alias =
         sql::helpers::make_column_alias(sql::helpers::CHECK_CONSTRAINT_FIELD.to_string());
 
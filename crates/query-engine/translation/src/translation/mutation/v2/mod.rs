@@ -33,11 +33,14 @@
 //!   >     ...,
 //!   >     update_columns: { <column>: { _set: <value> }, ... },
 //!   >     pre_check: <boolexpr>,
-//!   >     post_check: <boolexpr>
+//!   >     post_check: <boolexpr>,
+//!   >     if_matched: { <column>: <value>, ... }
 //!   > )
 //!
 //!   It allows us to update a single row using the uniqueness constraint by updating the relevant columns,
-//!   and contains a pre check and post check for permissions.
+//!   and contains a pre check and post check for permissions. The optional `if_matched` argument only
+//!   updates the row if its columns have the given values, such as a version, and reports an error
+//!   when the row exists but does not match, rather than 0 affected rows.
 //!
 //! * Mutations using uniqueness constraints use the naming schema `by_column_and_column_and_column` instead of the db constraint name,
 //!   because the former is far more helpful.
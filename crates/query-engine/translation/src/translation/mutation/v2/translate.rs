@@ -12,6 +12,8 @@ use super::delete::DeleteByKey;
 /// Translate a built-in delete mutation into an ExecutionPlan (SQL) to be run against the database.
 /// This part is specialized for this mutations versions.
 /// To be invoke from the main mutations translate function.
+///
+/// Update mutations given an `if_matched` argument also return whether the row exists.
 pub fn translate(
     env: &Env,
     state: &mut State,
@@ -22,10 +24,12 @@ pub fn translate(
         models::CollectionName,
         sql::ast::CTExpr,
         sql::ast::ColumnAlias,
+        Option<sql::ast::Expression>,
     ),
     Error,
 > {
-    let mutation = lookup_generated_mutation(env, procedure_name, env.mutations_prefix.as_ref())?;
+    let mutation =
+        lookup_generated_mutation(env, procedure_name, env.options.mutations_prefix.as_ref())?;
 
     Ok(match mutation {
         super::generate::Mutation::DeleteMutation(delete) => {
@@ -39,11 +43,7 @@ pub fn translate(
             let (delete_cte, check_constraint_alias) =
                 super::delete::translate(env, state, &delete, arguments)?;
 
-            (
-                return_collection,
-                sql::ast::CTExpr::Delete(delete_cte),
-                check_constraint_alias,
-            )
+            (return_collection, delete_cte, check_constraint_alias, None)
         }
         super::generate::Mutation::InsertMutation(insert) => {
             let (insert_cte, check_constraint_alias) =
@@ -55,10 +55,11 @@ pub fn translate(
                 return_collection,
                 sql::ast::CTExpr::Insert(insert_cte),
                 check_constraint_alias,
+                None,
             )
         }
         super::generate::Mutation::UpdateMutation(update) => {
-            let (update_cte, check_constraint_alias) =
+            let (update_cte, check_constraint_alias, row_exists) =
                 super::update::translate(env, state, &update, arguments)?;
 
             let super::update::UpdateMutation::UpdateByKey(update_by_key) = update;
@@ -69,6 +70,7 @@ pub fn translate(
                 return_collection,
                 sql::ast::CTExpr::Update(update_cte),
                 check_constraint_alias,
+                row_exists,
             )
         }
     })
@@ -2,8 +2,9 @@
 
 use crate::translation::error::Error;
 use crate::translation::helpers::{self, TableSourceAndReference};
-use crate::translation::mutation::check_columns;
+use crate::translation::mutation::{audit_columns, check_columns};
 use crate::translation::query::filtering;
+use crate::translation::query::root;
 use crate::translation::query::values;
 use ndc_models as models;
 use nonempty::NonEmpty;
@@ -34,7 +35,10 @@ pub struct UpdateByKey {
     pub update_columns_argument_name: models::ArgumentName,
     pub pre_check: CheckArgument,
     pub post_check: CheckArgument,
+    pub if_matched: CheckArgument,
     pub table_columns: BTreeMap<models::FieldName, metadata::database::ColumnInfo>,
+    pub audit_columns: BTreeMap<models::FieldName, metadata::database::AuditColumn>,
+    pub inherited_rows: metadata::InheritedRows,
 }
 
 /// Generate a update for each simple unique constraint on this table.
@@ -87,7 +91,15 @@ pub fn generate_update_by_unique(
                         "Update permission post-condition predicate over the '{collection_name}' collection"
                     ),
                 },
+                if_matched: CheckArgument {
+                    argument_name: "if_matched".into(),
+                    description: format!(
+                        "Expected values of the columns of the row in the '{collection_name}' collection, such as a version. The row is only updated if they match"
+                    ),
+                },
                 table_columns: table_info.columns.clone(),
+                audit_columns: table_info.audit_columns.clone(),
+                inherited_rows: table_info.inherited_rows.clone(),
 
                 description,
             });
@@ -99,12 +111,22 @@ pub fn generate_update_by_unique(
 
 /// Given the description of an update mutation (ie, `UpdateMutation`),
 /// and the arguments, output the SQL AST.
+///
+/// When the `if_matched` argument is given, also return an expression which tells whether the
+/// row to update exists, so that a mismatch can be told apart from a missing row.
 pub fn translate(
     env: &crate::translation::helpers::Env,
     state: &mut crate::translation::helpers::State,
     mutation: &UpdateMutation,
     arguments: &BTreeMap<models::ArgumentName, serde_json::Value>,
-) -> Result<(sql::ast::Update, sql::ast::ColumnAlias), Error> {
+) -> Result<
+    (
+        sql::ast::Update,
+        sql::ast::ColumnAlias,
+        Option<sql::ast::Expression>,
+    ),
+    Error,
+> {
     match mutation {
         UpdateMutation::UpdateByKey(mutation) => {
             let object = arguments
@@ -123,46 +145,66 @@ pub fn translate(
                 },
             };
 
-            // Build the `UNIQUE_KEY = <value>, ...` boolean expression.
-            let unique_expressions = mutation
-                .by_columns
-                .iter()
-                .map(|by_column| {
-                    let argument_name =
-                        format!("{}{}", mutation.columns_prefix, by_column.name).into();
-                    let unique_key = arguments
-                        .get(&argument_name)
-                        .ok_or(Error::ArgumentNotFound(argument_name))?;
-
-                    let key_value =
-                        values::translate(env, state, unique_key, &by_column.r#type).unwrap();
-
-                    let unique_expression = sql::ast::Expression::BinaryOperation {
-                        left: Box::new(sql::ast::Expression::ColumnReference(
-                            sql::ast::ColumnReference::TableColumn {
-                                table: table_name_and_reference.reference.clone(),
-                                name: sql::ast::ColumnName(by_column.name.clone()),
-                            },
-                        )),
-                        right: Box::new(key_value),
-                        operator: sql::ast::BinaryOperator("=".to_string()),
+            let row_expression = translate_row_expression(
+                env,
+                state,
+                mutation,
+                arguments,
+                &table_name_and_reference,
+            )?;
+
+            // Build the `if_matched` argument boolean expression, and the check for whether the
+            // row exists, which selects it by key under a fresh alias.
+            let (where_, row_exists) = match translate_if_matched(
+                env,
+                state,
+                mutation,
+                arguments,
+                &table_name_and_reference.reference,
+            )? {
+                None => (row_expression, None),
+                Some(if_matched_expression) => {
+                    let table_alias = state.make_table_alias(mutation.table_name.0.clone());
+                    let aliased_table = TableSourceAndReference {
+                        source: table_name_and_reference.source.clone(),
+                        reference: sql::ast::TableReference::AliasedTable(table_alias.clone()),
+                    };
+                    let from = match mutation.inherited_rows {
+                        metadata::InheritedRows::Included => sql::ast::From::Table {
+                            reference: table_name_and_reference.reference.clone(),
+                            alias: table_alias,
+                        },
+                        metadata::InheritedRows::Excluded => sql::ast::From::TableOnly {
+                            reference: table_name_and_reference.reference.clone(),
+                            alias: table_alias,
+                        },
                     };
-                    Ok::<sql::ast::Expression, Error>(unique_expression)
-                })
-                .collect::<Result<Vec<sql::ast::Expression>, Error>>()?;
+                    let row_exists = sql::helpers::where_exists_select(
+                        from,
+                        vec![],
+                        sql::ast::Where(translate_row_expression(
+                            env,
+                            state,
+                            mutation,
+                            arguments,
+                            &aliased_table,
+                        )?),
+                    );
+                    (
+                        sql::ast::Expression::And {
+                            left: Box::new(row_expression),
+                            right: Box::new(if_matched_expression),
+                        },
+                        Some(row_exists),
+                    )
+                }
+            };
 
             let root_and_current_tables = helpers::RootAndCurrentTables {
                 root_table: table_name_and_reference.clone(),
                 current_table: table_name_and_reference,
             };
 
-            // Build the `pre_constraint` argument boolean expression.
-            let pre_predicate =
-                get_nullable_predicate_argument(&mutation.pre_check.argument_name, arguments)?;
-
-            let pre_predicate_expression =
-                filtering::translate(env, state, &root_and_current_tables, &pre_predicate)?;
-
             // Build the `post_constraint` argument boolean expression.
             let post_predicate =
                 get_nullable_predicate_argument(&mutation.post_check.argument_name, arguments)?;
@@ -170,21 +212,25 @@ pub fn translate(
             let post_predicate_expression =
                 filtering::translate(env, state, &root_and_current_tables, &post_predicate)?;
 
+            // Rows may not be updated into ones the collection hides from queries.
+            let mut post_checks = vec![post_predicate_expression];
+            post_checks.extend(root::translate_collection_restrictions(
+                env,
+                state,
+                &root_and_current_tables.current_table,
+            )?);
+            let post_predicate_expression = sql::helpers::fold_and(post_checks);
+
             let check_constraint_alias =
                 sql::helpers::make_column_alias(sql::helpers::CHECK_CONSTRAINT_FIELD.to_string());
 
-            // Create a WHERE clause by combining the unique key expression and the pre condition.
-            let where_ = sql::ast::Where(sql::ast::Expression::And {
-                left: Box::new(sql::helpers::fold_and(unique_expressions)),
-                right: Box::new(pre_predicate_expression),
-            });
-
             // Create a SQL UPDATE statement.
             let update = sql::ast::Update {
                 schema: mutation.schema_name.clone(),
                 table: mutation.table_name.clone(),
+                only: matches!(mutation.inherited_rows, metadata::InheritedRows::Excluded),
                 set,
-                where_,
+                where_: sql::ast::Where(where_),
                 returning: sql::ast::Returning(sql::ast::SelectList::SelectListComposite(
                     Box::new(sql::ast::SelectList::SelectStar),
                     Box::new(sql::ast::SelectList::SelectList(vec![(
@@ -194,11 +240,125 @@ pub fn translate(
                 )),
             };
 
-            Ok((update, check_constraint_alias))
+            Ok((update, check_constraint_alias, row_exists))
         }
     }
 }
 
+/// Build the boolean expression selecting the row to update, combining the unique key
+/// expression and the pre condition.
+fn translate_row_expression(
+    env: &crate::translation::helpers::Env,
+    state: &mut crate::translation::helpers::State,
+    mutation: &UpdateByKey,
+    arguments: &BTreeMap<models::ArgumentName, serde_json::Value>,
+    table: &TableSourceAndReference,
+) -> Result<sql::ast::Expression, Error> {
+    // Build the `UNIQUE_KEY = <value>, ...` boolean expression.
+    let unique_expressions = mutation
+        .by_columns
+        .iter()
+        .map(|by_column| {
+            let argument_name = format!("{}{}", mutation.columns_prefix, by_column.name).into();
+            let unique_key = arguments
+                .get(&argument_name)
+                .ok_or(Error::ArgumentNotFound(argument_name))?;
+
+            let key_value = values::translate(env, state, unique_key, &by_column.r#type)?;
+
+            let unique_expression = sql::ast::Expression::BinaryOperation {
+                left: Box::new(sql::ast::Expression::ColumnReference(
+                    sql::ast::ColumnReference::TableColumn {
+                        table: table.reference.clone(),
+                        name: sql::ast::ColumnName(by_column.name.clone()),
+                    },
+                )),
+                right: Box::new(key_value),
+                operator: sql::ast::BinaryOperator("=".to_string()),
+            };
+            Ok::<sql::ast::Expression, Error>(unique_expression)
+        })
+        .collect::<Result<Vec<sql::ast::Expression>, Error>>()?;
+
+    // Build the `pre_constraint` argument boolean expression.
+    let pre_predicate =
+        get_nullable_predicate_argument(&mutation.pre_check.argument_name, arguments)?;
+
+    let pre_predicate_expression = filtering::translate(
+        env,
+        state,
+        &helpers::RootAndCurrentTables {
+            root_table: table.clone(),
+            current_table: table.clone(),
+        },
+        &pre_predicate,
+    )?;
+
+    // Rows the collection hides from queries may not be updated either.
+    let restrictions = root::translate_collection_restrictions(env, state, table)?;
+
+    Ok(sql::helpers::fold_and(
+        vec![
+            sql::helpers::fold_and(unique_expressions),
+            pre_predicate_expression,
+        ]
+        .into_iter()
+        .chain(restrictions)
+        .collect(),
+    ))
+}
+
+/// Build the `<column> = <expected value> AND ...` boolean expression of the `if_matched`
+/// argument, or `None` if it is missing or checks no columns. Columns set to null are not
+/// checked, as with `update_columns`.
+fn translate_if_matched(
+    env: &crate::translation::helpers::Env,
+    state: &mut crate::translation::helpers::State,
+    mutation: &UpdateByKey,
+    arguments: &BTreeMap<models::ArgumentName, serde_json::Value>,
+    table: &sql::ast::TableReference,
+) -> Result<Option<sql::ast::Expression>, Error> {
+    let argument_name = &mutation.if_matched.argument_name;
+    match arguments.get(argument_name) {
+        None | Some(serde_json::Value::Null) => Ok(None),
+        Some(serde_json::Value::Object(object)) => {
+            let mut expressions = vec![];
+            for (name, value) in object {
+                if value.is_null() {
+                    continue;
+                }
+                let column_info = mutation.table_columns.get(name.as_str()).ok_or_else(|| {
+                    Error::ColumnNotFoundInCollection(
+                        name.clone().into(),
+                        mutation.collection_name.clone(),
+                    )
+                })?;
+                // comparing a column reads it.
+                if !column_info.selectable {
+                    return Err(Error::ColumnNotSelectable(
+                        name.clone().into(),
+                        mutation.collection_name.clone(),
+                    ));
+                }
+                expressions.push(sql::ast::Expression::BinaryOperation {
+                    left: Box::new(sql::ast::Expression::ColumnReference(
+                        sql::ast::ColumnReference::TableColumn {
+                            table: table.clone(),
+                            name: sql::ast::ColumnName(column_info.name.clone()),
+                        },
+                    )),
+                    operator: sql::ast::BinaryOperator("=".to_string()),
+                    right: Box::new(values::translate(env, state, value, &column_info.r#type)?),
+                });
+            }
+            Ok((!expressions.is_empty()).then(|| sql::helpers::fold_and(expressions)))
+        }
+        Some(_) => Err(Error::UnexpectedStructure(format!(
+            "value structure in update '{argument_name}' argument. Expecting an object.",
+        ))),
+    }
+}
+
 /// Translate a single update object into a mapping from column names to values.
 fn parse_update_columns(
     env: &crate::translation::helpers::Env,
@@ -226,7 +386,14 @@ fn parse_update_columns(
                     columns_to_values.insert(sql::ast::ColumnName(column_info.name.clone()), value);
                 }
             }
-            Ok(())
+            audit_columns::set_audit_columns(
+                env,
+                audit_columns::Operation::Update,
+                &mutation.table_columns,
+                &mutation.audit_columns,
+                &mutation.collection_name,
+                &mut columns_to_values,
+            )
         }
         serde_json::Value::Array(_) => Err(Error::UnexpectedStructure(format!(
             "array structure in update '{}' argument. Expecting an object.",
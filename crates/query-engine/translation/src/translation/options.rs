@@ -0,0 +1,112 @@
+//! The options translation follows, which are the same for every request.
+
+use std::collections::BTreeMap;
+
+use ndc_models as models;
+use ndc_postgres_configuration as configuration;
+use query_engine_metadata::metadata;
+use query_engine_sql::sql;
+
+use super::query::aggregates::CountEstimation;
+use super::query::distribution::DistributedQueryRouting;
+use super::query::limits::Limits;
+use super::query::policies::{CollectionPolicies, CollectionPolicy};
+
+/// How requests are translated to SQL.
+#[derive(Debug, Clone, Default)]
+pub struct Options {
+    /// The database we generate SQL for.
+    pub dialect: sql::dialect::SqlDialect,
+    /// The isolation level of the transactions queries begin themselves, such as to set a
+    /// statement timeout.
+    pub isolation_level: sql::ast::transaction::IsolationLevel,
+    /// Which version of the auto-generated mutations is offered, if any.
+    pub mutations_version: Option<metadata::mutations::MutationsVersion>,
+    /// The prefix of the names of the auto-generated mutations, if not the default.
+    pub mutations_prefix: Option<String>,
+    /// Offer aggregates over the rows returned by procedures as fields of their responses.
+    pub mutations_returning_aggregates: bool,
+    /// Join relationships using `LEFT OUTER JOIN LATERAL`, rather than selecting them using
+    /// correlated subqueries.
+    pub use_lateral_joins: bool,
+    /// Keep the rows which tie with the last row within the limit of ordered queries.
+    pub limit_with_ties: bool,
+    /// Keep comparisons of boolean columns with `true` or `false` as they are.
+    pub strict_boolean_comparisons: bool,
+    /// Copy the variable sets of foreach queries with many of them into a temporary table.
+    pub temporary_variables_table: bool,
+    /// Read table collections from the snapshot given by their `snapshot` argument.
+    pub snapshot_reads: bool,
+    /// Limits on the size of queries.
+    pub limits: Limits,
+    /// Restrictions on the rows of collections which apply to every request.
+    pub collection_policies: CollectionPolicies,
+    /// How queries over tables distributed by Citus are planned.
+    pub distributed_query_routing: DistributedQueryRouting,
+    /// Whether star counts over large tables are answered with estimates.
+    pub count_estimation: Option<CountEstimation>,
+    /// Hints for `pg_hint_plan` to attach to queries over collections, in which `{{table}}`
+    /// stands for the alias each scan of the collection is given.
+    pub planner_hints: BTreeMap<models::CollectionName, String>,
+}
+
+impl Options {
+    /// The options a configuration asks for.
+    pub fn from_configuration(configuration: &configuration::Configuration) -> Options {
+        Options {
+            dialect: configuration.dialect,
+            isolation_level: match configuration.isolation_level {
+                configuration::IsolationLevel::ReadCommitted => {
+                    sql::ast::transaction::IsolationLevel::ReadCommitted
+                }
+                configuration::IsolationLevel::RepeatableRead => {
+                    sql::ast::transaction::IsolationLevel::RepeatableRead
+                }
+                configuration::IsolationLevel::Serializable => {
+                    sql::ast::transaction::IsolationLevel::Serializable
+                }
+            },
+            mutations_version: configuration.mutations_version,
+            mutations_prefix: configuration.mutations_prefix.clone(),
+            mutations_returning_aggregates: configuration.mutations_returning_aggregates,
+            use_lateral_joins: configuration.use_lateral_joins,
+            limit_with_ties: configuration.limit_with_ties,
+            strict_boolean_comparisons: configuration.strict_boolean_comparisons,
+            temporary_variables_table: configuration.temporary_variables_table,
+            snapshot_reads: configuration.snapshot_reads,
+            limits: Limits {
+                max_relationship_depth: configuration.query_limits.max_relationship_depth,
+                max_joins: configuration.query_limits.max_joins,
+                max_predicate_nodes: configuration.query_limits.max_predicate_nodes,
+            },
+            collection_policies: CollectionPolicies(
+                configuration
+                    .collection_policies
+                    .iter()
+                    .map(|(collection_name, policy)| {
+                        (
+                            collection_name.clone(),
+                            CollectionPolicy {
+                                filter: policy.filter.clone(),
+                                max_rows: policy.max_rows,
+                            },
+                        )
+                    })
+                    .collect(),
+            ),
+            distributed_query_routing: match configuration.distributed_query_routing {
+                configuration::DistributedQueryRouting::Off => DistributedQueryRouting::Off,
+                configuration::DistributedQueryRouting::Warn => DistributedQueryRouting::Warn,
+                configuration::DistributedQueryRouting::PushDown => {
+                    DistributedQueryRouting::PushDown
+                }
+            },
+            count_estimation: configuration.count_estimation.map(|count_estimation| {
+                CountEstimation {
+                    min_rows: count_estimation.min_rows,
+                }
+            }),
+            planner_hints: configuration.planner_hints.clone(),
+        }
+    }
+}
@@ -0,0 +1,312 @@
+//! A logical plan describing which rows a query reads, before it is lowered to SQL.
+//!
+//! Translation first decides which relation a query reads from, which rows it keeps and in
+//! which order, and only then turns that into a `sql::ast::Select`. Keeping these decisions in a
+//! small structure of their own means rewrites over them (such as simplifying the predicate)
+//! can be written and tested without going through the SQL AST.
+//!
+//! The plan covers the relational core of a collection query - the relation, the predicate,
+//! the ordering, and paging - for both the rows and the aggregates of a query. Fields,
+//! relationships, and aggregate functions are translated to SQL directly, on top of the
+//! lowered plan.
+
+use query_engine_metadata::metadata;
+use query_engine_sql::sql;
+
+/// A relation we read rows from.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Relation {
+    /// A table, view, or other relation defined in the database.
+    Table {
+        schema: sql::ast::SchemaName,
+        table: sql::ast::TableName,
+        inherited_rows: metadata::InheritedRows,
+    },
+    /// A relation that already has a name in the query, such as a native query CTE.
+    Reference(sql::ast::TableReference),
+    /// A native query which refers to the rows of an outer query, defined in a subquery of its
+    /// own rather than as a CTE of the whole query.
+    Correlated(sql::ast::CommonTableExpression),
+}
+
+/// Reading all the rows of a relation under an alias.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Scan {
+    pub relation: Relation,
+    pub alias: sql::ast::TableAlias,
+}
+
+/// The rows of a scan which satisfy a predicate, in order, and possibly paged.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Selection {
+    pub scan: Scan,
+    /// Joins the predicate or the ordering refer to, such as to order by the columns of
+    /// related rows.
+    pub joins: Vec<sql::ast::Join>,
+    pub predicate: sql::ast::Expression,
+    /// Keep only the first row of each group of rows with equal values for these expressions.
+    pub distinct_on: Vec<sql::ast::Expression>,
+    pub order_by: sql::ast::OrderBy,
+    pub limit: Option<u32>,
+    pub offset: Option<u32>,
+    /// Keep the rows which tie with the last row within the limit, if the selection is ordered.
+    pub with_ties: bool,
+}
+
+impl Scan {
+    /// Lower a scan to a FROM clause.
+    pub fn lower(self) -> sql::ast::From {
+        let Scan { relation, alias } = self;
+        match relation {
+            Relation::Table {
+                schema,
+                table,
+                inherited_rows,
+            } => {
+                let reference = sql::ast::TableReference::DBTable { schema, table };
+                match inherited_rows {
+                    metadata::InheritedRows::Included => sql::ast::From::Table { reference, alias },
+                    metadata::InheritedRows::Excluded => {
+                        sql::ast::From::TableOnly { reference, alias }
+                    }
+                }
+            }
+            Relation::Reference(reference) => sql::ast::From::Table { reference, alias },
+            Relation::Correlated(cte) => {
+                let reference = sql::ast::TableReference::AliasedTable(cte.alias.clone());
+                let mut select = sql::helpers::star_select(sql::ast::From::Table {
+                    reference,
+                    alias: cte.alias.clone(),
+                });
+                select.with = sql::ast::With {
+                    common_table_expressions: vec![cte],
+                };
+                sql::ast::From::Select {
+                    select: Box::new(select),
+                    alias,
+                }
+            }
+        }
+    }
+}
+
+impl Selection {
+    /// Select every row of a scan, in no particular order.
+    pub fn all(scan: Scan) -> Selection {
+        Selection {
+            scan,
+            joins: vec![],
+            predicate: sql::helpers::true_expr(),
+            distinct_on: vec![],
+            order_by: sql::helpers::empty_order_by(),
+            limit: None,
+            offset: None,
+            with_ties: false,
+        }
+    }
+
+    /// Only keep the rows which also satisfy this predicate.
+    pub fn filter(mut self, predicate: sql::ast::Expression) -> Selection {
+        self.predicate =
+            sql::rewrites::constant_folding::normalize_expr(sql::ast::Expression::And {
+                left: Box::new(self.predicate),
+                right: Box::new(predicate),
+            });
+        self
+    }
+
+    /// Lower a selection to a `SELECT <alias>.* FROM ...` query.
+    pub fn lower(self) -> sql::ast::Select {
+        let Selection {
+            scan,
+            joins,
+            predicate,
+            distinct_on,
+            order_by,
+            limit,
+            offset,
+            with_ties,
+        } = self;
+        let reference = sql::ast::TableReference::AliasedTable(scan.alias.clone());
+        let mut select = sql::helpers::star_from_select(reference, scan.lower());
+        select.joins = joins;
+        select.where_ = sql::ast::Where(predicate);
+        select.order_by = order_by;
+        sql::helpers::distinct_on(&mut select, distinct_on);
+        select.limit = sql::ast::Limit {
+            limit,
+            limit_expression: None,
+            offset,
+            with_ties: with_ties && !select.order_by.elements.is_empty(),
+        };
+        select
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn album_scan(inherited_rows: metadata::InheritedRows) -> Scan {
+        Scan {
+            relation: Relation::Table {
+                schema: sql::ast::SchemaName("public".to_string()),
+                table: sql::ast::TableName("Album".to_string()),
+                inherited_rows,
+            },
+            alias: sql::ast::TableAlias {
+                unique_index: 0,
+                name: "Album".to_string(),
+            },
+        }
+    }
+
+    fn lower_to_sql(selection: Selection) -> String {
+        lower_to_dialect(sql::dialect::SqlDialect::Postgres, selection)
+    }
+
+    fn lower_to_dialect(dialect: sql::dialect::SqlDialect, selection: Selection) -> String {
+        let mut sql = sql::string::SQL::with_dialect(dialect);
+        selection.lower().to_sql(&mut sql);
+        sql.sql
+    }
+
+    #[test]
+    fn lower_all_rows() {
+        let selection = Selection::all(album_scan(metadata::InheritedRows::Included));
+        assert_eq!(
+            lower_to_sql(selection),
+            r#"SELECT "%0_Album".* FROM "public"."Album" AS "%0_Album""#
+        );
+    }
+
+    #[test]
+    fn lower_excluding_inherited_rows() {
+        let selection = Selection::all(album_scan(metadata::InheritedRows::Excluded));
+        assert_eq!(
+            lower_to_sql(selection),
+            r#"SELECT "%0_Album".* FROM ONLY "public"."Album" AS "%0_Album""#
+        );
+    }
+
+    #[test]
+    fn only_is_omitted_without_table_inheritance() {
+        let selection = Selection::all(album_scan(metadata::InheritedRows::Excluded));
+        assert_eq!(
+            lower_to_dialect(sql::dialect::SqlDialect::CockroachDB, selection),
+            r#"SELECT "%0_Album".* FROM "public"."Album" AS "%0_Album""#
+        );
+    }
+
+    #[test]
+    fn lower_correlated_native_query() {
+        let cte = sql::ast::CommonTableExpression {
+            alias: sql::ast::TableAlias {
+                unique_index: 1,
+                name: "NATIVE_QUERY_tracks_of".to_string(),
+            },
+            column_names: None,
+            select: sql::ast::CTExpr::RawSql(vec![
+                sql::ast::RawSql::RawText(
+                    "SELECT * FROM \"Track\" WHERE \"AlbumId\" = ".to_string(),
+                ),
+                sql::ast::RawSql::Expression(sql::ast::Expression::ColumnReference(
+                    sql::ast::ColumnReference::TableColumn {
+                        table: sql::ast::TableReference::AliasedTable(sql::ast::TableAlias {
+                            unique_index: 0,
+                            name: "Album".to_string(),
+                        }),
+                        name: sql::ast::ColumnName("AlbumId".to_string()),
+                    },
+                )),
+            ]),
+        };
+        let selection = Selection::all(Scan {
+            relation: Relation::Correlated(cte),
+            alias: sql::ast::TableAlias {
+                unique_index: 2,
+                name: "tracks_of".to_string(),
+            },
+        });
+        assert_eq!(
+            lower_to_sql(selection),
+            r#"SELECT "%2_tracks_of".* FROM (WITH "%1_NATIVE_QUERY_tracks_of" AS (SELECT * FROM "Track" WHERE "AlbumId" = "%0_Album"."AlbumId"
+)SELECT * FROM "%1_NATIVE_QUERY_tracks_of" AS "%1_NATIVE_QUERY_tracks_of") AS "%2_tracks_of""#
+        );
+    }
+
+    #[test]
+    fn filters_are_combined() {
+        let selection = Selection::all(album_scan(metadata::InheritedRows::Included))
+            .filter(sql::helpers::true_expr())
+            .filter(sql::helpers::false_expr());
+        assert_eq!(selection.predicate, sql::helpers::false_expr());
+    }
+
+    #[test]
+    fn lower_distinct_selection() {
+        let mut selection = Selection::all(album_scan(metadata::InheritedRows::Included));
+        let artist_id =
+            sql::ast::Expression::ColumnReference(sql::ast::ColumnReference::AliasedColumn {
+                table: sql::ast::TableReference::AliasedTable(selection.scan.alias.clone()),
+                column: sql::ast::ColumnAlias("ArtistId".to_string()),
+            });
+        selection.distinct_on = vec![artist_id];
+        selection.order_by.elements.push(sql::ast::OrderByElement {
+            target: sql::ast::Expression::ColumnReference(
+                sql::ast::ColumnReference::AliasedColumn {
+                    table: sql::ast::TableReference::AliasedTable(selection.scan.alias.clone()),
+                    column: sql::ast::ColumnAlias("AlbumId".to_string()),
+                },
+            ),
+            direction: sql::ast::OrderByDirection::Desc,
+        });
+        assert_eq!(
+            lower_to_sql(selection),
+            r#"SELECT DISTINCT ON ("%0_Album"."ArtistId") "%0_Album".* FROM "public"."Album" AS "%0_Album" ORDER BY "%0_Album"."ArtistId" ASC, "%0_Album"."AlbumId" DESC"#
+        );
+    }
+
+    #[test]
+    fn lower_paged_selection() {
+        let mut selection = Selection::all(album_scan(metadata::InheritedRows::Included));
+        selection.limit = Some(5);
+        selection.offset = Some(3);
+        assert_eq!(
+            lower_to_sql(selection),
+            r#"SELECT "%0_Album".* FROM "public"."Album" AS "%0_Album" LIMIT 5 OFFSET 3"#
+        );
+    }
+
+    #[test]
+    fn lower_ordered_selection_with_ties() {
+        let mut selection = Selection::all(album_scan(metadata::InheritedRows::Included));
+        selection.order_by.elements.push(sql::ast::OrderByElement {
+            target: sql::ast::Expression::ColumnReference(
+                sql::ast::ColumnReference::AliasedColumn {
+                    table: sql::ast::TableReference::AliasedTable(selection.scan.alias.clone()),
+                    column: sql::ast::ColumnAlias("AlbumId".to_string()),
+                },
+            ),
+            direction: sql::ast::OrderByDirection::Desc,
+        });
+        selection.limit = Some(5);
+        selection.offset = Some(10);
+        selection.with_ties = true;
+        assert_eq!(
+            lower_to_sql(selection),
+            r#"SELECT "%0_Album".* FROM "public"."Album" AS "%0_Album" ORDER BY "%0_Album"."AlbumId" DESC OFFSET 10 ROWS FETCH FIRST 5 ROWS WITH TIES"#
+        );
+    }
+
+    #[test]
+    fn ties_are_ignored_without_ordering() {
+        let mut selection = Selection::all(album_scan(metadata::InheritedRows::Included));
+        selection.limit = Some(5);
+        selection.with_ties = true;
+        assert_eq!(
+            lower_to_sql(selection),
+            r#"SELECT "%0_Album".* FROM "public"."Album" AS "%0_Album" LIMIT 5"#
+        );
+    }
+}
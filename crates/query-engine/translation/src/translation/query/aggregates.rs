@@ -5,8 +5,20 @@ use indexmap::IndexMap;
 use ndc_models as models;
 
 use crate::translation::error::Error;
+use crate::translation::helpers::State;
+use query_engine_metadata::metadata;
 use query_engine_sql::sql;
 
+/// Answer star counts over whole tables with the number of rows PostgreSQL estimates they have,
+/// rather than counting them, for tables estimated to have at least `min_rows` rows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CountEstimation {
+    pub min_rows: u32,
+}
+
+/// The aggregate which says whether the counts of a query were estimated.
+pub const IS_ESTIMATE_FIELD: &str = "is_estimate";
+
 /// Translate any aggregates we should include in the query into our SQL AST.
 pub fn translate(
     table: &sql::ast::TableReference,
@@ -62,3 +74,94 @@ pub fn translate(
         })
         .collect::<Result<Vec<_>, Error>>()
 }
+
+/// Answer the star counts of a query over a whole table with the number of rows PostgreSQL
+/// estimates it has, if that is large enough, or count the rows selected by `from` otherwise.
+/// The rows are only counted when there is no such estimate. Whether there was is returned as
+/// the `is_estimate` aggregate, unless the query asks for an aggregate of that name itself.
+///
+/// The statistics of a table do not include the rows of the tables which inherit from it, so a
+/// table whose inherited rows are queried too is only estimated if no table inherits from it.
+pub fn estimate_count(
+    state: &mut State,
+    table_info: &metadata::TableInfo,
+    count_estimation: CountEstimation,
+    from: sql::ast::From,
+    aggregates: &IndexMap<models::FieldName, models::Aggregate>,
+) -> sql::ast::Select {
+    let pg_class = state.make_table_alias("pg_class".to_string());
+    let mut estimate = sql::helpers::estimated_row_count(
+        pg_class.clone(),
+        &table_info.schema_name,
+        &table_info.table_name,
+        count_estimation.min_rows,
+    );
+    if let metadata::InheritedRows::Included = table_info.inherited_rows {
+        let has_no_children = sql::ast::Expression::Not(Box::new(has_children(state, pg_class)));
+        estimate.where_ = sql::ast::Where(sql::ast::Expression::And {
+            left: Box::new(estimate.where_.0),
+            right: Box::new(has_no_children),
+        });
+    }
+    let mut count = sql::helpers::simple_select(vec![(
+        sql::helpers::make_column_alias("count".to_string()),
+        sql::ast::Expression::Count(sql::ast::CountType::Star),
+    )]);
+    count.from = Some(from);
+    let estimate_or_count = sql::ast::Expression::FunctionCall {
+        function: sql::ast::Function::Coalesce,
+        args: vec![
+            sql::ast::Expression::CorrelatedSubSelect(Box::new(estimate.clone())),
+            sql::ast::Expression::CorrelatedSubSelect(Box::new(count)),
+        ],
+    };
+
+    let mut columns: Vec<(sql::ast::ColumnAlias, sql::ast::Expression)> = aggregates
+        .keys()
+        .map(|alias| {
+            (
+                sql::helpers::make_column_alias(alias.to_string()),
+                estimate_or_count.clone(),
+            )
+        })
+        .collect();
+    if !aggregates
+        .keys()
+        .any(|alias| alias.as_str() == IS_ESTIMATE_FIELD)
+    {
+        columns.push((
+            sql::helpers::make_column_alias(IS_ESTIMATE_FIELD.to_string()),
+            sql::ast::Expression::Exists {
+                select: Box::new(estimate),
+            },
+        ));
+    }
+    sql::helpers::simple_select(columns)
+}
+
+/// Whether any table inherits from the table of `pg_class`, as partitions of a partitioned
+/// table do.
+fn has_children(state: &mut State, pg_class: sql::ast::TableAlias) -> sql::ast::Expression {
+    let pg_inherits = state.make_table_alias("pg_inherits".to_string());
+    let column = |alias: &sql::ast::TableAlias, name: &str| {
+        sql::ast::Expression::ColumnReference(sql::ast::ColumnReference::TableColumn {
+            table: sql::ast::TableReference::AliasedTable(alias.clone()),
+            name: sql::ast::ColumnName(name.to_string()),
+        })
+    };
+    sql::helpers::where_exists_select(
+        sql::ast::From::Table {
+            reference: sql::ast::TableReference::DBTable {
+                schema: sql::ast::SchemaName("pg_catalog".to_string()),
+                table: sql::ast::TableName("pg_inherits".to_string()),
+            },
+            alias: pg_inherits.clone(),
+        },
+        vec![],
+        sql::ast::Where(sql::ast::Expression::BinaryOperation {
+            left: Box::new(column(&pg_inherits, "inhparent")),
+            operator: sql::ast::BinaryOperator("=".to_string()),
+            right: Box::new(column(&pg_class, "oid")),
+        }),
+    )
+}
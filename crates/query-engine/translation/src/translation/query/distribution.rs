@@ -0,0 +1,270 @@
+//! Route queries over tables which Citus distributes across nodes to as few shards as possible.
+//!
+//! Citus can run a query on a single node when it filters the distribution column of each of
+//! its distributed tables by equality, and joins distributed tables on their distribution columns.
+
+use std::collections::BTreeSet;
+use std::sync::Mutex;
+
+use ndc_models as models;
+
+use crate::translation::helpers::{Env, TableSource, TableSourceAndReference};
+use query_engine_metadata::metadata;
+use query_engine_sql::sql;
+
+/// How queries over tables which Citus distributes across nodes are planned.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DistributedQueryRouting {
+    /// Queries are planned as they are.
+    #[default]
+    Off,
+    /// Warn about distributed tables queried in ways which cannot be routed to a single shard.
+    Warn,
+    /// Also join related distributed tables on their distribution columns.
+    PushDown,
+}
+
+/// The collections which have been warned about, so that each is warned about once rather than
+/// on every query.
+static WARNED_COLLECTIONS: Mutex<BTreeSet<models::CollectionName>> = Mutex::new(BTreeSet::new());
+
+/// The column a table is distributed by, if it is a distributed table.
+fn distribution_column(table_info: &metadata::TableInfo) -> Option<&str> {
+    match &table_info.distribution {
+        Some(metadata::Distribution::Distributed { column, .. }) => Some(column),
+        Some(metadata::Distribution::Reference) | None => None,
+    }
+}
+
+/// Are the shards of two tables placed together, so that rows with the same value of their
+/// distribution columns are on the same node?
+fn colocated(
+    source_distribution: Option<&metadata::Distribution>,
+    target_distribution: Option<&metadata::Distribution>,
+) -> bool {
+    match (source_distribution, target_distribution) {
+        (
+            Some(metadata::Distribution::Distributed {
+                colocation_id: Some(source_colocation_id),
+                ..
+            }),
+            Some(metadata::Distribution::Distributed {
+                colocation_id: Some(target_colocation_id),
+                ..
+            }),
+        ) => source_colocation_id == target_colocation_id,
+        _ => false,
+    }
+}
+
+/// Warn when a query over a distributed table does not filter its distribution column by
+/// equality, in its predicate or in the policy of the collection, as Citus then has to run it
+/// on every shard. Each collection is only warned about the first time.
+pub fn warn_if_not_routable(
+    env: &Env,
+    collection: &models::CollectionName,
+    predicate: Option<&models::Expression>,
+) {
+    if env.distributed_query_routing() == DistributedQueryRouting::Off {
+        return;
+    }
+    let Some(table_info) = env.metadata.tables.0.get(collection) else {
+        return;
+    };
+    let Some(column) = distribution_column(table_info) else {
+        return;
+    };
+    let policy_filter = env
+        .lookup_collection_policy(&TableSource::Collection(collection.clone()))
+        .and_then(|policy| policy.filter.as_ref());
+    // a distribution column which is not exposed cannot be filtered by
+    let pinned = table_info
+        .columns
+        .iter()
+        .find(|(_, column_info)| column_info.name == column)
+        .is_some_and(|(field, _)| {
+            predicate
+                .into_iter()
+                .chain(policy_filter)
+                .any(|expression| pins_column(expression, field))
+        });
+    if !pinned
+        && WARNED_COLLECTIONS
+            .lock()
+            .is_ok_and(|mut warned| warned.insert(collection.clone()))
+    {
+        tracing::warn!(
+            collection = collection.as_str(),
+            distribution_column = column,
+            "The query does not filter the distribution column of the table by equality, so it cannot be routed to a single shard."
+        );
+    }
+}
+
+/// Does the expression require a column of the table to equal a single value, in a conjunct
+/// which every row must satisfy?
+fn pins_column(expression: &models::Expression, field: &models::FieldName) -> bool {
+    match expression {
+        models::Expression::And { expressions } => expressions
+            .iter()
+            .any(|expression| pins_column(expression, field)),
+        models::Expression::BinaryComparisonOperator {
+            column,
+            operator,
+            value,
+        } => {
+            let compares_field = match column {
+                models::ComparisonTarget::Column {
+                    name,
+                    path,
+                    field_path: None,
+                } => name == field && path.is_empty(),
+                models::ComparisonTarget::RootCollectionColumn {
+                    name,
+                    field_path: None,
+                } => name == field,
+                models::ComparisonTarget::Column { .. }
+                | models::ComparisonTarget::RootCollectionColumn { .. } => false,
+            };
+            compares_field
+                && operator.as_str() == "_eq"
+                && matches!(
+                    value,
+                    models::ComparisonValue::Scalar { .. }
+                        | models::ComparisonValue::Variable { .. }
+                )
+        }
+        models::Expression::Or { .. }
+        | models::Expression::Not { .. }
+        | models::Expression::UnaryComparisonOperator { .. }
+        | models::Expression::Exists { .. } => false,
+    }
+}
+
+/// The equality of the distribution columns of the tables a relationship joins, when pushing
+/// them down, both tables are distributed and colocated, and the relationship does not already
+/// map one to the other. Tables which are not colocated have related rows on different nodes,
+/// which the equality would lose.
+pub fn translate_distribution_join(
+    env: &Env,
+    current_table: &TableSourceAndReference,
+    target_collection_alias_reference: &sql::ast::TableReference,
+    relationship: &models::Relationship,
+) -> Option<sql::ast::Expression> {
+    if env.distributed_query_routing() != DistributedQueryRouting::PushDown {
+        return None;
+    }
+    let TableSource::Collection(source_collection) = &current_table.source else {
+        return None;
+    };
+    let source_info = env.metadata.tables.0.get(source_collection)?;
+    let target_info = env.metadata.tables.0.get(&relationship.target_collection)?;
+    let source_column = distribution_column(source_info)?;
+    let target_column = distribution_column(target_info)?;
+    if !colocated(
+        source_info.distribution.as_ref(),
+        target_info.distribution.as_ref(),
+    ) {
+        return None;
+    }
+
+    let already_mapped = relationship
+        .column_mapping
+        .iter()
+        .any(|(source_field, target_field)| {
+            source_info
+                .columns
+                .get(source_field)
+                .is_some_and(|column_info| column_info.name == source_column)
+                && target_info
+                    .columns
+                    .get(target_field)
+                    .is_some_and(|column_info| column_info.name == target_column)
+        });
+    if already_mapped {
+        return None;
+    }
+
+    Some(sql::ast::Expression::BinaryOperation {
+        left: Box::new(sql::ast::Expression::ColumnReference(
+            sql::ast::ColumnReference::TableColumn {
+                table: current_table.reference.clone(),
+                name: sql::ast::ColumnName(source_column.to_string()),
+            },
+        )),
+        operator: sql::ast::BinaryOperator("=".to_string()),
+        right: Box::new(sql::ast::Expression::ColumnReference(
+            sql::ast::ColumnReference::TableColumn {
+                table: target_collection_alias_reference.clone(),
+                name: sql::ast::ColumnName(target_column.to_string()),
+            },
+        )),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn equals(column: &str, value: serde_json::Value) -> models::Expression {
+        models::Expression::BinaryComparisonOperator {
+            column: models::ComparisonTarget::Column {
+                name: column.into(),
+                path: vec![],
+                field_path: None,
+            },
+            operator: "_eq".into(),
+            value: models::ComparisonValue::Scalar { value },
+        }
+    }
+
+    #[test]
+    fn equality_in_a_conjunction_pins_a_column() {
+        let predicate = models::Expression::And {
+            expressions: vec![
+                equals("status", serde_json::json!("open")),
+                equals("tenant_id", serde_json::json!(42)),
+            ],
+        };
+        assert!(pins_column(&predicate, &"tenant_id".into()));
+        assert!(!pins_column(&predicate, &"order_id".into()));
+    }
+
+    #[test]
+    fn equality_in_a_disjunction_does_not_pin_a_column() {
+        let predicate = models::Expression::Or {
+            expressions: vec![
+                equals("tenant_id", serde_json::json!(1)),
+                equals("tenant_id", serde_json::json!(2)),
+            ],
+        };
+        assert!(!pins_column(&predicate, &"tenant_id".into()));
+    }
+
+    fn distributed(colocation_id: Option<u32>) -> metadata::Distribution {
+        metadata::Distribution::Distributed {
+            column: "tenant_id".to_string(),
+            colocation_id,
+        }
+    }
+
+    #[test]
+    fn only_tables_of_the_same_colocation_group_are_colocated() {
+        assert!(colocated(
+            Some(&distributed(Some(1))),
+            Some(&distributed(Some(1)))
+        ));
+        assert!(!colocated(
+            Some(&distributed(Some(1))),
+            Some(&distributed(Some(2)))
+        ));
+        assert!(!colocated(
+            Some(&distributed(None)),
+            Some(&distributed(None))
+        ));
+        assert!(!colocated(
+            Some(&distributed(Some(1))),
+            Some(&metadata::Distribution::Reference)
+        ));
+    }
+}
@@ -1,6 +1,6 @@
 //! Handle 'rows' and 'aggregates' translation.
 
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet};
 
 use indexmap::indexmap;
 use indexmap::IndexMap;
@@ -8,18 +8,23 @@ use indexmap::IndexMap;
 use ndc_models as models;
 
 use super::relationships;
+use super::values;
 use crate::translation::error::Error;
 use crate::translation::error::UnsupportedCapabilities;
 use crate::translation::helpers::FieldPath;
 use crate::translation::helpers::FieldsInfo;
 use crate::translation::helpers::TableSource;
 use crate::translation::helpers::{ColumnInfo, Env, State, TableSourceAndReference};
+use query_engine_metadata::metadata;
 use query_engine_metadata::metadata::{Type, TypeRepresentation};
 use query_engine_sql::sql;
 
 /// Translate the field-selection of a query to SQL.
 /// Because field selection may be nested this function is mutually recursive with
 /// 'translate_nested_field'.
+///
+/// Composite columns in `composites_as_json` are returned as a single JSON value rather than
+/// unpacked field by field.
 pub(crate) fn translate(
     env: &Env,
     state: &mut State,
@@ -27,6 +32,7 @@ pub(crate) fn translate(
     current_table: &TableSourceAndReference,
     from: sql::ast::From,
     join_relationship_fields: &mut Vec<relationships::JoinFieldInfo>,
+    composites_as_json: &BTreeSet<models::FieldName>,
 ) -> Result<sql::ast::Select, Error> {
     // find the table according to the metadata.
     let fields_info = env.lookup_fields_info(&current_table.source)?;
@@ -41,21 +47,54 @@ pub(crate) fn translate(
                 column,
                 fields: None,
                 arguments,
-            } if arguments.is_empty() => unpack_and_wrap_fields(
+            } if arguments.is_empty() && composites_as_json.contains(&column) => composite_as_json(
                 env,
                 state,
                 current_table,
                 &column,
                 sql::helpers::make_column_alias(alias.to_string()),
                 &fields_info,
-                &mut nested_field_joins,
             ),
+            models::Field::Column {
+                column,
+                fields: None,
+                arguments,
+            } if arguments.is_empty() => match fields_info.lookup_computed_field(&column) {
+                Some(computed_field) => Ok((
+                    sql::helpers::make_column_alias(alias.to_string()),
+                    translate_computed_field(current_table, computed_field),
+                )),
+                None => unpack_and_wrap_fields(
+                    env,
+                    state,
+                    current_table,
+                    &column,
+                    sql::helpers::make_column_alias(alias.to_string()),
+                    &fields_info,
+                    &mut nested_field_joins,
+                ),
+            },
             models::Field::Column {
                 column,
                 fields: Some(nested_field),
                 arguments,
             } if arguments.is_empty() => {
                 let column_info = fields_info.lookup_column(&column)?;
+                if is_json_column(env, &column_info) {
+                    let (alias, document) = sql::helpers::make_column(
+                        current_table.reference.clone(),
+                        column_info.name.clone(),
+                        sql::helpers::make_column_alias(alias.to_string()),
+                    );
+                    let document = sql::ast::Expression::Cast {
+                        expression: Box::new(document),
+                        r#type: sql::ast::ScalarType::BaseType(sql::helpers::jsonb_type_name()),
+                    };
+                    return Ok((
+                        alias,
+                        translate_json_nested_field(state, document, nested_field)?,
+                    ));
+                }
                 let (nested_field_join, nested_column_reference) = translate_nested_field(
                     env,
                     state,
@@ -116,6 +155,26 @@ pub(crate) fn translate(
     Ok(select)
 }
 
+/// The expression of a computed field, with its `{{row}}` placeholders replaced by references to
+/// the current table.
+pub(crate) fn translate_computed_field(
+    current_table: &TableSourceAndReference,
+    computed_field: &metadata::ComputedField,
+) -> sql::ast::Expression {
+    sql::ast::Expression::RawSql(
+        computed_field
+            .expression
+            .iter()
+            .map(|part| match part {
+                metadata::ExpressionPart::Text(text) => sql::ast::RawSql::RawText(text.clone()),
+                metadata::ExpressionPart::Row => {
+                    sql::ast::RawSql::TableReference(current_table.reference.clone())
+                }
+            })
+            .collect(),
+    )
+}
+
 /// This type collects the salient parts of joined-on subqueries that compute the result of a
 /// nested field selection.
 #[derive(Debug)]
@@ -321,6 +380,7 @@ fn translate_nested_field(
         &nested_field_table_reference,
         nested_field_from,
         &mut join_relationship_fields,
+        &BTreeSet::new(),
     )?;
 
     // collect any joins for relationships from fields selection.
@@ -331,7 +391,11 @@ fn translate_nested_field(
         join_relationship_fields,
     )?;
 
-    fields_select.joins.extend(relationship_joins);
+    if env.use_lateral_joins() {
+        fields_select.joins.extend(relationship_joins);
+    } else {
+        relationships::select_as_subqueries(&mut fields_select, relationship_joins);
+    }
 
     // The top-level select statement which collects the fields at the next level of nesting into a
     // single json object.
@@ -364,6 +428,260 @@ fn translate_nested_field(
     ))
 }
 
+/// Is the column a json document, rather than a composite value?
+fn is_json_column(env: &Env, column: &ColumnInfo) -> bool {
+    match &column.r#type {
+        Type::ScalarType(scalar_type) => matches!(
+            env.lookup_type_representation(scalar_type),
+            Some(TypeRepresentation::Json)
+        ),
+        Type::CompositeType(_) | Type::ArrayType(_) => false,
+    }
+}
+
+/// Translate a nested field selection on a json document.
+///
+/// Unlike composite values, json documents carry no type information we could use to bring
+/// their fields into scope as columns, so we extract the requested fields directly instead:
+///
+/// When the nested field is an object, we build a new object of the selected fields:
+///
+///   json_build_object(<alias>, <document> -> <field>, ...)
+///
+/// When the nested field is an array, we select from each of its elements, keeping their order:
+///
+///   (
+///     SELECT coalesce(json_agg(<selection from element> ORDER BY <json_elements>."ordinality"), '[]') AS "collected"
+///     FROM jsonb_array_elements(<document>) WITH ORDINALITY AS <json_elements>("element", "ordinality")
+///   )
+///
+/// Fields missing from the document are returned as null, except for arrays, which are returned
+/// empty like arrays without elements.
+fn translate_json_nested_field(
+    state: &mut State,
+    document: sql::ast::Expression,
+    field: models::NestedField,
+) -> Result<sql::ast::Expression, Error> {
+    match field {
+        models::NestedField::Object(models::NestedObject { fields }) => {
+            let mut args = Vec::with_capacity(fields.len() * 2);
+            for (alias, field) in fields {
+                match field {
+                    models::Field::Column {
+                        column,
+                        fields,
+                        arguments,
+                    } if arguments.is_empty() => {
+                        // <document> -> <field>
+                        let value = sql::ast::Expression::BinaryOperation {
+                            left: Box::new(document.clone()),
+                            operator: sql::helpers::json_extract_operator(),
+                            right: Box::new(sql::ast::Expression::Value(sql::ast::Value::String(
+                                column.to_string(),
+                            ))),
+                        };
+                        let value = match fields {
+                            None => value,
+                            Some(nested_field) => {
+                                translate_json_nested_field(state, value, nested_field)?
+                            }
+                        };
+                        args.push(sql::ast::Expression::Value(sql::ast::Value::String(
+                            alias.to_string(),
+                        )));
+                        args.push(value);
+                    }
+                    models::Field::Column { .. } => {
+                        return Err(Error::CapabilityNotSupported(
+                            UnsupportedCapabilities::FieldArguments,
+                        ))
+                    }
+                    models::Field::Relationship { .. } => {
+                        return Err(Error::RelationshipInJsonField { field_name: alias })
+                    }
+                }
+            }
+            Ok(sql::ast::Expression::FunctionCall {
+                function: sql::ast::Function::Unknown("json_build_object".to_string()),
+                args,
+            })
+        }
+        models::NestedField::Array(models::NestedArray { fields }) => {
+            let elements_alias = state.make_table_alias("json_elements".to_string());
+            let element_column = sql::helpers::make_column_alias("element".to_string());
+            let ordinality_column = sql::helpers::make_column_alias("ordinality".to_string());
+            let column_reference = |column: &sql::ast::ColumnAlias| {
+                sql::ast::Expression::ColumnReference(sql::ast::ColumnReference::AliasedColumn {
+                    table: sql::ast::TableReference::AliasedTable(elements_alias.clone()),
+                    column: column.clone(),
+                })
+            };
+            let element = column_reference(&element_column);
+            let ordinality = column_reference(&ordinality_column);
+
+            let mut select = sql::helpers::simple_select(vec![(
+                sql::helpers::make_column_alias("collected".to_string()),
+                sql::ast::Expression::FunctionCall {
+                    function: sql::ast::Function::Coalesce,
+                    args: vec![
+                        sql::ast::Expression::OrderedAggregateCall {
+                            function: sql::ast::Function::JsonAgg,
+                            args: vec![translate_json_nested_field(state, element, *fields)?],
+                            order_by: sql::ast::OrderBy {
+                                elements: vec![sql::ast::OrderByElement {
+                                    target: ordinality,
+                                    direction: sql::ast::OrderByDirection::Asc,
+                                }],
+                            },
+                        },
+                        sql::ast::Expression::Value(sql::ast::Value::EmptyJsonArray),
+                    ],
+                },
+            )]);
+            select.from = Some(sql::ast::From::JsonbArrayElements {
+                expression: document,
+                alias: elements_alias,
+                column: element_column,
+                ordinality_column: Some(ordinality_column),
+            });
+
+            Ok(sql::ast::Expression::CorrelatedSubSelect(Box::new(select)))
+        }
+    }
+}
+
+/// Select a composite column, or an array of them, as one JSON value built in place.
+/// Unlike unpacking, this needs no joins. The fields are still named and represented as the
+/// schema describes them.
+fn composite_as_json(
+    env: &Env,
+    state: &mut State,
+    current_table: &TableSourceAndReference,
+    column: &models::FieldName,
+    alias: sql::ast::ColumnAlias,
+    fields_info: &FieldsInfo<'_>,
+) -> Result<(sql::ast::ColumnAlias, sql::ast::Expression), Error> {
+    let column_info = fields_info.lookup_column(column)?;
+    let (alias, expression) = sql::helpers::make_column(
+        current_table.reference.clone(),
+        column_info.name.clone(),
+        alias,
+    );
+    Ok((
+        alias,
+        value_as_json(env, state, expression, &column_info.r#type)?,
+    ))
+}
+
+/// The most arguments `json_build_object` accepts are 100, which is 50 fields.
+const JSON_BUILD_OBJECT_MAX_FIELDS: usize = 50;
+
+/// Convert a value to the JSON the schema describes for its type:
+///
+/// - Scalars are cast to their type representation.
+/// - Composite values become objects keyed by the names of their fields in the schema:
+///   ```sql
+///   CASE WHEN num_nulls(<expression>) = 1 THEN NULL
+///   ELSE json_build_object('<field>', <value_as_json((<expression>)."<column>")>, ...) END
+///   ```
+///   `num_nulls` tells a null value apart from one whose fields are all null, which `IS NULL`
+///   does not. Objects with more fields than `json_build_object` accepts are built in parts,
+///   which are concatenated as `jsonb`.
+/// - Arrays convert each of their elements, in order:
+///   ```sql
+///   CASE WHEN <expression> IS NULL THEN NULL
+///   ELSE coalesce((SELECT json_agg(<value_as_json(<expression>[i])> ORDER BY i)
+///                  FROM generate_subscripts(<expression>, 1) AS i), '[]') END
+///   ```
+fn value_as_json(
+    env: &Env,
+    state: &mut State,
+    expression: sql::ast::Expression,
+    r#type: &Type,
+) -> Result<sql::ast::Expression, Error> {
+    match r#type {
+        Type::ScalarType(scalar_type) => Ok(match type_representation_cast(env, scalar_type) {
+            None if values::is_bytes(env, scalar_type) => sql::helpers::encode_base64(expression),
+            cast_type => wrap_in_type_representation(expression, cast_type),
+        }),
+        Type::CompositeType(type_name) => {
+            let composite_type = env.lookup_composite_type(type_name)?;
+            let mut fields = vec![];
+            for (field_name, _) in composite_type.fields() {
+                let field_info = composite_type.lookup_column(&field_name.as_str().into())?;
+                let field_value = sql::ast::Expression::NestedFieldSelect {
+                    expression: Box::new(expression.clone()),
+                    nested_field: sql::ast::NestedField(field_info.name.0),
+                };
+                fields.push((
+                    field_name,
+                    value_as_json(env, state, field_value, &field_info.r#type)?,
+                ));
+            }
+
+            let mut objects: Vec<sql::ast::Expression> = fields
+                .chunks(JSON_BUILD_OBJECT_MAX_FIELDS)
+                .map(|fields| {
+                    sql::ast::Expression::JsonBuildObject(fields.iter().cloned().collect())
+                })
+                .collect();
+            let empty_object = sql::ast::Expression::JsonBuildObject(BTreeMap::new());
+            let object = if objects.len() <= 1 {
+                objects.pop().unwrap_or(empty_object)
+            } else {
+                objects
+                    .into_iter()
+                    .map(|object| sql::ast::Expression::Cast {
+                        expression: Box::new(object),
+                        r#type: sql::helpers::jsonb_type(),
+                    })
+                    .reduce(|left, right| sql::ast::Expression::BinaryOperation {
+                        left: Box::new(left),
+                        operator: sql::ast::BinaryOperator("||".to_string()),
+                        right: Box::new(right),
+                    })
+                    .unwrap_or(empty_object)
+            };
+
+            Ok(sql::ast::Expression::RawSql(vec![
+                sql::ast::RawSql::RawText("CASE WHEN num_nulls(".to_string()),
+                sql::ast::RawSql::Expression(expression),
+                sql::ast::RawSql::RawText(") = 1 THEN NULL ELSE ".to_string()),
+                sql::ast::RawSql::Expression(object),
+                sql::ast::RawSql::RawText(" END".to_string()),
+            ]))
+        }
+        Type::ArrayType(element_type) => {
+            let subscript = sql::ast::TableReference::AliasedTable(
+                state.make_table_alias("subscript".to_string()),
+            );
+            let element = sql::ast::Expression::RawSql(vec![
+                sql::ast::RawSql::Expression(expression.clone()),
+                sql::ast::RawSql::RawText("[".to_string()),
+                sql::ast::RawSql::TableReference(subscript.clone()),
+                sql::ast::RawSql::RawText("]".to_string()),
+            ]);
+            let element = value_as_json(env, state, element, element_type)?;
+
+            Ok(sql::ast::Expression::RawSql(vec![
+                sql::ast::RawSql::RawText("CASE WHEN ".to_string()),
+                sql::ast::RawSql::Expression(expression.clone()),
+                sql::ast::RawSql::RawText(
+                    " IS NULL THEN NULL ELSE coalesce((SELECT json_agg(".to_string(),
+                ),
+                sql::ast::RawSql::Expression(element),
+                sql::ast::RawSql::RawText(" ORDER BY ".to_string()),
+                sql::ast::RawSql::TableReference(subscript.clone()),
+                sql::ast::RawSql::RawText(") FROM generate_subscripts(".to_string()),
+                sql::ast::RawSql::Expression(expression),
+                sql::ast::RawSql::RawText(", 1) AS ".to_string()),
+                sql::ast::RawSql::TableReference(subscript),
+                sql::ast::RawSql::RawText("), '[]') END".to_string()),
+            ]))
+        }
+    }
+}
+
 #[allow(clippy::too_many_arguments)]
 /// In order to return the expected type representation for each column,
 /// we need to wrap columns in type representation cast, and unpack composite types
@@ -384,16 +702,18 @@ fn unpack_and_wrap_fields(
     match column_info.r#type {
         // Scalar types can just be wrapped in a cast.
         Type::ScalarType(scalar_type) => {
-            let column_type_representation = env.lookup_type_representation(&scalar_type);
+            let cast_type = type_representation_cast(env, &scalar_type);
             let (alias, expression) = sql::helpers::make_column(
                 current_table.reference.clone(),
                 column_info.name.clone(),
                 alias,
             );
-            Ok((
-                alias,
-                wrap_in_type_representation(expression, column_type_representation),
-            ))
+            match cast_type {
+                None if values::is_bytes(env, &scalar_type) => {
+                    Ok((alias, sql::helpers::encode_base64(expression)))
+                }
+                cast_type => Ok((alias, wrap_in_type_representation(expression, cast_type))),
+            }
         }
         // Composite types are a more involved case because we cannot just "cast"
         // a composite type, we need to unpack it and cast the individual fields.
@@ -447,16 +767,21 @@ fn unpack_and_wrap_fields(
                 ))
             }
             Type::ScalarType(ref scalar_type) => {
-                let inner_column_type_representation = env.lookup_type_representation(scalar_type);
+                let inner_cast_type = type_representation_cast(env, scalar_type);
                 let (alias, expression) = sql::helpers::make_column(
                     current_table.reference.clone(),
                     column_info.name.clone(),
                     alias,
                 );
-                Ok((
-                    alias,
-                    wrap_array_in_type_representation(expression, inner_column_type_representation),
-                ))
+                match inner_cast_type {
+                    None if values::is_bytes(env, scalar_type) => {
+                        Ok((alias, encode_base64_array(state, expression)))
+                    }
+                    inner_cast_type => Ok((
+                        alias,
+                        wrap_array_in_type_representation(expression, inner_cast_type),
+                    )),
+                }
             }
         },
     }
@@ -467,21 +792,70 @@ fn unpack_and_wrap_fields(
 /// For array columns of those type representation, we wrap the result in a cast.
 fn wrap_array_in_type_representation(
     expression: sql::ast::Expression,
-    column_type_representation: Option<&TypeRepresentation>,
+    cast_type: Option<sql::ast::ScalarTypeName>,
 ) -> sql::ast::Expression {
-    match column_type_representation {
+    match cast_type {
         None => expression,
-        Some(type_rep) => {
-            if let Some(cast_type) = get_type_representation_cast_type(type_rep) {
-                sql::ast::Expression::Cast {
-                    expression: Box::new(expression),
-                    // make it an array of cast type
-                    r#type: sql::ast::ScalarType::ArrayType(cast_type),
-                }
-            } else {
-                expression
-            }
-        }
+        Some(cast_type) => sql::ast::Expression::Cast {
+            expression: Box::new(expression),
+            // make it an array of cast type
+            r#type: sql::ast::ScalarType::ArrayType(cast_type),
+        },
+    }
+}
+
+/// Arrays of bytea are encoded element by element, keeping their order:
+/// ```sql
+/// coalesce(
+///   ( SELECT array_agg(<encode_base64("bytes"."element")> ORDER BY "bytes"."ordinality") AS "element"
+///     FROM UNNEST(<expression>) WITH ORDINALITY AS "bytes"("element", "ordinality") ),
+///   cast(<expression> as text[])
+/// )
+/// ```
+/// Aggregating the elements of an empty array yields null, so empty arrays, like null ones, are
+/// passed through the fallback cast instead.
+fn encode_base64_array(
+    state: &mut State,
+    expression: sql::ast::Expression,
+) -> sql::ast::Expression {
+    let bytes_table = state.make_table_alias("bytes".to_string());
+    let element_column = sql::helpers::make_column_alias("element".to_string());
+    let ordinality_column = sql::helpers::make_column_alias("ordinality".to_string());
+    let column_reference = |column: &sql::ast::ColumnAlias| {
+        sql::ast::Expression::ColumnReference(sql::ast::ColumnReference::AliasedColumn {
+            table: sql::ast::TableReference::AliasedTable(bytes_table.clone()),
+            column: column.clone(),
+        })
+    };
+    let element = column_reference(&element_column);
+    let ordinality = column_reference(&ordinality_column);
+
+    let mut select = sql::helpers::simple_select(vec![(
+        element_column.clone(),
+        sql::ast::Expression::OrderedAggregateCall {
+            function: sql::ast::Function::Unknown("array_agg".to_string()),
+            args: vec![sql::helpers::encode_base64(element)],
+            order_by: sql::ast::OrderBy {
+                elements: vec![sql::ast::OrderByElement {
+                    target: ordinality,
+                    direction: sql::ast::OrderByDirection::Asc,
+                }],
+            },
+        },
+    )]);
+    select.from = Some(sql::ast::From::Unnest {
+        expression: expression.clone(),
+        alias: bytes_table,
+        columns: vec![element_column],
+        ordinality_column: Some(ordinality_column),
+    });
+
+    sql::ast::Expression::FunctionCall {
+        function: sql::ast::Function::Coalesce,
+        args: vec![
+            sql::ast::Expression::CorrelatedSubSelect(Box::new(select)),
+            wrap_array_in_type_representation(expression, Some(sql::helpers::text_type_name())),
+        ],
     }
 }
 
@@ -490,20 +864,33 @@ fn wrap_array_in_type_representation(
 /// For columns of those type representation, we wrap the result in a cast.
 fn wrap_in_type_representation(
     expression: sql::ast::Expression,
-    column_type_representation: Option<&TypeRepresentation>,
+    cast_type: Option<sql::ast::ScalarTypeName>,
 ) -> sql::ast::Expression {
-    match column_type_representation {
+    match cast_type {
         None => expression,
-        Some(type_rep) => {
-            if let Some(cast_type) = get_type_representation_cast_type(type_rep) {
-                sql::ast::Expression::Cast {
-                    expression: Box::new(expression),
-                    r#type: sql::ast::ScalarType::BaseType(cast_type),
-                }
-            } else {
-                expression
-            }
-        }
+        Some(cast_type) => sql::ast::Expression::Cast {
+            expression: Box::new(expression),
+            r#type: sql::ast::ScalarType::BaseType(cast_type),
+        },
+    }
+}
+
+/// The type to cast values of a scalar type to when selecting them, if any.
+/// A cast configured for the scalar type takes precedence over the one implied by its type
+/// representation.
+pub(crate) fn type_representation_cast(
+    env: &Env,
+    scalar_type: &models::ScalarTypeName,
+) -> Option<sql::ast::ScalarTypeName> {
+    match env
+        .lookup_scalar_type(scalar_type)
+        .ok()
+        .and_then(|t| t.cast_as.as_ref())
+    {
+        Some(cast_as) => Some(sql::ast::ScalarTypeName::Unqualified(cast_as.clone())),
+        None => env
+            .lookup_type_representation(scalar_type)
+            .and_then(get_type_representation_cast_type),
     }
 }
 
@@ -517,6 +904,8 @@ fn get_type_representation_cast_type(
         TypeRepresentation::Int64AsString | TypeRepresentation::BigDecimalAsString => {
             Some(sql::helpers::text_type_name())
         }
+        // hstore values are sent as json objects, using the cast the extension provides.
+        TypeRepresentation::Hstore => Some(sql::helpers::jsonb_type_name()),
 
         // In these situations the type representation should be the same as
         // the expression, so we don't cast it.
@@ -536,6 +925,7 @@ fn get_type_representation_cast_type(
         | TypeRepresentation::UUID
         | TypeRepresentation::Geography
         | TypeRepresentation::Geometry
+        | TypeRepresentation::Bytes
         | TypeRepresentation::Json
         | TypeRepresentation::Enum(_) => None,
     }
@@ -6,6 +6,7 @@ use ndc_models as models;
 use query_engine_metadata::metadata;
 use query_engine_sql::sql::helpers::where_exists_select;
 
+use super::fields;
 use super::relationships;
 use super::root;
 use super::values;
@@ -20,6 +21,10 @@ use query_engine_metadata::metadata::database;
 use query_engine_sql::sql;
 use std::collections::VecDeque;
 
+/// `IN` comparisons against more values than this are translated to `= ANY` with the values
+/// bound as a single array.
+const IN_LIST_ARRAY_THRESHOLD: usize = 64;
+
 /// Translate a boolean expression to a SQL expression.
 pub fn translate(
     env: &Env,
@@ -63,40 +68,26 @@ pub fn translate_expression_with_joins(
             let and_exprs = expressions
                 .iter()
                 .map(|expr| {
-                    translate_expression_with_joins(env, state, root_and_current_tables, expr)
+                    let (expr, joins) =
+                        translate_expression_with_joins(env, state, root_and_current_tables, expr)?;
+                    acc_joins.extend(joins);
+                    Ok(expr)
                 })
-                .try_fold(
-                    sql::ast::Expression::Value(sql::ast::Value::Bool(true)),
-                    |acc, expr| {
-                        let (right, right_joins) = expr?;
-                        acc_joins.extend(right_joins);
-                        Ok(sql::ast::Expression::And {
-                            left: Box::new(acc),
-                            right: Box::new(right),
-                        })
-                    },
-                )?;
-            Ok((and_exprs, acc_joins))
+                .collect::<Result<Vec<_>, Error>>()?;
+            Ok((sql::helpers::fold_and(and_exprs), acc_joins))
         }
         models::Expression::Or { expressions } => {
             let mut acc_joins = vec![];
             let or_exprs = expressions
                 .iter()
                 .map(|expr| {
-                    translate_expression_with_joins(env, state, root_and_current_tables, expr)
+                    let (expr, joins) =
+                        translate_expression_with_joins(env, state, root_and_current_tables, expr)?;
+                    acc_joins.extend(joins);
+                    Ok(expr)
                 })
-                .try_fold(
-                    sql::ast::Expression::Value(sql::ast::Value::Bool(false)),
-                    |acc, expr| {
-                        let (right, right_joins) = expr?;
-                        acc_joins.extend(right_joins);
-                        Ok(sql::ast::Expression::Or {
-                            left: Box::new(acc),
-                            right: Box::new(right),
-                        })
-                    },
-                )?;
-            Ok((or_exprs, acc_joins))
+                .collect::<Result<Vec<_>, Error>>()?;
+            Ok((sql::helpers::fold_or(or_exprs), acc_joins))
         }
         models::Expression::Not { expression } => {
             let (expr, joins) =
@@ -139,9 +130,41 @@ pub fn translate_expression_with_joins(
                     }
                     models::ComparisonValue::Scalar { value: json_value } => match json_value {
                         serde_json::Value::Array(values) => {
+                            // Null never equals anything, so null values in the list match no
+                            // rows, as with `_eq`. We leave them out rather than let them turn
+                            // the comparison into null, which `NOT` would not turn into true.
+                            let values = values
+                                .iter()
+                                .filter(|value| !value.is_null())
+                                .collect::<Vec<_>>();
                             // The expression on the left is definitely not IN an empty list of values
                             if values.is_empty() {
                                 Ok((sql::helpers::false_expr(), joins))
+                            }
+                            // Bind long lists of scalars as the text of a single array rather
+                            // than as a parameter per value, which keeps the query text the same
+                            // whatever the length.
+                            else if let Some(array_literal) =
+                                in_list_array_literal(env, &left_typ, &values)
+                            {
+                                let right = sql::ast::Expression::Cast {
+                                    expression: Box::new(sql::ast::Expression::Value(
+                                        sql::ast::Value::String(array_literal),
+                                    )),
+                                    r#type: values::type_to_ast_scalar_type(
+                                        env,
+                                        &database::Type::ArrayType(Box::new(left_typ.clone())),
+                                    )?,
+                                };
+
+                                Ok((
+                                    sql::ast::Expression::BinaryArrayOperation {
+                                        left: Box::new(left),
+                                        operator: sql::ast::BinaryArrayOperator::EqualAny,
+                                        right: vec![right],
+                                    },
+                                    joins,
+                                ))
                             } else {
                                 let right = values
                                     .iter()
@@ -151,9 +174,9 @@ pub fn translate_expression_with_joins(
                                             state,
                                             root_and_current_tables,
                                             &models::ComparisonValue::Scalar {
-                                                value: value.clone(),
+                                                value: (*value).clone(),
                                             },
-                                            &database::Type::ScalarType(left_typ.clone()),
+                                            &left_typ,
                                         )?;
                                         joins.extend(right_joins);
                                         Ok(right)
@@ -170,12 +193,13 @@ pub fn translate_expression_with_joins(
                                 ))
                             }
                         }
-                        _ => Err(Error::TypeMismatch(json_value.clone(), left_typ)),
+                        _ => Err(Error::TypeMismatch(
+                            json_value.clone(),
+                            op.argument_type.clone(),
+                        )),
                     },
                     models::ComparisonValue::Variable { .. } => {
-                        let array_type = database::Type::ArrayType(Box::new(
-                            database::Type::ScalarType(left_typ),
-                        ));
+                        let array_type = database::Type::ArrayType(Box::new(left_typ));
                         let (right, right_joins) = translate_comparison_value(
                             env,
                             state,
@@ -203,12 +227,28 @@ pub fn translate_expression_with_joins(
                     translate_comparison_target(env, state, root_and_current_tables, column)?;
                 joins.extend(left_joins);
 
+                if let Some(expected) = boolean_shortcut(env, &left_typ, &op, value) {
+                    let expression = if expected {
+                        left
+                    } else {
+                        sql::ast::Expression::Not(Box::new(left))
+                    };
+                    return Ok((expression, joins));
+                }
+
+                // Operators on arrays compare against arrays of the same element type.
+                let argument_type = match left_typ {
+                    database::Type::ArrayType(_) => database::Type::ArrayType(Box::new(
+                        database::Type::ScalarType(op.argument_type.clone()),
+                    )),
+                    _ => database::Type::ScalarType(op.argument_type.clone()),
+                };
                 let (right, right_joins) = translate_comparison_value(
                     env,
                     state,
                     root_and_current_tables,
                     value,
-                    &database::Type::ScalarType(op.argument_type.clone()),
+                    &argument_type,
                 )?;
                 joins.extend(right_joins);
 
@@ -266,6 +306,34 @@ pub fn translate_expression_with_joins(
     }
 }
 
+/// Comparisons of boolean columns with `true` or `false` are translated to the column itself or
+/// its negation, unless strict comparisons are configured. Both forms give the same results,
+/// including for nulls, but the short form matches partial indexes such as `WHERE is_active`.
+fn boolean_shortcut(
+    env: &Env,
+    left_typ: &database::Type,
+    op: &metadata::ComparisonOperator,
+    value: &models::ComparisonValue,
+) -> Option<bool> {
+    if env.strict_boolean_comparisons() || op.operator_kind != metadata::OperatorKind::Equal {
+        return None;
+    }
+    let database::Type::ScalarType(scalar_type) = left_typ else {
+        return None;
+    };
+    if env.lookup_type_representation(scalar_type) != Some(&metadata::TypeRepresentation::Boolean) {
+        return None;
+    }
+    match value {
+        models::ComparisonValue::Scalar {
+            value: serde_json::Value::Bool(expected),
+        } => Some(*expected),
+        models::ComparisonValue::Scalar { .. }
+        | models::ComparisonValue::Column { .. }
+        | models::ComparisonValue::Variable { .. } => None,
+    }
+}
+
 /// Given a vector of PathElements and the table alias for the table the
 /// expression is over, we return a join in the form of:
 ///
@@ -325,6 +393,7 @@ fn translate_comparison_pathelements(
             // get the relationship table
             let relationship_name = &relationship;
             let relationship = env.lookup_relationship(relationship_name)?;
+            state.count_join()?;
 
             // new alias for the target table
             let target_table_alias: sql::ast::TableAlias =
@@ -449,18 +518,20 @@ fn translate_comparison_target(
 
             // get the unrelated table information from the metadata.
             let collection_info = env.lookup_fields_info(&table_ref.source)?;
-            let ColumnInfo { name, .. } = collection_info.lookup_column(name)?;
-
-            Ok((
-                wrap_in_field_path(
-                    &field_path.into(),
+            let expression = match collection_info.lookup_computed_field(name) {
+                Some(computed_field) => {
+                    fields::translate_computed_field(&table_ref, computed_field)
+                }
+                None => {
+                    let ColumnInfo { name, .. } = collection_info.lookup_filterable_column(name)?;
                     sql::ast::Expression::ColumnReference(sql::ast::ColumnReference::TableColumn {
                         table: table_ref.reference.clone(),
                         name,
-                    }),
-                ),
-                joins,
-            ))
+                    })
+                }
+            };
+
+            Ok((wrap_in_field_path(&field_path.into(), expression), joins))
         }
 
         // Compare a column from the root table.
@@ -469,19 +540,21 @@ fn translate_comparison_target(
             // get the unrelated table information from the metadata.
             let collection_info = env.lookup_fields_info(&root_table.source)?;
 
-            // find the requested column in the tables columns.
-            let ColumnInfo { name, .. } = collection_info.lookup_column(name)?;
-
-            Ok((
-                wrap_in_field_path(
-                    &field_path.into(),
+            // find the requested column in the tables columns, or its computed fields.
+            let expression = match collection_info.lookup_computed_field(name) {
+                Some(computed_field) => {
+                    fields::translate_computed_field(root_table, computed_field)
+                }
+                None => {
+                    let ColumnInfo { name, .. } = collection_info.lookup_filterable_column(name)?;
                     sql::ast::Expression::ColumnReference(sql::ast::ColumnReference::TableColumn {
                         table: root_table.reference.clone(),
                         name,
-                    }),
-                ),
-                vec![],
-            ))
+                    })
+                }
+            };
+
+            Ok((wrap_in_field_path(&field_path.into(), expression), vec![]))
         }
     }
 }
@@ -523,6 +596,7 @@ pub fn translate_exists_in_collection(
             collection,
             arguments,
         } => {
+            state.count_join()?;
             let arguments = relationships::make_relationship_arguments(
                 relationships::MakeRelationshipArguments {
                     caller_arguments: BTreeMap::new(),
@@ -560,7 +634,14 @@ pub fn translate_exists_in_collection(
                 &new_root_and_current_tables,
                 predicate,
             )?;
-            select.where_ = sql::ast::Where(expr);
+            // Rows the collection hides from queries must not be found this way either.
+            let mut restrictions = vec![expr];
+            restrictions.extend(root::translate_collection_restrictions(
+                env,
+                state,
+                &new_root_and_current_tables.current_table,
+            )?);
+            select.where_ = sql::ast::Where(sql::helpers::fold_and(restrictions));
 
             select.joins = expr_joins;
 
@@ -578,6 +659,7 @@ pub fn translate_exists_in_collection(
         } => {
             // get the relationship table
             let relationship = env.lookup_relationship(&relationship)?;
+            state.count_join()?;
 
             let arguments = relationships::make_relationship_arguments(
                 relationships::MakeRelationshipArguments {
@@ -622,6 +704,13 @@ pub fn translate_exists_in_collection(
                 &new_root_and_current_tables,
                 predicate,
             )?;
+            let mut restrictions = vec![exists_cond];
+            restrictions.extend(root::translate_collection_restrictions(
+                env,
+                state,
+                &new_root_and_current_tables.current_table,
+            )?);
+            let exists_cond = sql::helpers::fold_and(restrictions);
 
             // relationship where clause
             let cond = relationships::translate_column_mapping(
@@ -658,7 +747,7 @@ pub fn translate_exists_in_collection(
             let collection_fields_info = env.lookup_fields_info(&table.source)?;
 
             // The initial column we start with, it's reference, and it's source.
-            let column_info = collection_fields_info.lookup_column(&column_name)?;
+            let column_info = collection_fields_info.lookup_filterable_column(&column_name)?;
             let column_expr =
                 sql::ast::Expression::ColumnReference(sql::ast::ColumnReference::AliasedColumn {
                     table: table.reference.clone(),
@@ -714,6 +803,7 @@ pub fn translate_exists_in_collection(
                 expression: select_expression,
                 alias: alias.clone(),
                 columns: vec![],
+                ordinality_column: None,
             };
 
             // Define a new root and current table structure pointing the current table
@@ -749,18 +839,18 @@ fn get_comparison_target_type(
     env: &Env,
     root_and_current_tables: &RootAndCurrentTables,
     column: &models::ComparisonTarget,
-) -> Result<models::ScalarTypeName, Error> {
+) -> Result<database::Type, Error> {
     match column {
         models::ComparisonTarget::RootCollectionColumn { name, field_path } => {
-            let column = env
+            let column_type = env
                 .lookup_fields_info(&root_and_current_tables.root_table.source)?
-                .lookup_column(name)?;
+                .lookup_field_type(name)?;
 
             let mut field_path = match field_path {
                 None => VecDeque::new(),
                 Some(field_path) => field_path.iter().collect(),
             };
-            get_column_scalar_type_name(env, &column.r#type, &mut field_path)
+            get_column_type(env, &column_type, &mut field_path)
         }
         models::ComparisonTarget::Column {
             name,
@@ -773,48 +863,51 @@ fn get_comparison_target_type(
             };
             match path.last() {
                 None => {
-                    let column = env
+                    let column_type = env
                         .lookup_fields_info(&root_and_current_tables.current_table.source)?
-                        .lookup_column(name)?;
+                        .lookup_field_type(name)?;
 
-                    get_column_scalar_type_name(env, &column.r#type, &mut field_path)
+                    get_column_type(env, &column_type, &mut field_path)
                 }
                 Some(last) => {
-                    let column = env
+                    let column_type = env
                         .lookup_fields_info(&TableSource::Collection(
                             env.lookup_relationship(&last.relationship)?
                                 .target_collection
                                 .clone(),
                         ))?
-                        .lookup_column(name)?;
+                        .lookup_field_type(name)?;
 
-                    get_column_scalar_type_name(env, &column.r#type, &mut field_path)
+                    get_column_type(env, &column_type, &mut field_path)
                 }
             }
         }
     }
 }
 
-/// Extract the scalar type name of a column down their nested field path.
-/// Will error if path do not lead to a scalar type.
-fn get_column_scalar_type_name(
+/// Extract the type of a column down their nested field path.
+/// Will error if path do not lead to a scalar type or an array.
+fn get_column_type(
     env: &Env,
     typ: &database::Type,
     field_path: &mut VecDeque<&models::FieldName>,
-) -> Result<models::ScalarTypeName, Error> {
+) -> Result<database::Type, Error> {
     let field = field_path.pop_front();
     match typ {
         database::Type::ScalarType(scalar_type) => match field {
-            None => Ok(scalar_type.clone()),
+            None => Ok(typ.clone()),
             // todo: what about json?
             Some(field) => Err(Error::ColumnNotFoundInCollection(
                 field.clone(),
                 scalar_type.as_str().into(),
             )),
         },
-        database::Type::ArrayType(_) => Err(Error::NonScalarTypeUsedInOperator {
-            r#type: typ.clone(),
-        }),
+        database::Type::ArrayType(_) => match field {
+            None => Ok(typ.clone()),
+            Some(_) => Err(Error::NonScalarTypeUsedInOperator {
+                r#type: typ.clone(),
+            }),
+        },
         database::Type::CompositeType(composite_type) => match field {
             None => Err(Error::NonScalarTypeUsedInOperator {
                 r#type: database::Type::CompositeType(composite_type.clone()),
@@ -832,7 +925,7 @@ fn get_column_scalar_type_name(
                                 name.as_str().into(),
                             ))?
                             .r#type;
-                        get_column_scalar_type_name(env, typ, field_path)
+                        get_column_type(env, typ, field_path)
                     }
                     CompositeTypeInfo::Table { info, name } => {
                         let typ = &info
@@ -840,7 +933,7 @@ fn get_column_scalar_type_name(
                             .get(field)
                             .ok_or(Error::ColumnNotFoundInCollection((*field).clone(), name))?
                             .r#type;
-                        get_column_scalar_type_name(env, typ, field_path)
+                        get_column_type(env, typ, field_path)
                     }
                 }
             }
@@ -859,6 +952,7 @@ fn make_unnest_subquery(
         expression,
         columns: vec![sql::helpers::make_column_alias("value".to_string())],
         alias: subquery_alias,
+        ordinality_column: None,
     };
     let mut subquery = sql::helpers::simple_select(vec![sql::helpers::make_column(
         subquery_reference,
@@ -877,3 +971,35 @@ fn underlying_type_name(typ: database::Type) -> models::TypeName {
         database::Type::ArrayType(typ) => underlying_type_name(*typ),
     }
 }
+
+/// The text of a Postgres array of the values of an `IN` list, if there are more of them than
+/// the threshold and they are all scalars which can be cast to the type from their text.
+/// Strings are quoted, so that commas, braces and the like in them are not taken for the syntax
+/// of the array.
+fn in_list_array_literal(
+    env: &Env,
+    r#type: &database::Type,
+    values: &[&serde_json::Value],
+) -> Option<String> {
+    if values.len() <= IN_LIST_ARRAY_THRESHOLD {
+        return None;
+    }
+    match r#type {
+        // Binary values arrive encoded in base64, and need decoding one by one.
+        database::Type::ScalarType(scalar_type) if !values::is_bytes(env, scalar_type) => {}
+        _ => return None,
+    }
+    let elements = values
+        .iter()
+        .map(|value| match value {
+            serde_json::Value::Bool(value) => Some(value.to_string()),
+            serde_json::Value::Number(value) => Some(value.to_string()),
+            serde_json::Value::String(value) => Some(format!(
+                "\"{}\"",
+                value.replace('\\', "\\\\").replace('"', "\\\"")
+            )),
+            _ => None,
+        })
+        .collect::<Option<Vec<_>>>()?;
+    Some(format!("{{{}}}", elements.join(",")))
+}
@@ -0,0 +1,33 @@
+//! Limits on the size of the SQL we generate for a request.
+//!
+//! A client can send arbitrarily deep relationship selections or arbitrarily large predicates.
+//! Rather than generating SQL the database would take ages to plan, we reject such requests
+//! during translation when they exceed the configured limits.
+
+use ndc_models as models;
+
+/// Limits on the size of a translated query. `None` means unlimited.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Limits {
+    /// How deeply relationships may be nested in a selection.
+    pub max_relationship_depth: Option<u32>,
+    /// How many relationships may be joined across the whole query.
+    pub max_joins: Option<u32>,
+    /// How many nodes the predicates of the whole query may have in total.
+    pub max_predicate_nodes: Option<u32>,
+}
+
+/// The number of nodes in a predicate, including those of any `EXISTS` subpredicates.
+pub fn count_predicate_nodes(expression: &models::Expression) -> u32 {
+    match expression {
+        models::Expression::And { expressions } | models::Expression::Or { expressions } => {
+            1 + expressions.iter().map(count_predicate_nodes).sum::<u32>()
+        }
+        models::Expression::Not { expression } => 1 + count_predicate_nodes(expression),
+        models::Expression::UnaryComparisonOperator { .. }
+        | models::Expression::BinaryComparisonOperator { .. } => 1,
+        models::Expression::Exists { predicate, .. } => {
+            1 + predicate.as_deref().map_or(0, count_predicate_nodes)
+        }
+    }
+}
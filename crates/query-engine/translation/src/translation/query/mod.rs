@@ -1,37 +1,55 @@
 //! Translate an incoming `QueryRequest`.
 
-mod aggregates;
+pub mod aggregates;
+pub mod distribution;
 pub mod fields;
 pub mod filtering;
+pub mod limits;
 pub mod native_queries;
+pub mod policies;
 pub mod relationships;
 pub mod root;
 mod sorting;
 pub mod values;
 pub mod variables;
 
+use std::collections::BTreeMap;
+
 use ndc_models as models;
 
 use crate::translation::error::Error;
 use crate::translation::helpers::{Env, State};
+use crate::translation::options::Options;
 use query_engine_metadata::metadata;
 use query_engine_sql::sql;
 
 /// Translate the incoming QueryRequest to an ExecutionPlan (SQL) to be run against the database.
 pub fn translate(
     metadata: &metadata::Metadata,
+    options: &Options,
     query_request: models::QueryRequest,
 ) -> Result<sql::execution_plan::ExecutionPlan<sql::execution_plan::Query>, Error> {
-    let mut state = State::new();
-    let variables_from = state.make_variables_table(&query_request.variables);
+    let mut state = State::with_limits(options.limits);
+    let variables_source = variables_source(options, query_request.variables.as_deref());
+    let variables_from = state.make_variables_table(&query_request.variables, variables_source);
     let variables_table_ref = variables_from.clone().map(|(_, table_ref)| table_ref);
     let env = Env::new(
         metadata,
         query_request.collection_relationships,
-        None,
-        None,
         variables_table_ref,
+        options,
+    );
+
+    distribution::warn_if_not_routable(
+        &env,
+        &query_request.collection,
+        query_request.query.predicate.as_ref(),
     );
+    let snapshot = if options.snapshot_reads {
+        root::translate_snapshot(&env, &query_request.collection, &query_request.arguments)?
+    } else {
+        None
+    };
 
     let select_set = root::translate_query(
         &env,
@@ -39,11 +57,26 @@ pub fn translate(
         &root::MakeFrom::Collection {
             name: query_request.collection.clone(),
             arguments: query_request.arguments.clone(),
+            column_arguments: BTreeMap::new(),
         },
         None,
         &query_request.query,
     )?;
 
+    // A query whose predicate can never hold returns no rows, which we may know the result of
+    // without running it.
+    let empty_result = if state.root_predicate_never_holds() {
+        empty_result(
+            &query_request.query,
+            query_request.variables.as_ref().map(Vec::len),
+        )
+    } else {
+        None
+    };
+
+    let planner_hints = state.take_planner_hints();
+    let statement_timeout_ms = state.statement_timeout_ms();
+
     // form a single JSON item shaped `{ rows: [], aggregates: {} }`
     // that matches the models::RowSet type
     let json_select = sql::helpers::select_rowset(
@@ -77,9 +110,233 @@ pub fn translate(
     // normalize ast
     let json_select = sql::rewrites::constant_folding::normalize_select(json_select);
 
-    Ok(sql::execution_plan::simple_query_execution_plan(
+    let mut plan = sql::execution_plan::simple_query_execution_plan(
+        env.dialect(),
         query_request.variables,
+        variables_source,
         query_request.collection,
         json_select,
-    ))
+    );
+    plan.query.empty_result = empty_result;
+    plan.query.planner_hints = planner_hints;
+    if let Some(snapshot) = snapshot {
+        read_from_snapshot(&mut plan, snapshot);
+    }
+    if let Some(statement_timeout_ms) = statement_timeout_ms {
+        set_statement_timeout(&mut plan, options.isolation_level, statement_timeout_ms);
+    }
+    Ok(plan)
+}
+
+/// The result of a query which returns no rows: a row set for each set of variables, with no rows
+/// and counts of zero. The other aggregates depend on their aggregate functions, so we only know
+/// the result of queries which ask for counts alone.
+fn empty_result(query: &models::Query, variable_sets: Option<usize>) -> Option<serde_json::Value> {
+    let mut row_set = serde_json::Map::new();
+    if let Some(aggregates) = &query.aggregates {
+        let mut aggregate_values = serde_json::Map::new();
+        for (name, aggregate) in aggregates {
+            match aggregate {
+                models::Aggregate::StarCount {} | models::Aggregate::ColumnCount { .. } => {
+                    aggregate_values.insert(name.to_string(), serde_json::Value::from(0));
+                }
+                models::Aggregate::SingleColumn { .. } => return None,
+            }
+        }
+        row_set.insert(
+            "aggregates".to_string(),
+            serde_json::Value::Object(aggregate_values),
+        );
+    }
+    // Rows are returned unless only aggregates were asked for, as in `root::translate_query`.
+    let returns_fields = query
+        .fields
+        .as_ref()
+        .is_some_and(|fields| !fields.is_empty());
+    if returns_fields || query.aggregates.is_none() {
+        row_set.insert("rows".to_string(), serde_json::Value::Array(vec![]));
+    }
+    let row_set = serde_json::Value::Object(row_set);
+    Some(serde_json::Value::Array(vec![
+        row_set;
+        variable_sets.unwrap_or(1)
+    ]))
+}
+
+/// Run the query in a read-only transaction which reads the data as it was in a snapshot.
+/// Snapshots can only be imported by transactions which see a single snapshot throughout, so
+/// the transaction is at least `REPEATABLE READ`.
+fn read_from_snapshot(
+    plan: &mut sql::execution_plan::ExecutionPlan<sql::execution_plan::Query>,
+    snapshot: sql::ast::transaction::SetSnapshot,
+) {
+    plan.pre = sql::helpers::begin(
+        sql::ast::transaction::IsolationLevel::RepeatableRead,
+        sql::ast::transaction::TransactionMode::ReadOnly,
+    );
+    plan.pre.push(sql::helpers::set_snapshot(snapshot));
+    plan.post = sql::helpers::commit();
+    plan.query.reads_from_snapshot = true;
+}
+
+/// Run the query in a read-only transaction which sets its own statement timeout, for tables
+/// which need a different budget from the rest of the database. A transaction the query already
+/// begins, such as to read from a snapshot, keeps its isolation level.
+fn set_statement_timeout(
+    plan: &mut sql::execution_plan::ExecutionPlan<sql::execution_plan::Query>,
+    isolation_level: sql::ast::transaction::IsolationLevel,
+    statement_timeout_ms: u64,
+) {
+    if plan.pre.is_empty() {
+        plan.pre = sql::helpers::begin(
+            isolation_level,
+            sql::ast::transaction::TransactionMode::ReadOnly,
+        );
+        plan.post = sql::helpers::commit();
+    }
+    plan.pre.push(sql::helpers::set_local(
+        "statement_timeout".to_string(),
+        statement_timeout_ms.to_string(),
+    ));
+}
+
+/// Beyond this many variable sets, passing them all in a single parameter risks running into the
+/// limits of the database, so we copy them into a temporary table instead.
+const VARIABLES_TABLE_THRESHOLD: usize = 10_000;
+
+/// Decide how to pass the variable sets of a foreach query to the database. Temporary tables are
+/// only used when the configuration enables them, as not every server or connection pooler
+/// allows creating them.
+fn variables_source(
+    options: &Options,
+    variables: Option<&[BTreeMap<models::VariableName, serde_json::Value>]>,
+) -> sql::execution_plan::VariablesSource {
+    match variables {
+        Some(variables)
+            if options.temporary_variables_table
+                && variables.len() > VARIABLES_TABLE_THRESHOLD
+                && options.dialect.dialect().supports_temporary_tables() =>
+        {
+            sql::execution_plan::VariablesSource::TemporaryTable
+        }
+        _ => sql::execution_plan::VariablesSource::Parameter,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use indexmap::IndexMap;
+
+    use super::*;
+
+    fn query(
+        fields: Option<IndexMap<models::FieldName, models::Field>>,
+        aggregates: Option<IndexMap<models::FieldName, models::Aggregate>>,
+    ) -> models::Query {
+        models::Query {
+            aggregates,
+            fields,
+            limit: None,
+            offset: None,
+            order_by: None,
+            predicate: None,
+        }
+    }
+
+    #[test]
+    fn empty_results_have_no_rows() {
+        let fields = IndexMap::from([(
+            "id".into(),
+            models::Field::Column {
+                column: "id".into(),
+                fields: None,
+                arguments: BTreeMap::new(),
+            },
+        )]);
+        assert_eq!(
+            empty_result(&query(Some(fields), None), None),
+            Some(serde_json::json!([{ "rows": [] }]))
+        );
+    }
+
+    #[test]
+    fn empty_results_count_zero_rows_for_each_variable_set() {
+        let aggregates = IndexMap::from([
+            ("count".into(), models::Aggregate::StarCount {}),
+            (
+                "ids".into(),
+                models::Aggregate::ColumnCount {
+                    column: "id".into(),
+                    field_path: None,
+                    distinct: true,
+                },
+            ),
+        ]);
+        assert_eq!(
+            empty_result(&query(None, Some(aggregates)), Some(2)),
+            Some(serde_json::json!([
+                { "aggregates": { "count": 0, "ids": 0 } },
+                { "aggregates": { "count": 0, "ids": 0 } },
+            ]))
+        );
+    }
+
+    fn variable_sets(count: usize) -> Vec<BTreeMap<models::VariableName, serde_json::Value>> {
+        vec![BTreeMap::new(); count]
+    }
+
+    #[test]
+    fn variables_are_passed_as_a_parameter_unless_temporary_tables_are_enabled() {
+        let variables = variable_sets(VARIABLES_TABLE_THRESHOLD + 1);
+        assert_eq!(
+            variables_source(&Options::default(), Some(&variables)),
+            sql::execution_plan::VariablesSource::Parameter
+        );
+
+        let options = Options {
+            temporary_variables_table: true,
+            ..Options::default()
+        };
+        assert_eq!(
+            variables_source(&options, Some(&variables)),
+            sql::execution_plan::VariablesSource::TemporaryTable
+        );
+        assert_eq!(
+            variables_source(&options, Some(&variable_sets(VARIABLES_TABLE_THRESHOLD))),
+            sql::execution_plan::VariablesSource::Parameter
+        );
+        assert_eq!(
+            variables_source(&options, None),
+            sql::execution_plan::VariablesSource::Parameter
+        );
+    }
+
+    #[test]
+    fn variables_are_passed_as_a_parameter_to_cockroach() {
+        let options = Options {
+            dialect: sql::dialect::SqlDialect::CockroachDB,
+            temporary_variables_table: true,
+            ..Options::default()
+        };
+        assert_eq!(
+            variables_source(
+                &options,
+                Some(&variable_sets(VARIABLES_TABLE_THRESHOLD + 1))
+            ),
+            sql::execution_plan::VariablesSource::Parameter
+        );
+    }
+
+    #[test]
+    fn empty_results_are_unknown_for_other_aggregates() {
+        let aggregates = IndexMap::from([(
+            "max_id".into(),
+            models::Aggregate::SingleColumn {
+                column: "id".into(),
+                field_path: None,
+                function: "max".into(),
+            },
+        )]);
+        assert_eq!(empty_result(&query(None, Some(aggregates)), None), None);
+    }
 }
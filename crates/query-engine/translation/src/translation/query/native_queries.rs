@@ -1,6 +1,7 @@
 //! Handle native queries translation after building the query.
 
 use std::borrow::Cow;
+use std::collections::BTreeMap;
 
 use ndc_models as models;
 use ref_cast::RefCast;
@@ -34,66 +35,14 @@ pub fn translate(
 
     // for each found table expression
     for native_query in native_queries {
-        // convert metadata representation to sql::ast representation
-        let sql: Vec<sql::ast::RawSql> = native_query
-            .info
-            .sql
-            .sql()
-            .map_err(Error::InternalError)?
-            .0
-            .into_iter()
-            .map(|part| match part {
-                metadata::NativeQueryPart::Text(text) => Ok(sql::ast::RawSql::RawText(text)),
-                metadata::NativeQueryPart::Parameter(param) => {
-                    let (typ, nullable) = match native_query
-                        .info
-                        .arguments
-                        .get(models::ArgumentName::ref_cast(&param))
-                    {
-                        None => Err(Error::ArgumentNotFound(param.to_string().into())),
-                        Some(argument) => Ok((&argument.r#type, &argument.nullable)),
-                    }?;
-                    let argument = native_query
-                        .arguments
-                        .get(models::ArgumentName::ref_cast(&param))
-                        .map_or_else(
-                            || {
-                                // If the argument is missing ...
-                                match nullable {
-                                    // ... and the type is nullable, we treat this like a null value has been passed explicitly ...
-                                    metadata::Nullable::Nullable => {
-                                        Ok(Cow::Owned(models::Argument::Literal {
-                                            value: serde_json::Value::Null,
-                                        }))
-                                    }
-                                    // ... but if the type is non-nullable, we should have received a value and this is error
-                                    metadata::Nullable::NonNullable => {
-                                        Err(Error::ArgumentNotFound(param.to_string().into()))
-                                    }
-                                }
-                            },
-                            |arg| Ok(Cow::Borrowed(arg)),
-                        )?;
-
-                    let exp = match argument.as_ref() {
-                        models::Argument::Literal { value } => {
-                            values::translate(env, &mut translation_state, value, typ)
-                        }
-                        models::Argument::Variable { name } => match &variables_table {
-                            Err(err) => Err(err.clone()),
-                            Ok(variables_table) => variables::translate(
-                                env,
-                                &mut translation_state,
-                                variables_table.clone(),
-                                name,
-                                typ,
-                            ),
-                        },
-                    }?;
-                    Ok(sql::ast::RawSql::Expression(exp))
-                }
-            })
-            .collect::<Result<Vec<sql::ast::RawSql>, Error>>()?;
+        let sql = translate_sql(
+            env,
+            &mut translation_state,
+            &variables_table,
+            &native_query.info,
+            &native_query.arguments,
+            &BTreeMap::new(),
+        )?;
 
         // add a cte
         ctes.push(sql::ast::CommonTableExpression {
@@ -106,6 +55,106 @@ pub fn translate(
     Ok((ctes, global_table_index))
 }
 
+/// Translate a native query whose arguments are taken, at least in part, from the columns of the
+/// rows of an outer query, as when it is the target of a relationship.
+///
+/// Such a native query cannot be a CTE of the whole query, so it becomes the CTE of a subquery
+/// of its own, which is correlated with the outer rows. Data-modifying statements are only
+/// allowed in top-level CTEs, so it does not need wrapping to guard against mutations.
+pub fn translate_correlated(
+    env: &Env,
+    state: &mut State,
+    name: &models::CollectionName,
+    info: &metadata::NativeQueryInfo,
+    arguments: &BTreeMap<models::ArgumentName, models::Argument>,
+    column_arguments: &BTreeMap<models::ArgumentName, sql::ast::Expression>,
+) -> Result<sql::ast::CommonTableExpression, Error> {
+    let variables_table = env.get_variables_table();
+    let sql = translate_sql(
+        env,
+        state,
+        &variables_table,
+        info,
+        arguments,
+        column_arguments,
+    )?;
+    Ok(sql::ast::CommonTableExpression {
+        alias: state.make_native_query_table_alias(name.as_str()),
+        column_names: None,
+        select: sql::ast::CTExpr::RawSql(sql),
+    })
+}
+
+/// Translate the SQL of a native query, replacing its parameters with its arguments.
+fn translate_sql(
+    env: &Env,
+    state: &mut State,
+    variables_table: &Result<sql::ast::TableReference, Error>,
+    info: &metadata::NativeQueryInfo,
+    arguments: &BTreeMap<models::ArgumentName, models::Argument>,
+    column_arguments: &BTreeMap<models::ArgumentName, sql::ast::Expression>,
+) -> Result<Vec<sql::ast::RawSql>, Error> {
+    // convert metadata representation to sql::ast representation
+    info.sql
+        .sql()
+        .map_err(Error::InternalError)?
+        .0
+        .into_iter()
+        .map(|part| match part {
+            metadata::NativeQueryPart::Text(text) => Ok(sql::ast::RawSql::RawText(text)),
+            metadata::NativeQueryPart::Parameter(param) => {
+                let (typ, nullable) =
+                    match info.arguments.get(models::ArgumentName::ref_cast(&param)) {
+                        None => Err(Error::ArgumentNotFound(param.to_string().into())),
+                        Some(argument) => Ok((&argument.r#type, &argument.nullable)),
+                    }?;
+
+                // arguments taken from the columns of the outer rows are cast to their type.
+                if let Some(column) = column_arguments.get(models::ArgumentName::ref_cast(&param)) {
+                    return Ok(sql::ast::RawSql::Expression(sql::ast::Expression::Cast {
+                        expression: Box::new(column.clone()),
+                        r#type: values::type_to_ast_scalar_type(env, typ)?,
+                    }));
+                }
+
+                let argument = arguments
+                    .get(models::ArgumentName::ref_cast(&param))
+                    .map_or_else(
+                        || {
+                            // If the argument is missing ...
+                            match nullable {
+                                // ... and the type is nullable, we treat this like a null value has been passed explicitly ...
+                                metadata::Nullable::Nullable => {
+                                    Ok(Cow::Owned(models::Argument::Literal {
+                                        value: serde_json::Value::Null,
+                                    }))
+                                }
+                                // ... but if the type is non-nullable, we should have received a value and this is error
+                                metadata::Nullable::NonNullable => {
+                                    Err(Error::ArgumentNotFound(param.to_string().into()))
+                                }
+                            }
+                        },
+                        |arg| Ok(Cow::Borrowed(arg)),
+                    )?;
+
+                let exp = match argument.as_ref() {
+                    models::Argument::Literal { value } => {
+                        values::translate(env, state, value, typ)
+                    }
+                    models::Argument::Variable { name } => match variables_table {
+                        Err(err) => Err(err.clone()),
+                        Ok(variables_table) => {
+                            variables::translate(env, state, variables_table.clone(), name, typ)
+                        }
+                    },
+                }?;
+                Ok(sql::ast::RawSql::Expression(exp))
+            }
+        })
+        .collect()
+}
+
 /// Wrap a CTE in another CTE so we can guard against mutations in queries.
 pub fn wrap_cte_in_cte(
     table_alias_index: &mut TableAliasIndex,
@@ -0,0 +1,53 @@
+//! Policies which restrict the rows of collections, whatever a request asks for.
+//!
+//! These do not replace the permissions of the engine in front of the connector; they are a
+//! second line of defense, applied to every query over the collection, including those over it
+//! as the target of a relationship.
+
+use std::collections::BTreeMap;
+
+use ndc_models as models;
+
+/// The policies of collections, by collection name.
+#[derive(Debug, Clone, Default)]
+pub struct CollectionPolicies(pub BTreeMap<models::CollectionName, CollectionPolicy>);
+
+/// Restrictions on the rows of a collection.
+#[derive(Debug, Clone, Default)]
+pub struct CollectionPolicy {
+    /// A predicate every row must satisfy, ANDed with the predicate of the query.
+    pub filter: Option<models::Expression>,
+    /// The most rows a query may return.
+    pub max_rows: Option<u32>,
+}
+
+impl CollectionPolicy {
+    /// Cap the limit a query asks for at the most rows the policy allows.
+    pub fn cap_limit(&self, limit: Option<u32>) -> Option<u32> {
+        match (limit, self.max_rows) {
+            (Some(limit), Some(max_rows)) => Some(limit.min(max_rows)),
+            (limit, None) => limit,
+            (None, max_rows) => max_rows,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn limits_are_capped_at_the_most_rows_allowed() {
+        let policy = CollectionPolicy {
+            filter: None,
+            max_rows: Some(100),
+        };
+        assert_eq!(policy.cap_limit(None), Some(100));
+        assert_eq!(policy.cap_limit(Some(10)), Some(10));
+        assert_eq!(policy.cap_limit(Some(1000)), Some(100));
+        assert_eq!(
+            CollectionPolicy::default().cap_limit(Some(1000)),
+            Some(1000)
+        );
+    }
+}
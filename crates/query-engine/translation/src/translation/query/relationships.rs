@@ -4,9 +4,11 @@ use std::collections::BTreeMap;
 
 use ndc_models as models;
 
+use super::distribution;
 use super::root;
 use crate::translation::error::Error;
 use crate::translation::helpers::{Env, State, TableSourceAndReference};
+use query_engine_metadata::metadata;
 use query_engine_sql::sql;
 
 #[derive(Debug)]
@@ -31,18 +33,24 @@ pub fn translate(
         .into_iter()
         .map(|join_field| {
             let relationship = env.lookup_relationship(&join_field.relationship_name)?;
-            let arguments = make_relationship_arguments(MakeRelationshipArguments {
-                caller_arguments: join_field.arguments,
-                relationship_arguments: relationship.arguments.clone(),
-            })?;
+            let (arguments, column_arguments) = make_join_arguments(
+                env,
+                current_table,
+                MakeRelationshipArguments {
+                    caller_arguments: join_field.arguments,
+                    relationship_arguments: relationship.arguments.clone(),
+                },
+            )?;
 
             // process inner query and get the SELECTs for the 'rows' and 'aggregates' fields.
+            state.enter_relationship()?;
             let select_set = root::translate_query(
                 env,
                 state,
                 &root::MakeFrom::Collection {
                     name: relationship.target_collection.clone(),
                     arguments,
+                    column_arguments,
                 },
                 // We ask to inject the join predicate into the where clause.
                 Some(root::JoinPredicate {
@@ -52,6 +60,7 @@ pub fn translate(
                 .as_ref(),
                 &join_field.query,
             )?;
+            state.exit_relationship();
 
             // form a single JSON item shaped `{ rows: [], aggregates: {} }`
             // that matches the models::RowSet type
@@ -82,7 +91,51 @@ pub fn translate(
         .collect::<Result<Vec<sql::ast::Join>, Error>>()
 }
 
+/// Select relationships using correlated subqueries rather than lateral joins.
+///
+/// Each relationship join produces a single row with a single column, which the select list
+/// refers to by the alias of the join. We replace that reference with the joined query itself.
+/// Joins which the select list does not refer to directly are kept as joins.
+pub fn select_as_subqueries(select: &mut sql::ast::Select, joins: Vec<sql::ast::Join>) {
+    for join in joins {
+        match find_join_reference(&mut select.select_list, join.alias()) {
+            Some(expression) => {
+                let (subquery, _) = join.get_select_and_alias();
+                *expression = sql::ast::Expression::CorrelatedSubSelect(subquery);
+            }
+            None => select.joins.push(join),
+        }
+    }
+}
+
+/// Find the column of the select list which refers to the join with the given alias.
+fn find_join_reference<'a>(
+    select_list: &'a mut sql::ast::SelectList,
+    join_alias: &sql::ast::TableAlias,
+) -> Option<&'a mut sql::ast::Expression> {
+    match select_list {
+        sql::ast::SelectList::SelectList(columns) => columns
+            .iter_mut()
+            .map(|(_, expression)| expression)
+            .find(|expression| {
+                matches!(
+                    expression,
+                    sql::ast::Expression::ColumnReference(
+                        sql::ast::ColumnReference::AliasedColumn {
+                            table: sql::ast::TableReference::AliasedTable(alias),
+                            column: _,
+                        },
+                    ) if alias == join_alias
+                )
+            }),
+        _ => None,
+    }
+}
+
 /// Given a relationship, turn it into a Where clause for a Join.
+///
+/// Relationships which map no columns use the condition of the computed relationship from the
+/// table to the target collection instead, if one is configured.
 pub fn translate_column_mapping(
     env: &Env,
     current_table: &TableSourceAndReference,
@@ -90,10 +143,40 @@ pub fn translate_column_mapping(
     expr: sql::ast::Expression,
     relationship: &models::Relationship,
 ) -> Result<sql::ast::Expression, Error> {
+    if relationship.column_mapping.is_empty() {
+        if let Some(computed_relationship) =
+            env.lookup_computed_relationship(&current_table.source, &relationship.target_collection)
+        {
+            return Ok(sql::ast::Expression::And {
+                left: Box::new(expr),
+                right: Box::new(translate_computed_relationship(
+                    &current_table.reference,
+                    target_collection_alias_reference,
+                    computed_relationship,
+                )),
+            });
+        }
+    }
+
     let table_info = env.lookup_fields_info(&current_table.source)?;
 
     let target_collection_info = env.lookup_collection(&relationship.target_collection)?;
 
+    // Citus can join distributed tables on the same shard when they are joined on their
+    // distribution columns, which we can add if configured to.
+    let expr = match distribution::translate_distribution_join(
+        env,
+        current_table,
+        target_collection_alias_reference,
+        relationship,
+    ) {
+        None => expr,
+        Some(distribution_join) => sql::ast::Expression::And {
+            left: Box::new(expr),
+            right: Box::new(distribution_join),
+        },
+    };
+
     relationship
         .column_mapping
         .iter()
@@ -125,6 +208,30 @@ pub fn translate_column_mapping(
         })
 }
 
+/// The condition of a computed relationship, with its placeholders replaced by references to
+/// the source and target tables.
+fn translate_computed_relationship(
+    source_reference: &sql::ast::TableReference,
+    target_reference: &sql::ast::TableReference,
+    computed_relationship: &metadata::ComputedRelationship,
+) -> sql::ast::Expression {
+    sql::ast::Expression::RawSql(
+        computed_relationship
+            .condition
+            .iter()
+            .map(|part| match part {
+                metadata::ConditionPart::Text(text) => sql::ast::RawSql::RawText(text.clone()),
+                metadata::ConditionPart::Source => {
+                    sql::ast::RawSql::TableReference(source_reference.clone())
+                }
+                metadata::ConditionPart::Target => {
+                    sql::ast::RawSql::TableReference(target_reference.clone())
+                }
+            })
+            .collect(),
+    )
+}
+
 #[derive(Debug)]
 /// Used in `make_relationship_arguments()` below.
 pub struct MakeRelationshipArguments {
@@ -134,42 +241,77 @@ pub struct MakeRelationshipArguments {
 
 /// Combine the caller arguments and the relationship arguments into a single map.
 ///
-/// We don't support relationships column arguments yet, so for now we convert to a regular argument
-/// and throw an error on the column case. Will be fixed in the future.
+/// Column arguments are only supported by the relationships we join, so here we convert to
+/// regular arguments and throw an error on the column case.
 pub fn make_relationship_arguments(
     arguments: MakeRelationshipArguments,
 ) -> Result<BTreeMap<models::ArgumentName, models::Argument>, Error> {
-    // these are arguments defined in the relationship definition.
-    let relationship_arguments: BTreeMap<models::ArgumentName, models::Argument> = arguments
-        .relationship_arguments
+    combine_relationship_arguments(arguments)?
         .into_iter()
         .map(|(key, argument)| Ok((key, relationship_argument_to_argument(argument)?)))
-        .collect::<Result<BTreeMap<models::ArgumentName, models::Argument>, Error>>()?;
+        .collect()
+}
 
-    // these are arguments defined when calling the relationship.
-    let caller_arguments: BTreeMap<models::ArgumentName, models::Argument> = arguments
-        .caller_arguments
-        .into_iter()
-        .map(|(key, argument)| Ok((key, relationship_argument_to_argument(argument)?)))
-        .collect::<Result<BTreeMap<models::ArgumentName, models::Argument>, Error>>()?;
+/// Combine the caller arguments and the relationship arguments of a relationship we join into a
+/// single map, and the column arguments into another, as references to the columns of the
+/// current table. A native query can then be called with the values of each of its rows.
+fn make_join_arguments(
+    env: &Env,
+    current_table: &TableSourceAndReference,
+    arguments: MakeRelationshipArguments,
+) -> Result<
+    (
+        BTreeMap<models::ArgumentName, models::Argument>,
+        BTreeMap<models::ArgumentName, sql::ast::Expression>,
+    ),
+    Error,
+> {
+    let mut regular_arguments = BTreeMap::new();
+    let mut column_arguments = BTreeMap::new();
+    for (key, argument) in combine_relationship_arguments(arguments)? {
+        match argument {
+            models::RelationshipArgument::Column { name } => {
+                let column_info = env
+                    .lookup_fields_info(&current_table.source)?
+                    .lookup_column(&name)?;
+                column_arguments.insert(
+                    key,
+                    sql::ast::Expression::ColumnReference(sql::ast::ColumnReference::TableColumn {
+                        table: current_table.reference.clone(),
+                        name: column_info.name,
+                    }),
+                );
+            }
+            argument => {
+                regular_arguments.insert(key, relationship_argument_to_argument(argument)?);
+            }
+        }
+    }
+    Ok((regular_arguments, column_arguments))
+}
 
-    let mut arguments = relationship_arguments;
+/// Combine the caller arguments and the relationship arguments into a single map.
+fn combine_relationship_arguments(
+    arguments: MakeRelationshipArguments,
+) -> Result<BTreeMap<models::ArgumentName, models::RelationshipArgument>, Error> {
+    // these are arguments defined in the relationship definition.
+    let mut relationship_arguments = arguments.relationship_arguments;
 
+    // these are arguments defined when calling the relationship.
     // We do not allow caller arguments to override relationship defined arguments,
     // because those might be specified as permissions.
     // We don't expect the engine to return such queries, but add this as a precaution.
-    for (key, value) in caller_arguments {
-        match arguments.insert(key.clone(), value) {
+    for (key, value) in arguments.caller_arguments {
+        match relationship_arguments.insert(key.clone(), value) {
             None => Ok(()),
             Some(_) => Err(Error::RelationshipArgumentWasOverriden(key)),
         }?;
     }
 
-    Ok(arguments)
+    Ok(relationship_arguments)
 }
 
-/// We don't support relationships column arguments yet, so for now we convert to a regular argument
-/// and throw an error on the column case. Will be fixed in the future.
+/// Convert a relationship argument to a regular argument, throwing an error on the column case.
 fn relationship_argument_to_argument(
     argument: models::RelationshipArgument,
 ) -> Result<models::Argument, Error> {
@@ -177,7 +319,7 @@ fn relationship_argument_to_argument(
         models::RelationshipArgument::Literal { value } => Ok(models::Argument::Literal { value }),
         models::RelationshipArgument::Variable { name } => Ok(models::Argument::Variable { name }),
         models::RelationshipArgument::Column { .. } => Err(Error::NotImplementedYet(
-            "relationship column arguments".to_string(),
+            "relationship column arguments outside of joins".to_string(),
         )),
     }
 }
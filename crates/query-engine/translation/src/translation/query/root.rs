@@ -1,6 +1,6 @@
 //! Handle 'rows' and 'aggregates' translation.
 
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet};
 
 use indexmap::IndexMap;
 
@@ -9,13 +9,17 @@ use ndc_models as models;
 use super::aggregates;
 use super::fields;
 use super::filtering;
+use super::native_queries;
 use super::relationships;
 use super::sorting;
+use super::variables;
 use crate::translation::error::Error;
 use crate::translation::helpers::TableSource;
 use crate::translation::helpers::{
     CollectionInfo, Env, RootAndCurrentTables, State, TableSourceAndReference,
 };
+use crate::translation::plan;
+use query_engine_metadata::metadata;
 use query_engine_sql::sql;
 
 /// Translate a query to sql ast.
@@ -66,12 +70,34 @@ fn translate_aggregates(
     match &query.aggregates {
         None => Ok(None),
         Some(aggregate_fields) => {
-            let (table, from_clause) = make_reference_and_from_clause(env, state, make_from)?;
-            let mut inner_query =
-                sql::helpers::star_from_select(table.reference.clone(), from_clause);
+            let estimable_table =
+                counted_table(env, make_from, join_predicate, query, aggregate_fields)?;
+            let (table, scan) = make_reference_and_scan(env, state, make_from)?;
 
-            // Translate the common part of the query - where, order by, limit, etc.
-            translate_query_part(env, state, &table, join_predicate, query, &mut inner_query)?;
+            // Select the rows to aggregate - where, order by, limit, etc.
+            let mut selection =
+                translate_selection(env, state, make_from, &table, scan, join_predicate, query)?;
+            // The aggregates may be limited separately from the rows.
+            let mut limit_expression = None;
+            if let Some(limit) = translate_aggregates_limit(env, state, make_from)? {
+                let policy = env.lookup_collection_policy(&table.source);
+                selection.limit = match policy {
+                    Some(policy) => policy.cap_limit(limit.limit),
+                    None => limit.limit,
+                };
+                selection.offset = limit.offset;
+                selection.with_ties = limit.with_ties;
+                limit_expression = limit.limit_expression.map(|limit| {
+                    match policy.and_then(|policy| policy.max_rows) {
+                        Some(max_rows) => cap_limit_expression(limit, max_rows),
+                        None => limit,
+                    }
+                });
+            }
+            selection.distinct_on = translate_distinct_on(env, make_from, &table)?;
+            let mut inner_query = selection.lower();
+            inner_query.limit.limit_expression = limit_expression;
+            select_aggregated_computed_fields(env, &table, aggregate_fields, &mut inner_query)?;
 
             // Aggregate queries can't contain where, order by, and limit stuff on the same level.
             // So we wrap this query part in another query that performs the aggregation.
@@ -87,6 +113,20 @@ fn translate_aggregates(
                 reference: sql::ast::TableReference::AliasedTable(from_alias),
             };
 
+            // Counting the rows of a very large table can take longer than a query may run, so we
+            // may answer with the number of rows PostgreSQL estimates it has instead.
+            if let (Some(count_estimation), Some(table_info)) =
+                (env.count_estimation(), estimable_table)
+            {
+                return Ok(Some(aggregates::estimate_count(
+                    state,
+                    table_info,
+                    count_estimation,
+                    from,
+                    aggregate_fields,
+                )));
+            }
+
             // create all aggregate columns
             let aggregate_columns =
                 aggregates::translate(&current_table.reference, aggregate_fields)?;
@@ -100,6 +140,40 @@ fn translate_aggregates(
     }
 }
 
+/// The table a query is over, if the query only counts all of its rows, so that the count could
+/// be estimated from the statistics of the table. This is not the case for relationships, nor
+/// for queries which filter, limit or otherwise restrict the rows of the table.
+fn counted_table<'a>(
+    env: &'a Env,
+    make_from: &MakeFrom,
+    join_predicate: Option<&JoinPredicate<'_, '_>>,
+    query: &models::Query,
+    aggregate_fields: &IndexMap<models::FieldName, models::Aggregate>,
+) -> Result<Option<&'a metadata::TableInfo>, Error> {
+    let MakeFrom::Collection {
+        name, arguments, ..
+    } = make_from
+    else {
+        return Ok(None);
+    };
+    let counts_all_rows = join_predicate.is_none()
+        && query.predicate.is_none()
+        && query.limit.is_none()
+        && query.offset.is_none()
+        && arguments.is_empty()
+        && env
+            .lookup_collection_policy(&TableSource::Collection(name.clone()))
+            .is_none()
+        && aggregate_fields
+            .values()
+            .all(|aggregate| matches!(aggregate, models::Aggregate::StarCount {}));
+    if !counts_all_rows {
+        return Ok(None);
+    }
+    Ok(lookup_table_info(env, make_from)?
+        .filter(|table_info| table_info.row_expiry.is_none() && table_info.soft_delete.is_none()))
+}
+
 /// Whether this rows query returns fields or not.
 pub enum ReturnsFields {
     FieldsWereRequested,
@@ -213,13 +287,18 @@ fn translate_rows(
         &current_table,
         from_clause,
         &mut join_relationship_fields,
+        &translate_composites_as_json(env, make_from)?,
     )?;
 
     // collect any joins for relationships from fields selection.
     let relationship_joins =
         relationships::translate(env, state, &current_table, join_relationship_fields)?;
 
-    fields_select.joins.extend(relationship_joins);
+    if env.use_lateral_joins() {
+        fields_select.joins.extend(relationship_joins);
+    } else {
+        relationships::select_as_subqueries(&mut fields_select, relationship_joins);
+    }
 
     if order_by_crosses_relationships(query) {
         // if order by crosses a relationship, the order by clause and resulting joins are created at this level
@@ -227,14 +306,10 @@ fn translate_rows(
         let (order_by, order_by_joins) =
             sorting::translate(env, state, &root_and_current_table, query.order_by.as_ref())?;
 
+        // Add the limit.
+        fields_select.limit = translate_limit(env, &current_table.source, query, &order_by);
         fields_select.order_by = order_by;
         fields_select.joins.extend(order_by_joins);
-
-        // Add the limit.
-        fields_select.limit = sql::ast::Limit {
-            limit: query.limit,
-            offset: query.offset,
-        };
     } else {
         // if we aren't ordering across a relationship, we expect joins to be empty, and the order by and limit to be applied in the subquery
         // however, we must repeat the order by clause, else ordering may not be guaranteed after joins
@@ -254,7 +329,7 @@ fn rows_subquery(
     join_predicate: Option<&JoinPredicate<'_, '_>>,
     query: &models::Query,
 ) -> Result<(sql::ast::Select, TableSource), Error> {
-    let (current_table, from_clause) = make_reference_and_from_clause(env, state, make_from)?;
+    let (current_table, scan) = make_reference_and_scan(env, state, make_from)?;
 
     // the root table and the current table are the same at this point
     let subquery_root_and_current_table = RootAndCurrentTables {
@@ -264,16 +339,23 @@ fn rows_subquery(
 
     // we want to put the where clause, including any required joins, in a subquery that is applied before any joins used to navigate relationships
     // this improves performance on cockroachdb
-    let mut subquery_select = sql::helpers::star_from_select(current_table.reference, from_clause);
-
     // Apply a join predicate if we want one.
-    subquery_select.where_ = translate_where_with_join_predicate(
+    let sql::ast::Where(predicate) = translate_where_with_join_predicate(
         env,
         state,
+        make_from,
         join_predicate,
         query,
         &subquery_root_and_current_table,
     )?;
+    let mut selection = plan::Selection::all(scan).filter(predicate);
+
+    selection.distinct_on = translate_distinct_on(env, make_from, &current_table)?;
+    if !selection.distinct_on.is_empty() && order_by_crosses_relationships(query) {
+        return Err(Error::NotImplementedYet(format!(
+            "'{DISTINCT_ON_ARGUMENT}' together with ordering by the columns of related collections"
+        )));
+    }
 
     // unless there is an order by clause that traverses relationships, we can put the order by clause and limit in the subquery
     if !order_by_crosses_relationships(query) {
@@ -285,20 +367,387 @@ fn rows_subquery(
             &subquery_root_and_current_table,
             query.order_by.as_ref(),
         )?;
-        subquery_select.order_by = order_by;
+        selection.order_by = order_by;
         // Add the limit.
-        subquery_select.limit = sql::ast::Limit {
-            limit: query.limit,
-            offset: query.offset,
+        let sql::ast::Limit {
+            limit,
+            offset,
+            with_ties,
+            ..
+        } = translate_limit(env, &current_table.source, query, &selection.order_by);
+        selection.limit = limit;
+        selection.offset = offset;
+        selection.with_ties = with_ties;
+    };
+
+    Ok((selection.lower(), current_table.source))
+}
+
+/// Select the computed fields aggregated over along with the rows to aggregate, named like the
+/// fields, so that the aggregates can refer to them as they do to columns.
+fn select_aggregated_computed_fields(
+    env: &Env,
+    table: &TableSourceAndReference,
+    aggregate_fields: &IndexMap<models::FieldName, models::Aggregate>,
+    select: &mut sql::ast::Select,
+) -> Result<(), Error> {
+    let fields_info = env.lookup_fields_info(&table.source)?;
+    let computed_fields: Vec<_> = aggregate_fields
+        .values()
+        .filter_map(|aggregate| match aggregate {
+            models::Aggregate::ColumnCount { column, .. }
+            | models::Aggregate::SingleColumn { column, .. } => Some(column),
+            models::Aggregate::StarCount {} => None,
+        })
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .filter_map(|column| {
+            fields_info
+                .lookup_computed_field(column)
+                .map(|computed_field| {
+                    (
+                        sql::helpers::make_column_alias(column.to_string()),
+                        fields::translate_computed_field(table, computed_field),
+                    )
+                })
+        })
+        .collect();
+    if !computed_fields.is_empty() {
+        let select_list =
+            std::mem::replace(&mut select.select_list, sql::ast::SelectList::SelectStar);
+        select.select_list = sql::ast::SelectList::SelectListComposite(
+            Box::new(select_list),
+            Box::new(sql::ast::SelectList::SelectList(computed_fields)),
+        );
+    }
+    Ok(())
+}
+
+/// The argument of table collections which asks for only the first row of each group of rows
+/// with equal values in the given columns, as `SELECT DISTINCT ON` does.
+pub const DISTINCT_ON_ARGUMENT: &str = "distinct_on";
+
+/// Translate the `distinct_on` argument of a table collection to the columns it refers to.
+///
+/// The columns named by a variable are only known once the query runs, so for those we make the
+/// rows distinct on every sortable column of the table, each replaced by null unless the variable
+/// names it:
+///
+///   CASE WHEN jsonb_exists(<variables> -> <name>, <column name>) THEN <column> END, ...
+fn translate_distinct_on(
+    env: &Env,
+    make_from: &MakeFrom,
+    current_table: &TableSourceAndReference,
+) -> Result<Vec<sql::ast::Expression>, Error> {
+    let MakeFrom::Collection {
+        name, arguments, ..
+    } = make_from
+    else {
+        return Ok(vec![]);
+    };
+    let Some(argument) = arguments.get(DISTINCT_ON_ARGUMENT) else {
+        return Ok(vec![]);
+    };
+    // The arguments of native queries are their own, even if one happens to share the name.
+    let CollectionInfo::Table { info, .. } = env.lookup_collection(name)? else {
+        return Ok(vec![]);
+    };
+
+    if let models::Argument::Variable { name: variable } = argument {
+        let column_names = sql::ast::Expression::BinaryOperation {
+            left: Box::new(sql::ast::Expression::ColumnReference(
+                sql::ast::ColumnReference::AliasedColumn {
+                    table: env.get_variables_table()?,
+                    column: sql::helpers::make_column_alias(
+                        sql::helpers::VARIABLES_FIELD.to_string(),
+                    ),
+                },
+            )),
+            operator: sql::helpers::json_extract_operator(),
+            right: Box::new(sql::ast::Expression::Value(sql::ast::Value::String(
+                variable.to_string(),
+            ))),
         };
+        return Ok(info
+            .columns
+            .iter()
+            .filter(|(_, column)| column.selectable && column.sortable)
+            .map(|(column_name, column)| {
+                sql::ast::Expression::RawSql(vec![
+                    sql::ast::RawSql::RawText("CASE WHEN ".to_string()),
+                    sql::ast::RawSql::Expression(sql::ast::Expression::FunctionCall {
+                        function: sql::ast::Function::Unknown("jsonb_exists".to_string()),
+                        args: vec![
+                            column_names.clone(),
+                            sql::ast::Expression::Value(sql::ast::Value::String(
+                                column_name.to_string(),
+                            )),
+                        ],
+                    }),
+                    sql::ast::RawSql::RawText(" THEN ".to_string()),
+                    sql::ast::RawSql::Expression(sql::ast::Expression::ColumnReference(
+                        sql::ast::ColumnReference::TableColumn {
+                            table: current_table.reference.clone(),
+                            name: sql::ast::ColumnName(column.name.clone()),
+                        },
+                    )),
+                    sql::ast::RawSql::RawText(" END".to_string()),
+                ])
+            })
+            .collect());
+    }
+
+    let fields_info = env.lookup_fields_info(&current_table.source)?;
+    column_names_argument(DISTINCT_ON_ARGUMENT, argument)?
+        .into_iter()
+        .map(|column_name| {
+            let column = fields_info.lookup_sortable_column(&column_name.into())?;
+            Ok(sql::ast::Expression::ColumnReference(
+                sql::ast::ColumnReference::TableColumn {
+                    table: current_table.reference.clone(),
+                    name: column.name,
+                },
+            ))
+        })
+        .collect()
+}
+
+/// The argument of table collections which asks for composite columns to be returned as they
+/// are, as a single JSON value, rather than by selecting each of their fields.
+pub const COMPOSITES_AS_JSON_ARGUMENT: &str = "composites_as_json";
+
+/// Translate the `composites_as_json` argument of a table collection to the columns to return
+/// without unpacking them. Each of them must be a column of the table holding a composite value
+/// or an array of them.
+fn translate_composites_as_json(
+    env: &Env,
+    make_from: &MakeFrom,
+) -> Result<BTreeSet<models::FieldName>, Error> {
+    let MakeFrom::Collection {
+        name, arguments, ..
+    } = make_from
+    else {
+        return Ok(BTreeSet::new());
+    };
+    let Some(argument) = arguments.get(COMPOSITES_AS_JSON_ARGUMENT) else {
+        return Ok(BTreeSet::new());
+    };
+    let collection = env.lookup_collection(name)?;
+    if !matches!(collection, CollectionInfo::Table { .. }) {
+        return Ok(BTreeSet::new());
+    }
+
+    column_names_argument(COMPOSITES_AS_JSON_ARGUMENT, argument)?
+        .into_iter()
+        .map(|column_name| {
+            let column_name = models::FieldName::from(column_name);
+            match collection.lookup_column(&column_name)?.r#type {
+                metadata::Type::CompositeType(_) => Ok(column_name),
+                metadata::Type::ArrayType(element_type)
+                    if matches!(*element_type, metadata::Type::CompositeType(_)) =>
+                {
+                    Ok(column_name)
+                }
+                actual_type => Err(Error::NestedFieldNotOfCompositeType {
+                    field_name: column_name,
+                    actual_type,
+                }),
+            }
+        })
+        .collect()
+}
+
+/// The argument of table collections which limits the rows their aggregates are computed over
+/// separately from the rows they return, such as to count all the tracks of an album while
+/// returning only the first three. A null value computes the aggregates over all the rows,
+/// ignoring the limit and offset of the query.
+pub const AGGREGATES_LIMIT_ARGUMENT: &str = "aggregates_limit";
+
+/// Translate the `aggregates_limit` argument of a table collection to the limit of the rows its
+/// aggregates are computed over, if it was given. A variable is read as an `int4` when the query
+/// runs, so its limit is an expression rather than a number.
+fn translate_aggregates_limit(
+    env: &Env,
+    state: &mut State,
+    make_from: &MakeFrom,
+) -> Result<Option<sql::ast::Limit>, Error> {
+    let MakeFrom::Collection {
+        name, arguments, ..
+    } = make_from
+    else {
+        return Ok(None);
+    };
+    let Some(argument) = arguments.get(AGGREGATES_LIMIT_ARGUMENT) else {
+        return Ok(None);
+    };
+    if !matches!(env.lookup_collection(name)?, CollectionInfo::Table { .. }) {
+        return Ok(None);
+    }
+
+    let unexpected = || {
+        Error::UnexpectedStructure(format!(
+            "'{AGGREGATES_LIMIT_ARGUMENT}' argument, which should be a non-negative integer or null"
+        ))
     };
+    let (limit, limit_expression) = match argument {
+        models::Argument::Literal {
+            value: serde_json::Value::Null,
+        } => (None, None),
+        models::Argument::Literal { value } => (
+            Some(
+                value
+                    .as_u64()
+                    .and_then(|limit| u32::try_from(limit).ok())
+                    .ok_or_else(unexpected)?,
+            ),
+            None,
+        ),
+        models::Argument::Variable { name } => (
+            None,
+            Some(variables::translate(
+                env,
+                state,
+                env.get_variables_table()?,
+                name,
+                &metadata::Type::ScalarType("int4".into()),
+            )?),
+        ),
+    };
+    Ok(Some(sql::ast::Limit {
+        limit,
+        limit_expression,
+        offset: None,
+        with_ties: false,
+    }))
+}
+
+/// Cap a limit only known when the query runs at the most rows a policy allows. `LEAST` ignores
+/// nulls, so a null limit is capped too.
+fn cap_limit_expression(limit: sql::ast::Expression, max_rows: u32) -> sql::ast::Expression {
+    sql::ast::Expression::FunctionCall {
+        function: sql::ast::Function::Unknown("LEAST".to_string()),
+        args: vec![
+            limit,
+            sql::ast::Expression::Value(sql::ast::Value::Int4(
+                i32::try_from(max_rows).unwrap_or(i32::MAX),
+            )),
+        ],
+    }
+}
+
+/// The argument of table collections which runs the query against a historical snapshot of the
+/// database, so that the pages of an export are consistent with each other: a time for
+/// `AS OF SYSTEM TIME` on databases which support it, such as `-10s`, or otherwise the identifier
+/// of a snapshot another transaction exported with `pg_export_snapshot()`.
+pub const SNAPSHOT_ARGUMENT: &str = "snapshot";
+
+/// Translate the `snapshot` argument of the collection a query is over, if it was given.
+/// The snapshot applies to the whole transaction, so it is only read from the root collection.
+pub fn translate_snapshot(
+    env: &Env,
+    collection: &models::CollectionName,
+    arguments: &BTreeMap<models::ArgumentName, models::Argument>,
+) -> Result<Option<sql::ast::transaction::SetSnapshot>, Error> {
+    let Some(argument) = arguments.get(SNAPSHOT_ARGUMENT) else {
+        return Ok(None);
+    };
+    if !matches!(
+        env.lookup_collection(collection)?,
+        CollectionInfo::Table { .. }
+    ) {
+        return Ok(None);
+    }
 
-    Ok((subquery_select, current_table.source))
+    let snapshot = match argument {
+        models::Argument::Literal {
+            value: serde_json::Value::Null,
+        } => return Ok(None),
+        models::Argument::Literal {
+            value: serde_json::Value::String(snapshot),
+        } => snapshot.clone(),
+        models::Argument::Literal { .. } | models::Argument::Variable { .. } => {
+            return Err(Error::UnexpectedStructure(format!(
+                "'{SNAPSHOT_ARGUMENT}' argument, which should be a string or null"
+            )))
+        }
+    };
+    Ok(Some(
+        if env.dialect().dialect().supports_as_of_system_time() {
+            sql::ast::transaction::SetSnapshot::AsOfSystemTime(snapshot)
+        } else {
+            sql::ast::transaction::SetSnapshot::Exported(snapshot)
+        },
+    ))
+}
+
+/// Read a collection argument which holds a list of column names. A null value is an empty list.
+fn column_names_argument<'a>(
+    argument_name: &str,
+    argument: &'a models::Argument,
+) -> Result<Vec<&'a str>, Error> {
+    let unexpected = || {
+        Error::UnexpectedStructure(format!(
+            "'{argument_name}' argument, which should be a list of column names"
+        ))
+    };
+    match argument {
+        models::Argument::Literal {
+            value: serde_json::Value::Null,
+        } => Ok(vec![]),
+        models::Argument::Literal {
+            value: serde_json::Value::Array(values),
+        } => values
+            .iter()
+            .map(|value| value.as_str().ok_or_else(unexpected))
+            .collect(),
+        models::Argument::Literal { .. } | models::Argument::Variable { .. } => Err(unexpected()),
+    }
+}
+
+/// The restrictions on the rows of a collection which apply wherever it is scanned, whatever
+/// the request asks for: in queries, relationships, `EXISTS` predicates, orderings and the rows
+/// mutations modify.
+pub fn translate_collection_restrictions(
+    env: &Env,
+    state: &mut State,
+    table: &TableSourceAndReference,
+) -> Result<Vec<sql::ast::Expression>, Error> {
+    let mut restrictions = vec![];
+    // The policy of the collection restricts the rows further. It is over the fields of the
+    // collection alone, so the collection is its root as well.
+    if let Some(policy_filter) = env
+        .lookup_collection_policy(&table.source)
+        .and_then(|policy| policy.filter.as_ref())
+    {
+        restrictions.push(filtering::translate(
+            env,
+            state,
+            &RootAndCurrentTables {
+                root_table: table.clone(),
+                current_table: table.clone(),
+            },
+            policy_filter,
+        )?);
+    }
+    if let TableSource::Collection(collection_name) = &table.source {
+        if let Some(info) = env.metadata.tables.0.get(collection_name) {
+            // Rows which have expired are left out, even if the database has not deleted them yet.
+            if let Some(row_expiry) = &info.row_expiry {
+                restrictions.push(translate_row_expiry(&table.reference, row_expiry));
+            }
+            // So are rows which have been marked as deleted.
+            if let Some(soft_delete) = &info.soft_delete {
+                restrictions.push(translate_soft_delete(&table.reference, soft_delete));
+            }
+        }
+    }
+    Ok(restrictions)
 }
 
 fn translate_where_with_join_predicate(
     env: &Env,
     state: &mut State,
+    make_from: &MakeFrom,
     join_predicate: Option<&JoinPredicate<'_, '_>>,
     query: &models::Query,
     root_and_current_table: &RootAndCurrentTables,
@@ -306,9 +755,41 @@ fn translate_where_with_join_predicate(
     // translate where
     let filter = match &query.predicate {
         None => Ok(sql::helpers::true_expr()),
-        Some(predicate) => filtering::translate(env, state, root_and_current_table, predicate),
+        Some(predicate) => {
+            state.count_predicate(predicate)?;
+            filtering::translate(env, state, root_and_current_table, predicate)
+        }
     }?;
 
+    let mut restrictions = vec![filter];
+    // The rows a mutation returns were restricted when it modified them, so only scans of the
+    // collection itself are restricted here.
+    if let MakeFrom::Collection { .. } = make_from {
+        restrictions.extend(translate_collection_restrictions(
+            env,
+            state,
+            &root_and_current_table.current_table,
+        )?);
+    }
+    let filter = sql::helpers::fold_and(restrictions);
+
+    // When the filter can never hold, say because permission filters require a column to be
+    // equal to two different values, replace it with 'false'. PostgreSQL then knows that the
+    // query returns no rows without touching the underlying table. If this is the root
+    // collection, we need not run the query at all.
+    let filter = if sql::rewrites::contradictions::is_contradiction(&filter) {
+        tracing::debug!(
+            collection = ?root_and_current_table.current_table.source,
+            "Predicate can never be satisfied, the query will return no rows."
+        );
+        if join_predicate.is_none() {
+            state.set_root_predicate_never_holds();
+        }
+        sql::helpers::false_expr()
+    } else {
+        filter
+    };
+
     // Apply a join predicate if we want one.
     Ok(match join_predicate {
         // Only apply the existing filter.
@@ -326,6 +807,81 @@ fn translate_where_with_join_predicate(
     })
 }
 
+/// The information of the table the rows are selected from, if they are selected from a table
+/// rather than, say, the rows returned by a mutation.
+fn lookup_table_info<'a>(
+    env: &'a Env,
+    make_from: &MakeFrom,
+) -> Result<Option<&'a metadata::TableInfo>, Error> {
+    let MakeFrom::Collection { name, .. } = make_from else {
+        return Ok(None);
+    };
+    Ok(match env.lookup_collection(name)? {
+        CollectionInfo::Table { info, .. } => Some(info),
+        CollectionInfo::NativeQuery { .. } => None,
+    })
+}
+
+/// The condition which rows of a table which expire must meet to not have expired yet, such as
+/// `"expires_at" IS NULL OR "expires_at" > now()` or
+/// `"time" IS NULL OR "time" > now() - interval '30 days'`. Rows without an expiry never expire.
+fn translate_row_expiry(
+    current_table: &sql::ast::TableReference,
+    row_expiry: &metadata::RowExpiry,
+) -> sql::ast::Expression {
+    let now = sql::ast::Expression::FunctionCall {
+        function: sql::ast::Function::Unknown("now".to_string()),
+        args: vec![],
+    };
+    let expires_from = match &row_expiry.after {
+        None => now,
+        Some(after) => sql::ast::Expression::BinaryOperation {
+            left: Box::new(now),
+            operator: sql::ast::BinaryOperator("-".to_string()),
+            right: Box::new(sql::ast::Expression::Cast {
+                expression: Box::new(sql::ast::Expression::Value(sql::ast::Value::String(
+                    after.clone(),
+                ))),
+                r#type: sql::ast::ScalarType::BaseType(sql::ast::ScalarTypeName::Unqualified(
+                    "interval".to_string(),
+                )),
+            }),
+        },
+    };
+    let column = sql::ast::Expression::ColumnReference(sql::ast::ColumnReference::TableColumn {
+        table: current_table.clone(),
+        name: sql::ast::ColumnName(row_expiry.column.clone()),
+    });
+    sql::ast::Expression::Or {
+        left: Box::new(sql::ast::Expression::UnaryOperation {
+            expression: Box::new(column.clone()),
+            operator: sql::ast::UnaryOperator::IsNull,
+        }),
+        right: Box::new(sql::ast::Expression::BinaryOperation {
+            left: Box::new(column),
+            operator: sql::ast::BinaryOperator(">".to_string()),
+            right: Box::new(expires_from),
+        }),
+    }
+}
+
+/// The condition which rows of a table which are deleted softly must meet to not have been
+/// deleted, `"deleted_at" IS NULL`.
+fn translate_soft_delete(
+    current_table: &sql::ast::TableReference,
+    soft_delete: &metadata::SoftDelete,
+) -> sql::ast::Expression {
+    sql::ast::Expression::UnaryOperation {
+        expression: Box::new(sql::ast::Expression::ColumnReference(
+            sql::ast::ColumnReference::TableColumn {
+                table: current_table.clone(),
+                name: sql::ast::ColumnName(soft_delete.column.clone()),
+            },
+        )),
+        operator: sql::ast::UnaryOperator::IsNull,
+    }
+}
+
 /// if any order by element contain a path that isn't empty, this query orders by fields in other tables
 /// and will thus produce joins when generating the order by clause
 fn order_by_crosses_relationships(query: &models::Query) -> bool {
@@ -341,19 +897,17 @@ fn order_by_crosses_relationships(query: &models::Query) -> bool {
     })
 }
 
-/// Translate the lion (or common) part of 'rows' or 'aggregates' part of a query.
-/// Specifically, from, joins, order bys, where, limit and offset clauses.
-///
-/// This expects to get the relevant information about tables, relationships, the root table,
-/// and the query, as well as the columns and join fields after processing.
-pub fn translate_query_part(
+/// Select the rows of a scan a query asks for: those which satisfy its predicate, in its order,
+/// and paged. This is the common part of the 'rows' and 'aggregates' parts of a query.
+fn translate_selection(
     env: &Env,
     state: &mut State,
+    make_from: &MakeFrom,
     current_table: &TableSourceAndReference,
+    scan: plan::Scan,
     join_predicate: Option<&JoinPredicate<'_, '_>>,
     query: &models::Query,
-    select: &mut sql::ast::Select,
-) -> Result<(), Error> {
+) -> Result<plan::Selection, Error> {
     // the root table and the current table are the same at this point
     let root_and_current_tables = RootAndCurrentTables {
         root_table: current_table.clone(),
@@ -368,25 +922,50 @@ pub fn translate_query_part(
         query.order_by.as_ref(),
     )?;
 
-    select.joins.extend(order_by_joins);
-
-    select.where_ = translate_where_with_join_predicate(
+    let sql::ast::Where(predicate) = translate_where_with_join_predicate(
         env,
         state,
+        make_from,
         join_predicate,
         query,
         &root_and_current_tables,
     )?;
-
-    select.order_by = order_by;
+    let mut selection = plan::Selection::all(scan).filter(predicate);
+    selection.joins = order_by_joins;
 
     // Add the limit.
-    select.limit = sql::ast::Limit {
-        limit: query.limit,
-        offset: query.offset,
-    };
+    let sql::ast::Limit {
+        limit,
+        offset,
+        with_ties,
+        ..
+    } = translate_limit(env, &current_table.source, query, &order_by);
+    selection.order_by = order_by;
+    selection.limit = limit;
+    selection.offset = offset;
+    selection.with_ties = with_ties;
 
-    Ok(())
+    Ok(selection)
+}
+
+/// Translate the limit and offset of a query, keeping the rows which tie with the last one if
+/// configured to and the query is ordered. The limit is capped at the most rows the policy of the
+/// collection allows, which rows that tie may not exceed either.
+fn translate_limit(
+    env: &Env,
+    source: &TableSource,
+    query: &models::Query,
+    order_by: &sql::ast::OrderBy,
+) -> sql::ast::Limit {
+    let policy = env.lookup_collection_policy(source);
+    sql::ast::Limit {
+        limit: policy.map_or(query.limit, |policy| policy.cap_limit(query.limit)),
+        limit_expression: None,
+        offset: query.offset,
+        with_ties: env.limit_with_ties()
+            && !order_by.elements.is_empty()
+            && !policy.is_some_and(|policy| policy.max_rows.is_some()),
+    }
 }
 
 /// Create a from clause from a collection name and its reference.
@@ -401,44 +980,116 @@ pub fn make_from_clause_and_reference(
         None => state.make_table_alias(collection_name.to_string()),
         Some(alias) => alias,
     };
+    let (current_table, scan) = make_collection_scan(
+        env,
+        state,
+        collection_name,
+        arguments,
+        &BTreeMap::new(),
+        collection_alias,
+    )?;
+    Ok((current_table, scan.lower()))
+}
+
+/// Build a scan over a collection under an alias, and return the table name and reference.
+/// Will add a Native Query to the 'State' if the collection is a native query.
+fn make_collection_scan(
+    env: &Env,
+    state: &mut State,
+    collection_name: &models::CollectionName,
+    arguments: &BTreeMap<models::ArgumentName, models::Argument>,
+    column_arguments: &BTreeMap<models::ArgumentName, sql::ast::Expression>,
+    collection_alias: sql::ast::TableAlias,
+) -> Result<(TableSourceAndReference, plan::Scan), Error> {
     // find the table according to the metadata.
     let collection_info = env.lookup_collection(collection_name)?;
-    let from_clause = make_from_clause(state, &collection_alias, &collection_info, arguments);
-
-    let collection_alias_name = sql::ast::TableReference::AliasedTable(collection_alias);
+    add_planner_hint(env, state, collection_name, &collection_alias);
+    if let CollectionInfo::Table { info, .. } = &collection_info {
+        if let Some(statement_timeout_ms) = info.statement_timeout_ms {
+            state.add_statement_timeout(statement_timeout_ms);
+        }
+    }
+    let scan = if column_arguments.is_empty() {
+        make_scan(state, &collection_alias, &collection_info, arguments)
+    } else {
+        make_correlated_scan(
+            env,
+            state,
+            &collection_alias,
+            &collection_info,
+            arguments,
+            column_arguments,
+        )?
+    };
     let current_table = TableSourceAndReference {
         source: TableSource::Collection(collection_name.clone()),
-        reference: collection_alias_name,
+        reference: sql::ast::TableReference::AliasedTable(collection_alias),
     };
-    Ok((current_table, from_clause))
+    Ok((current_table, scan))
+}
+
+/// Attach the planner hint configured for a collection, if any, to the query, referring to the
+/// collection by the alias we scan it under, as `pg_hint_plan` expects.
+fn add_planner_hint(
+    env: &Env,
+    state: &mut State,
+    collection_name: &models::CollectionName,
+    collection_alias: &sql::ast::TableAlias,
+) {
+    if let Some(planner_hint) = env.lookup_planner_hint(collection_name) {
+        let mut table = sql::string::SQL::with_dialect(env.dialect());
+        collection_alias.to_sql(&mut table);
+        state.add_planner_hint(planner_hint.replace("{{table}}", &table.sql));
+    }
 }
 
-/// Build a FROM clause from a collection info and an alias.
+/// Build a scan over a collection, under an alias.
 /// Will add a Native Query to the 'State' if the collection is a native query.
-fn make_from_clause(
+fn make_scan(
+    state: &mut State,
+    current_table_alias: &sql::ast::TableAlias,
+    collection_info: &CollectionInfo,
+    arguments: &BTreeMap<models::ArgumentName, models::Argument>,
+) -> plan::Scan {
+    let relation = match collection_info {
+        CollectionInfo::Table { info, .. } => plan::Relation::Table {
+            schema: sql::ast::SchemaName(info.schema_name.clone()),
+            table: sql::ast::TableName(info.table_name.clone()),
+            inherited_rows: info.inherited_rows.clone(),
+        },
+        CollectionInfo::NativeQuery { name, info } => plan::Relation::Reference(
+            state.insert_native_query(name, (*info).clone(), arguments.clone()),
+        ),
+    };
+    plan::Scan {
+        relation,
+        alias: current_table_alias.clone(),
+    }
+}
+
+/// Build a scan over a native query whose arguments are taken in part from the columns of the
+/// rows of an outer query. Only native queries take such arguments.
+fn make_correlated_scan(
+    env: &Env,
     state: &mut State,
     current_table_alias: &sql::ast::TableAlias,
     collection_info: &CollectionInfo,
     arguments: &BTreeMap<models::ArgumentName, models::Argument>,
-) -> sql::ast::From {
+    column_arguments: &BTreeMap<models::ArgumentName, sql::ast::Expression>,
+) -> Result<plan::Scan, Error> {
     match collection_info {
-        CollectionInfo::Table { info, .. } => {
-            let db_table = sql::ast::TableReference::DBTable {
-                schema: sql::ast::SchemaName(info.schema_name.clone()),
-                table: sql::ast::TableName(info.table_name.clone()),
-            };
-            sql::ast::From::Table {
-                reference: db_table,
-                alias: current_table_alias.clone(),
-            }
-        }
-        CollectionInfo::NativeQuery { name, info } => {
-            let aliased_table = state.insert_native_query(name, (*info).clone(), arguments.clone());
-            sql::ast::From::Table {
-                reference: aliased_table,
-                alias: current_table_alias.clone(),
-            }
-        }
+        CollectionInfo::Table { name, .. } => Err(Error::ColumnArgumentsToTable((*name).clone())),
+        CollectionInfo::NativeQuery { name, info } => Ok(plan::Scan {
+            relation: plan::Relation::Correlated(native_queries::translate_correlated(
+                env,
+                state,
+                name,
+                info,
+                arguments,
+                column_arguments,
+            )?),
+            alias: current_table_alias.clone(),
+        }),
     }
 }
 
@@ -458,6 +1109,9 @@ pub enum MakeFrom {
         name: models::CollectionName,
         /// Native query arguments.
         arguments: BTreeMap<models::ArgumentName, models::Argument>,
+        /// Native query arguments taken from the columns of the rows of an outer query, when
+        /// the collection is the target of a relationship.
+        column_arguments: BTreeMap<models::ArgumentName, sql::ast::Expression>,
     },
     /// From an existing relation.
     TableReference {
@@ -468,20 +1122,32 @@ pub enum MakeFrom {
     },
 }
 
-/// Build a from clause and return the table name and reference.
-fn make_reference_and_from_clause(
+/// Build a scan and return the table name and reference.
+fn make_reference_and_scan(
     env: &Env,
     state: &mut State,
     make_from: &MakeFrom,
-) -> Result<(TableSourceAndReference, sql::ast::From), Error> {
+) -> Result<(TableSourceAndReference, plan::Scan), Error> {
     match make_from {
-        MakeFrom::Collection { name, arguments } => {
-            make_from_clause_and_reference(name, arguments, env, state, None)
+        MakeFrom::Collection {
+            name,
+            arguments,
+            column_arguments,
+        } => {
+            let collection_alias = state.make_table_alias(name.to_string());
+            make_collection_scan(
+                env,
+                state,
+                name,
+                arguments,
+                column_arguments,
+                collection_alias,
+            )
         }
         MakeFrom::TableReference { name, reference } => {
             let table_alias = state.make_table_alias(name.to_string());
-            let from_clause = sql::ast::From::Table {
-                reference: reference.clone(),
+            let scan = plan::Scan {
+                relation: plan::Relation::Reference(reference.clone()),
                 alias: table_alias.clone(),
             };
             let reference = sql::ast::TableReference::AliasedTable(table_alias);
@@ -490,7 +1156,7 @@ fn make_reference_and_from_clause(
                     source: TableSource::Collection(name.clone()),
                     reference,
                 },
-                from_clause,
+                scan,
             ))
         }
     }
@@ -5,6 +5,7 @@ use std::hash::{Hash, Hasher};
 
 use ndc_models as models;
 
+use super::fields;
 use super::filtering;
 use super::relationships;
 use super::root;
@@ -249,14 +250,11 @@ fn translate_order_by_target_group(
         // The column is from the source table, we just need to query it directly.
         ColumnsOrSelect::Columns(columns) => Ok(columns
             .into_iter()
-            .map(|(i, direction, field_path, column_name)| {
+            .map(|(i, direction, field_path, expression)| {
                 (
                     i,
                     sql::ast::OrderByElement {
-                        target: wrap_in_field_path(
-                            &field_path,
-                            sql::ast::Expression::ColumnReference(column_name),
-                        ),
+                        target: wrap_in_field_path(&field_path, expression),
                         direction: match direction {
                             models::OrderDirection::Asc => sql::ast::OrderByDirection::Asc,
                             models::OrderDirection::Desc => sql::ast::OrderByDirection::Desc,
@@ -324,10 +322,10 @@ enum ColumnsOrSelect {
     /// Columns represents target columns that are referenced from the current table.
     Columns(
         Vec<(
-            usize,                     // The global order by index for this column.
-            models::OrderDirection,    // The order direction.
-            FieldPath,                 // The nested field path.
-            sql::ast::ColumnReference, // A reference for this column.
+            usize,                  // The global order by index for this column.
+            models::OrderDirection, // The order direction.
+            FieldPath,              // The nested field path.
+            sql::ast::Expression,   // A reference for this column, or its computed value.
         )>,
     ),
     /// Select represents a select query for a relationship table which contain the requested columns.
@@ -395,10 +393,7 @@ fn build_select_and_joins_for_order_by_group(
                         column.index,
                         column.direction,
                         column.field_path,
-                        sql::ast::ColumnReference::AliasedColumn {
-                            table: root_and_current_tables.current_table.reference.clone(),
-                            column: column.alias,
-                        },
+                        column.expression,
                     )
                 })
                 .collect();
@@ -556,6 +551,21 @@ fn process_path_element_for_order_by_targets(
 ) -> Result<(TableSourceAndReference, PathElementSelectColumns), Error> {
     // examine the path elements' relationship.
     let relationship = env.lookup_relationship(&path_element.relationship)?;
+    state.count_join()?;
+
+    // The condition of a computed relationship may refer to any column of its source table, but
+    // the tables along the path only select the columns the next relationship maps.
+    if index > 0
+        && relationship.column_mapping.is_empty()
+        && env
+            .lookup_computed_relationship(&last_table.source, &relationship.target_collection)
+            .is_some()
+    {
+        return Err(Error::NotImplementedYet(format!(
+            "ordering through the computed relationship '{}' after another relationship",
+            path_element.relationship
+        )));
+    }
 
     let target_collection_alias =
         state.make_order_path_part_table_alias(relationship.target_collection.as_str());
@@ -659,23 +669,13 @@ fn translate_targets(
                 .iter()
                 .map(|element| {
                     let (Column(target_column_name), field_path) = &element.element;
-                    let selected_column = target_collection.lookup_column(target_column_name)?;
-                    // we are going to deliberately use the table column name and not an alias we get from
-                    // the query request because this is internal to the sorting mechanism.
-                    let selected_column_alias =
-                        sql::helpers::make_column_alias(selected_column.name.0);
-
-                    // we use the real name of the column as an alias as well.
+                    let (alias, expression) =
+                        translate_target_column(target_collection, table, target_column_name)?;
                     Ok::<OrderBySelectExpression, Error>(OrderBySelectExpression {
                         index: element.index,
                         direction: element.direction,
-                        alias: selected_column_alias.clone(),
-                        expression: sql::ast::Expression::ColumnReference(
-                            sql::ast::ColumnReference::AliasedColumn {
-                                table: table.reference.clone(),
-                                column: selected_column_alias,
-                            },
-                        ),
+                        alias,
+                        expression,
                         field_path: field_path.clone(),
                         aggregate: None,
                     })
@@ -702,24 +702,15 @@ fn translate_targets(
                             })
                         }
                         Aggregate::SingleColumn { column, function } => {
-                            let selected_column = target_collection.lookup_column(column)?;
-                            // we are going to deliberately use the table column name and not an alias we get from
-                            // the query request because this is internal to the sorting mechanism.
-                            let selected_column_alias =
-                                sql::helpers::make_column_alias(selected_column.name.0);
-                            // we use the real name of the column as an alias as well.
+                            let (alias, expression) =
+                                translate_target_column(target_collection, table, column)?;
                             Ok(OrderBySelectExpression {
                                 index: element.index,
                                 direction: element.direction,
-                                alias: selected_column_alias.clone(),
+                                alias,
                                 // Aggregates do not have a field path.
                                 field_path: (&None).into(),
-                                expression: sql::ast::Expression::ColumnReference(
-                                    sql::ast::ColumnReference::AliasedColumn {
-                                        table: table.reference.clone(),
-                                        column: selected_column_alias,
-                                    },
-                                ),
+                                expression,
                                 aggregate: Some(sql::ast::Function::Unknown(function.to_string())),
                             })
                         }
@@ -732,6 +723,32 @@ fn translate_targets(
     }
 }
 
+/// The alias and the expression of a column or a computed field to order by.
+fn translate_target_column(
+    target_collection: &FieldsInfo<'_>,
+    table: &TableSourceAndReference,
+    column_name: &models::FieldName,
+) -> Result<(sql::ast::ColumnAlias, sql::ast::Expression), Error> {
+    if let Some(computed_field) = target_collection.lookup_computed_field(column_name) {
+        return Ok((
+            sql::helpers::make_column_alias(column_name.to_string()),
+            fields::translate_computed_field(table, computed_field),
+        ));
+    }
+    let selected_column = target_collection.lookup_sortable_column(column_name)?;
+    // we are going to deliberately use the table column name and not an alias we get from
+    // the query request because this is internal to the sorting mechanism.
+    let selected_column_alias = sql::helpers::make_column_alias(selected_column.name.0);
+    // we use the real name of the column as an alias as well.
+    Ok((
+        selected_column_alias.clone(),
+        sql::ast::Expression::ColumnReference(sql::ast::ColumnReference::AliasedColumn {
+            table: table.reference.clone(),
+            column: selected_column_alias,
+        }),
+    ))
+}
+
 /// Create a from clause and a table reference from a path element's relationship.
 fn from_clause_for_path_element(
     env: &Env,
@@ -785,18 +802,23 @@ fn select_for_path_element(
         relationship,
     )?;
 
-    select.where_ = match predicate {
-        None => sql::ast::Where(join_condition),
-        Some(predicate) => {
-            // generate a condition for the predicate.
-            let predicate_expr = filtering::translate(env, state, &predicate_tables, predicate)?;
-
-            sql::ast::Where(sql::ast::Expression::And {
-                left: Box::new(join_condition),
-                right: Box::new(predicate_expr),
-            })
-        }
-    };
+    let mut conditions = vec![join_condition];
+    // generate a condition for the predicate.
+    if let Some(predicate) = predicate {
+        conditions.push(filtering::translate(
+            env,
+            state,
+            &predicate_tables,
+            predicate,
+        )?);
+    }
+    // Rows the collection hides from queries must not decide their order either.
+    conditions.extend(root::translate_collection_restrictions(
+        env,
+        state,
+        &predicate_tables.current_table,
+    )?);
+    select.where_ = sql::ast::Where(sql::helpers::fold_and(conditions));
 
     Ok(select)
 }
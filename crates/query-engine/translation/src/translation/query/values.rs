@@ -1,6 +1,8 @@
 //! Handle the translation of literal values.
 
 use crate::translation::{error::Error, helpers::Env, helpers::State};
+use ndc_models as models;
+use query_engine_metadata::metadata;
 use query_engine_metadata::metadata::database;
 use query_engine_sql::sql;
 use query_engine_sql::sql::ast::{ColumnReference, Expression, Value};
@@ -18,25 +20,53 @@ pub fn translate(
             r#type: type_to_ast_scalar_type(env, r#type)?,
         }),
         (serde_json::Value::Bool(b), _) => Ok(Expression::Value(Value::Bool(*b))),
-        (serde_json::Value::Number(n), _) => {
-            let lit = n
-                .as_f64()
-                .ok_or(Error::UnableToDeserializeNumberAsF64(n.clone()))?;
-            Ok(Expression::Value(Value::Float8(lit)))
+        (serde_json::Value::Number(n), _) => match exact_integer(env, r#type, n) {
+            Some(typed_value) => Ok(Expression::Cast {
+                expression: Box::new(Expression::Value(Value::Typed(typed_value))),
+                r#type: type_to_ast_scalar_type(env, r#type)?,
+            }),
+            None => {
+                let lit = n
+                    .as_f64()
+                    .ok_or(Error::UnableToDeserializeNumberAsF64(n.clone()))?;
+                Ok(Expression::Value(Value::Float8(lit)))
+            }
+        },
+        (serde_json::Value::String(str), database::Type::ScalarType(scalar_type))
+            if is_bytes(env, scalar_type) =>
+        {
+            Ok(sql::helpers::decode_base64(Expression::Value(
+                Value::String(str.clone()),
+            )))
         }
         (serde_json::Value::String(str), _) => Ok(Expression::Cast {
             expression: Box::new(Expression::Value(Value::String(str.clone()))),
             r#type: type_to_ast_scalar_type(env, r#type)?,
         }),
+        // Aggregating the elements of an empty array yields null rather than an empty array,
+        // so we spell it out instead.
+        (serde_json::Value::Array(elements), database::Type::ArrayType(_))
+            if elements.is_empty() =>
+        {
+            Ok(Expression::Cast {
+                expression: Box::new(Expression::Value(Value::Array(vec![]))),
+                r#type: type_to_ast_scalar_type(env, r#type)?,
+            })
+        }
         (serde_json::Value::Array(_), database::Type::ArrayType(_)) => {
+            validate_input(env, value, r#type, "$")?;
+            construct(env, state, value, r#type)
+        }
+        (serde_json::Value::Object(_obj), database::Type::ScalarType(scalar_type))
+            if is_hstore(env, scalar_type) =>
+        {
             let value_expression =
                 sql::ast::Expression::Value(sql::ast::Value::JsonValue(value.clone()));
             translate_projected(env, state, r#type, value_expression)
         }
         (serde_json::Value::Object(_obj), database::Type::CompositeType(_type_name)) => {
-            let value_expression =
-                sql::ast::Expression::Value(sql::ast::Value::JsonValue(value.clone()));
-            translate_projected(env, state, r#type, value_expression)
+            validate_input(env, value, r#type, "$")?;
+            construct(env, state, value, r#type)
         }
         // If the type is not congruent with the value constructor we simply pass the json value
         // raw and cast to the specified type. This allows users to consume any json values,
@@ -51,6 +81,207 @@ pub fn translate(
     }
 }
 
+/// Float8 represents every integer up to this magnitude exactly.
+const MAX_EXACT_FLOAT8_INTEGER: u64 = 1 << 53;
+
+/// Numbers are written into the query as float8 literals, which would round integers beyond
+/// the range float8 represents exactly. When such an integer is used as an `int8` or `numeric`
+/// we bind it as a parameter of its own type instead, so that it reaches the database intact.
+///
+/// Fractional numbers are still written as float8 literals. Requests are parsed without
+/// serde_json's `arbitrary_precision`, so such numbers have already been rounded to the nearest
+/// float8 by the time they get here; a `numeric` which needs more precision must be sent as a
+/// string, which is cast from its text.
+fn exact_integer(
+    env: &Env,
+    r#type: &database::Type,
+    n: &serde_json::Number,
+) -> Option<sql::string::TypedValue> {
+    let database::Type::ScalarType(scalar_type) = r#type else {
+        return None;
+    };
+    if !matches!(
+        env.lookup_type_representation(scalar_type),
+        Some(
+            database::TypeRepresentation::Int64
+                | database::TypeRepresentation::Int64AsString
+                | database::TypeRepresentation::BigDecimal
+                | database::TypeRepresentation::BigDecimalAsString
+        )
+    ) {
+        return None;
+    }
+    match n.as_i64() {
+        Some(int) if int.unsigned_abs() > MAX_EXACT_FLOAT8_INTEGER => {
+            Some(sql::string::TypedValue::Int8(int))
+        }
+        Some(_) => None,
+        // only integers beyond the range of int8 are neither i64 nor f64.
+        None if n.is_u64() => Some(sql::string::TypedValue::Numeric(n.to_string())),
+        None => None,
+    }
+}
+
+/// Construct a structured value which has been validated against its type.
+///
+/// Values of composite types whose field positions are known are built field by field, and
+/// arrays of them element by element, with each field cast to its own type:
+/// ```sql
+/// cast(ROW(cast(<field> as <field type>), ...) as <composite type>)
+/// cast(ARRAY[<element>, ...] as <composite type>[])
+/// ```
+/// Other structured values are passed as json and converted by the database, using
+/// `jsonb_populate_record` for composite types.
+fn construct(
+    env: &Env,
+    state: &mut State,
+    value: &serde_json::Value,
+    r#type: &database::Type,
+) -> Result<sql::ast::Expression, Error> {
+    match (value, r#type) {
+        (serde_json::Value::Object(object), database::Type::CompositeType(type_name)) => {
+            let composite_type = env.lookup_composite_type(type_name)?;
+            let Some(fields) = composite_type.fields_in_order() else {
+                return translate_json(env, state, value, r#type);
+            };
+            let field_values = fields
+                .into_iter()
+                .map(|(field_name, field_type)| {
+                    let field_value = object
+                        .get(field_name.as_str())
+                        .unwrap_or(&serde_json::Value::Null);
+                    construct(env, state, field_value, field_type)
+                })
+                .collect::<Result<Vec<_>, Error>>()?;
+            Ok(Expression::Cast {
+                expression: Box::new(Expression::RowConstructor(field_values)),
+                r#type: type_to_ast_scalar_type(env, r#type)?,
+            })
+        }
+        (serde_json::Value::Array(elements), database::Type::ArrayType(element_type))
+            if !elements.is_empty() =>
+        {
+            if !matches!(**element_type, database::Type::CompositeType(_)) {
+                return translate_json(env, state, value, r#type);
+            }
+            let element_values = elements
+                .iter()
+                .map(|element| construct(env, state, element, element_type))
+                .collect::<Result<Vec<_>, Error>>()?;
+            Ok(Expression::Cast {
+                expression: Box::new(Expression::ArrayConstructor(element_values)),
+                r#type: type_to_ast_scalar_type(env, r#type)?,
+            })
+        }
+        _ => match translate(env, state, value, r#type)? {
+            expression @ Expression::Cast { .. } => Ok(expression),
+            expression => Ok(Expression::Cast {
+                expression: Box::new(expression),
+                r#type: type_to_ast_scalar_type(env, r#type)?,
+            }),
+        },
+    }
+}
+
+/// Pass a structured value as json, and convert it to its type in the database.
+fn translate_json(
+    env: &Env,
+    state: &mut State,
+    value: &serde_json::Value,
+    r#type: &database::Type,
+) -> Result<sql::ast::Expression, Error> {
+    let value_expression = sql::ast::Expression::Value(sql::ast::Value::JsonValue(value.clone()));
+    translate_projected(env, state, r#type, value_expression)
+}
+
+/// Check that a structured json value matches the shape of the type it is used as.
+///
+/// The database would reject a mismatch as well, but only with a cast error that does not say
+/// which part of the value is at fault. We report the path into the value instead,
+/// e.g. `$.address.lines[1]`.
+fn validate_input(
+    env: &Env,
+    value: &serde_json::Value,
+    r#type: &database::Type,
+    path: &str,
+) -> Result<(), Error> {
+    let mismatch = || Error::InputTypeMismatch {
+        path: path.to_string(),
+        expected: r#type.clone(),
+    };
+    match (value, r#type) {
+        (serde_json::Value::Null, _) => Ok(()),
+        (serde_json::Value::Object(object), database::Type::CompositeType(type_name)) => {
+            let composite_type = env.lookup_composite_type(type_name)?;
+            for (field_name, field_value) in object {
+                let field_path = format!("{path}.{field_name}");
+                let field_name: models::FieldName = field_name.as_str().into();
+                let field = match composite_type.lookup_column(&field_name) {
+                    Err(Error::ColumnNotFoundInCollection(..)) => Err(Error::UnknownInputField {
+                        path: field_path.clone(),
+                        type_name: type_name.clone(),
+                    }),
+                    result => result,
+                }?;
+                if field_value.is_null()
+                    && composite_type.field_nullable(&field_name)
+                        == Some(&metadata::Nullable::NonNullable)
+                {
+                    return Err(Error::InputTypeMismatch {
+                        path: field_path,
+                        expected: field.r#type,
+                    });
+                }
+                validate_input(env, field_value, &field.r#type, &field_path)?;
+            }
+            Ok(())
+        }
+        (serde_json::Value::Array(elements), database::Type::ArrayType(element_type)) => {
+            for (index, element) in elements.iter().enumerate() {
+                validate_input(env, element, element_type, &format!("{path}[{index}]"))?;
+            }
+            Ok(())
+        }
+        (_, database::Type::CompositeType(_) | database::Type::ArrayType(_)) => Err(mismatch()),
+        (_, database::Type::ScalarType(scalar_type)) => {
+            if scalar_input_fits(env.lookup_type_representation(scalar_type), value) {
+                Ok(())
+            } else {
+                Err(mismatch())
+            }
+        }
+    }
+}
+
+/// Whether a json value is acceptable input for a scalar type.
+///
+/// Postgres parses json strings and numbers into most scalar types, so we only reject values
+/// which can never be converted: objects and arrays where a plain value is expected, and
+/// strings which are not variants of an enum.
+fn scalar_input_fits(
+    type_representation: Option<&metadata::TypeRepresentation>,
+    value: &serde_json::Value,
+) -> bool {
+    match (type_representation, value) {
+        (
+            None
+            | Some(
+                metadata::TypeRepresentation::Json
+                | metadata::TypeRepresentation::Hstore
+                | metadata::TypeRepresentation::Geography
+                | metadata::TypeRepresentation::Geometry,
+            ),
+            _,
+        ) => true,
+        (
+            Some(metadata::TypeRepresentation::Enum(variants)),
+            serde_json::Value::String(variant),
+        ) => variants.contains(variant),
+        (Some(_), serde_json::Value::Object(_) | serde_json::Value::Array(_)) => false,
+        (Some(_), _) => true,
+    }
+}
+
 /// Translate a NDC 'Type' to an SQL scalar type.
 pub(crate) fn type_to_ast_scalar_type(
     env: &Env,
@@ -144,6 +375,7 @@ pub(crate) fn translate_projected(
                 expression,
                 alias: array_table.clone(),
                 column: element_column.clone(),
+                ordinality_column: None,
             };
 
             let element_expression =
@@ -167,20 +399,397 @@ pub(crate) fn translate_projected(
 
             sql::ast::Expression::CorrelatedSubSelect(Box::new(result_select))
         }
+        database::Type::ScalarType(scalar_type) if is_hstore(env, scalar_type) => {
+            translate_projected_hstore(env, state, r#type, expression)?
+        }
+        database::Type::ScalarType(scalar_type) if is_bytes(env, scalar_type) => {
+            sql::helpers::decode_base64(json_as_text(expression))
+        }
         database::Type::ScalarType(_) => sql::ast::Expression::Cast {
-            expression: Box::new(sql::ast::Expression::BinaryOperation {
-                left: Box::new(expression),
-                operator: sql::ast::BinaryOperator("#>>".to_string()),
-                right: Box::new(sql::ast::Expression::Cast {
-                    expression: Box::new(sql::ast::Expression::Value(sql::ast::Value::Array(
-                        vec![],
-                    ))),
-                    r#type: sql::ast::ScalarType::ArrayType(sql::helpers::text_type_name()),
-                }),
-            }),
+            expression: Box::new(json_as_text(expression)),
             r#type: type_to_ast_scalar_type(env, r#type)?,
         },
     };
 
     Ok(result)
 }
+
+/// The text of a jsonb scalar: `<expression> #>> cast(ARRAY[] as text[])`.
+fn json_as_text(expression: sql::ast::Expression) -> sql::ast::Expression {
+    sql::ast::Expression::BinaryOperation {
+        left: Box::new(expression),
+        operator: sql::ast::BinaryOperator("#>>".to_string()),
+        right: Box::new(sql::ast::Expression::Cast {
+            expression: Box::new(sql::ast::Expression::Value(sql::ast::Value::Array(vec![]))),
+            r#type: sql::ast::ScalarType::ArrayType(sql::helpers::text_type_name()),
+        }),
+    }
+}
+
+/// Is this scalar type represented as base64 encoded bytes?
+pub(crate) fn is_bytes(env: &Env, scalar_type: &models::ScalarTypeName) -> bool {
+    matches!(
+        env.lookup_type_representation(scalar_type),
+        Some(database::TypeRepresentation::Bytes)
+    )
+}
+
+/// Is this scalar type represented as an hstore?
+fn is_hstore(env: &Env, scalar_type: &models::ScalarTypeName) -> bool {
+    matches!(
+        env.lookup_type_representation(scalar_type),
+        Some(database::TypeRepresentation::Hstore)
+    )
+}
+
+/// There is no cast from jsonb to hstore, so we build the hstore from the keys and values
+/// of the object instead:
+/// ```sql
+/// ( SELECT
+///     coalesce(
+///       "public"."hstore"(array_agg("pair"."key"), array_agg("pair"."value")),
+///       cast('' as "public"."hstore")
+///     ) AS "hstore"
+///   FROM
+///     jsonb_each_text(<expression>) AS "pair"("key", "value")
+/// )
+/// ```
+fn translate_projected_hstore(
+    env: &Env,
+    state: &mut State,
+    r#type: &database::Type,
+    expression: sql::ast::Expression,
+) -> Result<sql::ast::Expression, Error> {
+    let pair_table = state.make_table_alias("pair".to_string());
+    let key_column = sql::helpers::make_column_alias("key".to_string());
+    let value_column = sql::helpers::make_column_alias("value".to_string());
+
+    let aggregate_column = |column: &sql::ast::ColumnAlias| sql::ast::Expression::FunctionCall {
+        function: sql::ast::Function::Unknown("array_agg".to_string()),
+        args: vec![sql::ast::Expression::ColumnReference(
+            ColumnReference::AliasedColumn {
+                table: sql::ast::TableReference::AliasedTable(pair_table.clone()),
+                column: column.clone(),
+            },
+        )],
+    };
+
+    // The extension defines the `hstore` function in the same schema as the type, which need not
+    // be on the search path.
+    let hstore_type = type_to_ast_scalar_type(env, r#type)?;
+    let hstore_function = match &hstore_type {
+        sql::ast::ScalarType::BaseType(sql::ast::ScalarTypeName::Qualified {
+            schema_name, ..
+        }) => sql::ast::Function::Qualified {
+            schema_name: schema_name.clone(),
+            function_name: "hstore".to_string(),
+        },
+        _ => sql::ast::Function::Unknown("hstore".to_string()),
+    };
+
+    let hstore_expression = sql::ast::Expression::FunctionCall {
+        function: sql::ast::Function::Coalesce,
+        args: vec![
+            sql::ast::Expression::FunctionCall {
+                function: hstore_function,
+                args: vec![
+                    aggregate_column(&key_column),
+                    aggregate_column(&value_column),
+                ],
+            },
+            // An empty object has no pairs to aggregate.
+            sql::ast::Expression::Cast {
+                expression: Box::new(sql::ast::Expression::Value(sql::ast::Value::String(
+                    String::new(),
+                ))),
+                r#type: hstore_type,
+            },
+        ],
+    };
+
+    let mut result_select = sql::helpers::simple_select(vec![(
+        sql::helpers::make_column_alias("hstore".to_string()),
+        hstore_expression,
+    )]);
+
+    result_select.from = Some(sql::ast::From::JsonbEachText {
+        expression,
+        alias: pair_table,
+        key_column,
+        value_column,
+    });
+
+    Ok(sql::ast::Expression::CorrelatedSubSelect(Box::new(
+        result_select,
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{translate, validate_input};
+    use crate::translation::error::Error;
+    use crate::translation::helpers::{Env, State};
+    use crate::translation::options::Options;
+    use query_engine_metadata::metadata;
+    use query_engine_sql::sql;
+    use std::collections::BTreeMap;
+
+    fn field(r#type: metadata::Type) -> metadata::FieldInfo {
+        metadata::FieldInfo {
+            field_name: String::new(),
+            r#type,
+            nullable: metadata::Nullable::Nullable,
+            has_default: metadata::HasDefault::NoDefault,
+            description: None,
+            position: None,
+        }
+    }
+
+    fn positioned(r#type: metadata::Type, position: u16) -> metadata::FieldInfo {
+        metadata::FieldInfo {
+            position: Some(position),
+            ..field(r#type)
+        }
+    }
+
+    fn sample_metadata() -> metadata::Metadata {
+        let mut metadata = metadata::Metadata::empty();
+        metadata.composite_types.0.insert(
+            "address".into(),
+            metadata::CompositeType {
+                type_name: "address".to_string(),
+                schema_name: None,
+                fields: BTreeMap::from([
+                    (
+                        "city".into(),
+                        field(metadata::Type::ScalarType("text".into())),
+                    ),
+                    (
+                        "lines".into(),
+                        field(metadata::Type::ArrayType(Box::new(
+                            metadata::Type::ScalarType("text".into()),
+                        ))),
+                    ),
+                ]),
+                description: None,
+            },
+        );
+        metadata.composite_types.0.insert(
+            "person".into(),
+            metadata::CompositeType {
+                type_name: "person".to_string(),
+                schema_name: None,
+                fields: BTreeMap::from([(
+                    "addresses".into(),
+                    field(metadata::Type::ArrayType(Box::new(
+                        metadata::Type::CompositeType("address".into()),
+                    ))),
+                )]),
+                description: None,
+            },
+        );
+        metadata.composite_types.0.insert(
+            "point".into(),
+            metadata::CompositeType {
+                type_name: "point".to_string(),
+                schema_name: None,
+                fields: BTreeMap::from([
+                    (
+                        "x".into(),
+                        positioned(metadata::Type::ScalarType("int4".into()), 1),
+                    ),
+                    (
+                        "y".into(),
+                        positioned(metadata::Type::ScalarType("int4".into()), 2),
+                    ),
+                    (
+                        "label".into(),
+                        positioned(metadata::Type::ScalarType("text".into()), 3),
+                    ),
+                ]),
+                description: None,
+            },
+        );
+        metadata
+    }
+
+    fn translate_to_sql(value: &serde_json::Value, r#type: &metadata::Type) -> String {
+        translate_to_parameterized_sql(value, r#type).sql
+    }
+
+    fn translate_to_parameterized_sql(
+        value: &serde_json::Value,
+        r#type: &metadata::Type,
+    ) -> sql::string::SQL {
+        let mut metadata = sample_metadata();
+        for (scalar_type, type_representation) in [
+            ("int4", None),
+            ("bytea", Some(metadata::TypeRepresentation::Bytes)),
+            ("int8", Some(metadata::TypeRepresentation::Int64AsString)),
+            ("text", None),
+            ("uuid", None),
+        ] {
+            metadata.scalar_types.0.insert(
+                scalar_type.into(),
+                metadata::ScalarType {
+                    type_name: scalar_type.to_string(),
+                    schema_name: None,
+                    description: None,
+                    aggregate_functions: BTreeMap::new(),
+                    comparison_operators: BTreeMap::new(),
+                    type_representation,
+                    cast_as: None,
+                },
+            );
+        }
+        let options = Options::default();
+        let env = Env::new(&metadata, BTreeMap::new(), None, &options);
+        let expression = translate(&env, &mut State::new(), value, r#type).unwrap();
+        let mut sql = sql::string::SQL::new();
+        expression.to_sql(&mut sql);
+        sql
+    }
+
+    fn array_of(scalar_type: &str) -> metadata::Type {
+        metadata::Type::ArrayType(Box::new(metadata::Type::ScalarType(scalar_type.into())))
+    }
+
+    #[test]
+    fn empty_arrays_are_not_null() {
+        for scalar_type in ["int4", "text", "uuid"] {
+            assert_eq!(
+                translate_to_sql(&serde_json::json!([]), &array_of(scalar_type)),
+                format!(r#"cast(ARRAY [] as "{scalar_type}"[])"#)
+            );
+        }
+    }
+
+    #[test]
+    fn array_elements_are_cast_to_the_element_type() {
+        for (scalar_type, value) in [
+            ("int4", serde_json::json!([1, null, 3])),
+            ("text", serde_json::json!(["a", null])),
+            (
+                "uuid",
+                serde_json::json!([null, "3fa85f64-5717-4562-b3fc-2c963f66afa6"]),
+            ),
+        ] {
+            let sql = translate_to_sql(&value, &array_of(scalar_type));
+            assert!(sql.contains("array_agg(cast("), "{sql}");
+            assert!(sql.contains(&format!(r#" as "{scalar_type}")"#)), "{sql}");
+            assert!(sql.contains("jsonb_array_elements("), "{sql}");
+        }
+    }
+
+    #[test]
+    fn large_integers_are_bound_as_their_own_type() {
+        let int8 = metadata::Type::ScalarType("int8".into());
+        assert_eq!(translate_to_sql(&serde_json::json!(42), &int8), "42");
+
+        let sql =
+            translate_to_parameterized_sql(&serde_json::json!(9_007_199_254_740_993_i64), &int8);
+        assert_eq!(sql.sql, r#"cast($1 as "int8")"#);
+        assert_eq!(
+            sql.params,
+            vec![sql::string::Param::Typed(sql::string::TypedValue::Int8(
+                9_007_199_254_740_993
+            ))]
+        );
+
+        let sql = translate_to_parameterized_sql(&serde_json::json!(u64::MAX), &int8);
+        assert_eq!(
+            sql.params,
+            vec![sql::string::Param::Typed(sql::string::TypedValue::Numeric(
+                u64::MAX.to_string()
+            ))]
+        );
+    }
+
+    #[test]
+    fn bytes_are_decoded_from_base64() {
+        let bytea = metadata::Type::ScalarType("bytea".into());
+        let sql = translate_to_parameterized_sql(&serde_json::json!("aGVsbG8="), &bytea);
+        assert_eq!(sql.sql, "decode($1, $2)");
+        assert_eq!(
+            sql.params,
+            vec![
+                sql::string::Param::String("aGVsbG8=".to_string()),
+                sql::string::Param::String("base64".to_string()),
+            ]
+        );
+
+        let sql = translate_to_sql(
+            &serde_json::json!(["aGVsbG8=", null]),
+            &metadata::Type::ArrayType(Box::new(bytea)),
+        );
+        assert!(sql.contains("array_agg(decode("), "{sql}");
+    }
+
+    #[test]
+    fn composite_values_are_constructed_in_field_order() {
+        let point = metadata::Type::CompositeType("point".into());
+        assert_eq!(
+            translate_to_sql(&serde_json::json!({ "y": 2, "x": 1 }), &point),
+            r#"cast(ROW(cast(1 as "int4"), cast(2 as "int4"), cast(null as "text")) as "point")"#
+        );
+        assert_eq!(
+            translate_to_sql(
+                &serde_json::json!([{ "x": 1, "y": 2 }, null]),
+                &metadata::Type::ArrayType(Box::new(point))
+            ),
+            r#"cast(ARRAY[cast(ROW(cast(1 as "int4"), cast(2 as "int4"), cast(null as "text")) as "point"), cast(null as "point")] as "point"[])"#
+        );
+    }
+
+    #[test]
+    fn composite_values_without_field_positions_are_populated_from_json() {
+        let sql = translate_to_sql(
+            &serde_json::json!({ "city": "Oslo" }),
+            &metadata::Type::CompositeType("address".into()),
+        );
+        assert!(sql.starts_with("jsonb_populate_record("), "{sql}");
+    }
+
+    fn validate(value: &serde_json::Value) -> Result<(), Error> {
+        let metadata = sample_metadata();
+        let options = Options::default();
+        let env = Env::new(&metadata, BTreeMap::new(), None, &options);
+        validate_input(
+            &env,
+            value,
+            &metadata::Type::CompositeType("person".into()),
+            "$",
+        )
+    }
+
+    #[test]
+    fn accepts_matching_input() {
+        let value = serde_json::json!({
+            "addresses": [{ "city": "Oslo", "lines": ["Karl Johans gate 1"] }, null]
+        });
+        assert!(validate(&value).is_ok());
+    }
+
+    #[test]
+    fn reports_unknown_nested_field() {
+        let value = serde_json::json!({ "addresses": [{ "city": "Oslo" }, { "town": "Bergen" }] });
+        match validate(&value) {
+            Err(Error::UnknownInputField { path, type_name }) => {
+                assert_eq!(path, "$.addresses[1].town");
+                assert_eq!(type_name.as_str(), "address");
+            }
+            result => panic!("unexpected result: {result:?}"),
+        }
+    }
+
+    #[test]
+    fn reports_mistyped_nested_field() {
+        let value = serde_json::json!({ "addresses": [{ "lines": "Karl Johans gate 1" }] });
+        match validate(&value) {
+            Err(Error::InputTypeMismatch { path, .. }) => {
+                assert_eq!(path, "$.addresses[0].lines");
+            }
+            result => panic!("unexpected result: {result:?}"),
+        }
+    }
+}
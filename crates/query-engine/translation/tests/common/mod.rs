@@ -20,12 +20,12 @@ pub async fn test_query_translation(testname: &str) -> anyhow::Result<String> {
             "the translation tests do not rely on a database connection".into(),
         )]),
     )?;
-    let metadata = configuration.metadata;
+    let options = translation::options::Options::from_configuration(&configuration);
 
     let request =
         serde_json::from_str(&fs::read_to_string(directory.join("request.json")).unwrap()).unwrap();
 
-    let plan = translation::query::translate(&metadata, request)?;
+    let plan = translation::query::translate(&configuration.metadata, &options, request)?;
 
     let mut sqls: Vec<String> = vec![];
 
@@ -38,6 +38,7 @@ pub async fn test_query_translation(testname: &str) -> anyhow::Result<String> {
         sqls.push(pretty);
     }
 
+    let empty_result = plan.query.empty_result.clone();
     let query = plan.query.query_sql();
 
     let pretty = sqlformat::format(
@@ -63,7 +64,13 @@ pub async fn test_query_translation(testname: &str) -> anyhow::Result<String> {
         sqls.push(pretty);
     }
 
-    Ok(format!("{}{:#?}", sqls.join(""), param))
+    let mut result = format!("{}{:#?}", sqls.join(""), param);
+    if let Some(empty_result) = empty_result {
+        result.push_str(&format!(
+            "\n\nResult without running the query: {empty_result}"
+        ));
+    }
+    Ok(result)
 }
 
 /// Translate a mutation to SQL and compare against the snapshot.
@@ -81,7 +88,14 @@ pub async fn test_mutation_translation(
             "the translation tests do not rely on a database connection".into(),
         )]),
     )?;
-    let metadata = configuration.metadata;
+    let options = translation::options::Options::from_configuration(&configuration);
+    // the goldenfiles which do not ask for a version are translated with the latest one
+    let options = translation::options::Options {
+        mutations_version: options.mutations_version.or(Some(
+            query_engine_metadata::metadata::mutations::MutationsVersion::V2,
+        )),
+        ..options
+    };
     let request: ndc_models::MutationRequest =
         serde_json::from_str(&fs::read_to_string(directory.join("request.json")).unwrap()).unwrap();
 
@@ -90,16 +104,19 @@ pub async fn test_mutation_translation(
         .into_iter()
         .map(|operation| {
             translation::mutation::translate(
-                &metadata,
+                &configuration.metadata,
+                &options,
                 operation,
                 request.collection_relationships.clone(),
-                Some(query_engine_metadata::metadata::mutations::MutationsVersion::V2),
-                configuration.mutations_prefix.clone(),
             )
         })
         .collect::<Result<Vec<_>, translation::error::Error>>()?;
 
-    let plan = sql::execution_plan::simple_mutations_execution_plan(isolation_level, mutations);
+    let plan = sql::execution_plan::simple_mutations_execution_plan(
+        isolation_level,
+        sql::execution_plan::FailurePolicy::default(),
+        mutations,
+    );
     let mut sqls: Vec<String> = vec![];
     let mut params: Vec<Vec<(usize, sql::string::Param)>> = vec![];
 
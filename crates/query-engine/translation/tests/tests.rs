@@ -46,6 +46,14 @@ async fn select_composite_column_simple() {
     insta::assert_snapshot!(result);
 }
 
+#[tokio::test]
+async fn select_composite_column_as_json() {
+    let result = common::test_translation("select_composite_column_as_json")
+        .await
+        .unwrap();
+    insta::assert_snapshot!(result);
+}
+
 #[tokio::test]
 async fn select_composite_column_complex() {
     let result = common::test_translation("select_composite_column_complex")
@@ -109,6 +117,70 @@ async fn it_converts_select_with_limit() {
     insta::assert_snapshot!(result);
 }
 
+#[tokio::test]
+async fn it_converts_select_with_limit_with_ties() {
+    let result = common::test_translation("select_with_limit_with_ties")
+        .await
+        .unwrap();
+    insta::assert_snapshot!(result);
+}
+
+#[tokio::test]
+async fn it_converts_select_with_statement_timeout() {
+    let result = common::test_translation("select_with_statement_timeout")
+        .await
+        .unwrap();
+    insta::assert_snapshot!(result);
+}
+
+#[tokio::test]
+async fn it_converts_select_with_statement_timeout_on_relationship() {
+    let result = common::test_translation("select_with_statement_timeout_on_relationship")
+        .await
+        .unwrap();
+    insta::assert_snapshot!(result);
+}
+
+#[tokio::test]
+async fn it_converts_select_from_snapshot() {
+    let result = common::test_translation("select_from_snapshot")
+        .await
+        .unwrap();
+    insta::assert_snapshot!(result);
+}
+
+#[tokio::test]
+async fn it_converts_select_from_snapshot_without_snapshot_reads() {
+    let result = common::test_translation("select_from_snapshot_without_snapshot_reads")
+        .await
+        .unwrap();
+    insta::assert_snapshot!(result);
+}
+
+#[tokio::test]
+async fn it_converts_select_with_collection_policy() {
+    let result = common::test_translation("select_with_collection_policy")
+        .await
+        .unwrap();
+    insta::assert_snapshot!(result);
+}
+
+#[tokio::test]
+async fn it_converts_select_with_row_expiry() {
+    let result = common::test_translation("select_with_row_expiry")
+        .await
+        .unwrap();
+    insta::assert_snapshot!(result);
+}
+
+#[tokio::test]
+async fn it_converts_select_with_soft_delete() {
+    let result = common::test_translation("select_with_soft_delete")
+        .await
+        .unwrap();
+    insta::assert_snapshot!(result);
+}
+
 #[tokio::test]
 async fn it_select_where_string() {
     let result = common::test_translation("select_where_string")
@@ -117,6 +189,54 @@ async fn it_select_where_string() {
     insta::assert_snapshot!(result);
 }
 
+#[tokio::test]
+async fn it_select_where_boolean_column() {
+    let result = common::test_translation("select_where_boolean_column")
+        .await
+        .unwrap();
+    insta::assert_snapshot!(result);
+}
+
+#[tokio::test]
+async fn it_select_where_boolean_column_strict() {
+    let result = common::test_translation("select_where_boolean_column_strict")
+        .await
+        .unwrap();
+    insta::assert_snapshot!(result);
+}
+
+#[tokio::test]
+async fn it_select_where_in_empty_list() {
+    let result = common::test_translation("select_where_in_empty_list")
+        .await
+        .unwrap();
+    insta::assert_snapshot!(result);
+}
+
+#[tokio::test]
+async fn it_select_nested_fields_of_json_column() {
+    let result = common::test_translation("select_nested_fields_of_json_column")
+        .await
+        .unwrap();
+    insta::assert_snapshot!(result);
+}
+
+#[tokio::test]
+async fn it_select_where_in_long_list() {
+    let result = common::test_translation("select_where_in_long_list")
+        .await
+        .unwrap();
+    insta::assert_snapshot!(result);
+}
+
+#[tokio::test]
+async fn it_select_where_not_in_with_nulls() {
+    let result = common::test_translation("select_where_not_in_with_nulls")
+        .await
+        .unwrap();
+    insta::assert_snapshot!(result);
+}
+
 #[tokio::test]
 async fn it_select_where_not_null() {
     let result = common::test_translation("select_where_not_null")
@@ -149,6 +269,14 @@ async fn it_select_where_unrelated_exists() {
     insta::assert_snapshot!(result);
 }
 
+#[tokio::test]
+async fn it_select_where_unrelated_exists_with_row_expiry() {
+    let result = common::test_translation("select_where_unrelated_exists_with_row_expiry")
+        .await
+        .unwrap();
+    insta::assert_snapshot!(result);
+}
+
 #[tokio::test]
 async fn it_select_where_related_exists() {
     let result = common::test_translation("select_where_related_exists")
@@ -157,6 +285,14 @@ async fn it_select_where_related_exists() {
     insta::assert_snapshot!(result);
 }
 
+#[tokio::test]
+async fn it_select_where_related_exists_with_collection_policy() {
+    let result = common::test_translation("select_where_related_exists_with_collection_policy")
+        .await
+        .unwrap();
+    insta::assert_snapshot!(result);
+}
+
 #[tokio::test]
 async fn select_where_array_relationship() {
     let result = common::test_translation("select_where_array_relationship")
@@ -173,6 +309,30 @@ async fn it_aggregate_count_albums() {
     insta::assert_snapshot!(result);
 }
 
+#[tokio::test]
+async fn it_aggregate_count_albums_aggregates_limit() {
+    let result = common::test_translation("aggregate_count_albums_aggregates_limit")
+        .await
+        .unwrap();
+    insta::assert_snapshot!(result);
+}
+
+#[tokio::test]
+async fn it_aggregate_count_albums_aggregates_limit_variable() {
+    let result = common::test_translation("aggregate_count_albums_aggregates_limit_variable")
+        .await
+        .unwrap();
+    insta::assert_snapshot!(result);
+}
+
+#[tokio::test]
+async fn it_aggregate_count_estimated() {
+    let result = common::test_translation("aggregate_count_estimated")
+        .await
+        .unwrap();
+    insta::assert_snapshot!(result);
+}
+
 #[tokio::test]
 async fn it_aggregate_distinct_albums() {
     let result = common::test_translation("aggregate_distinct_albums")
@@ -197,6 +357,55 @@ async fn aggregate_limit_offset_order_by() {
     insta::assert_snapshot!(result);
 }
 
+#[tokio::test]
+async fn it_computed_relationship() {
+    let result = common::test_translation("computed_relationship")
+        .await
+        .unwrap();
+    insta::assert_snapshot!(result);
+}
+
+#[tokio::test]
+async fn it_rejects_sorting_by_computed_relationship_after_relationship() {
+    let error = common::test_translation("sorting_by_computed_relationship_after_relationship")
+        .await
+        .unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "Queries containing ordering through the computed relationship 'Albums' after another relationship are not supported."
+    );
+}
+
+#[tokio::test]
+async fn it_computed_field() {
+    let result = common::test_translation("computed_field").await.unwrap();
+    insta::assert_snapshot!(result);
+}
+
+#[tokio::test]
+async fn it_computed_field_filter_sort() {
+    let result = common::test_translation("computed_field_filter_sort")
+        .await
+        .unwrap();
+    insta::assert_snapshot!(result);
+}
+
+#[tokio::test]
+async fn it_computed_field_aggregates() {
+    let result = common::test_translation("computed_field_aggregates")
+        .await
+        .unwrap();
+    insta::assert_snapshot!(result);
+}
+
+#[tokio::test]
+async fn it_distribution_key_pushdown() {
+    let result = common::test_translation("distribution_key_pushdown")
+        .await
+        .unwrap();
+    insta::assert_snapshot!(result);
+}
+
 #[tokio::test]
 async fn it_simple_array_relationship() {
     let result = common::test_translation("simple_array_relationship")
@@ -205,6 +414,14 @@ async fn it_simple_array_relationship() {
     insta::assert_snapshot!(result);
 }
 
+#[tokio::test]
+async fn it_select_with_planner_hints() {
+    let result = common::test_translation("select_with_planner_hints")
+        .await
+        .unwrap();
+    insta::assert_snapshot!(result);
+}
+
 #[tokio::test]
 async fn it_simple_object_relationship() {
     let result = common::test_translation("simple_object_relationship")
@@ -213,6 +430,14 @@ async fn it_simple_object_relationship() {
     insta::assert_snapshot!(result);
 }
 
+#[tokio::test]
+async fn it_simple_object_relationship_without_lateral_joins() {
+    let result = common::test_translation("simple_object_relationship_without_lateral_joins")
+        .await
+        .unwrap();
+    insta::assert_snapshot!(result);
+}
+
 #[tokio::test]
 async fn nested_array_relationships() {
     let result = common::test_translation("nested_array_relationships")
@@ -227,6 +452,14 @@ async fn nested_aggregates() {
     insta::assert_snapshot!(result);
 }
 
+#[tokio::test]
+async fn nested_aggregates_only() {
+    let result = common::test_translation("nested_aggregates_only")
+        .await
+        .unwrap();
+    insta::assert_snapshot!(result);
+}
+
 #[tokio::test]
 async fn dup_array_relationship() {
     let result = common::test_translation("dup_array_relationship")
@@ -333,6 +566,70 @@ mod negative_tests {
             .expect_err("Expected error");
         insta::assert_snapshot!(result.to_string());
     }
+
+    #[tokio::test]
+    async fn sorting_by_unsortable_column() {
+        let result = common::test_translation("sorting_by_unsortable_column")
+            .await
+            .expect_err("Expected error");
+        insta::assert_snapshot!(result.to_string());
+    }
+
+    #[tokio::test]
+    async fn selecting_unselectable_column() {
+        let result = common::test_translation("selecting_unselectable_column")
+            .await
+            .expect_err("Expected error");
+        insta::assert_snapshot!(result.to_string());
+    }
+
+    #[tokio::test]
+    async fn filtering_by_unfilterable_column() {
+        let result = common::test_translation("filtering_by_unfilterable_column")
+            .await
+            .expect_err("Expected error");
+        insta::assert_snapshot!(result.to_string());
+    }
+
+    #[tokio::test]
+    async fn filtering_by_unfilterable_column_in_exists() {
+        let result = common::test_translation("filtering_by_unfilterable_column_in_exists")
+            .await
+            .expect_err("Expected error");
+        insta::assert_snapshot!(result.to_string());
+    }
+
+    #[tokio::test]
+    async fn distinct_on_unsortable_column() {
+        let result = common::test_translation("distinct_on_unsortable_column")
+            .await
+            .expect_err("Expected error");
+        insta::assert_snapshot!(result.to_string());
+    }
+
+    #[tokio::test]
+    async fn relationship_depth_exceeds_limit() {
+        let result = common::test_translation("relationship_depth_exceeds_limit")
+            .await
+            .expect_err("Expected error");
+        insta::assert_snapshot!(result.to_string());
+    }
+
+    #[tokio::test]
+    async fn exists_joins_exceed_limit() {
+        let result = common::test_translation("exists_joins_exceed_limit")
+            .await
+            .expect_err("Expected error");
+        insta::assert_snapshot!(result.to_string());
+    }
+
+    #[tokio::test]
+    async fn order_by_joins_exceed_limit() {
+        let result = common::test_translation("order_by_joins_exceed_limit")
+            .await
+            .expect_err("Expected error");
+        insta::assert_snapshot!(result.to_string());
+    }
 }
 
 mod native_queries {
@@ -379,6 +676,15 @@ mod native_queries {
         .unwrap();
         insta::assert_snapshot!(result);
     }
+
+    #[tokio::test]
+    async fn select_artist_with_albums_by_column_argument() {
+        let result =
+            common::test_translation("native_queries/select_artist_with_albums_by_column_argument")
+                .await
+                .unwrap();
+        insta::assert_snapshot!(result);
+    }
 }
 
 mod types {
@@ -414,6 +720,15 @@ mod mutations {
         insta::assert_snapshot!(result);
     }
 
+    #[tokio::test]
+    async fn sequence_functions() {
+        let result =
+            common::test_mutation_translation(IsolationLevel::default(), "sequence_functions")
+                .await
+                .unwrap();
+        insta::assert_snapshot!(result);
+    }
+
     #[tokio::test]
     async fn v2_insert() {
         let result = common::test_mutation_translation(IsolationLevel::default(), "v2_insert")
@@ -422,6 +737,28 @@ mod mutations {
         insta::assert_snapshot!(result);
     }
 
+    #[tokio::test]
+    async fn v2_insert_generated_uuid() {
+        let result = common::test_mutation_translation(
+            IsolationLevel::default(),
+            "v2_insert_generated_uuid",
+        )
+        .await
+        .unwrap();
+        insta::assert_snapshot!(result);
+    }
+
+    #[tokio::test]
+    async fn v2_insert_affected_rows_only() {
+        let result = common::test_mutation_translation(
+            IsolationLevel::default(),
+            "v2_insert_affected_rows_only",
+        )
+        .await
+        .unwrap();
+        insta::assert_snapshot!(result);
+    }
+
     #[tokio::test]
     async fn v2_insert_empty_objects() {
         let result =
@@ -439,4 +776,129 @@ mod mutations {
                 .unwrap();
         insta::assert_snapshot!(result);
     }
+
+    #[tokio::test]
+    async fn v2_update_excluding_inherited_rows() {
+        let result = common::test_mutation_translation(
+            IsolationLevel::default(),
+            "v2_update_excluding_inherited_rows",
+        )
+        .await
+        .unwrap();
+        insta::assert_snapshot!(result);
+    }
+
+    #[tokio::test]
+    async fn v1_delete_soft() {
+        let result = common::test_mutation_translation(IsolationLevel::default(), "v1_delete_soft")
+            .await
+            .unwrap();
+        insta::assert_snapshot!(result);
+    }
+
+    #[tokio::test]
+    async fn v2_delete_with_collection_policy() {
+        let result = common::test_mutation_translation(
+            IsolationLevel::default(),
+            "v2_delete_with_collection_policy",
+        )
+        .await
+        .unwrap();
+        insta::assert_snapshot!(result);
+    }
+
+    #[tokio::test]
+    async fn v2_delete_soft() {
+        let result = common::test_mutation_translation(IsolationLevel::default(), "v2_delete_soft")
+            .await
+            .unwrap();
+        insta::assert_snapshot!(result);
+    }
+
+    #[tokio::test]
+    async fn v2_insert_with_audit_columns() {
+        let result = common::test_mutation_translation(
+            IsolationLevel::default(),
+            "v2_insert_with_audit_columns",
+        )
+        .await
+        .unwrap();
+        insta::assert_snapshot!(result);
+    }
+
+    #[tokio::test]
+    async fn v2_update_with_audit_columns() {
+        let result = common::test_mutation_translation(
+            IsolationLevel::default(),
+            "v2_update_with_audit_columns",
+        )
+        .await
+        .unwrap();
+        insta::assert_snapshot!(result);
+    }
+
+    #[tokio::test]
+    async fn v2_update_if_matched() {
+        let result =
+            common::test_mutation_translation(IsolationLevel::default(), "v2_update_if_matched")
+                .await
+                .unwrap();
+        insta::assert_snapshot!(result);
+    }
+
+    #[tokio::test]
+    async fn v1_insert_into_read_only_view() {
+        let error = common::test_mutation_translation(
+            IsolationLevel::default(),
+            "v1_insert_into_read_only_view",
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Procedure 'v1_insert_ArtistNames' not found."
+        );
+    }
+
+    #[tokio::test]
+    async fn v2_insert_into_read_only_view() {
+        let error = common::test_mutation_translation(
+            IsolationLevel::default(),
+            "v2_insert_into_read_only_view",
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Procedure 'v2_insert_ArtistNames' not found."
+        );
+    }
+
+    #[tokio::test]
+    async fn v2_insert_unwritable_column() {
+        let error = common::test_mutation_translation(
+            IsolationLevel::default(),
+            "v2_insert_unwritable_column",
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Unable to insert into the column 'name' in collection 'Artist'."
+        );
+    }
+
+    #[tokio::test]
+    async fn v2_update_unwritable_column() {
+        let error = common::test_mutation_translation(
+            IsolationLevel::default(),
+            "v2_update_unwritable_column",
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Unable to update the column 'height_cm' in collection 'Dog'."
+        );
+    }
 }
@@ -43,8 +43,12 @@ async fn introspection_is_idempotent(
         connection_string.into(),
     )]);
 
-    let introspected_configuration =
-        ndc_postgres_configuration::introspect(parsed_configuration.clone(), environment).await?;
+    let introspected_configuration = ndc_postgres_configuration::introspect(
+        parsed_configuration.clone(),
+        environment,
+        &ndc_postgres_configuration::SessionSettings::default(),
+    )
+    .await?;
 
     assert_eq!(parsed_configuration, introspected_configuration);
     Ok(())
@@ -1,5 +1,6 @@
 pub mod common;
 pub mod configuration_tests;
+pub mod distribution_tests;
 pub mod explain_tests;
 pub mod ndc_tests;
 pub mod query_tests;
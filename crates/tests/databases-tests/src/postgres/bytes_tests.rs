@@ -0,0 +1,62 @@
+//! Binary strings are sent as base64, keeping the order of the elements of arrays.
+
+use tests_common::request::run_query;
+
+use super::common;
+
+/// A native query returning a `bytea` and an array of them, whose elements are out of order
+/// once sorted.
+async fn bytes_configuration(directory: &std::path::Path) {
+    common::copy_chinook_configuration(directory, |configuration| {
+        configuration["metadata"]["types"]["scalar"]["bytea"] = serde_json::json!({
+            "typeName": "bytea",
+            "schemaName": "pg_catalog",
+            "description": null,
+            "aggregateFunctions": {},
+            "comparisonOperators": {},
+            "typeRepresentation": "bytes",
+        });
+        configuration["metadata"]["nativeOperations"]["queries"]["bytes"] = serde_json::json!({
+            "sql": {
+                "inline": "SELECT '\\x00ff10'::bytea AS bytes, ARRAY['\\x03'::bytea, '\\x01', '\\x02'] AS byte_arrays"
+            },
+            "columns": {
+                "bytes": {
+                    "name": "bytes",
+                    "type": { "scalarType": "bytea" },
+                    "nullable": "nullable",
+                    "description": null
+                },
+                "byte_arrays": {
+                    "name": "byte_arrays",
+                    "type": { "arrayType": { "scalarType": "bytea" } },
+                    "nullable": "nullable",
+                    "description": null
+                }
+            },
+            "arguments": {},
+            "description": null
+        });
+    })
+    .await;
+}
+
+#[tokio::test]
+async fn bytea_values_are_encoded_as_base64_in_order() {
+    let directory = tempfile::tempdir().unwrap();
+    bytes_configuration(directory.path()).await;
+    let router =
+        tests_common::router::create_router(directory.path(), common::CONNECTION_URI).await;
+
+    let result = run_query(router, "native_queries/select_bytes").await;
+
+    assert_eq!(
+        serde_json::to_value(result).unwrap(),
+        serde_json::json!([{
+            "rows": [{
+                "bytes": "AP8Q",
+                "byte_arrays": ["Aw==", "AQ==", "Ag=="]
+            }]
+        }])
+    );
+}
@@ -0,0 +1,131 @@
+//! Statements which we stop waiting for are cancelled on the database.
+
+#[cfg(test)]
+mod cancellation {
+    use std::time::Duration;
+
+    use query_engine_execution::database_info::{self, DatabaseInfo, DatabaseVersion};
+    use query_engine_execution::error::Error;
+    use query_engine_execution::metrics::Metrics;
+    use query_engine_execution::query;
+    use query_engine_execution::retry::RetryPolicy;
+    use query_engine_sql::sql;
+    use sqlx::postgres::{PgPool, PgPoolOptions};
+
+    use super::super::common::CONNECTION_URI;
+
+    /// A plan which sleeps for longer than any test waits before it queries anything.
+    fn sleeping_plan(
+        sleep: &str,
+    ) -> sql::execution_plan::ExecutionPlan<sql::execution_plan::Query> {
+        let mut statement = sql::string::SQL::new();
+        statement.append_syntax(sleep);
+        let mut plan = sql::execution_plan::simple_query_execution_plan(
+            sql::dialect::SqlDialect::default(),
+            None,
+            sql::execution_plan::VariablesSource::Parameter,
+            "sleep".into(),
+            sql::helpers::simple_select(vec![]),
+        );
+        plan.pre.push(sql::string::Statement(statement));
+        plan
+    }
+
+    fn database_info() -> DatabaseInfo {
+        DatabaseInfo {
+            system_name: database_info::DATABASE_POSTGRESQL,
+            system_version: DatabaseVersion {
+                string: None,
+                number: None,
+            },
+            server_host: None,
+            server_port: None,
+            server_username: None,
+            server_database: None,
+        }
+    }
+
+    fn no_retries() -> RetryPolicy {
+        RetryPolicy {
+            max_attempts: 1,
+            initial_backoff: Duration::ZERO,
+            max_backoff: Duration::ZERO,
+            jitter: false,
+        }
+    }
+
+    /// A pool with a single connection, so that the cancellation cannot be sent through it
+    /// while the statement holds on to that connection.
+    async fn single_connection_pool() -> PgPool {
+        PgPoolOptions::new()
+            .max_connections(1)
+            .acquire_timeout(Duration::from_secs(30))
+            .connect(CONNECTION_URI)
+            .await
+            .unwrap()
+    }
+
+    /// Wait for the database to stop running a statement, and fail if it does not.
+    async fn assert_cancelled(sleep: &str) {
+        let observer = PgPool::connect(CONNECTION_URI).await.unwrap();
+        for _ in 0..50 {
+            let running: i64 = sqlx::query_scalar(
+                "SELECT count(*) FROM pg_stat_activity WHERE state = 'active' AND query = $1",
+            )
+            .bind(sleep)
+            .fetch_one(&observer)
+            .await
+            .unwrap();
+            if running == 0 {
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+        panic!("the statement '{sleep}' is still running");
+    }
+
+    #[tokio::test]
+    async fn statements_past_the_timeout_are_cancelled() {
+        let sleep = "SELECT pg_sleep(31)";
+        let pool = single_connection_pool().await;
+        let metrics = Metrics::initialize(&mut prometheus::Registry::new()).unwrap();
+
+        let result = query::execute(
+            &pool,
+            &database_info(),
+            &metrics,
+            None,
+            Some(Duration::from_millis(500)),
+            &no_retries(),
+            sleeping_plan(sleep),
+        )
+        .await;
+
+        assert!(matches!(result, Err(Error::Timeout(_))), "{result:?}");
+        assert_cancelled(sleep).await;
+    }
+
+    #[tokio::test]
+    async fn abandoned_statements_are_cancelled_without_a_timeout() {
+        let sleep = "SELECT pg_sleep(32)";
+        let pool = single_connection_pool().await;
+        let metrics = Metrics::initialize(&mut prometheus::Registry::new()).unwrap();
+
+        let abandoned = tokio::time::timeout(
+            Duration::from_millis(500),
+            query::execute(
+                &pool,
+                &database_info(),
+                &metrics,
+                None,
+                None,
+                &no_retries(),
+                sleeping_plan(sleep),
+            ),
+        )
+        .await;
+
+        assert!(abandoned.is_err());
+        assert_cancelled(sleep).await;
+    }
+}
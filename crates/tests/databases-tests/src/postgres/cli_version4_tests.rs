@@ -14,9 +14,13 @@ async fn postgres_current_only_configure_initial_configuration_is_unchanged() {
         connection_string.into(),
     )]);
 
-    let default_configuration = introspect(args, environment)
-        .await
-        .expect("configuration::introspect");
+    let default_configuration = introspect(
+        args,
+        environment,
+        &ndc_postgres_configuration::SessionSettings::default(),
+    )
+    .await
+    .expect("configuration::introspect");
 
     insta::assert_json_snapshot!(default_configuration);
 }
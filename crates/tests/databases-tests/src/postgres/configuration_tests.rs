@@ -8,6 +8,8 @@
 //! they rely on supporting data (the chinook NDC metadata configuration) which we maintain only for
 //! the latest version.
 
+use ndc_postgres_configuration::version5::metadata::NativeQueryInfo;
+use ndc_postgres_configuration::version5::native_operations::Inference;
 use tests_common::common_tests::configuration_tests::*;
 
 pub const CHINOOK_NDC_METADATA_PATH: &str = "static/postgres/v5-configuration";
@@ -28,6 +30,26 @@ async fn postgres_current_only_configure_is_idempotent() -> anyhow::Result<()> {
     introspection_is_idempotent(CONNECTION_URI, CHINOOK_NDC_METADATA_PATH).await
 }
 
+#[tokio::test]
+async fn postgres_current_only_configuration_is_valid() -> anyhow::Result<()> {
+    configuration_is_valid(CONNECTION_URI, CHINOOK_NDC_METADATA_PATH).await
+}
+
+#[tokio::test]
+async fn postgres_current_only_connection_check_succeeds() -> anyhow::Result<()> {
+    connection_check_succeeds(CONNECTION_URI, CHINOOK_NDC_METADATA_PATH).await
+}
+
+#[tokio::test]
+async fn postgres_current_only_split_metadata_round_trips() -> anyhow::Result<()> {
+    split_metadata_round_trips(CHINOOK_NDC_METADATA_PATH).await
+}
+
+#[tokio::test]
+async fn postgres_current_only_native_operations_are_verified() -> anyhow::Result<()> {
+    native_operations_are_verified(CONNECTION_URI, CHINOOK_NDC_METADATA_PATH).await
+}
+
 const MY_NATIVE_QUERY: &str = r#"
 SELECT "ArtistId" as artist_id,
        "Name",
@@ -46,6 +68,7 @@ async fn create_native_operation() -> anyhow::Result<()> {
         CONNECTION_URI,
         CHINOOK_NDC_METADATA_PATH,
         MY_NATIVE_QUERY.to_string(),
+        Inference::Describe,
     )
     .await?;
 
@@ -53,3 +76,43 @@ async fn create_native_operation() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+const RANKED_ALBUMS_NATIVE_QUERY: &str = r#"
+SELECT "AlbumId" AS album_id,
+       row_number() OVER (ORDER BY "AlbumId") AS position,
+       nullif("Title", '') AS title_unless_empty
+FROM "Album"
+"#;
+
+#[tokio::test]
+async fn create_native_operation_inferring_nullability_with_explain() -> anyhow::Result<()> {
+    let described = test_native_operation_create_v5(
+        CONNECTION_URI,
+        CHINOOK_NDC_METADATA_PATH,
+        RANKED_ALBUMS_NATIVE_QUERY.to_string(),
+        Inference::Describe,
+    )
+    .await?;
+    assert_eq!(nullability(&described, "position"), "nullable");
+
+    let explained = test_native_operation_create_v5(
+        CONNECTION_URI,
+        CHINOOK_NDC_METADATA_PATH,
+        RANKED_ALBUMS_NATIVE_QUERY.to_string(),
+        Inference::Explain,
+    )
+    .await?;
+    // The plan shows that `row_number()` is computed for every row.
+    assert_eq!(nullability(&explained, "position"), "nonNullable");
+    // `nullif` can return null, so the column is left nullable.
+    assert_eq!(nullability(&explained, "title_unless_empty"), "nullable");
+    // Columns taken straight from a table keep what the database reports about them.
+    assert_eq!(nullability(&explained, "album_id"), "nonNullable");
+
+    Ok(())
+}
+
+/// The nullability of a column of a Native Operation, as written to the configuration.
+fn nullability(native_operation: &NativeQueryInfo, column: &str) -> serde_json::Value {
+    serde_json::json!(native_operation)["columns"][column]["nullable"].clone()
+}
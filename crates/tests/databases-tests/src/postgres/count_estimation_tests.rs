@@ -0,0 +1,28 @@
+//! Star counts over whole tables are estimated, and say whether they were.
+
+use tests_common::request::run_query;
+
+use super::common;
+
+#[tokio::test]
+async fn tables_below_the_minimum_rows_are_counted_exactly() {
+    let directory = tempfile::tempdir().unwrap();
+    common::copy_chinook_configuration(directory.path(), |configuration| {
+        configuration["countEstimation"] = serde_json::json!({ "minRows": 2_000_000_000 });
+    })
+    .await;
+    let router =
+        tests_common::router::create_router(directory.path(), common::CONNECTION_URI).await;
+
+    let result = run_query(router, "aggregate_count_estimated").await;
+
+    assert_eq!(
+        serde_json::to_value(result).unwrap(),
+        serde_json::json!([{
+            "aggregates": {
+                "how_many_albums": 347,
+                "is_estimate": false
+            }
+        }])
+    );
+}
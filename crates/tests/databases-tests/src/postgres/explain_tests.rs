@@ -8,6 +8,10 @@ mod query {
     async fn select_by_pk() {
         let result = run_query_explain(create_router().await, "select_by_pk").await;
         is_contained_in_lines(&["Aggregate", "Scan", "35"], &result.details.plan);
+        is_contained_in_lines(
+            &["Tables: public.Album", "Joins: none", "Predicates: 1"],
+            &result.details.summary,
+        );
         insta::assert_snapshot!(result.details.query);
     }
 
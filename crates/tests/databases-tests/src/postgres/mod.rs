@@ -1,11 +1,16 @@
+pub mod bytes_tests;
+pub mod cancellation_tests;
 pub mod cli_update_tests;
 pub mod cli_version3_tests;
 pub mod cli_version4_tests;
 pub mod cli_version5_tests;
 pub mod common;
 pub mod configuration_tests;
+pub mod count_estimation_tests;
 pub mod explain_tests;
 pub mod mutation_tests;
 pub mod ndc_tests;
 pub mod query_tests;
+pub mod replay_tests;
 pub mod schema_tests;
+pub mod verify_requests_tests;
@@ -233,7 +233,7 @@ mod basic {
 mod negative {
     use super::super::common;
     use tests_common::ndc_metadata::FreshDeployment;
-    use tests_common::request::{run_mutation_fail, run_query, StatusCode};
+    use tests_common::request::{run_mutation, run_mutation_fail, run_query, StatusCode};
 
     #[tokio::test(flavor = "multi_thread")]
     /// Check that the second statement fails on duplicate key constraint,
@@ -265,6 +265,39 @@ mod negative {
         insta::assert_json_snapshot!(result);
     }
 
+    #[tokio::test(flavor = "multi_thread")]
+    /// Check that, with the best-effort failure policy, the second statement fails on duplicate
+    /// key constraint with a null result, and that the first statement is kept.
+    async fn insert_artist_album_bad_best_effort() {
+        let ndc_metadata =
+            FreshDeployment::create(common::CONNECTION_URI, common::CHINOOK_NDC_METADATA_PATH)
+                .await
+                .unwrap();
+
+        let configuration_path = ndc_metadata.ndc_metadata_path.join("configuration.json");
+        let configuration = std::fs::read_to_string(&configuration_path).unwrap();
+        std::fs::write(
+            &configuration_path,
+            configuration.replacen('{', "{\n  \"mutationFailurePolicy\": \"bestEffort\",", 1),
+        )
+        .unwrap();
+
+        let router = tests_common::router::create_router(
+            &ndc_metadata.ndc_metadata_path,
+            &ndc_metadata.connection_uri,
+        )
+        .await;
+
+        let mutation_result = run_mutation(router.clone(), "insert_artist_album_bad").await;
+
+        // expect the artist to be returned, because only the second operation was rolled back.
+        let selection_result = run_query(router, "mutations/select_specific_artist").await;
+
+        let result = (mutation_result, selection_result);
+
+        insta::assert_json_snapshot!(result);
+    }
+
     #[tokio::test(flavor = "multi_thread")]
     /// Check that insert fails due to missing column.
     async fn v2_insert_custom_dog_missing_column() {
@@ -366,4 +399,58 @@ mod negative {
 
         insta::assert_json_snapshot!(result);
     }
+
+    #[tokio::test(flavor = "multi_thread")]
+    /// Check that a duplicate key is reported as a conflict of the operation which raised it.
+    async fn v2_insert_album_duplicate_key() {
+        let ndc_metadata =
+            FreshDeployment::create(common::CONNECTION_URI, common::CHINOOK_NDC_METADATA_PATH)
+                .await
+                .unwrap();
+
+        let router = tests_common::router::create_router(
+            &ndc_metadata.ndc_metadata_path,
+            &ndc_metadata.connection_uri,
+        )
+        .await;
+
+        let mutation_result = run_mutation_fail(
+            router.clone(),
+            "v2_insert_Album_duplicate_key",
+            StatusCode::CONFLICT,
+        )
+        .await;
+
+        assert_eq!(mutation_result.details["kind"], "unique_violation");
+        assert_eq!(mutation_result.details["procedure"], "insert_Album");
+        assert_eq!(
+            mutation_result.details["fields"],
+            serde_json::json!(["AlbumId"])
+        );
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    /// Check that a missing value of a non-nullable column is reported as an unmet constraint.
+    async fn v2_insert_album_null_title() {
+        let ndc_metadata =
+            FreshDeployment::create(common::CONNECTION_URI, common::CHINOOK_NDC_METADATA_PATH)
+                .await
+                .unwrap();
+
+        let router = tests_common::router::create_router(
+            &ndc_metadata.ndc_metadata_path,
+            &ndc_metadata.connection_uri,
+        )
+        .await;
+
+        let mutation_result = run_mutation_fail(
+            router.clone(),
+            "v2_insert_Album_null_title",
+            StatusCode::FORBIDDEN,
+        )
+        .await;
+
+        assert_eq!(mutation_result.details["kind"], "not_null_violation");
+        assert_eq!(mutation_result.details["column"], "Title");
+    }
 }
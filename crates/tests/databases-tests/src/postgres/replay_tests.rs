@@ -0,0 +1,87 @@
+//! Recording the requests the connector serves, and replaying them.
+
+use std::path::Path;
+use std::time::Duration;
+
+use tests_common::replay;
+use tests_common::request::{run_query, StatusCode};
+
+use super::common;
+
+/// Copy the Chinook configuration into a directory, recording the requests it serves to
+/// `recordings`.
+async fn recording_configuration(directory: &Path, recordings: &Path) {
+    common::copy_chinook_configuration(directory, |configuration| {
+        configuration["requestRecording"] = serde_json::json!({
+            "directory": recordings,
+            "includeValues": true,
+        });
+    })
+    .await;
+}
+
+/// Wait for the connector to write the given number of recordings in the background.
+async fn wait_for_recordings(
+    recordings: &Path,
+    count: usize,
+) -> Vec<(std::path::PathBuf, replay::Recording)> {
+    for _ in 0..50 {
+        if let Ok(written) = replay::read_recordings(recordings).await {
+            if written.len() >= count {
+                return written;
+            }
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    panic!("the connector did not write {count} recording(s)");
+}
+
+#[tokio::test]
+async fn recorded_requests_are_replayed_against_their_endpoint() {
+    let directory = tempfile::tempdir().unwrap();
+    let recordings = directory.path().join("recordings");
+    recording_configuration(directory.path(), &recordings).await;
+    let router =
+        tests_common::router::create_router(directory.path(), common::CONNECTION_URI).await;
+
+    run_query(router.clone(), "select_5").await;
+    let written = wait_for_recordings(&recordings, 1).await;
+    assert_eq!(written[0].1.endpoint, "/query");
+    assert_eq!(written[0].1.statements.len(), 1);
+
+    let replays = replay::replay(router, &recordings).await.unwrap();
+
+    assert_eq!(replays.len(), 1);
+    assert_eq!(replays[0].status, StatusCode::OK);
+    assert!(replays[0].slowdown().is_some());
+}
+
+#[tokio::test]
+async fn replays_which_fail_are_not_compared() {
+    let recordings = tempfile::tempdir().unwrap();
+    tokio::fs::write(
+        recordings.path().join("0-000000.json"),
+        serde_json::json!({
+            "endpoint": "/query",
+            "request": {
+                "collection": "NoSuchCollection",
+                "query": { "fields": {} },
+                "arguments": {},
+                "collection_relationships": {}
+            },
+            "statements": [],
+            "elapsedMs": 10
+        })
+        .to_string(),
+    )
+    .await
+    .unwrap();
+
+    let replays = replay::replay(common::create_router().await, recordings.path())
+        .await
+        .unwrap();
+
+    assert_eq!(replays.len(), 1);
+    assert!(!replays[0].succeeded());
+    assert!(replays[0].slowdown().is_none());
+}
@@ -0,0 +1,110 @@
+//! Checking that the database accepts the SQL of requests without running them.
+
+use ndc_postgres_cli::*;
+use ndc_postgres_configuration::environment::FixedEnvironment;
+use query_engine_sql::sql;
+use tests_common::ndc_metadata::helpers::get_path_from_project_root;
+
+use super::common;
+
+/// Copy the given query requests of the goldenfiles into a directory of their own.
+async fn requests_directory(requests: &[&str]) -> tempfile::TempDir {
+    let directory = tempfile::tempdir().unwrap();
+    for request in requests {
+        let source = get_path_from_project_root(format!(
+            "crates/tests/tests-common/goldenfiles/{request}.json"
+        ));
+        let file_name = source.file_name().unwrap().to_owned();
+        tokio::fs::copy(source, directory.path().join(file_name))
+            .await
+            .unwrap();
+    }
+    directory
+}
+
+async fn verify_requests(
+    configuration_path: &str,
+    connection_uri: &str,
+    requests: &tempfile::TempDir,
+) -> anyhow::Result<()> {
+    let context = Context {
+        context_path: get_path_from_project_root(configuration_path),
+        environment: FixedEnvironment::from([(
+            "CONNECTION_URI".into(),
+            connection_uri.to_string(),
+        )]),
+        release_version: None,
+        output_format: OutputFormat::Text,
+    };
+    run(
+        Command::VerifyRequests {
+            directory: requests.path().to_owned(),
+        },
+        context,
+    )
+    .await
+}
+
+#[tokio::test]
+async fn requests_the_database_accepts_are_verified() {
+    let requests = requests_directory(&["select_5", "aggregate_count_albums"]).await;
+
+    verify_requests(
+        common::CHINOOK_NDC_METADATA_PATH,
+        common::CONNECTION_URI,
+        &requests,
+    )
+    .await
+    .unwrap();
+}
+
+#[tokio::test]
+async fn requests_the_database_rejects_are_counted() {
+    let requests = requests_directory(&["broken_queries/broken", "broken_queries/working"]).await;
+
+    let error = verify_requests(
+        common::BROKEN_QUERIES_NDC_METADATA_PATH,
+        common::EMPTY_CONNECTION_URI,
+        &requests,
+    )
+    .await
+    .unwrap_err();
+
+    assert_eq!(
+        error.downcast_ref::<Error>(),
+        Some(&Error::RejectedRequests(1))
+    );
+}
+
+fn select_star_from(table: &str) -> sql::execution_plan::ExecutionPlan<sql::execution_plan::Query> {
+    sql::execution_plan::simple_query_execution_plan(
+        sql::dialect::SqlDialect::Postgres,
+        None,
+        sql::execution_plan::VariablesSource::Parameter,
+        table.into(),
+        sql::helpers::star_select(sql::ast::From::Table {
+            reference: sql::ast::TableReference::DBTable {
+                schema: sql::ast::SchemaName("public".to_string()),
+                table: sql::ast::TableName(table.to_string()),
+            },
+            alias: sql::ast::TableAlias {
+                unique_index: 0,
+                name: table.to_string(),
+            },
+        }),
+    )
+}
+
+#[tokio::test]
+async fn verify_plans_queries_without_running_them() {
+    let pool = sqlx::PgPool::connect(common::CONNECTION_URI).await.unwrap();
+
+    query_engine_execution::query::verify(&pool, &select_star_from("Artist"))
+        .await
+        .unwrap();
+    assert!(
+        query_engine_execution::query::verify(&pool, &select_star_from("NoSuchTable"))
+            .await
+            .is_err()
+    );
+}
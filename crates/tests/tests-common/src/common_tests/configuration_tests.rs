@@ -11,6 +11,7 @@ pub async fn test_native_operation_create_v5(
     connection_string: &str,
     ndc_metadata_path: impl AsRef<Path> + Sync,
     sql: String,
+    inference: version5::native_operations::Inference,
 ) -> anyhow::Result<version5::metadata::NativeQueryInfo> {
     let configuration = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
         .join("../../..")
@@ -29,6 +30,7 @@ pub async fn test_native_operation_create_v5(
                 connection_string,
                 &PathBuf::from("test.sql"),
                 &sql,
+                inference,
             )
             .await?;
 
@@ -58,9 +60,190 @@ pub async fn introspection_is_idempotent(
         connection_string.into(),
     )]);
 
-    let introspected_configuration =
-        ndc_postgres_configuration::introspect(parsed_configuration.clone(), environment).await?;
+    let introspected_configuration = ndc_postgres_configuration::introspect(
+        parsed_configuration.clone(),
+        environment,
+        &ndc_postgres_configuration::SessionSettings::default(),
+    )
+    .await?;
 
     assert_eq!(parsed_configuration, introspected_configuration);
     Ok(())
 }
+
+/// Tests that the metadata is consistent, and matches the tables in the database.
+pub async fn configuration_is_valid(
+    connection_string: &str,
+    ndc_metadata_path: impl AsRef<Path> + Sync,
+) -> anyhow::Result<()> {
+    let parsed_configuration = ndc_postgres_configuration::parse_configuration(
+        PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("../../..")
+            .join(ndc_metadata_path),
+    )
+    .await?;
+    let environment = HashMap::from([(
+        ndc_postgres_configuration::DEFAULT_CONNECTION_URI_VARIABLE.into(),
+        connection_string.into(),
+    )]);
+
+    let configuration =
+        ndc_postgres_configuration::make_runtime_configuration(parsed_configuration, &environment)?;
+
+    let mut problems = ndc_postgres_configuration::validate::check_metadata(&configuration);
+    problems.extend(
+        ndc_postgres_configuration::validate::check_database(&configuration, &environment).await?,
+    );
+
+    assert_eq!(problems, Vec::<String>::new());
+    Ok(())
+}
+
+/// Tests that the connector can connect to the database of a configuration and read every
+/// schema of its metadata.
+pub async fn connection_check_succeeds(
+    connection_string: &str,
+    ndc_metadata_path: impl AsRef<Path> + Sync,
+) -> anyhow::Result<()> {
+    let parsed_configuration = ndc_postgres_configuration::parse_configuration(
+        PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("../../..")
+            .join(ndc_metadata_path),
+    )
+    .await?;
+    let environment = HashMap::from([(
+        ndc_postgres_configuration::DEFAULT_CONNECTION_URI_VARIABLE.into(),
+        connection_string.into(),
+    )]);
+
+    let configuration =
+        ndc_postgres_configuration::make_runtime_configuration(parsed_configuration, &environment)?;
+
+    let report = ndc_postgres_configuration::connection_check::check_connection(
+        &configuration,
+        &environment,
+    )
+    .await?;
+
+    assert_eq!(report.current_role, "postgres");
+    assert!(!report.schemas.is_empty());
+    assert_eq!(report.problems(), Vec::<String>::new());
+    Ok(())
+}
+
+/// Tests that a configuration split across several files reads back the same as it was written.
+pub async fn split_metadata_round_trips(
+    ndc_metadata_path: impl AsRef<Path> + Sync,
+) -> anyhow::Result<()> {
+    let ParsedConfiguration::Version5(mut parsed_configuration) =
+        ndc_postgres_configuration::parse_configuration(
+            PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+                .join("../../..")
+                .join(ndc_metadata_path),
+        )
+        .await?
+    else {
+        anyhow::bail!("Expected a version 5 configuration");
+    };
+    parsed_configuration.split_metadata = true;
+
+    let dir = tempfile::tempdir()?;
+    ndc_postgres_configuration::write_parsed_configuration(
+        ParsedConfiguration::Version5(parsed_configuration.clone()),
+        &dir,
+    )
+    .await?;
+    assert!(dir.path().join("tables").is_dir());
+
+    let written_configuration = ndc_postgres_configuration::parse_configuration(&dir).await?;
+
+    assert_eq!(
+        ParsedConfiguration::Version5(parsed_configuration),
+        written_configuration
+    );
+    Ok(())
+}
+
+/// Tests that Native Operations are checked against the database: a column their SQL does not
+/// return, or which is declared non-nullable but can be null, is reported.
+pub async fn native_operations_are_verified(
+    connection_string: &str,
+    ndc_metadata_path: impl AsRef<Path> + Sync,
+) -> anyhow::Result<()> {
+    let ParsedConfiguration::Version5(mut parsed_configuration) =
+        ndc_postgres_configuration::parse_configuration(
+            PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+                .join("../../..")
+                .join(ndc_metadata_path),
+        )
+        .await?
+    else {
+        anyhow::bail!("Expected a version 5 configuration");
+    };
+    let environment = HashMap::from([(
+        ndc_postgres_configuration::DEFAULT_CONNECTION_URI_VARIABLE.into(),
+        connection_string.into(),
+    )]);
+
+    let column = |name: &str, scalar_type: &str, nullable| version5::metadata::ReadOnlyColumnInfo {
+        name: name.to_string(),
+        r#type: version5::metadata::Type::ScalarType(scalar_type.into()),
+        nullable,
+        description: None,
+    };
+    parsed_configuration
+        .metadata
+        .native_operations
+        .queries
+        .0
+        .insert(
+            "unverified_artist".into(),
+            version5::metadata::NativeQueryInfo {
+                sql: version5::metadata::NativeQuerySqlEither::NativeQuerySql(
+                    version5::metadata::NativeQuerySql::Inline {
+                        sql: version5::metadata::parse_native_query(
+                            r#"SELECT "ArtistId" AS "id", "Name" AS "name" FROM "Artist""#,
+                        ),
+                    },
+                ),
+                columns: [
+                    (
+                        "id".into(),
+                        column("id", "int4", version5::metadata::Nullable::NonNullable),
+                    ),
+                    (
+                        "name".into(),
+                        column("name", "varchar", version5::metadata::Nullable::NonNullable),
+                    ),
+                    (
+                        "missing".into(),
+                        column("missing", "int4", version5::metadata::Nullable::Nullable),
+                    ),
+                ]
+                .into(),
+                arguments: std::collections::BTreeMap::new(),
+                description: None,
+                foreign_relations: version5::metadata::ForeignRelations::default(),
+                isolation_level: None,
+            },
+        );
+
+    let problems = version5::native_operations::verify(
+        &parsed_configuration,
+        &environment,
+        &ndc_postgres_configuration::SessionSettings::default(),
+    )
+    .await?;
+
+    assert_eq!(
+        problems
+            .into_iter()
+            .filter(|problem| problem.starts_with("Native Query 'unverified_artist'"))
+            .collect::<Vec<_>>(),
+        vec![
+            "Native Query 'unverified_artist' declares the column 'missing', which its SQL does not return.".to_string(),
+            "Native Query 'unverified_artist' declares the column 'name' as non-nullable, but the database reports that it can be null.".to_string(),
+        ]
+    );
+    Ok(())
+}
@@ -3,5 +3,6 @@
 pub mod assert;
 pub mod common_tests;
 pub mod ndc_metadata;
+pub mod replay;
 pub mod request;
 pub mod router;
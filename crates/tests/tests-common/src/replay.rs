@@ -0,0 +1,119 @@
+//! Replay the requests recorded by the connector, to see how a change to the translation affects
+//! how long they take.
+//!
+//! The values of redacted recordings are replaced with placeholders of the same type, which the
+//! database may reject. Replays which fail are not compared with their recordings. Mutations are
+//! run again when they are replayed, so replay them against a database which can be thrown away.
+
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use axum::http::StatusCode;
+
+use super::request::{create_client, TestClient};
+
+/// A request recorded by the connector.
+#[derive(Clone, Debug, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Recording {
+    /// The path the request was sent to, such as `/query`.
+    pub endpoint: String,
+    pub request: serde_json::Value,
+    pub statements: Vec<RecordedStatement>,
+    pub elapsed_ms: u64,
+}
+
+/// A SQL statement a recorded request was translated to.
+#[derive(Clone, Debug, serde::Deserialize)]
+pub struct RecordedStatement {
+    pub sql: String,
+    pub parameters: Vec<serde_json::Value>,
+}
+
+/// The outcome of replaying a recording.
+#[derive(Debug)]
+pub struct Replay {
+    pub path: PathBuf,
+    pub recording: Recording,
+    pub status: StatusCode,
+    pub elapsed: Duration,
+}
+
+impl Replay {
+    /// Whether the request succeeded when it was replayed, as it did when it was recorded.
+    pub fn succeeded(&self) -> bool {
+        self.status.is_success()
+    }
+
+    /// How much longer the request took when it was replayed than when it was recorded, as a
+    /// ratio, if it succeeded. A request which failed may have failed faster or slower than it
+    /// would have taken to run, so it cannot be compared.
+    pub fn slowdown(&self) -> Option<f64> {
+        if !self.succeeded() {
+            return None;
+        }
+        let recorded = Duration::from_millis(self.recording.elapsed_ms.max(1));
+        Some(self.elapsed.as_secs_f64() / recorded.as_secs_f64())
+    }
+}
+
+/// Read the recordings in a directory, in the order they were written.
+pub async fn read_recordings(
+    directory: impl AsRef<Path>,
+) -> anyhow::Result<Vec<(PathBuf, Recording)>> {
+    let mut paths = vec![];
+    let mut entries = tokio::fs::read_dir(directory).await?;
+    while let Some(entry) = entries.next_entry().await? {
+        let path = entry.path();
+        if path
+            .extension()
+            .is_some_and(|extension| extension == "json")
+        {
+            paths.push(path);
+        }
+    }
+    paths.sort();
+
+    let mut recordings = vec![];
+    for path in paths {
+        let contents = tokio::fs::read_to_string(&path).await?;
+        let recording = serde_json::from_str(&contents)
+            .map_err(|err| anyhow::anyhow!("{}: {err}", path.display()))?;
+        recordings.push((path, recording));
+    }
+    Ok(recordings)
+}
+
+/// Send each recorded request to the server again, one at a time, and time the responses.
+pub async fn replay(
+    router: axum::Router,
+    directory: impl AsRef<Path>,
+) -> anyhow::Result<Vec<Replay>> {
+    let client = create_client(router);
+    let mut replays = vec![];
+    for (path, recording) in read_recordings(directory).await? {
+        let (status, elapsed) = replay_request(&client, &recording).await;
+        replays.push(Replay {
+            path,
+            recording,
+            status,
+            elapsed,
+        });
+    }
+    Ok(replays)
+}
+
+/// Send a single request to the endpoint it was recorded from, and wait for the whole of the
+/// response.
+async fn replay_request(client: &TestClient, recording: &Recording) -> (StatusCode, Duration) {
+    let started = Instant::now();
+    let response = client
+        .post(&recording.endpoint)
+        .header("Content-Type", "application/json")
+        .body(recording.request.to_string())
+        .send()
+        .await;
+    let status = response.status();
+    let _body = response.bytes().await;
+    (status, started.elapsed())
+}
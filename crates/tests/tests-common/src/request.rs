@@ -37,6 +37,8 @@ pub struct ExplainDetails {
     pub query: String,
     #[serde(rename = "Execution Plan")]
     pub plan: String,
+    #[serde(rename = "Translation Summary")]
+    pub summary: String,
 }
 
 /// Run a query against the server, get the result, and compare against the snapshot.